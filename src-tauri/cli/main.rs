@@ -0,0 +1,14 @@
+use fireworks_collaboration_lib::cli::{run_from_env, USAGE};
+
+fn main() {
+    match run_from_env() {
+        Ok(code) => std::process::exit(code),
+        Err(err) => {
+            eprintln!("fwc: {err:#}");
+            if std::env::args().len() <= 1 {
+                eprint!("{USAGE}");
+            }
+            std::process::exit(2);
+        }
+    }
+}
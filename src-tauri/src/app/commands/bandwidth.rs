@@ -0,0 +1,64 @@
+//! Bandwidth throttling commands.
+
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::core::git::transport::throttle::{
+    active_task_throttles, global_limit, set_global_limit_override, set_task_limit, ThrottleInfo,
+};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskThrottleStatus {
+    pub task_id: String,
+    pub kind: String,
+    pub throttle: ThrottleInfo,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BandwidthStatus {
+    /// Effective global limit in KiB/s; `None` means unlimited.
+    pub global_kib_per_sec: Option<u64>,
+    pub tasks: Vec<TaskThrottleStatus>,
+}
+
+/// Get the effective global limit and the throttle of every running git task.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn bandwidth_get_status() -> Result<BandwidthStatus, String> {
+    Ok(BandwidthStatus {
+        global_kib_per_sec: global_limit(),
+        tasks: active_task_throttles()
+            .into_iter()
+            .map(|(id, kind, throttle)| TaskThrottleStatus {
+                task_id: id.to_string(),
+                kind,
+                throttle,
+            })
+            .collect(),
+    })
+}
+
+/// Override the global limit at runtime (KiB/s, `0` = unlimited).
+///
+/// Passing `None` drops the override and restores the configured value.
+/// The override is not persisted.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn bandwidth_set_global_limit(kib_per_sec: Option<u64>) -> Result<(), String> {
+    tracing::info!(target = "bandwidth", limit = ?kib_per_sec, "global bandwidth limit override");
+    set_global_limit_override(kib_per_sec);
+    Ok(())
+}
+
+/// Adjust the limit of a running task (KiB/s, `0` = unlimited, `None` = back to config/strategy).
+///
+/// Returns `false` when the task is not currently transferring.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn bandwidth_set_task_limit(
+    task_id: String,
+    kib_per_sec: Option<u64>,
+) -> Result<bool, String> {
+    let id = Uuid::parse_str(&task_id).map_err(|e| format!("invalid task id: {e}"))?;
+    tracing::info!(target = "bandwidth", task_id = %id, limit = ?kib_per_sec, "task bandwidth limit override");
+    Ok(set_task_limit(&id, kib_per_sec))
+}
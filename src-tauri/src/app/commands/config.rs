@@ -8,9 +8,10 @@ use crate::core::{
     config::{
         loader as cfg_loader,
         model::AppConfig,
+        network_profile,
         team_template::{
             apply_template_to_config, backup_config_file, export_template, load_template_from_path,
-            write_template_to_path, TemplateExportOptions, TemplateImportOptions,
+            write_template_to_path, SectionStrategy, TemplateExportOptions, TemplateImportOptions,
             TemplateImportOutcome, TemplateImportReport,
         },
     },
@@ -40,9 +41,11 @@ pub async fn set_config(
     pool: State<'_, SharedIpPool>,
     status_service: State<'_, SharedWorkspaceStatusService>,
 ) -> Result<(), String> {
-    // Update in-memory configuration
+    let mut new_config = new_config;
+    // Update in-memory configuration; edits made under a network profile go to its baseline
     {
         let mut guard = cfg.lock().map_err(|e| e.to_string())?;
+        network_profile::rebase_saved_config(&guard, &mut new_config).map_err(|e| e.to_string())?;
         *guard = new_config.clone();
     }
 
@@ -52,6 +55,12 @@ pub async fn set_config(
     // Save configuration to disk
     cfg_loader::save_at(&new_config, &*base).map_err(|e| e.to_string())?;
 
+    // Apply the new global bandwidth limit to running transfers
+    crate::core::git::transport::throttle::sync_global_limit(&new_config.bandwidth);
+    // Re-read custom CA bundles on the next TLS handshake
+    crate::core::tls::trust::reset_custom_trust_cache();
+    crate::core::tls::client_cert::reset_client_cert_cache();
+
     // Refresh IP pool configuration
     match ip_pool::load_effective_config_at(&new_config, base.as_path()) {
         Ok(effective) => {
@@ -87,12 +96,21 @@ pub async fn export_team_config_template(
     options: Option<TemplateExportOptions>,
     cfg: State<'_, SharedConfig>,
     base: State<'_, ConfigBaseDir>,
+    pool: State<'_, SharedIpPool>,
 ) -> Result<String, String> {
     let snapshot = cfg.lock().map_err(|e| e.to_string())?.clone();
     let export_opts = options.unwrap_or_default();
 
-    let template =
+    let mut template =
         export_template(&snapshot, base.as_path(), &export_opts).map_err(|e| e.to_string())?;
+    if export_opts.include_ip_history {
+        let guard = pool.inner().lock().map_err(|e| e.to_string())?;
+        template.sections.ip_history = Some(
+            guard
+                .history()
+                .export_snapshot(ip_pool::preheat::current_epoch_ms()),
+        );
+    }
 
     let dest_path = destination.map(PathBuf::from).unwrap_or_else(|| {
         let mut path = cfg_loader::config_path_at(base.as_path());
@@ -129,6 +147,7 @@ pub async fn import_team_config_template(
     let TemplateImportOutcome {
         mut report,
         updated_ip_pool_file,
+        ip_history,
     } = apply_template_to_config(&mut *guard, Some(ip_file), &template, &import_opts)
         .map_err(|e| e.to_string())?;
 
@@ -147,6 +166,20 @@ pub async fn import_team_config_template(
         Ok(effective) => {
             if let Ok(mut pool_guard) = pool.inner().lock() {
                 pool_guard.update_config(effective);
+                if let Some((snapshot, strategy)) = ip_history.as_ref() {
+                    let replace = *strategy == SectionStrategy::Overwrite;
+                    match pool_guard.import_history(snapshot, replace) {
+                        Ok(summary) => tracing::info!(
+                            target = "config",
+                            imported = summary.imported,
+                            kept_local = summary.kept_local,
+                            "Team template IP history imported"
+                        ),
+                        Err(err) => report
+                            .warnings
+                            .push(format!("ip history import failed: {err}")),
+                    }
+                }
                 tracing::info!(target = "config", "Team template import applied to IP pool");
             } else {
                 tracing::error!(
@@ -8,8 +8,12 @@ use serde::{Deserialize, Serialize};
 use tauri::State;
 
 use crate::core::credential::{
-    audit::AuditLogger, config::CredentialConfig, factory::CredentialStoreFactory, Credential,
-    CredentialStore,
+    audit::{AuditChainReport, AuditLogger, AuditQuery, ChainedAuditEvent},
+    backup::{CredentialBackup, ImportAction, ImportConflictStrategy, ImportReport},
+    config::{CredentialConfig, StorageType},
+    factory::CredentialStoreFactory,
+    file_store::{Argon2Params, EncryptedFileStore, RekeyReport, WRONG_MASTER_PASSWORD_ERROR},
+    Credential, CredentialStore,
 };
 
 use std::sync::{Arc, Mutex};
@@ -405,7 +409,7 @@ pub async fn set_master_password(
         .lock()
         .map_err(|e| format!("Failed to acquire factory lock: {}", e))?;
 
-    *factory_guard = Some(store);
+    install_store(&mut factory_guard, store);
 
     tracing::info!(
         target = "credential",
@@ -485,6 +489,235 @@ pub async fn unlock_store(
     result
 }
 
+/// Change master password request.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeMasterPasswordRequest {
+    pub old_password: String,
+    pub new_password: String,
+    /// Optional Argon2 parameters to migrate to (defaults to the file's current parameters)
+    pub argon2_params: Option<Argon2Params>,
+}
+
+/// Change the master password of the encrypted file store.
+///
+/// Decrypts every entry with the old password, re-encrypts with a fresh salt
+/// and key (optionally migrating Argon2 parameters), and atomically replaces the
+/// file with rollback on failure. The rotation is recorded in the audit log.
+///
+/// # Arguments
+///
+/// * `request` - Old/new password and optional Argon2 parameters
+/// * `config` - Credential configuration (must use file storage)
+/// * `factory` - Shared credential store factory
+/// * `audit` - Shared audit logger
+///
+/// # Returns
+///
+/// Returns a report of the rotation on success, or an error message on failure.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn change_master_password(
+    request: ChangeMasterPasswordRequest,
+    config: CredentialConfig,
+    factory: State<'_, SharedCredentialFactory>,
+    audit: State<'_, SharedAuditLogger>,
+) -> Result<RekeyReport, String> {
+    if config.storage != StorageType::File {
+        return Err("Master password rotation requires file storage".to_string());
+    }
+
+    {
+        let logger = audit
+            .lock()
+            .map_err(|e| format!("Failed to lock audit logger: {}", e))?;
+        if logger.is_locked() {
+            return Err(
+                "Credential store is locked due to too many failed attempts. Please try again later."
+                    .to_string(),
+            );
+        }
+    }
+
+    let store = EncryptedFileStore::new(&config)
+        .map_err(|e| format!("Failed to open encrypted file store: {}", e))?;
+    let result = store.change_master_password(
+        &request.old_password,
+        &request.new_password,
+        request.argon2_params,
+    );
+
+    if let Ok(logger) = audit.lock() {
+        match &result {
+            Ok(_) => logger.log_operation(
+                crate::core::credential::audit::OperationType::Rekey,
+                "credential_store",
+                "master",
+                None,
+                true,
+                None,
+            ),
+            Err(e) => {
+                logger.log_operation(
+                    crate::core::credential::audit::OperationType::Rekey,
+                    "credential_store",
+                    "master",
+                    None,
+                    false,
+                    Some(e.clone()),
+                );
+                // Only a wrong old password counts toward lockout, not I/O or verification errors
+                if e.starts_with(WRONG_MASTER_PASSWORD_ERROR) {
+                    logger.record_auth_failure();
+                }
+            }
+        }
+    }
+
+    let report = result?;
+
+    let mut factory_guard = factory
+        .lock()
+        .map_err(|e| format!("Failed to acquire factory lock: {}", e))?;
+    install_store(&mut factory_guard, Arc::new(store));
+
+    tracing::info!(
+        target = "credential",
+        count = report.credentials_rekeyed,
+        "Master password changed and credential store re-encrypted"
+    );
+
+    Ok(report)
+}
+
+/// Replaces the shared credential store and points client-certificate key lookups at it.
+fn install_store(slot: &mut Option<Arc<dyn CredentialStore>>, store: Arc<dyn CredentialStore>) {
+    crate::core::tls::client_cert::set_key_store(Some(Arc::clone(&store)));
+    *slot = Some(store);
+}
+
+/// Export credential backup request.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportCredentialBackupRequest {
+    pub passphrase: String,
+    /// Optional Argon2 parameters (defaults to the file store defaults)
+    pub argon2_params: Option<Argon2Params>,
+}
+
+/// Export all credentials as a passphrase-encrypted portable bundle.
+///
+/// # Arguments
+///
+/// * `request` - Backup passphrase and optional Argon2 parameters
+/// * `factory` - Shared credential store factory
+/// * `audit` - Shared audit logger
+///
+/// # Returns
+///
+/// Returns the backup bundle as a JSON string.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn export_credentials_backup(
+    request: ExportCredentialBackupRequest,
+    factory: State<'_, SharedCredentialFactory>,
+    audit: State<'_, SharedAuditLogger>,
+) -> Result<String, String> {
+    let store = factory
+        .lock()
+        .map_err(|e| format!("Failed to acquire factory lock: {}", e))?
+        .as_ref()
+        .ok_or("Credential store not initialized")?
+        .clone();
+
+    let credentials = store
+        .list_all()
+        .map_err(|e| format!("Failed to list credentials: {}", e))?;
+    let backup = CredentialBackup::export_credentials(
+        &credentials,
+        &request.passphrase,
+        request.argon2_params.unwrap_or_default(),
+    )?;
+    let json = backup.to_json()?;
+
+    // Log audit event for every exported credential
+    if let Ok(logger) = audit.lock() {
+        for cred in &credentials {
+            logger.log_operation(
+                crate::core::credential::audit::OperationType::Export,
+                &cred.host,
+                &cred.username,
+                Some(&cred.password_or_token),
+                true,
+                None,
+            );
+        }
+    }
+
+    Ok(json)
+}
+
+/// Import credentials from a passphrase-encrypted backup bundle.
+///
+/// # Arguments
+///
+/// * `bundle` - Backup bundle JSON produced by `export_credentials_backup`
+/// * `passphrase` - Backup passphrase
+/// * `strategy` - Conflict strategy (skip, overwrite, keepNewest)
+/// * `factory` - Shared credential store factory
+/// * `audit` - Shared audit logger
+///
+/// # Returns
+///
+/// Returns an import report with per-entry outcomes.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn import_credentials_backup(
+    bundle: String,
+    passphrase: String,
+    strategy: Option<ImportConflictStrategy>,
+    factory: State<'_, SharedCredentialFactory>,
+    audit: State<'_, SharedAuditLogger>,
+) -> Result<ImportReport, String> {
+    let store = factory
+        .lock()
+        .map_err(|e| format!("Failed to acquire factory lock: {}", e))?
+        .as_ref()
+        .ok_or("Credential store not initialized")?
+        .clone();
+
+    let result = CredentialBackup::from_json(&bundle).and_then(|backup| {
+        backup.import_into(store.as_ref(), &passphrase, strategy.unwrap_or_default())
+    });
+
+    if let Ok(logger) = audit.lock() {
+        match &result {
+            Ok(report) => {
+                for entry in &report.entries {
+                    logger.log_operation(
+                        crate::core::credential::audit::OperationType::Import,
+                        &entry.host,
+                        &entry.username,
+                        None,
+                        entry.action != ImportAction::Failed,
+                        entry.error.clone().or_else(|| {
+                            (entry.action == ImportAction::Skipped)
+                                .then(|| "Skipped due to conflict strategy".to_string())
+                        }),
+                    );
+                }
+            }
+            Err(e) => logger.log_operation(
+                crate::core::credential::audit::OperationType::Import,
+                "credential_store",
+                "backup",
+                None,
+                false,
+                Some(e.clone()),
+            ),
+        }
+    }
+
+    result
+}
+
 /// Export audit log as JSON.
 ///
 /// # Arguments
@@ -612,6 +845,49 @@ pub async fn cleanup_audit_logs(
     logger.cleanup_expired_logs(retention_days)
 }
 
+/// Verify the integrity of the hash-chained audit log.
+///
+/// # Arguments
+///
+/// * `audit` - Shared audit logger
+///
+/// # Returns
+///
+/// Returns a report listing every record whose MAC, sequence number or chain
+/// link does not verify. `valid` is `true` only when the whole chain is intact.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn verify_audit_log(
+    audit: State<'_, SharedAuditLogger>,
+) -> Result<AuditChainReport, String> {
+    let logger = audit
+        .lock()
+        .map_err(|e| format!("Failed to lock audit logger: {}", e))?;
+
+    logger.verify_chain()
+}
+
+/// Query audit log records by operation, host, result and time range.
+///
+/// # Arguments
+///
+/// * `query` - Filter conditions; unset fields match everything
+/// * `audit` - Shared audit logger
+///
+/// # Returns
+///
+/// Returns matching records in chain order.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn query_audit_log(
+    query: Option<AuditQuery>,
+    audit: State<'_, SharedAuditLogger>,
+) -> Result<Vec<ChainedAuditEvent>, String> {
+    let logger = audit
+        .lock()
+        .map_err(|e| format!("Failed to lock audit logger: {}", e))?;
+
+    Ok(logger.query(&query.unwrap_or_default()))
+}
+
 /// Check if credential store is locked due to authentication failures.
 ///
 /// # Arguments
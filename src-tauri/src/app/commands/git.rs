@@ -1,14 +1,20 @@
 //! Git operation commands.
 
-use tauri::State;
+use tauri::{Manager, State};
 
+use crate::core::credential::binding::{
+    find_credential_for_remote, resolve_binding, CredentialBinding,
+};
 use crate::core::git::runner::GitRunner;
 use crate::core::git::utils::{parse_depth, resolve_push_credentials};
 use crate::core::tasks::TaskKind;
 
 // Command functions use raw tauri::AppHandle for CommandArg trait compatibility,
 // then convert to wrapper for spawn calls
-use super::super::types::{AppHandle, SharedCredentialFactory, TaskRegistryState, TauriRuntime};
+use super::super::types::{
+    AppHandle, SharedConfig, SharedCredentialFactory, TaskRegistryState, TauriRuntime,
+};
+use super::workspace::SharedWorkspaceManager;
 
 /// Clone a Git repository.
 ///
@@ -132,7 +138,7 @@ pub async fn git_push(
     let should_fetch_stored = use_stored && username.is_none() && password.is_none();
 
     let (stored_username, stored_password) = if should_fetch_stored {
-        match try_get_git_credentials(&dest, &credential_factory).await {
+        match try_get_git_credentials(&dest, &credential_factory, &app).await {
             Ok(Some((u, p))) => {
                 tracing::info!(
                     target = "git",
@@ -192,14 +198,29 @@ pub async fn git_push(
 }
 
 /// Helper function to try to get Git credentials from storage.
+///
+/// Honors credential bindings: an explicit binding on the workspace repository
+/// whose remote matches, then URL pattern rules from the credential config.
+/// When a binding applies, only that identity is looked up.
 async fn try_get_git_credentials(
     repo_path: &str,
     credential_factory: &State<'_, SharedCredentialFactory>,
+    app: &tauri::AppHandle<TauriRuntime>,
 ) -> Result<Option<(String, String)>, String> {
-    // Extract host
-    let host = extract_git_host(repo_path)?;
+    let remote_url = extract_git_remote_url(repo_path)?;
+    let host = parse_git_host(&remote_url)?;
     tracing::info!(target = "git", "Extracted host from repo: {}", host);
 
+    let explicit = app.try_state::<SharedWorkspaceManager>().and_then(|ws| {
+        ws.lock().ok().and_then(|guard| {
+            guard
+                .as_ref()
+                .and_then(|w| w.find_repository_by_remote(&remote_url))
+                .and_then(|r| r.credential.clone())
+        })
+    });
+    let binding = resolve_credential_binding(app, &remote_url, explicit.as_ref());
+
     // Get credential store
     let factory_guard = credential_factory
         .lock()
@@ -209,51 +230,30 @@ async fn try_get_git_credentials(
         .as_ref()
         .ok_or("Credential store not initialized")?;
 
-    // For GitHub, try with x-access-token username (used for token auth)
-    // This is the standard username for GitHub personal access tokens
-    let usernames_to_try = if host.contains("github.com") {
-        vec![Some("x-access-token"), None]
-    } else {
-        vec![None]
-    };
-
-    for username in usernames_to_try {
-        tracing::info!(
-            target = "git",
-            "Looking up credentials for host: {}, username: {:?}",
-            host,
-            username
-        );
-        match store.get(&host, username) {
-            Ok(Some(cred)) => {
-                tracing::info!(
-                    target = "git",
-                    "Found credential for host: {}, username: {}",
-                    host,
-                    cred.username
-                );
-                return Ok(Some((
-                    cred.username.to_string(),
-                    cred.password_or_token.to_string(),
-                )));
-            }
-            Ok(None) => {
-                tracing::info!(
-                    target = "git",
-                    "No credential found for host: {}, username: {:?}",
-                    host,
-                    username
-                );
-                continue;
-            }
-            Err(e) => {
-                tracing::warn!(target = "git", "Error looking up credential: {}", e);
-                continue;
-            }
-        }
-    }
+    tracing::info!(
+        target = "git",
+        "Looking up credentials for host: {}, binding: {:?}",
+        host,
+        binding
+    );
+    let found = find_credential_for_remote(store.as_ref(), &host, binding.as_ref())?;
+    Ok(found.map(|cred| (cred.username, cred.password_or_token)))
+}
 
-    Ok(None)
+/// Resolve the credential binding for a remote URL.
+///
+/// `explicit` (from a workspace repository entry) wins; otherwise the first
+/// matching rule in `credential.bindings` applies.
+pub(crate) fn resolve_credential_binding<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    remote_url: &str,
+    explicit: Option<&CredentialBinding>,
+) -> Option<CredentialBinding> {
+    let rules = app
+        .try_state::<SharedConfig>()
+        .and_then(|cfg| cfg.lock().ok().map(|c| c.credential.bindings.clone()))
+        .unwrap_or_default();
+    resolve_binding(remote_url, explicit, &rules)
 }
 
 /// Extract Git host from a repository path by reading git config.
@@ -261,6 +261,14 @@ async fn try_get_git_credentials(
 /// This function attempts to read the remote URL from the Git repository
 /// and extract the host part.
 pub(crate) fn extract_git_host(repo_path: &str) -> Result<String, String> {
+    let url = extract_git_remote_url(repo_path)?;
+
+    // Parse host from URL
+    parse_git_host(&url)
+}
+
+/// Read `remote.origin.url` from a local repository.
+pub(crate) fn extract_git_remote_url(repo_path: &str) -> Result<String, String> {
     use std::path::Path;
 
     let path = Path::new(repo_path);
@@ -276,12 +284,9 @@ pub(crate) fn extract_git_host(repo_path: &str) -> Result<String, String> {
         .config()
         .map_err(|e| format!("Failed to get config: {}", e))?;
 
-    let url = config
+    config
         .get_string("remote.origin.url")
-        .map_err(|_| "Failed to get remote URL".to_string())?;
-
-    // Parse host from URL
-    parse_git_host(&url)
+        .map_err(|_| "Failed to get remote URL".to_string())
 }
 
 /// Parse host from various Git URL formats.
@@ -1291,6 +1296,7 @@ pub async fn git_worktree_remove(
     remote: Option<String>,
     use_stored_credential: Option<bool>,
     credential_factory: State<'_, SharedCredentialFactory>,
+    app: tauri::AppHandle<TauriRuntime>,
 ) -> Result<(), String> {
     let repo_path = Path::new(&dest);
     if !repo_path.exists() {
@@ -1390,7 +1396,8 @@ pub async fn git_worktree_remove(
             let use_stored = use_stored_credential.unwrap_or(false);
             let mut creds: Option<(String, String)> = None;
             if use_stored {
-                if let Ok(Some((u, p))) = try_get_git_credentials(&dest, &credential_factory).await
+                if let Ok(Some((u, p))) =
+                    try_get_git_credentials(&dest, &credential_factory, &app).await
                 {
                     creds = Some((u, p));
                 }
@@ -40,6 +40,8 @@ pub fn classify_error_msg(e: &str) -> (&'static str, String) {
 
     if msg.contains("SAN whitelist mismatch") {
         ("Verify", msg)
+    } else if msg.contains("client certificate") {
+        ("ClientCert", msg)
     } else if msg.contains("tls handshake") {
         ("Tls", msg)
     } else if msg.contains("connect timeout")
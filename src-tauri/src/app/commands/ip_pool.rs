@@ -1,4 +1,5 @@
 use std::collections::HashSet;
+use std::path::PathBuf;
 
 use serde::Serialize;
 use tauri::State;
@@ -8,9 +9,13 @@ use crate::core::config::loader as cfg_loader;
 use crate::core::ip_pool::config as ip_pool_cfg;
 use crate::core::ip_pool::{
     self,
+    circuit_breaker::CircuitBreakerRecord,
     config::{IpPoolFileConfig, IpPoolRuntimeConfig},
+    history::{IpHistoryExport, IpHistoryImportReport},
     manager::{IpPool, IpSelectionStrategy, OutcomeMetrics},
-    preheat, IpStat,
+    preheat,
+    subscription::{subscription_status, SubscriptionStatus},
+    IpStat,
 };
 
 #[derive(Debug, Serialize, Clone)]
@@ -44,6 +49,9 @@ pub struct IpPoolSnapshotDto {
     pub auto_disabled_until: Option<i64>,
     pub cache_entries: Vec<IpPoolCacheEntryDto>,
     pub tripped_ips: Vec<String>,
+    /// Per-(host, IP) breaker scopes that are open, cooling down or half-open
+    pub circuit_breakers: Vec<CircuitBreakerRecord>,
+    pub subscriptions: Vec<SubscriptionStatus>,
     pub timestamp_ms: i64,
 }
 
@@ -188,6 +196,59 @@ pub async fn ip_pool_pick_best(
     .map_err(|e| e.to_string())?
 }
 
+/// Export verified IP history to a JSON file that teammates can import.
+/// Returns the written path.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn ip_pool_export_history(
+    destination: Option<String>,
+    base: State<'_, ConfigBaseDir>,
+    pool: State<'_, SharedIpPool>,
+) -> Result<String, String> {
+    let dest_path = destination.map(PathBuf::from).unwrap_or_else(|| {
+        let mut path = cfg_loader::config_path_at(base.as_path());
+        path.set_file_name("ip-history-export.json");
+        path
+    });
+    let pool_arc = pool.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let snapshot = {
+            let guard = pool_arc.lock().map_err(|e| e.to_string())?;
+            guard.history().export_snapshot(preheat::current_epoch_ms())
+        };
+        let json = serde_json::to_string_pretty(&snapshot).map_err(|e| e.to_string())?;
+        if let Some(parent) = dest_path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        std::fs::write(&dest_path, json).map_err(|e| e.to_string())?;
+        Ok(dest_path.to_string_lossy().to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Import an exported IP history file. Entries are merged by freshness and
+/// latency (or replace local history when `replace` is set) and stay pending
+/// until the preheater re-probes them.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn ip_pool_import_history(
+    source: String,
+    replace: Option<bool>,
+    pool: State<'_, SharedIpPool>,
+) -> Result<IpHistoryImportReport, String> {
+    let pool_arc = pool.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let data = std::fs::read(&source).map_err(|e| format!("{source}: {e}"))?;
+        let snapshot: IpHistoryExport =
+            serde_json::from_slice(&data).map_err(|e| format!("{source}: {e}"))?;
+        let mut guard = pool_arc.lock().map_err(|e| e.to_string())?;
+        guard
+            .import_history(&snapshot, replace.unwrap_or(false))
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
 fn build_snapshot(pool: &IpPool) -> IpPoolSnapshotDto {
     let now_ms = preheat::current_epoch_ms();
     let cache_snapshot = pool.cache().snapshot();
@@ -252,6 +313,8 @@ fn build_snapshot(pool: &IpPool) -> IpPoolSnapshotDto {
         auto_disabled_until: pool.auto_disabled_until(),
         cache_entries: entries,
         tripped_ips,
+        circuit_breakers: pool.circuit_breaker_snapshot(),
+        subscriptions: subscription_status(&pool.file_config().subscriptions),
         timestamp_ms: now_ms,
     }
 }
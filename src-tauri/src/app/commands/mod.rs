@@ -1,25 +1,30 @@
 //! Commands module - Re-exports all Tauri command handlers.
 
+pub mod bandwidth;
 pub mod config;
 pub mod credential;
 pub mod git;
 pub mod http;
 pub mod ip_pool;
 pub mod metrics;
+pub mod network;
 pub mod oauth;
 pub mod proxy;
 pub mod submodule;
 pub mod tasks;
+pub mod tls;
 pub mod vitepress;
 pub mod workspace;
 
 // Re-export all command functions
+pub use bandwidth::{bandwidth_get_status, bandwidth_set_global_limit, bandwidth_set_task_limit};
 pub use config::{
     check_tool_version, export_team_config_template, get_config, greet,
     import_team_config_template, set_config,
 };
 pub use credential::{
-    add_credential, delete_credential, export_audit_log, get_credential, list_credentials,
+    add_credential, change_master_password, delete_credential, export_audit_log,
+    export_credentials_backup, get_credential, import_credentials_backup, list_credentials,
     set_master_password, unlock_store, update_credential, SharedAuditLogger,
     SharedCredentialFactory,
 };
@@ -31,10 +36,11 @@ pub use git::{
 };
 pub use http::http_fake_request;
 pub use ip_pool::{
-    ip_pool_clear_auto_disabled, ip_pool_get_snapshot, ip_pool_pick_best, ip_pool_request_refresh,
-    ip_pool_update_config,
+    ip_pool_clear_auto_disabled, ip_pool_export_history, ip_pool_get_snapshot,
+    ip_pool_import_history, ip_pool_pick_best, ip_pool_request_refresh, ip_pool_update_config,
 };
 pub use metrics::metrics_snapshot;
+pub use network::{network_profile_detect, network_profile_signals, network_profile_switch};
 pub use oauth::{clear_oauth_state, get_oauth_callback_data, start_oauth_server};
 pub use proxy::{
     detect_system_proxy, force_proxy_fallback, force_proxy_recovery, get_system_proxy,
@@ -45,6 +51,7 @@ pub use submodule::{
     SharedSubmoduleManager,
 };
 pub use tasks::{task_cancel, task_list, task_snapshot, task_start_sleep};
+pub use tls::tls_capture_spki;
 pub use vitepress::{
     vitepress_check_dependencies, vitepress_cleanup_previews, vitepress_create_document,
     vitepress_create_folder, vitepress_create_preview, vitepress_delete, vitepress_delete_preview,
@@ -58,7 +65,7 @@ pub use workspace::{
     create_workspace, get_repository, get_workspace, get_workspace_config, get_workspace_statuses,
     invalidate_workspace_status_entry, list_enabled_repositories, list_repositories,
     load_workspace, remove_repository, reorder_repositories, restore_workspace, save_workspace,
-    toggle_repository_enabled, update_repository_tags, validate_workspace_file,
-    workspace_batch_clone, workspace_batch_fetch, workspace_batch_push, SharedWorkspaceManager,
-    SharedWorkspaceStatusService,
+    set_repository_credential, toggle_repository_enabled, update_repository_tags,
+    validate_workspace_file, workspace_batch_clone, workspace_batch_fetch, workspace_batch_push,
+    SharedWorkspaceManager, SharedWorkspaceStatusService,
 };
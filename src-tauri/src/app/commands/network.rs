@@ -0,0 +1,116 @@
+//! Network environment profile commands.
+
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::core::{
+    config::{
+        loader as cfg_loader,
+        network_profile::{self, NetworkSignals, ProfileSwitch, TRIGGER_AUTO, TRIGGER_MANUAL},
+    },
+    ip_pool,
+};
+
+use super::super::types::{ConfigBaseDir, SharedConfig, SharedIpPool};
+
+/// Result of an on-demand network profile detection.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkProfileDetection {
+    pub signals: NetworkSignals,
+    pub switch: ProfileSwitch,
+}
+
+/// Collect the current network signals without switching profiles.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn network_profile_signals() -> Result<NetworkSignals, String> {
+    tauri::async_runtime::spawn_blocking(NetworkSignals::collect)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Detect the current network and switch to the first matching profile.
+///
+/// Falls back to the baseline configuration when no profile matches.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn network_profile_detect(
+    cfg: State<'_, SharedConfig>,
+    base: State<'_, ConfigBaseDir>,
+    pool: State<'_, SharedIpPool>,
+) -> Result<NetworkProfileDetection, String> {
+    let mut snapshot = cfg.lock().map_err(|e| e.to_string())?.clone();
+    let (signals, switch, snapshot) = tauri::async_runtime::spawn_blocking(move || {
+        let signals = NetworkSignals::collect();
+        network_profile::auto_switch(&mut snapshot, &signals)
+            .map(|switch| (signals, switch, snapshot))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| e.to_string())?;
+
+    apply_profile_switch(&switch, TRIGGER_AUTO, snapshot, &cfg, &base, &pool)?;
+    Ok(NetworkProfileDetection { signals, switch })
+}
+
+/// Manually switch to a named network profile, or back to the baseline when `name` is empty.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn network_profile_switch(
+    name: Option<String>,
+    cfg: State<'_, SharedConfig>,
+    base: State<'_, ConfigBaseDir>,
+    pool: State<'_, SharedIpPool>,
+) -> Result<ProfileSwitch, String> {
+    let mut snapshot = cfg.lock().map_err(|e| e.to_string())?.clone();
+    let switch =
+        network_profile::switch_profile(&mut snapshot, name.as_deref().filter(|n| !n.is_empty()))
+            .map_err(|e| e.to_string())?;
+
+    apply_profile_switch(&switch, TRIGGER_MANUAL, snapshot, &cfg, &base, &pool)?;
+    Ok(switch)
+}
+
+/// Persist the switched configuration, drop network-bound caches and publish the switch event.
+fn apply_profile_switch(
+    switch: &ProfileSwitch,
+    trigger: &str,
+    new_config: crate::core::config::model::AppConfig,
+    cfg: &SharedConfig,
+    base: &ConfigBaseDir,
+    pool: &SharedIpPool,
+) -> Result<(), String> {
+    if !switch.changed {
+        return Ok(());
+    }
+
+    cfg_loader::save_at(&new_config, base).map_err(|e| e.to_string())?;
+    {
+        let mut guard = cfg.lock().map_err(|e| e.to_string())?;
+        *guard = new_config.clone();
+    }
+
+    network_profile::reset_network_caches();
+
+    match ip_pool::load_effective_config_at(&new_config, base.as_path()) {
+        Ok(effective) => {
+            if let Ok(mut guard) = pool.lock() {
+                guard.update_config(effective);
+                guard.reset_network_state();
+            } else {
+                tracing::error!(
+                    target = "ip_pool",
+                    "Failed to acquire IP pool lock while switching network profile"
+                );
+            }
+        }
+        Err(err) => {
+            tracing::error!(
+                target = "ip_pool",
+                error = %err,
+                "Failed to refresh IP pool after network profile switch"
+            );
+        }
+    }
+
+    network_profile::emit_profile_switch(switch, trigger);
+    Ok(())
+}
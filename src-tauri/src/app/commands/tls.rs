@@ -0,0 +1,36 @@
+//! TLS pinning helper commands.
+
+use std::time::Duration;
+
+use tauri::State;
+
+use crate::core::tls::pin_policy::{capture_spki_fingerprints, CapturedSpki};
+
+use super::super::types::SharedConfig;
+
+/// Handshake timeout used when capturing fingerprints.
+const CAPTURE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Capture the SPKI fingerprints of the certificate chain a host presents right now.
+///
+/// The chain is validated against the configured trust anchors but no pins are
+/// enforced, so the result can be used to bootstrap (or repair) a pin policy.
+/// The capture is also appended to the certificate fingerprint log.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn tls_capture_spki(
+    host: String,
+    port: Option<u16>,
+    config: State<'_, SharedConfig>,
+) -> Result<Vec<CapturedSpki>, String> {
+    let tls = {
+        let g = config.lock().map_err(|e| e.to_string())?;
+        g.tls.clone()
+    };
+    let host = host.trim().to_string();
+    if host.is_empty() {
+        return Err("host is required".into());
+    }
+    capture_spki_fingerprints(&tls, &host, port.unwrap_or(443), CAPTURE_TIMEOUT)
+        .await
+        .map_err(|e| format!("capture spki for {host}: {e:#}"))
+}
@@ -4,10 +4,14 @@
 //! including create, load, save, add/remove repositories, and status queries.
 
 use serde::{Deserialize, Serialize};
-use tauri::State;
+use tauri::{Manager, State};
 use tracing::{debug, error, info, warn};
 
-use super::super::types::{AppHandle, SharedConfig, TaskRegistryState, TauriRuntime};
+use super::super::types::{
+    AppHandle, SharedConfig, SharedCredentialFactory, TaskRegistryState, TauriRuntime,
+};
+use super::git::{extract_git_remote_url, resolve_credential_binding};
+use crate::core::credential::binding::{resolve_push_credentials, CredentialBinding};
 use crate::core::tasks::{
     model::WorkspaceBatchOperation,
     workspace_batch::{
@@ -67,6 +71,7 @@ pub struct RepositoryInfo {
     pub remote_url: String,
     pub tags: Vec<String>,
     pub enabled: bool,
+    pub credential: Option<CredentialBinding>,
 }
 
 impl From<&RepositoryEntry> for RepositoryInfo {
@@ -78,6 +83,7 @@ impl From<&RepositoryEntry> for RepositoryInfo {
             remote_url: repo.remote_url.clone(),
             tags: repo.tags.clone(),
             enabled: repo.enabled,
+            credential: repo.credential.clone(),
         }
     }
 }
@@ -101,6 +107,7 @@ pub struct AddRepositoryRequest {
     pub remote_url: String,
     pub tags: Option<Vec<String>>,
     pub enabled: Option<bool>,
+    pub credential: Option<CredentialBinding>,
 }
 
 /// Update repository request.
@@ -152,6 +159,10 @@ pub struct WorkspaceBatchPushRequest {
     pub refspecs: Option<Vec<String>>,
     pub username: Option<String>,
     pub password: Option<String>,
+    /// Look up stored credentials for repositories without a binding.
+    /// Repositories with a credential binding (explicit or via URL rule) always
+    /// push with the bound identity, overriding `username`/`password`.
+    pub use_stored_credential: Option<bool>,
     pub strategy_override: Option<serde_json::Value>,
 }
 
@@ -301,6 +312,7 @@ pub async fn add_repository(
     );
     repo.tags = request.tags.unwrap_or_default();
     repo.enabled = request.enabled.unwrap_or(true);
+    repo.credential = request.credential;
 
     workspace_manager.add_repository(repo).map_err(|e| {
         error!("Failed to add repository {}: {}", request.name, e);
@@ -733,6 +745,7 @@ pub async fn workspace_batch_push(
 
     let root_path = resolve_workspace_root(&workspace.root_path)?;
     let concurrency = resolve_concurrency(request.max_concurrency, &config)?;
+    let use_stored = request.use_stored_credential.unwrap_or(false);
 
     let mut specs = Vec::with_capacity(repos.len());
     for repo in repos {
@@ -740,12 +753,21 @@ pub async fn workspace_batch_push(
         ensure_existing_repo(&dest_path)?;
         let dest_str = path_to_string(&dest_path)?;
 
+        let (username, password) = resolve_batch_push_credentials(
+            &app,
+            &repo,
+            &dest_str,
+            request.username.clone(),
+            request.password.clone(),
+            use_stored,
+        )?;
+
         let push_opts = PushOptions {
             dest: dest_str,
             remote: request.remote.clone(),
             refspecs: request.refspecs.clone(),
-            username: request.username.clone(),
-            password: request.password.clone(),
+            username,
+            password,
             strategy_override: request.strategy_override.clone(),
         };
 
@@ -775,6 +797,42 @@ pub async fn workspace_batch_push(
     Ok(parent_id.to_string())
 }
 
+/// Resolve push credentials for one repository of a batch push.
+///
+/// Explicit username/password win, as in `git_push`. Otherwise a binding
+/// (explicit on the entry, or from a URL rule) is always honored and a missing
+/// bound credential aborts the batch instead of pushing as another account;
+/// without a binding the host-only lookup runs only when `use_stored`.
+fn resolve_batch_push_credentials(
+    app: &tauri::AppHandle<TauriRuntime>,
+    repo: &RepositoryEntry,
+    dest: &str,
+    username: Option<String>,
+    password: Option<String>,
+    use_stored: bool,
+) -> Result<(Option<String>, Option<String>), String> {
+    let remote_url = extract_git_remote_url(dest).unwrap_or_else(|_| repo.remote_url.clone());
+    let binding: Option<CredentialBinding> =
+        resolve_credential_binding(app, &remote_url, repo.credential.as_ref());
+    resolve_push_credentials(
+        username,
+        password,
+        &remote_url,
+        binding.as_ref(),
+        use_stored,
+        || {
+            let Some(factory) = app.try_state::<SharedCredentialFactory>() else {
+                return Ok(None);
+            };
+            let guard = factory
+                .lock()
+                .map_err(|e| format!("Failed to lock credential factory: {}", e))?;
+            Ok(guard.clone())
+        },
+    )
+    .map_err(|e| format!("Repository '{}': {}", repo.id, e))
+}
+
 fn resolve_workspace_root(root: &PathBuf) -> Result<PathBuf, String> {
     if root.is_absolute() {
         Ok(root.clone())
@@ -925,6 +983,42 @@ pub async fn update_repository_tags(
     Ok(())
 }
 
+/// Bind a repository to a credential identity, or clear the binding with `None`.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn set_repository_credential(
+    repo_id: String,
+    credential: Option<CredentialBinding>,
+    manager: State<'_, SharedWorkspaceManager>,
+) -> Result<(), String> {
+    info!("Updating credential binding for repository: {}", repo_id);
+
+    let mut manager_guard = manager.lock().map_err(|e| {
+        error!("Failed to lock workspace manager: {}", e);
+        format!("Workspace manager lock error: {}", e)
+    })?;
+
+    let workspace_manager = manager_guard.as_mut().ok_or_else(|| {
+        warn!("No workspace loaded");
+        "No workspace loaded".to_string()
+    })?;
+
+    if let Some(binding) = &credential {
+        if binding.username.trim().is_empty() {
+            return Err("Credential binding username must not be empty".to_string());
+        }
+    }
+
+    workspace_manager
+        .update_repository(&repo_id, |repo| repo.credential = credential)
+        .map_err(|e| {
+            warn!("Repository not found: {}", repo_id);
+            e
+        })?;
+
+    info!("Credential binding updated for repository '{}'", repo_id);
+    Ok(())
+}
+
 /// Toggle repository enabled state.
 #[tauri::command(rename_all = "camelCase")]
 pub async fn toggle_repository_enabled(
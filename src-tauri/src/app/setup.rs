@@ -15,7 +15,7 @@ use tauri::Manager;
 #[cfg(feature = "tauri-app")]
 use crate::{
     core::{
-        config::{loader as cfg_loader, model::AppConfig},
+        config::{loader as cfg_loader, model::AppConfig, network_profile},
         credential::audit::AuditLogger,
         ip_pool,
         tasks::TaskRegistry,
@@ -68,12 +68,17 @@ pub fn run() {
             crate::app::commands::config::export_team_config_template,
             crate::app::commands::config::import_team_config_template,
             crate::app::commands::config::check_tool_version,
+            crate::app::commands::network::network_profile_signals,
+            crate::app::commands::network::network_profile_detect,
+            crate::app::commands::network::network_profile_switch,
             crate::app::commands::ip_pool::ip_pool_get_snapshot,
             crate::app::commands::ip_pool::ip_pool_update_config,
             crate::app::commands::ip_pool::ip_pool_request_refresh,
             crate::app::commands::ip_pool::ip_pool_start_preheater,
             crate::app::commands::ip_pool::ip_pool_clear_auto_disabled,
             crate::app::commands::ip_pool::ip_pool_pick_best,
+            crate::app::commands::ip_pool::ip_pool_export_history,
+            crate::app::commands::ip_pool::ip_pool_import_history,
             crate::app::commands::tasks::task_list,
             crate::app::commands::tasks::task_cancel,
             crate::app::commands::tasks::task_start_sleep,
@@ -100,6 +105,10 @@ pub fn run() {
             crate::app::commands::git::git_worktree_remove,
             crate::app::commands::http::http_fake_request,
             crate::app::commands::metrics::metrics_snapshot,
+            crate::app::commands::bandwidth::bandwidth_get_status,
+            crate::app::commands::bandwidth::bandwidth_set_global_limit,
+            crate::app::commands::bandwidth::bandwidth_set_task_limit,
+            crate::app::commands::tls::tls_capture_spki,
             crate::app::commands::proxy::detect_system_proxy,
             crate::app::commands::proxy::force_proxy_fallback,
             crate::app::commands::proxy::force_proxy_recovery,
@@ -110,9 +119,14 @@ pub fn run() {
             crate::app::commands::credential::list_credentials,
             crate::app::commands::credential::set_master_password,
             crate::app::commands::credential::unlock_store,
+            crate::app::commands::credential::change_master_password,
             crate::app::commands::credential::export_audit_log,
+            crate::app::commands::credential::export_credentials_backup,
+            crate::app::commands::credential::import_credentials_backup,
             crate::app::commands::credential::cleanup_expired_credentials,
             crate::app::commands::credential::cleanup_audit_logs,
+            crate::app::commands::credential::verify_audit_log,
+            crate::app::commands::credential::query_audit_log,
             crate::app::commands::credential::is_credential_locked,
             crate::app::commands::credential::reset_credential_lock,
             crate::app::commands::credential::remaining_auth_attempts,
@@ -131,6 +145,7 @@ pub fn run() {
             crate::app::commands::workspace::clear_workspace_status_cache,
             crate::app::commands::workspace::invalidate_workspace_status_entry,
             crate::app::commands::workspace::update_repository_tags,
+            crate::app::commands::workspace::set_repository_credential,
             crate::app::commands::workspace::toggle_repository_enabled,
             crate::app::commands::workspace::get_workspace_config,
             crate::app::commands::workspace::validate_workspace_file,
@@ -199,7 +214,7 @@ fn setup_app_state(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error
     cfg_loader::set_global_base_dir(&base_dir);
 
     // Load or initialize configuration
-    let cfg = cfg_loader::load_or_init_at(&base_dir).unwrap_or_else(|e| {
+    let mut cfg = cfg_loader::load_or_init_at(&base_dir).unwrap_or_else(|e| {
         tracing::warn!(
             target = "app",
             error = %e,
@@ -208,6 +223,10 @@ fn setup_app_state(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error
         AppConfig::default()
     });
 
+    if cfg.network_profiles.auto_detect {
+        detect_network_profile(&mut cfg, &base_dir);
+    }
+
     if let Err(err) = crate::core::metrics::init_basic_observability(&cfg.observability) {
         tracing::warn!(target = "metrics", error = %err, "failed to initialize basic observability metrics");
     }
@@ -291,12 +310,24 @@ fn setup_app_state(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error
         }
     };
 
+    // mTLS 客户端私钥可保存在凭证库中
+    crate::core::tls::client_cert::set_key_store(cred_store.clone());
+
     // Manage credential factory state
     app.manage(Arc::new(Mutex::new(cred_store)) as SharedCredentialFactory);
 
     // Initialize audit logger
+    // 审计模式下持久化为哈希链日志，使 verify_audit_log 能校验磁盘内容
     let audit_mode = cred_config.audit_mode;
-    let audit_logger = AuditLogger::new(audit_mode);
+    let audit_logger = if audit_mode {
+        let log_path = base_dir_clone.join("credential-audit.json");
+        AuditLogger::with_log_file(audit_mode, &log_path).unwrap_or_else(|e| {
+            tracing::warn!(target = "credential", error = %e, "Persistent audit log unavailable; using in-memory log");
+            AuditLogger::new(audit_mode)
+        })
+    } else {
+        AuditLogger::new(audit_mode)
+    };
     app.manage(Arc::new(Mutex::new(audit_logger)) as SharedAuditLogger);
 
     tracing::info!(
@@ -341,6 +372,24 @@ fn setup_app_state(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error
     Ok(())
 }
 
+/// Switch to the network profile matching the current environment before any state is built.
+#[cfg(feature = "tauri-app")]
+fn detect_network_profile(cfg: &mut AppConfig, base_dir: &std::path::Path) {
+    let signals = network_profile::NetworkSignals::collect();
+    match network_profile::auto_switch(cfg, &signals) {
+        Ok(switch) if switch.changed => {
+            if let Err(err) = cfg_loader::save_at(cfg, base_dir) {
+                tracing::warn!(target = "config", error = %err, "Failed to persist detected network profile");
+            }
+            network_profile::emit_profile_switch(&switch, network_profile::TRIGGER_AUTO);
+        }
+        Ok(_) => {}
+        Err(err) => {
+            tracing::warn!(target = "config", error = %err, "Network profile detection failed");
+        }
+    }
+}
+
 /// Get fallback configuration directory.
 ///
 /// This is used when the standard app config directory cannot be determined.
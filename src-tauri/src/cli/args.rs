@@ -0,0 +1,406 @@
+use anyhow::{anyhow, bail, Context, Result};
+use std::path::PathBuf;
+
+use crate::core::tasks::model::WorkspaceBatchOperation;
+
+/// Usage text printed by `fwc help` and on argument errors.
+pub const USAGE: &str = "\
+fwc - headless Fireworks Collaboration git client
+
+USAGE:
+    fwc [--config-dir <DIR>] [--json] [--master-password <PW>] <COMMAND> [ARGS]
+
+GLOBAL OPTIONS:
+    --config-dir <DIR>        Configuration directory (default: $FWC_CONFIG_DIR or the desktop app's)
+    --json                    Print task events as JSON lines
+    --master-password <PW>    Master password of the encrypted credential file
+                              (default: $FWC_MASTER_PASSWORD, else the first line of stdin)
+
+COMMANDS:
+    clone <REPO> <DEST> [--depth N] [--filter SPEC] [--recurse-submodules]
+    fetch <DEST> [--repo URL] [--depth N] [--filter SPEC]
+    push <DEST> [--remote NAME] [--refspec SPEC]... [--username U --password P]
+                [--use-stored-credential]
+    workspace <clone|fetch|push> <WORKSPACE_FILE> [--repo ID]... [--include-disabled]
+                [--concurrency N] [clone/fetch/push options]
+    ip-pool status
+    ip-pool pick <HOST> [--port N]
+    proxy status
+    credential list
+    credential add <HOST> <USERNAME> [--expires-in-days N]
+                (secret read from $FWC_CREDENTIAL_SECRET or the next line of stdin)
+    credential remove <HOST> <USERNAME>
+    help
+
+clone/fetch/push/workspace also accept --strategy-override <JSON>.
+Commands that need stored credentials fail instead of falling back to memory-only storage.
+Ctrl-C cancels the running task (exit code 130).
+";
+
+/// Parsed command line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CliOptions {
+    pub config_dir: Option<PathBuf>,
+    pub json: bool,
+    pub master_password: Option<String>,
+    pub command: CliCommand,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CliCommand {
+    Clone(CloneArgs),
+    Fetch(FetchArgs),
+    Push(PushArgs),
+    Workspace(WorkspaceArgs),
+    IpPool(IpPoolCommand),
+    Proxy(ProxyCommand),
+    Credential(CredentialCommand),
+    Help,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CloneArgs {
+    pub repo: String,
+    pub dest: String,
+    pub depth: Option<u32>,
+    pub filter: Option<String>,
+    pub recurse_submodules: bool,
+    pub strategy_override: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FetchArgs {
+    pub dest: String,
+    pub repo: Option<String>,
+    pub depth: Option<u32>,
+    pub filter: Option<String>,
+    pub strategy_override: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PushArgs {
+    pub dest: String,
+    pub remote: Option<String>,
+    pub refspecs: Vec<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub use_stored_credential: bool,
+    pub strategy_override: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkspaceArgs {
+    pub operation: WorkspaceBatchOperation,
+    pub workspace_file: PathBuf,
+    pub repo_ids: Vec<String>,
+    pub include_disabled: bool,
+    pub max_concurrency: Option<usize>,
+    pub depth: Option<u32>,
+    pub filter: Option<String>,
+    pub recurse_submodules: Option<bool>,
+    pub remote: Option<String>,
+    pub refspecs: Vec<String>,
+    pub use_stored_credential: bool,
+    pub strategy_override: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IpPoolCommand {
+    Status,
+    Pick { host: String, port: u16 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProxyCommand {
+    Status,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CredentialCommand {
+    List,
+    Add {
+        host: String,
+        username: String,
+        expires_in_days: Option<u64>,
+    },
+    Remove {
+        host: String,
+        username: String,
+    },
+}
+
+/// Parse arguments (without the program name).
+pub fn parse_args<I, S>(args: I) -> Result<CliOptions>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let mut config_dir = None;
+    let mut json = false;
+    let mut master_password = None;
+    let mut rest = Vec::new();
+
+    let mut iter = args.into_iter().map(Into::into);
+    while let Some(arg) = iter.next() {
+        if arg == "--json" {
+            json = true;
+        } else if arg == "--config-dir" {
+            let value = iter
+                .next()
+                .ok_or_else(|| anyhow!("--config-dir requires a value"))?;
+            config_dir = Some(PathBuf::from(value));
+        } else if let Some(value) = arg.strip_prefix("--config-dir=") {
+            config_dir = Some(PathBuf::from(value));
+        } else if arg == "--master-password" {
+            let value = iter
+                .next()
+                .ok_or_else(|| anyhow!("--master-password requires a value"))?;
+            master_password = Some(value);
+        } else if let Some(value) = arg.strip_prefix("--master-password=") {
+            master_password = Some(value.to_string());
+        } else {
+            rest.push(arg);
+        }
+    }
+
+    let command = parse_command(&rest)?;
+    Ok(CliOptions {
+        config_dir,
+        json,
+        master_password,
+        command,
+    })
+}
+
+const CLONE_VALUES: &[&str] = &["depth", "filter", "strategy-override"];
+const FETCH_VALUES: &[&str] = &["repo", "depth", "filter", "strategy-override"];
+const PUSH_VALUES: &[&str] = &[
+    "remote",
+    "refspec",
+    "username",
+    "password",
+    "strategy-override",
+];
+const WORKSPACE_VALUES: &[&str] = &[
+    "repo",
+    "concurrency",
+    "depth",
+    "filter",
+    "remote",
+    "refspec",
+    "strategy-override",
+];
+
+fn parse_command(args: &[String]) -> Result<CliCommand> {
+    let Some((name, rest)) = args.split_first() else {
+        return Ok(CliCommand::Help);
+    };
+
+    match name.as_str() {
+        "help" | "--help" | "-h" => Ok(CliCommand::Help),
+        "clone" => {
+            let p = Parsed::parse(rest, CLONE_VALUES, &["recurse-submodules"])?;
+            let [repo, dest] = p.positionals::<2>("clone <REPO> <DEST>")?;
+            Ok(CliCommand::Clone(CloneArgs {
+                repo,
+                dest,
+                depth: p.number("depth")?,
+                filter: p.value("filter"),
+                recurse_submodules: p.switch("recurse-submodules"),
+                strategy_override: p.json("strategy-override")?,
+            }))
+        }
+        "fetch" => {
+            let p = Parsed::parse(rest, FETCH_VALUES, &[])?;
+            let [dest] = p.positionals::<1>("fetch <DEST>")?;
+            Ok(CliCommand::Fetch(FetchArgs {
+                dest,
+                repo: p.value("repo"),
+                depth: p.number("depth")?,
+                filter: p.value("filter"),
+                strategy_override: p.json("strategy-override")?,
+            }))
+        }
+        "push" => {
+            let p = Parsed::parse(rest, PUSH_VALUES, &["use-stored-credential"])?;
+            let [dest] = p.positionals::<1>("push <DEST>")?;
+            Ok(CliCommand::Push(PushArgs {
+                dest,
+                remote: p.value("remote"),
+                refspecs: p.values("refspec"),
+                username: p.value("username"),
+                password: p.value("password"),
+                use_stored_credential: p.switch("use-stored-credential"),
+                strategy_override: p.json("strategy-override")?,
+            }))
+        }
+        "workspace" => {
+            let p = Parsed::parse(
+                rest,
+                WORKSPACE_VALUES,
+                &[
+                    "include-disabled",
+                    "recurse-submodules",
+                    "use-stored-credential",
+                ],
+            )?;
+            let [operation, file] = p.positionals::<2>("workspace <clone|fetch|push> <FILE>")?;
+            let operation = match operation.as_str() {
+                "clone" => WorkspaceBatchOperation::Clone,
+                "fetch" => WorkspaceBatchOperation::Fetch,
+                "push" => WorkspaceBatchOperation::Push,
+                other => bail!("unknown workspace operation '{other}'"),
+            };
+            let max_concurrency = p.number::<usize>("concurrency")?;
+            if max_concurrency == Some(0) {
+                bail!("--concurrency must be greater than 0");
+            }
+            Ok(CliCommand::Workspace(WorkspaceArgs {
+                operation,
+                workspace_file: PathBuf::from(file),
+                repo_ids: p.values("repo"),
+                include_disabled: p.switch("include-disabled"),
+                max_concurrency,
+                depth: p.number("depth")?,
+                filter: p.value("filter"),
+                recurse_submodules: p.switch("recurse-submodules").then_some(true),
+                remote: p.value("remote"),
+                refspecs: p.values("refspec"),
+                use_stored_credential: p.switch("use-stored-credential"),
+                strategy_override: p.json("strategy-override")?,
+            }))
+        }
+        "ip-pool" => match rest.split_first() {
+            Some((sub, rest)) if sub == "status" => {
+                Parsed::parse(rest, &[], &[])?.positionals::<0>("ip-pool status")?;
+                Ok(CliCommand::IpPool(IpPoolCommand::Status))
+            }
+            Some((sub, rest)) if sub == "pick" => {
+                let p = Parsed::parse(rest, &["port"], &[])?;
+                let [host] = p.positionals::<1>("ip-pool pick <HOST>")?;
+                Ok(CliCommand::IpPool(IpPoolCommand::Pick {
+                    host,
+                    port: p.number("port")?.unwrap_or(443),
+                }))
+            }
+            _ => bail!("usage: fwc ip-pool <status|pick>"),
+        },
+        "proxy" => match rest.split_first() {
+            Some((sub, rest)) if sub == "status" => {
+                Parsed::parse(rest, &[], &[])?.positionals::<0>("proxy status")?;
+                Ok(CliCommand::Proxy(ProxyCommand::Status))
+            }
+            _ => bail!("usage: fwc proxy status"),
+        },
+        "credential" => match rest.split_first() {
+            Some((sub, rest)) if sub == "list" => {
+                Parsed::parse(rest, &[], &[])?.positionals::<0>("credential list")?;
+                Ok(CliCommand::Credential(CredentialCommand::List))
+            }
+            Some((sub, rest)) if sub == "add" => {
+                let p = Parsed::parse(rest, &["expires-in-days"], &[])?;
+                let [host, username] = p.positionals::<2>("credential add <HOST> <USERNAME>")?;
+                Ok(CliCommand::Credential(CredentialCommand::Add {
+                    host,
+                    username,
+                    expires_in_days: p.number("expires-in-days")?,
+                }))
+            }
+            Some((sub, rest)) if sub == "remove" => {
+                let p = Parsed::parse(rest, &[], &[])?;
+                let [host, username] = p.positionals::<2>("credential remove <HOST> <USERNAME>")?;
+                Ok(CliCommand::Credential(CredentialCommand::Remove {
+                    host,
+                    username,
+                }))
+            }
+            _ => bail!("usage: fwc credential <list|add|remove>"),
+        },
+        other => bail!("unknown command '{other}'"),
+    }
+}
+
+/// Positional arguments plus `--flag value` / `--flag=value` / `--switch` options.
+struct Parsed {
+    positionals: Vec<String>,
+    values: Vec<(String, String)>,
+    switches: Vec<String>,
+}
+
+impl Parsed {
+    fn parse(args: &[String], value_flags: &[&str], switch_flags: &[&str]) -> Result<Self> {
+        let mut parsed = Parsed {
+            positionals: Vec::new(),
+            values: Vec::new(),
+            switches: Vec::new(),
+        };
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            let Some(flag) = arg.strip_prefix("--") else {
+                parsed.positionals.push(arg.clone());
+                continue;
+            };
+            let (name, inline) = match flag.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (flag, None),
+            };
+            if value_flags.contains(&name) {
+                let value = match inline {
+                    Some(v) => v,
+                    None => iter
+                        .next()
+                        .cloned()
+                        .ok_or_else(|| anyhow!("--{name} requires a value"))?,
+                };
+                parsed.values.push((name.to_string(), value));
+            } else if switch_flags.contains(&name) && inline.is_none() {
+                parsed.switches.push(name.to_string());
+            } else {
+                bail!("unknown option '{arg}'");
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn positionals<const N: usize>(&self, usage: &str) -> Result<[String; N]> {
+        <[String; N]>::try_from(self.positionals.clone()).map_err(|_| anyhow!("usage: fwc {usage}"))
+    }
+
+    fn value(&self, name: &str) -> Option<String> {
+        self.values
+            .iter()
+            .rev()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.clone())
+    }
+
+    fn values(&self, name: &str) -> Vec<String> {
+        self.values
+            .iter()
+            .filter(|(k, _)| k == name)
+            .map(|(_, v)| v.clone())
+            .collect()
+    }
+
+    fn switch(&self, name: &str) -> bool {
+        self.switches.iter().any(|s| s == name)
+    }
+
+    fn number<T: std::str::FromStr>(&self, name: &str) -> Result<Option<T>>
+    where
+        T::Err: std::error::Error + Send + Sync + 'static,
+    {
+        self.value(name)
+            .map(|v| {
+                v.parse::<T>()
+                    .with_context(|| format!("--{name} expects a non-negative integer"))
+            })
+            .transpose()
+    }
+
+    fn json(&self, name: &str) -> Result<Option<serde_json::Value>> {
+        self.value(name)
+            .map(|v| serde_json::from_str(&v).with_context(|| format!("--{name} expects JSON")))
+            .transpose()
+    }
+}
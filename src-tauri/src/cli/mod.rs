@@ -0,0 +1,34 @@
+//! Headless command line interface (`fwc`).
+//!
+//! Drives the same core task engine as the desktop app without Tauri, so CI
+//! scripts and servers get the accelerated transport (Fake SNI, IP pool,
+//! proxy, retry) for clone / fetch / push and workspace batch operations.
+//!
+//! ## Module Structure
+//!
+//! - `args`: Command line parsing into [`CliOptions`]
+//! - `runner`: Config loading, `TaskRegistry` wiring and command execution
+//! - `output`: Printing of `TaskProgressEvent` / state / error events
+//!
+//! Task events reach the CLI through a headless
+//! [`AppHandle`](crate::events::emitter::AppHandle) listener, i.e. the exact
+//! payloads the frontend receives on `task://progress`.
+//!
+//! ## Usage
+//!
+//! ```no_run
+//! use fireworks_collaboration_lib::cli::{parse_args, run};
+//! let opts = parse_args(["clone", "https://github.com/owner/repo", "./repo"]).unwrap();
+//! let exit_code = run(opts).unwrap();
+//! ```
+
+mod args;
+mod output;
+mod runner;
+
+pub use args::{
+    parse_args, CliCommand, CliOptions, CloneArgs, CredentialCommand, FetchArgs, IpPoolCommand,
+    ProxyCommand, PushArgs, WorkspaceArgs, USAGE,
+};
+pub use output::{format_event, format_progress, EventPrinter};
+pub use runner::{default_config_dir, run, run_from_env, EXIT_CANCELED};
@@ -0,0 +1,106 @@
+use std::io::Write;
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::core::tasks::model::{TaskErrorEvent, TaskProgressEvent, TaskStateEvent};
+use crate::events::emitter::EventListener;
+
+/// Prints task events received through a headless [`crate::events::emitter::AppHandle`].
+///
+/// Human mode writes one line per event to stderr so stdout stays free for
+/// command results; JSON mode writes `{"event": .., "payload": ..}` lines to stdout.
+#[derive(Debug, Clone, Copy)]
+pub struct EventPrinter {
+    json: bool,
+}
+
+impl EventPrinter {
+    pub fn new(json: bool) -> Self {
+        Self { json }
+    }
+
+    pub fn listener(self) -> EventListener {
+        Arc::new(move |event, payload| self.print(event, payload))
+    }
+
+    fn print(&self, event: &str, payload: &serde_json::Value) {
+        if self.json {
+            let line = serde_json::json!({ "event": event, "payload": payload });
+            let mut out = std::io::stdout().lock();
+            let _ = writeln!(out, "{line}");
+            return;
+        }
+        if let Some(line) = format_event(event, payload) {
+            eprintln!("{line}");
+        }
+    }
+}
+
+/// Render one task event as a human readable line.
+pub fn format_event(event: &str, payload: &serde_json::Value) -> Option<String> {
+    match event {
+        "task://progress" => {
+            let p: TaskProgressEvent = serde_json::from_value(payload.clone()).ok()?;
+            Some(format_progress(&p))
+        }
+        "task://state" => {
+            let s: TaskStateEvent = serde_json::from_value(payload.clone()).ok()?;
+            let state = serde_json::to_value(&s.state).ok()?;
+            Some(format!(
+                "[{} {}] state: {}",
+                s.kind,
+                short_id(&s.task_id.to_string()),
+                state.as_str().unwrap_or("unknown")
+            ))
+        }
+        "task://error" => {
+            let e: TaskErrorEvent = serde_json::from_value(payload.clone()).ok()?;
+            Some(format!(
+                "[{} {}] error ({}): {}",
+                e.kind,
+                short_id(&e.task_id.to_string()),
+                e.category,
+                e.message
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Render a progress event, e.g. `[GitClone 1a2b3c4d] Receiving 45% objects=120 bytes=4096`.
+pub fn format_progress(p: &TaskProgressEvent) -> String {
+    let mut line = format!(
+        "[{} {}] {} {}%",
+        p.kind,
+        short_id(&p.task_id.to_string()),
+        p.phase,
+        p.percent
+    );
+    match (p.objects, p.total_hint) {
+        (Some(objects), Some(total)) => line.push_str(&format!(" objects={objects}/{total}")),
+        (Some(objects), None) => line.push_str(&format!(" objects={objects}")),
+        _ => {}
+    }
+    if let Some(bytes) = p.bytes {
+        line.push_str(&format!(" bytes={bytes}"));
+    }
+    if let Some(retried) = p.retried_times {
+        line.push_str(&format!(" retry={retried}"));
+    }
+    line
+}
+
+/// Print a command result to stdout as pretty JSON.
+pub fn print_json<T: Serialize>(value: &T) -> anyhow::Result<()> {
+    let text = serde_json::to_string_pretty(value)?;
+    match writeln!(std::io::stdout().lock(), "{text}") {
+        // 下游管道提前关闭（如 `| head`）不视为错误
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+        other => Ok(other?),
+    }
+}
+
+fn short_id(id: &str) -> &str {
+    id.get(..8).unwrap_or(id)
+}
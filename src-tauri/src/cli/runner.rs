@@ -0,0 +1,710 @@
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Serialize;
+use std::cell::OnceCell;
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::runtime::Runtime;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use crate::core::config::{loader, model::AppConfig};
+use crate::core::credential::{
+    audit::OperationType,
+    binding::{resolve_binding, resolve_push_credentials},
+    AuditLogger, Credential, CredentialBinding, CredentialStore, CredentialStoreFactory,
+};
+use crate::core::ip_pool::{
+    self, cache::IpStat, global::obtain_global_pool, manager::IpSelectionStrategy,
+    IpPoolFileConfig, IpPoolRuntimeConfig,
+};
+use crate::core::proxy::{ProxyManager, ProxyMode, ProxyState, SystemProxyDetector};
+use crate::core::tasks::model::{TaskKind, TaskState, WorkspaceBatchOperation};
+use crate::core::tasks::workspace_batch::{
+    CloneOptions, FetchOptions, PushOptions, WorkspaceBatchChildOperation, WorkspaceBatchChildSpec,
+};
+use crate::core::tasks::TaskRegistry;
+use crate::core::workspace::{RepositoryEntry, WorkspaceStorage};
+use crate::events::emitter::AppHandle;
+
+use super::args::{
+    CliCommand, CliOptions, CloneArgs, CredentialCommand, FetchArgs, IpPoolCommand, ProxyCommand,
+    PushArgs, WorkspaceArgs, USAGE,
+};
+use super::output::{print_json, EventPrinter};
+
+/// Exit code for a task that ended in `Canceled`.
+pub const EXIT_CANCELED: i32 = 130;
+
+/// Parse `std::env::args()` and run; returns the process exit code.
+pub fn run_from_env() -> Result<i32> {
+    let opts = super::args::parse_args(std::env::args().skip(1))?;
+    run(opts)
+}
+
+/// Run a parsed command; returns the process exit code.
+///
+/// Task commands return 0 on `Completed`, 1 on `Failed` and [`EXIT_CANCELED`] on `Canceled`;
+/// Ctrl-C cancels the running task.
+pub fn run(opts: CliOptions) -> Result<i32> {
+    if opts.command == CliCommand::Help {
+        print!("{USAGE}");
+        return Ok(0);
+    }
+
+    let ctx = CliContext::init(&opts)?;
+    match opts.command {
+        CliCommand::Help => unreachable!(),
+        CliCommand::Clone(args) => ctx.clone_repo(args),
+        CliCommand::Fetch(args) => ctx.fetch_repo(args),
+        CliCommand::Push(args) => ctx.push_repo(args),
+        CliCommand::Workspace(args) => ctx.workspace_batch(args),
+        CliCommand::IpPool(cmd) => ctx.ip_pool(cmd),
+        CliCommand::Proxy(cmd) => ctx.proxy(cmd),
+        CliCommand::Credential(cmd) => ctx.credential(cmd),
+    }
+}
+
+/// Default configuration directory, shared with the desktop app.
+pub fn default_config_dir() -> PathBuf {
+    if let Ok(dir) = std::env::var("FWC_CONFIG_DIR") {
+        if !dir.trim().is_empty() {
+            return PathBuf::from(dir);
+        }
+    }
+    match dirs_next::config_dir() {
+        Some(dir) => dir.join("top.jwyihao.fireworks-collaboration"),
+        None => std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+    }
+}
+
+struct CliContext {
+    base_dir: PathBuf,
+    config: AppConfig,
+    registry: Arc<TaskRegistry>,
+    runtime: Runtime,
+    app: AppHandle,
+    master_password: Option<String>,
+    store: OnceCell<Arc<dyn CredentialStore>>,
+}
+
+impl CliContext {
+    /// Mirror the desktop setup: global base dir, config, IP pool.
+    fn init(opts: &CliOptions) -> Result<Self> {
+        let base_dir = opts.config_dir.clone().unwrap_or_else(default_config_dir);
+        std::fs::create_dir_all(&base_dir)
+            .with_context(|| format!("create config dir: {}", base_dir.display()))?;
+        loader::set_global_base_dir(&base_dir);
+        let config = loader::load_or_init_at(&base_dir)
+            .with_context(|| format!("load config from {}", base_dir.display()))?;
+
+        let effective = ip_pool::load_effective_config_at(&config, &base_dir).unwrap_or_else(|e| {
+            tracing::warn!(target = "ip_pool", error = %e, "Failed to load IP pool config; using defaults");
+            ip_pool::EffectiveIpPoolConfig::from_parts(
+                config.ip_pool.clone(),
+                IpPoolFileConfig::default(),
+            )
+        });
+        if let Ok(mut pool) = obtain_global_pool().lock() {
+            pool.update_config(effective);
+        }
+
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .context("build tokio runtime")?;
+
+        Ok(Self {
+            base_dir,
+            config,
+            registry: Arc::new(TaskRegistry::new()),
+            runtime,
+            app: AppHandle::headless(EventPrinter::new(opts.json).listener()),
+            master_password: opts.master_password.clone(),
+            store: OnceCell::new(),
+        })
+    }
+
+    fn app(&self) -> Option<AppHandle> {
+        Some(self.app.clone())
+    }
+
+    /// Spawn inside the runtime, wait for the task and map its final state to an exit code.
+    ///
+    /// Ctrl-C cancels the task through the registry and still waits for it to settle.
+    fn run_task(&self, id: Uuid, spawn: impl FnOnce() -> JoinHandle<()>) -> Result<i32> {
+        let mut handle = {
+            let _guard = self.runtime.enter();
+            spawn()
+        };
+        self.runtime
+            .block_on(async {
+                tokio::select! {
+                    joined = &mut handle => joined,
+                    Ok(()) = tokio::signal::ctrl_c() => {
+                        eprintln!("fwc: canceling task {id}");
+                        self.registry.cancel(&id);
+                        handle.await
+                    }
+                }
+            })
+            .map_err(|e| anyhow!("task join error: {e}"))?;
+
+        let state = self
+            .registry
+            .snapshot(&id)
+            .ok_or_else(|| anyhow!("task snapshot missing"))?
+            .state;
+        Ok(match state {
+            TaskState::Completed => 0,
+            TaskState::Canceled => EXIT_CANCELED,
+            _ => {
+                if let Some(reason) = self.registry.fail_reason(&id) {
+                    eprintln!("fwc: task failed: {reason}");
+                }
+                1
+            }
+        })
+    }
+
+    fn clone_repo(&self, args: CloneArgs) -> Result<i32> {
+        let depth = args.depth.map(serde_json::Value::from);
+        let (id, token) = self.registry.create(TaskKind::GitClone {
+            repo: args.repo.clone(),
+            dest: args.dest.clone(),
+            depth: args.depth,
+            filter: args.filter.clone(),
+            strategy_override: args.strategy_override.clone(),
+            recurse_submodules: args.recurse_submodules,
+        });
+        self.run_task(id, || {
+            self.registry.spawn_git_clone_task_with_opts(
+                self.app(),
+                id,
+                token,
+                args.repo,
+                args.dest,
+                depth,
+                args.filter,
+                args.strategy_override,
+                args.recurse_submodules,
+                None,
+            )
+        })
+    }
+
+    fn fetch_repo(&self, args: FetchArgs) -> Result<i32> {
+        let repo = args.repo.unwrap_or_default();
+        let depth = args.depth.map(serde_json::Value::from);
+        let (id, token) = self.registry.create(TaskKind::GitFetch {
+            repo: repo.clone(),
+            dest: args.dest.clone(),
+            depth: args.depth,
+            filter: args.filter.clone(),
+            strategy_override: args.strategy_override.clone(),
+        });
+        self.run_task(id, || {
+            self.registry.spawn_git_fetch_task_with_opts(
+                self.app(),
+                id,
+                token,
+                repo,
+                args.dest,
+                None,
+                depth,
+                args.filter,
+                args.strategy_override,
+                None,
+            )
+        })
+    }
+
+    fn push_repo(&self, args: PushArgs) -> Result<i32> {
+        let (username, password) = self.push_credentials(
+            &args.dest,
+            args.remote.as_deref(),
+            None,
+            args.username,
+            args.password,
+            args.use_stored_credential,
+        )?;
+        let refspecs = (!args.refspecs.is_empty()).then_some(args.refspecs);
+
+        let (id, token) = self.registry.create(TaskKind::GitPush {
+            dest: args.dest.clone(),
+            remote: args.remote.clone(),
+            refspecs: refspecs.clone(),
+            username: username.clone(),
+            password: password.clone(),
+            strategy_override: args.strategy_override.clone(),
+        });
+        self.run_task(id, || {
+            self.registry.spawn_git_push_task(
+                self.app(),
+                id,
+                token,
+                args.dest,
+                args.remote,
+                refspecs,
+                username,
+                password,
+                args.strategy_override,
+                None,
+            )
+        })
+    }
+
+    /// Resolve push credentials with the desktop app's rules.
+    ///
+    /// Explicit username/password win; otherwise a binding (explicit or from
+    /// `credential.bindings`) always applies and never falls back to another
+    /// account, and without one the host-only lookup runs only when `use_stored`.
+    fn push_credentials(
+        &self,
+        dest: &str,
+        remote: Option<&str>,
+        explicit: Option<&CredentialBinding>,
+        username: Option<String>,
+        password: Option<String>,
+        use_stored: bool,
+    ) -> Result<(Option<String>, Option<String>)> {
+        let remote_url = match repo_remote_url(dest, remote.unwrap_or("origin")) {
+            Ok(url) => url,
+            Err(_) if explicit.is_some() && username.is_none() && password.is_none() => {
+                bail!("cannot resolve remote URL of '{dest}' for its credential binding")
+            }
+            Err(_) => return Ok((username, password)),
+        };
+        let binding = resolve_binding(&remote_url, explicit, &self.config.credential.bindings);
+        resolve_push_credentials(
+            username,
+            password,
+            &remote_url,
+            binding.as_ref(),
+            use_stored,
+            || {
+                self.credential_store()
+                    .map(Some)
+                    .map_err(|e| format!("{e:#}"))
+            },
+        )
+        .map_err(|e| anyhow!(e))
+    }
+
+    fn workspace_batch(&self, args: WorkspaceArgs) -> Result<i32> {
+        let workspace = WorkspaceStorage::new(args.workspace_file.clone())
+            .load()
+            .with_context(|| format!("load workspace {}", args.workspace_file.display()))?;
+
+        let repos = select_repos(
+            &workspace.repositories,
+            &args.repo_ids,
+            args.include_disabled,
+        )?;
+        if repos.is_empty() {
+            bail!("No repositories selected for batch operation");
+        }
+        let root = if workspace.root_path.is_absolute() {
+            workspace.root_path.clone()
+        } else {
+            std::env::current_dir()?.join(&workspace.root_path)
+        };
+        let concurrency = args
+            .max_concurrency
+            .unwrap_or(self.config.workspace.max_concurrent_repos)
+            .max(1);
+        let depth_value = args.depth.map(serde_json::Value::from);
+        let use_stored = args.use_stored_credential;
+        let refspecs = (!args.refspecs.is_empty()).then(|| args.refspecs.clone());
+
+        let mut specs = Vec::with_capacity(repos.len());
+        for repo in repos {
+            let dest_path = if repo.path.is_absolute() {
+                repo.path.clone()
+            } else {
+                root.join(&repo.path)
+            };
+            let dest = dest_path
+                .to_str()
+                .ok_or_else(|| anyhow!("Path '{}' is not valid UTF-8", dest_path.display()))?
+                .to_string();
+
+            let operation = match args.operation {
+                WorkspaceBatchOperation::Clone => {
+                    if repo.remote_url.trim().is_empty() {
+                        bail!("Repository '{}' has no remote URL", repo.id);
+                    }
+                    if dest_path.exists() {
+                        bail!("Destination '{}' already exists", dest_path.display());
+                    }
+                    WorkspaceBatchChildOperation::Clone(CloneOptions {
+                        repo_url: repo.remote_url.clone(),
+                        dest,
+                        depth_u32: args.depth,
+                        depth_value: depth_value.clone(),
+                        filter: args.filter.clone(),
+                        strategy_override: args.strategy_override.clone(),
+                        recurse_submodules: args.recurse_submodules.unwrap_or(repo.has_submodules),
+                    })
+                }
+                WorkspaceBatchOperation::Fetch => {
+                    ensure_git_repo(&dest_path)?;
+                    WorkspaceBatchChildOperation::Fetch(FetchOptions {
+                        repo_url: repo.remote_url.clone(),
+                        dest,
+                        preset: None,
+                        depth_u32: args.depth,
+                        depth_value: depth_value.clone(),
+                        filter: args.filter.clone(),
+                        strategy_override: args.strategy_override.clone(),
+                    })
+                }
+                WorkspaceBatchOperation::Push => {
+                    ensure_git_repo(&dest_path)?;
+                    let (username, password) = self
+                        .push_credentials(
+                            &dest,
+                            args.remote.as_deref(),
+                            repo.credential.as_ref(),
+                            None,
+                            None,
+                            use_stored,
+                        )
+                        .with_context(|| format!("Repository '{}'", repo.id))?;
+                    WorkspaceBatchChildOperation::Push(PushOptions {
+                        dest,
+                        remote: args.remote.clone(),
+                        refspecs: refspecs.clone(),
+                        username,
+                        password,
+                        strategy_override: args.strategy_override.clone(),
+                    })
+                }
+            };
+            specs.push(WorkspaceBatchChildSpec {
+                repo_id: repo.id.clone(),
+                repo_name: repo.name.clone(),
+                operation,
+            });
+        }
+
+        let (id, token) = self.registry.create(TaskKind::WorkspaceBatch {
+            operation: args.operation.clone(),
+            total: specs.len() as u32,
+        });
+        self.run_task(id, || {
+            self.registry.spawn_workspace_batch_task(
+                self.app(),
+                id,
+                token,
+                args.operation,
+                specs,
+                concurrency,
+            )
+        })
+    }
+
+    fn ip_pool(&self, cmd: IpPoolCommand) -> Result<i32> {
+        let pool = obtain_global_pool();
+        let pool = pool
+            .lock()
+            .map_err(|e| anyhow!("ip pool lock poisoned: {e}"))?;
+        match cmd {
+            IpPoolCommand::Status => {
+                let mut cache_entries: Vec<IpPoolCacheView> = pool
+                    .cache()
+                    .snapshot()
+                    .into_iter()
+                    .map(|(key, slot)| IpPoolCacheView {
+                        host: key.host,
+                        port: key.port,
+                        best: slot.best,
+                        alternatives: slot.alternatives,
+                    })
+                    .collect();
+                cache_entries.sort_by(|a, b| a.host.cmp(&b.host).then(a.port.cmp(&b.port)));
+                let mut tripped_ips: Vec<String> = pool
+                    .get_tripped_ips()
+                    .into_iter()
+                    .map(|ip| ip.to_string())
+                    .collect();
+                tripped_ips.sort();
+
+                print_json(&IpPoolStatusView {
+                    enabled: pool.is_enabled(),
+                    preheat_enabled: pool.preheat_enabled(),
+                    preheat_targets: pool.preheat_target_count(),
+                    auto_disabled_until: pool.auto_disabled_until(),
+                    runtime: pool.runtime_config().clone(),
+                    file: pool.file_config().clone(),
+                    cache_entries,
+                    tripped_ips,
+                })?;
+            }
+            IpPoolCommand::Pick { host, port } => {
+                let selection = pool.pick_best_blocking(&host, port);
+                print_json(&IpSelectionView {
+                    strategy: match selection.strategy() {
+                        IpSelectionStrategy::SystemDefault => "system",
+                        IpSelectionStrategy::Cached => "cached",
+                    },
+                    selected: selection.selected().cloned(),
+                    alternatives: selection.alternatives().to_vec(),
+                    host,
+                    port,
+                })?;
+            }
+        }
+        Ok(0)
+    }
+
+    fn proxy(&self, cmd: ProxyCommand) -> Result<i32> {
+        match cmd {
+            ProxyCommand::Status => {
+                let manager = ProxyManager::new(self.config.proxy.clone());
+                let system = (manager.mode() == ProxyMode::System)
+                    .then(SystemProxyDetector::detect)
+                    .flatten()
+                    .map(|cfg| SystemProxyView {
+                        mode: cfg.mode,
+                        url: cfg.sanitized_url(),
+                    });
+                print_json(&ProxyStatusView {
+                    mode: manager.mode(),
+                    state: manager.state(),
+                    enabled: manager.is_enabled(),
+                    url: manager.sanitized_url(),
+                    custom_transport_disabled: manager.should_disable_custom_transport(),
+                    system,
+                })?;
+            }
+        }
+        Ok(0)
+    }
+
+    fn credential(&self, cmd: CredentialCommand) -> Result<i32> {
+        let store = self.credential_store()?;
+        let audit = self.audit_logger();
+        match cmd {
+            CredentialCommand::List => {
+                let creds = store.list().map_err(|e| anyhow!("list credentials: {e}"))?;
+                audit.log_operation(OperationType::List, "", "", None, true, None);
+                let views: Vec<CredentialView> = creds.iter().map(CredentialView::from).collect();
+                print_json(&views)?;
+            }
+            CredentialCommand::Add {
+                host,
+                username,
+                expires_in_days,
+            } => {
+                let secret = read_secret()?;
+                let credential = match expires_in_days {
+                    Some(days) => Credential::new_with_expiry(
+                        host.clone(),
+                        username.clone(),
+                        secret,
+                        SystemTime::now() + Duration::from_secs(days * 86400),
+                    ),
+                    None => Credential::new(host.clone(), username.clone(), secret),
+                };
+                let result = store.add(credential.clone());
+                audit.log_operation(
+                    OperationType::Add,
+                    &host,
+                    &username,
+                    Some(&credential.password_or_token),
+                    result.is_ok(),
+                    result.as_ref().err().map(|e| e.to_string()),
+                );
+                result.map_err(|e| anyhow!("add credential: {e}"))?;
+                eprintln!("fwc: credential {username}@{host} added");
+            }
+            CredentialCommand::Remove { host, username } => {
+                let result = store.remove(&host, &username);
+                audit.log_operation(
+                    OperationType::Remove,
+                    &host,
+                    &username,
+                    None,
+                    result.is_ok(),
+                    result.as_ref().err().map(|e| e.to_string()),
+                );
+                result.map_err(|e| anyhow!("remove credential: {e}"))?;
+                eprintln!("fwc: credential {username}@{host} removed");
+            }
+        }
+        Ok(0)
+    }
+
+    /// Open the persistent credential store once per run.
+    ///
+    /// Unlike the desktop app there is no unlock dialog later, so a store that
+    /// would only live in memory is an error rather than a silent fallback.
+    fn credential_store(&self) -> Result<Arc<dyn CredentialStore>> {
+        if let Some(store) = self.store.get() {
+            return Ok(Arc::clone(store));
+        }
+        let store = CredentialStoreFactory::create_persistent(&self.config.credential, || {
+            read_master_password(self.master_password.as_deref()).map_err(|e| format!("{e:#}"))
+        })
+        .map_err(|e| anyhow!("Failed to open credential store: {e}"))?;
+        Ok(Arc::clone(self.store.get_or_init(|| store)))
+    }
+
+    /// Same audit log file as the desktop app so CLI operations join its hash chain.
+    fn audit_logger(&self) -> AuditLogger {
+        let audit_mode = self.config.credential.audit_mode;
+        if !audit_mode {
+            return AuditLogger::new(false);
+        }
+        AuditLogger::with_log_file(true, self.base_dir.join("credential-audit.json"))
+            .unwrap_or_else(|e| {
+                tracing::warn!(target = "credential", error = %e, "Persistent audit log unavailable; using in-memory log");
+                AuditLogger::new(true)
+            })
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct IpPoolStatusView {
+    enabled: bool,
+    preheat_enabled: bool,
+    preheat_targets: usize,
+    auto_disabled_until: Option<i64>,
+    runtime: IpPoolRuntimeConfig,
+    file: IpPoolFileConfig,
+    cache_entries: Vec<IpPoolCacheView>,
+    tripped_ips: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct IpPoolCacheView {
+    host: String,
+    port: u16,
+    best: Option<IpStat>,
+    alternatives: Vec<IpStat>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct IpSelectionView {
+    host: String,
+    port: u16,
+    strategy: &'static str,
+    selected: Option<IpStat>,
+    alternatives: Vec<IpStat>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProxyStatusView {
+    mode: ProxyMode,
+    state: ProxyState,
+    enabled: bool,
+    url: String,
+    custom_transport_disabled: bool,
+    system: Option<SystemProxyView>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SystemProxyView {
+    mode: ProxyMode,
+    url: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CredentialView {
+    host: String,
+    username: String,
+    masked_password: String,
+    expired: bool,
+}
+
+impl From<&Credential> for CredentialView {
+    fn from(cred: &Credential) -> Self {
+        Self {
+            host: cred.host.clone(),
+            username: cred.username.clone(),
+            masked_password: cred.masked_password(),
+            expired: cred.is_expired(),
+        }
+    }
+}
+
+fn select_repos(
+    repos: &[RepositoryEntry],
+    ids: &[String],
+    include_disabled: bool,
+) -> Result<Vec<RepositoryEntry>> {
+    if ids.is_empty() {
+        return Ok(repos
+            .iter()
+            .filter(|r| include_disabled || r.enabled)
+            .cloned()
+            .collect());
+    }
+    ids.iter()
+        .map(|id| {
+            let repo = repos
+                .iter()
+                .find(|r| &r.id == id)
+                .ok_or_else(|| anyhow!("Repository '{id}' not found"))?;
+            if !include_disabled && !repo.enabled {
+                bail!("Repository '{id}' is disabled");
+            }
+            Ok(repo.clone())
+        })
+        .collect()
+}
+
+fn ensure_git_repo(path: &Path) -> Result<()> {
+    if !path.join(".git").exists() {
+        bail!("Repository '{}' is not initialized", path.display());
+    }
+    Ok(())
+}
+
+fn repo_remote_url(dest: &str, remote: &str) -> Result<String> {
+    let repo = git2::Repository::open(dest)?;
+    let remote = repo.find_remote(remote)?;
+    remote
+        .url()
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("remote URL is not valid UTF-8"))
+}
+
+/// Secret for `credential add`: `$FWC_CREDENTIAL_SECRET`, else the next stdin line.
+fn read_secret() -> Result<String> {
+    read_env_or_stdin_line("FWC_CREDENTIAL_SECRET", "secret")
+}
+
+/// Master password: `--master-password`, else `$FWC_MASTER_PASSWORD`, else the first stdin line.
+fn read_master_password(flag: Option<&str>) -> Result<String> {
+    match flag {
+        Some(password) if !password.is_empty() => Ok(password.to_string()),
+        _ => read_env_or_stdin_line("FWC_MASTER_PASSWORD", "master password"),
+    }
+}
+
+fn read_env_or_stdin_line(var: &str, what: &str) -> Result<String> {
+    if let Ok(value) = std::env::var(var) {
+        if !value.is_empty() {
+            return Ok(value);
+        }
+    }
+    let mut line = String::new();
+    std::io::stdin()
+        .lock()
+        .read_line(&mut line)
+        .with_context(|| format!("read {what} from stdin"))?;
+    let value = line.trim_end_matches(['\r', '\n']).to_string();
+    if value.is_empty() {
+        bail!("no {what} provided (set {var} or pipe it on stdin)");
+    }
+    Ok(value)
+}
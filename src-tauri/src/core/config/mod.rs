@@ -1,4 +1,5 @@
 pub mod fs;
 pub mod loader;
 pub mod model;
+pub mod network_profile;
 pub mod team_template;
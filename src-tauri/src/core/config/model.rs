@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 
+use super::network_profile::NetworkProfilesConfig;
 use crate::core::credential::config::CredentialConfig;
 use crate::core::ip_pool::IpPoolRuntimeConfig;
 use crate::core::proxy::ProxyConfig;
@@ -32,6 +33,18 @@ pub struct HttpCfg {
     /// P3.5: 触发自动禁用后保持禁用状态的冷却秒数。
     #[serde(default = "default_auto_disable_cooldown_sec")]
     pub auto_disable_fake_cooldown_sec: u64,
+    /// 自定义传输 keep-alive 连接池：每个 (host, SNI, IP) 最多保留的空闲连接数。0 表示禁用复用。
+    #[serde(default = "default_pool_max_idle_per_host")]
+    pub pool_max_idle_per_host: usize,
+    /// 空闲连接在池中保留的最长秒数，超时后关闭。
+    #[serde(default = "default_pool_idle_timeout_sec")]
+    pub pool_idle_timeout_sec: u64,
+    /// 自定义传输是否通过 ALPN 协商 HTTP/2（服务端不支持时自动回落 HTTP/1.1）
+    #[serde(default = "default_true")]
+    pub http2_enabled: bool,
+    /// 自定义传输是否使用 Git 协议 v2（ls-refs 按 ref-prefix 过滤引用通告）；服务端不支持时回落 v0
+    #[serde(default)]
+    pub protocol_v2_enabled: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -49,6 +62,158 @@ pub struct TlsCfg {
     /// P3.2: 证书指纹日志文件最大字节数（达到后进行简单滚动 rename 为 .1 并重新开始）。默认 5MB。
     #[serde(default = "default_cert_fp_max_bytes")]
     pub cert_fp_max_bytes: u64,
+    /// 额外信任锚（如企业 TLS 检查代理的私有 CA），可按主机限定作用范围。
+    #[serde(default)]
+    pub custom_cas: Vec<CustomCaCfg>,
+    /// 是否额外信任操作系统证书库（对所有主机生效）。默认关闭。
+    #[serde(default)]
+    pub use_system_trust_store: bool,
+    /// mTLS 客户端证书，按主机模式选择（首个匹配项生效）。
+    #[serde(default)]
+    pub client_certs: Vec<ClientCertCfg>,
+    /// 按主机模式配置的 SPKI Pin 策略（首个匹配项生效）；匹配的主机不再使用全局 `spki_pins`。
+    #[serde(default)]
+    pub pin_policies: Vec<SpkiPinPolicy>,
+}
+
+/// 一组主机的 SPKI Pin 策略
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SpkiPinPolicy {
+    /// 生效的主机（精确或 `*.example.com`）
+    pub hosts: Vec<String>,
+    /// 当前证书链使用的 pin（Base64URL 无填充，长度=43）
+    pub primary: Vec<String>,
+    /// 轮换备用 pin（如下一把密钥或上级 CA 的 SPKI），与主 pin 同等有效
+    #[serde(default)]
+    pub backup: Vec<String>,
+    /// 策略过期日（`YYYY-MM-DD` 或 RFC 3339）；过期后不再阻断，仅报告不匹配
+    #[serde(default)]
+    pub expires_at: Option<String>,
+    /// 仅报告：不匹配时发送 `CertFpPinMismatch` 事件但不中断连接
+    #[serde(default)]
+    pub report_only: bool,
+}
+
+/// 一组额外信任锚
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomCaCfg {
+    /// PEM 文件，或目录（加载其中的 `.pem` / `.crt` / `.cer`）
+    pub path: String,
+    /// 生效的主机（精确或 `*.example.com`）；为空表示所有主机
+    #[serde(default)]
+    pub hosts: Vec<String>,
+}
+
+/// 一张 mTLS 客户端证书
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientCertCfg {
+    /// 生效的主机（精确或 `*.example.com`）
+    pub hosts: Vec<String>,
+    /// PEM 证书链（可同时包含私钥），或 PKCS#12 文件（扩展名 `.p12` / `.pfx`）
+    pub cert_path: String,
+    /// PEM 私钥文件；PKCS#12 或私钥保存在凭证库时留空
+    #[serde(default)]
+    pub key_path: Option<String>,
+    /// 私钥保存在加密凭证库中时对应的凭证主机：PEM 证书取其密码字段作为私钥文本，
+    /// PKCS#12 证书取其作为解密口令。
+    #[serde(default)]
+    pub key_credential_host: Option<String>,
+    #[serde(default)]
+    pub key_credential_username: Option<String>,
+}
+
+/// insteadOf 风格的 URL 前缀改写规则
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct UrlRewriteRule {
+    /// 被替换的 URL 前缀（对应 git 的 `insteadOf`）
+    pub prefix: String,
+    /// 替换后的前缀
+    pub replacement: String,
+    /// 仅对推送生效（对应 git 的 `pushInsteadOf`）；默认仅对读取（clone/fetch/子模块）生效
+    #[serde(default)]
+    pub push_only: bool,
+}
+
+/// 某个主机的只读镜像列表；镜像地址为 URL 前缀，仓库路径拼接其后。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct HostMirror {
+    pub host: String,
+    /// 按优先级排列，例如 `https://mirror.example.com/github.com`
+    pub mirrors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct MirrorCfg {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub rewrite_rules: Vec<UrlRewriteRule>,
+    /// 读取走镜像、推送始终走源站
+    #[serde(default)]
+    pub host_mirrors: Vec<HostMirror>,
+    /// 所有镜像都失败（或处于冷却）时是否回落源站
+    #[serde(default = "default_true")]
+    pub fallback_to_origin: bool,
+    /// 镜像连续失败达到该次数后进入冷却
+    #[serde(default = "default_mirror_failure_threshold")]
+    pub failure_threshold: u32,
+    /// 冷却秒数，期满后重新参与选择
+    #[serde(default = "default_mirror_cooldown_sec")]
+    pub cooldown_sec: u64,
+}
+
+impl Default for MirrorCfg {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rewrite_rules: Vec::new(),
+            host_mirrors: Vec::new(),
+            fallback_to_origin: default_true(),
+            failure_threshold: default_mirror_failure_threshold(),
+            cooldown_sec: default_mirror_cooldown_sec(),
+        }
+    }
+}
+
+/// 本地时间窗口（`HH:MM`，左闭右开，可跨午夜），窗口内对后台批量任务叠加限速
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BandwidthWindow {
+    pub start: String,
+    pub end: String,
+    /// 窗口内单任务上限（KiB/s）；0 表示窗口内不限速
+    pub limit_kib_per_sec: u64,
+}
+
+/// 传输带宽限制；所有上限单位均为 KiB/s，0 表示不限。
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BandwidthCfg {
+    /// 所有任务共享的全局上限
+    #[serde(default)]
+    pub global_kib_per_sec: u64,
+    /// 单任务默认上限
+    #[serde(default)]
+    pub per_task_kib_per_sec: u64,
+    /// 按任务类型覆盖单任务上限，键为 `GitClone` / `GitFetch` / `GitPush`
+    #[serde(default)]
+    pub per_kind: std::collections::HashMap<String, u64>,
+    /// 仅对工作区批量任务的子任务生效
+    #[serde(default)]
+    pub windows: Vec<BandwidthWindow>,
+}
+
+fn default_mirror_failure_threshold() -> u32 {
+    2
+}
+fn default_mirror_cooldown_sec() -> u64 {
+    300
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -90,6 +255,15 @@ pub struct AppConfig {
     /// P8.1: 可观测性与指标配置，默认启用基础埋点。
     #[serde(default)]
     pub observability: ObservabilityConfig,
+    /// Git 远程 URL 改写与镜像规则，默认关闭。
+    #[serde(default)]
+    pub mirror: MirrorCfg,
+    /// Git 传输限速，默认不限。
+    #[serde(default)]
+    pub bandwidth: BandwidthCfg,
+    /// 网络环境配置档（按网络自动切换 IP 池 / 代理 / HTTP 覆盖项），默认无配置档。
+    #[serde(default)]
+    pub network_profiles: NetworkProfilesConfig,
 }
 
 fn default_true() -> bool {
@@ -107,6 +281,12 @@ pub fn default_auto_disable_threshold_pct() -> u8 {
 pub fn default_auto_disable_cooldown_sec() -> u64 {
     300
 }
+fn default_pool_max_idle_per_host() -> usize {
+    4
+}
+fn default_pool_idle_timeout_sec() -> u64 {
+    90
+}
 fn default_log_level() -> String {
     "info".to_string()
 }
@@ -181,12 +361,20 @@ impl Default for AppConfig {
                 large_body_warn_bytes: default_large_body_warn(),
                 auto_disable_fake_threshold_pct: default_auto_disable_threshold_pct(),
                 auto_disable_fake_cooldown_sec: default_auto_disable_cooldown_sec(),
+                pool_max_idle_per_host: default_pool_max_idle_per_host(),
+                pool_idle_timeout_sec: default_pool_idle_timeout_sec(),
+                http2_enabled: default_true(),
+                protocol_v2_enabled: false,
             },
             tls: TlsCfg {
                 spki_pins: Vec::new(),
                 metrics_enabled: true,
                 cert_fp_log_enabled: true,
                 cert_fp_max_bytes: default_cert_fp_max_bytes(),
+                custom_cas: Vec::new(),
+                use_system_trust_store: false,
+                client_certs: Vec::new(),
+                pin_policies: Vec::new(),
             },
             logging: LoggingCfg {
                 auth_header_masked: default_true(),
@@ -200,6 +388,9 @@ impl Default for AppConfig {
             workspace: WorkspaceConfig::default(),
             submodule: SubmoduleConfig::default(),
             observability: ObservabilityConfig::default(),
+            mirror: MirrorCfg::default(),
+            bandwidth: BandwidthCfg::default(),
+            network_profiles: NetworkProfilesConfig::default(),
         }
     }
 }
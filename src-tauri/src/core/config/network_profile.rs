@@ -0,0 +1,557 @@
+//! 网络环境配置档：按所处网络（默认网关、本机子网、DNS 后缀、可达性探测）自动匹配，
+//! 将 IP 池、代理与 HTTP（伪 SNI 等）覆盖项应用到运行期配置。
+//!
+//! 覆盖项是 JSON merge-patch（RFC 7396）：只写配置档要改的键，其余字段沿用基线。
+//! 切换时先把当前三个分区保存为基线，离开配置档（或切换到另一个配置档）时先恢复基线再应用，
+//! 因此覆盖项不会层层叠加。配置档生效期间保存配置时，改动写回基线（见 [`rebase_saved_config`]）。
+
+use std::net::{IpAddr, Ipv4Addr, TcpStream, ToSocketAddrs, UdpSocket};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use ipnet::IpNet;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::model::{AppConfig, HttpCfg};
+use crate::core::ip_pool::family::parse_ip_literal;
+use crate::core::ip_pool::IpPoolRuntimeConfig;
+use crate::core::proxy::ProxyConfig;
+use crate::events::structured::{publish_global, Event, StrategyEvent};
+
+/// 用户手动切换
+pub const TRIGGER_MANUAL: &str = "manual";
+/// 自动检测触发的切换
+pub const TRIGGER_AUTO: &str = "auto";
+
+/// 可达性探测超时上限（毫秒），避免异常配置阻塞检测过久。
+const MAX_PROBE_TIMEOUT_MS: u64 = 10_000;
+
+/// 配置档匹配条件。每个非空类别内任一条目命中即视为该类别命中，所有非空类别都命中才算匹配；
+/// 全部为空的配置档只能手动启用。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkMatch {
+    /// 默认网关地址
+    #[serde(default)]
+    pub gateways: Vec<String>,
+    /// 本机地址所在子网（CIDR 或单个 IP）
+    #[serde(default)]
+    pub subnets: Vec<String>,
+    /// DNS 搜索后缀（后缀匹配，如 `corp.example.com` 命中 `lab.corp.example.com`）
+    #[serde(default)]
+    pub dns_suffixes: Vec<String>,
+    /// 可达性探测目标（`host:port`），TCP 建连成功视为命中；仅在其他条件命中后才探测
+    #[serde(default)]
+    pub reachable: Vec<String>,
+}
+
+impl NetworkMatch {
+    pub fn is_empty(&self) -> bool {
+        self.gateways.is_empty()
+            && self.subnets.is_empty()
+            && self.dns_suffixes.is_empty()
+            && self.reachable.is_empty()
+    }
+
+    fn passive_matches(&self, signals: &NetworkSignals) -> bool {
+        let gateway_ok = self.gateways.is_empty()
+            || self.gateways.iter().any(|entry| {
+                parse_ip_literal(entry).is_some_and(|gw| signals.gateways.contains(&gw))
+            });
+        let subnet_ok = self.subnets.is_empty()
+            || self.subnets.iter().any(|entry| {
+                signals
+                    .local_addrs
+                    .iter()
+                    .any(|ip| subnet_contains(entry, *ip))
+            });
+        let suffix_ok = self.dns_suffixes.is_empty()
+            || self.dns_suffixes.iter().any(|entry| {
+                signals
+                    .dns_suffixes
+                    .iter()
+                    .any(|suffix| suffix_matches(suffix, entry))
+            });
+        gateway_ok && subnet_ok && suffix_ok
+    }
+}
+
+/// 单个网络环境配置档。覆盖项是对应分区的 JSON merge-patch（camelCase 键），
+/// 为 `None` 的分区与补丁中未出现的键沿用基线配置。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkProfile {
+    pub name: String,
+    #[serde(default, rename = "match")]
+    pub matcher: NetworkMatch,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip_pool: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http: Option<Value>,
+}
+
+impl NetworkProfile {
+    /// 在基线分区上应用本配置档的覆盖项
+    fn apply(&self, cfg: &mut AppConfig) -> Result<()> {
+        let name = &self.name;
+        if let Some(patch) = &self.ip_pool {
+            cfg.ip_pool = patch_section(&cfg.ip_pool, patch)
+                .with_context(|| format!("network profile {name}: invalid ipPool override"))?;
+        }
+        if let Some(patch) = &self.proxy {
+            cfg.proxy = patch_section(&cfg.proxy, patch)
+                .with_context(|| format!("network profile {name}: invalid proxy override"))?;
+        }
+        if let Some(patch) = &self.http {
+            cfg.http = patch_section(&cfg.http, patch)
+                .with_context(|| format!("network profile {name}: invalid http override"))?;
+        }
+        Ok(())
+    }
+}
+
+/// 启用配置档前的原始分区，用于离开配置档时恢复。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileBaseline {
+    pub ip_pool: IpPoolRuntimeConfig,
+    pub proxy: ProxyConfig,
+    pub http: HttpCfg,
+}
+
+impl ProfileBaseline {
+    fn capture(cfg: &AppConfig) -> Self {
+        Self {
+            ip_pool: cfg.ip_pool.clone(),
+            proxy: cfg.proxy.clone(),
+            http: cfg.http.clone(),
+        }
+    }
+
+    fn restore(self, cfg: &mut AppConfig) {
+        cfg.ip_pool = self.ip_pool;
+        cfg.proxy = self.proxy;
+        cfg.http = self.http;
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkProfilesConfig {
+    /// 启动时（以及调用检测命令时）自动检测并切换配置档
+    #[serde(default)]
+    pub auto_detect: bool,
+    /// 当前生效的配置档名称，`None` 表示使用基线配置
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active: Option<String>,
+    /// 按顺序匹配，第一个命中的配置档生效
+    #[serde(default)]
+    pub profiles: Vec<NetworkProfile>,
+    /// 单个可达性探测的超时（毫秒）
+    #[serde(default = "default_probe_timeout_ms")]
+    pub probe_timeout_ms: u64,
+    /// 配置档生效期间保存的基线分区
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<ProfileBaseline>,
+}
+
+fn default_probe_timeout_ms() -> u64 {
+    1_500
+}
+
+impl Default for NetworkProfilesConfig {
+    fn default() -> Self {
+        Self {
+            auto_detect: false,
+            active: None,
+            profiles: Vec::new(),
+            probe_timeout_ms: default_probe_timeout_ms(),
+            baseline: None,
+        }
+    }
+}
+
+impl NetworkProfilesConfig {
+    pub fn find(&self, name: &str) -> Option<&NetworkProfile> {
+        self.profiles.iter().find(|p| p.name == name)
+    }
+
+    pub fn probe_timeout(&self) -> Duration {
+        Duration::from_millis(self.probe_timeout_ms.clamp(1, MAX_PROBE_TIMEOUT_MS))
+    }
+}
+
+/// 当前网络环境的被动信号
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkSignals {
+    pub gateways: Vec<IpAddr>,
+    pub local_addrs: Vec<IpAddr>,
+    pub dns_suffixes: Vec<String>,
+}
+
+impl NetworkSignals {
+    /// 采集当前系统的网关、本机出口地址与 DNS 搜索后缀；任一来源失败时对应列表为空。
+    pub fn collect() -> Self {
+        Self {
+            gateways: default_gateways(),
+            local_addrs: local_addrs(),
+            dns_suffixes: dns_suffixes(),
+        }
+    }
+}
+
+/// 按顺序返回第一个匹配的配置档。被动信号命中后才调用 `reachable` 进行可达性探测。
+pub fn detect_profile<'a, F>(
+    profiles: &'a [NetworkProfile],
+    signals: &NetworkSignals,
+    mut reachable: F,
+) -> Option<&'a NetworkProfile>
+where
+    F: FnMut(&str) -> bool,
+{
+    profiles.iter().find(|profile| {
+        let matcher = &profile.matcher;
+        !matcher.is_empty()
+            && matcher.passive_matches(signals)
+            && (matcher.reachable.is_empty()
+                || matcher.reachable.iter().any(|target| reachable(target)))
+    })
+}
+
+/// TCP 建连探测 `host:port` 是否可达
+pub fn probe_reachable(target: &str, timeout: Duration) -> bool {
+    let Ok(addrs) = target.to_socket_addrs() else {
+        return false;
+    };
+    addrs
+        .into_iter()
+        .any(|addr| TcpStream::connect_timeout(&addr, timeout).is_ok())
+}
+
+/// 一次配置档切换的结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileSwitch {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub changed: bool,
+}
+
+/// 切换到指定配置档（`None` 恢复基线）。仅修改内存中的配置，持久化与缓存清理由调用方负责。
+pub fn switch_profile(cfg: &mut AppConfig, name: Option<&str>) -> Result<ProfileSwitch> {
+    let from = cfg.network_profiles.active.clone();
+    let target = match name {
+        Some(name) => Some(
+            cfg.network_profiles
+                .find(name)
+                .cloned()
+                .ok_or_else(|| anyhow!("network profile not found: {name}"))?,
+        ),
+        None => None,
+    };
+    let to = target.as_ref().map(|p| p.name.clone());
+    if from == to {
+        return Ok(ProfileSwitch {
+            from,
+            to,
+            changed: false,
+        });
+    }
+
+    // 先在副本上应用，覆盖项无效时保持原配置不变
+    let mut next = cfg.clone();
+    if let Some(baseline) = next.network_profiles.baseline.take() {
+        baseline.restore(&mut next);
+    }
+    if let Some(profile) = &target {
+        next.network_profiles.baseline = Some(ProfileBaseline::capture(&next));
+        profile.apply(&mut next)?;
+    }
+    next.network_profiles.active = to.clone();
+    *cfg = next;
+    tracing::info!(
+        target = "config",
+        from = ?from,
+        to = ?to,
+        "network profile switched"
+    );
+    Ok(ProfileSwitch {
+        from,
+        to,
+        changed: true,
+    })
+}
+
+/// 配置档生效期间保存配置：把 `saved` 相对 `previous` 在三个分区上的改动写回基线，
+/// 再按（可能已被编辑的）配置档重新生成生效值，避免配置档的覆盖项被当作基线保存。
+/// 生效的配置档已被删除时恢复基线并退出配置档。
+pub fn rebase_saved_config(previous: &AppConfig, saved: &mut AppConfig) -> Result<()> {
+    let Some(mut baseline) = previous.network_profiles.baseline.clone() else {
+        return Ok(());
+    };
+    if previous.network_profiles.active.is_none() {
+        return Ok(());
+    }
+    baseline.ip_pool = rebase_section(&previous.ip_pool, &saved.ip_pool, &baseline.ip_pool)?;
+    baseline.proxy = rebase_section(&previous.proxy, &saved.proxy, &baseline.proxy)?;
+    baseline.http = rebase_section(&previous.http, &saved.http, &baseline.http)?;
+
+    let active = saved.network_profiles.active.clone();
+    let profile = active
+        .as_deref()
+        .and_then(|name| saved.network_profiles.find(name))
+        .cloned();
+    baseline.restore(saved);
+    saved.network_profiles.baseline = None;
+    saved.network_profiles.active = None;
+    if let Some(profile) = profile {
+        saved.network_profiles.baseline = Some(ProfileBaseline::capture(saved));
+        profile.apply(saved)?;
+        saved.network_profiles.active = Some(profile.name);
+    }
+    Ok(())
+}
+
+/// 把 `after` 相对 `before` 的差异以 merge-patch 形式应用到 `base`
+fn rebase_section<T>(before: &T, after: &T, base: &T) -> Result<T>
+where
+    T: Serialize + DeserializeOwned + Clone,
+{
+    match diff_patch(
+        &serde_json::to_value(before)?,
+        &serde_json::to_value(after)?,
+    ) {
+        Some(patch) => patch_section(base, &patch),
+        None => Ok(base.clone()),
+    }
+}
+
+/// 以 JSON merge-patch 覆盖分区：序列化基线、合并补丁后再反序列化
+fn patch_section<T: Serialize + DeserializeOwned>(base: &T, patch: &Value) -> Result<T> {
+    let mut merged = serde_json::to_value(base)?;
+    merge_patch(&mut merged, patch);
+    Ok(serde_json::from_value(merged)?)
+}
+
+/// RFC 7396 JSON merge-patch：对象逐键合并，`null` 删除键，其余值整体替换
+pub fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    if let Value::Object(target) = target {
+        for (key, value) in patch {
+            if value.is_null() {
+                target.remove(key);
+            } else {
+                merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
+
+/// 生成从 `before` 变为 `after` 的 merge-patch；无差异时返回 `None`
+fn diff_patch(before: &Value, after: &Value) -> Option<Value> {
+    if before == after {
+        return None;
+    }
+    let (Value::Object(before), Value::Object(after)) = (before, after) else {
+        return Some(after.clone());
+    };
+    let mut patch = Map::new();
+    for (key, value) in after {
+        let changed = match before.get(key) {
+            Some(old) => diff_patch(old, value),
+            None => Some(value.clone()),
+        };
+        if let Some(changed) = changed {
+            patch.insert(key.clone(), changed);
+        }
+    }
+    for key in before.keys().filter(|key| !after.contains_key(*key)) {
+        patch.insert(key.clone(), Value::Null);
+    }
+    Some(Value::Object(patch))
+}
+
+/// 采集信号、检测并切换配置档；没有配置档匹配时恢复基线。
+pub fn auto_switch(cfg: &mut AppConfig, signals: &NetworkSignals) -> Result<ProfileSwitch> {
+    let timeout = cfg.network_profiles.probe_timeout();
+    let detected = detect_profile(&cfg.network_profiles.profiles, signals, |target| {
+        probe_reachable(target, timeout)
+    })
+    .map(|p| p.name.clone());
+    switch_profile(cfg, detected.as_deref())
+}
+
+/// 清理与网络环境相关的进程级缓存：keep-alive 连接、信任库/客户端证书缓存、伪 SNI 自动禁用状态、PAC/WPAD 缓存、代理 TLS 客户端配置与上游代理池健康状态。
+/// IP 池由持有者调用 `IpPool::reset_network_state`。
+pub fn reset_network_caches() {
+    crate::core::git::http_transport::connection_pool().clear();
+    crate::core::tls::trust::reset_custom_trust_cache();
+    crate::core::tls::client_cert::reset_client_cert_cache();
+    crate::core::git::transport::runtime::reset_auto_disable_internal();
+    crate::core::proxy::pac::clear_cache();
+    crate::core::proxy::tls::clear_cache();
+    crate::core::proxy::pool::clear_shared_pool();
+}
+
+/// 发布配置档切换事件（指标由事件桥统计）
+pub fn emit_profile_switch(switch: &ProfileSwitch, trigger: &str) {
+    publish_global(Event::Strategy(StrategyEvent::NetworkProfileSwitched {
+        from: switch.from.clone(),
+        to: switch.to.clone(),
+        trigger: trigger.to_string(),
+    }));
+}
+
+fn subnet_contains(entry: &str, ip: IpAddr) -> bool {
+    match entry.trim().parse::<IpNet>() {
+        Ok(net) => net.contains(&ip),
+        Err(_) => parse_ip_literal(entry) == Some(ip),
+    }
+}
+
+fn suffix_matches(suffix: &str, pattern: &str) -> bool {
+    let suffix = suffix.trim().trim_matches('.').to_ascii_lowercase();
+    let pattern = pattern.trim().trim_matches('.').to_ascii_lowercase();
+    !pattern.is_empty() && (suffix == pattern || suffix.ends_with(&format!(".{pattern}")))
+}
+
+/// 解析 Linux `/proc/net/route` 中的默认路由网关
+pub fn parse_proc_net_route(content: &str) -> Vec<IpAddr> {
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let cols: Vec<&str> = line.split_whitespace().collect();
+            if cols.len() < 3 || cols[1] != "00000000" {
+                return None;
+            }
+            let raw = u32::from_str_radix(cols[2], 16).ok()?;
+            (raw != 0).then(|| IpAddr::V4(Ipv4Addr::from(raw.to_le_bytes())))
+        })
+        .collect()
+}
+
+/// 解析 `resolv.conf` 中的 `search` / `domain` 后缀
+pub fn parse_resolv_conf(content: &str) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for line in content.lines() {
+        let mut parts = line.split_whitespace();
+        if matches!(parts.next(), Some("search" | "domain")) {
+            for suffix in parts {
+                let suffix = suffix.trim_matches('.').to_ascii_lowercase();
+                if !suffix.is_empty() && !out.contains(&suffix) {
+                    out.push(suffix);
+                }
+            }
+        }
+    }
+    out
+}
+
+fn default_gateways() -> Vec<IpAddr> {
+    #[cfg(target_os = "linux")]
+    {
+        std::fs::read_to_string("/proc/net/route")
+            .map(|content| parse_proc_net_route(&content))
+            .unwrap_or_default()
+    }
+    #[cfg(target_os = "macos")]
+    {
+        command_output("route", &["-n", "get", "default"])
+            .map(|out| {
+                out.lines()
+                    .filter_map(|line| line.trim().strip_prefix("gateway:"))
+                    .filter_map(parse_ip_literal)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+    #[cfg(target_os = "windows")]
+    {
+        command_output("route", &["print", "0.0.0.0"])
+            .map(|out| {
+                out.lines()
+                    .filter_map(|line| {
+                        let cols: Vec<&str> = line.split_whitespace().collect();
+                        (cols.len() >= 3 && cols[0] == "0.0.0.0" && cols[1] == "0.0.0.0")
+                            .then(|| parse_ip_literal(cols[2]))
+                            .flatten()
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    {
+        Vec::new()
+    }
+}
+
+/// 通过未发送数据的 UDP `connect` 获取各地址族的出口地址
+pub fn local_addrs() -> Vec<IpAddr> {
+    ["0.0.0.0:0", "[::]:0"]
+        .iter()
+        .zip(["8.8.8.8:53", "[2001:4860:4860::8888]:53"])
+        .filter_map(|(bind, target)| {
+            let socket = UdpSocket::bind(bind).ok()?;
+            socket.connect(target).ok()?;
+            let ip = socket.local_addr().ok()?.ip();
+            (!ip.is_unspecified() && !ip.is_loopback()).then_some(ip)
+        })
+        .collect()
+}
+
+/// 当前系统的 DNS 搜索后缀（小写、去掉首尾点）
+pub fn dns_suffixes() -> Vec<String> {
+    #[cfg(unix)]
+    {
+        std::fs::read_to_string("/etc/resolv.conf")
+            .map(|content| parse_resolv_conf(&content))
+            .unwrap_or_default()
+    }
+    #[cfg(target_os = "windows")]
+    {
+        let mut out: Vec<String> = command_output("ipconfig", &["/all"])
+            .map(|text| {
+                text.lines()
+                    .filter(|line| line.contains("DNS Suffix"))
+                    .filter_map(|line| line.split_once(':').map(|(_, v)| v.trim()))
+                    .filter(|v| !v.is_empty())
+                    .map(|v| v.trim_matches('.').to_ascii_lowercase())
+                    .collect()
+            })
+            .unwrap_or_default();
+        if let Ok(domain) = std::env::var("USERDNSDOMAIN") {
+            out.push(domain.to_ascii_lowercase());
+        }
+        out.dedup();
+        out
+    }
+    #[cfg(not(any(unix, target_os = "windows")))]
+    {
+        Vec::new()
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -7,7 +7,9 @@ use serde::{Deserialize, Serialize};
 use crate::core::config::loader;
 use crate::core::config::model::{AppConfig, TlsCfg};
 use crate::core::credential::config::CredentialConfig;
-use crate::core::ip_pool::{config::load_or_init_file_at, IpPoolFileConfig, IpPoolRuntimeConfig};
+use crate::core::ip_pool::{
+    config::load_or_init_file_at, IpHistoryExport, IpPoolFileConfig, IpPoolRuntimeConfig,
+};
 use crate::core::proxy::config::ProxyConfig;
 
 const TEMPLATE_SCHEMA_MAJOR: u32 = 1;
@@ -21,6 +23,10 @@ fn default_overwrite() -> SectionStrategy {
     SectionStrategy::Overwrite
 }
 
+fn default_merge() -> SectionStrategy {
+    SectionStrategy::Merge
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TeamConfigTemplate {
@@ -65,6 +71,9 @@ pub struct TemplateSections {
     pub tls: Option<TlsCfg>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub credential: Option<CredentialConfig>,
+    /// 管理员审核过的 IP 历史快照，导入后作为待确认记录由预热重新探测
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip_history: Option<IpHistoryExport>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -88,6 +97,9 @@ pub struct TemplateExportOptions {
     pub include_tls: bool,
     #[serde(default = "default_true")]
     pub include_credential: bool,
+    /// IP 历史与机器网络环境相关，默认不导出
+    #[serde(default)]
+    pub include_ip_history: bool,
     #[serde(default)]
     pub metadata: Option<TemplateMetadata>,
 }
@@ -100,6 +112,7 @@ impl Default for TemplateExportOptions {
             include_proxy: true,
             include_tls: true,
             include_credential: true,
+            include_ip_history: false,
             metadata: None,
         }
     }
@@ -118,6 +131,8 @@ pub struct TemplateImportOptions {
     pub include_tls: bool,
     #[serde(default = "default_true")]
     pub include_credential: bool,
+    #[serde(default = "default_true")]
+    pub include_ip_history: bool,
     #[serde(default)]
     pub strategies: ImportStrategyConfig,
 }
@@ -130,6 +145,7 @@ impl Default for TemplateImportOptions {
             include_proxy: true,
             include_tls: true,
             include_credential: true,
+            include_ip_history: true,
             strategies: ImportStrategyConfig::default(),
         }
     }
@@ -148,6 +164,9 @@ pub struct ImportStrategyConfig {
     pub tls: SectionStrategy,
     #[serde(default = "default_overwrite")]
     pub credential: SectionStrategy,
+    /// `Merge` 按新鲜度与延迟合并，`Overwrite` 先清空本地历史
+    #[serde(default = "default_merge")]
+    pub ip_history: SectionStrategy,
 }
 
 impl Default for ImportStrategyConfig {
@@ -158,6 +177,7 @@ impl Default for ImportStrategyConfig {
             proxy: SectionStrategy::Overwrite,
             tls: SectionStrategy::Overwrite,
             credential: SectionStrategy::Overwrite,
+            ip_history: SectionStrategy::Merge,
         }
     }
 }
@@ -218,11 +238,14 @@ pub enum TemplateSectionKind {
     Proxy,
     Tls,
     Credential,
+    IpHistory,
 }
 
 pub struct TemplateImportOutcome {
     pub report: TemplateImportReport,
     pub updated_ip_pool_file: Option<IpPoolFileConfig>,
+    /// 待导入 IP 历史的快照与策略（`Merge` / `Overwrite`），由调用方写入历史存储
+    pub ip_history: Option<(IpHistoryExport, SectionStrategy)>,
 }
 
 pub fn export_template(
@@ -587,9 +610,31 @@ pub fn apply_template_to_config(
         });
     }
 
+    let mut ip_history = None;
+    if let Some(history_section) = template.sections.ip_history.as_ref() {
+        if !options.include_ip_history {
+            report.skipped.push(SkippedSection {
+                section: TemplateSectionKind::IpHistory,
+                reason: "sectionDisabled".to_string(),
+            });
+        } else if options.strategies.ip_history == SectionStrategy::KeepLocal {
+            report.skipped.push(SkippedSection {
+                section: TemplateSectionKind::IpHistory,
+                reason: "strategyKeepLocal".to_string(),
+            });
+        } else {
+            ip_history = Some((history_section.clone(), options.strategies.ip_history));
+            report.applied.push(AppliedSection {
+                section: TemplateSectionKind::IpHistory,
+                strategy: options.strategies.ip_history,
+            });
+        }
+    }
+
     Ok(TemplateImportOutcome {
         report,
         updated_ip_pool_file: ip_file_work,
+        ip_history,
     })
 }
 
@@ -617,6 +662,12 @@ fn parse_schema_major(schema: &str) -> Result<u32> {
 
 fn sanitized_proxy(mut proxy: ProxyConfig) -> ProxyConfig {
     proxy.password = None;
+    for named in proxy.proxies.iter_mut() {
+        named.password = None;
+    }
+    for upstream in proxy.upstreams.iter_mut() {
+        upstream.password = None;
+    }
     proxy
 }
 
@@ -694,6 +745,26 @@ fn merge_ip_pool_runtime(dest: &mut IpPoolRuntimeConfig, src: &IpPoolRuntimeConf
         dest.circuit_breaker_enabled = src.circuit_breaker_enabled;
         changed = true;
     }
+    if src.half_open_max_trials != defaults.half_open_max_trials {
+        dest.half_open_max_trials = src.half_open_max_trials;
+        changed = true;
+    }
+    if src.racing != defaults.racing {
+        dest.racing = src.racing.clone();
+        changed = true;
+    }
+    if src.ipv6 != defaults.ipv6 {
+        dest.ipv6 = src.ipv6;
+        changed = true;
+    }
+    if src.ipv6_bias_ms != defaults.ipv6_bias_ms {
+        dest.ipv6_bias_ms = src.ipv6_bias_ms;
+        changed = true;
+    }
+    if src.scoring != defaults.scoring {
+        dest.scoring = src.scoring.clone();
+        changed = true;
+    }
 
     changed
 }
@@ -719,6 +790,9 @@ fn merge_ip_pool_file(dest: &mut IpPoolFileConfig, src: &IpPoolFileConfig) -> bo
     if merge_unique(&mut dest.whitelist, &src.whitelist) {
         changed = true;
     }
+    if merge_unique(&mut dest.subscriptions, &src.subscriptions) {
+        changed = true;
+    }
 
     changed
 }
@@ -787,6 +861,84 @@ fn merge_proxy_config(dest: &mut ProxyConfig, src: &ProxyConfig) -> bool {
         dest.debug_proxy_logging = src.debug_proxy_logging;
         changed = true;
     }
+    if let Some(pac_url) = src.pac_url.as_ref() {
+        if !pac_url.trim().is_empty() && dest.pac_url.as_ref() != Some(pac_url) {
+            dest.pac_url = Some(pac_url.clone());
+            changed = true;
+        }
+    }
+    if src.wpad_enabled != defaults.wpad_enabled {
+        dest.wpad_enabled = src.wpad_enabled;
+        changed = true;
+    }
+    if !src.no_proxy.is_empty() && dest.no_proxy != src.no_proxy {
+        dest.no_proxy = src.no_proxy.clone();
+        changed = true;
+    }
+    if src.honor_env_no_proxy != defaults.honor_env_no_proxy {
+        dest.honor_env_no_proxy = src.honor_env_no_proxy;
+        changed = true;
+    }
+    if !src.rules.is_empty() && dest.rules != src.rules {
+        dest.rules = src.rules.clone();
+        changed = true;
+    }
+    if !src.proxies.is_empty() {
+        // 模板中的命名代理不含密码：同名代理保留本地已有密码
+        let merged: Vec<_> = src
+            .proxies
+            .iter()
+            .map(|p| {
+                let mut p = p.clone();
+                if p.password.is_none() {
+                    p.password = dest
+                        .proxies
+                        .iter()
+                        .find(|d| d.name == p.name)
+                        .and_then(|d| d.password.clone());
+                }
+                p
+            })
+            .collect();
+        if dest.proxies != merged {
+            dest.proxies = merged;
+            changed = true;
+        }
+    }
+    if !src.upstreams.is_empty() {
+        // 上游代理池同样不含密码：同名上游保留本地已有密码
+        let merged: Vec<_> = src
+            .upstreams
+            .iter()
+            .map(|u| {
+                let mut u = u.clone();
+                if u.password.is_none() {
+                    u.password = dest
+                        .upstreams
+                        .iter()
+                        .find(|d| d.name == u.name)
+                        .and_then(|d| d.password.clone());
+                }
+                u
+            })
+            .collect();
+        if dest.upstreams != merged {
+            dest.upstreams = merged;
+            changed = true;
+        }
+    }
+    if src.upstream_strategy != defaults.upstream_strategy {
+        dest.upstream_strategy = src.upstream_strategy;
+        changed = true;
+    }
+    if src.sticky_host_ttl_seconds != defaults.sticky_host_ttl_seconds {
+        dest.sticky_host_ttl_seconds = src.sticky_host_ttl_seconds;
+        changed = true;
+    }
+    if src.tls != defaults.tls && dest.tls != src.tls {
+        dest.tls = src.tls.clone();
+        changed = true;
+    }
 
     changed
 }
@@ -842,6 +994,9 @@ fn merge_credential_config(dest: &mut CredentialConfig, src: &CredentialConfig)
         dest.key_cache_ttl_seconds = src.key_cache_ttl_seconds;
         changed = true;
     }
+    if merge_unique(&mut dest.bindings, &src.bindings) {
+        changed = true;
+    }
 
     changed
 }
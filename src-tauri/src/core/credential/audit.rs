@@ -9,6 +9,18 @@
 //! - 永远不记录明文密码或令牌
 //! - 哈希摘要使用 SHA-256，加盐防止彩虹表攻击
 //! - 审计日志可导出为 JSON 格式用于合规审查
+//! - 每条记录以 HMAC-SHA256 串联成哈希链，编辑、插入或删除记录均可被
+//!   [`AuditLogger::verify_chain`] 检出；保留策略截断时写入带签名的链锚点，
+//!   截断后的日志依然可完整校验
+//! - 加载时若链校验失败，原文件被封存为 `<日志文件>.broken-<时间戳>`，新记录写入
+//!   一段衔接旧链尾、带签名锚点的新链段，并以一条失败的校验记录标明断点，
+//!   不会在被篡改的历史之上继续追加；断点信息通过 [`AuditChainReport::chain_break`] 暴露
+//!
+//! # 已知局限
+//!
+//! 链密钥与日志文件同目录保存（`<日志文件>.key`，权限 0600）。能同时改写密钥
+//! 文件的攻击者可以伪造整条链；仅删除日志尾部记录时链本身无法察觉，
+//! 需结合 [`AuditChainReport::last_seq`] 与外部留存的序号比对。
 //!
 //! # 示例
 //!
@@ -26,17 +38,32 @@
 //! );
 //! ```
 
+use base64::{engine::general_purpose, Engine as _};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 use std::fs::{self, File};
 use std::io::BufWriter;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// 哈希链日志文件格式版本（v1 为旧版无链的事件数组）
+const AUDIT_FILE_VERSION: u32 = 2;
+
+/// 链密钥长度（字节）
+const CHAIN_KEY_SIZE: usize = 32;
+
+/// 链首记录的前驱 MAC
+const GENESIS_MAC: &str = "0000000000000000000000000000000000000000000000000000000000000000";
 
 /// 凭证操作类型
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum OperationType {
     /// 添加凭证
@@ -55,6 +82,12 @@ pub enum OperationType {
     Expired,
     /// 解锁凭证存储
     Unlock,
+    /// 主密码轮换（重新加密存储）
+    Rekey,
+    /// 导出凭证备份
+    Export,
+    /// 从备份导入凭证
+    Import,
 }
 
 impl fmt::Display for OperationType {
@@ -68,6 +101,9 @@ impl fmt::Display for OperationType {
             OperationType::Validate => write!(f, "validate"),
             OperationType::Expired => write!(f, "expired"),
             OperationType::Unlock => write!(f, "unlock"),
+            OperationType::Rekey => write!(f, "rekey"),
+            OperationType::Export => write!(f, "export"),
+            OperationType::Import => write!(f, "import"),
         }
     }
 }
@@ -125,6 +161,385 @@ mod system_time_serde {
     }
 }
 
+/// 哈希链中的一条审计记录
+///
+/// 在 [`AuditEvent`] 之外附加链序号与 MAC，序列化时事件字段被展平，
+/// 因此导出结果仍可按 [`AuditEvent`] 反序列化。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChainedAuditEvent {
+    /// 链序号，自 0 起单调递增，保留策略清理后不重置
+    pub seq: u64,
+
+    /// 审计事件
+    #[serde(flatten)]
+    pub event: AuditEvent,
+
+    /// 前一条记录的 MAC（链首为锚点或创世值）
+    pub prev_mac: String,
+
+    /// HMAC-SHA256(链密钥, seq ‖ prevMac ‖ 事件 JSON)
+    pub mac: String,
+}
+
+/// 保留策略截断后的链锚点
+///
+/// 记录首条保留记录应衔接的序号与前驱 MAC，并由链密钥签名，
+/// 防止通过伪造锚点掩盖对日志头部的删除。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditChainAnchor {
+    /// 首条保留记录的序号
+    pub seq: u64,
+    /// 首条保留记录的前驱 MAC
+    pub prev_mac: String,
+    /// 累计被保留策略移除的记录数
+    pub removed: u64,
+    /// 最近一次截断时间（Unix 秒）
+    pub truncated_at: u64,
+    /// 锚点签名
+    pub mac: String,
+}
+
+/// 链校验发现的问题类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AuditChainViolationKind {
+    /// 记录内容与 MAC 不符（被编辑）
+    MacMismatch,
+    /// 前驱 MAC 与上一条记录不衔接（被删除、插入或重排）
+    BrokenLink,
+    /// 序号不连续
+    SequenceGap,
+    /// 锚点签名无效或与首条记录不衔接
+    InvalidAnchor,
+}
+
+/// 链校验发现的单个问题
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditChainViolation {
+    /// 出问题的记录序号
+    pub seq: u64,
+    /// 问题类型
+    pub kind: AuditChainViolationKind,
+}
+
+/// 审计日志链校验报告
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditChainReport {
+    /// 整条链是否完好
+    pub valid: bool,
+    /// 校验的记录数
+    pub checked: usize,
+    /// 首条记录序号
+    pub first_seq: Option<u64>,
+    /// 末条记录序号
+    pub last_seq: Option<u64>,
+    /// 累计被保留策略移除的记录数
+    pub removed_by_retention: u64,
+    /// 发现的问题
+    pub violations: Vec<AuditChainViolation>,
+    /// 本次加载时检测到并封存的损坏链段
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_break: Option<AuditChainBreak>,
+}
+
+/// 加载时校验失败而被封存的旧链段
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditChainBreak {
+    /// 封存的旧日志文件路径
+    pub archived_path: String,
+    /// 旧链段的记录数
+    pub records: usize,
+    /// 旧链段发现的问题
+    pub violations: Vec<AuditChainViolation>,
+    /// 检测时间（Unix 秒）
+    pub detected_at: u64,
+}
+
+/// 审计日志查询条件
+///
+/// 所有条件为“与”关系，未设置的条件不参与过滤。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AuditQuery {
+    /// 操作类型
+    pub operation: Option<OperationType>,
+    /// 主机地址（不区分大小写的精确匹配）
+    pub host: Option<String>,
+    /// 操作结果
+    pub success: Option<bool>,
+    /// 起始时间（Unix 秒，包含）
+    pub since: Option<u64>,
+    /// 截止时间（Unix 秒，包含）
+    pub until: Option<u64>,
+    /// 最多返回条数，保留最近的记录
+    pub limit: Option<usize>,
+}
+
+/// 审计日志文件（v2，哈希链格式）
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AuditLogFile {
+    version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    anchor: Option<AuditChainAnchor>,
+    records: Vec<ChainedAuditEvent>,
+}
+
+/// 记录位置索引，按操作类型、主机、结果与时间（秒）分桶
+#[derive(Debug, Default)]
+struct AuditIndex {
+    by_operation: HashMap<OperationType, Vec<usize>>,
+    by_host: HashMap<String, Vec<usize>>,
+    by_success: HashMap<bool, Vec<usize>>,
+    by_time: BTreeMap<u64, Vec<usize>>,
+}
+
+impl AuditIndex {
+    fn insert(&mut self, pos: usize, event: &AuditEvent) {
+        self.by_operation
+            .entry(event.operation)
+            .or_default()
+            .push(pos);
+        self.by_host
+            .entry(event.host.to_ascii_lowercase())
+            .or_default()
+            .push(pos);
+        self.by_success.entry(event.success).or_default().push(pos);
+        self.by_time
+            .entry(unix_secs(event.timestamp))
+            .or_default()
+            .push(pos);
+    }
+
+    fn rebuild(records: &[ChainedAuditEvent]) -> Self {
+        let mut index = Self::default();
+        for (pos, record) in records.iter().enumerate() {
+            index.insert(pos, &record.event);
+        }
+        index
+    }
+
+    /// 选出最小的候选集合（已按位置升序）；无任何可用条件时返回 `None`
+    fn candidates(&self, query: &AuditQuery) -> Option<Vec<usize>> {
+        let mut best: Option<Vec<usize>> = None;
+        let mut consider = |list: Vec<usize>| {
+            if best.as_ref().is_none_or(|b| list.len() < b.len()) {
+                best = Some(list);
+            }
+        };
+
+        if let Some(op) = query.operation {
+            consider(self.by_operation.get(&op).cloned().unwrap_or_default());
+        }
+        if let Some(host) = &query.host {
+            consider(
+                self.by_host
+                    .get(&host.to_ascii_lowercase())
+                    .cloned()
+                    .unwrap_or_default(),
+            );
+        }
+        if let Some(success) = query.success {
+            consider(self.by_success.get(&success).cloned().unwrap_or_default());
+        }
+        if query.since.is_some() || query.until.is_some() {
+            let since = query.since.unwrap_or(0);
+            let until = query.until.unwrap_or(u64::MAX);
+            if since > until {
+                return Some(Vec::new());
+            }
+            let mut list: Vec<usize> = self
+                .by_time
+                .range(since..=until)
+                .flat_map(|(_, positions)| positions.iter().copied())
+                .collect();
+            list.sort_unstable();
+            consider(list);
+        }
+
+        best
+    }
+}
+
+/// 审计日志内部状态：链记录、锚点、索引与加载时检测到的断点
+#[derive(Debug, Default)]
+struct AuditState {
+    records: Vec<ChainedAuditEvent>,
+    anchor: Option<AuditChainAnchor>,
+    index: AuditIndex,
+    chain_break: Option<AuditChainBreak>,
+    /// `clear()` 隐藏的链首记录数：仅从内存视图移除，仍保留在链与日志文件中
+    cleared: usize,
+}
+
+impl AuditState {
+    /// 内存视图中可见的记录（跳过 `clear()` 之前的记录）
+    fn visible(&self) -> &[ChainedAuditEvent] {
+        &self.records[self.cleared.min(self.records.len())..]
+    }
+
+    fn next_seq(&self) -> u64 {
+        match (self.records.last(), &self.anchor) {
+            (Some(last), _) => last.seq + 1,
+            (None, Some(anchor)) => anchor.seq,
+            (None, None) => 0,
+        }
+    }
+
+    fn head_mac(&self) -> String {
+        match (self.records.last(), &self.anchor) {
+            (Some(last), _) => last.mac.clone(),
+            (None, Some(anchor)) => anchor.prev_mac.clone(),
+            (None, None) => GENESIS_MAC.to_string(),
+        }
+    }
+
+    fn push(&mut self, key: &[u8], event: AuditEvent) -> &ChainedAuditEvent {
+        let seq = self.next_seq();
+        let prev_mac = self.head_mac();
+        let mac = record_mac(key, seq, &prev_mac, &event);
+        self.index.insert(self.records.len(), &event);
+        self.records.push(ChainedAuditEvent {
+            seq,
+            event,
+            prev_mac,
+            mac,
+        });
+        &self.records[self.records.len() - 1]
+    }
+
+    /// 移除最前面的 `count` 条记录并写入新锚点，保持剩余记录可校验
+    fn truncate_front(&mut self, key: &[u8], count: usize) {
+        if count == 0 {
+            return;
+        }
+        let seq = self
+            .records
+            .get(count)
+            .map(|r| r.seq)
+            .unwrap_or_else(|| self.next_seq());
+        let prev_mac = self
+            .records
+            .get(count)
+            .map(|r| r.prev_mac.clone())
+            .unwrap_or_else(|| self.head_mac());
+        let removed = self.anchor.as_ref().map_or(0, |a| a.removed) + count as u64;
+        let truncated_at = unix_secs(SystemTime::now());
+
+        self.records.drain(..count);
+        self.cleared = self.cleared.saturating_sub(count);
+        self.anchor = Some(AuditChainAnchor {
+            mac: anchor_mac(key, seq, &prev_mac, removed, truncated_at),
+            seq,
+            prev_mac,
+            removed,
+            truncated_at,
+        });
+        self.index = AuditIndex::rebuild(&self.records);
+    }
+
+    fn to_file(&self) -> AuditLogFile {
+        AuditLogFile {
+            version: AUDIT_FILE_VERSION,
+            anchor: self.anchor.clone(),
+            records: self.records.clone(),
+        }
+    }
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn new_mac(key: &[u8]) -> HmacSha256 {
+    <HmacSha256 as Mac>::new_from_slice(key).expect("HMAC accepts keys of any length")
+}
+
+/// 计算记录 MAC：HMAC(key, seq ‖ prev_mac ‖ 事件 JSON)
+fn record_mac(key: &[u8], seq: u64, prev_mac: &str, event: &AuditEvent) -> String {
+    let body = serde_json::to_vec(event).unwrap_or_default();
+    let mut mac = new_mac(key);
+    mac.update(b"record");
+    mac.update(&seq.to_be_bytes());
+    mac.update(prev_mac.as_bytes());
+    mac.update(&body);
+    format!("{:x}", mac.finalize().into_bytes())
+}
+
+/// 计算锚点签名
+fn anchor_mac(key: &[u8], seq: u64, prev_mac: &str, removed: u64, truncated_at: u64) -> String {
+    let mut mac = new_mac(key);
+    mac.update(b"anchor");
+    mac.update(&seq.to_be_bytes());
+    mac.update(prev_mac.as_bytes());
+    mac.update(&removed.to_be_bytes());
+    mac.update(&truncated_at.to_be_bytes());
+    format!("{:x}", mac.finalize().into_bytes())
+}
+
+/// 逐条校验哈希链
+fn verify_records(
+    key: &[u8],
+    anchor: Option<&AuditChainAnchor>,
+    records: &[ChainedAuditEvent],
+) -> AuditChainReport {
+    let mut violations = Vec::new();
+
+    let (mut expected_seq, mut expected_prev) = match anchor {
+        Some(a) => {
+            let valid = anchor_mac(key, a.seq, &a.prev_mac, a.removed, a.truncated_at) == a.mac;
+            if !valid {
+                violations.push(AuditChainViolation {
+                    seq: a.seq,
+                    kind: AuditChainViolationKind::InvalidAnchor,
+                });
+            }
+            (a.seq, a.prev_mac.clone())
+        }
+        None => (0, GENESIS_MAC.to_string()),
+    };
+
+    for record in records {
+        if record.seq != expected_seq {
+            violations.push(AuditChainViolation {
+                seq: record.seq,
+                kind: AuditChainViolationKind::SequenceGap,
+            });
+        }
+        if record.prev_mac != expected_prev {
+            violations.push(AuditChainViolation {
+                seq: record.seq,
+                kind: AuditChainViolationKind::BrokenLink,
+            });
+        }
+        if record_mac(key, record.seq, &record.prev_mac, &record.event) != record.mac {
+            violations.push(AuditChainViolation {
+                seq: record.seq,
+                kind: AuditChainViolationKind::MacMismatch,
+            });
+        }
+        expected_seq = record.seq + 1;
+        expected_prev = record.mac.clone();
+    }
+
+    AuditChainReport {
+        valid: violations.is_empty(),
+        checked: records.len(),
+        first_seq: records.first().map(|r| r.seq),
+        last_seq: records.last().map(|r| r.seq),
+        removed_by_retention: anchor.map_or(0, |a| a.removed),
+        violations,
+        chain_break: None,
+    }
+}
+
 /// 凭证审计日志记录器
 ///
 /// 线程安全的审计日志记录器，支持标准模式和审计模式。
@@ -146,13 +561,17 @@ mod system_time_serde {
 ///
 /// let events = logger.get_events();
 /// assert_eq!(events.len(), 1);
+/// assert!(logger.verify_chain().unwrap().valid);
 /// ```
 pub struct AuditLogger {
     /// 是否启用审计模式（记录哈希摘要）
     audit_mode: bool,
 
-    /// 审计事件列表（线程安全）
-    events: Arc<Mutex<Vec<AuditEvent>>>,
+    /// 审计链状态（记录、锚点、索引；线程安全）
+    state: Arc<Mutex<AuditState>>,
+
+    /// 哈希链 HMAC 密钥
+    chain_key: Arc<Vec<u8>>,
 
     /// 哈希盐值（用于防止彩虹表攻击）
     salt: String,
@@ -233,13 +652,16 @@ impl AccessControl {
 impl AuditLogger {
     /// 创建新的审计日志记录器
     ///
+    /// 链密钥随机生成且仅存在于内存中。
+    ///
     /// # 参数
     ///
     /// - `audit_mode`: 是否启用审计模式（记录哈希摘要）
     pub fn new(audit_mode: bool) -> Self {
         Self {
             audit_mode,
-            events: Arc::new(Mutex::new(Vec::new())),
+            state: Arc::new(Mutex::new(AuditState::default())),
+            chain_key: Arc::new(Self::generate_chain_key()),
             salt: Self::generate_salt(),
             log_file_path: None,
             access_control: Arc::new(Mutex::new(AccessControl::new())),
@@ -248,6 +670,9 @@ impl AuditLogger {
 
     /// 创建带持久化日志文件的审计日志记录器
     ///
+    /// 链密钥保存在 `<日志文件>.key`，不存在时自动生成。旧版（无链的事件数组）
+    /// 日志在加载时会以当前密钥封存成链并立即改写为新格式。
+    ///
     /// # 参数
     ///
     /// - `audit_mode`: 是否启用审计模式
@@ -264,6 +689,7 @@ impl AuditLogger {
         }
 
         let mut logger = Self::new(audit_mode);
+        logger.chain_key = Arc::new(Self::load_or_create_chain_key(&path)?);
         logger.log_file_path = Some(path.clone());
 
         // 尝试从文件加载现有日志
@@ -283,7 +709,6 @@ impl AuditLogger {
 
     /// 生成随机盐值
     fn generate_salt() -> String {
-        use std::time::UNIX_EPOCH;
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .unwrap_or_default()
@@ -291,6 +716,53 @@ impl AuditLogger {
         format!("audit_salt_{now}")
     }
 
+    /// 生成随机链密钥
+    fn generate_chain_key() -> Vec<u8> {
+        let mut key = vec![0u8; CHAIN_KEY_SIZE];
+        rand::thread_rng().fill_bytes(&mut key);
+        key
+    }
+
+    /// 链密钥文件路径：`<日志文件>.key`
+    fn chain_key_path(log_path: &Path) -> PathBuf {
+        let mut name = log_path.as_os_str().to_os_string();
+        name.push(".key");
+        PathBuf::from(name)
+    }
+
+    /// 读取链密钥，不存在时生成并以 0600 权限写入
+    fn load_or_create_chain_key(log_path: &Path) -> Result<Vec<u8>, String> {
+        let key_path = Self::chain_key_path(log_path);
+        if key_path.exists() {
+            let content =
+                fs::read_to_string(&key_path).map_err(|e| format!("读取链密钥失败: {e}"))?;
+            let key = general_purpose::STANDARD
+                .decode(content.trim())
+                .map_err(|e| format!("解析链密钥失败: {e}"))?;
+            if key.len() != CHAIN_KEY_SIZE {
+                return Err(format!("链密钥长度无效: {}", key.len()));
+            }
+            return Ok(key);
+        }
+
+        if log_path.exists() {
+            tracing::warn!(
+                "审计日志 {:?} 缺少链密钥，已生成新密钥，既有记录将无法通过校验",
+                log_path
+            );
+        }
+        let key = Self::generate_chain_key();
+        fs::write(&key_path, general_purpose::STANDARD.encode(&key))
+            .map_err(|e| format!("写入链密钥失败: {e}"))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&key_path, fs::Permissions::from_mode(0o600))
+                .map_err(|e| format!("设置链密钥权限失败: {e}"))?;
+        }
+        Ok(key)
+    }
+
     /// 计算凭证的 SHA-256 哈希摘要
     ///
     /// 使用格式: SHA256(salt + host + username + password)
@@ -339,15 +811,14 @@ impl AuditLogger {
             credential_hash,
         };
 
-        // 记录到内存
-        if let Ok(mut events) = self.events.lock() {
-            events.push(event.clone());
-        }
+        // 追加到链并持久化；持锁写文件以保证文件中的链顺序与内存一致
+        if let Ok(mut state) = self.state.lock() {
+            state.push(&self.chain_key, event);
 
-        // 持久化到文件（如果配置了）
-        if let Some(path) = &self.log_file_path {
-            if let Err(e) = self.append_to_file(&event, path) {
-                tracing::warn!("写入审计日志文件失败: {}", e);
+            if let Some(path) = &self.log_file_path {
+                if let Err(e) = Self::save_to_file(&state, path) {
+                    tracing::warn!("写入审计日志文件失败: {}", e);
+                }
             }
         }
     }
@@ -356,40 +827,107 @@ impl AuditLogger {
     fn load_from_file(&self, path: &Path) -> Result<(), String> {
         let content = fs::read_to_string(path).map_err(|e| format!("读取文件失败: {e}"))?;
 
-        let loaded_events: Vec<AuditEvent> =
-            serde_json::from_str(&content).map_err(|e| format!("解析JSON失败: {e}"))?;
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|e| format!("获取审计日志锁失败: {e}"))?;
 
-        if let Ok(mut events) = self.events.lock() {
-            *events = loaded_events;
+        if let Ok(file) = serde_json::from_str::<AuditLogFile>(&content) {
+            let report = verify_records(&self.chain_key, file.anchor.as_ref(), &file.records);
+            state.index = AuditIndex::rebuild(&file.records);
+            state.records = file.records;
+            state.cleared = 0;
+            state.anchor = file.anchor;
+            if !report.valid {
+                self.start_new_segment(&mut state, path, report)?;
+            }
+            return Ok(());
         }
 
+        // 旧版格式：无链事件数组，封存为新链后改写文件
+        let legacy: Vec<AuditEvent> =
+            serde_json::from_str(&content).map_err(|e| format!("解析JSON失败: {e}"))?;
+        *state = AuditState::default();
+        for event in legacy {
+            state.push(&self.chain_key, event);
+        }
+        Self::save_to_file(&state, path)?;
+        tracing::info!("已将旧版审计日志 {:?} 迁移为哈希链格式", path);
+
         Ok(())
     }
 
-    /// 追加事件到文件
-    fn append_to_file(&self, event: &AuditEvent, path: &Path) -> Result<(), String> {
-        // 读取现有事件
-        let mut all_events = if path.exists() {
-            let content = fs::read_to_string(path).map_err(|e| format!("读取文件失败: {e}"))?;
-            serde_json::from_str::<Vec<AuditEvent>>(&content).unwrap_or_default()
-        } else {
-            Vec::new()
-        };
-
-        // 添加新事件
-        all_events.push(event.clone());
+    /// 链校验失败时封存原文件，并开启衔接旧链尾的新链段
+    ///
+    /// 新链段以签名锚点起始，首条记录为一条失败的 `Validate` 事件，标明断点与封存位置；
+    /// 此后的记录不再依附于被篡改的历史。
+    fn start_new_segment(
+        &self,
+        state: &mut AuditState,
+        path: &Path,
+        report: AuditChainReport,
+    ) -> Result<(), String> {
+        let detected_at = unix_secs(SystemTime::now());
+        let mut archived = path.as_os_str().to_os_string();
+        archived.push(format!(".broken-{detected_at}"));
+        let archived = PathBuf::from(archived);
+        fs::copy(path, &archived).map_err(|e| format!("封存损坏的审计日志失败: {e}"))?;
+        tracing::error!(
+            "审计日志 {:?} 链校验失败，发现 {} 处异常；原文件已封存至 {:?}，后续记录写入新链段",
+            path,
+            report.violations.len(),
+            archived
+        );
 
-        // 写回文件
-        let file = File::create(path).map_err(|e| format!("创建文件失败: {e}"))?;
-        let writer = BufWriter::new(file);
-        serde_json::to_writer_pretty(writer, &all_events)
-            .map_err(|e| format!("写入JSON失败: {e}"))?;
+        // 新锚点衔接旧链尾并重新签名（旧锚点本身也可能已失效）
+        let records = state.records.len();
+        let seq = state.next_seq();
+        let prev_mac = state.head_mac();
+        let removed = state.anchor.as_ref().map_or(0, |a| a.removed) + records as u64;
+        state.records.clear();
+        state.cleared = 0;
+        state.index = AuditIndex::default();
+        state.anchor = Some(AuditChainAnchor {
+            mac: anchor_mac(&self.chain_key, seq, &prev_mac, removed, detected_at),
+            seq,
+            prev_mac,
+            removed,
+            truncated_at: detected_at,
+        });
+        state.chain_break = Some(AuditChainBreak {
+            archived_path: archived.to_string_lossy().to_string(),
+            records,
+            violations: report.violations,
+            detected_at,
+        });
+        state.push(
+            &self.chain_key,
+            AuditEvent {
+                operation: OperationType::Validate,
+                host: "audit_log".to_string(),
+                username: String::new(),
+                timestamp: SystemTime::now(),
+                success: false,
+                error: Some(format!(
+                    "Audit chain verification failed; previous segment archived to {}",
+                    archived.display()
+                )),
+                credential_hash: None,
+            },
+        );
+        Self::save_to_file(state, path)
+    }
 
-        Ok(())
+    /// 加载时检测到的损坏链段（未发生时为 `None`）
+    pub fn chain_break(&self) -> Option<AuditChainBreak> {
+        self.state.lock().ok()?.chain_break.clone()
     }
 
     /// 清理过期的审计日志
     ///
+    /// 从链首开始移除早于保留期的记录，并写入签名锚点，
+    /// 使剩余记录仍能通过 [`verify_chain`](Self::verify_chain)。
+    ///
     /// # 参数
     ///
     /// - `retention_days`: 保留天数，早于此时间的日志将被删除
@@ -400,19 +938,25 @@ impl AuditLogger {
     pub fn cleanup_expired_logs(&self, retention_days: u64) -> Result<usize, String> {
         let cutoff_time = SystemTime::now() - Duration::from_secs(retention_days * 24 * 60 * 60);
 
-        let removed_count = if let Ok(mut events) = self.events.lock() {
-            let original_len = events.len();
-            events.retain(|event| event.timestamp >= cutoff_time);
-            let new_len = events.len();
+        let removed_count = if let Ok(mut state) = self.state.lock() {
+            // 仅截断连续的链首，避免在链中间留下空洞
+            let removed = state
+                .records
+                .iter()
+                .take_while(|record| record.event.timestamp < cutoff_time)
+                .count();
+            state.truncate_front(&self.chain_key, removed);
 
             // 如果有日志文件，更新它
-            if let Some(path) = &self.log_file_path {
-                if let Err(e) = self.save_to_file(&events, path) {
-                    tracing::warn!("保存清理后的日志文件失败: {}", e);
+            if removed > 0 {
+                if let Some(path) = &self.log_file_path {
+                    if let Err(e) = Self::save_to_file(&state, path) {
+                        tracing::warn!("保存清理后的日志文件失败: {}", e);
+                    }
                 }
             }
 
-            original_len - new_len
+            removed
         } else {
             0
         };
@@ -420,14 +964,85 @@ impl AuditLogger {
         Ok(removed_count)
     }
 
-    /// 保存事件到文件（覆盖写）
-    fn save_to_file(&self, events: &[AuditEvent], path: &Path) -> Result<(), String> {
+    /// 保存链状态到文件（覆盖写）
+    fn save_to_file(state: &AuditState, path: &Path) -> Result<(), String> {
         let file = File::create(path).map_err(|e| format!("创建文件失败: {e}"))?;
         let writer = BufWriter::new(file);
-        serde_json::to_writer_pretty(writer, events).map_err(|e| format!("写入JSON失败: {e}"))?;
+        serde_json::to_writer_pretty(writer, &state.to_file())
+            .map_err(|e| format!("写入JSON失败: {e}"))?;
         Ok(())
     }
 
+    /// 校验审计日志哈希链
+    ///
+    /// 配置了日志文件时校验磁盘上的内容（文件才是被篡改的对象），
+    /// 否则校验内存中的链。
+    pub fn verify_chain(&self) -> Result<AuditChainReport, String> {
+        if let Some(path) = &self.log_file_path {
+            if path.exists() {
+                // 持锁读取，避免与并发写入交错
+                let state = self
+                    .state
+                    .lock()
+                    .map_err(|e| format!("获取审计日志锁失败: {e}"))?;
+                let content = fs::read_to_string(path).map_err(|e| format!("读取文件失败: {e}"))?;
+                let file: AuditLogFile = serde_json::from_str(&content)
+                    .map_err(|e| format!("审计日志不是有效的哈希链格式: {e}"))?;
+                let mut report =
+                    verify_records(&self.chain_key, file.anchor.as_ref(), &file.records);
+                report.chain_break = state.chain_break.clone();
+                return Ok(report);
+            }
+        }
+
+        let state = self
+            .state
+            .lock()
+            .map_err(|e| format!("获取审计日志锁失败: {e}"))?;
+        let mut report = verify_records(&self.chain_key, state.anchor.as_ref(), &state.records);
+        report.chain_break = state.chain_break.clone();
+        Ok(report)
+    }
+
+    /// 按条件查询审计记录
+    ///
+    /// 利用内存索引缩小候选集合，结果按链序号升序返回。
+    pub fn query(&self, query: &AuditQuery) -> Vec<ChainedAuditEvent> {
+        let Ok(state) = self.state.lock() else {
+            return Vec::new();
+        };
+
+        let host = query.host.as_ref().map(|h| h.to_ascii_lowercase());
+        let matches = |record: &ChainedAuditEvent| {
+            let event = &record.event;
+            let secs = unix_secs(event.timestamp);
+            query.operation.is_none_or(|op| event.operation == op)
+                && host
+                    .as_ref()
+                    .is_none_or(|h| event.host.eq_ignore_ascii_case(h))
+                && query.success.is_none_or(|s| event.success == s)
+                && query.since.is_none_or(|t| secs >= t)
+                && query.until.is_none_or(|t| secs <= t)
+        };
+
+        let mut results: Vec<ChainedAuditEvent> = match state.index.candidates(query) {
+            Some(positions) => positions
+                .into_iter()
+                .filter(|&pos| pos >= state.cleared)
+                .filter_map(|pos| state.records.get(pos))
+                .filter(|record| matches(record))
+                .cloned()
+                .collect(),
+            None => state.visible().to_vec(),
+        };
+
+        if let Some(limit) = query.limit {
+            let skip = results.len().saturating_sub(limit);
+            results.drain(..skip);
+        }
+        results
+    }
+
     /// 检查是否被锁定
     pub fn is_locked(&self) -> bool {
         self.access_control
@@ -460,28 +1075,40 @@ impl AuditLogger {
 
     /// 获取所有审计事件（克隆）
     pub fn get_events(&self) -> Vec<AuditEvent> {
-        self.events
+        self.state
             .lock()
-            .map(|events| events.clone())
+            .map(|state| state.visible().iter().map(|r| r.event.clone()).collect())
             .unwrap_or_default()
     }
 
     /// 清除所有审计事件
+    ///
+    /// 仅清空内存视图：记录仍保留在哈希链与日志文件中，后续记录照常接续；
+    /// 持久化记录只按保留期由 [`cleanup_expired_logs`](Self::cleanup_expired_logs) 截断。
     pub fn clear(&self) {
-        if let Ok(mut events) = self.events.lock() {
-            events.clear();
+        if let Ok(mut state) = self.state.lock() {
+            state.cleared = state.records.len();
         }
     }
 
     /// 导出审计日志为 JSON 格式
+    ///
+    /// 导出内容为带链序号与 MAC 的记录数组。
     pub fn export_to_json(&self) -> Result<String, String> {
-        let events = self.get_events();
-        serde_json::to_string_pretty(&events).map_err(|e| format!("序列化失败: {e}"))
+        let records = self
+            .state
+            .lock()
+            .map(|state| state.visible().to_vec())
+            .unwrap_or_default();
+        serde_json::to_string_pretty(&records).map_err(|e| format!("序列化失败: {e}"))
     }
 
     /// 获取事件数量
     pub fn event_count(&self) -> usize {
-        self.events.lock().map(|events| events.len()).unwrap_or(0)
+        self.state
+            .lock()
+            .map(|state| state.visible().len())
+            .unwrap_or(0)
     }
 
     /// 检查是否启用审计模式
@@ -500,7 +1127,8 @@ impl Clone for AuditLogger {
     fn clone(&self) -> Self {
         Self {
             audit_mode: self.audit_mode,
-            events: Arc::clone(&self.events),
+            state: Arc::clone(&self.state),
+            chain_key: Arc::clone(&self.chain_key),
             salt: self.salt.clone(),
             log_file_path: self.log_file_path.clone(),
             access_control: Arc::clone(&self.access_control),
@@ -0,0 +1,500 @@
+//! 凭证加密备份导出与导入
+//!
+//! 将任意 [`CredentialStore`] 中的全部凭证导出为使用口令加密的可移植备份包，
+//! 并支持按冲突策略导入到任意后端（例如从加密文件存储导出、导入到系统钥匙串）。
+//!
+//! # 加密方案
+//!
+//! 与 `file_store` 保持一致：
+//!
+//! - **密钥派生**: Argon2id，参数记录在备份包头中（`kdf` 字段）
+//! - **加密**: AES-256-GCM，每次导出随机生成盐值与 96 位 Nonce
+//! - **完整性**: GCM 认证标签；包头（格式、版本、盐值、KDF 参数）作为附加认证数据（AAD），
+//!   任何对包头的篡改都会导致解密失败
+//!
+//! # 备份格式
+//!
+//! ```json
+//! {
+//!   "format": "fireworks-credential-backup",
+//!   "version": 1,
+//!   "createdAt": 1700000000,
+//!   "kdf": { "mCost": 65536, "tCost": 3, "pCost": 1 },
+//!   "salt": "base64-encoded-salt",
+//!   "nonce": "base64-encoded-nonce",
+//!   "ciphertext": "base64-encoded-encrypted-data"
+//! }
+//! ```
+//!
+//! 明文部分（仅存在于密文中）包含主机、用户名、口令/令牌以及创建、过期、最后使用时间。
+//!
+//! # 使用示例
+//!
+//! ```rust,no_run
+//! use fireworks_collaboration_lib::core::credential::{
+//!     backup::{CredentialBackup, ImportConflictStrategy},
+//!     file_store::Argon2Params,
+//!     storage::MemoryCredentialStore,
+//! };
+//!
+//! let source = MemoryCredentialStore::new();
+//! let backup = CredentialBackup::export(&source, "backup-passphrase", Argon2Params::default())?;
+//! let json = backup.to_json()?;
+//!
+//! let target = MemoryCredentialStore::new();
+//! let report = CredentialBackup::from_json(&json)?.import_into(
+//!     &target,
+//!     "backup-passphrase",
+//!     ImportConflictStrategy::KeepNewest,
+//! )?;
+//! println!("imported {}", report.imported);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use super::{
+    file_store::{Argon2Params, EncryptedFileStore},
+    model::Credential,
+    storage::{CredentialStore, CredentialStoreError},
+};
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Nonce,
+};
+use argon2::password_hash::SaltString;
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use zeroize::Zeroizing;
+
+/// Backup bundle format identifier.
+pub const BACKUP_FORMAT: &str = "fireworks-credential-backup";
+
+/// Backup bundle format version.
+const BACKUP_VERSION: u32 = 1;
+
+/// Nonce size for AES-256-GCM (96 bits).
+const NONCE_SIZE: usize = 12;
+
+/// 导入时与已有凭证冲突的处理策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportConflictStrategy {
+    /// 保留已有凭证，跳过备份中的条目
+    #[default]
+    Skip,
+    /// 使用备份中的条目覆盖已有凭证
+    Overwrite,
+    /// 按 `created_at` 保留较新的一方
+    KeepNewest,
+}
+
+/// 单条凭证的导入结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportAction {
+    /// 目标存储中不存在，已新增
+    Imported,
+    /// 已覆盖目标存储中的已有凭证
+    Overwritten,
+    /// 因冲突策略跳过
+    Skipped,
+    /// 写入目标存储失败
+    Failed,
+}
+
+/// 单条凭证的导入明细
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportEntryResult {
+    pub host: String,
+    pub username: String,
+    pub action: ImportAction,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 导入汇总报告
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub imported: usize,
+    pub overwritten: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub entries: Vec<ImportEntryResult>,
+}
+
+impl ImportReport {
+    fn record(&mut self, host: &str, username: &str, action: ImportAction, error: Option<String>) {
+        match action {
+            ImportAction::Imported => self.imported += 1,
+            ImportAction::Overwritten => self.overwritten += 1,
+            ImportAction::Skipped => self.skipped += 1,
+            ImportAction::Failed => self.failed += 1,
+        }
+        self.entries.push(ImportEntryResult {
+            host: host.to_string(),
+            username: username.to_string(),
+            action,
+            error,
+        });
+    }
+}
+
+/// 备份明文中的单条凭证（时间统一为 Unix 秒，便于跨平台）
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BackupEntry {
+    host: String,
+    username: String,
+    password_or_token: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires_at: Option<u64>,
+    created_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_used_at: Option<u64>,
+}
+
+fn to_unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn from_unix_secs(secs: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+impl From<&Credential> for BackupEntry {
+    fn from(cred: &Credential) -> Self {
+        Self {
+            host: cred.host.clone(),
+            username: cred.username.clone(),
+            password_or_token: cred.password_or_token.clone(),
+            expires_at: cred.expires_at.map(to_unix_secs),
+            created_at: to_unix_secs(cred.created_at),
+            last_used_at: cred.last_used_at.map(to_unix_secs),
+        }
+    }
+}
+
+impl From<BackupEntry> for Credential {
+    fn from(entry: BackupEntry) -> Self {
+        let mut cred = Credential::new(entry.host, entry.username, entry.password_or_token);
+        cred.expires_at = entry.expires_at.map(from_unix_secs);
+        cred.created_at = from_unix_secs(entry.created_at);
+        cred.last_used_at = entry.last_used_at.map(from_unix_secs);
+        cred
+    }
+}
+
+/// 备份明文容器
+#[derive(Serialize, Deserialize)]
+struct BackupPayload {
+    credentials: Vec<BackupEntry>,
+}
+
+/// 口令加密的可移植凭证备份包
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialBackup {
+    /// 格式标识，固定为 [`BACKUP_FORMAT`]
+    pub format: String,
+    /// 格式版本
+    pub version: u32,
+    /// 导出时间（Unix 秒）
+    pub created_at: u64,
+    /// 派生密钥使用的 Argon2 参数
+    pub kdf: Argon2Params,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+impl CredentialBackup {
+    /// 导出存储中的全部凭证（包括已过期凭证）为加密备份包
+    ///
+    /// # 参数
+    ///
+    /// * `store` - 任意凭证存储后端
+    /// * `passphrase` - 备份口令（与主密码无关，导入时需提供相同口令）
+    /// * `params` - Argon2 参数
+    pub fn export(
+        store: &dyn CredentialStore,
+        passphrase: &str,
+        params: Argon2Params,
+    ) -> Result<Self, String> {
+        let credentials = store
+            .list_all()
+            .map_err(|e| format!("Failed to list credentials: {e}"))?;
+        Self::export_credentials(&credentials, passphrase, params)
+    }
+
+    /// 将已列出的凭证导出为加密备份包（调用方已持有列表时避免重复枚举存储）
+    pub fn export_credentials(
+        credentials: &[Credential],
+        passphrase: &str,
+        params: Argon2Params,
+    ) -> Result<Self, String> {
+        if passphrase.is_empty() {
+            return Err("Backup passphrase must not be empty".to_string());
+        }
+        params.validate()?;
+
+        let payload = BackupPayload {
+            credentials: credentials.iter().map(BackupEntry::from).collect(),
+        };
+        let plaintext = Zeroizing::new(
+            serde_json::to_vec(&payload)
+                .map_err(|e| format!("Failed to serialize credentials: {e}"))?,
+        );
+        drop(payload);
+
+        let salt = SaltString::generate(&mut OsRng);
+        let key = EncryptedFileStore::derive_key_from(passphrase, &salt, params)?;
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+        let mut backup = Self {
+            format: BACKUP_FORMAT.to_string(),
+            version: BACKUP_VERSION,
+            created_at: to_unix_secs(SystemTime::now()),
+            kdf: params,
+            salt: general_purpose::STANDARD.encode(salt.as_str()),
+            nonce: general_purpose::STANDARD.encode(nonce.as_slice()),
+            ciphertext: String::new(),
+        };
+
+        let cipher = Aes256Gcm::new_from_slice(key.as_slice())
+            .map_err(|e| format!("Failed to create cipher: {e}"))?;
+        let aad = backup.associated_data();
+        let ciphertext = cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: &plaintext,
+                    aad: &aad,
+                },
+            )
+            .map_err(|e| format!("Encryption failed: {e}"))?;
+        backup.ciphertext = general_purpose::STANDARD.encode(ciphertext);
+
+        tracing::info!(
+            target = "credential",
+            count = credentials.len(),
+            "Credential backup exported"
+        );
+
+        Ok(backup)
+    }
+
+    /// 序列化为 JSON 字符串
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|e| format!("Failed to serialize backup: {e}"))
+    }
+
+    /// 从 JSON 字符串解析备份包（仅校验格式，不解密）
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let backup: Self =
+            serde_json::from_str(json).map_err(|e| format!("Failed to parse backup: {e}"))?;
+        if backup.format != BACKUP_FORMAT {
+            return Err(format!("Unsupported backup format: {}", backup.format));
+        }
+        if backup.version != BACKUP_VERSION {
+            return Err(format!(
+                "Unsupported backup version: {}, expected {}",
+                backup.version, BACKUP_VERSION
+            ));
+        }
+        Ok(backup)
+    }
+
+    /// 包头附加认证数据
+    fn associated_data(&self) -> Vec<u8> {
+        format!(
+            "{}|{}|{}|{}|{}|{}|{}",
+            self.format,
+            self.version,
+            self.created_at,
+            self.kdf.m_cost,
+            self.kdf.t_cost,
+            self.kdf.p_cost,
+            self.salt
+        )
+        .into_bytes()
+    }
+
+    /// 解密备份包中的全部凭证
+    pub fn decrypt(&self, passphrase: &str) -> Result<Vec<Credential>, String> {
+        // 导入的参数同样不可信：先按上限拒绝，再做口令校验
+        self.kdf.check_limits().map_err(|e| format!("Backup {e}"))?;
+
+        let salt_bytes = general_purpose::STANDARD
+            .decode(&self.salt)
+            .map_err(|e| format!("Failed to decode salt: {e}"))?;
+        let salt_str =
+            String::from_utf8(salt_bytes).map_err(|e| format!("Invalid salt encoding: {e}"))?;
+        let salt = SaltString::from_b64(&salt_str).map_err(|e| format!("Invalid salt: {e}"))?;
+
+        let nonce_bytes = general_purpose::STANDARD
+            .decode(&self.nonce)
+            .map_err(|e| format!("Failed to decode nonce: {e}"))?;
+        if nonce_bytes.len() != NONCE_SIZE {
+            return Err(format!(
+                "Invalid nonce size: expected {}, got {}",
+                NONCE_SIZE,
+                nonce_bytes.len()
+            ));
+        }
+        let ciphertext = general_purpose::STANDARD
+            .decode(&self.ciphertext)
+            .map_err(|e| format!("Failed to decode ciphertext: {e}"))?;
+
+        let key = EncryptedFileStore::derive_key_from(passphrase, &salt, self.kdf)?;
+        let cipher = Aes256Gcm::new_from_slice(key.as_slice())
+            .map_err(|e| format!("Failed to create cipher: {e}"))?;
+        let aad = self.associated_data();
+        let plaintext = Zeroizing::new(
+            cipher
+                .decrypt(
+                    Nonce::from_slice(&nonce_bytes),
+                    Payload {
+                        msg: &ciphertext,
+                        aad: &aad,
+                    },
+                )
+                .map_err(|_| {
+                    "Failed to decrypt backup - wrong passphrase or corrupted bundle".to_string()
+                })?,
+        );
+
+        let payload: BackupPayload = serde_json::from_slice(&plaintext)
+            .map_err(|e| format!("Failed to deserialize backup: {e}"))?;
+
+        Ok(payload
+            .credentials
+            .into_iter()
+            .map(Credential::from)
+            .collect())
+    }
+
+    /// 解密并导入到目标存储
+    ///
+    /// 解密失败（口令错误或备份被篡改）时返回错误，不会修改目标存储；
+    /// 单条写入失败记录在报告中，不中断其余条目。
+    pub fn import_into(
+        &self,
+        store: &dyn CredentialStore,
+        passphrase: &str,
+        strategy: ImportConflictStrategy,
+    ) -> Result<ImportReport, String> {
+        let credentials = self.decrypt(passphrase)?;
+
+        // 部分后端（如 macOS 钥匙串）无法枚举，缺失时再按条目查询；
+        // 读取失败直接中止，避免按空集合判定冲突而覆盖已有凭证
+        let existing: HashMap<(String, String), Credential> = store
+            .list_all()
+            .map_err(|e| format!("Failed to list existing credentials: {e}"))?
+            .into_iter()
+            .map(|c| ((c.host.clone(), c.username.clone()), c))
+            .collect();
+
+        let mut report = ImportReport::default();
+        for cred in credentials {
+            let key = (cred.host.clone(), cred.username.clone());
+            let current = match existing.get(&key) {
+                Some(c) => Some(c.clone()),
+                None => match store.get(&cred.host, Some(&cred.username)) {
+                    Ok(found) => found,
+                    Err(e) => {
+                        // 无法确认是否冲突时不写入该条目
+                        report.record(
+                            &cred.host,
+                            &cred.username,
+                            ImportAction::Failed,
+                            Some(e.to_string()),
+                        );
+                        continue;
+                    }
+                },
+            };
+
+            let overwrite = match (&current, strategy) {
+                (None, _) => false,
+                (Some(_), ImportConflictStrategy::Skip) => {
+                    report.record(&cred.host, &cred.username, ImportAction::Skipped, None);
+                    continue;
+                }
+                (Some(_), ImportConflictStrategy::Overwrite) => true,
+                (Some(cur), ImportConflictStrategy::KeepNewest) => {
+                    if cred.created_at <= cur.created_at {
+                        report.record(&cred.host, &cred.username, ImportAction::Skipped, None);
+                        continue;
+                    }
+                    true
+                }
+            };
+
+            let result = if overwrite {
+                match store.remove(&cred.host, &cred.username) {
+                    Ok(()) | Err(CredentialStoreError::NotFound(_)) => {
+                        store.add(cred.clone()).inspect_err(|_| {
+                            // 写入失败时恢复原条目，覆盖失败不应丢失已有凭证
+                            if let Some(previous) = &current {
+                                if let Err(e) = store.add(previous.clone()) {
+                                    tracing::error!(
+                                        target = "credential",
+                                        host = %previous.host,
+                                        username = %previous.username,
+                                        error = %e,
+                                        "Failed to restore credential after overwrite failure"
+                                    );
+                                }
+                            }
+                        })
+                    }
+                    Err(e) => Err(e),
+                }
+            } else {
+                store.add(cred.clone())
+            };
+
+            match result {
+                Ok(()) => {
+                    let action = if overwrite {
+                        ImportAction::Overwritten
+                    } else {
+                        ImportAction::Imported
+                    };
+                    report.record(&cred.host, &cred.username, action, None);
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        target = "credential",
+                        host = %cred.host,
+                        username = %cred.username,
+                        error = %e,
+                        "Failed to import credential from backup"
+                    );
+                    report.record(
+                        &cred.host,
+                        &cred.username,
+                        ImportAction::Failed,
+                        Some(e.to_string()),
+                    );
+                }
+            }
+        }
+
+        tracing::info!(
+            target = "credential",
+            imported = report.imported,
+            overwritten = report.overwritten,
+            skipped = report.skipped,
+            failed = report.failed,
+            "Credential backup imported"
+        );
+
+        Ok(report)
+    }
+}
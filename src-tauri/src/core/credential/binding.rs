@@ -0,0 +1,249 @@
+//! 凭证绑定
+//!
+//! 同一主机存在多个账号时，仅按主机查找凭证会取到错误的令牌。
+//! 本模块允许仓库条目或远程 URL 模式绑定到具体的凭证身份（主机 + 用户名），
+//! 绑定规则本身不含任何密钥，可随团队配置模板分发。
+//!
+//! # 匹配规则
+//!
+//! URL 与模式均先归一化为 `host/path` 形式（去掉协议、用户信息、端口、`.git` 后缀，
+//! SSH 的 `git@host:path` 同样处理），再按 `*` 通配符不区分大小写匹配。
+//! 例如模式 `github.com/acme/*` 同时匹配 `https://github.com/acme/app.git`
+//! 与 `git@github.com:acme/app.git`。
+
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use super::{model::Credential, storage::CredentialStore};
+
+/// 凭证身份
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialBinding {
+    /// 凭证所在主机；为空时使用远程 URL 的主机
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    /// 凭证用户名
+    pub username: String,
+}
+
+impl CredentialBinding {
+    /// 创建绑定到远程 URL 主机上指定用户名的身份
+    pub fn new(username: impl Into<String>) -> Self {
+        Self {
+            host: None,
+            username: username.into(),
+        }
+    }
+
+    /// 指定凭证所在主机
+    pub fn with_host(mut self, host: impl Into<String>) -> Self {
+        self.host = Some(host.into());
+        self
+    }
+
+    /// 解析实际查找用的主机
+    pub fn effective_host<'a>(&'a self, remote_host: &'a str) -> &'a str {
+        self.host.as_deref().unwrap_or(remote_host)
+    }
+}
+
+/// 按远程 URL 模式绑定凭证的规则
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialBindingRule {
+    /// 远程 URL 模式，支持 `*` 通配符
+    pub pattern: String,
+    /// 匹配后使用的凭证身份
+    #[serde(flatten)]
+    pub binding: CredentialBinding,
+}
+
+impl CredentialBindingRule {
+    /// 判断规则是否匹配远程 URL
+    pub fn matches(&self, remote_url: &str) -> bool {
+        wildcard_match(
+            &normalize_remote(&self.pattern),
+            &normalize_remote(remote_url),
+        )
+    }
+}
+
+/// 解析仓库应使用的凭证绑定
+///
+/// 仓库条目上的显式绑定优先，其次按顺序取第一条匹配的 URL 规则。
+pub fn resolve_binding(
+    remote_url: &str,
+    explicit: Option<&CredentialBinding>,
+    rules: &[CredentialBindingRule],
+) -> Option<CredentialBinding> {
+    explicit.cloned().or_else(|| {
+        rules
+            .iter()
+            .find(|rule| rule.matches(remote_url))
+            .map(|rule| rule.binding.clone())
+    })
+}
+
+/// 按绑定从存储中取出凭证
+///
+/// 有绑定时只按 (主机, 用户名) 精确查找，找不到即报错，
+/// 不会回退到按主机查找，以免误用其他账号的令牌。
+pub fn lookup_bound_credential(
+    store: &dyn CredentialStore,
+    remote_host: &str,
+    binding: &CredentialBinding,
+) -> Result<Credential, String> {
+    let host = binding.effective_host(remote_host);
+    store
+        .get(host, Some(&binding.username))
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("绑定的凭证不存在: {}@{}", binding.username, host))
+}
+
+/// 为远程主机查找存储的凭证
+///
+/// 有绑定时走 [`lookup_bound_credential`]；无绑定时沿用按主机查找的旧行为，
+/// GitHub 优先尝试个人访问令牌惯用的 `x-access-token` 用户名。
+pub fn find_credential_for_remote(
+    store: &dyn CredentialStore,
+    remote_host: &str,
+    binding: Option<&CredentialBinding>,
+) -> Result<Option<Credential>, String> {
+    if let Some(binding) = binding {
+        return lookup_bound_credential(store, remote_host, binding).map(Some);
+    }
+
+    let usernames_to_try = if remote_host.contains("github.com") {
+        vec![Some("x-access-token"), None]
+    } else {
+        vec![None]
+    };
+
+    for username in usernames_to_try {
+        match store.get(remote_host, username) {
+            Ok(Some(cred)) => return Ok(Some(cred)),
+            Ok(None) => continue,
+            Err(e) => {
+                tracing::warn!(target = "git", "Error looking up credential: {}", e);
+                continue;
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// 解析推送使用的用户名与密码
+///
+/// 显式提供用户名或密码时原样使用，不再查询存储；否则有绑定时按绑定精确查找，
+/// 无绑定时仅在 `use_stored` 为真时按主机查找。`store` 只在需要查找时调用，
+/// 返回 `None` 表示凭证存储不可用：有绑定时报错，无绑定时视为未找到。
+pub fn resolve_push_credentials<F>(
+    username: Option<String>,
+    password: Option<String>,
+    remote_url: &str,
+    binding: Option<&CredentialBinding>,
+    use_stored: bool,
+    store: F,
+) -> Result<(Option<String>, Option<String>), String>
+where
+    F: FnOnce() -> Result<Option<Arc<dyn CredentialStore>>, String>,
+{
+    if username.is_some() || password.is_some() || (binding.is_none() && !use_stored) {
+        return Ok((username, password));
+    }
+
+    let normalized = normalize_remote(remote_url);
+    let host = normalized.split('/').next().unwrap_or_default();
+    if host.is_empty() {
+        return Err(format!("无法从远程 URL 解析主机: {remote_url}"));
+    }
+    let Some(store) = store()? else {
+        return match binding {
+            Some(b) => Err(format!(
+                "存在凭证绑定 {}@{} 但凭证存储不可用",
+                b.username,
+                b.effective_host(host)
+            )),
+            None => Ok((None, None)),
+        };
+    };
+
+    let found = find_credential_for_remote(store.as_ref(), host, binding)?;
+    Ok(match found {
+        Some(cred) => (Some(cred.username), Some(cred.password_or_token)),
+        None => (None, None),
+    })
+}
+
+/// 将远程 URL 归一化为小写的 `host/path` 形式
+pub fn normalize_remote(url: &str) -> String {
+    let trimmed = url.trim();
+    let (rest, scp_like) = match trimmed.find("://") {
+        Some(pos) => (&trimmed[pos + 3..], false),
+        None => (trimmed, true),
+    };
+
+    // 去掉用户信息
+    let rest = match rest.find('@') {
+        Some(at) if rest[..at].find('/').is_none() => &rest[at + 1..],
+        _ => rest,
+    };
+
+    let (authority, path) = if scp_like {
+        // git@host:path 形式；若无冒号则视为 host/path
+        match (rest.find(':'), rest.find('/')) {
+            (Some(colon), slash) if slash.is_none_or(|s| colon < s) => {
+                (&rest[..colon], &rest[colon + 1..])
+            }
+            _ => split_authority(rest),
+        }
+    } else {
+        split_authority(rest)
+    };
+
+    let host = authority.split(':').next().unwrap_or(authority);
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+
+    if path.is_empty() {
+        host.to_ascii_lowercase()
+    } else {
+        format!("{}/{}", host, path).to_ascii_lowercase()
+    }
+}
+
+fn split_authority(rest: &str) -> (&str, &str) {
+    match rest.find('/') {
+        Some(slash) => (&rest[..slash], &rest[slash + 1..]),
+        None => (rest, ""),
+    }
+}
+
+/// `*` 通配符匹配（`*` 可匹配任意长度字符，包括 `/`）
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let p = pattern.as_bytes();
+    let t = text.as_bytes();
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while ti < t.len() {
+        if pi < p.len() && p[pi] == b'*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if pi < p.len() && p[pi] == t[ti] {
+            pi += 1;
+            ti += 1;
+        } else if let Some((sp, st)) = star {
+            pi = sp + 1;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+
+    p[pi..].iter().all(|&c| c == b'*')
+}
@@ -4,6 +4,8 @@
 
 use serde::{Deserialize, Serialize};
 
+use super::binding::CredentialBindingRule;
+
 /// 凭证存储类型
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -49,6 +51,10 @@ pub struct CredentialConfig {
     /// 密钥缓存时间（秒），用于加密文件模式
     #[serde(default = "default_key_cache_ttl")]
     pub key_cache_ttl_seconds: u64,
+
+    /// 按远程 URL 模式绑定凭证身份的规则（不含密钥，可随团队模板分发）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bindings: Vec<CredentialBindingRule>,
 }
 
 fn default_ttl_seconds() -> Option<u64> {
@@ -71,6 +77,7 @@ impl Default for CredentialConfig {
             require_confirmation: false,
             file_path: None,
             key_cache_ttl_seconds: default_key_cache_ttl(),
+            bindings: Vec::new(),
         }
     }
 }
@@ -111,6 +118,12 @@ impl CredentialConfig {
         self
     }
 
+    /// 追加凭证绑定规则
+    pub fn with_binding(mut self, rule: CredentialBindingRule) -> Self {
+        self.bindings.push(rule);
+        self
+    }
+
     /// 验证配置的有效性
     pub fn validate(&self) -> Result<(), String> {
         // 如果使用文件存储，必须指定文件路径
@@ -130,6 +143,12 @@ impl CredentialConfig {
             return Err("key_cache_ttl_seconds 必须大于 0".to_string());
         }
 
+        for rule in &self.bindings {
+            if rule.pattern.trim().is_empty() || rule.binding.username.trim().is_empty() {
+                return Err("凭证绑定规则的 pattern 与 username 不能为空".to_string());
+            }
+        }
+
         Ok(())
     }
 
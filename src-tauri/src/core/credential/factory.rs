@@ -148,6 +148,57 @@ impl CredentialStoreFactory {
         }
     }
 
+    /// Creates a store that persists credentials, never falling back to memory.
+    ///
+    /// For headless callers, where a silent memory fallback would drop every
+    /// credential at exit. `master_password` is only called when the encrypted
+    /// file is used; the file is read once so a wrong password fails here.
+    ///
+    /// Fallback logic:
+    /// - System → File
+    /// - File → error
+    /// - Memory → error
+    pub fn create_persistent<F>(
+        config: &CredentialConfig,
+        master_password: F,
+    ) -> Result<Arc<dyn CredentialStore>, String>
+    where
+        F: FnOnce() -> Result<String, String>,
+    {
+        match config.storage {
+            StorageType::System => Self::try_system_keychain(config).or_else(|e| {
+                tracing::warn!(
+                    "System keychain unavailable: {}, falling back to encrypted file",
+                    e
+                );
+                Self::open_encrypted_file(config, master_password)
+            }),
+            StorageType::File => Self::open_encrypted_file(config, master_password),
+            StorageType::Memory => Err(
+                "Memory storage does not persist credentials; configure system or file storage"
+                    .to_string(),
+            ),
+        }
+    }
+
+    /// Opens the encrypted file store and unlocks it with the master password.
+    fn open_encrypted_file<F>(
+        config: &CredentialConfig,
+        master_password: F,
+    ) -> Result<Arc<dyn CredentialStore>, String>
+    where
+        F: FnOnce() -> Result<String, String>,
+    {
+        use super::file_store::EncryptedFileStore;
+        let store = EncryptedFileStore::new(config)
+            .map_err(|e| format!("Encrypted file storage unavailable: {e}"))?;
+        store.set_master_password(master_password()?)?;
+        store
+            .list()
+            .map_err(|e| format!("Failed to unlock encrypted credential file: {e}"))?;
+        Ok(Arc::new(store) as Arc<dyn CredentialStore>)
+    }
+
     /// Attempts to create a system keychain store.
     #[cfg(target_os = "windows")]
     fn try_system_keychain(_config: &CredentialConfig) -> Result<Arc<dyn CredentialStore>, String> {
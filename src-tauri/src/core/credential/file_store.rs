@@ -85,10 +85,22 @@
 //!   "salt": "base64-encoded-salt",
 //!   "nonce": "base64-encoded-nonce",
 //!   "ciphertext": "base64-encoded-encrypted-data",
-//!   "hmac": "base64-encoded-hmac"
+//!   "hmac": "base64-encoded-hmac",
+//!   "kdf": { "mCost": 65536, "tCost": 3, "pCost": 1 }
 //! }
 //! ```
 //!
+//! `kdf` 字段记录派生密钥时使用的 Argon2 参数；旧文件缺失该字段时按默认参数解析。
+//!
+//! # 主密码轮换
+//!
+//! [`EncryptedFileStore::change_master_password`] 提供安全的重新加密路径：
+//!
+//! 1. 使用旧密码（及文件记录的 Argon2 参数）解密全部凭证
+//! 2. 生成新盐值，按新密码与（可选的）新 Argon2 参数派生新密钥并重新加密
+//! 3. 写入同目录临时文件并回读校验，随后原子替换原文件
+//! 4. 任一步骤失败时从备份恢复原文件，旧密码保持可用
+//!
 //! # 安全注意事项
 //!
 //! 1. **主密码强度**: 建议使用 12+ 字符的强密码
@@ -122,7 +134,7 @@ use sha2::Sha256;
 use std::{
     collections::HashMap,
     fs,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
     time::{Duration, SystemTime},
 };
@@ -141,6 +153,76 @@ const ARGON2_P_COST: u32 = 1; // 1 parallelism
 /// Nonce size for AES-256-GCM (96 bits).
 const NONCE_SIZE: usize = 12;
 
+/// 从文件头或备份包读取的 Argon2 参数上限（1 GiB 内存、16 次迭代、16 并行度），
+/// 防止伪造的参数在校验口令前耗尽内存或 CPU
+pub(crate) const MAX_KDF_M_COST: u32 = 1024 * 1024;
+pub(crate) const MAX_KDF_T_COST: u32 = 16;
+pub(crate) const MAX_KDF_P_COST: u32 = 16;
+
+/// Error prefix returned by `change_master_password` when the old password is wrong (or the file was tampered with).
+pub const WRONG_MASTER_PASSWORD_ERROR: &str = "Failed to decrypt with current master password";
+
+/// Argon2id key derivation parameters.
+///
+/// 持久化在加密文件头中，允许在主密码轮换时迁移到更强的参数而不丢失凭证。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Argon2Params {
+    /// Memory cost in KiB
+    pub m_cost: u32,
+    /// Number of iterations
+    pub t_cost: u32,
+    /// Degree of parallelism
+    pub p_cost: u32,
+}
+
+impl Default for Argon2Params {
+    fn default() -> Self {
+        Self {
+            m_cost: ARGON2_M_COST,
+            t_cost: ARGON2_T_COST,
+            p_cost: ARGON2_P_COST,
+        }
+    }
+}
+
+impl Argon2Params {
+    /// Validates the parameters against Argon2 limits.
+    pub fn validate(&self) -> Result<(), String> {
+        self.to_argon2().map(|_| ())
+    }
+
+    /// Rejects parameters read from untrusted input that exceed the accepted limits.
+    pub(crate) fn check_limits(&self) -> Result<(), String> {
+        if self.m_cost > MAX_KDF_M_COST
+            || self.t_cost > MAX_KDF_T_COST
+            || self.p_cost > MAX_KDF_P_COST
+        {
+            return Err(format!(
+                "KDF parameters exceed limits (mCost <= {MAX_KDF_M_COST}, tCost <= {MAX_KDF_T_COST}, pCost <= {MAX_KDF_P_COST})"
+            ));
+        }
+        self.validate()
+    }
+
+    fn to_argon2(self) -> Result<argon2::Params, String> {
+        argon2::Params::new(self.m_cost, self.t_cost, self.p_cost, Some(32))
+            .map_err(|e| format!("Invalid Argon2 params: {e}"))
+    }
+}
+
+/// Summary of a completed master password rotation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RekeyReport {
+    /// Number of credentials re-encrypted
+    pub credentials_rekeyed: usize,
+    /// Argon2 parameters before rotation
+    pub previous_params: Argon2Params,
+    /// Argon2 parameters now in effect
+    pub new_params: Argon2Params,
+}
+
 /// Master password wrapper with zeroization.
 #[derive(Clone, ZeroizeOnDrop)]
 struct MasterPassword(String);
@@ -157,14 +239,14 @@ impl MasterPassword {
 
 /// Encryption key with automatic zeroization.
 #[derive(ZeroizeOnDrop)]
-struct EncryptionKey([u8; 32]);
+pub(crate) struct EncryptionKey([u8; 32]);
 
 impl EncryptionKey {
     fn new(bytes: [u8; 32]) -> Self {
         Self(bytes)
     }
 
-    fn as_slice(&self) -> &[u8] {
+    pub(crate) fn as_slice(&self) -> &[u8] {
         &self.0
     }
 }
@@ -173,6 +255,7 @@ impl EncryptionKey {
 struct CachedKey {
     key: EncryptionKey,
     salt: String,
+    params: Argon2Params,
     expires_at: SystemTime,
 }
 
@@ -184,6 +267,15 @@ struct EncryptedCredentialFile {
     nonce: String,      // Base64-encoded nonce for AES-GCM
     ciphertext: String, // Base64-encoded encrypted credentials JSON
     hmac: String,       // Base64-encoded HMAC of ciphertext
+    /// Argon2 parameters used for this file (absent in legacy files)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kdf: Option<Argon2Params>,
+}
+
+impl EncryptedCredentialFile {
+    fn kdf_params(&self) -> Argon2Params {
+        self.kdf.unwrap_or_default()
+    }
 }
 
 /// Internal credential structure for serialization (includes password).
@@ -242,12 +334,14 @@ struct CredentialsContainer {
 /// - `key_cache`: 缓存派生的密钥，避免重复计算
 /// - `key_cache_ttl`: 密钥缓存的生存时间
 /// - `master_password`: 主密码（使用 Zeroize 保护）
+/// - `argon2_params`: 保存时使用的 Argon2 参数（加载文件后与文件头保持一致）
 /// - `file_lock`: 保护文件并发访问的互斥锁
 pub struct EncryptedFileStore {
     file_path: PathBuf,
     key_cache: Arc<Mutex<Option<CachedKey>>>,
     key_cache_ttl: Duration,
     master_password: Arc<Mutex<Option<MasterPassword>>>,
+    argon2_params: Arc<Mutex<Argon2Params>>,
     /// Mutex to protect concurrent file access
     file_lock: Arc<Mutex<()>>,
 }
@@ -340,10 +434,26 @@ impl EncryptedFileStore {
             key_cache: Arc::new(Mutex::new(None)),
             key_cache_ttl,
             master_password: Arc::new(Mutex::new(None)),
+            argon2_params: Arc::new(Mutex::new(Argon2Params::default())),
             file_lock: Arc::new(Mutex::new(())),
         })
     }
 
+    /// Sets the Argon2 parameters used when creating a new credential file.
+    ///
+    /// 已存在的文件始终按其文件头记录的参数解析；如需迁移已有文件的参数，
+    /// 请使用 [`EncryptedFileStore::change_master_password`]。
+    pub fn with_argon2_params(self, params: Argon2Params) -> Result<Self, String> {
+        params.validate()?;
+        *self.argon2_params.lock().unwrap() = params;
+        Ok(self)
+    }
+
+    /// Returns the Argon2 parameters currently used for saving.
+    pub fn argon2_params(&self) -> Argon2Params {
+        *self.argon2_params.lock().unwrap()
+    }
+
     /// Sets the master password (required before any operations).
     ///
     /// # 参数
@@ -384,8 +494,8 @@ impl EncryptedFileStore {
     ///
     /// # 注意
     ///
-    /// 更换密码后，旧密码加密的文件将无法读取。如需迁移数据，应先用旧密码读取所有凭证，
-    /// 再用新密码重新写入。
+    /// 本方法只替换内存中的密码，不会重新加密文件；更换密码后旧密码加密的文件将无法读取。
+    /// 如需迁移数据，请使用 [`EncryptedFileStore::change_master_password`]。
     pub fn set_master_password(&self, password: String) -> Result<(), String> {
         let mut mp = self.master_password.lock().unwrap();
         *mp = Some(MasterPassword::new(password));
@@ -397,22 +507,20 @@ impl EncryptedFileStore {
         Ok(())
     }
 
-    /// Derives encryption key from master password using Argon2id.
-    fn derive_key(&self, salt: &SaltString) -> Result<EncryptionKey, String> {
-        let mp_guard = self.master_password.lock().unwrap();
-        let master_password = mp_guard
-            .as_ref()
-            .ok_or_else(|| "Master password not set".to_string())?;
-
+    /// Derives encryption key from a password using Argon2id.
+    pub(crate) fn derive_key_from(
+        password: &str,
+        salt: &SaltString,
+        params: Argon2Params,
+    ) -> Result<EncryptionKey, String> {
         let argon2 = Argon2::new(
             argon2::Algorithm::Argon2id,
             argon2::Version::V0x13,
-            argon2::Params::new(ARGON2_M_COST, ARGON2_T_COST, ARGON2_P_COST, Some(32))
-                .map_err(|e| format!("Invalid Argon2 params: {e}"))?,
+            params.to_argon2()?,
         );
 
         let password_hash = argon2
-            .hash_password(master_password.as_str().as_bytes(), salt)
+            .hash_password(password.as_bytes(), salt)
             .map_err(|e| format!("Failed to derive key: {e}"))?;
 
         let hash_bytes = password_hash.hash.ok_or("No hash produced")?;
@@ -428,13 +536,30 @@ impl EncryptedFileStore {
         Ok(EncryptionKey::new(key_bytes))
     }
 
+    /// Derives encryption key from master password using Argon2id.
+    fn derive_key(&self, salt: &SaltString, params: Argon2Params) -> Result<EncryptionKey, String> {
+        let mp_guard = self.master_password.lock().unwrap();
+        let master_password = mp_guard
+            .as_ref()
+            .ok_or_else(|| "Master password not set".to_string())?;
+
+        Self::derive_key_from(master_password.as_str(), salt, params)
+    }
+
     /// Gets or derives the encryption key (with caching).
-    fn get_or_derive_key(&self, salt: &SaltString) -> Result<EncryptionKey, String> {
+    fn get_or_derive_key(
+        &self,
+        salt: &SaltString,
+        params: Argon2Params,
+    ) -> Result<EncryptionKey, String> {
         let mut cache = self.key_cache.lock().unwrap();
 
-        // Check if cached key is still valid and matches salt
+        // Check if cached key is still valid and matches salt and params
         if let Some(cached) = cache.as_ref() {
-            if SystemTime::now() < cached.expires_at && cached.salt == salt.as_str() {
+            if SystemTime::now() < cached.expires_at
+                && cached.salt == salt.as_str()
+                && cached.params == params
+            {
                 // Clone the key bytes (creates new EncryptionKey)
                 let key_bytes = cached.key.as_slice();
                 let mut new_key_bytes = [0u8; 32];
@@ -444,7 +569,7 @@ impl EncryptedFileStore {
         }
 
         // Derive new key
-        let key = self.derive_key(salt)?;
+        let key = self.derive_key(salt, params)?;
 
         // Cache it
         let key_bytes = key.as_slice();
@@ -454,6 +579,7 @@ impl EncryptedFileStore {
         *cache = Some(CachedKey {
             key: EncryptionKey::new(cached_key_bytes),
             salt: salt.as_str().to_string(),
+            params,
             expires_at: SystemTime::now() + self.key_cache_ttl,
         });
 
@@ -465,9 +591,19 @@ impl EncryptedFileStore {
         &self,
         container: &CredentialsContainer,
         salt: &SaltString,
+        params: Argon2Params,
     ) -> Result<EncryptedCredentialFile, String> {
-        let key = self.get_or_derive_key(salt)?;
+        let key = self.get_or_derive_key(salt, params)?;
+        Self::encrypt_with_key(container, salt, params, &key)
+    }
 
+    /// Encrypts credentials container with an explicit key.
+    fn encrypt_with_key(
+        container: &CredentialsContainer,
+        salt: &SaltString,
+        params: Argon2Params,
+        key: &EncryptionKey,
+    ) -> Result<EncryptedCredentialFile, String> {
         // Serialize credentials to JSON
         let plaintext = serde_json::to_string(container)
             .map_err(|e| format!("Failed to serialize credentials: {e}"))?;
@@ -497,21 +633,32 @@ impl EncryptedFileStore {
             nonce: general_purpose::STANDARD.encode(nonce.as_slice()),
             ciphertext: general_purpose::STANDARD.encode(&ciphertext),
             hmac: general_purpose::STANDARD.encode(hmac_bytes),
+            kdf: Some(params),
         })
     }
 
-    /// Decrypts credentials container.
-    fn decrypt(&self, file: &EncryptedCredentialFile) -> Result<CredentialsContainer, String> {
-        // Decode salt
+    /// Decodes the Argon2 salt stored in the file header.
+    fn decode_salt(file: &EncryptedCredentialFile) -> Result<SaltString, String> {
         let salt_bytes = general_purpose::STANDARD
             .decode(&file.salt)
             .map_err(|e| format!("Failed to decode salt: {e}"))?;
         let salt_str =
             String::from_utf8(salt_bytes).map_err(|e| format!("Invalid salt encoding: {e}"))?;
-        let salt = SaltString::from_b64(&salt_str).map_err(|e| format!("Invalid salt: {e}"))?;
+        SaltString::from_b64(&salt_str).map_err(|e| format!("Invalid salt: {e}"))
+    }
 
-        let key = self.get_or_derive_key(&salt)?;
+    /// Decrypts credentials container.
+    fn decrypt(&self, file: &EncryptedCredentialFile) -> Result<CredentialsContainer, String> {
+        let salt = Self::decode_salt(file)?;
+        let key = self.get_or_derive_key(&salt, file.kdf_params())?;
+        Self::decrypt_with_key(file, &key)
+    }
 
+    /// Decrypts credentials container with an explicit key.
+    fn decrypt_with_key(
+        file: &EncryptedCredentialFile,
+        key: &EncryptionKey,
+    ) -> Result<CredentialsContainer, String> {
         // Decode ciphertext and HMAC
         let ciphertext = general_purpose::STANDARD
             .decode(&file.ciphertext)
@@ -557,12 +704,10 @@ impl EncryptedFileStore {
         Ok(container)
     }
 
-    /// Loads credentials from file.
-    fn load_credentials(&self) -> Result<CredentialsContainer, String> {
+    /// Reads and parses the encrypted file header (without decrypting).
+    fn read_encrypted_file(&self) -> Result<Option<EncryptedCredentialFile>, String> {
         if !self.file_path.exists() {
-            return Ok(CredentialsContainer {
-                credentials: HashMap::new(),
-            });
+            return Ok(None);
         }
 
         let file_content = fs::read_to_string(&self.file_path)
@@ -577,16 +722,37 @@ impl EncryptedFileStore {
                 encrypted_file.version, FILE_VERSION
             ));
         }
+        encrypted_file
+            .kdf_params()
+            .check_limits()
+            .map_err(|e| format!("Invalid credential file header: {e}"))?;
+
+        Ok(Some(encrypted_file))
+    }
+
+    /// Loads credentials from file.
+    fn load_credentials(&self) -> Result<CredentialsContainer, String> {
+        let Some(encrypted_file) = self.read_encrypted_file()? else {
+            return Ok(CredentialsContainer {
+                credentials: HashMap::new(),
+            });
+        };
+
+        let container = self.decrypt(&encrypted_file)?;
 
-        self.decrypt(&encrypted_file)
+        // Keep subsequent saves on the parameters recorded in the file
+        *self.argon2_params.lock().unwrap() = encrypted_file.kdf_params();
+
+        Ok(container)
     }
 
     /// Saves credentials to file.
     fn save_credentials(&self, container: &CredentialsContainer) -> Result<(), String> {
         // Generate new salt for each save
         let salt = SaltString::generate(&mut OsRng);
+        let params = self.argon2_params();
 
-        let encrypted_file = self.encrypt(container, &salt)?;
+        let encrypted_file = self.encrypt(container, &salt, params)?;
 
         let file_content = serde_json::to_string_pretty(&encrypted_file)
             .map_err(|e| format!("Failed to serialize encrypted file: {e}"))?;
@@ -595,21 +761,179 @@ impl EncryptedFileStore {
         fs::write(&self.file_path, file_content)
             .map_err(|e| format!("Failed to write credential file: {e}"))?;
 
-        // Set file permissions (Unix-like systems)
+        Self::restrict_permissions(&self.file_path)
+    }
+
+    /// Sets file permissions to owner read/write only (Unix-like systems).
+    fn restrict_permissions(path: &Path) -> Result<(), String> {
         #[cfg(unix)]
         {
             use std::os::unix::fs::PermissionsExt;
-            let mut perms = fs::metadata(&self.file_path)
+            let mut perms = fs::metadata(path)
                 .map_err(|e| format!("Failed to get file metadata: {}", e))?
                 .permissions();
             perms.set_mode(0o600); // rw-------
-            fs::set_permissions(&self.file_path, perms)
+            fs::set_permissions(path, perms)
                 .map_err(|e| format!("Failed to set file permissions: {}", e))?;
         }
+        #[cfg(not(unix))]
+        let _ = path;
 
         Ok(())
     }
 
+    /// Writes the encrypted file via a temporary sibling and rename.
+    ///
+    /// 先写入同目录临时文件再原子替换目标文件，避免写入中断导致文件半写损坏。
+    /// 目标文件为只读时直接拒绝，与普通保存路径的语义保持一致。
+    fn write_atomic(path: &Path, encrypted_file: &EncryptedCredentialFile) -> Result<(), String> {
+        if let Ok(meta) = fs::metadata(path) {
+            if meta.permissions().readonly() {
+                return Err("Failed to write credential file: file is read-only".to_string());
+            }
+        }
+
+        let file_content = serde_json::to_string_pretty(encrypted_file)
+            .map_err(|e| format!("Failed to serialize encrypted file: {e}"))?;
+
+        let tmp_path = Self::sibling_path(path, "tmp");
+        fs::write(&tmp_path, file_content)
+            .map_err(|e| format!("Failed to write credential file: {e}"))?;
+
+        let result = Self::restrict_permissions(&tmp_path).and_then(|_| {
+            fs::rename(&tmp_path, path)
+                .map_err(|e| format!("Failed to replace credential file: {e}"))
+        });
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp_path);
+        }
+        result
+    }
+
+    /// Builds a sibling path such as `credentials.enc.tmp`.
+    fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(format!(".{suffix}"));
+        path.with_file_name(name)
+    }
+
+    /// Changes the master password and re-encrypts every stored credential.
+    ///
+    /// # 参数
+    ///
+    /// * `old_password` - 当前主密码，用于解密现有文件
+    /// * `new_password` - 新主密码
+    /// * `new_params` - 可选的新 Argon2 参数；为 `None` 时沿用文件当前参数
+    ///
+    /// # 流程
+    ///
+    /// 1. 按文件头记录的参数，用旧密码派生密钥并解密全部凭证（包括已过期凭证）
+    /// 2. 生成新盐值，用新密码/新参数派生密钥重新加密
+    /// 3. 备份原文件后原子替换，并用新密钥回读校验
+    /// 4. 校验失败时从备份回滚，旧密码保持有效
+    ///
+    /// 成功后内存中的主密码与 Argon2 参数更新为新值，密钥缓存被清空。
+    /// 审计记录由调用方（命令层）写入。
+    pub fn change_master_password(
+        &self,
+        old_password: &str,
+        new_password: &str,
+        new_params: Option<Argon2Params>,
+    ) -> Result<RekeyReport, String> {
+        if new_password.is_empty() {
+            return Err("New master password must not be empty".to_string());
+        }
+        if let Some(params) = new_params {
+            params.validate()?;
+        }
+
+        let _lock = self.file_lock.lock().unwrap();
+
+        let existing = self.read_encrypted_file()?;
+        let previous_params = existing
+            .as_ref()
+            .map(EncryptedCredentialFile::kdf_params)
+            .unwrap_or_else(|| self.argon2_params());
+        let target_params = new_params.unwrap_or(previous_params);
+
+        // Decrypt with the old password (fails on wrong password / tampering)
+        let container = match &existing {
+            Some(file) => {
+                let salt = Self::decode_salt(file)?;
+                let old_key = Self::derive_key_from(old_password, &salt, previous_params)?;
+                Self::decrypt_with_key(file, &old_key)
+                    .map_err(|e| format!("{WRONG_MASTER_PASSWORD_ERROR}: {e}"))?
+            }
+            None => CredentialsContainer {
+                credentials: HashMap::new(),
+            },
+        };
+
+        // Re-encrypt with a fresh salt and the new key
+        let new_salt = SaltString::generate(&mut OsRng);
+        let new_key = Self::derive_key_from(new_password, &new_salt, target_params)?;
+        let reencrypted = Self::encrypt_with_key(&container, &new_salt, target_params, &new_key)?;
+
+        // Back up the original so a failed write or verification can be rolled back
+        let backup_path = Self::sibling_path(&self.file_path, "bak");
+        if existing.is_some() {
+            fs::copy(&self.file_path, &backup_path)
+                .map_err(|e| format!("Failed to back up credential file: {e}"))?;
+        }
+
+        let write_result = Self::write_atomic(&self.file_path, &reencrypted).and_then(|_| {
+            let written = self
+                .read_encrypted_file()?
+                .ok_or_else(|| "Credential file missing after rewrite".to_string())?;
+            let verified = Self::decrypt_with_key(&written, &new_key)?;
+            if verified.credentials.len() != container.credentials.len() {
+                return Err("Re-encrypted credential count mismatch".to_string());
+            }
+            Ok(())
+        });
+
+        if let Err(e) = write_result {
+            let rollback = if existing.is_some() {
+                fs::rename(&backup_path, &self.file_path)
+            } else {
+                fs::remove_file(&self.file_path)
+            };
+            if let Err(rollback_err) = rollback {
+                tracing::error!(
+                    target = "credential",
+                    error = %rollback_err,
+                    "Failed to roll back credential file after rekey failure"
+                );
+            }
+            return Err(format!(
+                "Master password change failed and was rolled back: {e}"
+            ));
+        }
+
+        if existing.is_some() {
+            if let Err(e) = fs::remove_file(&backup_path) {
+                tracing::warn!("Failed to remove credential backup file: {e}");
+            }
+        }
+
+        *self.master_password.lock().unwrap() = Some(MasterPassword::new(new_password.to_string()));
+        *self.argon2_params.lock().unwrap() = target_params;
+        *self.key_cache.lock().unwrap() = None;
+
+        tracing::info!(
+            target = "credential",
+            count = container.credentials.len(),
+            params_migrated = target_params != previous_params,
+            "Master password rotated and credential file re-encrypted"
+        );
+
+        Ok(RekeyReport {
+            credentials_rekeyed: container.credentials.len(),
+            previous_params,
+            new_params: target_params,
+        })
+    }
+
     /// Makes a credential key from host and username.
     fn make_key(host: &str, username: &str) -> String {
         format!("{host}:{username}")
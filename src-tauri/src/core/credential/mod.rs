@@ -4,6 +4,8 @@
 //! 用于安全存储和管理 Git 操作所需的凭证信息。
 
 pub mod audit;
+pub mod backup;
+pub mod binding;
 pub mod config;
 pub mod factory;
 pub mod model;
@@ -20,6 +22,7 @@ pub mod keychain_unix;
 pub mod file_store;
 
 pub use audit::{AuditEvent, AuditLogger, OperationType};
+pub use binding::{CredentialBinding, CredentialBindingRule};
 pub use config::CredentialConfig;
 pub use factory::CredentialStoreFactory;
 pub use model::Credential;
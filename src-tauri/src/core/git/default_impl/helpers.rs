@@ -5,13 +5,145 @@ use std::sync::{
 use url::Url;
 
 use crate::core::config::loader::load_or_init;
-use crate::core::tls::util::{decide_sni_host_with_proxy, proxy_present};
+use crate::core::proxy::pac::{self, PacDirective};
+use crate::core::proxy::pool;
+use crate::core::proxy::routing::{
+    host_port_of, proxy_in_use, proxy_in_use_for_url, strip_userinfo, ProxyRouter, RouteDecision,
+};
+use crate::core::proxy::{ProxyConfig, ProxyMode};
+use crate::core::tls::util::decide_sni_host_with_proxy;
 
 use super::super::{
     errors::{ErrorCategory, GitError},
     service::ProgressPayload,
 };
 
+/// 按代理路由为 libgit2 内置 HTTP 传输生成代理选项。
+///
+/// 代理启用时自定义传输层被禁用（仅直连路由的主机仍会改写），走代理的目标都由 libgit2 处理：
+/// 直连不设代理；系统代理按 PAC 结果选取，无 PAC 时交给 libgit2 自动探测；
+/// 命名代理/默认代理（或上游代理池中首个可用的 `http://` 上游）写入 URL（含凭证）。
+/// libgit2 只能经明文 HTTP 代理 CONNECT，SOCKS 与 `https://` 代理（需按代理 TLS 配置校验）返回错误，
+/// 而不是交给 libgit2 后以 "unknown http scheme" 失败或绕过校验。
+pub fn proxy_options_for(url: &str) -> Result<git2::ProxyOptions<'static>, GitError> {
+    let mut opts = git2::ProxyOptions::new();
+    if !(url.starts_with("https://") || url.starts_with("http://")) {
+        return Ok(opts);
+    }
+    let cfg = match load_or_init() {
+        Ok(c) => c,
+        Err(_) => return Ok(opts),
+    };
+    let proxy = &cfg.proxy;
+    let (proxy_url, user, pass) = match ProxyRouter::new(proxy).route_url(url) {
+        RouteDecision::Direct => return Ok(opts),
+        RouteDecision::System => return system_proxy_options(proxy, url, opts),
+        RouteDecision::Named(p) => (p.url, p.username, p.password),
+        RouteDecision::Default => match proxy.mode {
+            ProxyMode::Http | ProxyMode::Socks5 if !proxy.upstreams.is_empty() => {
+                // 上游代理池：按粘性/健康顺序取首个 libgit2 可用的上游；没有可用上游时直连。
+                // libgit2 自行拨号，无法在池内故障转移或回写结果；走自定义传输的目标经 PoolConnector 完成这两点
+                let candidates = match (pool::shared_pool(proxy), host_port_of(url)) {
+                    (Some(p), Some((host, _))) => p.candidates(&host),
+                    _ => Vec::new(),
+                };
+                if candidates.is_empty() {
+                    return Ok(opts);
+                }
+                match candidates
+                    .into_iter()
+                    .find(|u| libgit2_supports_proxy(&u.url))
+                {
+                    Some(u) => (u.url, u.username, u.password),
+                    None => return Err(unsupported_proxy(url, "socks5")),
+                }
+            }
+            ProxyMode::Http | ProxyMode::Socks5 => (
+                proxy.url.clone(),
+                proxy.username.clone(),
+                proxy.password.clone(),
+            ),
+            ProxyMode::System => return system_proxy_options(proxy, url, opts),
+            ProxyMode::Off => return Ok(opts),
+        },
+    };
+    if !libgit2_supports_proxy(&proxy_url) {
+        let scheme = proxy_url.split("://").next().unwrap_or_default();
+        return Err(unsupported_proxy(url, scheme));
+    }
+    if let Some(with_creds) = proxy_url_with_credentials(&proxy_url, user, pass) {
+        opts.url(&with_creds);
+    }
+    Ok(opts)
+}
+
+/// 系统代理：有 PAC 时按 `FindProxyForURL` 结果取首个 libgit2 可处理的项（DIRECT 或明文 HTTP 代理），
+/// libgit2 无法逐项回退；没有 PAC 时交给 libgit2 自动探测（环境变量 / git 配置）。
+fn system_proxy_options(
+    proxy: &ProxyConfig,
+    url: &str,
+    mut opts: git2::ProxyOptions<'static>,
+) -> Result<git2::ProxyOptions<'static>, GitError> {
+    let script =
+        pac::resolve_pac_location(proxy).and_then(|location| pac::load_cached(&location).ok());
+    let Some(script) = script else {
+        opts.auto();
+        return Ok(opts);
+    };
+    let host = host_port_of(url).map(|(host, _)| host).unwrap_or_default();
+    let directives = script.evaluate(url, &host).unwrap_or_else(|e| {
+        tracing::warn!(
+            "PAC evaluation for {} failed, connecting directly: {}",
+            host,
+            e
+        );
+        vec![PacDirective::Direct]
+    });
+    for directive in &directives {
+        match directive {
+            PacDirective::Direct => return Ok(opts),
+            PacDirective::Http(_) => {
+                if let Some(proxy_url) = directive.proxy_url() {
+                    opts.url(&proxy_url);
+                }
+                return Ok(opts);
+            }
+            PacDirective::Https(_) | PacDirective::Socks(_) => {}
+        }
+    }
+    Err(unsupported_proxy(url, "PAC"))
+}
+
+/// libgit2 内置 HTTP 只支持明文 HTTP 代理（未写 scheme 时按 HTTP 处理）
+fn libgit2_supports_proxy(proxy_url: &str) -> bool {
+    let url = proxy_url.trim().to_ascii_lowercase();
+    url.starts_with("http://") || !url.contains("://")
+}
+
+fn unsupported_proxy(url: &str, scheme: &str) -> GitError {
+    GitError::new(
+        ErrorCategory::Proxy,
+        format!(
+            "{scheme} proxy is not supported by the libgit2 HTTP transport for {}; \
+             configure an http:// proxy for git",
+            strip_userinfo(url)
+        ),
+    )
+}
+
+fn proxy_url_with_credentials(
+    proxy_url: &str,
+    user: Option<String>,
+    pass: Option<String>,
+) -> Option<String> {
+    let mut parsed = Url::parse(proxy_url.trim()).ok()?;
+    if let Some(user) = user.filter(|u| !u.is_empty()) {
+        parsed.set_username(&user).ok()?;
+        parsed.set_password(pass.as_deref()).ok()?;
+    }
+    Some(parsed.to_string())
+}
+
 #[inline]
 pub fn preflight_generic<F: FnMut(ProgressPayload)>(
     kind: &str,
@@ -46,7 +178,10 @@ pub fn emit_sni_status<F: FnMut(ProgressPayload)>(
     let host_opt = maybe_repo_url
         .and_then(|u| Url::parse(u).ok())
         .and_then(|u| u.host_str().map(|s| s.to_string()));
-    let proxy = proxy_present();
+    let proxy = match maybe_repo_url {
+        Some(u) => proxy_in_use_for_url(&cfg.proxy, u),
+        None => proxy_in_use(&cfg.proxy, "github.com", 443),
+    };
     let (sni, used_fake) = match host_opt.as_deref() {
         Some(h) => decide_sni_host_with_proxy(&cfg, false, h, proxy),
         None => decide_sni_host_with_proxy(&cfg, false, "github.com", proxy),
@@ -78,6 +213,10 @@ pub fn map_git2_error(e: &git2::Error) -> ErrorCategory {
         return ErrorCategory::Cancel;
     }
     let msg = e.message().to_ascii_lowercase();
+    // mTLS 失败消息可能含 "connection" 等字样，需先于网络类判断
+    if msg.contains("client certificate") {
+        return ErrorCategory::ClientCert;
+    }
     // 增补中文网络关键字："连接"/"无法"/"超时"/"失败" 与常见英文保持并列
     if msg.contains("timed out")
         || msg.contains("timeout")
@@ -215,6 +354,39 @@ mod tests {
         assert!(!is_local_path_candidate("ftp://some-host/file"));
     }
 
+    #[test]
+    fn test_system_proxy_options_follow_pac() {
+        let dir = tempfile::tempdir().unwrap();
+        let pac_path = dir.path().join("git.pac");
+        std::fs::write(
+            &pac_path,
+            r#"function FindProxyForURL(url, host) {
+                 if (host === "socks-only.example") { return "SOCKS5 127.0.0.1:1080"; }
+                 return "SOCKS5 127.0.0.1:1080; PROXY 127.0.0.1:3128; DIRECT";
+               }"#,
+        )
+        .unwrap();
+        let proxy = ProxyConfig {
+            mode: ProxyMode::System,
+            pac_url: Some(pac_path.to_string_lossy().to_string()),
+            ..Default::default()
+        };
+
+        // 跳过 libgit2 无法处理的 SOCKS 项，取后续的明文 HTTP 代理
+        let url = "http://git.example/repo.git";
+        assert!(system_proxy_options(&proxy, url, git2::ProxyOptions::new()).is_ok());
+
+        // 结果中没有 libgit2 可处理的项时报代理错误，而不是退回自动探测
+        let err = system_proxy_options(
+            &proxy,
+            "http://socks-only.example/repo.git",
+            git2::ProxyOptions::new(),
+        )
+        .err()
+        .unwrap();
+        assert_eq!(err.category(), ErrorCategory::Proxy);
+    }
+
     #[test]
     fn test_percent_and_hint() {
         assert_eq!(percent(50, 100), 50);
@@ -1,6 +1,8 @@
 use std::{path::Path, sync::atomic::AtomicBool};
 
-use crate::core::git::transport::{ensure_registered, maybe_rewrite_https_to_custom};
+use crate::core::git::transport::{
+    ensure_registered, maybe_rewrite_https_to_custom, run_with_failover, UrlPurpose,
+};
 // adaptive_tls_rollout 事件在任务注册层发射；此处不直接发射以便获取真实 task id。
 // (helpers module handles SNI decision and config loading when needed)
 
@@ -104,19 +106,22 @@ impl GitService for DefaultGitService {
                 format!("register custom transport: {}", e.message()),
             ));
         }
-        let rewritten = maybe_rewrite_https_to_custom(&cfg, repo);
-        let adaptive_used = rewritten.is_some();
-        let repo_url_final = rewritten.unwrap_or_else(|| repo.to_string());
         // 若是本地路径克隆，git2/libgit2 不支持 depth 参数；忽略之以保持兼容（后续可发回退事件）。
         let effective_depth = if looks_like_path { None } else { depth };
-        // Call runner directly
-        let r = self.runner.clone_repo(
-            repo_url_final.as_str(),
-            dest,
-            effective_depth,
-            should_interrupt,
-            &mut on_progress,
-        );
+        // 镜像规则选出候选地址（失败的镜像依次切换；libgit2 clone 失败时会自行清理目标目录）
+        let mut adaptive_used = false;
+        let r = ops::clone_with_mirrors(&cfg.mirror, repo, dest, |candidate| {
+            let rewritten = maybe_rewrite_https_to_custom(&cfg, &candidate.url);
+            adaptive_used = rewritten.is_some();
+            let repo_url_final = rewritten.unwrap_or_else(|| candidate.url.clone());
+            self.runner.clone_repo(
+                repo_url_final.as_str(),
+                dest,
+                effective_depth,
+                should_interrupt,
+                &mut on_progress,
+            )
+        });
         if adaptive_used {
             tracing::debug!(
                 target = "git.clone",
@@ -194,14 +199,36 @@ impl GitService for DefaultGitService {
         } else {
             depth
         };
-        // Call runner directly
-        self.runner.fetch_repo(
-            dest,
-            repo_url,
-            effective_depth,
-            should_interrupt,
-            &mut on_progress,
-        )
+        // 镜像规则需要真实 URL：远程名（或空=origin）先解析为其配置的 URL
+        let origin_url = if cfg.mirror.enabled {
+            resolve_fetch_url(dest, repo_url_trimmed)
+        } else {
+            None
+        };
+        let Some(origin_url) = origin_url else {
+            return self.runner.fetch_repo(
+                dest,
+                repo_url,
+                effective_depth,
+                should_interrupt,
+                &mut on_progress,
+            );
+        };
+        run_with_failover(&cfg.mirror, &origin_url, UrlPurpose::Read, |candidate| {
+            // 候选即源站原地址时保留调用方传入的远程名，沿用其 refspec
+            let target = if candidate.mirror.is_none() && candidate.url == origin_url {
+                repo_url
+            } else {
+                candidate.url.as_str()
+            };
+            self.runner.fetch_repo(
+                dest,
+                target,
+                effective_depth,
+                should_interrupt,
+                &mut on_progress,
+            )
+        })
     }
 
     fn push_blocking<F: FnMut(ProgressPayload)>(
@@ -225,6 +252,21 @@ impl GitService for DefaultGitService {
     }
 }
 
+/// 解析 fetch 目标的 URL：显式 URL 原样返回；远程名（为空时取 origin）返回其配置的 URL。
+fn resolve_fetch_url(dest: &Path, repo_url: &str) -> Option<String> {
+    if repo_url.contains("://") {
+        return Some(repo_url.to_string());
+    }
+    let repo = git2::Repository::open(dest).ok()?;
+    let name = if repo_url.is_empty() {
+        "origin"
+    } else {
+        repo_url
+    };
+    let remote = repo.find_remote(name).ok()?;
+    remote.url().map(str::to_string)
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum RepoSourceType {
     LocalPath,
@@ -3,7 +3,11 @@ use std::{
     sync::{Arc, Mutex},
 };
 
-use crate::core::git::transport::maybe_rewrite_https_to_custom;
+use crate::core::config::model::MirrorCfg;
+use crate::core::git::transport::{
+    maybe_rewrite_https_to_custom, ref_prefixes_for_refspecs, run_with_failover,
+    set_ls_refs_prefixes, throttle_progress, MirrorCandidate, UrlPurpose,
+};
 
 use super::super::{
     errors::{ErrorCategory, GitError},
@@ -11,6 +15,32 @@ use super::super::{
 };
 use super::helpers;
 
+/// 按镜像规则克隆：依次以候选地址执行 `clone`。
+///
+/// 实际服务方不是原始地址（镜像或 insteadOf 改写）时，成功后把 origin 恢复为原始 URL，
+/// 使推送按 pushInsteadOf 走源站，后续 fetch 也重新按规则选择镜像。
+pub fn clone_with_mirrors(
+    cfg: &MirrorCfg,
+    repo_url: &str,
+    dest: &Path,
+    mut clone: impl FnMut(&MirrorCandidate) -> Result<(), GitError>,
+) -> Result<(), GitError> {
+    run_with_failover(cfg, repo_url, UrlPurpose::Read, |candidate| {
+        clone(candidate)?;
+        if candidate.url != repo_url {
+            git2::Repository::open(dest)
+                .and_then(|repo| repo.remote_set_url("origin", repo_url))
+                .map_err(|e| {
+                    GitError::new(
+                        ErrorCategory::Internal,
+                        format!("restore origin url after mirrored clone: {e}"),
+                    )
+                })?;
+        }
+        Ok(())
+    })
+}
+
 pub fn do_clone<F: FnMut(ProgressPayload)>(
     repo_url_final: &str,
     dest: &Path,
@@ -29,6 +59,7 @@ pub fn do_clone<F: FnMut(ProgressPayload)>(
         let received = stats.received_objects() as u64;
         let total = stats.total_objects() as u64;
         let bytes = stats.received_bytes() as u64;
+        throttle_progress(bytes, false);
         let percent = helpers::percent(received, total).min(100);
         if let Ok(mut f) = cb_for_transfer.lock() {
             (*f)(ProgressPayload {
@@ -50,7 +81,7 @@ pub fn do_clone<F: FnMut(ProgressPayload)>(
     }
     fo.remote_callbacks(callbacks);
     fo.download_tags(git2::AutotagOption::Unspecified);
-    fo.proxy_options(git2::ProxyOptions::new());
+    fo.proxy_options(helpers::proxy_options_for(repo_url_final)?);
     fo.update_fetchhead(true);
 
     let mut co = git2::build::CheckoutBuilder::new();
@@ -81,7 +112,14 @@ pub fn do_clone<F: FnMut(ProgressPayload)>(
     builder.fetch_options(fo);
     builder.with_checkout(co);
 
-    match builder.clone(repo_url_final, dest) {
+    // 协议 v2 下仅请求分支、标签与 HEAD 的引用通告
+    set_ls_refs_prefixes(Some(ref_prefixes_for_refspecs(
+        &["+refs/heads/*:refs/remotes/origin/*"],
+        true,
+    )));
+    let result = builder.clone(repo_url_final, dest);
+    set_ls_refs_prefixes(None);
+    match result {
         Ok(_repo) => {
             if let Ok(mut f) = cb.lock() {
                 (*f)(ProgressPayload {
@@ -131,6 +169,7 @@ pub fn do_fetch<F: FnMut(ProgressPayload)>(
         let received = stats.received_objects() as u64;
         let total = stats.total_objects() as u64;
         let bytes = stats.received_bytes() as u64;
+        throttle_progress(bytes, false);
         let percent = helpers::percent(received, total).min(100);
         if let Ok(mut f) = cb_for_transfer.lock() {
             (*f)(ProgressPayload {
@@ -166,7 +205,29 @@ pub fn do_fetch<F: FnMut(ProgressPayload)>(
         vec![]
     };
 
-    match remote.fetch(&refspecs, Some(&mut fo), None) {
+    // 协议 v2 下按本次 refspec 过滤引用通告（命名远程取其配置的 fetch refspec）
+    let configured: Vec<String> = if refspecs.is_empty() {
+        remote
+            .fetch_refspecs()
+            .map(|specs| specs.iter().flatten().map(str::to_string).collect())
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+    let prefix_specs: Vec<&str> = if refspecs.is_empty() {
+        configured.iter().map(String::as_str).collect()
+    } else {
+        refspecs.clone()
+    };
+    set_ls_refs_prefixes(
+        (!prefix_specs.is_empty()).then(|| ref_prefixes_for_refspecs(&prefix_specs, true)),
+    );
+    if let Some(u) = remote.url() {
+        fo.proxy_options(helpers::proxy_options_for(u)?);
+    }
+    let result = remote.fetch(&refspecs, Some(&mut fo), None);
+    set_ls_refs_prefixes(None);
+    match result {
         Ok(_) => {
             if let Ok(mut f) = cb.lock() {
                 (*f)(ProgressPayload {
@@ -46,11 +46,20 @@ pub struct StrategyRetryOverride {
     pub jitter: Option<bool>,
 }
 
+/// 单任务限速覆盖（KiB/s，0 表示不限），优先于按任务类型的配置
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct StrategyBandwidthOverride {
+    #[serde(alias = "limit_kib_per_sec")]
+    pub limit_kib_per_sec: Option<u64>,
+}
+
 #[derive(Debug, Clone, Default, Deserialize, PartialEq)]
 #[serde(default, rename_all = "camelCase")]
 pub struct StrategyOverrideInput {
     pub http: Option<StrategyHttpOverride>,
     pub retry: Option<StrategyRetryOverride>,
+    pub bandwidth: Option<StrategyBandwidthOverride>,
     // Unknown top-level keys are ignored by serde; we emit warn logs in parser when present.
 }
 
@@ -124,7 +133,7 @@ pub fn parse_strategy_override(
         };
         // detect unknown top-level keys
         for k in &top_keys {
-            if k != "http" && k != "retry" {
+            if !matches!(k.as_str(), "http" | "retry" | "bandwidth") {
                 tracing::warn!(target="strategy", key=%k, "unknown top-level strategyOverride key ignored");
                 res.ignored_top_level.push(k.clone());
             }
@@ -153,6 +162,15 @@ pub fn parse_strategy_override(
             }
         }
 
+        if let Some(bw) = obj.get("bandwidth").and_then(|v| v.as_object()) {
+            for k in bw.keys() {
+                if !matches!(k.as_str(), "limitKibPerSec" | "limit_kib_per_sec") {
+                    tracing::warn!(target="strategy", section="bandwidth", key=%k, "unknown bandwidth override field ignored");
+                    res.ignored_nested.push(("bandwidth".into(), k.clone()));
+                }
+            }
+        }
+
         // value/range validation (P2.3a enhancement)
         if let Some(http) = &res.parsed.as_ref().unwrap().http {
             if let Some(max_r) = http.max_redirects {
@@ -5,8 +5,10 @@ use std::{
     sync::{atomic::Ordering, Arc, Mutex},
 };
 
+use crate::core::git::transport::mirror::tl_set_mirror_outcome;
 use crate::core::git::transport::{
-    ensure_registered, maybe_rewrite_https_to_custom, set_push_auth_header_value,
+    apply_rewrite_rules, ensure_registered, maybe_rewrite_https_to_custom,
+    set_push_auth_header_value, throttle_progress, MirrorOutcome, UrlPurpose,
 };
 
 use super::super::{
@@ -66,6 +68,7 @@ pub(crate) fn do_push_internal<F: FnMut(ProgressPayload)>(
         let received = stats.received_objects() as u64;
         let total = stats.total_objects() as u64;
         let bytes = stats.received_bytes() as u64;
+        throttle_progress(bytes, false);
         let percent = helpers::percent(received, total).min(100);
         if let Ok(mut f) = cb_for_transfer.lock() {
             (*f)(ProgressPayload {
@@ -80,6 +83,10 @@ pub(crate) fn do_push_internal<F: FnMut(ProgressPayload)>(
         }
         true
     });
+    // 上传字节（内置传输下的限速兜底）
+    callbacks.push_transfer_progress(|_current, _total, bytes| {
+        throttle_progress(bytes as u64, true);
+    });
     // 阶段事件
     let cb_for_phase = Arc::clone(&cb);
     callbacks.sideband_progress(move |_data| {
@@ -92,6 +99,8 @@ pub(crate) fn do_push_internal<F: FnMut(ProgressPayload)>(
 
     // 选择远程并发出 SNI 状态
     let remote_name = remote.unwrap_or("origin");
+    // 推送始终走源站：只应用 pushInsteadOf/insteadOf 改写，不使用只读镜像
+    let mut push_target: Option<(String, String)> = None;
     let mut remote = match repo.find_remote(remote_name) {
         Ok(r) => {
             if let Some(u) = r.url() {
@@ -108,7 +117,12 @@ pub(crate) fn do_push_internal<F: FnMut(ProgressPayload)>(
                 });
             }
             if let Some(u) = r.url() {
-                if let Some(new_url) = maybe_rewrite_https_to_custom(&cfg, u) {
+                let target = apply_rewrite_rules(&cfg.mirror, u, UrlPurpose::Push);
+                let rewritten_by_rule = target != u;
+                push_target = Some((u.to_string(), target.clone()));
+                let new_url = maybe_rewrite_https_to_custom(&cfg, &target)
+                    .or_else(|| rewritten_by_rule.then(|| target.clone()));
+                if let Some(new_url) = new_url {
                     match repo.remote_anonymous(&new_url) {
                         Ok(r2) => r2,
                         Err(e) => {
@@ -180,12 +194,26 @@ pub(crate) fn do_push_internal<F: FnMut(ProgressPayload)>(
         }
     };
 
+    if let Some(u) = remote.url() {
+        po.proxy_options(helpers::proxy_options_for(u)?);
+    }
     let push_res = if specs.is_empty() {
         remote.push(&[] as &[&str], Some(&mut po))
     } else {
         remote.push(&specs, Some(&mut po))
     };
     set_push_auth_header_value(None);
+    if cfg.mirror.enabled {
+        if let Some((origin, served_by)) = push_target {
+            tl_set_mirror_outcome(Some(MirrorOutcome {
+                origin,
+                served_by,
+                mirror: None,
+                failovers: Vec::new(),
+                success: push_res.is_ok(),
+            }));
+        }
+    }
 
     match push_res {
         Ok(()) => {
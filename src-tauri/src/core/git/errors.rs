@@ -5,6 +5,8 @@ pub enum ErrorCategory {
     Network,
     Tls,
     Verify,
+    /// mTLS：客户端证书加载失败或被服务端拒绝
+    ClientCert,
     Protocol,
     Proxy,
     Auth,
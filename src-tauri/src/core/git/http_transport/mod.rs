@@ -3,17 +3,27 @@
 // Public API:
 // - struct CustomHttpsSubtransport (used by transport::register)
 // - fn set_push_auth_header_value (re-exported to transport::)
+// - keep-alive connection pool (connection_pool / ConnectionPool / PoolStats)
+// - protocol v2 ref-prefix hints (set_ls_refs_prefixes / ref_prefixes_for_refspecs)
 
 mod auth;
 mod fallback;
+mod pool;
+mod protocol_v2;
 mod stream;
 mod subtransport;
 mod util;
 
 pub use auth::set_push_auth_header_value;
+pub use pool::{
+    connection_pool, shared_client_config, ConnectionPool, HttpVersion, PoolKey, PoolStats,
+    PooledConnection,
+};
+pub use protocol_v2::{ref_prefixes_for_refspecs, set_ls_refs_prefixes};
 pub use subtransport::CustomHttpsSubtransport;
 
 /// HTTP 操作类型（smart 协议的四种阶段），仅限本模块及子模块使用。
+#[derive(Clone, Copy)]
 pub(super) enum HttpOp {
     // GET /info/refs?service=git-upload-pack
     InfoRefsUpload,
@@ -25,17 +35,16 @@ pub(super) enum HttpOp {
     ReceivePack,
 }
 
-pub(super) enum TransferKind {
-    Chunked,
-    Length,
-    Eof,
-}
-
 pub mod testing {
     //! Aggregates HTTP transport testing helpers for integration tests.
     pub use super::fallback::testing::{
         classify_and_count_fallback, inject_fake_failure, inject_real_failure,
         reset_fallback_counters, reset_injected_failures, snapshot_fallback_counters,
     };
+    pub use super::protocol_v2::{
+        build_ls_refs_request, next_pkt, parse_capability_advertisement, parse_ls_refs_response,
+        pkt_line, synthesize_v0_advertisement, translate_fetch_request, AdvertisedRef,
+        FetchRequest, FetchResponseTranslator, Pkt, ProtocolV2Session, ServerCapabilities,
+    };
     pub use super::subtransport::testing::TestSubtransport;
 }
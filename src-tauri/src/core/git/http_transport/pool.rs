@@ -0,0 +1,585 @@
+//! Keep-alive 连接池：按 (host, port, SNI, 选中 IP) 复用已完成 TCP+TLS 握手的 HTTP 连接。
+//!
+//! 每个 smart 协议请求（info/refs GET、upload-pack / receive-pack POST）从池中借出连接，
+//! 响应体完整读取且服务端未要求 `Connection: close` 时归还。TLS 配置按 (期望主机, SPKI pins)
+//! 全局共享，因此即便需要新建连接，也能通过会话恢复跳过完整握手。
+//!
+//! ALPN 协商到 `h2` 的连接可多路复用：借出的是共享句柄，连接始终留在池中，
+//! 同一主机的并发请求（如工作区批量 fetch）共用一条 TLS 连接。
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use futures::FutureExt;
+use hyper::client::conn::SendRequest;
+use hyper::{Body, Request, Response};
+use rustls::{ClientConfig, ServerName};
+use serde::Serialize;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio::runtime::{Builder as RuntimeBuilder, Runtime};
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::TlsConnector;
+
+use crate::core::config::model::{AppConfig, ClientCertCfg, HttpCfg, TlsCfg};
+use crate::core::proxy::pac::target_url;
+use crate::core::proxy::routing::routed_through_proxy;
+use crate::core::proxy::{tunnel, AsyncProxyStream, ProxyConfig};
+use crate::core::tls::client_cert::{
+    apply_client_cert, describe_handshake_error, select_client_cert,
+};
+use crate::core::tls::verifier::{create_client_config, create_client_config_with_expected_name};
+
+/// 连接 IP 池候选地址的 TCP 超时
+pub(super) const CANDIDATE_CONNECT_TIMEOUT: Duration = Duration::from_millis(500);
+
+const ALPN_H2: &[u8] = b"h2";
+const ALPN_HTTP1: &[u8] = b"http/1.1";
+
+/// 连接池键：同一真实主机、端口、握手所用 SNI 与所连 IP（`None` 表示系统 DNS）。
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PoolKey {
+    pub host: String,
+    pub port: u16,
+    pub sni: String,
+    pub ip: Option<IpAddr>,
+}
+
+impl PoolKey {
+    pub fn new(host: &str, port: u16, sni: &str, ip: Option<IpAddr>) -> Self {
+        Self {
+            host: host.to_string(),
+            port,
+            sni: sni.to_string(),
+            ip,
+        }
+    }
+}
+
+/// 连接上协商出的 HTTP 版本（TLS 连接由 ALPN 决定）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum HttpVersion {
+    #[serde(rename = "HTTP/1.1")]
+    Http1,
+    #[serde(rename = "HTTP/2")]
+    Http2,
+}
+
+impl HttpVersion {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HttpVersion::Http1 => "HTTP/1.1",
+            HttpVersion::Http2 => "HTTP/2",
+        }
+    }
+}
+
+/// HTTP/1.1 连接同一时刻只承载一个请求，需独占借出；
+/// HTTP/2 连接可多路复用，借出的是共享句柄，连接本身留在池中。
+enum Sender {
+    Http1(SendRequest<Body>),
+    Http2(Arc<tokio::sync::Mutex<SendRequest<Body>>>),
+}
+
+/// 一条已完成 HTTP 握手的连接；后台连接任务运行在传输运行时上。
+pub struct PooledConnection {
+    key: PoolKey,
+    sender: Sender,
+    used_fake: bool,
+    reused: bool,
+    idle_since: Instant,
+}
+
+impl PooledConnection {
+    /// 在任意已建立的字节流（TLS 或明文）上完成 HTTP/1.1 握手。须在 Tokio 运行时内调用。
+    pub async fn handshake<T>(key: PoolKey, used_fake: bool, io: T) -> hyper::Result<Self>
+    where
+        T: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        Self::handshake_version(key, used_fake, io, HttpVersion::Http1).await
+    }
+
+    /// 按指定版本完成握手；HTTP/2 要求对端已同意（ALPN `h2` 或明文 prior knowledge）。
+    pub async fn handshake_version<T>(
+        key: PoolKey,
+        used_fake: bool,
+        io: T,
+        version: HttpVersion,
+    ) -> hyper::Result<Self>
+    where
+        T: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let (sender, conn) = hyper::client::conn::Builder::new()
+            .http2_only(version == HttpVersion::Http2)
+            .handshake(io)
+            .await?;
+        let host = key.host.clone();
+        tokio::spawn(async move {
+            if let Err(e) = conn.await {
+                tracing::debug!(target="git.transport.http", host=%host, error=%e, "pooled connection ended");
+            }
+        });
+        let sender = match version {
+            HttpVersion::Http1 => Sender::Http1(sender),
+            HttpVersion::Http2 => Sender::Http2(Arc::new(tokio::sync::Mutex::new(sender))),
+        };
+        Ok(Self {
+            key,
+            sender,
+            used_fake,
+            reused: false,
+            idle_since: Instant::now(),
+        })
+    }
+
+    /// 在 TLS 流上按 ALPN 结果选择 HTTP/2 或 HTTP/1.1
+    pub async fn handshake_negotiated<T>(
+        key: PoolKey,
+        used_fake: bool,
+        tls: TlsStream<T>,
+    ) -> hyper::Result<Self>
+    where
+        T: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let version = match tls.get_ref().1.alpn_protocol() {
+            Some(proto) if proto == ALPN_H2 => HttpVersion::Http2,
+            _ => HttpVersion::Http1,
+        };
+        Self::handshake_version(key, used_fake, tls, version).await
+    }
+
+    pub fn key(&self) -> &PoolKey {
+        &self.key
+    }
+
+    pub fn used_fake(&self) -> bool {
+        self.used_fake
+    }
+
+    pub fn version(&self) -> HttpVersion {
+        match self.sender {
+            Sender::Http1(_) => HttpVersion::Http1,
+            Sender::Http2(_) => HttpVersion::Http2,
+        }
+    }
+
+    /// 是否为从池中取回（而非本次新建）的连接
+    pub fn is_reused(&self) -> bool {
+        self.reused
+    }
+
+    pub async fn send_request(&mut self, req: Request<Body>) -> hyper::Result<Response<Body>> {
+        match &mut self.sender {
+            Sender::Http1(sender) => sender.send_request(req).await,
+            Sender::Http2(shared) => {
+                // 仅在等待连接可接收新流并入队期间持锁；响应在锁外等待，多个流并发复用同一连接
+                let pending = {
+                    let mut sender = shared.lock().await;
+                    futures::future::poll_fn(|cx| sender.poll_ready(cx)).await?;
+                    sender.send_request(req)
+                };
+                pending.await
+            }
+        }
+    }
+
+    /// 等待连接回到可发送状态（上一个响应体读完后，后台任务需要片刻切换到空闲）。
+    pub async fn wait_ready(mut self, limit: Duration) -> Option<Self> {
+        if let Sender::Http2(_) = self.sender {
+            return (self.poll_ready_now() != Some(false)).then_some(self);
+        }
+        let ready = futures::future::poll_fn(|cx| self.poll_ready(cx));
+        match tokio::time::timeout(limit, ready).await {
+            Ok(Ok(())) => Some(self),
+            _ => None,
+        }
+    }
+
+    fn poll_ready(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<hyper::Result<()>> {
+        match &mut self.sender {
+            Sender::Http1(sender) => sender.poll_ready(cx),
+            // 其他流正在入队时视为暂不可用，而非已关闭
+            Sender::Http2(shared) => match shared.try_lock() {
+                Ok(mut sender) => sender.poll_ready(cx),
+                Err(_) => std::task::Poll::Pending,
+            },
+        }
+    }
+
+    /// 非阻塞探测：`Some(true)` 可发送，`Some(false)` 已关闭，`None` 暂不可用
+    fn poll_ready_now(&mut self) -> Option<bool> {
+        futures::future::poll_fn(|cx| self.poll_ready(cx))
+            .now_or_never()
+            .map(|r| r.is_ok())
+    }
+
+    /// 非阻塞检查：连接仍然存活且空闲（HTTP/2 只要求未关闭）
+    fn is_idle_alive(&mut self) -> bool {
+        match self.sender {
+            Sender::Http1(_) => self.poll_ready_now() == Some(true),
+            Sender::Http2(_) => self.poll_ready_now() != Some(false),
+        }
+    }
+
+    /// HTTP/2 连接的共享句柄；HTTP/1.1 返回 `None`
+    fn share(&self) -> Option<Self> {
+        match &self.sender {
+            Sender::Http1(_) => None,
+            Sender::Http2(shared) => Some(Self {
+                key: self.key.clone(),
+                sender: Sender::Http2(shared.clone()),
+                used_fake: self.used_fake,
+                reused: true,
+                idle_since: self.idle_since,
+            }),
+        }
+    }
+
+    fn same_connection(&self, other: &Self) -> bool {
+        match (&self.sender, &other.sender) {
+            (Sender::Http2(a), Sender::Http2(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+/// 连接池计数快照
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolStats {
+    pub idle: usize,
+    /// 池中的 HTTP/2（可多路复用）连接数，已计入 `idle`
+    pub http2: usize,
+    pub created: u64,
+    pub reused: u64,
+    pub returned: u64,
+    pub evicted: u64,
+}
+
+pub struct ConnectionPool {
+    idle: Mutex<HashMap<PoolKey, Vec<PooledConnection>>>,
+    limits: Mutex<(usize, Duration)>,
+    created: AtomicU64,
+    reused: AtomicU64,
+    returned: AtomicU64,
+    evicted: AtomicU64,
+}
+
+impl ConnectionPool {
+    /// `max_idle_per_key = 0` 表示禁用复用（归还的连接直接关闭）。
+    pub fn new(max_idle_per_key: usize, idle_timeout: Duration) -> Self {
+        Self {
+            idle: Mutex::new(HashMap::new()),
+            limits: Mutex::new((max_idle_per_key, idle_timeout)),
+            created: AtomicU64::new(0),
+            reused: AtomicU64::new(0),
+            returned: AtomicU64::new(0),
+            evicted: AtomicU64::new(0),
+        }
+    }
+
+    /// 按配置更新上限；超出新上限的空闲连接会被关闭。
+    pub fn configure(&self, max_idle_per_key: usize, idle_timeout: Duration) {
+        *self.limits.lock().expect("pool limits poisoned") = (max_idle_per_key, idle_timeout);
+        let mut idle = self.idle.lock().expect("pool mutex poisoned");
+        for conns in idle.values_mut() {
+            if conns.len() > max_idle_per_key {
+                let extra = conns.len() - max_idle_per_key;
+                conns.drain(..extra);
+                self.evicted.fetch_add(extra as u64, Ordering::Relaxed);
+            }
+        }
+        idle.retain(|_, conns| !conns.is_empty());
+    }
+
+    pub fn apply_http_cfg(&self, http: &HttpCfg) {
+        self.configure(
+            http.pool_max_idle_per_host,
+            Duration::from_secs(http.pool_idle_timeout_sec),
+        );
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.limits().0 > 0
+    }
+
+    fn limits(&self) -> (usize, Duration) {
+        *self.limits.lock().expect("pool limits poisoned")
+    }
+
+    /// 取出 `host:port` 最近归还的可用连接；`allow_fake=false` 时跳过以伪 SNI 握手的连接。
+    /// 过期、已被对端关闭或 `admit` 拒绝的连接在扫描过程中被淘汰。
+    ///
+    /// 有意不按键中的 SNI 与 IP 精确匹配：借出发生在 IP 选择与 SNI 决策之前，
+    /// 已完成握手（证书按真实主机校验）的连接可跨 IP 池候选与伪 SNI 候选复用，
+    /// 伪 SNI 是否可用由 `allow_fake` 把关，所连 IP 是否仍可用由 `admit` 按池键判断
+    /// （例如 IP 已被熔断）；键中的 SNI/IP 同时用于拨号、分桶与 IP 结果上报。
+    pub fn checkout(
+        &self,
+        host: &str,
+        port: u16,
+        allow_fake: bool,
+        admit: impl Fn(&PoolKey) -> bool,
+    ) -> Option<PooledConnection> {
+        let (_, idle_timeout) = self.limits();
+        let mut idle = self.idle.lock().expect("pool mutex poisoned");
+        let now = Instant::now();
+        let mut best: Option<(PoolKey, usize, Instant)> = None;
+        for (key, conns) in idle.iter_mut() {
+            if key.host != host || key.port != port {
+                continue;
+            }
+            let before = conns.len();
+            if admit(key) {
+                conns.retain_mut(|c| {
+                    now.duration_since(c.idle_since) < idle_timeout && c.is_idle_alive()
+                });
+            } else {
+                conns.clear();
+            }
+            self.evicted
+                .fetch_add((before - conns.len()) as u64, Ordering::Relaxed);
+            if let Some((idx, conn)) = conns
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| allow_fake || !c.used_fake)
+                .max_by_key(|(_, c)| c.idle_since)
+            {
+                if best.as_ref().is_none_or(|(_, _, t)| conn.idle_since > *t) {
+                    best = Some((key.clone(), idx, conn.idle_since));
+                }
+            }
+        }
+        let picked = best.and_then(|(key, idx, _)| {
+            let conns = idle.get_mut(&key)?;
+            // HTTP/2 连接留在池中供其他流并发使用，只借出共享句柄
+            match conns[idx].share() {
+                Some(handle) => {
+                    conns[idx].idle_since = now;
+                    Some(handle)
+                }
+                None => Some(conns.swap_remove(idx)),
+            }
+        });
+        idle.retain(|_, conns| !conns.is_empty());
+        picked.map(|mut conn| {
+            conn.reused = true;
+            self.reused.fetch_add(1, Ordering::Relaxed);
+            tracing::debug!(target="git.transport.http", host=%conn.key.host, sni=%conn.key.sni, ip=?conn.key.ip, "reuse pooled connection");
+            conn
+        })
+    }
+
+    /// 归还空闲连接；超过单键上限时丢弃最旧的一条。
+    pub fn checkin(&self, mut conn: PooledConnection) {
+        let (max_idle, _) = self.limits();
+        if max_idle == 0 {
+            self.evicted.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        conn.idle_since = Instant::now();
+        let mut idle = self.idle.lock().expect("pool mutex poisoned");
+        let conns = idle.entry(conn.key.clone()).or_default();
+        self.returned.fetch_add(1, Ordering::Relaxed);
+        if let Some(existing) = conns.iter_mut().find(|c| c.same_connection(&conn)) {
+            existing.idle_since = conn.idle_since;
+            return;
+        }
+        conns.push(conn);
+        if conns.len() > max_idle {
+            conns.remove(0);
+            self.evicted.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// 记录一次新建连接（由建连路径调用，用于统计复用率）
+    pub fn record_created(&self) {
+        self.created.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 关闭全部空闲连接（例如网络环境变化后）
+    pub fn clear(&self) {
+        let mut idle = self.idle.lock().expect("pool mutex poisoned");
+        let n: usize = idle.values().map(Vec::len).sum();
+        idle.clear();
+        self.evicted.fetch_add(n as u64, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        let (idle, http2) = self
+            .idle
+            .lock()
+            .map(|m| {
+                m.values().flatten().fold((0, 0), |(all, h2), c| {
+                    (all + 1, h2 + usize::from(c.version() == HttpVersion::Http2))
+                })
+            })
+            .unwrap_or((0, 0));
+        PoolStats {
+            idle,
+            http2,
+            created: self.created.load(Ordering::Relaxed),
+            reused: self.reused.load(Ordering::Relaxed),
+            returned: self.returned.load(Ordering::Relaxed),
+            evicted: self.evicted.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// 自定义 HTTPS 子传输共用的全局连接池
+pub fn connection_pool() -> &'static ConnectionPool {
+    static POOL: OnceLock<ConnectionPool> = OnceLock::new();
+    POOL.get_or_init(|| {
+        let defaults = AppConfig::default().http;
+        ConnectionPool::new(
+            defaults.pool_max_idle_per_host,
+            Duration::from_secs(defaults.pool_idle_timeout_sec),
+        )
+    })
+}
+
+/// 按 (TLS 配置, 客户端证书, 期望主机) 共享的 rustls 配置。
+///
+/// rustls 的会话缓存挂在 `ClientConfig` 上，复用同一实例才能让新连接走会话恢复。
+/// `host` 为真实目标主机，用于选择 mTLS 客户端证书；证书加载失败时返回错误。
+/// `expected_host` 为 `Some` 时对应伪 SNI 握手（证书按真实主机名校验）。
+/// `http2` 为真时通过 ALPN 同时通告 `h2` 与 `http/1.1`，由服务端选择。
+pub fn shared_client_config(
+    tls: &TlsCfg,
+    host: &str,
+    expected_host: Option<&str>,
+    http2: bool,
+) -> std::io::Result<Arc<ClientConfig>> {
+    type CacheKey = (String, Option<ClientCertCfg>, Option<String>, bool);
+    static CONFIGS: OnceLock<Mutex<HashMap<CacheKey, Arc<ClientConfig>>>> = OnceLock::new();
+    let key: CacheKey = (
+        serde_json::to_string(tls).unwrap_or_default(),
+        select_client_cert(tls, host).cloned(),
+        expected_host.map(str::to_string),
+        http2,
+    );
+    let mut map = CONFIGS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .expect("tls config cache poisoned");
+    if let Some(hit) = map.get(&key) {
+        return Ok(hit.clone());
+    }
+    let mut cfg = match expected_host {
+        Some(expected) => create_client_config_with_expected_name(tls, expected),
+        None => create_client_config(tls),
+    };
+    apply_client_cert(&mut cfg, tls, host).map_err(std::io::Error::other)?;
+    if http2 {
+        cfg.alpn_protocols = vec![ALPN_H2.to_vec(), ALPN_HTTP1.to_vec()];
+    }
+    let cfg = Arc::new(cfg);
+    map.insert(key, cfg.clone());
+    Ok(cfg)
+}
+
+/// 子传输专用运行时：驱动 hyper 连接任务，并供同步的 git2 流以 `block_on` 调用。
+pub(super) fn transport_runtime() -> std::io::Result<&'static Runtime> {
+    static RUNTIME: OnceLock<std::result::Result<Runtime, String>> = OnceLock::new();
+    let entry = RUNTIME.get_or_init(|| {
+        RuntimeBuilder::new_multi_thread()
+            .worker_threads(2)
+            .thread_name("git-http-transport")
+            .enable_all()
+            .build()
+            .map_err(|err| err.to_string())
+    });
+    match entry {
+        Ok(rt) => Ok(rt),
+        Err(err) => Err(std::io::Error::other(format!(
+            "http transport runtime unavailable: {err}"
+        ))),
+    }
+}
+
+/// 建立 TCP 连接：`addr` 为 IP 池候选地址，`None` 时走系统 DNS 解析 `host:port`。
+pub(super) async fn tcp_connect(
+    host: &str,
+    port: u16,
+    addr: Option<SocketAddr>,
+    limit: Option<Duration>,
+) -> std::io::Result<TcpStream> {
+    let connect = async {
+        match addr {
+            Some(addr) => TcpStream::connect(addr).await,
+            None => TcpStream::connect((host, port)).await,
+        }
+    };
+    let tcp = match limit {
+        Some(limit) => tokio::time::timeout(limit, connect).await.map_err(|_| {
+            std::io::Error::new(std::io::ErrorKind::TimedOut, "connection timed out")
+        })??,
+        None => connect.await?,
+    };
+    tcp.set_nodelay(true).ok();
+    Ok(tcp)
+}
+
+/// 建立到 `host:port` 的字节流：未指定 `addr` 且目标按路由走代理时，经代理连接器建立隧道
+/// （代理负责拨号与解析，超时由代理配置决定）；否则同 [`tcp_connect`] 直连。
+pub(super) async fn open_stream(
+    proxy: &ProxyConfig,
+    host: &str,
+    port: u16,
+    addr: Option<SocketAddr>,
+    limit: Option<Duration>,
+) -> std::io::Result<AsyncProxyStream> {
+    if addr.is_none() && routed_through_proxy(proxy, host, port) {
+        return tunnel::connect(proxy, &target_url(host, port), host, port).await;
+    }
+    tcp_connect(host, port, addr, limit)
+        .await
+        .map(AsyncProxyStream::from)
+}
+
+/// 以 `sni` 完成 TLS 握手；`tls_cfg` 应来自 [`shared_client_config`] 以便会话恢复。
+pub(super) async fn tls_connect<T>(
+    tcp: T,
+    sni: &str,
+    tls_cfg: Arc<ClientConfig>,
+) -> std::io::Result<TlsStream<T>>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    let server_name = ServerName::try_from(sni)
+        .map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidInput, "invalid sni host"))?;
+    TlsConnector::from(tls_cfg).connect(server_name, tcp).await
+}
+
+/// 阻塞建立一条新连接：TCP（`key.ip`、系统 DNS 或代理隧道）→ TLS（以 `key.sni` 握手）→ 按 ALPN 选择 HTTP/2 或 HTTP/1.1。
+/// 连接 `key.ip` 时与首次选择候选一样受 [`CANDIDATE_CONNECT_TIMEOUT`] 限制。
+pub(super) fn connect_blocking(
+    key: PoolKey,
+    used_fake: bool,
+    tls_cfg: Arc<ClientConfig>,
+    proxy: &ProxyConfig,
+) -> std::io::Result<PooledConnection> {
+    let rt = transport_runtime()?;
+    let conn = rt.block_on(async move {
+        let addr = key.ip.map(|ip| SocketAddr::new(ip, key.port));
+        let limit = addr.map(|_| CANDIDATE_CONNECT_TIMEOUT);
+        let tcp = open_stream(proxy, &key.host, key.port, addr, limit).await?;
+        let tls = tls_connect(tcp, &key.sni, tls_cfg).await.map_err(|e| {
+            std::io::Error::new(
+                e.kind(),
+                describe_handshake_error(&key.host, &e.to_string()),
+            )
+        })?;
+        PooledConnection::handshake_negotiated(key, used_fake, tls)
+            .await
+            .map_err(std::io::Error::other)
+    })?;
+    connection_pool().record_created();
+    Ok(conn)
+}
@@ -0,0 +1,532 @@
+//! Git protocol v2 (smart HTTP) 适配层。
+//!
+//! libgit2 只会说 v0/v1：它解析 v0 形式的引用通告，并以 v0 的 want/have 协商拉取 pack。
+//! 启用 `http.protocol_v2_enabled` 后，自定义传输在线路上改用 v2：
+//! - `info/refs` 携带 `Git-Protocol: version=2`；服务端若回以 v2 能力通告，则改发
+//!   `command=ls-refs`（按 ref-prefix 过滤），再把结果合成为 v0 通告交给 libgit2；
+//! - `git-upload-pack` 请求中的 v0 want/have 转写为 `command=fetch`，响应中的
+//!   acknowledgments / packfile 段再还原为 v0 的 ACK/NAK 与 side-band 数据。
+//!
+//! 服务端不支持 v2 时原样透传 v0 通告；浅克隆（deepen/shallow）等无法无损转写的请求回落 v0。
+
+use std::cell::RefCell;
+
+/// 发起 v2 请求时附带的请求头
+pub(super) const PROTOCOL_HEADER: &str = "Git-Protocol";
+pub(super) const PROTOCOL_V2: &str = "version=2";
+
+const AGENT: &str = "git/2.46.0";
+const FLUSH: &[u8] = b"0000";
+const DELIM: &[u8] = b"0001";
+
+// ls-refs 使用的 ref-prefix 列表（线程局部，由 clone/fetch 在调用 libgit2 前设置）。
+thread_local! { static LS_REFS_PREFIXES: RefCell<Option<Vec<String>>> = const { RefCell::new(None) }; }
+
+/// 设置当前线程后续 ls-refs 请求的 ref-prefix；`None` 表示不过滤（通告全部引用）。
+pub fn set_ls_refs_prefixes(v: Option<Vec<String>>) {
+    LS_REFS_PREFIXES.with(|p| {
+        *p.borrow_mut() = v;
+    });
+}
+
+pub(super) fn get_ls_refs_prefixes() -> Option<Vec<String>> {
+    LS_REFS_PREFIXES.with(|p| p.borrow().clone())
+}
+
+/// 由 fetch refspec 推导 ls-refs 的 ref-prefix 列表。
+///
+/// 通配 refspec 取 `*` 之前的部分；简写名（如 `main`）同时匹配 `refs/heads/` 与 `refs/tags/`；
+/// 负向 refspec 忽略。结果总是包含 `HEAD`（clone 需要据此确定默认分支），
+/// `include_tags` 为真时追加 `refs/tags/` 以支持标签自动跟随。
+pub fn ref_prefixes_for_refspecs(refspecs: &[&str], include_tags: bool) -> Vec<String> {
+    let mut out: Vec<String> = vec!["HEAD".to_string()];
+    let mut push = |p: String| {
+        if !p.is_empty() && !out.contains(&p) {
+            out.push(p);
+        }
+    };
+    for spec in refspecs {
+        let spec = spec.trim().trim_start_matches('+');
+        if spec.starts_with('^') {
+            continue;
+        }
+        let src = spec.split(':').next().unwrap_or("");
+        let src = match src.find('*') {
+            Some(i) => &src[..i],
+            None => src,
+        };
+        if src.is_empty() {
+            continue;
+        }
+        if src.starts_with("refs/") || src == "HEAD" {
+            push(src.to_string());
+        } else {
+            push(format!("refs/heads/{src}"));
+            push(format!("refs/tags/{src}"));
+        }
+    }
+    if include_tags {
+        push("refs/tags/".to_string());
+    }
+    out
+}
+
+/// 编码一个 pkt-line（长度前缀包含自身 4 字节）。
+pub fn pkt_line(data: &[u8]) -> Vec<u8> {
+    let mut out = format!("{:04x}", data.len() + 4).into_bytes();
+    out.extend_from_slice(data);
+    out
+}
+
+/// 解析出的单个 pkt-line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pkt<'a> {
+    Flush,
+    Delim,
+    ResponseEnd,
+    Data(&'a [u8]),
+}
+
+/// 从缓冲区头部解析一个 pkt-line，返回 (pkt, 消耗字节数)；数据不完整时返回 `Ok(None)`。
+pub fn next_pkt(buf: &[u8]) -> Result<Option<(Pkt<'_>, usize)>, String> {
+    if buf.len() < 4 {
+        return Ok(None);
+    }
+    let len_str = std::str::from_utf8(&buf[..4]).map_err(|_| "invalid pkt-line length")?;
+    let len = usize::from_str_radix(len_str, 16)
+        .map_err(|_| format!("invalid pkt-line length: {len_str:?}"))?;
+    match len {
+        0 => Ok(Some((Pkt::Flush, 4))),
+        1 => Ok(Some((Pkt::Delim, 4))),
+        2 => Ok(Some((Pkt::ResponseEnd, 4))),
+        3 => Err("invalid pkt-line length: 3".to_string()),
+        _ if buf.len() < len => Ok(None),
+        _ => Ok(Some((Pkt::Data(&buf[4..len]), len))),
+    }
+}
+
+fn pkt_text(data: &[u8]) -> &str {
+    std::str::from_utf8(data)
+        .unwrap_or("")
+        .trim_end_matches('\n')
+}
+
+/// 将完整的响应体切分为 pkt-line 序列。
+fn split_pkts(body: &[u8]) -> Result<Vec<Pkt<'_>>, String> {
+    let mut out = Vec::new();
+    let mut rest = body;
+    while !rest.is_empty() {
+        match next_pkt(rest)? {
+            Some((pkt, used)) => {
+                out.push(pkt);
+                rest = &rest[used..];
+            }
+            None => return Err("truncated pkt-line".to_string()),
+        }
+    }
+    Ok(out)
+}
+
+/// 服务端 v2 能力通告
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServerCapabilities {
+    pub ls_refs: bool,
+    /// `fetch=<features>` 中声明的特性（如 `shallow`、`filter`、`wait-for-done`）
+    pub fetch: Option<Vec<String>>,
+    pub object_format: Option<String>,
+    pub agent: Option<String>,
+}
+
+impl ServerCapabilities {
+    pub fn supports_fetch(&self) -> bool {
+        self.fetch.is_some()
+    }
+
+    pub fn fetch_feature(&self, name: &str) -> bool {
+        self.fetch
+            .as_ref()
+            .is_some_and(|f| f.iter().any(|x| x == name))
+    }
+}
+
+/// 解析 `info/refs` 响应。服务端以 `version 2` 应答时返回其能力；返回 `None` 表示仍是 v0 通告。
+pub fn parse_capability_advertisement(body: &[u8]) -> Result<Option<ServerCapabilities>, String> {
+    let pkts = split_pkts(body)?;
+    let mut iter = pkts.into_iter().peekable();
+    // smart HTTP 可能先发送 "# service=git-upload-pack" 与 flush
+    if let Some(Pkt::Data(d)) = iter.peek() {
+        if d.starts_with(b"# service=") {
+            iter.next();
+            if iter.peek() == Some(&Pkt::Flush) {
+                iter.next();
+            }
+        }
+    }
+    match iter.next() {
+        Some(Pkt::Data(d)) if pkt_text(d) == "version 2" => {}
+        _ => return Ok(None),
+    }
+    let mut caps = ServerCapabilities::default();
+    for pkt in iter {
+        let Pkt::Data(d) = pkt else { break };
+        let line = pkt_text(d);
+        let (key, value) = match line.split_once('=') {
+            Some((k, v)) => (k, Some(v)),
+            None => (line, None),
+        };
+        match key {
+            "ls-refs" => caps.ls_refs = true,
+            "fetch" => {
+                caps.fetch = Some(
+                    value
+                        .unwrap_or("")
+                        .split_whitespace()
+                        .map(str::to_string)
+                        .collect(),
+                )
+            }
+            "object-format" => caps.object_format = value.map(str::to_string),
+            "agent" => caps.agent = value.map(str::to_string),
+            _ => {}
+        }
+    }
+    Ok(Some(caps))
+}
+
+fn command_header(command: &str, caps: &ServerCapabilities) -> Vec<u8> {
+    let mut out = pkt_line(format!("command={command}\n").as_bytes());
+    out.extend(pkt_line(format!("agent={AGENT}\n").as_bytes()));
+    if let Some(fmt) = caps.object_format.as_deref() {
+        out.extend(pkt_line(format!("object-format={fmt}\n").as_bytes()));
+    }
+    out.extend_from_slice(DELIM);
+    out
+}
+
+/// 构造 `command=ls-refs` 请求体：请求 symref 与 peeled 信息，并按前缀过滤。
+pub fn build_ls_refs_request(caps: &ServerCapabilities, prefixes: &[String]) -> Vec<u8> {
+    let mut out = command_header("ls-refs", caps);
+    out.extend(pkt_line(b"symrefs\n"));
+    out.extend(pkt_line(b"peel\n"));
+    for p in prefixes {
+        out.extend(pkt_line(format!("ref-prefix {p}\n").as_bytes()));
+    }
+    out.extend_from_slice(FLUSH);
+    out
+}
+
+/// ls-refs 返回的单个引用
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdvertisedRef {
+    pub oid: String,
+    pub name: String,
+    pub symref_target: Option<String>,
+    pub peeled: Option<String>,
+}
+
+/// 解析 ls-refs 响应：`<oid> <name>[ symref-target:<t>][ peeled:<oid>]`，以 flush 结束。
+pub fn parse_ls_refs_response(body: &[u8]) -> Result<Vec<AdvertisedRef>, String> {
+    let mut refs = Vec::new();
+    for pkt in split_pkts(body)? {
+        let Pkt::Data(d) = pkt else { break };
+        let line = pkt_text(d);
+        if let Some(msg) = line.strip_prefix("ERR ") {
+            return Err(format!("ls-refs: {msg}"));
+        }
+        let mut parts = line.split(' ');
+        let (Some(oid), Some(name)) = (parts.next(), parts.next()) else {
+            return Err(format!("ls-refs: malformed line {line:?}"));
+        };
+        let mut r = AdvertisedRef {
+            oid: oid.to_string(),
+            name: name.to_string(),
+            symref_target: None,
+            peeled: None,
+        };
+        for attr in parts {
+            if let Some(t) = attr.strip_prefix("symref-target:") {
+                r.symref_target = Some(t.to_string());
+            } else if let Some(p) = attr.strip_prefix("peeled:") {
+                r.peeled = Some(p.to_string());
+            }
+        }
+        refs.push(r);
+    }
+    Ok(refs)
+}
+
+/// 合成交给 libgit2 的 v0 能力列表。只声明转写层能忠实还原的能力（不含 no-done）。
+fn v0_capabilities(refs: &[AdvertisedRef], caps: &ServerCapabilities) -> String {
+    let mut out = vec![
+        "multi_ack_detailed",
+        "side-band-64k",
+        "thin-pack",
+        "ofs-delta",
+        "shallow",
+        "no-progress",
+        "include-tag",
+        "allow-tip-sha1-in-want",
+        "allow-reachable-sha1-in-want",
+    ]
+    .into_iter()
+    .map(str::to_string)
+    .collect::<Vec<_>>();
+    if caps.fetch_feature("filter") {
+        out.push("filter".to_string());
+    }
+    for r in refs {
+        if let Some(target) = r.symref_target.as_deref() {
+            out.push(format!("symref={}:{}", r.name, target));
+        }
+    }
+    if let Some(fmt) = caps.object_format.as_deref() {
+        out.push(format!("object-format={fmt}"));
+    }
+    out.push(format!("agent={AGENT}"));
+    out.join(" ")
+}
+
+/// 把 ls-refs 结果合成为 v0 smart HTTP 通告（含 `# service` 头，能力附在首条引用之后）。
+pub fn synthesize_v0_advertisement(refs: &[AdvertisedRef], caps: &ServerCapabilities) -> Vec<u8> {
+    let mut out = pkt_line(b"# service=git-upload-pack\n");
+    out.extend_from_slice(FLUSH);
+    let capabilities = v0_capabilities(refs, caps);
+    if refs.is_empty() {
+        let zero = "0".repeat(40);
+        out.extend(pkt_line(
+            format!("{zero} capabilities^{{}}\0{capabilities}\n").as_bytes(),
+        ));
+    }
+    for (i, r) in refs.iter().enumerate() {
+        let line = if i == 0 {
+            format!("{} {}\0{}\n", r.oid, r.name, capabilities)
+        } else {
+            format!("{} {}\n", r.oid, r.name)
+        };
+        out.extend(pkt_line(line.as_bytes()));
+        if let Some(peeled) = r.peeled.as_deref() {
+            out.extend(pkt_line(format!("{} {}^{{}}\n", peeled, r.name).as_bytes()));
+        }
+    }
+    out.extend_from_slice(FLUSH);
+    out
+}
+
+/// v0 upload-pack 请求转写后的 v2 fetch 请求
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchRequest {
+    pub body: Vec<u8>,
+    /// 客户端是否已发送 `done`（本轮应直接拿到 packfile）
+    pub done: bool,
+}
+
+/// 将 libgit2 发出的 v0 upload-pack 请求转写为 v2 `command=fetch`。
+///
+/// 返回 `Ok(None)` 表示该请求无法无损转写（浅克隆相关参数、服务端不支持的 filter），调用方应回落 v0。
+pub fn translate_fetch_request(
+    v0: &[u8],
+    caps: &ServerCapabilities,
+) -> Result<Option<FetchRequest>, String> {
+    if !caps.supports_fetch() {
+        return Ok(None);
+    }
+    let mut client_caps: Vec<String> = Vec::new();
+    let mut wants: Vec<String> = Vec::new();
+    let mut haves: Vec<String> = Vec::new();
+    let mut filter: Option<String> = None;
+    let mut done = false;
+    for pkt in split_pkts(v0)? {
+        let Pkt::Data(d) = pkt else { continue };
+        let line = pkt_text(d);
+        if let Some(rest) = line.strip_prefix("want ") {
+            let mut parts = rest.split(' ');
+            if let Some(oid) = parts.next() {
+                wants.push(oid.to_string());
+            }
+            if wants.len() == 1 {
+                client_caps.extend(parts.map(str::to_string));
+            }
+        } else if let Some(oid) = line.strip_prefix("have ") {
+            haves.push(oid.to_string());
+        } else if let Some(spec) = line.strip_prefix("filter ") {
+            filter = Some(spec.to_string());
+        } else if line == "done" {
+            done = true;
+        } else if line.starts_with("shallow ") || line.starts_with("deepen") {
+            return Ok(None);
+        } else {
+            return Err(format!("upload-pack request: unexpected line {line:?}"));
+        }
+    }
+    if filter.is_some() && !caps.fetch_feature("filter") {
+        return Ok(None);
+    }
+
+    let mut body = command_header("fetch", caps);
+    for arg in ["thin-pack", "ofs-delta", "include-tag", "no-progress"] {
+        if client_caps.iter().any(|c| c == arg) {
+            body.extend(pkt_line(format!("{arg}\n").as_bytes()));
+        }
+    }
+    for oid in &wants {
+        body.extend(pkt_line(format!("want {oid}\n").as_bytes()));
+    }
+    if let Some(spec) = filter.as_deref() {
+        body.extend(pkt_line(format!("filter {spec}\n").as_bytes()));
+    }
+    for oid in &haves {
+        body.extend(pkt_line(format!("have {oid}\n").as_bytes()));
+    }
+    if done {
+        body.extend(pkt_line(b"done\n"));
+    } else if caps.fetch_feature("wait-for-done") {
+        body.extend(pkt_line(b"wait-for-done\n"));
+    }
+    body.extend_from_slice(FLUSH);
+    Ok(Some(FetchRequest { body, done }))
+}
+
+/// 同一传输内跨请求保存的 v2 协商状态（libgit2 的每个 smart 请求都是独立的流）。
+#[derive(Debug, Default)]
+pub struct ProtocolV2Session {
+    /// 服务端以 v2 应答 info/refs 后记录其能力；为 `None` 时 upload-pack 走 v0
+    pub caps: Option<ServerCapabilities>,
+    /// 最近一次被服务端确认的共同提交
+    pub last_common: Option<String>,
+    /// 服务端在未收到 done 时（ready）提前发送的 packfile，留待客户端发送 done 时交付
+    pub pending_pack: Option<Vec<u8>>,
+}
+
+impl ProtocolV2Session {
+    /// 客户端发送 done 时若已有提前收到的 pack，则直接合成 v0 响应，无需再次请求。
+    pub fn take_pending_response(&mut self) -> Option<Vec<u8>> {
+        let pack = self.pending_pack.take()?;
+        let mut out = final_ack(self.last_common.as_deref());
+        out.extend(pack);
+        Some(out)
+    }
+}
+
+/// done 之后、pack 之前的最终应答：有共同提交时为 `ACK <oid>`，否则 `NAK`。
+fn final_ack(last_common: Option<&str>) -> Vec<u8> {
+    match last_common {
+        Some(oid) => pkt_line(format!("ACK {oid}\n").as_bytes()),
+        None => pkt_line(b"NAK\n"),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Header,
+    Acknowledgments,
+    Skipped,
+    Packfile,
+    End,
+}
+
+/// 流式把 v2 fetch 响应还原为 v0（multi_ack_detailed + side-band-64k）响应。
+#[derive(Debug)]
+pub struct FetchResponseTranslator {
+    done: bool,
+    section: Section,
+    buf: Vec<u8>,
+    last_common: Option<String>,
+    /// 非 done 轮次中提前到达的 packfile（side-band pkt 原样保存）
+    stash: Option<Vec<u8>>,
+}
+
+impl FetchResponseTranslator {
+    pub fn new(done: bool, last_common: Option<String>) -> Self {
+        Self {
+            done,
+            section: Section::Header,
+            buf: Vec::new(),
+            last_common,
+            stash: None,
+        }
+    }
+
+    /// 输入一段响应字节，返回可立即交给 libgit2 的 v0 字节（可能为空）。
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<Vec<u8>, String> {
+        self.buf.extend_from_slice(chunk);
+        let mut out = Vec::new();
+        let mut consumed = 0;
+        while self.section != Section::End {
+            let Some((pkt, used)) = next_pkt(&self.buf[consumed..])? else {
+                break;
+            };
+            let raw = consumed..consumed + used;
+            consumed += used;
+            match (self.section, pkt) {
+                (_, Pkt::ResponseEnd) => self.section = Section::End,
+                (Section::Header, Pkt::Data(d)) => {
+                    self.section = match pkt_text(d) {
+                        "acknowledgments" => Section::Acknowledgments,
+                        "packfile" => {
+                            if self.done {
+                                out.extend(final_ack(self.last_common.as_deref()));
+                            } else {
+                                self.stash = Some(Vec::new());
+                            }
+                            Section::Packfile
+                        }
+                        "shallow-info" | "wanted-refs" | "packfile-uris" => Section::Skipped,
+                        other => return Err(format!("fetch: unexpected section {other:?}")),
+                    };
+                }
+                (Section::Header, _) => {
+                    return Err("fetch: missing section header".to_string());
+                }
+                (Section::Acknowledgments, Pkt::Data(d)) => {
+                    let line = pkt_text(d);
+                    if let Some(oid) = line.strip_prefix("ACK ") {
+                        out.extend(pkt_line(format!("ACK {oid} common\n").as_bytes()));
+                        self.last_common = Some(oid.to_string());
+                    } else if line == "ready" {
+                        if let Some(oid) = self.last_common.as_deref() {
+                            out.extend(pkt_line(format!("ACK {oid} ready\n").as_bytes()));
+                        }
+                    } else if line != "NAK" {
+                        return Err(format!("fetch: unexpected acknowledgment {line:?}"));
+                    }
+                }
+                (Section::Acknowledgments, delim_or_flush) => {
+                    // 非 done 轮次以 NAK 结束（multi_ack 语义）
+                    out.extend(pkt_line(b"NAK\n"));
+                    self.section = match delim_or_flush {
+                        Pkt::Delim => Section::Header,
+                        _ => Section::End,
+                    };
+                }
+                (Section::Skipped, Pkt::Data(_)) => {}
+                (Section::Skipped, Pkt::Delim) => self.section = Section::Header,
+                (Section::Skipped, _) => self.section = Section::End,
+                (Section::Packfile, pkt) => {
+                    let bytes = &self.buf[raw];
+                    match self.stash.as_mut() {
+                        Some(stash) => stash.extend_from_slice(bytes),
+                        None => out.extend_from_slice(bytes),
+                    }
+                    if pkt == Pkt::Flush {
+                        self.section = Section::End;
+                    }
+                }
+                (Section::End, _) => {}
+            }
+        }
+        self.buf.drain(..consumed);
+        Ok(out)
+    }
+
+    /// 响应结束：把协商状态写回会话；响应不完整时返回错误。
+    pub fn finish(self, session: &mut ProtocolV2Session) -> Result<(), String> {
+        session.last_common = self.last_common;
+        if self.stash.is_some() {
+            session.pending_pack = self.stash;
+        }
+        if self.section != Section::End || !self.buf.is_empty() {
+            return Err("fetch: truncated protocol v2 response".to_string());
+        }
+        Ok(())
+    }
+}
@@ -1,25 +1,45 @@
 use std::io::{Read, Write};
-use std::net::TcpStream;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use git2::Error;
+use hyper::body::HttpBody as _;
+use hyper::header::{HeaderValue, CONNECTION, CONTENT_TYPE, TRANSFER_ENCODING, WWW_AUTHENTICATE};
+use hyper::{Body, Request, Response, Version};
 use rand::seq::SliceRandom;
-use rustls::StreamOwned;
-use rustls::{ClientConfig, ClientConnection, ServerName};
 
 use crate::core::config::loader::load_or_init;
 use crate::core::config::model::AppConfig;
-use crate::core::tls::util::{proxy_present, set_last_good_sni};
-use crate::core::tls::verifier::{create_client_config, create_client_config_with_expected_name};
+use crate::core::ip_pool::{self, IpOutcome};
+use crate::core::proxy::routing::proxy_in_use;
+use crate::core::tls::client_cert::describe_handshake_error;
+use crate::core::tls::util::set_last_good_sni;
 
 use super::auth::get_push_auth_header;
-use super::util::{
-    find_crlf, find_double_crlf, log_body_preview, parse_http_header_first_line_and_host,
+use super::pool::{
+    connect_blocking, connection_pool, shared_client_config, transport_runtime, HttpVersion,
+    PoolKey, PooledConnection,
 };
-use super::{HttpOp, TransferKind};
+use super::protocol_v2::{
+    build_ls_refs_request, get_ls_refs_prefixes, parse_capability_advertisement,
+    parse_ls_refs_response, synthesize_v0_advertisement, translate_fetch_request,
+    FetchResponseTranslator, ProtocolV2Session, PROTOCOL_HEADER, PROTOCOL_V2,
+};
+use super::util::{find_double_crlf, log_body_preview, parse_http_header_first_line_and_host};
+use super::HttpOp;
 use crate::core::git::transport::metrics::tl_mark_first_byte;
+use crate::core::git::transport::throttle::throttle_transfer;
+
+/// 归还连接前等待其回到空闲状态的上限
+const CHECKIN_READY_WAIT: Duration = Duration::from_millis(500);
+/// 上报吞吐量所需的最小响应体大小；过小的响应只反映往返延迟
+const MIN_THROUGHPUT_SAMPLE_BYTES: u64 = 256 * 1024;
 
+/// 单个 smart 协议请求的流：从连接池借出连接，按 git2 的同步 Read/Write 接口
+/// 发送请求并逐块读取（由 hyper 完成分块 / 定长解码的）响应体；读完后归还连接。
 pub(super) struct SniffingStream {
-    pub(super) inner: StreamOwned<ClientConnection, TcpStream>,
+    pub(super) conn: Option<PooledConnection>,
     pub(super) host: String,
     pub(super) port: u16,
     pub(super) used_fake_sni: bool,
@@ -27,54 +47,44 @@ pub(super) struct SniffingStream {
     pub(super) path: String,
     pub(super) op: HttpOp,
     pub(super) cfg: AppConfig,
-    // 嗅探缓冲（仅用于日志，最多 2KB）
-    pub(super) wrote_buf: Vec<u8>,
-    pub(super) wrote_logged: bool,
-    pub(super) read_buf: Vec<u8>,
-    pub(super) read_logged: bool,
     pub(super) read_body_logged: bool,
     // POST 缓冲
     pub(super) post_buf: Vec<u8>,
-    pub(super) posted: bool,
-    // 请求发送标记（GET）
+    // 请求已发送且响应头已处理
     pub(super) requested: bool,
-    // 响应解析状态
-    pub(super) headers_parsed: bool,
-    pub(super) header_buf: Vec<u8>,
-    // 传输编码
-    pub(super) transfer: Option<TransferKind>,
-    // 输入原始缓冲（已去掉头部后剩余的 body 原始字节）
-    pub(super) inbuf: Vec<u8>,
+    // 响应体及连接是否可复用（HTTP/2，或 HTTP/1.1 且无 `Connection: close`）
+    pub(super) body: Option<Body>,
+    pub(super) keep_alive: bool,
     // 解码后可供上层读取的字节
     pub(super) decoded: Vec<u8>,
-    // 分块状态
-    pub(super) chunk_remaining: usize,
-    pub(super) reading_chunk_size: bool,
-    pub(super) chunk_line: Vec<u8>,
-    pub(super) trailer_mode: bool,
-    // 内容长度剩余
-    pub(super) content_remaining: usize,
     // EOF
     pub(super) eof: bool,
     // 403 轮换保护：仅允许轮换一次，避免无限循环
     pub(super) rotated_once: bool,
     // 若解析到致命 HTTP 状态（如 401 on receive-pack），优先通过 read() 返回该错误
     pub(super) fatal_error: Option<String>,
+    // 协议 v2：传输级协商状态，以及当前 fetch 响应的 v2 -> v0 转写器
+    pub(super) v2: Arc<Mutex<ProtocolV2Session>>,
+    pub(super) translator: Option<FetchResponseTranslator>,
+    // 响应体已读取字节数与首个数据块到达时间，用于被动吞吐量统计
+    pub(super) body_bytes: u64,
+    pub(super) body_started: Option<Instant>,
 }
 
 impl SniffingStream {
     pub(super) fn new(
-        inner: StreamOwned<ClientConnection, TcpStream>,
+        conn: PooledConnection,
         host: String,
         port: u16,
-        used_fake_sni: bool,
-        current_sni: String,
         path: String,
         op: HttpOp,
         cfg: AppConfig,
+        v2: Arc<Mutex<ProtocolV2Session>>,
     ) -> Self {
+        let used_fake_sni = conn.used_fake();
+        let current_sni = conn.key().sni.clone();
         Self {
-            inner,
+            conn: Some(conn),
             host,
             port,
             used_fake_sni,
@@ -82,508 +92,500 @@ impl SniffingStream {
             path,
             op,
             cfg,
-            wrote_buf: Vec::with_capacity(2048),
-            wrote_logged: false,
-            read_buf: Vec::with_capacity(2048),
-            read_logged: false,
             read_body_logged: false,
             post_buf: Vec::new(),
-            posted: false,
             requested: false,
-            headers_parsed: false,
-            header_buf: Vec::new(),
-            transfer: None,
-            inbuf: Vec::new(),
+            body: None,
+            keep_alive: false,
             decoded: Vec::new(),
-            chunk_remaining: 0,
-            reading_chunk_size: true,
-            chunk_line: Vec::new(),
-            trailer_mode: false,
-            content_remaining: 0,
             eof: false,
             rotated_once: false,
             fatal_error: None,
+            v2,
+            translator: None,
+            body_bytes: 0,
+            body_started: None,
         }
     }
 
-    fn try_log_request(&mut self) {
-        if self.wrote_logged {
-            return;
-        }
-        let cap = 2048usize;
-        let upto = self.wrote_buf.len().min(cap);
-        let slice = &self.wrote_buf[..upto];
-        if let Some(pos) = find_double_crlf(slice) {
-            let header = &slice[..pos];
-            let (line1, host, has_auth) = parse_http_header_first_line_and_host(header);
-            tracing::debug!(target="git.transport.http", host=%self.host, request_line=%line1, host_header=%host, auth_header_present=%has_auth, "http request");
-            if let Ok(text) = std::str::from_utf8(header) {
-                let trimmed = text.replace("\r\n", " | ");
-                tracing::debug!(target="git.transport.http", host=%self.host, headers=%trimmed, "http request headers");
-            }
-            self.wrote_logged = true;
-        } else if self.wrote_buf.len() >= cap {
-            tracing::debug!(target="git.transport.http", host=%self.host, "http request headers not complete within sniff cap");
-            self.wrote_logged = true;
-        }
-    }
-
-    fn try_log_response(&mut self) {
-        if self.read_logged {
-            return;
-        }
-        let cap = 2048usize;
-        let upto = self.read_buf.len().min(cap);
-        let slice = &self.read_buf[..upto];
-        if let Some(pos) = find_double_crlf(slice) {
-            let header = &slice[..pos];
-            let (status, _host, _auth) = parse_http_header_first_line_and_host(header);
-            tracing::debug!(target="git.transport.http", host=%self.host, status_line=%status, "http response");
-            if let Ok(text) = std::str::from_utf8(header) {
-                let trimmed = text.replace("\r\n", " | ");
-                tracing::debug!(target="git.transport.http", host=%self.host, headers=%trimmed, "http response headers");
-            }
-            self.read_logged = true;
-        } else if self.read_buf.len() >= cap {
-            tracing::debug!(target="git.transport.http", host=%self.host, "http response headers not complete within sniff cap");
-            self.read_logged = true;
-        }
-    }
-
-    fn ensure_request_sent(&mut self) -> std::io::Result<()> {
-        match self.op {
-            HttpOp::InfoRefsUpload | HttpOp::InfoRefsReceive => {
-                if !self.requested {
-                    let service = match self.op {
-                        HttpOp::InfoRefsUpload => "git-upload-pack",
-                        HttpOp::InfoRefsReceive => "git-receive-pack",
-                        _ => unreachable!(),
-                    };
-                    let path = format!("{}/info/refs?service={}", self.path, service);
-                    let host_hdr = if self.port == 443 {
-                        self.host.clone()
-                    } else {
-                        format!("{}:{}", self.host, self.port)
-                    };
-                    // 仅在 receive-pack 的 info/refs 阶段尝试注入 Authorization
-                    let auth_line = if matches!(self.op, HttpOp::InfoRefsReceive) {
-                        get_push_auth_header().map(|v| format!("Authorization: {v}\r\n"))
-                    } else {
-                        None
-                    };
-                    let req = if let Some(al) = auth_line.as_deref() {
-                        format!(
-                            concat!(
-                                "GET {} HTTP/1.1\r\n",
-                                "Host: {}\r\n",
-                                "User-Agent: git/2.46.0\r\n",
-                                "Accept: */*\r\n",
-                                "Accept-Encoding: identity\r\n",
-                                "Pragma: no-cache\r\n",
-                                "Cache-Control: no-cache\r\n",
-                                "{}",
-                                "Connection: close\r\n",
-                                "\r\n"
-                            ),
-                            path, host_hdr, al
-                        )
-                    } else {
-                        format!(
-                            concat!(
-                                "GET {} HTTP/1.1\r\n",
-                                "Host: {}\r\n",
-                                "User-Agent: git/2.46.0\r\n",
-                                "Accept: */*\r\n",
-                                "Accept-Encoding: identity\r\n",
-                                "Pragma: no-cache\r\n",
-                                "Cache-Control: no-cache\r\n",
-                                "Connection: close\r\n",
-                                "\r\n"
-                            ),
-                            path, host_hdr
-                        )
-                    };
-                    tracing::debug!(target="git.transport.http", host=%self.host, request_line=%format!("GET {} HTTP/1.1", path), "send GET info/refs");
-                    if auth_line.is_some() {
-                        tracing::debug!(target="git.transport.http", host=%self.host, auth_injected=true, "authorization header injected for info/refs (receive-pack)");
-                    }
-                    self.wrote_buf.extend_from_slice(req.as_bytes());
-                    self.inner.write_all(req.as_bytes())?;
-                    self.inner.flush()?;
-                    self.try_log_request();
-                    self.requested = true;
-                }
-            }
-            HttpOp::UploadPack | HttpOp::ReceivePack => {
-                if !self.posted {
-                    self.send_post()?;
-                }
-            }
-        }
-        Ok(())
-    }
-
-    fn send_post(&mut self) -> std::io::Result<()> {
-        let (path, accept, content_type) = match self.op {
+    fn build_request(
+        &self,
+        op: HttpOp,
+        body: &[u8],
+        protocol_v2: bool,
+    ) -> std::io::Result<Request<Body>> {
+        let (method, path, accept, content_type, service) = match op {
+            HttpOp::InfoRefsUpload => (
+                "GET",
+                format!("{}/info/refs?service=git-upload-pack", self.path),
+                "*/*",
+                None,
+                "info/refs",
+            ),
+            HttpOp::InfoRefsReceive => (
+                "GET",
+                format!("{}/info/refs?service=git-receive-pack", self.path),
+                "*/*",
+                None,
+                "info/refs",
+            ),
             HttpOp::UploadPack => (
+                "POST",
                 format!("{}/git-upload-pack", self.path),
                 "application/x-git-upload-pack-result",
-                "application/x-git-upload-pack-request",
+                Some("application/x-git-upload-pack-request"),
+                "git-upload-pack",
             ),
             HttpOp::ReceivePack => (
+                "POST",
                 format!("{}/git-receive-pack", self.path),
                 "application/x-git-receive-pack-result",
-                "application/x-git-receive-pack-request",
+                Some("application/x-git-receive-pack-request"),
+                "git-receive-pack",
             ),
-            _ => unreachable!(),
         };
         let host_hdr = if self.port == 443 {
             self.host.clone()
         } else {
             format!("{}:{}", self.host, self.port)
         };
-        let len = self.post_buf.len();
-        let auth_line = if matches!(self.op, HttpOp::ReceivePack) {
-            get_push_auth_header().map(|v| format!("Authorization: {v}\r\n"))
+        // 仅在 receive-pack 阶段（info/refs 与 POST）尝试注入 Authorization
+        let auth = if matches!(op, HttpOp::InfoRefsReceive | HttpOp::ReceivePack) {
+            get_push_auth_header()
         } else {
             None
         };
-        let headers = if let Some(al) = auth_line.as_deref() {
-            format!(
-                concat!(
-                    "POST {} HTTP/1.1\r\n",
-                    "Host: {}\r\n",
-                    "User-Agent: git/2.46.0\r\n",
-                    "Accept: {}\r\n",
-                    "Content-Type: {}\r\n",
-                    "Content-Length: {}\r\n",
-                    "Accept-Encoding: identity\r\n",
-                    "Pragma: no-cache\r\n",
-                    "Cache-Control: no-cache\r\n",
-                    "{}",
-                    "Connection: close\r\n",
-                    "\r\n"
-                ),
-                path, host_hdr, accept, content_type, len, al
-            )
+        let version = self.http_version();
+        // HTTP/2 以 :authority 伪首部代替 Host，需要绝对 URI
+        let mut builder = match version {
+            HttpVersion::Http1 => Request::builder()
+                .uri(path.as_str())
+                .version(Version::HTTP_11)
+                .header("Host", host_hdr),
+            HttpVersion::Http2 => Request::builder()
+                .uri(format!("https://{host_hdr}{path}"))
+                .version(Version::HTTP_2),
+        };
+        builder = builder
+            .method(method)
+            .header("User-Agent", "git/2.46.0")
+            .header("Accept", accept);
+        if let Some(ct) = content_type {
+            builder = builder
+                .header("Content-Type", ct)
+                .header("Content-Length", body.len());
+        }
+        if protocol_v2 {
+            builder = builder.header(PROTOCOL_HEADER, PROTOCOL_V2);
+        }
+        builder = builder
+            .header("Accept-Encoding", "identity")
+            .header("Pragma", "no-cache")
+            .header("Cache-Control", "no-cache");
+        if let Some(value) = auth.as_deref() {
+            builder = builder.header("Authorization", value);
+        }
+        let content_length = body.len();
+        let body = if content_type.is_some() {
+            Body::from(body.to_vec())
         } else {
-            format!(
-                concat!(
-                    "POST {} HTTP/1.1\r\n",
-                    "Host: {}\r\n",
-                    "User-Agent: git/2.46.0\r\n",
-                    "Accept: {}\r\n",
-                    "Content-Type: {}\r\n",
-                    "Content-Length: {}\r\n",
-                    "Accept-Encoding: identity\r\n",
-                    "Pragma: no-cache\r\n",
-                    "Cache-Control: no-cache\r\n",
-                    "Connection: close\r\n",
-                    "\r\n"
-                ),
-                path, host_hdr, accept, content_type, len
-            )
+            Body::empty()
         };
-        tracing::debug!(target="git.transport.http", host=%self.host, request_line=%format!("POST {} HTTP/1.1", path), content_length=%len, "send POST git-upload-pack");
-        if auth_line.is_some() {
-            tracing::debug!(target="git.transport.http", host=%self.host, auth_injected=true, "authorization header injected for receive-pack POST");
-        }
-        self.wrote_buf.extend_from_slice(headers.as_bytes());
-        self.inner.write_all(headers.as_bytes())?;
-        if len > 0 {
-            self.inner.write_all(&self.post_buf)?;
+        let req = builder
+            .body(body)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+        tracing::debug!(target="git.transport.http", host=%self.host, request_line=%format!("{method} {path} {}", version.as_str()), content_length=%content_length, protocol_v2=%protocol_v2, "send {}", service);
+        if auth.is_some() {
+            tracing::debug!(target="git.transport.http", host=%self.host, auth_injected=true, "authorization header injected ({})", service);
+        }
+        Ok(req)
+    }
+
+    fn http_version(&self) -> HttpVersion {
+        self.conn
+            .as_ref()
+            .map_or(HttpVersion::Http1, PooledConnection::version)
+    }
+
+    fn log_request(&self, req: &Request<Body>) {
+        let mut head = format!(
+            "{} {} {}\r\n",
+            req.method(),
+            req.uri(),
+            self.http_version().as_str()
+        );
+        for (k, v) in req.headers() {
+            head.push_str(&format!("{}: {}\r\n", k, v.to_str().unwrap_or("<binary>")));
+        }
+        head.push_str("\r\n");
+        let bytes = head.as_bytes();
+        let header = &bytes[..find_double_crlf(bytes).unwrap_or(bytes.len())];
+        let (line1, host, has_auth) = parse_http_header_first_line_and_host(header);
+        tracing::debug!(target="git.transport.http", host=%self.host, request_line=%line1, host_header=%host, auth_header_present=%has_auth, reused=%self.conn.as_ref().is_some_and(PooledConnection::is_reused), http_version=%self.http_version().as_str(), "http request");
+    }
+
+    /// 发送请求并等待响应头。若复用的池连接已被对端关闭，改用同一 (SNI, IP) 新建连接重试一次。
+    fn dispatch(
+        &mut self,
+        op: HttpOp,
+        body: &[u8],
+        protocol_v2: bool,
+    ) -> std::io::Result<Response<Body>> {
+        let rt = transport_runtime()?;
+        loop {
+            let req = self.build_request(op, body, protocol_v2)?;
+            self.log_request(&req);
+            let conn = self
+                .conn
+                .as_mut()
+                .ok_or_else(|| std::io::Error::other("http connection missing"))?;
+            let reused = conn.is_reused();
+            match rt.block_on(conn.send_request(req)) {
+                Ok(resp) => return Ok(resp),
+                Err(e) if reused => {
+                    tracing::debug!(target="git.transport.http", host=%self.host, error=%e, "pooled connection stale; reconnecting");
+                    let key = conn.key().clone();
+                    self.conn = Some(self.connect_same(key)?);
+                }
+                Err(e) => {
+                    // TLS 1.3 下服务端对客户端证书的拒绝告警在首次读写时才到达
+                    let detail = describe_handshake_error(&self.host, &e.to_string());
+                    return Err(std::io::Error::other(format!("http request: {detail}")));
+                }
+            }
         }
-        self.inner.flush()?;
-        self.posted = true;
-        self.try_log_request();
-        Ok(())
     }
 
-    fn parse_headers_and_setup(&mut self) -> std::io::Result<()> {
-        if self.headers_parsed {
+    /// 以相同的 (SNI, IP) 新建一条连接。
+    fn connect_same(&self, key: PoolKey) -> std::io::Result<PooledConnection> {
+        let expected = self.used_fake_sni.then_some(self.host.as_str());
+        let tls_cfg = shared_client_config(
+            &self.cfg.tls,
+            &self.host,
+            expected,
+            self.cfg.http.http2_enabled,
+        )?;
+        connect_blocking(key, self.used_fake_sni, tls_cfg, &self.cfg.proxy)
+    }
+
+    fn ensure_request_sent(&mut self) -> std::io::Result<()> {
+        if self.requested {
             return Ok(());
         }
-        loop {
-            if let Some(pos) = find_double_crlf(&self.header_buf) {
-                let header = self.header_buf[..pos].to_vec();
-                let remaining = self.header_buf[pos..].to_vec();
-                self.inbuf.extend_from_slice(&remaining);
-                let mut status_line = String::new();
-                let mut content_len: Option<usize> = None;
-                let mut is_chunked = false;
-                let mut content_type = String::new();
-                let mut status_code: Option<u16> = None;
-                let mut www_authenticate: Option<String> = None;
-                if let Ok(text) = std::str::from_utf8(&header) {
-                    for (i, line) in text.split("\r\n").enumerate() {
-                        if i == 0 {
-                            status_line = line.to_string();
-                            let parts: Vec<&str> = line.split_whitespace().collect();
-                            if parts.len() >= 2 {
-                                if let Ok(code) = parts[1].parse::<u16>() {
-                                    status_code = Some(code);
-                                }
-                            }
-                            continue;
-                        }
-                        let mut parts = line.splitn(2, ':');
-                        if let (Some(k), Some(v)) = (parts.next(), parts.next()) {
-                            let k_l = k.trim().to_ascii_lowercase();
-                            let v_t = v.trim();
-                            if k_l == "content-length" {
-                                if let Ok(n) = v_t.parse::<usize>() {
-                                    content_len = Some(n);
-                                }
-                            }
-                            if k_l == "transfer-encoding" && v_t.eq_ignore_ascii_case("chunked") {
-                                is_chunked = true;
-                            }
-                            if k_l == "content-type" {
-                                content_type = v_t.to_string();
-                            }
-                            if k_l == "www-authenticate" {
-                                www_authenticate = Some(v_t.to_string());
-                            }
-                        }
+        // 协议 v2：info/refs 携带 Git-Protocol 头；upload-pack 请求转写为 command=fetch
+        let mut protocol_v2 =
+            matches!(self.op, HttpOp::InfoRefsUpload) && self.cfg.http.protocol_v2_enabled;
+        let mut body = self.post_buf.clone();
+        let mut fetch_done: Option<bool> = None;
+        if matches!(self.op, HttpOp::UploadPack) {
+            let mut session = self.v2.lock().map_err(|_| lock_poisoned())?;
+            if let Some(caps) = session.caps.clone() {
+                match translate_fetch_request(&self.post_buf, &caps)
+                    .map_err(std::io::Error::other)?
+                {
+                    Some(req) if req.done && session.pending_pack.is_some() => {
+                        // 服务端上一轮已随 ready 发送 pack，直接交付，无需再次请求
+                        self.decoded = session.take_pending_response().unwrap_or_default();
+                        drop(session);
+                        tracing::debug!(target="git.transport.http", host=%self.host, "protocol v2: deliver packfile received with ready");
+                        self.requested = true;
+                        self.eof = true;
+                        self.keep_alive = true;
+                        self.release_connection();
+                        return Ok(());
+                    }
+                    Some(req) => {
+                        body = req.body;
+                        protocol_v2 = true;
+                        fetch_done = Some(req.done);
+                    }
+                    None => {
+                        tracing::debug!(target="git.transport.http", host=%self.host, "protocol v2: request not translatable, fall back to v0");
                     }
                 }
-                tracing::debug!(target="git.transport.http", host=%self.host, status_line=%status_line, content_type=%content_type, chunked=%is_chunked, content_length=?content_len, "http response parsed");
+            }
+        }
+        while !self.requested {
+            let resp = self.dispatch(self.op, &body, protocol_v2)?;
+            let status_code = resp.status().as_u16();
+            let headers = resp.headers();
+            let header_str =
+                |v: Option<&HeaderValue>| v.and_then(|v| v.to_str().ok()).unwrap_or("").to_string();
+            let content_type = header_str(headers.get(CONTENT_TYPE));
+            let is_chunked = header_str(headers.get(TRANSFER_ENCODING))
+                .to_ascii_lowercase()
+                .contains("chunked");
+            let www_authenticate = header_str(headers.get(WWW_AUTHENTICATE));
+            let connection_close =
+                header_str(headers.get(CONNECTION)).eq_ignore_ascii_case("close");
+            let status_line = format!(
+                "{:?} {} {}",
+                resp.version(),
+                status_code,
+                resp.status().canonical_reason().unwrap_or("")
+            );
+            tracing::debug!(target="git.transport.http", host=%self.host, status_line=%status_line, content_type=%content_type, chunked=%is_chunked, content_length=?resp.body().size_hint().exact(), keep_alive=%!connection_close, "http response parsed");
 
-                // 401 观测（通常表示需要认证）
-                if let Some(401) = status_code {
-                    let has_auth = get_push_auth_header().is_some();
-                    tracing::debug!(target="git.transport.http", host=%self.host, auth_present_in_request=%has_auth, "401 Unauthorized encountered");
-                    if matches!(self.op, HttpOp::InfoRefsReceive | HttpOp::ReceivePack) {
-                        let realm = www_authenticate.as_deref().unwrap_or("");
-                        let msg = if !realm.is_empty() {
-                            format!("HTTP 401 Unauthorized ({realm}). Authentication required for git-receive-pack; please provide credentials.")
-                        } else {
-                            "HTTP 401 Unauthorized. Authentication required for git-receive-pack; please provide credentials.".to_string()
-                        };
-                        self.fatal_error = Some(msg);
-                    }
+            // 401 观测（通常表示需要认证）
+            if status_code == 401 {
+                let has_auth = get_push_auth_header().is_some();
+                tracing::debug!(target="git.transport.http", host=%self.host, auth_present_in_request=%has_auth, "401 Unauthorized encountered");
+                if matches!(self.op, HttpOp::InfoRefsReceive | HttpOp::ReceivePack) {
+                    let msg = if !www_authenticate.is_empty() {
+                        format!("HTTP 401 Unauthorized ({www_authenticate}). Authentication required for git-receive-pack; please provide credentials.")
+                    } else {
+                        "HTTP 401 Unauthorized. Authentication required for git-receive-pack; please provide credentials.".to_string()
+                    };
+                    self.fatal_error = Some(msg);
                 }
-                // 403 自动 SNI 轮换（仅限 InfoRefs 阶段；开启 sni_rotate_on_403；每个流仅尝试一次）。
-                if matches!(self.op, HttpOp::InfoRefsUpload | HttpOp::InfoRefsReceive) {
-                    if let Some(403) = status_code {
-                        if self.cfg.http.sni_rotate_on_403 && !self.rotated_once {
-                            tracing::debug!(target="git.transport.http", host=%self.host, sni=%self.current_sni, "received 403, try rotate SNI and retry once");
-                            if let Ok((new_stream, new_used_fake, new_sni)) =
-                                Self::reconnect_with_rotated_sni(
-                                    &self.cfg,
-                                    &self.host,
-                                    self.port,
-                                    &self.current_sni,
-                                )
-                            {
-                                self.inner = new_stream;
-                                self.used_fake_sni = new_used_fake;
-                                self.current_sni = new_sni;
-                                self.rotated_once = true;
-                                self.header_buf.clear();
-                                self.inbuf.clear();
-                                self.decoded.clear();
-                                self.read_buf.clear();
-                                self.read_logged = false;
-                                self.wrote_buf.clear();
-                                self.wrote_logged = false;
-                                self.requested = false;
-                                self.headers_parsed = false;
-                                self.transfer = None;
-                                self.chunk_remaining = 0;
-                                self.reading_chunk_size = true;
-                                self.trailer_mode = false;
-                                self.content_remaining = 0;
-                                self.eof = false;
-                                self.ensure_request_sent()?;
-                                continue;
-                            }
-                        }
-                    }
-                    if let Some(code) = status_code {
-                        if (200..300).contains(&code) && self.used_fake_sni {
-                            set_last_good_sni(&self.host, &self.current_sni);
-                        }
+            }
+            // 403 自动 SNI 轮换（仅限 InfoRefs 阶段；开启 sni_rotate_on_403；每个流仅尝试一次）。
+            if matches!(self.op, HttpOp::InfoRefsUpload | HttpOp::InfoRefsReceive) {
+                if status_code == 403 && self.cfg.http.sni_rotate_on_403 && !self.rotated_once {
+                    tracing::debug!(target="git.transport.http", host=%self.host, sni=%self.current_sni, "received 403, try rotate SNI and retry once");
+                    let ip = self.conn.as_ref().and_then(|c| c.key().ip);
+                    if let Ok(new_conn) = Self::reconnect_with_rotated_sni(
+                        &self.host,
+                        self.port,
+                        &self.current_sni,
+                        ip,
+                    ) {
+                        // 旧连接上的 403 响应体不再读取，连接随之丢弃
+                        self.used_fake_sni = new_conn.used_fake();
+                        self.current_sni = new_conn.key().sni.clone();
+                        self.conn = Some(new_conn);
+                        self.rotated_once = true;
+                        continue;
                     }
                 }
-                if is_chunked {
-                    self.transfer = Some(TransferKind::Chunked);
-                    self.reading_chunk_size = true;
-                    self.chunk_remaining = 0;
-                } else if let Some(n) = content_len {
-                    self.transfer = Some(TransferKind::Length);
-                    self.content_remaining = n;
-                } else {
-                    self.transfer = Some(TransferKind::Eof);
+                if (200..300).contains(&status_code) && self.used_fake_sni {
+                    set_last_good_sni(&self.host, &self.current_sni);
                 }
-                self.headers_parsed = true;
-                break;
             }
-            let mut tmp = [0u8; 4096];
-            let n = self.inner.read(&mut tmp)?;
-            if n == 0 {
-                return Err(std::io::Error::new(
-                    std::io::ErrorKind::UnexpectedEof,
-                    "unexpected eof in headers",
-                ));
+            self.keep_alive = match resp.version() {
+                Version::HTTP_2 => true,
+                Version::HTTP_11 => !connection_close,
+                _ => false,
+            };
+            self.body = Some(resp.into_body());
+            self.requested = true;
+            if !(200..300).contains(&status_code) {
+                continue;
+            }
+            if let Some(done) = fetch_done {
+                let last_common = self
+                    .v2
+                    .lock()
+                    .map_err(|_| lock_poisoned())?
+                    .last_common
+                    .clone();
+                self.translator = Some(FetchResponseTranslator::new(done, last_common));
+            } else if protocol_v2
+                && matches!(self.op, HttpOp::InfoRefsUpload)
+                && !self.upgrade_advertisement()?
+            {
+                // 服务端声明 v2 却不支持 ls-refs/fetch：以 v0 重新请求通告
+                protocol_v2 = false;
+                self.requested = false;
+                self.prepare_followup()?;
             }
-            self.header_buf.extend_from_slice(&tmp[..n]);
         }
         Ok(())
     }
 
-    fn fill_decoded(&mut self) -> std::io::Result<()> {
-        if self.eof {
-            return Ok(());
-        }
-        if !self.headers_parsed {
-            self.parse_headers_and_setup()?;
-        }
-        match self.transfer {
-            Some(TransferKind::Chunked) => self.decode_chunked(),
-            Some(TransferKind::Length) => self.decode_content_length(),
-            Some(TransferKind::Eof) => self.decode_to_eof(),
-            None => Ok(()),
-        }
+    /// 读取完整响应体（仅用于体积较小的通告类响应）。
+    fn read_full_body(&mut self) -> std::io::Result<Vec<u8>> {
+        let Some(body) = self.body.take() else {
+            return Ok(Vec::new());
+        };
+        let rt = transport_runtime()?;
+        rt.block_on(hyper::body::to_bytes(body))
+            .map(|b| b.to_vec())
+            .map_err(|e| {
+                self.conn = None;
+                std::io::Error::other(format!("http body: {e}"))
+            })
     }
 
-    fn read_more(&mut self) -> std::io::Result<usize> {
-        let mut tmp = [0u8; 8192];
-        let n = self.inner.read(&mut tmp)?;
-        if n > 0 {
-            self.inbuf.extend_from_slice(&tmp[..n]);
-        }
-        Ok(n)
+    /// 在同一流内发起下一个请求前等待连接空闲；连接不可复用时以相同 (SNI, IP) 重连。
+    fn prepare_followup(&mut self) -> std::io::Result<()> {
+        let rt = transport_runtime()?;
+        let conn = self
+            .conn
+            .take()
+            .ok_or_else(|| std::io::Error::other("http connection missing"))?;
+        let key = conn.key().clone();
+        let ready = if self.keep_alive {
+            rt.block_on(conn.wait_ready(CHECKIN_READY_WAIT))
+        } else {
+            None
+        };
+        self.conn = Some(match ready {
+            Some(conn) => conn,
+            None => self.connect_same(key)?,
+        });
+        Ok(())
     }
 
-    fn decode_chunked(&mut self) -> std::io::Result<()> {
-        loop {
-            if self.trailer_mode {
-                if let Some(pos) = find_double_crlf(&self.inbuf) {
-                    self.inbuf.drain(..pos);
-                    self.eof = true;
-                    return Ok(());
-                }
-                let n = self.read_more()?;
-                if n == 0 {
-                    self.eof = true;
-                    return Ok(());
-                }
-                continue;
-            }
-            if self.reading_chunk_size {
-                if let Some(idx) = find_crlf(&self.inbuf) {
-                    let line = self.inbuf.drain(..idx + 2).collect::<Vec<u8>>();
-                    let line_no_crlf = &line[..line.len() - 2];
-                    let hex_part = match line_no_crlf.split(|&b| b == b';').next() {
-                        Some(h) => h,
-                        None => line_no_crlf,
-                    };
-                    let hex_str = std::str::from_utf8(hex_part).unwrap_or("");
-                    let size = usize::from_str_radix(hex_str.trim(), 16).unwrap_or(0);
-                    self.chunk_remaining = size;
-                    self.reading_chunk_size = false;
-                    if size == 0 {
-                        self.trailer_mode = true;
+    /// 处理以 v2 发起的 info/refs：服务端返回 v2 能力时改发 ls-refs，并把结果合成为 v0 通告；
+    /// 服务端仍返回 v0 通告时原样交付。返回 `false` 表示需以 v0 重新请求。
+    fn upgrade_advertisement(&mut self) -> std::io::Result<bool> {
+        let advert = self.read_full_body()?;
+        let caps = parse_capability_advertisement(&advert).map_err(std::io::Error::other)?;
+        let Some(caps) = caps else {
+            tracing::debug!(target="git.transport.http", host=%self.host, "protocol v2 not supported by server; using v0 advertisement");
+            self.finish_buffered(advert);
+            return Ok(true);
+        };
+        if !caps.ls_refs || !caps.supports_fetch() {
+            tracing::debug!(target="git.transport.http", host=%self.host, "protocol v2 advertised without ls-refs/fetch; retry with v0");
+            return Ok(false);
+        }
+        let prefixes = get_ls_refs_prefixes().unwrap_or_default();
+        let request = build_ls_refs_request(&caps, &prefixes);
+        self.prepare_followup()?;
+        let resp = self.dispatch(HttpOp::UploadPack, &request, true)?;
+        let status = resp.status();
+        if !status.is_success() {
+            return Err(std::io::Error::other(format!(
+                "protocol v2 ls-refs failed: HTTP {status}"
+            )));
+        }
+        self.keep_alive = resp.version() == Version::HTTP_2
+            || !resp
+                .headers()
+                .get(CONNECTION)
+                .and_then(|v| v.to_str().ok())
+                .is_some_and(|v| v.eq_ignore_ascii_case("close"));
+        self.body = Some(resp.into_body());
+        let listing = self.read_full_body()?;
+        let refs = parse_ls_refs_response(&listing).map_err(std::io::Error::other)?;
+        tracing::debug!(target="git.transport.http", host=%self.host, ref_prefixes=?prefixes, refs=%refs.len(), "protocol v2 ls-refs");
+        self.finish_buffered(synthesize_v0_advertisement(&refs, &caps));
+        self.v2.lock().map_err(|_| lock_poisoned())?.caps = Some(caps);
+        Ok(true)
+    }
+
+    /// 以内存中已就绪的数据作为整个响应，并归还连接。
+    fn finish_buffered(&mut self, data: Vec<u8>) {
+        self.decoded = data;
+        self.body = None;
+        self.eof = true;
+        self.release_connection();
+    }
+
+    fn fill_decoded(&mut self) -> std::io::Result<()> {
+        if self.eof {
+            return Ok(());
+        }
+        let Some(body) = self.body.as_mut() else {
+            self.eof = true;
+            return Ok(());
+        };
+        let rt = transport_runtime()?;
+        match rt.block_on(body.data()) {
+            Some(Ok(bytes)) => {
+                self.body_started.get_or_insert_with(Instant::now);
+                self.body_bytes += bytes.len() as u64;
+                match self.translator.as_mut() {
+                    Some(translator) => {
+                        let out = translator.feed(&bytes).map_err(std::io::Error::other)?;
+                        self.decoded.extend(out);
                     }
-                    continue;
-                }
-                let n = self.read_more()?;
-                if n == 0 {
-                    return Ok(());
+                    None => self.decoded.extend_from_slice(&bytes),
                 }
-                continue;
             }
-            if self.chunk_remaining > 0 {
-                if self.inbuf.is_empty() {
-                    let n = self.read_more()?;
-                    if n == 0 {
-                        return Ok(());
-                    }
-                }
-                let take = self.chunk_remaining.min(self.inbuf.len());
-                if take > 0 {
-                    let data = self.inbuf.drain(..take).collect::<Vec<u8>>();
-                    self.decoded.extend_from_slice(&data);
-                    self.chunk_remaining -= take;
-                }
-                if self.chunk_remaining > 0 {
-                    return Ok(());
-                }
-                if self.inbuf.len() < 2 {
-                    let _ = self.read_more()?;
-                }
-                if self.inbuf.len() >= 2 && &self.inbuf[..2] == b"\r\n" {
-                    self.inbuf.drain(..2);
+            Some(Err(e)) => {
+                // 响应体中途出错：连接状态未知，不再归还
+                self.body = None;
+                self.conn = None;
+                return Err(std::io::Error::other(format!("http body: {e}")));
+            }
+            None => {
+                self.eof = true;
+                self.body = None;
+                if let Some(translator) = self.translator.take() {
+                    let mut session = self.v2.lock().map_err(|_| lock_poisoned())?;
+                    translator
+                        .finish(&mut session)
+                        .map_err(std::io::Error::other)?;
                 }
-                self.reading_chunk_size = true;
-                continue;
+                self.report_throughput();
+                self.release_connection();
             }
         }
+        Ok(())
     }
 
-    fn decode_content_length(&mut self) -> std::io::Result<()> {
-        if self.content_remaining == 0 {
-            self.eof = true;
-            return Ok(());
-        }
-        if self.inbuf.is_empty() {
-            let _ = self.read_more()?;
-        }
-        if self.inbuf.is_empty() {
-            return Ok(());
-        }
-        let take = self.content_remaining.min(self.inbuf.len());
-        let data = self.inbuf.drain(..take).collect::<Vec<u8>>();
-        self.decoded.extend_from_slice(&data);
-        self.content_remaining -= take;
-        if self.content_remaining == 0 {
-            self.eof = true;
+    /// packfile 下载完成后把实测吞吐量反馈给 IP 池（仅经由 IP 池候选建立的连接）。
+    fn report_throughput(&mut self) {
+        let started = self.body_started.take();
+        let bytes = std::mem::take(&mut self.body_bytes);
+        if !matches!(self.op, HttpOp::UploadPack) || bytes < MIN_THROUGHPUT_SAMPLE_BYTES {
+            return;
         }
-        Ok(())
+        let (Some(started), Some(ip)) = (started, self.conn.as_ref().and_then(|c| c.key().ip))
+        else {
+            return;
+        };
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+        let pool = ip_pool::global::obtain_global_pool();
+        let Ok(guard) = pool.lock() else {
+            return;
+        };
+        guard.report_ip_outcome(
+            &self.host,
+            self.port,
+            ip,
+            IpOutcome::Throughput { bytes, elapsed_ms },
+        );
     }
 
-    fn decode_to_eof(&mut self) -> std::io::Result<()> {
-        if self.inbuf.is_empty() {
-            let n = self.read_more()?;
-            if n == 0 {
-                self.eof = true;
-                return Ok(());
-            }
+    /// 响应体读完后将连接归还连接池（服务端要求关闭或池已禁用时直接丢弃）。
+    fn release_connection(&mut self) {
+        let Some(conn) = self.conn.take() else {
+            return;
+        };
+        let pool = connection_pool();
+        if !self.keep_alive || !pool.is_enabled() {
+            return;
         }
-        if !self.inbuf.is_empty() {
-            let data = self.inbuf.drain(..).collect::<Vec<u8>>();
-            self.decoded.extend_from_slice(&data);
+        let Ok(rt) = transport_runtime() else {
+            return;
+        };
+        if let Some(conn) = rt.block_on(conn.wait_ready(CHECKIN_READY_WAIT)) {
+            tracing::debug!(target="git.transport.http", host=%self.host, sni=%self.current_sni, "return connection to pool");
+            pool.checkin(conn);
         }
-        Ok(())
     }
 
     /// 尝试以不同的 SNI 重新建立 TLS 连接，优先随机选择不同于 `current_sni` 的伪 SNI 候选；若失败则返回错误。
+    /// `ip` 为原连接所连的 IP 池候选，新连接沿用同一地址，使池键与结果上报仍对应该候选。
     fn reconnect_with_rotated_sni(
-        _cfg: &AppConfig,
         host: &str,
         port: u16,
         current_sni: &str,
-    ) -> Result<(StreamOwned<ClientConnection, TcpStream>, bool, String), Error> {
+        ip: Option<IpAddr>,
+    ) -> Result<PooledConnection, Error> {
         let cfg_now = load_or_init().unwrap_or_else(|_| AppConfig::default());
-        let present = proxy_present();
+        let connect = |sni: &str, used_fake: bool| {
+            let expected = used_fake.then_some(host);
+            shared_client_config(&cfg_now.tls, host, expected, cfg_now.http.http2_enabled)
+                .and_then(|tls_cfg| {
+                    connect_blocking(
+                        PoolKey::new(host, port, sni, ip),
+                        used_fake,
+                        tls_cfg,
+                        &cfg_now.proxy,
+                    )
+                })
+                .map_err(|e| Error::from_str(&format!("reconnect: {e}")))
+        };
+        let present = proxy_in_use(&cfg_now.proxy, host, port);
         if present || !cfg_now.http.fake_sni_enabled {
-            let server_name =
-                ServerName::try_from(host).map_err(|_| Error::from_str("invalid real host"))?;
-            let tls_cfg: std::sync::Arc<ClientConfig> =
-                std::sync::Arc::new(create_client_config(&cfg_now.tls));
-            let addr = format!("{host}:{port}");
-            let tcp = TcpStream::connect(addr)
-                .map_err(|e| Error::from_str(&format!("tcp connect: {e}")))?;
-            tcp.set_nodelay(true).ok();
-            let mut conn = ClientConnection::new(tls_cfg, server_name)
-                .map_err(|e| Error::from_str(&format!("tls client: {e}")))?;
-            conn.complete_io(&mut &tcp)
-                .map_err(|e| Error::from_str(&format!("tls handshake: {e}")))?;
-            let mut stream = StreamOwned::new(conn, tcp);
-            let _ = stream.flush();
+            let conn = connect(host, false)?;
             tracing::debug!(target="git.transport.http", host=%host, new_sni=%host, used_fake=false, "reconnect with real SNI due to proxy/disabled");
-            return Ok((stream, false, host.to_string()));
+            return Ok(conn);
         }
 
         let mut candidates: Vec<String> = Vec::new();
@@ -594,22 +596,9 @@ impl SniffingStream {
             }
         }
         if candidates.is_empty() {
-            let server_name =
-                ServerName::try_from(host).map_err(|_| Error::from_str("invalid real host"))?;
-            let tls_cfg: std::sync::Arc<ClientConfig> =
-                std::sync::Arc::new(create_client_config(&cfg_now.tls));
-            let addr = format!("{host}:{port}");
-            let tcp = TcpStream::connect(addr)
-                .map_err(|e| Error::from_str(&format!("tcp connect: {e}")))?;
-            tcp.set_nodelay(true).ok();
-            let mut conn = ClientConnection::new(tls_cfg, server_name)
-                .map_err(|e| Error::from_str(&format!("tls client: {e}")))?;
-            conn.complete_io(&mut &tcp)
-                .map_err(|e| Error::from_str(&format!("tls handshake: {e}")))?;
-            let mut stream = StreamOwned::new(conn, tcp);
-            let _ = stream.flush();
+            let conn = connect(host, false)?;
             tracing::debug!(target="git.transport.http", host=%host, new_sni=%host, used_fake=false, "no alternative fake SNI, fallback real");
-            return Ok((stream, false, host.to_string()));
+            return Ok(conn);
         }
         let mut rng = rand::thread_rng();
         let pick = candidates
@@ -617,103 +606,52 @@ impl SniffingStream {
             .cloned()
             .unwrap_or_else(|| candidates[0].clone());
 
-        let server_name =
-            ServerName::try_from(pick.as_str()).map_err(|_| Error::from_str("invalid sni host"))?;
-        let tls_cfg: std::sync::Arc<ClientConfig> =
-            std::sync::Arc::new(create_client_config_with_expected_name(&cfg_now.tls, host));
-        let addr = format!("{host}:{port}");
-        let tcp =
-            TcpStream::connect(addr).map_err(|e| Error::from_str(&format!("tcp connect: {e}")))?;
-        tcp.set_nodelay(true).ok();
-        let mut conn = ClientConnection::new(tls_cfg, server_name)
-            .map_err(|e| Error::from_str(&format!("tls client: {e}")))?;
-        conn.complete_io(&mut &tcp)
-            .map_err(|e| Error::from_str(&format!("tls handshake: {e}")))?;
-        let mut stream = StreamOwned::new(conn, tcp);
-        let _ = stream.flush();
+        let conn = connect(&pick, true)?;
         tracing::debug!(target="git.transport.http", host=%host, new_sni=%pick, used_fake=true, "reconnect with rotated SNI ok");
-        Ok((stream, true, pick))
+        Ok(conn)
+    }
+
+    fn take_decoded(&mut self, buf: &mut [u8]) -> usize {
+        let n = self.decoded.len().min(buf.len());
+        if !self.read_body_logged {
+            log_body_preview(
+                &self.decoded[..n.min(32)],
+                &self.host,
+                "first decoded bytes",
+            );
+            self.read_body_logged = true;
+            tl_mark_first_byte();
+        }
+        buf[..n].copy_from_slice(&self.decoded[..n]);
+        self.decoded.drain(..n);
+        n
     }
 }
 
+fn lock_poisoned() -> std::io::Error {
+    std::io::Error::other("protocol v2 session lock poisoned")
+}
+
 impl Read for SniffingStream {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
         tracing::debug!(target="git.transport", host=%self.host, read_buf_len=%buf.len(), "stream read attempt");
         self.ensure_request_sent()?;
-        if !self.headers_parsed {
-            self.parse_headers_and_setup()?;
-        }
         if let Some(msg) = self.fatal_error.clone() {
             return Err(std::io::Error::new(
                 std::io::ErrorKind::PermissionDenied,
                 msg,
             ));
         }
-        if !self.decoded.is_empty() {
-            let n = self.decoded.len().min(buf.len());
-            if !self.read_body_logged {
-                log_body_preview(
-                    &self.decoded[..n.min(32)],
-                    &self.host,
-                    "first decoded bytes",
-                );
-                self.read_body_logged = true;
-                tl_mark_first_byte();
-            }
-            buf[..n].copy_from_slice(&self.decoded[..n]);
-            self.decoded.drain(..n);
-            if !self.read_logged {
-                let cap = 2048usize;
-                let upto = self.header_buf.len().min(cap);
-                self.read_buf.extend_from_slice(&self.header_buf[..upto]);
-                self.try_log_response();
-            }
-            return Ok(n);
-        }
-        self.fill_decoded()?;
-        if !self.decoded.is_empty() {
-            let n = self.decoded.len().min(buf.len());
-            if !self.read_body_logged {
-                log_body_preview(
-                    &self.decoded[..n.min(32)],
-                    &self.host,
-                    "first decoded bytes",
-                );
-                self.read_body_logged = true;
-                tl_mark_first_byte();
-            }
-            buf[..n].copy_from_slice(&self.decoded[..n]);
-            self.decoded.drain(..n);
-            if !self.read_logged {
-                let cap = 2048usize;
-                let upto = self.header_buf.len().min(cap);
-                self.read_buf.extend_from_slice(&self.header_buf[..upto]);
-                self.try_log_response();
-            }
-            return Ok(n);
-        }
-        if self.eof {
-            return Ok(0);
-        }
         loop {
-            self.fill_decoded()?;
             if !self.decoded.is_empty() {
-                let n = self.decoded.len().min(buf.len());
-                buf[..n].copy_from_slice(&self.decoded[..n]);
-                self.decoded.drain(..n);
-                if !self.read_logged {
-                    let cap = 2048usize;
-                    let upto = self.header_buf.len().min(cap);
-                    self.read_buf.extend_from_slice(&self.header_buf[..upto]);
-                    self.try_log_response();
-                    tl_mark_first_byte();
-                }
+                let n = self.take_decoded(buf);
+                throttle_transfer(n);
                 return Ok(n);
             }
             if self.eof {
                 return Ok(0);
             }
-            let _ = self.read_more()?;
+            self.fill_decoded()?;
         }
     }
 }
@@ -724,14 +662,15 @@ impl Write for SniffingStream {
         match self.op {
             HttpOp::InfoRefsUpload | HttpOp::InfoRefsReceive => Ok(buf.len()),
             HttpOp::UploadPack | HttpOp::ReceivePack => {
+                throttle_transfer(buf.len());
                 self.post_buf.extend_from_slice(buf);
                 Ok(buf.len())
             }
         }
     }
     fn flush(&mut self) -> std::io::Result<()> {
-        if matches!(self.op, HttpOp::UploadPack | HttpOp::ReceivePack) && !self.posted {
-            self.send_post()?;
+        if matches!(self.op, HttpOp::UploadPack | HttpOp::ReceivePack) {
+            self.ensure_request_sent()?;
         }
         Ok(())
     }
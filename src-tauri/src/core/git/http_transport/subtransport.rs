@@ -1,17 +1,15 @@
-use std::io::Write;
-use std::net::{SocketAddr, TcpStream};
+use std::net::SocketAddr;
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
 
 use git2::Error;
-use rustls::StreamOwned;
-use rustls::{ClientConfig, ClientConnection, ServerName};
+use rustls::{Certificate, ClientConfig};
 use url::Url;
 
 use crate::core::config::model::AppConfig;
 use crate::core::git::transport::metrics::{
     finish_and_store, tl_push_fallback_event, tl_reset, tl_set_cert_fp_changed,
-    tl_set_fallback_stage, tl_set_ip_selection, tl_set_used_fake, FallbackEventRecord,
+    tl_set_fallback_stage, tl_set_http_version, tl_set_ip_selection, tl_set_used_fake,
+    FallbackEventRecord,
 };
 use crate::core::git::transport::metrics_enabled;
 use crate::core::git::transport::record_certificate;
@@ -19,18 +17,27 @@ use crate::core::git::transport::{
     is_fake_disabled, record_fake_attempt, AutoDisableConfig, AutoDisableEvent, DecisionCtx,
     FallbackDecision, FallbackStage, TimingRecorder,
 };
-use crate::core::ip_pool::{self, IpOutcome, IpPool, IpSelectionStrategy, IpStat};
-use crate::core::tls::util::{decide_sni_host_with_proxy, proxy_present};
-use crate::core::tls::verifier::{create_client_config, create_client_config_with_expected_name};
+use crate::core::ip_pool::racing::{interleave_families, race_candidates, should_race};
+use crate::core::ip_pool::{self, IpOutcome, IpPool, IpSelection, IpSelectionStrategy, IpStat};
+use crate::core::proxy::routing::{proxy_in_use, routed_through_proxy};
+use crate::core::proxy::AsyncProxyStream;
+use crate::core::tls::client_cert::describe_handshake_error;
+use crate::core::tls::util::decide_sni_host_with_proxy;
 
 use super::fallback::{classify_and_count_fallback, reason_label, stage_label};
+use super::pool::{
+    connection_pool, open_stream, shared_client_config, tcp_connect, tls_connect,
+    transport_runtime, PoolKey, PooledConnection, CANDIDATE_CONNECT_TIMEOUT,
+};
+use super::protocol_v2::ProtocolV2Session;
 use super::util::format_ip_sources;
 use super::{stream, HttpOp};
 
 pub struct CustomHttpsSubtransport {
     pub(super) cfg: AppConfig,
-    pub(super) tls: Arc<ClientConfig>,
     pub(super) pool: Arc<Mutex<IpPool>>,
+    // 同一传输内各 smart 请求共享的协议 v2 协商状态
+    pub(super) v2: Arc<Mutex<ProtocolV2Session>>,
 }
 
 pub mod testing {
@@ -52,7 +59,7 @@ pub mod testing {
             &self,
             host: &str,
             port: u16,
-        ) -> Result<(StreamOwned<ClientConnection, TcpStream>, bool, String), Error> {
+        ) -> Result<(PooledConnection, bool, String), Error> {
             self.inner.connect_tls_with_fallback(host, port)
         }
     }
@@ -75,9 +82,18 @@ impl git2::transport::SmartSubtransport for CustomHttpsSubtransport {
         let port = parsed.port_or_known_default().unwrap_or(443);
         let path = parsed.path().to_string();
 
-        tracing::debug!(target="git.transport", host=%host, port=%port, "connecting tls with fallback");
-        let (stream, used_fake_sni, sni_used) = self.connect_tls_with_fallback(host, port)?;
-        tracing::debug!(target="git.transport", host=%host, port=%port, used_fake_sni=%used_fake_sni, "connected and returning stream");
+        let conn = match self.checkout_pooled(host, port) {
+            Some(conn) => {
+                tl_set_http_version(conn.version().as_str());
+                conn
+            }
+            None => {
+                tracing::debug!(target="git.transport", host=%host, port=%port, "connecting tls with fallback");
+                let (conn, used_fake_sni, _) = self.connect_tls_with_fallback(host, port)?;
+                tracing::debug!(target="git.transport", host=%host, port=%port, used_fake_sni=%used_fake_sni, "connected and returning stream");
+                conn
+            }
+        };
 
         let op = match action {
             git2::transport::Service::UploadPackLs => HttpOp::InfoRefsUpload,
@@ -87,14 +103,13 @@ impl git2::transport::SmartSubtransport for CustomHttpsSubtransport {
         };
 
         let wrapped = stream::SniffingStream::new(
-            stream,
+            conn,
             host.to_string(),
             port,
-            used_fake_sni,
-            sni_used,
             path,
             op,
             self.cfg.clone(),
+            Arc::clone(&self.v2),
         );
         tracing::debug!(target="git.transport", host=%host, port=%port, "sniffing stream created");
         Ok(Box::new(wrapped))
@@ -107,13 +122,37 @@ impl git2::transport::SmartSubtransport for CustomHttpsSubtransport {
 
 impl CustomHttpsSubtransport {
     pub fn new(cfg: AppConfig) -> Self {
-        let tls = Arc::new(create_client_config(&cfg.tls));
         let pool = ip_pool::global::obtain_global_pool();
-        Self { cfg, tls, pool }
+        connection_pool().apply_http_cfg(&cfg.http);
+        Self {
+            cfg,
+            pool,
+            v2: Arc::new(Mutex::new(ProtocolV2Session::default())),
+        }
+    }
+
+    /// 优先复用连接池中到同一主机的空闲连接；伪 SNI 被策略、运行时保护或代理禁用时只接受真实 SNI 连接，
+    /// 所连 IP 已被熔断的连接直接淘汰，不绕过 IP 池与熔断器。
+    fn checkout_pooled(&self, host: &str, port: u16) -> Option<PooledConnection> {
+        let pool = connection_pool();
+        if !pool.is_enabled() {
+            return None;
+        }
+        let auto_cfg = AutoDisableConfig::from_http_cfg(&self.cfg.http);
+        let allow_fake = self.cfg.http.fake_sni_enabled
+            && !is_fake_disabled(&auto_cfg)
+            && !proxy_in_use(&self.cfg.proxy, host, port);
+        pool.checkout(host, port, allow_fake, |key| match key.ip {
+            Some(ip) => match self.pool.lock() {
+                Ok(ip_pool) => !ip_pool.is_ip_tripped_for(host, ip),
+                Err(_) => false,
+            },
+            None => true,
+        })
     }
 
-    fn compute_sni(&self, real_host: &str) -> (String, bool) {
-        let present = proxy_present();
+    fn compute_sni(&self, real_host: &str, port: u16) -> (String, bool) {
+        let present = proxy_in_use(&self.cfg.proxy, real_host, port);
         let (sni, used_fake) = decide_sni_host_with_proxy(&self.cfg, false, real_host, present);
         tracing::debug!(target="git.transport", real_host=%real_host, sni=%sni, used_fake=%used_fake, proxy_present=%present, "decided SNI host");
         (sni, used_fake)
@@ -123,7 +162,7 @@ impl CustomHttpsSubtransport {
         &self,
         host: &str,
         port: u16,
-    ) -> Result<(StreamOwned<ClientConnection, TcpStream>, bool, String), Error> {
+    ) -> Result<(PooledConnection, bool, String), Error> {
         let mut timing = TimingRecorder::new();
         tl_reset();
         tracing::debug!(target="git.transport", host=%host, port=%port, "begin tcp connect");
@@ -143,7 +182,10 @@ impl CustomHttpsSubtransport {
             runtime_fake_disabled,
         });
 
-        let selection = {
+        // 走代理的目标由代理连接器拨号（socks5h / HTTP CONNECT 由代理解析域名），不查询 IP 池
+        let selection = if routed_through_proxy(&self.cfg.proxy, host, port) {
+            IpSelection::system_default(host, port)
+        } else {
             let guard = self.pool.lock().expect("ip pool mutex poisoned");
             guard.pick_best_blocking(host, port)
         };
@@ -217,12 +259,21 @@ impl CustomHttpsSubtransport {
         enum ConnectTarget<'a> {
             System,
             Direct(&'a IpStat),
+            /// 对多个候选错峰竞速，首个完成 TLS 握手者胜出
+            Race(&'a [IpStat]),
         }
 
-        struct StageResult {
-            stream: StreamOwned<ClientConnection, TcpStream>,
+        struct Connected {
+            conn: PooledConnection,
             used_fake: bool,
             sni: String,
+            peer_certs: Option<Vec<Certificate>>,
+            /// 竞速胜出的候选
+            winner: Option<IpStat>,
+        }
+
+        struct StageResult {
+            connected: Connected,
             candidate: Option<IpStat>,
         }
 
@@ -230,10 +281,7 @@ impl CustomHttpsSubtransport {
                            host: &str,
                            port: u16,
                            target: ConnectTarget<'_>|
-         -> Result<
-            (StreamOwned<ClientConnection, TcpStream>, bool, String),
-            Error,
-        > {
+         -> Result<Connected, Error> {
             timing.mark_connect_start();
             #[cfg(any(test, not(feature = "tauri-app")))]
             {
@@ -250,11 +298,95 @@ impl CustomHttpsSubtransport {
                 }
             }
 
+            let rt = transport_runtime().map_err(|e| Error::from_str(&e.to_string()))?;
+            let (sni, used_fake) = match stage {
+                FallbackStage::Fake => self.compute_sni(host, port),
+                FallbackStage::Real | FallbackStage::Default | FallbackStage::None => {
+                    (host.to_string(), false)
+                }
+            };
+            if let ConnectTarget::Race(cands) = target {
+                let tls_cfg: Arc<ClientConfig> = shared_client_config(
+                    &self.cfg.tls,
+                    host,
+                    used_fake.then_some(host),
+                    self.cfg.http.http2_enabled,
+                )
+                .map_err(|e| Error::from_str(&e.to_string()))?;
+                // 竞速时各候选的 TCP 与 TLS 交织进行，整体计入 TLS 阶段
+                timing.mark_connect_end();
+                timing.mark_tls_start();
+                let result = rt.block_on(race_candidates(
+                    cands.to_vec(),
+                    &self.cfg.ip_pool.racing,
+                    |stat| {
+                        let sni = sni.clone();
+                        let tls_cfg = Arc::clone(&tls_cfg);
+                        async move {
+                            let addr = SocketAddr::new(stat.candidate.address, stat.candidate.port);
+                            let tcp = tcp_connect(
+                                host,
+                                port,
+                                Some(addr),
+                                Some(CANDIDATE_CONNECT_TIMEOUT),
+                            )
+                            .await
+                            .map_err(|e| format!("tcp connect: {e}"))?;
+                            tls_connect(tcp, &sni, tls_cfg).await.map_err(|e| {
+                                format!(
+                                    "tls handshake: {}",
+                                    describe_handshake_error(host, &e.to_string())
+                                )
+                            })
+                        }
+                    },
+                    |stat, outcome| record_candidate_outcome(stat, outcome),
+                ));
+                let Some((stat, tls)) = result.winner else {
+                    let errors = result.errors();
+                    if matches!(stage, FallbackStage::Fake) {
+                        if let Some(last) = errors.last() {
+                            classify_and_count_fallback(last);
+                        }
+                    }
+                    tracing::debug!(
+                        target="git.transport",
+                        host=%host,
+                        port=%port,
+                        stage=?stage,
+                        attempts=result.started,
+                        "ip pool candidate race failed"
+                    );
+                    return Err(Error::from_str(&format!(
+                        "candidate race failed: {}",
+                        errors.join("; ")
+                    )));
+                };
+                timing.mark_tls_end();
+                let peer_certs = tls.get_ref().1.peer_certificates().map(<[_]>::to_vec);
+                let key = PoolKey::new(host, port, &sni, Some(stat.candidate.address));
+                let conn = rt
+                    .block_on(PooledConnection::handshake_negotiated(key, used_fake, tls))
+                    .map_err(|e| {
+                        Error::from_str(&format!(
+                            "http handshake: {}",
+                            describe_handshake_error(host, &e.to_string())
+                        ))
+                    })?;
+                connection_pool().record_created();
+                return Ok(Connected {
+                    conn,
+                    used_fake,
+                    sni,
+                    peer_certs,
+                    winner: Some(stat),
+                });
+            }
             let (tcp, candidate_ref) = match target {
-                ConnectTarget::System => {
-                    let addr = format!("{host}:{port}");
-                    (
-                        TcpStream::connect(addr.as_str()).map_err(|e| {
+                ConnectTarget::Race(_) => unreachable!("race target handled above"),
+                ConnectTarget::System => (
+                    rt.block_on(open_stream(&self.cfg.proxy, host, port, None, None))
+                        .map_err(|e| {
                             tracing::debug!(
                                 target="git.transport",
                                 host=%host,
@@ -265,9 +397,8 @@ impl CustomHttpsSubtransport {
                             );
                             Error::from_str(&format!("tcp connect: {e}"))
                         })?,
-                        None,
-                    )
-                }
+                    None,
+                ),
                 ConnectTarget::Direct(stat) => {
                     let addr = SocketAddr::new(stat.candidate.address, stat.candidate.port);
                     tracing::debug!(
@@ -282,42 +413,40 @@ impl CustomHttpsSubtransport {
                         "attempting ip pool candidate"
                     );
                     (
-                        TcpStream::connect_timeout(&addr, Duration::from_millis(500)).map_err(
-                            |e| {
-                                tracing::debug!(
-                                    target="git.transport",
-                                    host=%host,
-                                    port=%port,
-                                    stage=?stage,
-                                    ip=%stat.candidate.address,
-                                    candidate_port=stat.candidate.port,
-                                    error=%e.to_string(),
-                                    "tcp connect failed for candidate"
-                                );
-                                Error::from_str(&format!("tcp connect: {e}"))
-                            },
-                        )?,
+                        rt.block_on(tcp_connect(
+                            host,
+                            port,
+                            Some(addr),
+                            Some(CANDIDATE_CONNECT_TIMEOUT),
+                        ))
+                        .map(AsyncProxyStream::from)
+                        .map_err(|e| {
+                            tracing::debug!(
+                                target="git.transport",
+                                host=%host,
+                                port=%port,
+                                stage=?stage,
+                                ip=%stat.candidate.address,
+                                candidate_port=stat.candidate.port,
+                                error=%e.to_string(),
+                                "tcp connect failed for candidate"
+                            );
+                            Error::from_str(&format!("tcp connect: {e}"))
+                        })?,
                         Some(stat),
                     )
                 }
             };
-            tcp.set_nodelay(true).ok();
             timing.mark_connect_end();
 
-            let (sni, used_fake) = match stage {
-                FallbackStage::Fake => self.compute_sni(host),
-                FallbackStage::Real | FallbackStage::Default | FallbackStage::None => {
-                    (host.to_string(), false)
-                }
-            };
             timing.mark_tls_start();
-            let server_name = ServerName::try_from(sni.as_str())
-                .map_err(|_| Error::from_str("invalid sni host"))?;
-            let tls_cfg: Arc<ClientConfig> = if used_fake {
-                Arc::new(create_client_config_with_expected_name(&self.cfg.tls, host))
-            } else {
-                self.tls.clone()
-            };
+            let tls_cfg: Arc<ClientConfig> = shared_client_config(
+                &self.cfg.tls,
+                host,
+                used_fake.then_some(host),
+                self.cfg.http.http2_enabled,
+            )
+            .map_err(|e| Error::from_str(&e.to_string()))?;
             tracing::debug!(
                 target="git.transport",
                 host=%host,
@@ -327,14 +456,32 @@ impl CustomHttpsSubtransport {
                 stage=?stage,
                 "start tls handshake"
             );
-            let mut conn = ClientConnection::new(tls_cfg.clone(), server_name)
-                .map_err(|e| Error::from_str(&format!("tls client: {e}")))?;
-            match conn.complete_io(&mut &tcp) {
-                Ok(_) => {
+            match rt.block_on(tls_connect(tcp, &sni, tls_cfg)) {
+                Ok(tls) => {
                     timing.mark_tls_end();
-                    let mut stream = StreamOwned::new(conn, tcp);
-                    let _ = stream.flush();
-                    Ok((stream, used_fake, sni))
+                    let peer_certs = tls.get_ref().1.peer_certificates().map(<[_]>::to_vec);
+                    let key = PoolKey::new(
+                        host,
+                        port,
+                        &sni,
+                        candidate_ref.map(|stat| stat.candidate.address),
+                    );
+                    let conn = rt
+                        .block_on(PooledConnection::handshake_negotiated(key, used_fake, tls))
+                        .map_err(|e| {
+                            Error::from_str(&format!(
+                                "http handshake: {}",
+                                describe_handshake_error(host, &e.to_string())
+                            ))
+                        })?;
+                    connection_pool().record_created();
+                    Ok(Connected {
+                        conn,
+                        used_fake,
+                        sni,
+                        peer_certs,
+                        winner: None,
+                    })
                 }
                 Err(err) => {
                     let em = err.to_string();
@@ -373,16 +520,54 @@ impl CustomHttpsSubtransport {
                             "tls handshake failed"
                         );
                     }
-                    Err(Error::from_str(&format!("tls handshake: {err}")))
+                    Err(Error::from_str(&format!(
+                        "tls handshake: {}",
+                        describe_handshake_error(host, &em)
+                    )))
                 }
             }
         };
 
         let mut run_stage = |stage: FallbackStage| -> Result<StageResult, Error> {
             let mut last_candidate_err: Option<Error> = None;
-            for stat in &candidates {
+            let mut sequential: Vec<&IpStat> = candidates.iter().collect();
+            if should_race(&self.cfg.ip_pool.racing, candidates.len()) {
+                let raced: Vec<IpStat> = interleave_families(candidates.clone())
+                    .into_iter()
+                    .take(self.cfg.ip_pool.racing.max_attempts)
+                    .collect();
+                // 各候选结果已在竞速中逐一报告；失败后只顺序尝试未参与竞速的候选
+                match attempt(stage, host, port, ConnectTarget::Race(&raced)) {
+                    Ok(connected) => {
+                        let candidate = connected.winner.clone();
+                        if let Some(stat) = candidate.as_ref() {
+                            tracing::debug!(
+                                target="git.transport",
+                                host=%host,
+                                port=%port,
+                                stage=?stage,
+                                ip=%stat.candidate.address,
+                                candidate_port=stat.candidate.port,
+                                sources=%format_ip_sources(&stat.sources),
+                                "ip pool candidate won connection race"
+                            );
+                        }
+                        return Ok(StageResult {
+                            connected,
+                            candidate,
+                        });
+                    }
+                    Err(err) => last_candidate_err = Some(err),
+                }
+                sequential.retain(|c| {
+                    !raced
+                        .iter()
+                        .any(|r| r.candidate.address == c.candidate.address)
+                });
+            }
+            for stat in sequential {
                 match attempt(stage, host, port, ConnectTarget::Direct(stat)) {
-                    Ok((stream, used_fake, sni)) => {
+                    Ok(connected) => {
                         record_candidate_outcome(stat, IpOutcome::Success);
                         tracing::debug!(
                             target="git.transport",
@@ -396,9 +581,7 @@ impl CustomHttpsSubtransport {
                             "ip pool candidate succeeded"
                         );
                         return Ok(StageResult {
-                            stream,
-                            used_fake,
-                            sni,
+                            connected,
                             candidate: Some(stat.clone()),
                         });
                     }
@@ -410,7 +593,7 @@ impl CustomHttpsSubtransport {
                 }
             }
             match attempt(stage, host, port, ConnectTarget::System) {
-                Ok((stream, used_fake, sni)) => {
+                Ok(connected) => {
                     if !candidates.is_empty() {
                         tracing::debug!(
                             target="git.transport",
@@ -421,9 +604,7 @@ impl CustomHttpsSubtransport {
                         );
                     }
                     Ok(StageResult {
-                        stream,
-                        used_fake,
-                        sni,
+                        connected,
                         candidate: None,
                     })
                 }
@@ -462,7 +643,7 @@ impl CustomHttpsSubtransport {
                     if metrics_enabled() {
                         finish_and_store(&mut timing);
                     }
-                    tl_set_used_fake(stage_ok.used_fake);
+                    tl_set_used_fake(stage_ok.connected.used_fake);
                     tl_set_fallback_stage(stage_label(stage));
                     used_candidate_stat = stage_ok.candidate.clone();
                     selection_success = stage_ok.candidate.is_some();
@@ -533,7 +714,7 @@ impl CustomHttpsSubtransport {
 
         match final_result {
             Ok(stage_ok) => {
-                if let Some(certs) = stage_ok.stream.conn.peer_certificates() {
+                if let Some(certs) = stage_ok.connected.peer_certs.as_deref() {
                     if let Some((changed, _spki, _cert)) = record_certificate(host, certs) {
                         if changed {
                             tl_set_cert_fp_changed(true);
@@ -559,7 +740,9 @@ impl CustomHttpsSubtransport {
                         "ip pool selection fell back to system dns"
                     );
                 }
-                Ok((stage_ok.stream, stage_ok.used_fake, stage_ok.sni))
+                let connected = stage_ok.connected;
+                tl_set_http_version(connected.conn.version().as_str());
+                Ok((connected.conn, connected.used_fake, connected.sni))
             }
             Err(err) => Err(err),
         }
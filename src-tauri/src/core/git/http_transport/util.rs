@@ -5,10 +5,6 @@ pub(super) fn find_double_crlf(buf: &[u8]) -> Option<usize> {
     buf.windows(4).position(|w| w == b"\r\n\r\n").map(|i| i + 4)
 }
 
-pub(super) fn find_crlf(buf: &[u8]) -> Option<usize> {
-    buf.windows(2).position(|w| w == b"\r\n")
-}
-
 pub(super) fn parse_http_header_first_line_and_host(header: &[u8]) -> (String, String, bool) {
     let mut line1 = String::new();
     let mut host = String::new();
@@ -63,6 +59,7 @@ pub(super) fn format_ip_sources(sources: &[IpSource]) -> String {
             IpSource::History => "History",
             IpSource::UserStatic => "UserStatic",
             IpSource::Fallback => "Fallback",
+            IpSource::Subscription => "Subscription",
         })
         .collect::<Vec<_>>()
         .join("+")
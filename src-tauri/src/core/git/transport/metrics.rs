@@ -133,6 +133,8 @@ thread_local! {
     static TL_IP_SOURCE: RefCell<Option<String>> = const { RefCell::new(None) };
     static TL_IP_LATENCY: std::cell::Cell<Option<u32>> = const { std::cell::Cell::new(None) };
     static TL_FALLBACK_EVENTS: RefCell<Vec<FallbackEventRecord>> = const { RefCell::new(Vec::new()) };
+    // 自定义传输连接协商出的 HTTP 版本（"HTTP/1.1" / "HTTP/2"）
+    static TL_HTTP_VERSION: std::cell::Cell<Option<&'static str>> = const { std::cell::Cell::new(None) };
     // P5.3: Proxy-related thread-local fields
     static TL_USED_PROXY: std::cell::Cell<Option<bool>> = const { std::cell::Cell::new(None) };
     static TL_PROXY_TYPE: RefCell<Option<String>> = const { RefCell::new(None) };
@@ -149,6 +151,7 @@ pub fn tl_reset() {
     TL_IP_SOURCE.with(|c| *c.borrow_mut() = None);
     TL_IP_LATENCY.with(|c| c.set(None));
     TL_FALLBACK_EVENTS.with(|c| c.borrow_mut().clear());
+    TL_HTTP_VERSION.with(|c| c.set(None));
     // P5.3: Reset proxy fields
     TL_USED_PROXY.with(|c| c.set(None));
     TL_PROXY_TYPE.with(|c| *c.borrow_mut() = None);
@@ -162,6 +165,9 @@ pub fn tl_set_used_fake(v: bool) {
 pub fn tl_set_fallback_stage(s: &'static str) {
     TL_FALLBACK_STAGE.with(|c| c.set(Some(s)));
 }
+pub fn tl_set_http_version(v: &'static str) {
+    TL_HTTP_VERSION.with(|c| c.set(Some(v)));
+}
 pub fn tl_set_cert_fp_changed(changed: bool) {
     TL_CERT_FP_CHANGED.with(|c| c.set(Some(changed)));
 }
@@ -203,6 +209,7 @@ pub struct TimingSnapshot {
     pub ip_strategy: Option<&'static str>,
     pub ip_source: Option<String>,
     pub ip_latency_ms: Option<u32>,
+    pub http_version: Option<&'static str>,
     // P5.3: Proxy-related fields
     pub used_proxy: Option<bool>,
     pub proxy_type: Option<String>,
@@ -219,6 +226,7 @@ pub fn tl_snapshot() -> TimingSnapshot {
         ip_strategy: TL_IP_STRATEGY.with(|c| c.get()),
         ip_source: TL_IP_SOURCE.with(|c| c.borrow().clone()),
         ip_latency_ms: TL_IP_LATENCY.with(|c| c.get()),
+        http_version: TL_HTTP_VERSION.with(|c| c.get()),
         // P5.3: Read proxy fields
         used_proxy: TL_USED_PROXY.with(|c| c.get()),
         proxy_type: TL_PROXY_TYPE.with(|c| c.borrow().clone()),
@@ -0,0 +1,306 @@
+//! Git 远程 URL 改写与镜像选择。
+//!
+//! - insteadOf / pushInsteadOf 风格的前缀改写（最长前缀优先）；
+//! - 按主机配置的只读镜像列表：读取（clone / fetch / 子模块）依次尝试健康镜像，
+//!   失败计数达到阈值的镜像进入冷却；推送始终走源站（仅应用 pushInsteadOf 规则）；
+//! - 每次操作最终由谁提供服务记录在线程局部结果中，由任务注册层发布事件。
+//!
+//! 与 `rewrite` 模块的 `https+custom://` 改写相互独立：本模块先选出目标 URL，再交给它决定是否走自定义传输。
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use url::Url;
+
+use crate::core::config::model::MirrorCfg;
+use crate::core::git::errors::{ErrorCategory, GitError};
+
+/// URL 的用途：读取可走镜像，推送只走源站
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UrlPurpose {
+    Read,
+    Push,
+}
+
+/// 依次尝试的候选地址；`mirror` 为 `None` 表示源站（可能已经过 insteadOf 改写）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MirrorCandidate {
+    pub url: String,
+    pub mirror: Option<String>,
+}
+
+/// 应用 insteadOf 规则。推送优先匹配 `push_only` 规则，未命中时再按普通规则改写（与 git 一致）。
+pub fn apply_rewrite_rules(cfg: &MirrorCfg, url: &str, purpose: UrlPurpose) -> String {
+    if !cfg.enabled {
+        return url.to_string();
+    }
+    let longest = |push_only: bool| {
+        cfg.rewrite_rules
+            .iter()
+            .filter(|r| r.push_only == push_only && !r.prefix.is_empty())
+            .filter(|r| url.starts_with(r.prefix.as_str()))
+            .max_by_key(|r| r.prefix.len())
+    };
+    let rule = match purpose {
+        UrlPurpose::Push => longest(true).or_else(|| longest(false)),
+        UrlPurpose::Read => longest(false),
+    };
+    match rule {
+        Some(r) => format!("{}{}", r.replacement, &url[r.prefix.len()..]),
+        None => url.to_string(),
+    }
+}
+
+/// 将仓库 URL 映射到镜像前缀下：`https://github.com/o/r.git` + `https://m.example/github.com`
+/// => `https://m.example/github.com/o/r.git`。
+fn mirror_url(mirror: &str, repo: &Url) -> String {
+    let mut out = format!(
+        "{}/{}",
+        mirror.trim_end_matches('/'),
+        repo.path().trim_start_matches('/')
+    );
+    if let Some(q) = repo.query() {
+        out.push('?');
+        out.push_str(q);
+    }
+    out
+}
+
+/// 解析本次操作应依次尝试的地址。
+///
+/// 读取：配置顺序中未处于冷却的镜像在前，`fallback_to_origin` 为真时源站垫底；
+/// 若没有可用镜像则总是包含源站。推送：只返回（经 pushInsteadOf 改写的）源站。
+pub fn resolve_candidates(cfg: &MirrorCfg, url: &str, purpose: UrlPurpose) -> Vec<MirrorCandidate> {
+    let rewritten = apply_rewrite_rules(cfg, url, purpose);
+    let origin = MirrorCandidate {
+        url: rewritten.clone(),
+        mirror: None,
+    };
+    if !cfg.enabled || purpose == UrlPurpose::Push {
+        return vec![origin];
+    }
+    let Ok(parsed) = Url::parse(&rewritten) else {
+        return vec![origin];
+    };
+    let Some(host) = parsed.host_str() else {
+        return vec![origin];
+    };
+    let mut out: Vec<MirrorCandidate> = cfg
+        .host_mirrors
+        .iter()
+        .filter(|m| m.host.eq_ignore_ascii_case(host))
+        .flat_map(|m| m.mirrors.iter())
+        .filter(|m| !m.trim().is_empty() && is_mirror_available(m))
+        .map(|m| MirrorCandidate {
+            url: mirror_url(m, &parsed),
+            mirror: Some(m.clone()),
+        })
+        .collect();
+    if cfg.fallback_to_origin || out.is_empty() {
+        out.push(origin);
+    }
+    out
+}
+
+// ---------------- 镜像健康状态 ----------------
+
+#[derive(Debug, Default)]
+struct HealthState {
+    consecutive_failures: u32,
+    cooling_until: Option<Instant>,
+    successes: u64,
+    failures: u64,
+    last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MirrorHealthSnapshot {
+    pub mirror: String,
+    pub consecutive_failures: u32,
+    pub cooling_down: bool,
+    pub cooldown_remaining_sec: u64,
+    pub successes: u64,
+    pub failures: u64,
+    pub last_error: Option<String>,
+}
+
+fn health() -> &'static Mutex<HashMap<String, HealthState>> {
+    static HEALTH: OnceLock<Mutex<HashMap<String, HealthState>>> = OnceLock::new();
+    HEALTH.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 镜像当前是否可参与选择（不在冷却期内）。
+pub fn is_mirror_available(mirror: &str) -> bool {
+    let Ok(map) = health().lock() else {
+        return true;
+    };
+    map.get(mirror)
+        .and_then(|s| s.cooling_until)
+        .is_none_or(|until| Instant::now() >= until)
+}
+
+pub fn report_mirror_success(mirror: &str) {
+    if let Ok(mut map) = health().lock() {
+        let state = map.entry(mirror.to_string()).or_default();
+        state.consecutive_failures = 0;
+        state.cooling_until = None;
+        state.successes += 1;
+    }
+}
+
+/// 记录一次失败；返回该镜像是否因此进入冷却。
+pub fn report_mirror_failure(cfg: &MirrorCfg, mirror: &str, reason: &str) -> bool {
+    let Ok(mut map) = health().lock() else {
+        return false;
+    };
+    let state = map.entry(mirror.to_string()).or_default();
+    state.consecutive_failures += 1;
+    state.failures += 1;
+    state.last_error = Some(reason.to_string());
+    if state.consecutive_failures >= cfg.failure_threshold.max(1) {
+        state.consecutive_failures = 0;
+        state.cooling_until = Some(Instant::now() + Duration::from_secs(cfg.cooldown_sec));
+        tracing::warn!(target = "git.mirror", mirror = %mirror, cooldown_sec = cfg.cooldown_sec, "mirror entered cooldown");
+        return true;
+    }
+    false
+}
+
+pub fn mirror_health_snapshot() -> Vec<MirrorHealthSnapshot> {
+    let Ok(map) = health().lock() else {
+        return Vec::new();
+    };
+    let now = Instant::now();
+    let mut out: Vec<MirrorHealthSnapshot> = map
+        .iter()
+        .map(|(mirror, s)| {
+            let remaining = s
+                .cooling_until
+                .map(|until| until.saturating_duration_since(now))
+                .unwrap_or_default();
+            MirrorHealthSnapshot {
+                mirror: mirror.clone(),
+                consecutive_failures: s.consecutive_failures,
+                cooling_down: !remaining.is_zero(),
+                cooldown_remaining_sec: remaining.as_secs(),
+                successes: s.successes,
+                failures: s.failures,
+                last_error: s.last_error.clone(),
+            }
+        })
+        .collect();
+    out.sort_by(|a, b| a.mirror.cmp(&b.mirror));
+    out
+}
+
+pub fn reset_mirror_health() {
+    if let Ok(mut map) = health().lock() {
+        map.clear();
+    }
+}
+
+// ---------------- 操作结果（线程局部） ----------------
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MirrorFailover {
+    pub mirror: String,
+    pub reason: String,
+}
+
+/// 一次 git 操作最终由哪个地址提供服务
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MirrorOutcome {
+    pub origin: String,
+    pub served_by: String,
+    pub mirror: Option<String>,
+    pub failovers: Vec<MirrorFailover>,
+    pub success: bool,
+}
+
+thread_local! { static MIRROR_OUTCOME: RefCell<Option<MirrorOutcome>> = const { RefCell::new(None) }; }
+
+pub fn tl_set_mirror_outcome(outcome: Option<MirrorOutcome>) {
+    MIRROR_OUTCOME.with(|o| *o.borrow_mut() = outcome);
+}
+
+pub fn tl_take_mirror_outcome() -> Option<MirrorOutcome> {
+    MIRROR_OUTCOME.with(|o| o.borrow_mut().take())
+}
+
+/// 镜像上的哪些失败值得换下一个候选：网络/TLS/协议类，以及镜像拒绝访问（Auth）。
+pub fn should_failover(category: ErrorCategory) -> bool {
+    matches!(
+        category,
+        ErrorCategory::Network
+            | ErrorCategory::Tls
+            | ErrorCategory::Verify
+            | ErrorCategory::ClientCert
+            | ErrorCategory::Protocol
+            | ErrorCategory::Auth
+    )
+}
+
+/// 依次以候选地址执行 `op`，镜像失败时按健康策略切换到下一个候选，并记录线程局部结果。
+/// 未启用镜像规则时直接以原地址执行且不记录结果。
+pub fn run_with_failover<T>(
+    cfg: &MirrorCfg,
+    url: &str,
+    purpose: UrlPurpose,
+    mut op: impl FnMut(&MirrorCandidate) -> Result<T, GitError>,
+) -> Result<T, GitError> {
+    if !cfg.enabled {
+        return op(&MirrorCandidate {
+            url: url.to_string(),
+            mirror: None,
+        });
+    }
+    let candidates = resolve_candidates(cfg, url, purpose);
+    let mut failovers: Vec<MirrorFailover> = Vec::new();
+    let last = candidates.len().saturating_sub(1);
+    for (i, candidate) in candidates.iter().enumerate() {
+        let result = op(candidate);
+        let finish = |success: bool, failovers: Vec<MirrorFailover>| {
+            tl_set_mirror_outcome(Some(MirrorOutcome {
+                origin: url.to_string(),
+                served_by: candidate.url.clone(),
+                mirror: candidate.mirror.clone(),
+                failovers,
+                success,
+            }));
+        };
+        match result {
+            Ok(v) => {
+                if let Some(m) = candidate.mirror.as_deref() {
+                    report_mirror_success(m);
+                }
+                finish(true, failovers);
+                return Ok(v);
+            }
+            Err(e) => {
+                if let Some(m) = candidate.mirror.as_deref() {
+                    report_mirror_failure(cfg, m, &e.to_string());
+                    if i < last && should_failover(e.category()) {
+                        tracing::warn!(target = "git.mirror", mirror = %m, error = %e, "mirror failed; trying next candidate");
+                        failovers.push(MirrorFailover {
+                            mirror: m.to_string(),
+                            reason: e.to_string(),
+                        });
+                        continue;
+                    }
+                }
+                finish(false, failovers);
+                return Err(e);
+            }
+        }
+    }
+    // resolve_candidates 总会返回至少一个候选
+    Err(GitError::new(
+        ErrorCategory::Internal,
+        "no mirror candidate available",
+    ))
+}
@@ -3,22 +3,37 @@
 // Public API:
 // - ensure_registered
 // - maybe_rewrite_https_to_custom
+// - mirror (insteadOf rewrite rules, read mirrors with failover)
+// - throttle (per-task / global bandwidth limits)
 // - set_push_auth_header_value (re-exported from http_transport)
+// - set_ls_refs_prefixes / ref_prefixes_for_refspecs (re-exported from http_transport)
 
 mod fallback;
 pub mod fingerprint; // made public for testing
 pub mod metrics; // made public for test helpers needing crate::core::git::transport::metrics::*
+pub mod mirror;
 mod register;
 pub mod rewrite; // made public for testing
 pub mod runtime; // made public for testing
+pub mod throttle;
 
 pub use fallback::{DecisionCtx, FallbackDecision, FallbackReason, FallbackStage};
 pub use metrics::{NoopCollector, TimingCapture, TimingRecorder, TransportMetricsCollector};
+pub use mirror::{
+    apply_rewrite_rules, resolve_candidates, run_with_failover, tl_take_mirror_outcome,
+    MirrorCandidate, MirrorOutcome, UrlPurpose,
+};
 pub use register::ensure_registered;
 pub use rewrite::{decide_https_to_custom, maybe_rewrite_https_to_custom, RewriteDecision};
 pub use runtime::{is_fake_disabled, record_fake_attempt, AutoDisableConfig, AutoDisableEvent};
+pub use throttle::{
+    enter_task as enter_throttle_scope, throttle_progress, throttle_transfer, tl_throttle_info,
+    ThrottleInfo, ThrottleScope,
+};
 // Re-export from http_transport
-pub use crate::core::git::http_transport::set_push_auth_header_value;
+pub use crate::core::git::http_transport::{
+    ref_prefixes_for_refspecs, set_ls_refs_prefixes, set_push_auth_header_value,
+};
 // P3.2: expose selective metrics thread-local helpers for task registry emission
 pub use fingerprint::record_certificate;
 pub use metrics::{
@@ -5,7 +5,7 @@ use git2::{transport, Error, Remote};
 use crate::core::config::loader::load_or_init;
 use crate::core::config::model::AppConfig;
 use crate::core::git::http_transport::CustomHttpsSubtransport;
-use crate::core::proxy::ProxyManager;
+use crate::core::proxy::{ProxyManager, ProxyRouter};
 
 use super::metrics::tl_set_proxy_usage;
 
@@ -18,13 +18,22 @@ static REGISTER_ONCE: OnceLock<()> = OnceLock::new();
 fn should_skip_custom_transport(cfg: &AppConfig) -> bool {
     // 创建临时ProxyManager检查配置
     let proxy_manager = ProxyManager::new(cfg.proxy.clone());
-    let should_disable = proxy_manager.should_disable_custom_transport();
     let is_enabled = proxy_manager.is_enabled();
+    // 存在直连路由（NO_PROXY / direct 规则）时仍需注册：直连主机可继续使用自定义传输层，
+    // 走代理的主机由 rewrite 按路由判定不改写，交给 libgit2 默认传输
+    let should_disable = if is_enabled
+        && !cfg.proxy.disable_custom_transport
+        && ProxyRouter::new(&cfg.proxy).has_direct_routes()
+    {
+        false
+    } else {
+        proxy_manager.should_disable_custom_transport()
+    };
 
     // P5.3: 记录proxy使用状态到metrics
     if is_enabled {
         let proxy_type = Some(format!("{}", proxy_manager.mode()).to_lowercase());
-        tl_set_proxy_usage(true, proxy_type, None, true);
+        tl_set_proxy_usage(true, proxy_type, None, should_disable);
     } else if should_disable {
         // 明确配置禁用自定义传输层但代理未启用
         tl_set_proxy_usage(false, None, None, true);
@@ -7,7 +7,8 @@ static ROLLOUT_HIT: AtomicU64 = AtomicU64::new(0);
 static ROLLOUT_MISS: AtomicU64 = AtomicU64::new(0);
 
 use crate::core::config::model::AppConfig;
-use crate::core::tls::util::{builtin_fake_sni_targets, match_domain, proxy_present};
+use crate::core::proxy::routing::proxy_in_use_for_url;
+use crate::core::tls::util::{builtin_fake_sni_targets, match_domain};
 
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct RewriteDecision {
@@ -18,7 +19,7 @@ pub struct RewriteDecision {
 
 /// 返回是否改写以及此次请求是否命中 rollout 采样（eligible 表示已进入采样阶段）。
 pub fn decide_https_to_custom(cfg: &AppConfig, url: &str) -> RewriteDecision {
-    decide_https_to_custom_inner(cfg, url, proxy_present())
+    decide_https_to_custom_inner(cfg, url, proxy_in_use_for_url(&cfg.proxy, url))
 }
 
 /// 若启用灰度且命中白名单，将 https:// 重写为 https+custom://
@@ -0,0 +1,454 @@
+//! Git 传输限速。
+//!
+//! - 令牌桶：全局一个桶由所有任务共享，每个任务另有自己的桶；两者取较长的等待；
+//! - 单任务上限按优先级决定：运行期调整 > 策略覆盖 (`strategyOverride.bandwidth`) > 按任务类型 > 默认；
+//! - 工作区批量任务的子任务在配置的本地时间窗口内叠加窗口上限（取较小值）；
+//! - 自定义传输在 `SniffingStream` 读写路径计量；其它传输（libgit2 内置 https/ssh）以进度回调中的
+//!   字节增量兜底，同一任务只会被计量一次。
+//!
+//! 任务上下文保存在线程局部中：git 操作在任务的阻塞线程上同步执行，传输读写与进度回调都在该线程。
+//! 等待按固定切片进行，任务取消后立即返回，不会被长时间的限速等待拖住。
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::core::config::model::{BandwidthCfg, BandwidthWindow};
+
+const KIB: u64 = 1024;
+/// 窗口判定与有效速率的重算间隔
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+/// 限速等待的单次休眠上限，两次休眠之间检查取消
+const WAIT_SLICE: Duration = Duration::from_millis(50);
+
+/// 令牌桶，容量为一秒的配额；速率为 0 表示不限。
+#[derive(Debug)]
+pub struct TokenBucket {
+    rate: AtomicU64,
+    state: Mutex<BucketState>,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    last: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(bytes_per_sec: u64) -> Self {
+        Self {
+            rate: AtomicU64::new(bytes_per_sec),
+            state: Mutex::new(BucketState {
+                tokens: bytes_per_sec as f64,
+                last: Instant::now(),
+            }),
+        }
+    }
+
+    pub fn rate(&self) -> u64 {
+        self.rate.load(Ordering::Relaxed)
+    }
+
+    pub fn set_rate(&self, bytes_per_sec: u64) {
+        if self.rate.swap(bytes_per_sec, Ordering::Relaxed) == bytes_per_sec {
+            return;
+        }
+        if let Ok(mut st) = self.state.lock() {
+            st.tokens = st.tokens.min(bytes_per_sec as f64);
+            st.last = Instant::now();
+        }
+    }
+
+    /// 预留 `bytes` 个令牌，返回调用方需要等待的时长（令牌允许透支，由等待偿还）。
+    pub fn reserve(&self, bytes: usize) -> Duration {
+        let rate = self.rate();
+        if rate == 0 || bytes == 0 {
+            return Duration::ZERO;
+        }
+        let Ok(mut st) = self.state.lock() else {
+            return Duration::ZERO;
+        };
+        let now = Instant::now();
+        let refill = now.duration_since(st.last).as_secs_f64() * rate as f64;
+        st.tokens = (st.tokens + refill).min(rate as f64);
+        st.last = now;
+        st.tokens -= bytes as f64;
+        if st.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-st.tokens / rate as f64)
+        }
+    }
+}
+
+// ---------------- 全局上限 ----------------
+
+struct GlobalLimit {
+    bucket: TokenBucket,
+    cfg_kib: AtomicU64,
+    /// 运行期覆盖；`u64::MAX` 表示未覆盖
+    runtime_kib: AtomicU64,
+}
+
+fn global() -> &'static GlobalLimit {
+    static GLOBAL: OnceLock<GlobalLimit> = OnceLock::new();
+    GLOBAL.get_or_init(|| GlobalLimit {
+        bucket: TokenBucket::new(0),
+        cfg_kib: AtomicU64::new(0),
+        runtime_kib: AtomicU64::new(u64::MAX),
+    })
+}
+
+fn apply_global() {
+    let g = global();
+    let runtime = g.runtime_kib.load(Ordering::Relaxed);
+    let kib = if runtime == u64::MAX {
+        g.cfg_kib.load(Ordering::Relaxed)
+    } else {
+        runtime
+    };
+    g.bucket.set_rate(kib.saturating_mul(KIB));
+}
+
+/// 同步配置中的全局上限（任务启动与保存配置时调用）；运行期覆盖仍然优先。
+pub fn sync_global_limit(cfg: &BandwidthCfg) {
+    global()
+        .cfg_kib
+        .store(cfg.global_kib_per_sec, Ordering::Relaxed);
+    apply_global();
+}
+
+/// 运行期调整全局上限；`None` 取消覆盖、恢复配置值。
+pub fn set_global_limit_override(kib_per_sec: Option<u64>) {
+    global()
+        .runtime_kib
+        .store(kib_per_sec.unwrap_or(u64::MAX), Ordering::Relaxed);
+    apply_global();
+}
+
+/// 当前生效的全局上限（KiB/s），`None` 表示不限。
+pub fn global_limit() -> Option<u64> {
+    let rate = global().bucket.rate();
+    (rate > 0).then_some(rate / KIB)
+}
+
+// ---------------- 时间窗口 ----------------
+
+fn parse_hhmm(s: &str) -> Option<u32> {
+    let (h, m) = s.trim().split_once(':')?;
+    let (h, m): (u32, u32) = (h.parse().ok()?, m.parse().ok()?);
+    (h < 24 && m < 60).then_some(h * 60 + m)
+}
+
+/// 返回 `minute_of_day` 时刻命中的首个窗口；格式非法的窗口被忽略，`start == end` 表示全天。
+pub fn active_window(windows: &[BandwidthWindow], minute_of_day: u32) -> Option<&BandwidthWindow> {
+    windows.iter().find(|w| {
+        let (Some(start), Some(end)) = (parse_hhmm(&w.start), parse_hhmm(&w.end)) else {
+            return false;
+        };
+        if start <= end {
+            start == end || (start..end).contains(&minute_of_day)
+        } else {
+            minute_of_day >= start || minute_of_day < end
+        }
+    })
+}
+
+fn local_minute_of_day() -> u32 {
+    use chrono::Timelike;
+    let now = chrono::Local::now();
+    now.hour() * 60 + now.minute()
+}
+
+// ---------------- 单任务限速 ----------------
+
+/// 单任务上限的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ThrottleSource {
+    Unlimited,
+    Default,
+    Kind,
+    Override,
+    Window,
+    Runtime,
+}
+
+/// 进度事件中携带的当前限速
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThrottleInfo {
+    /// 单任务上限（KiB/s），`None` 表示不限
+    pub task_kib_per_sec: Option<u64>,
+    /// 全局上限（KiB/s），`None` 表示不限
+    pub global_kib_per_sec: Option<u64>,
+    pub source: ThrottleSource,
+    pub window_active: bool,
+}
+
+pub struct TaskThrottle {
+    pub task_id: Uuid,
+    pub kind: String,
+    base_kib: u64,
+    base_source: ThrottleSource,
+    background: bool,
+    windows: Vec<BandwidthWindow>,
+    runtime_kib: AtomicU64,
+    bucket: TokenBucket,
+    info: Mutex<(Option<Instant>, ThrottleInfo)>,
+    /// 已经过传输读写路径计量，进度回调不再重复计量
+    metered: AtomicBool,
+    seen_down: AtomicU64,
+    seen_up: AtomicU64,
+    /// 任务取消令牌；取消后限速等待立即结束
+    cancel: OnceLock<CancellationToken>,
+}
+
+impl TaskThrottle {
+    fn new(
+        cfg: &BandwidthCfg,
+        task_id: Uuid,
+        kind: &str,
+        override_kib: Option<u64>,
+        background: bool,
+    ) -> Self {
+        let (base_kib, base_source) = match (override_kib, cfg.per_kind.get(kind)) {
+            (Some(v), _) => (v, ThrottleSource::Override),
+            (None, Some(&v)) => (v, ThrottleSource::Kind),
+            (None, None) => (cfg.per_task_kib_per_sec, ThrottleSource::Default),
+        };
+        let this = Self {
+            task_id,
+            kind: kind.to_string(),
+            base_kib,
+            base_source,
+            background,
+            windows: if background {
+                cfg.windows.clone()
+            } else {
+                Vec::new()
+            },
+            runtime_kib: AtomicU64::new(u64::MAX),
+            bucket: TokenBucket::new(0),
+            info: Mutex::new((None, unlimited_info())),
+            metered: AtomicBool::new(false),
+            seen_down: AtomicU64::new(0),
+            seen_up: AtomicU64::new(0),
+            cancel: OnceLock::new(),
+        };
+        this.refresh(true);
+        this
+    }
+
+    fn compute(&self, minute_of_day: u32) -> ThrottleInfo {
+        let runtime = self.runtime_kib.load(Ordering::Relaxed);
+        let (mut kib, mut source) = if runtime != u64::MAX {
+            (runtime, ThrottleSource::Runtime)
+        } else {
+            (self.base_kib, self.base_source)
+        };
+        let window = if self.background && runtime == u64::MAX {
+            active_window(&self.windows, minute_of_day)
+        } else {
+            None
+        };
+        if let Some(w) = window {
+            if w.limit_kib_per_sec > 0 && (kib == 0 || w.limit_kib_per_sec < kib) {
+                kib = w.limit_kib_per_sec;
+                source = ThrottleSource::Window;
+            }
+        }
+        if kib == 0 {
+            source = ThrottleSource::Unlimited;
+        }
+        ThrottleInfo {
+            task_kib_per_sec: (kib > 0).then_some(kib),
+            global_kib_per_sec: global_limit(),
+            source,
+            window_active: window.is_some(),
+        }
+    }
+
+    /// 按需（或强制）重算有效上限并更新令牌桶
+    fn refresh(&self, force: bool) -> ThrottleInfo {
+        let Ok(mut guard) = self.info.lock() else {
+            return unlimited_info();
+        };
+        let stale = guard.0.is_none_or(|at| at.elapsed() >= REFRESH_INTERVAL);
+        if force || stale {
+            let info = self.compute(local_minute_of_day());
+            self.bucket
+                .set_rate(info.task_kib_per_sec.unwrap_or(0).saturating_mul(KIB));
+            *guard = (Some(Instant::now()), info);
+        }
+        guard.1.clone()
+    }
+
+    pub fn info(&self) -> ThrottleInfo {
+        self.refresh(false)
+    }
+
+    /// 运行期调整本任务上限；`None` 恢复配置/策略值，`Some(0)` 表示不限。
+    pub fn set_runtime_limit(&self, kib_per_sec: Option<u64>) {
+        self.runtime_kib
+            .store(kib_per_sec.unwrap_or(u64::MAX), Ordering::Relaxed);
+        self.refresh(true);
+    }
+
+    fn consume(&self, bytes: usize) -> Duration {
+        self.refresh(false);
+        self.bucket.reserve(bytes)
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel
+            .get()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+}
+
+fn unlimited_info() -> ThrottleInfo {
+    ThrottleInfo {
+        task_kib_per_sec: None,
+        global_kib_per_sec: None,
+        source: ThrottleSource::Unlimited,
+        window_active: false,
+    }
+}
+
+fn tasks() -> &'static Mutex<HashMap<Uuid, Arc<TaskThrottle>>> {
+    static TASKS: OnceLock<Mutex<HashMap<Uuid, Arc<TaskThrottle>>>> = OnceLock::new();
+    TASKS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+thread_local! { static CURRENT: RefCell<Option<Arc<TaskThrottle>>> = const { RefCell::new(None) }; }
+
+/// 任务限速作用域；drop 时清理线程局部上下文并注销任务。
+pub struct ThrottleScope {
+    task_id: Uuid,
+    throttle: Arc<TaskThrottle>,
+}
+
+impl ThrottleScope {
+    /// 任务取消时中断本作用域内的限速等待
+    pub fn cancel_on(self, token: CancellationToken) -> Self {
+        let _ = self.throttle.cancel.set(token);
+        self
+    }
+}
+
+impl Drop for ThrottleScope {
+    fn drop(&mut self) {
+        CURRENT.with(|c| c.borrow_mut().take());
+        if let Ok(mut map) = tasks().lock() {
+            map.remove(&self.task_id);
+        }
+    }
+}
+
+/// 在当前线程上为任务启用限速。`background` 为真（工作区批量任务的子任务）时时间窗口生效。
+pub fn enter_task(
+    cfg: &BandwidthCfg,
+    task_id: Uuid,
+    kind: &str,
+    override_kib: Option<u64>,
+    background: bool,
+) -> ThrottleScope {
+    sync_global_limit(cfg);
+    let throttle = Arc::new(TaskThrottle::new(
+        cfg,
+        task_id,
+        kind,
+        override_kib,
+        background,
+    ));
+    if let Ok(mut map) = tasks().lock() {
+        map.insert(task_id, Arc::clone(&throttle));
+    }
+    CURRENT.with(|c| *c.borrow_mut() = Some(Arc::clone(&throttle)));
+    ThrottleScope { task_id, throttle }
+}
+
+/// 运行期调整某个运行中任务的上限；任务不存在时返回 false。
+pub fn set_task_limit(task_id: &Uuid, kib_per_sec: Option<u64>) -> bool {
+    let throttle = tasks().lock().ok().and_then(|m| m.get(task_id).cloned());
+    match throttle {
+        Some(t) => {
+            t.set_runtime_limit(kib_per_sec);
+            true
+        }
+        None => false,
+    }
+}
+
+/// 运行中任务的限速快照（按任务 ID 排序）
+pub fn active_task_throttles() -> Vec<(Uuid, String, ThrottleInfo)> {
+    let list: Vec<Arc<TaskThrottle>> = tasks()
+        .lock()
+        .map(|m| m.values().cloned().collect())
+        .unwrap_or_default();
+    let mut out: Vec<_> = list
+        .iter()
+        .map(|t| (t.task_id, t.kind.clone(), t.info()))
+        .collect();
+    out.sort_by_key(|(id, _, _)| *id);
+    out
+}
+
+/// 当前线程任务的限速信息（无任务上下文时为 `None`）
+pub fn tl_throttle_info() -> Option<ThrottleInfo> {
+    CURRENT.with(|c| c.borrow().as_ref().map(|t| t.info()))
+}
+
+/// 分片休眠 `delay`；任务已取消时提前返回。
+fn wait(delay: Duration, task: Option<&TaskThrottle>) {
+    let deadline = Instant::now() + delay;
+    loop {
+        if task.is_some_and(TaskThrottle::is_cancelled) {
+            return;
+        }
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return;
+        }
+        std::thread::sleep(left.min(WAIT_SLICE));
+    }
+}
+
+/// 传输读写路径上的计量：扣减全局与当前任务的令牌，必要时阻塞等待。
+pub fn throttle_transfer(bytes: usize) {
+    let task = CURRENT.with(|c| c.borrow().clone());
+    let mut delay = global().bucket.reserve(bytes);
+    if let Some(t) = &task {
+        t.metered.store(true, Ordering::Relaxed);
+        delay = delay.max(t.consume(bytes));
+    }
+    wait(delay, task.as_deref());
+}
+
+/// 进度回调兜底计量：`total` 为本次操作累计字节数。仅在传输路径未计量时生效。
+pub fn throttle_progress(total: u64, upload: bool) {
+    let Some(t) = CURRENT.with(|c| c.borrow().clone()) else {
+        return;
+    };
+    let seen = if upload { &t.seen_up } else { &t.seen_down };
+    let prev = seen.swap(total, Ordering::Relaxed);
+    if t.metered.load(Ordering::Relaxed) {
+        return;
+    }
+    // 新一轮操作（如重试）计数从零开始
+    let delta = if total >= prev { total - prev } else { total };
+    if delta > 0 {
+        let bytes = delta as usize;
+        wait(
+            global().bucket.reserve(bytes).max(t.consume(bytes)),
+            Some(&t),
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use std::{collections::HashMap, sync::Arc, time::Instant};
+use std::{collections::HashMap, net::SocketAddr, sync::Arc, time::Instant};
 
 use anyhow::{anyhow, Context, Result};
 use base64::engine::general_purpose::STANDARD as BASE64;
@@ -9,24 +9,93 @@ use hyper::{Body, Request, Uri, Version};
 use rustls::ServerName;
 use tokio::net::TcpStream;
 use tokio::time::{timeout, Duration};
+use tokio_rustls::client::TlsStream;
 use tokio_rustls::rustls::ClientConfig;
 use tokio_rustls::TlsConnector;
 
 use crate::core::config::model::AppConfig;
 use crate::core::ip_pool::global::pick_best_async;
 use crate::core::ip_pool::global::report_outcome_async;
-use crate::core::ip_pool::IpOutcome;
+use crate::core::ip_pool::racing::{race_candidates, should_race};
+use crate::core::ip_pool::{IpOutcome, IpSelection, IpStat};
 // Metrics / events instrumentation
 // use crate::events::structured::{
 //    publish_global, Event as StructuredEvent, StrategyEvent as StructuredStrategyEvent,
 // };
 // Reuse existing metrics enabled flag
 // use crate::core::git::transport::metrics::metrics_enabled;
-use crate::core::tls::util::{decide_sni_host_with_proxy, proxy_present};
+use crate::core::proxy::routing::{proxy_in_use, routed_through_proxy};
+use crate::core::proxy::{tunnel, AsyncProxyStream};
+use crate::core::tls::client_cert::{
+    apply_client_cert, describe_handshake_error, select_client_cert,
+};
+use crate::core::tls::util::decide_sni_host_with_proxy;
 use crate::core::tls::verifier::{create_client_config, create_client_config_with_expected_name};
 
 use super::types::{HttpRequestInput, HttpResponseOutput, TimingInfo};
 
+/// 竞速胜出、已完成 TLS 握手的连接
+struct RacedConn {
+    stream: TlsStream<TcpStream>,
+    connect_ms: u32,
+    tls_ms: u32,
+    fake_sni: bool,
+}
+
+/// 竞速中的单个候选：TCP 连接 + TLS 握手，各阶段均受 `limit` 限制。
+async fn race_connect(
+    stat: IpStat,
+    port: u16,
+    host: String,
+    sni_host: String,
+    fake_sni: bool,
+    tls_config: Arc<ClientConfig>,
+    limit: Duration,
+) -> std::result::Result<RacedConn, String> {
+    let addr = SocketAddr::new(stat.candidate.address, port);
+    let start_connect = Instant::now();
+    let tcp = timeout(limit, TcpStream::connect(addr))
+        .await
+        .map_err(|_| "connect timeout".to_string())?
+        .map_err(|e| format!("connect error: {e}"))?;
+    let connect_ms = start_connect.elapsed().as_millis() as u32;
+    let server_name = ServerName::try_from(sni_host.as_str())
+        .map_err(|e| format!("invalid sni {sni_host}: {e}"))?;
+    let start_tls = Instant::now();
+    let stream = timeout(
+        limit,
+        TlsConnector::from(tls_config).connect(server_name, tcp),
+    )
+    .await
+    .map_err(|_| "tls handshake timeout".to_string())?
+    .map_err(|e| {
+        format!(
+            "tls handshake error: {}",
+            describe_handshake_error(&host, &e.to_string())
+        )
+    })?;
+    Ok(RacedConn {
+        stream,
+        connect_ms,
+        tls_ms: start_tls.elapsed().as_millis() as u32,
+        fake_sni,
+    })
+}
+
+/// 在已建立的连接上完成 HTTP/1.1 握手，并在后台驱动连接任务。
+async fn http_handshake<S>(io: S) -> hyper::Result<hyper::client::conn::SendRequest<Body>>
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send + 'static,
+{
+    let (sender, conn) = hyper::client::conn::handshake(io).await?;
+    tokio::spawn(async move {
+        if let Err(e) = conn.await {
+            tracing::debug!(target = "http", "conn ended: {:?}", e);
+        }
+    });
+    Ok(sender)
+}
+
 /// 内部简单 HTTP 客户端：使用手动连接 + hyper `client::conn，便于自定义` SNI
 pub struct HttpClient {
     cfg: AppConfig,
@@ -42,9 +111,27 @@ impl HttpClient {
         }
     }
 
-    /// 计算用于 TLS 握手的 SNI 主机名，并返回是否使用了伪 SNI
+    /// 目标主机的 rustls 配置：伪 SNI 按真实主机名校验，配置了 mTLS 客户端证书时挂上证书。
+    fn client_config_for(
+        &self,
+        host: &str,
+        fake: bool,
+    ) -> std::result::Result<Arc<ClientConfig>, String> {
+        if !fake && select_client_cert(&self.cfg.tls, host).is_none() {
+            return Ok(self.tls_default.clone());
+        }
+        let mut cfg = if fake {
+            create_client_config_with_expected_name(&self.cfg.tls, host)
+        } else {
+            create_client_config(&self.cfg.tls)
+        };
+        apply_client_cert(&mut cfg, &self.cfg.tls, host)?;
+        Ok(Arc::new(cfg))
+    }
+
+    /// 计算用于 TLS 握手的 SNI 主机名，并返回是否使用了伪 SNI（按代理路由规则判断该主机是否走代理）
     pub fn compute_sni_host(&self, force_real_sni: bool, real_host: &str) -> (String, bool) {
-        let proxy = proxy_present();
+        let proxy = proxy_in_use(&self.cfg.proxy, real_host, 443);
         decide_sni_host_with_proxy(&self.cfg, force_real_sni, real_host, proxy)
     }
 
@@ -80,9 +167,14 @@ impl HttpClient {
             .to_string();
         let port = url.port_u16().unwrap_or(if is_https { 443 } else { 80 });
 
-        // 集成全局IP池，优先用 pick_best 选出的 IP 建立连接。
+        // 集成全局IP池，优先用 pick_best 选出的 IP 建立连接；走代理的目标经代理连接器建立隧道，不查询 IP 池。
         let start_total = Instant::now();
-        let sel = pick_best_async(&host, port).await;
+        let routed = routed_through_proxy(&self.cfg.proxy, &host, port);
+        let sel = if routed {
+            IpSelection::system_default(host.clone(), port)
+        } else {
+            pick_best_async(&host, port).await
+        };
 
         let mut errors: Vec<String> = Vec::new();
         let mut success_out: Option<HttpResponseOutput> = None;
@@ -94,12 +186,59 @@ impl HttpClient {
             sel.iter_candidates().cloned().collect();
         enum Target {
             Candidate(crate::core::ip_pool::IpStat),
+            Raced(crate::core::ip_pool::IpStat, Box<RacedConn>),
             System,
         }
-        let mut targets: Vec<Target> = candidates
-            .iter()
-            .map(|c| Target::Candidate(c.clone()))
-            .collect();
+        let mut targets: Vec<Target> = Vec::new();
+        let mut remaining = candidates.clone();
+
+        // 多个候选时先错峰竞速（Happy Eyeballs），胜出连接优先使用；竞速中已失败的候选不再顺序重试
+        if is_https && should_race(&self.cfg.ip_pool.racing, candidates.len()) {
+            let (sni_host, fake) = self.compute_sni_host(input.force_real_sni, &host);
+            match self.client_config_for(&host, fake) {
+                Ok(tls_config) => {
+                    let limit = Duration::from_millis(input.timeout_ms);
+                    let result = race_candidates(
+                        candidates.clone(),
+                        &self.cfg.ip_pool.racing,
+                        |stat| {
+                            race_connect(
+                                stat,
+                                port,
+                                host.clone(),
+                                sni_host.clone(),
+                                fake,
+                                tls_config.clone(),
+                                limit,
+                            )
+                        },
+                        |stat, outcome| {
+                            report_outcome_async(
+                                crate::core::ip_pool::IpSelection::from_cached(
+                                    &host,
+                                    port,
+                                    stat.clone(),
+                                ),
+                                outcome,
+                            )
+                        },
+                    )
+                    .await;
+                    errors.extend(result.errors());
+                    remaining.retain(|c| {
+                        !result
+                            .attempts
+                            .iter()
+                            .any(|a| a.stat.candidate.address == c.candidate.address)
+                    });
+                    if let Some((stat, conn)) = result.winner {
+                        targets.push(Target::Raced(stat, Box::new(conn)));
+                    }
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+        targets.extend(remaining.into_iter().map(Target::Candidate));
 
         // If system strategy or fallback needed, append System
         // Note: subtransport tries system after candidates. We do same.
@@ -115,41 +254,32 @@ impl HttpClient {
                 }
             }
 
-            let (connect_addr, is_pool_candidate, current_stat) = match &target {
+            let (connect_addr, is_pool_candidate, current_stat, raced_conn) = match target {
                 Target::Candidate(stat) => {
-                    (stat.candidate.address.to_string(), true, Some(stat.clone()))
+                    (stat.candidate.address.to_string(), true, Some(stat), None)
                 }
-                Target::System => (host.clone(), false, None),
+                Target::Raced(stat, conn) => (
+                    stat.candidate.address.to_string(),
+                    true,
+                    Some(stat),
+                    Some(conn),
+                ),
+                Target::System => (host.clone(), false, None, None),
             };
 
-            let start_connect = Instant::now();
-            let tcp_res = timeout(
-                Duration::from_millis(input.timeout_ms),
-                TcpStream::connect((connect_addr.as_str(), port)),
-            )
-            .await;
-
-            let tcp = match tcp_res {
-                Ok(Ok(stream)) => stream,
-                Ok(Err(e)) => {
-                    errors.push(format!("connect error to {}: {}", connect_addr, e));
-                    if is_pool_candidate {
-                        if let Some(c) = &current_stat {
-                            report_outcome_async(
-                                crate::core::ip_pool::IpSelection::from_cached(
-                                    &host,
-                                    port,
-                                    c.clone(),
-                                ),
-                                IpOutcome::Failure,
-                            );
-                        }
-                    }
-                    continue;
-                }
-                Err(_) => {
-                    errors.push(format!("connect timeout to {}", connect_addr));
-                    if is_pool_candidate {
+            let mut tls_ms: u32 = 0;
+            let mut fake_sni = false;
+            let connect_ms: u32;
+
+            let sender_opt = if let Some(raced) = raced_conn {
+                // 竞速胜出的连接已完成 TCP + TLS，直接进行 HTTP 握手
+                connect_ms = raced.connect_ms;
+                tls_ms = raced.tls_ms;
+                fake_sni = raced.fake_sni;
+                match http_handshake(raced.stream).await {
+                    Ok(sender) => Some(sender),
+                    Err(e) => {
+                        errors.push(format!("http handshake error with {}: {}", connect_addr, e));
                         if let Some(c) = &current_stat {
                             report_outcome_async(
                                 crate::core::ip_pool::IpSelection::from_cached(
@@ -160,76 +290,26 @@ impl HttpClient {
                                 IpOutcome::Failure,
                             );
                         }
+                        None
                     }
-                    continue;
                 }
-            };
-            let connect_ms = start_connect.elapsed().as_millis() as u32;
-
-            // TLS / HTTP Handshake
-            // If HTTPS, we wrap in TLS then handshake.
-            // If HTTP, we handshake directly.
-
-            let mut tls_ms: u32 = 0;
-            let mut fake_sni = false;
-
-            let sender_opt = if is_https {
-                // TLS Handshake
-                let (sni_host_final, fake) = self.compute_sni_host(input.force_real_sni, &host);
-                fake_sni = fake;
-                let server_name = match ServerName::try_from(sni_host_final.as_str()) {
-                    Ok(sn) => sn,
-                    Err(e) => {
-                        errors.push(format!("invalid sni {}: {}", sni_host_final, e));
-                        continue;
+            } else {
+                let start_connect = Instant::now();
+                let connect = async {
+                    if routed && !is_pool_candidate {
+                        tunnel::connect(&self.cfg.proxy, &input.url, &host, port).await
+                    } else {
+                        TcpStream::connect((connect_addr.as_str(), port))
+                            .await
+                            .map(AsyncProxyStream::from)
                     }
                 };
+                let tcp_res = timeout(Duration::from_millis(input.timeout_ms), connect).await;
 
-                let start_tls = Instant::now();
-                let tls_config: Arc<ClientConfig> = if fake {
-                    Arc::new(create_client_config_with_expected_name(
-                        &self.cfg.tls,
-                        &host,
-                    ))
-                } else {
-                    self.tls_default.clone()
-                };
-                let tls = TlsConnector::from(tls_config);
-                match tls.connect(server_name, tcp).await {
-                    Ok(s) => {
-                        tls_ms = start_tls.elapsed().as_millis() as u32;
-                        match hyper::client::conn::handshake(s).await {
-                            Ok((sender, conn)) => {
-                                tokio::spawn(async move {
-                                    if let Err(e) = conn.await {
-                                        tracing::debug!(target = "http", "conn ended: {:?}", e);
-                                    }
-                                });
-                                Some(sender)
-                            }
-                            Err(e) => {
-                                errors.push(format!(
-                                    "http handshake error with {}: {}",
-                                    connect_addr, e
-                                ));
-                                if is_pool_candidate {
-                                    if let Some(c) = &current_stat {
-                                        report_outcome_async(
-                                            crate::core::ip_pool::IpSelection::from_cached(
-                                                &host,
-                                                port,
-                                                c.clone(),
-                                            ),
-                                            IpOutcome::Failure,
-                                        );
-                                    }
-                                }
-                                None
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        errors.push(format!("tls handshake error with {}: {}", connect_addr, e));
+                let tcp = match tcp_res {
+                    Ok(Ok(stream)) => stream,
+                    Ok(Err(e)) => {
+                        errors.push(format!("connect error to {}: {}", connect_addr, e));
                         if is_pool_candidate {
                             if let Some(c) = &current_stat {
                                 report_outcome_async(
@@ -242,21 +322,10 @@ impl HttpClient {
                                 );
                             }
                         }
-                        None // Signal fail
-                    }
-                }
-            } else {
-                match hyper::client::conn::handshake(tcp).await {
-                    Ok((sender, conn)) => {
-                        tokio::spawn(async move {
-                            if let Err(e) = conn.await {
-                                tracing::debug!(target = "http", "conn ended: {:?}", e);
-                            }
-                        });
-                        Some(sender)
+                        continue;
                     }
-                    Err(e) => {
-                        errors.push(format!("http handshake error with {}: {}", connect_addr, e));
+                    Err(_) => {
+                        errors.push(format!("connect timeout to {}", connect_addr));
                         if is_pool_candidate {
                             if let Some(c) = &current_stat {
                                 report_outcome_async(
@@ -269,7 +338,117 @@ impl HttpClient {
                                 );
                             }
                         }
-                        None
+                        continue;
+                    }
+                };
+                connect_ms = start_connect.elapsed().as_millis() as u32;
+
+                // TLS / HTTP Handshake
+                // If HTTPS, we wrap in TLS then handshake.
+                // If HTTP, we handshake directly.
+
+                if is_https {
+                    // TLS Handshake
+                    let (sni_host_final, fake) = self.compute_sni_host(input.force_real_sni, &host);
+                    fake_sni = fake;
+                    let server_name = match ServerName::try_from(sni_host_final.as_str()) {
+                        Ok(sn) => sn,
+                        Err(e) => {
+                            errors.push(format!("invalid sni {}: {}", sni_host_final, e));
+                            continue;
+                        }
+                    };
+
+                    let start_tls = Instant::now();
+                    let tls_config = match self.client_config_for(&host, fake) {
+                        Ok(c) => c,
+                        Err(e) => {
+                            errors.push(e);
+                            continue;
+                        }
+                    };
+                    let tls = TlsConnector::from(tls_config);
+                    match tls.connect(server_name, tcp).await {
+                        Ok(s) => {
+                            tls_ms = start_tls.elapsed().as_millis() as u32;
+                            match hyper::client::conn::handshake(s).await {
+                                Ok((sender, conn)) => {
+                                    tokio::spawn(async move {
+                                        if let Err(e) = conn.await {
+                                            tracing::debug!(target = "http", "conn ended: {:?}", e);
+                                        }
+                                    });
+                                    Some(sender)
+                                }
+                                Err(e) => {
+                                    errors.push(format!(
+                                        "http handshake error with {}: {}",
+                                        connect_addr, e
+                                    ));
+                                    if is_pool_candidate {
+                                        if let Some(c) = &current_stat {
+                                            report_outcome_async(
+                                                crate::core::ip_pool::IpSelection::from_cached(
+                                                    &host,
+                                                    port,
+                                                    c.clone(),
+                                                ),
+                                                IpOutcome::Failure,
+                                            );
+                                        }
+                                    }
+                                    None
+                                }
+                            }
+                        }
+                        Err(e) => {
+                            errors.push(format!(
+                                "tls handshake error with {}: {}",
+                                connect_addr,
+                                describe_handshake_error(&host, &e.to_string())
+                            ));
+                            if is_pool_candidate {
+                                if let Some(c) = &current_stat {
+                                    report_outcome_async(
+                                        crate::core::ip_pool::IpSelection::from_cached(
+                                            &host,
+                                            port,
+                                            c.clone(),
+                                        ),
+                                        IpOutcome::Failure,
+                                    );
+                                }
+                            }
+                            None // Signal fail
+                        }
+                    }
+                } else {
+                    match hyper::client::conn::handshake(tcp).await {
+                        Ok((sender, conn)) => {
+                            tokio::spawn(async move {
+                                if let Err(e) = conn.await {
+                                    tracing::debug!(target = "http", "conn ended: {:?}", e);
+                                }
+                            });
+                            Some(sender)
+                        }
+                        Err(e) => {
+                            errors
+                                .push(format!("http handshake error with {}: {}", connect_addr, e));
+                            if is_pool_candidate {
+                                if let Some(c) = &current_stat {
+                                    report_outcome_async(
+                                        crate::core::ip_pool::IpSelection::from_cached(
+                                            &host,
+                                            port,
+                                            c.clone(),
+                                        ),
+                                        IpOutcome::Failure,
+                                    );
+                                }
+                            }
+                            None
+                        }
                     }
                 }
             };
@@ -383,7 +562,11 @@ impl HttpClient {
                     break;
                 }
                 Err(e) => {
-                    errors.push(format!("send request error to {}: {}", connect_addr, e));
+                    errors.push(format!(
+                        "send request error to {}: {}",
+                        connect_addr,
+                        describe_handshake_error(&host, &e.to_string())
+                    ));
                     if is_pool_candidate {
                         if let Some(c) = &current_stat {
                             report_outcome_async(
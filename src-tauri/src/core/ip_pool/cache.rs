@@ -14,6 +14,8 @@ pub enum IpSource {
     History,
     UserStatic,
     Fallback,
+    /// 外部订阅的签名 IP 列表
+    Subscription,
 }
 
 /// IP 候选条目，记录来源与端口信息。
@@ -64,6 +66,29 @@ pub struct IpStat {
     /// 解析器来源信息标签。
     #[serde(default)]
     pub resolver_metadata: Vec<String>,
+    /// 多维质量指标（已与历史 EWMA 合并）；缺失时仅按延迟评分。
+    #[serde(default)]
+    pub quality: Option<IpQuality>,
+}
+
+/// 候选 IP 的多维质量指标：重复探测得到延迟 / 抖动 / 丢包，真实传输反馈吞吐量。
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct IpQuality {
+    #[serde(default)]
+    pub latency_ms: Option<u32>,
+    /// 相邻两次采样延迟差的平均值
+    #[serde(default)]
+    pub jitter_ms: Option<u32>,
+    /// 丢包率（千分比）
+    #[serde(default)]
+    pub loss_permille: Option<u16>,
+    /// 被动观测的下载吞吐量（KiB/s）
+    #[serde(default)]
+    pub throughput_kibps: Option<u32>,
+    /// 已合并的观测次数
+    #[serde(default)]
+    pub samples: u32,
 }
 
 impl IpStat {
@@ -76,6 +101,7 @@ impl IpStat {
             expires_at_epoch_ms: None,
             sources: vec![initial_source],
             resolver_metadata: Vec::new(),
+            quality: None,
         }
     }
 
@@ -1,28 +1,44 @@
 //! IP 池熔断器模块
 //!
-//! 为每个 IP 维护失败统计与熔断状态，当失败率超过阈值时自动触发熔断，
-//! 冷却期结束后自动恢复。熔断期间该 IP 将被临时拉黑，不参与候选选择。
+//! 按 (host, IP) 作用域维护失败统计与熔断状态：同一 IP 对 `github.com` 熔断不影响它服务
+//! `codeload.github.com`。失败率超过阈值时触发熔断，冷却期结束后进入半开状态，只放行
+//! `half_open_max_trials` 个试探请求；试探全部成功才恢复，任一失败则重新熔断。
+//! 不带主机的旧接口（如 [`CircuitBreaker::record_failure`]）作用于全局作用域，全局熔断对所有主机生效。
 
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     net::IpAddr,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
 };
 
+use serde::{Deserialize, Serialize};
+
 use super::preheat::current_epoch_ms;
 
+/// 全局作用域（不区分主机）使用的主机名
+pub const GLOBAL_SCOPE: &str = "";
+
+/// 半开试探发出后未回报结果的超时（毫秒），超时后视为试探丢失并重新放行
+const HALF_OPEN_TRIAL_TIMEOUT_MS: i64 = 30_000;
+
 /// 熔断器状态
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub enum CircuitState {
     /// 正常状态，允许使用该 IP
     Normal,
     /// 熔断打开状态，该 IP 被临时拉黑
     Open,
-    /// 冷却状态，等待自动恢复
+    /// 冷却状态，等待进入半开
     Cooldown,
+    /// 半开状态，只放行有限的试探请求
+    HalfOpen,
 }
 
-/// 单个 IP 的熔断状态跟踪
+/// 单个作用域的熔断状态跟踪
 #[derive(Debug, Clone)]
 struct IpCircuitState {
     /// 当前状态
@@ -39,6 +55,14 @@ struct IpCircuitState {
     window_start_ms: i64,
     /// 连续失败次数（不计时间窗口）
     consecutive_failures: u32,
+    /// 半开期间已放行的试探数
+    half_open_granted: u32,
+    /// 半开期间成功的试探数
+    half_open_successes: u32,
+    /// 最近一次放行试探的时间戳（毫秒）
+    half_open_granted_at_ms: i64,
+    /// 累计熔断次数
+    trip_count: u32,
 }
 
 impl Default for IpCircuitState {
@@ -51,6 +75,10 @@ impl Default for IpCircuitState {
             window_successes: 0,
             window_start_ms: current_epoch_ms(),
             consecutive_failures: 0,
+            half_open_granted: 0,
+            half_open_successes: 0,
+            half_open_granted_at_ms: 0,
+            trip_count: 0,
         }
     }
 }
@@ -75,8 +103,8 @@ impl IpCircuitState {
         false
     }
 
-    /// 检查是否应该从冷却期恢复
-    fn should_reset(&self, now_ms: i64, cooldown_ms: i64) -> bool {
+    /// 检查冷却期是否结束
+    fn cooldown_elapsed(&self, now_ms: i64, cooldown_ms: i64) -> bool {
         matches!(self.state, CircuitState::Cooldown) && now_ms >= self.cooldown_at_ms + cooldown_ms
     }
 
@@ -91,6 +119,43 @@ impl IpCircuitState {
     fn is_window_expired(&self, now_ms: i64, window_ms: i64) -> bool {
         now_ms >= self.window_start_ms + window_ms
     }
+
+    /// 冷却结束时转入半开；返回是否发生了转换
+    fn advance(&mut self, now_ms: i64, config: &CircuitBreakerConfig) -> bool {
+        if !self.cooldown_elapsed(now_ms, config.cooldown_ms()) {
+            return false;
+        }
+        self.state = CircuitState::HalfOpen;
+        self.half_open_granted = 0;
+        self.half_open_successes = 0;
+        self.half_open_granted_at_ms = 0;
+        true
+    }
+
+    /// 当前是否拒绝请求（不消耗试探名额）
+    fn blocks(&mut self, now_ms: i64, config: &CircuitBreakerConfig) -> bool {
+        match self.state {
+            CircuitState::Normal => false,
+            CircuitState::Open | CircuitState::Cooldown => true,
+            CircuitState::HalfOpen => {
+                if self.half_open_granted > self.half_open_successes
+                    && now_ms >= self.half_open_granted_at_ms + HALF_OPEN_TRIAL_TIMEOUT_MS
+                {
+                    // 未回报的试探视为丢失，归还名额
+                    self.half_open_granted = self.half_open_successes;
+                }
+                self.half_open_granted >= config.half_open_max_trials.max(1)
+            }
+        }
+    }
+
+    fn trip(&mut self, now_ms: i64) {
+        self.state = CircuitState::Open;
+        self.opened_at_ms = now_ms;
+        self.cooldown_at_ms = now_ms; // 进入冷却期
+        self.state = CircuitState::Cooldown;
+        self.trip_count = self.trip_count.saturating_add(1);
+    }
 }
 
 /// 熔断器配置
@@ -108,6 +173,8 @@ pub struct CircuitBreakerConfig {
     pub min_samples_in_window: u32,
     /// 冷却时间（秒）
     pub cooldown_seconds: u32,
+    /// 半开状态放行的试探请求数；全部成功才恢复
+    pub half_open_max_trials: u32,
 }
 
 impl Default for CircuitBreakerConfig {
@@ -119,6 +186,7 @@ impl Default for CircuitBreakerConfig {
             window_seconds: 60,
             min_samples_in_window: 5,
             cooldown_seconds: 300,
+            half_open_max_trials: 1,
         }
     }
 }
@@ -133,10 +201,42 @@ impl CircuitBreakerConfig {
     }
 }
 
+/// 非正常状态作用域的快照；用于持久化与前端展示
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CircuitBreakerRecord {
+    /// 主机；全局作用域为空字符串
+    #[serde(default)]
+    pub host: String,
+    pub ip: IpAddr,
+    pub state: CircuitState,
+    /// 最近一次熔断时间
+    pub opened_at_epoch_ms: i64,
+    #[serde(default)]
+    pub trip_count: u32,
+    #[serde(default)]
+    pub consecutive_failures: u32,
+    /// 冷却状态下进入半开的时间
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub half_open_at_epoch_ms: Option<i64>,
+    #[serde(default)]
+    pub half_open_trials: u32,
+    #[serde(default)]
+    pub half_open_successes: u32,
+}
+
+type ScopeKey = (String, IpAddr);
+
+fn scope_key(host: &str, ip: IpAddr) -> ScopeKey {
+    (host.trim().to_ascii_lowercase(), ip)
+}
+
 /// IP 熔断器管理器
 pub struct CircuitBreaker {
     config: Arc<Mutex<CircuitBreakerConfig>>,
-    states: Arc<Mutex<HashMap<IpAddr, IpCircuitState>>>,
+    states: Arc<Mutex<HashMap<ScopeKey, IpCircuitState>>>,
+    /// 熔断 / 恢复等状态变化后置位，供调用方决定是否持久化
+    dirty: AtomicBool,
 }
 
 impl CircuitBreaker {
@@ -145,6 +245,7 @@ impl CircuitBreaker {
         Self {
             config: Arc::new(Mutex::new(config)),
             states: Arc::new(Mutex::new(HashMap::new())),
+            dirty: AtomicBool::new(false),
         }
     }
 
@@ -163,73 +264,158 @@ impl CircuitBreaker {
             .unwrap_or_default()
     }
 
-    /// 检查 IP 是否被熔断（不可用）
-    pub fn is_tripped(&self, ip: IpAddr) -> bool {
-        let now_ms = current_epoch_ms();
-        let config = match self.config.lock() {
-            Ok(cfg) => cfg.clone(),
-            Err(_) => return false,
-        };
+    fn enabled_config(&self) -> Option<CircuitBreakerConfig> {
+        self.config
+            .lock()
+            .ok()
+            .map(|cfg| cfg.clone())
+            .filter(|cfg| cfg.enabled)
+    }
 
-        if !config.enabled {
+    /// 推进状态并判断作用域是否拒绝请求；`admit` 为真时在半开状态消耗一个试探名额
+    fn check_scope(
+        &self,
+        states: &mut HashMap<ScopeKey, IpCircuitState>,
+        key: &ScopeKey,
+        config: &CircuitBreakerConfig,
+        now_ms: i64,
+        admit: bool,
+    ) -> bool {
+        let Some(state) = states.get_mut(key) else {
             return false;
+        };
+        if state.advance(now_ms, config) {
+            self.dirty.store(true, Ordering::Relaxed);
+            tracing::info!(
+                target: "ip_pool",
+                host = %key.0,
+                ip = %key.1,
+                trials = config.half_open_max_trials.max(1),
+                "circuit breaker half-open: allowing trial requests"
+            );
         }
+        if state.blocks(now_ms, config) {
+            return true;
+        }
+        if admit && state.state == CircuitState::HalfOpen {
+            state.half_open_granted += 1;
+            state.half_open_granted_at_ms = now_ms;
+        }
+        false
+    }
 
-        let mut states = match self.states.lock() {
-            Ok(s) => s,
-            Err(_) => return false,
+    /// 检查 IP 在任一作用域内是否被熔断（不可用）
+    pub fn is_tripped(&self, ip: IpAddr) -> bool {
+        let Some(config) = self.enabled_config() else {
+            return false;
+        };
+        let now_ms = current_epoch_ms();
+        let Ok(mut states) = self.states.lock() else {
+            return false;
         };
+        let keys: Vec<ScopeKey> = states.keys().filter(|k| k.1 == ip).cloned().collect();
+        keys.iter()
+            .any(|key| self.check_scope(&mut states, key, &config, now_ms, false))
+    }
 
-        let state = states.entry(ip).or_default();
+    /// 检查 IP 对指定主机是否被熔断（含全局作用域），不消耗半开试探名额
+    pub fn is_tripped_for(&self, host: &str, ip: IpAddr) -> bool {
+        self.check_for(host, ip, false)
+    }
 
-        // 检查是否应该从冷却期恢复
-        if state.should_reset(now_ms, config.cooldown_ms()) {
-            crate::core::ip_pool::events::emit_ip_pool_ip_recovered(ip);
-            tracing::info!(
-                target: "ip_pool",
-                ip = %ip,
-                "circuit breaker reset: ip recovered from cooldown"
-            );
-            *state = IpCircuitState::default();
+    /// 请求使用 IP 访问主机：被熔断时返回 false；半开状态下放行并占用一个试探名额
+    pub fn admit(&self, host: &str, ip: IpAddr) -> bool {
+        !self.check_for(host, ip, true)
+    }
+
+    fn check_for(&self, host: &str, ip: IpAddr, admit: bool) -> bool {
+        let Some(config) = self.enabled_config() else {
             return false;
+        };
+        let now_ms = current_epoch_ms();
+        let Ok(mut states) = self.states.lock() else {
+            return false;
+        };
+        let global = scope_key(GLOBAL_SCOPE, ip);
+        if self.check_scope(&mut states, &global, &config, now_ms, admit) {
+            return true;
         }
-
-        matches!(state.state, CircuitState::Open | CircuitState::Cooldown)
+        let scoped = scope_key(host, ip);
+        scoped != global && self.check_scope(&mut states, &scoped, &config, now_ms, admit)
     }
 
-    /// 记录成功结果
+    /// 记录成功结果（全局作用域）
     pub fn record_success(&self, ip: IpAddr) {
-        let now_ms = current_epoch_ms();
-        self.record_outcome(ip, true, now_ms);
+        self.record_outcome(GLOBAL_SCOPE, ip, true, current_epoch_ms());
     }
 
-    /// 记录失败结果
+    /// 记录失败结果（全局作用域）
     pub fn record_failure(&self, ip: IpAddr) {
-        let now_ms = current_epoch_ms();
-        self.record_outcome(ip, false, now_ms);
+        self.record_outcome(GLOBAL_SCOPE, ip, false, current_epoch_ms());
     }
 
-    /// 记录结果并检查是否触发熔断
-    fn record_outcome(&self, ip: IpAddr, success: bool, now_ms: i64) {
-        let config = match self.config.lock() {
-            Ok(cfg) => cfg.clone(),
-            Err(_) => return,
-        };
+    /// 记录 IP 访问指定主机成功
+    pub fn record_success_for(&self, host: &str, ip: IpAddr) {
+        self.record_outcome(host, ip, true, current_epoch_ms());
+    }
+
+    /// 记录 IP 访问指定主机失败
+    pub fn record_failure_for(&self, host: &str, ip: IpAddr) {
+        self.record_outcome(host, ip, false, current_epoch_ms());
+    }
 
-        if !config.enabled {
+    /// 记录结果并检查是否触发熔断
+    fn record_outcome(&self, host: &str, ip: IpAddr, success: bool, now_ms: i64) {
+        let Some(config) = self.enabled_config() else {
             return;
-        }
+        };
 
         let mut states = match self.states.lock() {
             Ok(s) => s,
             Err(_) => return,
         };
 
-        let state = states.entry(ip).or_default();
+        let key = scope_key(host, ip);
+        let state = states.entry(key.clone()).or_default();
+        if state.advance(now_ms, &config) {
+            self.dirty.store(true, Ordering::Relaxed);
+        }
 
-        // 如果已经熔断，不再更新统计
-        if matches!(state.state, CircuitState::Open | CircuitState::Cooldown) {
-            return;
+        match state.state {
+            // 如果已经熔断，不再更新统计
+            CircuitState::Open | CircuitState::Cooldown => return,
+            CircuitState::HalfOpen => {
+                if success {
+                    state.half_open_successes += 1;
+                    state.half_open_granted =
+                        state.half_open_granted.max(state.half_open_successes);
+                    if state.half_open_successes >= config.half_open_max_trials.max(1) {
+                        let trip_count = state.trip_count;
+                        *state = IpCircuitState {
+                            trip_count,
+                            ..IpCircuitState::default()
+                        };
+                        self.dirty.store(true, Ordering::Relaxed);
+                        crate::core::ip_pool::events::emit_ip_pool_ip_recovered(ip);
+                        tracing::info!(
+                            target: "ip_pool",
+                            host = %key.0,
+                            ip = %ip,
+                            "circuit breaker closed: half-open trials succeeded"
+                        );
+                    }
+                } else {
+                    state.consecutive_failures = state.consecutive_failures.saturating_add(1);
+                    state.trip(now_ms);
+                    self.dirty.store(true, Ordering::Relaxed);
+                    crate::core::ip_pool::events::emit_ip_pool_ip_tripped(
+                        ip,
+                        &trip_reason(&key.0, "half-open trial failed"),
+                    );
+                }
+                return;
+            }
+            CircuitState::Normal => {}
         }
 
         // 检查窗口是否过期，需要重置
@@ -248,37 +434,41 @@ impl CircuitBreaker {
 
         // 检查是否应该触发熔断
         if state.should_trip(&config) {
-            crate::core::ip_pool::events::emit_ip_pool_ip_tripped(ip, "circuit breaker tripped");
+            crate::core::ip_pool::events::emit_ip_pool_ip_tripped(
+                ip,
+                &trip_reason(&key.0, "circuit breaker tripped"),
+            );
             tracing::warn!(
                 target: "ip_pool",
+                host = %key.0,
                 ip = %ip,
                 consecutive_failures = state.consecutive_failures,
                 window_failures = state.window_failures,
                 window_successes = state.window_successes,
                 "circuit breaker tripped: ip temporarily blacklisted"
             );
-
-            state.state = CircuitState::Open;
-            state.opened_at_ms = now_ms;
-            state.cooldown_at_ms = now_ms; // 进入冷却期
-            state.state = CircuitState::Cooldown;
+            state.trip(now_ms);
+            self.dirty.store(true, Ordering::Relaxed);
         }
     }
 
-    /// 手动重置指定 IP 的熔断状态
+    /// 手动重置指定 IP 在所有作用域的熔断状态
     pub fn reset_ip(&self, ip: IpAddr) {
         if let Ok(mut states) = self.states.lock() {
-            if let Some(state) = states.get_mut(&ip) {
-                let was_tripped =
-                    matches!(state.state, CircuitState::Open | CircuitState::Cooldown);
-                *state = IpCircuitState::default();
-                if was_tripped {
-                    tracing::info!(
-                        target: "ip_pool",
-                        ip = %ip,
-                        "circuit breaker manually reset"
-                    );
-                }
+            let before = states.len();
+            let was_tripped = states
+                .iter()
+                .any(|(k, s)| k.1 == ip && s.state != CircuitState::Normal);
+            states.retain(|k, _| k.1 != ip);
+            if states.len() != before {
+                self.dirty.store(true, Ordering::Relaxed);
+            }
+            if was_tripped {
+                tracing::info!(
+                    target: "ip_pool",
+                    ip = %ip,
+                    "circuit breaker manually reset"
+                );
             }
         }
     }
@@ -289,6 +479,7 @@ impl CircuitBreaker {
             let count = states.len();
             states.clear();
             if count > 0 {
+                self.dirty.store(true, Ordering::Relaxed);
                 tracing::info!(
                     target: "ip_pool",
                     cleared_count = count,
@@ -298,10 +489,15 @@ impl CircuitBreaker {
         }
     }
 
-    /// 获取指定 IP 的统计信息（用于测试和观测）
+    /// 获取指定 IP 全局作用域的统计信息（用于测试和观测）
     pub fn get_stats(&self, ip: IpAddr) -> Option<CircuitStats> {
+        self.get_stats_for(GLOBAL_SCOPE, ip)
+    }
+
+    /// 获取指定 (host, IP) 作用域的统计信息
+    pub fn get_stats_for(&self, host: &str, ip: IpAddr) -> Option<CircuitStats> {
         let states = self.states.lock().ok()?;
-        let state = states.get(&ip)?;
+        let state = states.get(&scope_key(host, ip))?;
         Some(CircuitStats {
             state: state.state,
             consecutive_failures: state.consecutive_failures,
@@ -310,18 +506,82 @@ impl CircuitBreaker {
         })
     }
 
-    /// 获取所有被熔断的 IP 列表
+    /// 获取在任一作用域内被熔断的 IP 列表
     pub fn get_tripped_ips(&self) -> Vec<IpAddr> {
         let states = match self.states.lock() {
             Ok(s) => s,
             Err(_) => return Vec::new(),
         };
 
-        states
+        let tripped: HashSet<IpAddr> = states
             .iter()
             .filter(|(_, state)| matches!(state.state, CircuitState::Open | CircuitState::Cooldown))
-            .map(|(ip, _)| *ip)
-            .collect()
+            .map(|(key, _)| key.1)
+            .collect();
+        tripped.into_iter().collect()
+    }
+
+    /// 所有非正常状态作用域的快照（按主机、IP 排序）
+    pub fn snapshot(&self) -> Vec<CircuitBreakerRecord> {
+        let cooldown_ms = self.get_config().cooldown_ms();
+        let Ok(states) = self.states.lock() else {
+            return Vec::new();
+        };
+        let mut records: Vec<CircuitBreakerRecord> = states
+            .iter()
+            .filter(|(_, s)| s.state != CircuitState::Normal)
+            .map(|((host, ip), s)| CircuitBreakerRecord {
+                host: host.clone(),
+                ip: *ip,
+                state: s.state,
+                opened_at_epoch_ms: s.cooldown_at_ms,
+                trip_count: s.trip_count,
+                consecutive_failures: s.consecutive_failures,
+                half_open_at_epoch_ms: matches!(s.state, CircuitState::Cooldown)
+                    .then_some(s.cooldown_at_ms + cooldown_ms),
+                half_open_trials: s.half_open_granted,
+                half_open_successes: s.half_open_successes,
+            })
+            .collect();
+        records.sort_by(|a, b| a.host.cmp(&b.host).then(a.ip.cmp(&b.ip)));
+        records
+    }
+
+    /// 从持久化快照恢复状态（覆盖同作用域的现有状态）。半开试探进度不恢复，重新开始试探。
+    pub fn restore(&self, records: &[CircuitBreakerRecord]) {
+        let Ok(mut states) = self.states.lock() else {
+            return;
+        };
+        for record in records {
+            if record.state == CircuitState::Normal {
+                continue;
+            }
+            let state = IpCircuitState {
+                state: match record.state {
+                    CircuitState::HalfOpen => CircuitState::HalfOpen,
+                    _ => CircuitState::Cooldown,
+                },
+                opened_at_ms: record.opened_at_epoch_ms,
+                cooldown_at_ms: record.opened_at_epoch_ms,
+                consecutive_failures: record.consecutive_failures,
+                trip_count: record.trip_count,
+                ..IpCircuitState::default()
+            };
+            states.insert(scope_key(&record.host, record.ip), state);
+        }
+    }
+
+    /// 取出并清除“状态已变化”标记
+    pub fn take_dirty(&self) -> bool {
+        self.dirty.swap(false, Ordering::Relaxed)
+    }
+}
+
+fn trip_reason(host: &str, reason: &str) -> String {
+    if host.is_empty() {
+        reason.to_string()
+    } else {
+        format!("{reason} for {host}")
     }
 }
 
@@ -30,7 +30,7 @@ fn default_probe_path() -> String {
     "/".to_string()
 }
 
-/// 延迟探测方法：HTTP 应用层协议测试或 TCP 握手测试
+/// 延迟探测方法：HTTP 应用层协议测试、TCP 握手测试或完整 TLS 握手测试
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum ProbeMethod {
@@ -39,6 +39,21 @@ pub enum ProbeMethod {
     Http,
     /// TCP 握手延迟测试（传统方式，TUN 模式下可能不准确）
     Tcp,
+    /// 按实际 SNI 策略完成 TLS 握手并校验真实主机证书；证书不匹配的 IP 被标记为不可用
+    Tls,
+}
+
+/// IPv6 候选策略
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Ipv6Mode {
+    /// 与 IPv4 同等对待，按延迟排序（默认）
+    #[default]
+    Allow,
+    /// 优先 IPv6：排序时 IPv6 候选享有 `ipv6_bias_ms` 的延迟优惠
+    Prefer,
+    /// 不解析 AAAA，并丢弃所有 IPv6 候选
+    Forbid,
 }
 
 const IP_CONFIG_FILE_NAME: &str = "ip-config.json";
@@ -88,12 +103,123 @@ pub struct IpPoolRuntimeConfig {
     /// 熔断器：是否启用。
     #[serde(default = "default_true")]
     pub circuit_breaker_enabled: bool,
+    /// 熔断器：半开状态放行的试探请求数。
+    #[serde(default = "default_half_open_max_trials")]
+    pub half_open_max_trials: u32,
     /// 延迟探测方法：HTTP（默认，TUN 兼容）或 TCP
     #[serde(default)]
     pub probe_method: ProbeMethod,
     /// HTTP 探测路径（默认 "/"）
     #[serde(default = "default_probe_path")]
     pub probe_path: String,
+    /// 多候选连接竞速（RFC 8305 Happy Eyeballs）
+    #[serde(default)]
+    pub racing: ConnectionRacingConfig,
+    /// IPv6 候选策略：优先 / 允许 / 禁止
+    #[serde(default)]
+    pub ipv6: Ipv6Mode,
+    /// `Prefer` 模式下 IPv6 候选的延迟优惠（毫秒）
+    #[serde(default = "default_ipv6_bias_ms")]
+    pub ipv6_bias_ms: u32,
+    /// 综合评分：重复采样与各维度权重
+    #[serde(default)]
+    pub scoring: ScoringConfig,
+}
+
+/// 综合评分配置。得分以毫秒为单位（越小越好）：
+/// `延迟 × latency_weight + 抖动 × jitter_weight + 丢包% × loss_weight − 吞吐(MiB/s) × throughput_weight`。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ScoringConfig {
+    /// 每个候选每轮探测的采样次数（1 表示单次探测，不计算抖动与丢包）
+    #[serde(default = "default_probe_samples")]
+    pub samples: u32,
+    /// 历史 EWMA 平滑系数（0-1，越大越偏向最新观测）
+    #[serde(default = "default_ewma_alpha")]
+    pub ewma_alpha: f64,
+    #[serde(default = "default_latency_weight")]
+    pub latency_weight: f64,
+    #[serde(default = "default_jitter_weight")]
+    pub jitter_weight: f64,
+    /// 每 1% 丢包折算的毫秒数
+    #[serde(default = "default_loss_weight")]
+    pub loss_weight: f64,
+    /// 每 MiB/s 吞吐量抵扣的毫秒数
+    #[serde(default = "default_throughput_weight")]
+    pub throughput_weight: f64,
+}
+
+pub fn default_probe_samples() -> u32 {
+    3
+}
+
+pub fn default_ewma_alpha() -> f64 {
+    0.3
+}
+
+pub fn default_latency_weight() -> f64 {
+    1.0
+}
+
+pub fn default_jitter_weight() -> f64 {
+    1.0
+}
+
+pub fn default_loss_weight() -> f64 {
+    20.0
+}
+
+pub fn default_throughput_weight() -> f64 {
+    10.0
+}
+
+impl Default for ScoringConfig {
+    fn default() -> Self {
+        Self {
+            samples: default_probe_samples(),
+            ewma_alpha: default_ewma_alpha(),
+            latency_weight: default_latency_weight(),
+            jitter_weight: default_jitter_weight(),
+            loss_weight: default_loss_weight(),
+            throughput_weight: default_throughput_weight(),
+        }
+    }
+}
+
+/// 连接竞速：按间隔错峰向前 N 个候选发起连接，首个完成 TLS 握手的连接胜出。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionRacingConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 参与竞速的候选数上限
+    #[serde(default = "default_racing_max_attempts")]
+    pub max_attempts: usize,
+    /// 相邻两次发起之间的错峰间隔（毫秒，RFC 8305 建议 250）
+    #[serde(default = "default_racing_attempt_delay_ms")]
+    pub attempt_delay_ms: u64,
+}
+
+pub fn default_racing_max_attempts() -> usize {
+    4
+}
+
+pub fn default_racing_attempt_delay_ms() -> u64 {
+    250
+}
+
+impl Default for ConnectionRacingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_attempts: default_racing_max_attempts(),
+            attempt_delay_ms: default_racing_attempt_delay_ms(),
+        }
+    }
+}
+
+pub fn default_ipv6_bias_ms() -> u32 {
+    50
 }
 
 pub fn default_probe_timeout_ms() -> u64 {
@@ -132,6 +258,10 @@ pub fn default_cooldown_seconds() -> u32 {
     300
 }
 
+pub fn default_half_open_max_trials() -> u32 {
+    1
+}
+
 impl Default for IpPoolRuntimeConfig {
     fn default() -> Self {
         Self {
@@ -150,8 +280,13 @@ impl Default for IpPoolRuntimeConfig {
             min_samples_in_window: default_min_samples_in_window(),
             cooldown_seconds: default_cooldown_seconds(),
             circuit_breaker_enabled: true,
+            half_open_max_trials: default_half_open_max_trials(),
             probe_method: ProbeMethod::default(),
             probe_path: default_probe_path(),
+            racing: ConnectionRacingConfig::default(),
+            ipv6: Ipv6Mode::default(),
+            ipv6_bias_ms: default_ipv6_bias_ms(),
+            scoring: ScoringConfig::default(),
         }
     }
 }
@@ -170,6 +305,8 @@ pub struct IpPoolSourceToggle {
     pub user_static: bool,
     #[serde(default = "default_true")]
     pub fallback: bool,
+    #[serde(default = "default_true")]
+    pub subscription: bool,
 }
 
 impl Default for IpPoolSourceToggle {
@@ -180,6 +317,7 @@ impl Default for IpPoolSourceToggle {
             history: true,
             user_static: true,
             fallback: true,
+            subscription: true,
         }
     }
 }
@@ -515,6 +653,9 @@ pub struct IpPoolFileConfig {
     /// 禁用内置预热域名列表（按域名匹配，不区分大小写）
     #[serde(default)]
     pub disabled_builtin_preheat: Vec<String>,
+    /// 外部签名 IP 列表订阅
+    #[serde(default)]
+    pub subscriptions: Vec<IpSubscription>,
 }
 
 impl Default for IpPoolFileConfig {
@@ -526,6 +667,7 @@ impl Default for IpPoolFileConfig {
             blacklist: Vec::new(),
             whitelist: Vec::new(),
             disabled_builtin_preheat: Vec::new(),
+            subscriptions: Vec::new(),
         }
     }
 }
@@ -548,7 +690,7 @@ impl PreheatDomain {
     }
 }
 
-/// 用户静态 IP 配置，允许针对特定域名写入固定 IP。
+/// 用户静态 IP 配置，允许针对特定域名写入固定 IP（IPv4 或 IPv6，IPv6 可带方括号）。
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "camelCase")]
 pub struct UserStaticIp {
@@ -558,6 +700,30 @@ pub struct UserStaticIp {
     pub ports: Vec<u16>,
 }
 
+/// IP 列表订阅：从本地文件或 HTTPS URL 定期加载 Ed25519 签名的候选列表。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct IpSubscription {
+    /// 订阅名，同时用作事件与候选标签
+    pub name: String,
+    /// 本地文件路径（可带 `file://`）或 `https://` URL
+    pub url: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 签名公钥（Ed25519 原始 32 字节，Base64）
+    pub public_key: String,
+    /// 允许列表声明的域名（支持 `*.` 通配）；为空时不采纳任何条目
+    #[serde(default)]
+    pub allowed_domains: Vec<String>,
+    /// 刷新间隔（秒，最小 60）
+    #[serde(default = "default_subscription_refresh_secs")]
+    pub refresh_interval_secs: u64,
+}
+
+pub fn default_subscription_refresh_secs() -> u64 {
+    6 * 60 * 60
+}
+
 /// 组合后的生效配置，便于 `IpPool` 管理运行期与外部文件配置。
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 #[serde(rename_all = "camelCase")]
@@ -12,7 +12,8 @@ use trust_dns_resolver::{
 };
 use url::Url;
 
-use super::config::{DnsResolverConfig, DnsResolverProtocol, DnsRuntimeConfig};
+use super::config::{DnsResolverConfig, DnsResolverProtocol, DnsRuntimeConfig, Ipv6Mode};
+use super::family::{family_allowed, normalize_ip};
 
 /// 单条 DNS 解析结果，包含 IP 与来源标签。
 #[derive(Debug, Clone)]
@@ -36,14 +37,25 @@ const DNS_TOTAL_TIMEOUT_MS: u64 = 4_000;
 const DNS_MIN_CUSTOM_SUCCESSES: usize = 1;
 const DNS_MAX_CONSECUTIVE_ERRORS: usize = 2;
 
-/// 根据运行期配置解析域名，支持系统解析及自定义 DoH/DoT/UDP 解析器。
+/// 根据运行期配置解析域名（同时查询 A 与 AAAA），支持系统解析及自定义 DoH/DoT/UDP 解析器。
 pub async fn resolve(host: &str, port: u16, cfg: &DnsRuntimeConfig) -> Result<Vec<DnsResolvedIp>> {
+    resolve_with_family(host, port, cfg, Ipv6Mode::Allow).await
+}
+
+/// 按 IPv6 策略解析：`Forbid` 时自定义解析器只查询 A 记录，系统解析结果中的 IPv6 地址被丢弃。
+pub async fn resolve_with_family(
+    host: &str,
+    port: u16,
+    cfg: &DnsRuntimeConfig,
+    mode: Ipv6Mode,
+) -> Result<Vec<DnsResolvedIp>> {
     let mut results: Vec<DnsResolvedIp> = Vec::new();
+    let strategy = lookup_strategy(mode);
 
     if cfg.use_system {
         match resolve_system(host, port).await {
             Ok(ips) => {
-                for ip in ips {
+                for ip in ips.into_iter().filter(|ip| family_allowed(mode, *ip)) {
                     results.push(DnsResolvedIp::new(ip, Some(SYSTEM_LABEL.to_string())));
                 }
             }
@@ -109,7 +121,7 @@ pub async fn resolve(host: &str, port: u16, cfg: &DnsRuntimeConfig) -> Result<Ve
                 let tag = entry.display_tag();
                 let label = entry.label.clone();
                 let protocol = entry.protocol.clone();
-                match resolve_with_custom(&host, &entry).await {
+                match resolve_with_custom(&host, &entry, strategy).await {
                     Ok(ips) => Ok((tag, ips)),
                     Err(err) => Err((label, protocol, err)),
                 }
@@ -203,29 +215,53 @@ pub async fn resolve(host: &str, port: u16, cfg: &DnsRuntimeConfig) -> Result<Ve
 
     results.sort_by(|a, b| a.ip.cmp(&b.ip).then_with(|| a.label.cmp(&b.label)));
     results.dedup_by(|a, b| a.ip == b.ip && a.label == b.label);
+    let v6 = results.iter().filter(|r| r.ip.is_ipv6()).count();
+    tracing::debug!(
+        target = "ip_pool",
+        host,
+        ipv4 = results.len() - v6,
+        ipv6 = v6,
+        mode = ?mode,
+        "dns resolution finished"
+    );
     Ok(results)
 }
 
+/// IPv6 策略对应的记录查询方式
+fn lookup_strategy(mode: Ipv6Mode) -> LookupIpStrategy {
+    match mode {
+        Ipv6Mode::Forbid => LookupIpStrategy::Ipv4Only,
+        Ipv6Mode::Allow | Ipv6Mode::Prefer => LookupIpStrategy::Ipv4AndIpv6,
+    }
+}
+
 async fn resolve_system(host: &str, port: u16) -> Result<Vec<IpAddr>> {
     let mut ips: Vec<IpAddr> = lookup_host((host, port))
         .await?
-        .map(|addr| addr.ip())
+        .map(|addr| normalize_ip(addr.ip()))
         .collect();
     ips.sort();
     ips.dedup();
     Ok(ips)
 }
 
-async fn resolve_with_custom(host: &str, entry: &DnsResolverConfig) -> Result<Vec<IpAddr>> {
-    let resolver = build_resolver(entry).await?;
+async fn resolve_with_custom(
+    host: &str,
+    entry: &DnsResolverConfig,
+    strategy: LookupIpStrategy,
+) -> Result<Vec<IpAddr>> {
+    let resolver = build_resolver(entry, strategy).await?;
     let response = resolver.lookup_ip(host).await?;
-    let mut ips: Vec<IpAddr> = response.iter().collect();
+    let mut ips: Vec<IpAddr> = response.iter().map(normalize_ip).collect();
     ips.sort();
     ips.dedup();
     Ok(ips)
 }
 
-async fn build_resolver(entry: &DnsResolverConfig) -> Result<TokioAsyncResolver> {
+async fn build_resolver(
+    entry: &DnsResolverConfig,
+    strategy: LookupIpStrategy,
+) -> Result<TokioAsyncResolver> {
     let resolver_config = match entry.protocol {
         DnsResolverProtocol::Udp => build_udp_config(entry).await?,
         DnsResolverProtocol::Dot => build_dot_config(entry).await?,
@@ -233,7 +269,7 @@ async fn build_resolver(entry: &DnsResolverConfig) -> Result<TokioAsyncResolver>
     };
 
     let mut opts = ResolverOpts::default();
-    opts.ip_strategy = LookupIpStrategy::Ipv4AndIpv6;
+    opts.ip_strategy = strategy;
     opts.cache_size = entry.cache_size.unwrap_or(0);
     opts.attempts = DNS_MAX_ATTEMPTS;
     opts.timeout = Duration::from_millis(DNS_REQUEST_TIMEOUT_MS);
@@ -48,6 +48,23 @@ pub fn emit_ip_pool_auto_disable(reason: &str, until_ms: i64) {
     }));
 }
 
+/// Emit `IpPoolSubscriptionUpdate` event after an IP list subscription refresh attempt.
+pub fn emit_ip_pool_subscription_update(
+    name: &str,
+    success: bool,
+    entries: usize,
+    rejected: usize,
+    error: Option<String>,
+) {
+    publish_global(Event::Strategy(StrategyEvent::IpPoolSubscriptionUpdate {
+        name: name.to_string(),
+        success,
+        entries: entries.min(u32::MAX as usize) as u32,
+        rejected: rejected.min(u32::MAX as usize) as u32,
+        error,
+    }));
+}
+
 /// Emit `IpPoolAutoEnable` event when the pool is auto-enabled after cooldown.
 pub fn emit_ip_pool_auto_enable() {
     tracing::info!(target = "ip_pool", "ip pool auto-enable after cooldown");
@@ -0,0 +1,45 @@
+//! 地址族处理：IPv6 策略过滤、按地址族评分与 IP 文本规范化。
+//!
+//! IPv4 映射地址（`::ffff:a.b.c.d`）统一还原为 IPv4，保证去重、CIDR 名单与熔断器按同一地址生效。
+
+use std::net::IpAddr;
+
+use super::config::Ipv6Mode;
+
+/// 规范化地址：IPv4 映射的 IPv6 地址还原为 IPv4。
+pub fn normalize_ip(ip: IpAddr) -> IpAddr {
+    ip.to_canonical()
+}
+
+/// 解析用户填写的 IP 文本，允许 IPv6 带方括号（如 `[2001:db8::1]`）。
+pub fn parse_ip_literal(raw: &str) -> Option<IpAddr> {
+    let trimmed = raw.trim();
+    let bare = trimmed
+        .strip_prefix('[')
+        .and_then(|s| s.strip_suffix(']'))
+        .unwrap_or(trimmed);
+    bare.parse::<IpAddr>().ok().map(normalize_ip)
+}
+
+/// 地址族标签，用于日志与事件
+pub fn family_label(ip: IpAddr) -> &'static str {
+    if normalize_ip(ip).is_ipv6() {
+        "ipv6"
+    } else {
+        "ipv4"
+    }
+}
+
+/// 当前策略下是否采纳该地址
+pub fn family_allowed(mode: Ipv6Mode, ip: IpAddr) -> bool {
+    !(mode == Ipv6Mode::Forbid && normalize_ip(ip).is_ipv6())
+}
+
+/// 地址族对排序得分的抵扣（毫秒）：仅 `Prefer` 模式下的 IPv6 候选享有 `bias_ms`。
+pub fn family_bias(mode: Ipv6Mode, bias_ms: u32, ip: IpAddr) -> u32 {
+    if mode == Ipv6Mode::Prefer && normalize_ip(ip).is_ipv6() {
+        bias_ms
+    } else {
+        0
+    }
+}
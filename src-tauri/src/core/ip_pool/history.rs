@@ -1,5 +1,6 @@
 use std::{
     fs,
+    net::IpAddr,
     path::{Path, PathBuf},
     sync::Mutex,
 };
@@ -7,9 +8,17 @@ use std::{
 use anyhow::{anyhow, Context, Result};
 use serde::{Deserialize, Serialize};
 
-use super::{IpCandidate, IpSource};
+use super::circuit_breaker::CircuitBreakerRecord;
+use super::scoring::blend_quality;
+use super::{IpCandidate, IpQuality, IpSource};
 
 const IP_HISTORY_FILE_NAME: &str = "ip-history.json";
+/// 质量记录保留时长：超过该时长未更新的记录在清理时移除
+const QUALITY_RETENTION_MS: i64 = 7 * 24 * 60 * 60 * 1000;
+/// 导入合并时视为“同样新鲜”的测量时间差，窗口内按延迟择优
+const IMPORT_FRESHNESS_WINDOW_MS: i64 = 10 * 60 * 1000;
+/// 导出格式版本
+pub const IP_HISTORY_EXPORT_VERSION: u32 = 1;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
@@ -24,6 +33,9 @@ pub struct IpHistoryRecord {
     pub expires_at_epoch_ms: i64,
     #[serde(default)]
     pub resolver_metadata: Vec<String>,
+    /// 由导入得到、尚未经本机探测确认的记录；预热重新探测后清除
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pending_validation: bool,
 }
 
 impl IpHistoryRecord {
@@ -32,11 +44,59 @@ impl IpHistoryRecord {
     }
 }
 
+/// 单个 (host, port, ip) 的 EWMA 质量历史
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct IpQualityRecord {
+    pub host: String,
+    pub port: u16,
+    pub ip: IpAddr,
+    pub quality: IpQuality,
+    pub updated_at_epoch_ms: i64,
+    /// 被标记为不可用（如证书与真实主机不匹配）的截止时间
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unusable_until_epoch_ms: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unusable_reason: Option<String>,
+}
+
+/// 可在机器之间共享的历史快照（导出文件与团队模板中的 `ipHistory` 段）
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct IpHistoryExport {
+    #[serde(default)]
+    pub version: u32,
+    #[serde(default)]
+    pub exported_at_epoch_ms: i64,
+    #[serde(default)]
+    pub entries: Vec<IpHistoryRecord>,
+    #[serde(default)]
+    pub quality: Vec<IpQualityRecord>,
+}
+
+/// 导入结果统计
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct IpHistoryImportReport {
+    /// 新增或替换本地记录的条目数（均待预热确认）
+    pub imported: usize,
+    /// 本地记录更新或更快而保留本地的条目数
+    pub kept_local: usize,
+    /// 已过期或字段非法而丢弃的条目数
+    pub discarded: usize,
+    /// 合并进来的质量记录数
+    pub quality_merged: usize,
+}
+
 #[derive(Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 struct IpHistoryFile {
     #[serde(default)]
     entries: Vec<IpHistoryRecord>,
+    #[serde(default)]
+    quality: Vec<IpQualityRecord>,
+    #[serde(default)]
+    breakers: Vec<CircuitBreakerRecord>,
 }
 
 #[derive(Debug)]
@@ -184,6 +244,280 @@ impl IpHistoryStore {
         None
     }
 
+    /// 读取某个候选的 EWMA 质量历史
+    pub fn quality(&self, host: &str, port: u16, ip: IpAddr) -> Option<IpQuality> {
+        self.inner.lock().ok().and_then(|guard| {
+            guard
+                .quality
+                .iter()
+                .find(|q| q.host == host && q.port == port && q.ip == ip)
+                .map(|q| q.quality)
+        })
+    }
+
+    /// 将一批观测按 EWMA 合并进质量历史并持久化，返回合并后的值（与输入顺序一致）。
+    pub fn fold_quality(
+        &self,
+        host: &str,
+        port: u16,
+        samples: &[(IpAddr, IpQuality)],
+        alpha: f64,
+        now_epoch_ms: i64,
+    ) -> Result<Vec<IpQuality>> {
+        let mut guard = self
+            .inner
+            .lock()
+            .map_err(|_| anyhow!("ip history poisoned"))?;
+        let mut merged = Vec::with_capacity(samples.len());
+        for (ip, sample) in samples {
+            let existing = guard
+                .quality
+                .iter_mut()
+                .find(|q| q.host == host && q.port == port && q.ip == *ip);
+            let blended = blend_quality(existing.as_deref().map(|q| &q.quality), sample, alpha);
+            match existing {
+                Some(record) => {
+                    record.quality = blended;
+                    record.updated_at_epoch_ms = now_epoch_ms;
+                }
+                None => guard.quality.push(IpQualityRecord {
+                    host: host.to_string(),
+                    port,
+                    ip: *ip,
+                    quality: blended,
+                    updated_at_epoch_ms: now_epoch_ms,
+                    unusable_until_epoch_ms: None,
+                    unusable_reason: None,
+                }),
+            }
+            merged.push(blended);
+        }
+        Self::persist(self.path.as_deref(), &guard)?;
+        Ok(merged)
+    }
+
+    /// 将候选标记为不可用直到 `until_epoch_ms`，期间预热与按需采样不再探测它。
+    pub fn mark_unusable(
+        &self,
+        host: &str,
+        port: u16,
+        ip: IpAddr,
+        reason: &str,
+        until_epoch_ms: i64,
+        now_epoch_ms: i64,
+    ) -> Result<()> {
+        let mut guard = self
+            .inner
+            .lock()
+            .map_err(|_| anyhow!("ip history poisoned"))?;
+        let idx = match guard
+            .quality
+            .iter()
+            .position(|q| q.host == host && q.port == port && q.ip == ip)
+        {
+            Some(idx) => idx,
+            None => {
+                guard.quality.push(IpQualityRecord {
+                    host: host.to_string(),
+                    port,
+                    ip,
+                    quality: IpQuality::default(),
+                    updated_at_epoch_ms: now_epoch_ms,
+                    unusable_until_epoch_ms: None,
+                    unusable_reason: None,
+                });
+                guard.quality.len() - 1
+            }
+        };
+        let record = &mut guard.quality[idx];
+        record.updated_at_epoch_ms = now_epoch_ms;
+        record.unusable_until_epoch_ms = Some(until_epoch_ms);
+        record.unusable_reason = Some(reason.to_string());
+        Self::persist(self.path.as_deref(), &guard)
+    }
+
+    /// 候选当前是否处于不可用标记期内，返回截止时间
+    pub fn unusable_until(
+        &self,
+        host: &str,
+        port: u16,
+        ip: IpAddr,
+        now_epoch_ms: i64,
+    ) -> Option<i64> {
+        self.inner.lock().ok().and_then(|guard| {
+            guard
+                .quality
+                .iter()
+                .find(|q| q.host == host && q.port == port && q.ip == ip)
+                .and_then(|q| q.unusable_until_epoch_ms)
+                .filter(|until| *until > now_epoch_ms)
+        })
+    }
+
+    /// 导出未过期且已在本机确认过的记录；不可用标记属于本机判断，不随导出共享。
+    pub fn export_snapshot(&self, now_epoch_ms: i64) -> IpHistoryExport {
+        let Ok(guard) = self.inner.lock() else {
+            return IpHistoryExport::default();
+        };
+        IpHistoryExport {
+            version: IP_HISTORY_EXPORT_VERSION,
+            exported_at_epoch_ms: now_epoch_ms,
+            entries: guard
+                .entries
+                .iter()
+                .filter(|e| !e.pending_validation && e.expires_at_epoch_ms > now_epoch_ms)
+                .cloned()
+                .collect(),
+            quality: guard
+                .quality
+                .iter()
+                .filter(|q| now_epoch_ms - q.updated_at_epoch_ms < QUALITY_RETENTION_MS)
+                .map(|q| IpQualityRecord {
+                    unusable_until_epoch_ms: None,
+                    unusable_reason: None,
+                    ..q.clone()
+                })
+                .collect(),
+        }
+    }
+
+    /// 将导出的快照合并进本地历史。
+    ///
+    /// 同一 (host, port) 取测量时间更新的一方；两者相差不超过 [`IMPORT_FRESHNESS_WINDOW_MS`] 时取延迟
+    /// 更低的一方。采纳的导入记录标记为待确认，由预热重新探测后才会按本机结果写回。质量记录按更新时间
+    /// 取较新者，本地的不可用标记保留。`replace` 为真时先清空本地历史。
+    pub fn import_snapshot(
+        &self,
+        snapshot: &IpHistoryExport,
+        replace: bool,
+        now_epoch_ms: i64,
+    ) -> Result<IpHistoryImportReport> {
+        let mut guard = self
+            .inner
+            .lock()
+            .map_err(|_| anyhow!("ip history poisoned"))?;
+        if replace {
+            guard.entries.clear();
+            guard.quality.clear();
+        }
+        let mut report = IpHistoryImportReport::default();
+        for incoming in &snapshot.entries {
+            if incoming.expires_at_epoch_ms <= now_epoch_ms
+                || incoming.host.trim().is_empty()
+                || incoming.port == 0
+            {
+                report.discarded += 1;
+                continue;
+            }
+            let mut record = incoming.clone();
+            record.host = record.host.trim().to_ascii_lowercase();
+            record.candidate.port = record.port;
+            record.pending_validation = true;
+            match guard
+                .entries
+                .iter_mut()
+                .find(|e| e.host == record.host && e.port == record.port)
+            {
+                Some(local) if local.expires_at_epoch_ms > now_epoch_ms => {
+                    if import_wins(local, &record) {
+                        *local = record;
+                        report.imported += 1;
+                    } else {
+                        report.kept_local += 1;
+                    }
+                }
+                Some(local) => {
+                    *local = record;
+                    report.imported += 1;
+                }
+                None => {
+                    guard.entries.push(record);
+                    report.imported += 1;
+                }
+            }
+        }
+        for incoming in &snapshot.quality {
+            if now_epoch_ms - incoming.updated_at_epoch_ms >= QUALITY_RETENTION_MS {
+                continue;
+            }
+            let host = incoming.host.trim().to_ascii_lowercase();
+            match guard
+                .quality
+                .iter_mut()
+                .find(|q| q.host == host && q.port == incoming.port && q.ip == incoming.ip)
+            {
+                Some(local) if local.updated_at_epoch_ms >= incoming.updated_at_epoch_ms => {}
+                Some(local) => {
+                    local.quality = incoming.quality;
+                    local.updated_at_epoch_ms = incoming.updated_at_epoch_ms;
+                    report.quality_merged += 1;
+                }
+                None => {
+                    guard.quality.push(IpQualityRecord {
+                        host,
+                        unusable_until_epoch_ms: None,
+                        unusable_reason: None,
+                        ..incoming.clone()
+                    });
+                    report.quality_merged += 1;
+                }
+            }
+        }
+        Self::persist(self.path.as_deref(), &guard)?;
+        tracing::info!(
+            target = "ip_pool",
+            imported = report.imported,
+            kept_local = report.kept_local,
+            discarded = report.discarded,
+            quality_merged = report.quality_merged,
+            "imported ip history"
+        );
+        Ok(report)
+    }
+
+    /// 待预热重新探测确认的 (host, port)
+    pub fn pending_validation(&self, now_epoch_ms: i64) -> Vec<(String, u16)> {
+        self.inner
+            .lock()
+            .map(|guard| {
+                guard
+                    .entries
+                    .iter()
+                    .filter(|e| e.pending_validation && e.expires_at_epoch_ms > now_epoch_ms)
+                    .map(|e| (e.host.clone(), e.port))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// 读取持久化的熔断状态
+    pub fn breaker_records(&self) -> Vec<CircuitBreakerRecord> {
+        self.inner
+            .lock()
+            .map(|guard| guard.breakers.clone())
+            .unwrap_or_default()
+    }
+
+    /// 以当前熔断快照整体替换持久化的熔断状态
+    pub fn save_breaker_records(&self, records: Vec<CircuitBreakerRecord>) -> Result<()> {
+        let mut guard = self
+            .inner
+            .lock()
+            .map_err(|_| anyhow!("ip history poisoned"))?;
+        if guard.breakers == records {
+            return Ok(());
+        }
+        guard.breakers = records;
+        Self::persist(self.path.as_deref(), &guard)
+    }
+
+    pub fn quality_snapshot(&self) -> Vec<IpQualityRecord> {
+        self.inner
+            .lock()
+            .map(|guard| guard.quality.clone())
+            .unwrap_or_default()
+    }
+
     pub fn snapshot(&self) -> Option<Vec<IpHistoryRecord>> {
         self.inner.lock().ok().map(|guard| guard.entries.clone())
     }
@@ -194,6 +528,7 @@ impl IpHistoryStore {
             .lock()
             .map_err(|_| anyhow!("ip history poisoned"))?;
         guard.entries.clear();
+        guard.quality.clear();
         Self::persist(self.path.as_deref(), &guard)
     }
 
@@ -259,6 +594,11 @@ impl IpHistoryStore {
             .entries
             .retain(|e| e.expires_at_epoch_ms > now_epoch_ms);
         let expired = before - guard.entries.len();
+        let quality_before = guard.quality.len();
+        guard
+            .quality
+            .retain(|q| now_epoch_ms - q.updated_at_epoch_ms < QUALITY_RETENTION_MS);
+        let quality_pruned = quality_before - guard.quality.len();
 
         let capacity_pruned = if guard.entries.len() > max_entries {
             guard.entries.sort_by_key(|e| e.measured_at_epoch_ms);
@@ -269,7 +609,7 @@ impl IpHistoryStore {
             0
         };
 
-        if expired > 0 || capacity_pruned > 0 {
+        if expired > 0 || capacity_pruned > 0 || quality_pruned > 0 {
             Self::persist(self.path.as_deref(), &guard)?;
             tracing::debug!(
                 target = "ip_pool",
@@ -302,3 +642,13 @@ impl IpHistoryStore {
         }
     }
 }
+
+/// 导入记录是否应替换本地记录：更新者优先，新鲜度相近时延迟低者优先。
+fn import_wins(local: &IpHistoryRecord, incoming: &IpHistoryRecord) -> bool {
+    let delta = incoming.measured_at_epoch_ms - local.measured_at_epoch_ms;
+    if delta.abs() <= IMPORT_FRESHNESS_WINDOW_MS {
+        incoming.latency_ms < local.latency_ms
+    } else {
+        delta > 0
+    }
+}
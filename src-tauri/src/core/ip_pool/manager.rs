@@ -19,13 +19,15 @@ use crate::core::config::model::AppConfig;
 
 use super::{
     builder,
-    cache::{IpCacheKey, IpCacheSlot, IpScoreCache, IpSource, IpStat},
-    circuit_breaker::{CircuitBreaker, CircuitBreakerConfig},
+    cache::{IpCacheKey, IpCacheSlot, IpCandidate, IpQuality, IpScoreCache, IpSource, IpStat},
+    circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, CircuitBreakerRecord},
     config::{EffectiveIpPoolConfig, IpPoolFileConfig, IpPoolRuntimeConfig},
-    history::IpHistoryStore,
+    family::normalize_ip,
+    history::{IpHistoryExport, IpHistoryImportReport, IpHistoryStore},
     maintenance,
     preheat::{self, PreheatService},
     sampling,
+    scoring::{rank_stats, throughput_kibps},
 };
 
 /// IP 选择策略：使用系统 DNS 或缓存的评分结果。
@@ -110,6 +112,17 @@ impl IpSelection {
 pub enum IpOutcome {
     Success,
     Failure,
+    /// 连接成功并完成一次数据传输，附带被动测得的吞吐量
+    Throughput {
+        bytes: u64,
+        elapsed_ms: u64,
+    },
+}
+
+impl IpOutcome {
+    pub fn is_success(self) -> bool {
+        !matches!(self, IpOutcome::Failure)
+    }
 }
 
 #[derive(Debug, Default, Clone)]
@@ -140,9 +153,10 @@ struct CandidateOutcomeStats {
 
 impl CandidateOutcomeStats {
     fn record(&mut self, outcome: IpOutcome, sources: &[IpSource]) {
-        match outcome {
-            IpOutcome::Success => self.success = self.success.saturating_add(1),
-            IpOutcome::Failure => self.failure = self.failure.saturating_add(1),
+        if outcome.is_success() {
+            self.success = self.success.saturating_add(1);
+        } else {
+            self.failure = self.failure.saturating_add(1);
         }
         self.last_outcome_ms = preheat::current_epoch_ms();
         if !sources.is_empty() {
@@ -209,12 +223,28 @@ impl IpPool {
             window_seconds: runtime.failure_window_seconds,
             min_samples_in_window: runtime.min_samples_in_window,
             cooldown_seconds: runtime.cooldown_seconds,
+            half_open_max_trials: runtime.half_open_max_trials,
+        }
+    }
+
+    /// 熔断状态变化后写入历史文件，重启后恢复
+    fn persist_breakers(&self) {
+        if !self.circuit_breaker.take_dirty() {
+            return;
+        }
+        if let Err(err) = self
+            .history
+            .save_breaker_records(self.circuit_breaker.snapshot())
+        {
+            tracing::warn!(target = "ip_pool", error = %err, "failed to persist circuit breaker state");
         }
     }
 
     pub fn new(config: EffectiveIpPoolConfig) -> Self {
         let history = builder::init_history_store(&config);
         let breaker_config = Self::build_circuit_breaker_config(&config.runtime);
+        let circuit_breaker = Arc::new(CircuitBreaker::new(breaker_config));
+        circuit_breaker.restore(&history.breaker_records());
         let mut pool = Self {
             config: Arc::new(config),
             cache: Arc::new(IpScoreCache::new()),
@@ -223,7 +253,7 @@ impl IpPool {
             pending: Arc::new(AsyncMutex::new(HashMap::new())),
             last_prune_at_ms: AtomicI64::new(0),
             outcomes: Arc::new(Mutex::new(HashMap::new())),
-            circuit_breaker: Arc::new(CircuitBreaker::new(breaker_config)),
+            circuit_breaker,
             auto_disabled_until: Arc::new(AtomicI64::new(0)),
             preheat_enabled: false,
         };
@@ -408,6 +438,21 @@ impl IpPool {
         }
     }
 
+    /// 导入共享的历史快照，并重启预热以便尽快重新探测导入的记录。
+    pub fn import_history(
+        &mut self,
+        snapshot: &IpHistoryExport,
+        replace: bool,
+    ) -> Result<IpHistoryImportReport> {
+        let report =
+            self.history
+                .import_snapshot(snapshot, replace, preheat::current_epoch_ms())?;
+        if report.imported > 0 {
+            self.rebuild_preheater();
+        }
+        Ok(report)
+    }
+
     pub fn update_config(&mut self, config: EffectiveIpPoolConfig) {
         let old_config = self.config.clone();
         let history_path_changed = self.config.runtime.history_path != config.runtime.history_path;
@@ -416,6 +461,10 @@ impl IpPool {
         self.config = Arc::new(config);
         if history_path_changed {
             self.history = builder::init_history_store(&self.config);
+            self.circuit_breaker.clear_all();
+            self.circuit_breaker
+                .restore(&self.history.breaker_records());
+            self.circuit_breaker.take_dirty();
         }
         self.last_prune_at_ms.store(0, Ordering::Relaxed);
         if let Ok(mut guard) = self.pending.try_lock() {
@@ -495,9 +544,10 @@ impl IpPool {
         match self.outcomes.lock() {
             Ok(mut guard) => {
                 let entry = guard.entry(key).or_default();
-                match outcome {
-                    IpOutcome::Success => entry.success = entry.success.saturating_add(1),
-                    IpOutcome::Failure => entry.failure = entry.failure.saturating_add(1),
+                if outcome.is_success() {
+                    entry.success = entry.success.saturating_add(1);
+                } else {
+                    entry.failure = entry.failure.saturating_add(1);
                 }
                 entry.last_outcome_ms = preheat::current_epoch_ms();
             }
@@ -541,12 +591,89 @@ impl IpPool {
 
         // 将结果报告给熔断器
         let ip = candidate.candidate.address;
-        match outcome {
-            IpOutcome::Success => self.circuit_breaker.record_success(ip),
-            IpOutcome::Failure => self.circuit_breaker.record_failure(ip),
+        if outcome.is_success() {
+            self.circuit_breaker.record_success_for(host, ip);
+        } else {
+            self.circuit_breaker.record_failure_for(host, ip);
+        }
+        self.persist_breakers();
+        if let IpOutcome::Throughput { bytes, elapsed_ms } = outcome {
+            self.record_throughput(host, port, ip, bytes, elapsed_ms);
         }
     }
 
+    /// 按地址上报候选结果（调用方只知道实际连接的 IP 时使用）。
+    pub fn report_ip_outcome(&self, host: &str, port: u16, ip: IpAddr, outcome: IpOutcome) {
+        let ip = normalize_ip(ip);
+        let stat = self
+            .cache
+            .get(host, port)
+            .and_then(|slot| {
+                slot.best
+                    .into_iter()
+                    .chain(slot.alternatives)
+                    .find(|s| s.candidate.address == ip)
+            })
+            .unwrap_or_else(|| IpStat {
+                candidate: IpCandidate::new(ip, port, IpSource::History),
+                sources: Vec::new(),
+                latency_ms: None,
+                measured_at_epoch_ms: None,
+                expires_at_epoch_ms: None,
+                resolver_metadata: Vec::new(),
+                quality: None,
+            });
+        self.report_candidate_outcome(host, port, &stat, outcome);
+    }
+
+    /// 将被动吞吐量按 EWMA 并入历史，并据此对缓存中的候选重新排序。
+    fn record_throughput(&self, host: &str, port: u16, ip: IpAddr, bytes: u64, elapsed_ms: u64) {
+        let sample = IpQuality {
+            throughput_kibps: Some(throughput_kibps(bytes, elapsed_ms)),
+            samples: 1,
+            ..IpQuality::default()
+        };
+        let runtime = &self.config.runtime;
+        let blended = match self.history.fold_quality(
+            host,
+            port,
+            &[(ip, sample)],
+            runtime.scoring.ewma_alpha,
+            preheat::current_epoch_ms(),
+        ) {
+            Ok(mut merged) => merged.pop().unwrap_or(sample),
+            Err(err) => {
+                tracing::warn!(target = "ip_pool", host, port, ip = %ip, error = %err, "failed to persist ip throughput");
+                return;
+            }
+        };
+        tracing::debug!(
+            target = "ip_pool",
+            host,
+            port,
+            ip = %ip,
+            throughput_kibps = ?blended.throughput_kibps,
+            "ip throughput recorded"
+        );
+        let Some(slot) = self.cache.get(host, port) else {
+            return;
+        };
+        let mut stats: Vec<IpStat> = slot.best.into_iter().chain(slot.alternatives).collect();
+        let Some(stat) = stats.iter_mut().find(|s| s.candidate.address == ip) else {
+            return;
+        };
+        stat.quality = Some(blended);
+        rank_stats(&mut stats, runtime);
+        let mut ranked = stats.into_iter();
+        self.cache.insert(
+            IpCacheKey::new(host.to_string(), port),
+            IpCacheSlot {
+                best: ranked.next(),
+                alternatives: ranked.collect(),
+            },
+        );
+    }
+
     /// 返回指定 host:port 的只读 outcome 统计信息。
     pub fn outcome_metrics(&self, host: &str, port: u16) -> Option<OutcomeMetrics> {
         let key = IpCacheKey::new(host, port);
@@ -588,7 +715,12 @@ impl IpPool {
             self.preheater = None;
             return;
         }
-        if preheat::resolve_preheat_domains(&self.config.file).is_empty() {
+        if preheat::resolve_preheat_domains(&self.config.file).is_empty()
+            && self
+                .history
+                .pending_validation(preheat::current_epoch_ms())
+                .is_empty()
+        {
             self.preheater = None;
             return;
         }
@@ -652,14 +784,43 @@ impl IpPool {
         self.circuit_breaker.is_tripped(ip)
     }
 
+    /// 检查指定 IP 对某主机是否被熔断（不占用半开试探名额）
+    pub fn is_ip_tripped_for(&self, host: &str, ip: IpAddr) -> bool {
+        self.circuit_breaker.is_tripped_for(host, ip)
+    }
+
+    /// 选择候选时调用：半开状态下放行并占用一个试探名额
+    pub(super) fn admit_ip(&self, host: &str, ip: IpAddr) -> bool {
+        self.circuit_breaker.admit(host, ip)
+    }
+
     /// 获取所有被熔断的 IP 列表
     pub fn get_tripped_ips(&self) -> Vec<IpAddr> {
         self.circuit_breaker.get_tripped_ips()
     }
 
+    /// 所有处于熔断、冷却或半开状态的 (host, IP) 作用域
+    pub fn circuit_breaker_snapshot(&self) -> Vec<CircuitBreakerRecord> {
+        self.circuit_breaker.snapshot()
+    }
+
     /// 手动重置指定 IP 的熔断状态
     pub fn reset_circuit_breaker(&self, ip: IpAddr) {
         self.circuit_breaker.reset_ip(ip);
+        self.persist_breakers();
+    }
+
+    /// 网络环境切换后丢弃与旧网络相关的运行期状态：评分缓存、结果统计、熔断与全局禁用，
+    /// 并请求预热器立即按新网络重新采样。历史记录保留，仅作为候选参与后续探测。
+    pub fn reset_network_state(&self) {
+        self.cache.clear();
+        if let Ok(mut outcomes) = self.outcomes.lock() {
+            outcomes.clear();
+        }
+        self.circuit_breaker.clear_all();
+        self.persist_breakers();
+        self.clear_auto_disabled();
+        self.request_preheat_refresh();
     }
 }
 
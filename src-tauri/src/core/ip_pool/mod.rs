@@ -3,23 +3,31 @@ pub mod circuit_breaker;
 pub mod config;
 pub mod dns;
 pub mod events;
+pub mod family;
 pub mod global;
 pub mod history;
 pub mod preheat;
+pub mod racing;
+pub mod scoring;
+pub mod subscription;
 
 mod builder;
 mod maintenance;
 pub mod manager;
 mod sampling;
 
-pub use cache::{IpCacheKey, IpCacheSlot, IpCandidate, IpScoreCache, IpSource, IpStat};
-pub use circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, CircuitState};
+pub use cache::{IpCacheKey, IpCacheSlot, IpCandidate, IpQuality, IpScoreCache, IpSource, IpStat};
+pub use circuit_breaker::{
+    CircuitBreaker, CircuitBreakerConfig, CircuitBreakerRecord, CircuitState,
+};
 pub use config::{
-    DnsResolverConfig, DnsResolverProtocol, DnsRuntimeConfig, EffectiveIpPoolConfig,
-    IpPoolFileConfig, IpPoolRuntimeConfig, IpPoolSourceToggle, PreheatDomain, ProbeMethod,
-    UserStaticIp,
+    ConnectionRacingConfig, DnsResolverConfig, DnsResolverProtocol, DnsRuntimeConfig,
+    EffectiveIpPoolConfig, IpPoolFileConfig, IpPoolRuntimeConfig, IpPoolSourceToggle,
+    IpSubscription, Ipv6Mode, PreheatDomain, ProbeMethod, ScoringConfig, UserStaticIp,
+};
+pub use history::{
+    IpHistoryExport, IpHistoryImportReport, IpHistoryRecord, IpHistoryStore, IpQualityRecord,
 };
-pub use history::{IpHistoryRecord, IpHistoryStore};
 
 pub use builder::{load_effective_config, load_effective_config_at};
 pub use manager::{IpOutcome, IpPool, IpSelection, IpSelectionStrategy, OutcomeMetrics};
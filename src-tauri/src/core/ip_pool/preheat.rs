@@ -25,13 +25,20 @@ use super::{
     cache::{IpCacheKey, IpCacheSlot, IpScoreCache, IpStat},
     config::{
         DnsRuntimeConfig, EffectiveIpPoolConfig, IpPoolFileConfig, IpPoolRuntimeConfig,
-        PreheatDomain, ProbeMethod, UserStaticIp,
+        IpSubscription, Ipv6Mode, PreheatDomain, ProbeMethod, UserStaticIp,
     },
     dns::{self, DnsResolvedIp},
+    family::{family_allowed, family_label, normalize_ip, parse_ip_literal},
     history::{IpHistoryRecord, IpHistoryStore},
-    IpCandidate, IpSource,
+    scoring::{blend_quality, rank_stats, summarize_samples},
+    subscription, IpCandidate, IpSource,
 };
-use crate::core::config::model::TlsCfg;
+use crate::core::config::loader::load_or_init;
+use crate::core::config::model::{AppConfig, TlsCfg};
+use crate::core::proxy::pac::target_url;
+use crate::core::proxy::routing::{proxy_in_use, routed_through_proxy};
+use crate::core::proxy::{tunnel, AsyncProxyStream, ProxyConfig};
+use crate::core::tls::util::decide_sni_host_with_proxy;
 use crate::core::tls::verifier::create_client_config_with_expected_name;
 use ipnet::IpNet;
 
@@ -42,29 +49,62 @@ pub type ResolverFn = Arc<
 >;
 
 pub type ProberFn = Arc<
-    dyn Fn(IpAddr, u16, &str, &str, &str, u64, ProbeMethod) -> BoxFuture<'static, Result<u32>>
+    dyn Fn(
+            IpAddr,
+            u16,
+            &str,
+            &str,
+            &str,
+            u64,
+            ProbeMethod,
+            &Arc<ProbeEnv>,
+        ) -> BoxFuture<'static, Result<u32>>
         + Send
         + Sync,
 >;
 
+/// 一轮探测共用的配置快照，避免逐次采样重复读取配置。
+#[derive(Debug, Clone)]
+pub struct ProbeEnv {
+    /// TLS 探测的证书校验设置
+    pub tls: TlsCfg,
+    /// 探测拨号的代理路由配置
+    pub proxy: ProxyConfig,
+}
+
+impl ProbeEnv {
+    pub fn from_config(cfg: &AppConfig) -> Self {
+        Self {
+            tls: cfg.tls.clone(),
+            proxy: cfg.proxy.clone(),
+        }
+    }
+}
+
 use futures::future::BoxFuture;
 
 pub fn default_dns_resolver() -> ResolverFn {
-    Arc::new(|host, port, cfg| {
+    default_dns_resolver_for(Ipv6Mode::default())
+}
+
+/// 按 IPv6 策略决定是否查询 AAAA 记录的默认解析器
+pub fn default_dns_resolver_for(mode: Ipv6Mode) -> ResolverFn {
+    Arc::new(move |host, port, cfg| {
         let host = host.to_string();
         let cfg = cfg.clone();
-        Box::pin(async move { dns::resolve(&host, port, &cfg).await })
+        Box::pin(async move { dns::resolve_with_family(&host, port, &cfg, mode).await })
     })
 }
 
 pub fn default_latency_prober() -> ProberFn {
-    Arc::new(|ip, port, host, sni, path, timeout, method| {
+    Arc::new(|ip, port, host, sni, path, timeout, method, env| {
         let host = host.to_string();
         let sni = sni.to_string();
         let path = path.to_string();
-        Box::pin(
-            async move { probe_latency_impl(ip, port, &host, &sni, &path, timeout, method).await },
-        )
+        let env = Arc::clone(env);
+        Box::pin(async move {
+            probe_latency_impl(ip, port, &host, &sni, &path, timeout, method, &env).await
+        })
     })
 }
 
@@ -89,14 +129,15 @@ impl CandidateChannel {
     }
 }
 
-/// 判断 IP 是否在名单（支持单 IP 和 CIDR）
+/// 判断 IP 是否在名单（支持 IPv4 / IPv6 单 IP 和 CIDR）
 fn is_ip_in_list(ip: IpAddr, list: &[String]) -> bool {
+    let ip = normalize_ip(ip);
     for entry in list {
-        if let Ok(net) = entry.parse::<IpNet>() {
+        if let Ok(net) = entry.trim().parse::<IpNet>() {
             if net.contains(&ip) {
                 return true;
             }
-        } else if let Ok(addr) = entry.parse::<IpAddr>() {
+        } else if let Some(addr) = parse_ip_literal(entry) {
             if addr == ip {
                 return true;
             }
@@ -117,6 +158,10 @@ const FAST_WAIT_DEFAULT_MS: u64 = 1_200;
 const FAST_WAIT_MIN_MS: u64 = 300;
 /// 触发快速结束的延迟阈值（毫秒）。
 const FAST_LATENCY_THRESHOLD_MS: u32 = 200;
+/// 单个候选每轮最多采样次数。
+const MAX_PROBE_SAMPLES: u32 = 10;
+/// 证书不匹配的候选被标记为不可用的时长（毫秒）。
+const CERT_MISMATCH_QUARANTINE_MS: i64 = 6 * 60 * 60 * 1000;
 
 #[derive(Debug)]
 pub struct PreheatService {
@@ -141,7 +186,8 @@ impl PreheatService {
         let thread_cache = cache.clone();
         let thread_history = history.clone();
 
-        let resolver = dns_resolver.unwrap_or_else(default_dns_resolver);
+        let resolver =
+            dns_resolver.unwrap_or_else(|| default_dns_resolver_for(config.runtime.ipv6));
         let prober = latency_prober.unwrap_or_else(default_latency_prober);
 
         let handle = thread::Builder::new()
@@ -226,7 +272,19 @@ async fn run_preheat_loop(
         return;
     }
 
-    let preheat_domains = resolve_preheat_domains(&config.file);
+    let subscriptions = &config.file.subscriptions;
+    if config.runtime.sources.subscription && subscriptions.iter().any(|s| s.enabled) {
+        // 首轮预热前加载一次，之后在后台按各订阅的刷新间隔更新
+        subscription::refresh_due(subscriptions, current_epoch_ms()).await;
+        tokio::spawn(subscription::run_refresh_loop(
+            subscriptions.clone(),
+            stop.clone(),
+            notify.clone(),
+        ));
+    }
+
+    let mut preheat_domains = resolve_preheat_domains(&config.file);
+    let revalidate = append_revalidation_domains(&mut preheat_domains, &history);
     if preheat_domains.is_empty() {
         tracing::info!(
             target = "ip_pool",
@@ -300,6 +358,20 @@ async fn run_preheat_loop(
             )
             .await;
             match result {
+                Ok(_) if revalidate.contains(&domain.host) => {
+                    // 导入记录已按本机探测结果写回，无需继续定期预热
+                    schedules.remove(index);
+                    tracing::debug!(
+                        target = "ip_pool",
+                        host = domain.host.as_str(),
+                        ports = ?domain.ports,
+                        "imported ip history revalidated"
+                    );
+                    if schedules.is_empty() {
+                        wait_for_shutdown(stop.clone(), notify.clone()).await;
+                        break;
+                    }
+                }
                 Ok(_) => {
                     let instant = Instant::now();
                     schedules[index].mark_success(instant);
@@ -367,6 +439,34 @@ async fn run_preheat_loop(
     tracing::info!(target = "ip_pool", "preheat loop stopped");
 }
 
+/// 把待确认的导入历史记录追加为一次性预热目标，返回追加的主机（已是预热目标的只补端口）。
+fn append_revalidation_domains(
+    domains: &mut Vec<PreheatDomain>,
+    history: &IpHistoryStore,
+) -> HashSet<String> {
+    let mut added = HashSet::new();
+    for (host, port) in history.pending_validation(current_epoch_ms()) {
+        match domains
+            .iter_mut()
+            .find(|d| d.host.eq_ignore_ascii_case(&host))
+        {
+            Some(domain) => {
+                if !domain.ports.contains(&port) {
+                    domain.ports.push(port);
+                }
+            }
+            None => {
+                domains.push(PreheatDomain {
+                    host: host.clone(),
+                    ports: vec![port],
+                });
+                added.insert(host);
+            }
+        }
+    }
+    added
+}
+
 async fn wait_for_shutdown(stop: Arc<AtomicBool>, notify: Arc<Notify>) {
     while !stop.load(Ordering::Relaxed) {
         notify.notified().await;
@@ -464,7 +564,20 @@ async fn preheat_domain(
 ) -> Result<()> {
     let host = domain.host.as_str();
     tracing::debug!(target = "ip_pool", host, ports = ?domain.ports, "preheat domain");
+    let proxy_cfg = load_or_init()
+        .unwrap_or_else(|_| AppConfig::default())
+        .proxy;
     for &port in &domain.ports {
+        // 走代理的目标由代理连接器拨号、不使用 IP 池，不做本地解析与预热，避免 DNS 泄漏
+        if routed_through_proxy(&proxy_cfg, host, port) {
+            tracing::debug!(
+                target = "ip_pool",
+                host,
+                port,
+                "skip preheat: routed through proxy"
+            );
+            continue;
+        }
         let toggles = &config.runtime.sources;
         let runtime_cfg = config.runtime.clone();
         let file_cfg = config.file.clone();
@@ -606,7 +719,8 @@ async fn preheat_domain(
                     );
 
                     let merged = merge_candidate_map(&mut candidate_map, batch);
-                    let filtered = apply_cidr_filters(&mut candidate_map, &whitelist, &blacklist);
+                    let filtered = apply_cidr_filters(&mut candidate_map, &whitelist, &blacklist)
+                        | apply_family_filter(&mut candidate_map, runtime_cfg.ipv6);
 
                     if merged || filtered {
                         let snapshot: Vec<AggregatedCandidate> =
@@ -754,6 +868,7 @@ pub struct AggregatedCandidate {
 
 impl AggregatedCandidate {
     pub fn new(ip: IpAddr, port: u16, source: IpSource) -> Self {
+        let ip = normalize_ip(ip);
         let mut sources = HashSet::new();
         sources.insert(source);
         Self {
@@ -883,6 +998,21 @@ fn apply_cidr_filters(
     changed
 }
 
+/// 按 IPv6 策略丢弃候选；返回是否有变化。
+fn apply_family_filter(map: &mut HashMap<IpAddr, AggregatedCandidate>, mode: Ipv6Mode) -> bool {
+    let before = map.len();
+    map.retain(|ip, _| family_allowed(mode, *ip));
+    let removed = before - map.len();
+    if removed > 0 {
+        tracing::debug!(
+            target = "ip_pool",
+            removed,
+            "ipv6 candidates removed by policy"
+        );
+    }
+    removed > 0
+}
+
 fn gather_builtin_candidates(host: &str, port: u16) -> Result<Vec<AggregatedCandidate>> {
     let candidates = builtin_lookup(host)
         .into_iter()
@@ -948,6 +1078,21 @@ fn gather_fallback_candidates(host: &str, port: u16) -> Result<Vec<AggregatedCan
     Ok(candidates)
 }
 
+fn gather_subscription_candidates(
+    host: &str,
+    port: u16,
+    subs: &[IpSubscription],
+) -> Vec<AggregatedCandidate> {
+    subscription::lookup(host, port, subs)
+        .into_iter()
+        .map(|(ip, name)| {
+            let mut candidate = AggregatedCandidate::new(ip, port, IpSource::Subscription);
+            candidate.merge_resolver_tag(format!("subscription:{name}"));
+            candidate
+        })
+        .collect()
+}
+
 async fn measure_and_update_candidates(
     host: &str,
     port: u16,
@@ -987,13 +1132,24 @@ async fn measure_and_update_candidates(
             port,
             candidates,
             runtime_cfg,
+            &history,
             ttl_secs,
             Some(&mut progress),
             prober,
         )
         .await
     } else {
-        measure_candidates(host, port, candidates, runtime_cfg, ttl_secs, None, prober).await
+        measure_candidates(
+            host,
+            port,
+            candidates,
+            runtime_cfg,
+            &history,
+            ttl_secs,
+            None,
+            prober,
+        )
+        .await
     };
 
     if stats.is_empty() {
@@ -1042,7 +1198,8 @@ async fn drain_remaining_candidates(
                 );
 
                 let merged = merge_candidate_map(&mut candidates, batch);
-                let filtered = apply_cidr_filters(&mut candidates, &whitelist, &blacklist);
+                let filtered = apply_cidr_filters(&mut candidates, &whitelist, &blacklist)
+                    | apply_family_filter(&mut candidates, runtime_cfg.ipv6);
 
                 if merged || filtered {
                     let snapshot: Vec<AggregatedCandidate> = candidates.values().cloned().collect();
@@ -1144,7 +1301,7 @@ pub async fn collect_candidates(
     }
 
     if toggles.dns {
-        let resolver = default_dns_resolver();
+        let resolver = default_dns_resolver_for(config.runtime.ipv6);
         match gather_dns_candidates(host, port, &config.runtime.dns, resolver).await {
             Ok(batch) => {
                 merge_candidate_map(&mut map, batch);
@@ -1167,15 +1324,28 @@ pub async fn collect_candidates(
         }
     }
 
+    // 订阅由预热线程定期刷新，此处只读取已加载的列表
+    if toggles.subscription && !config.file.subscriptions.is_empty() {
+        merge_candidate_map(
+            &mut map,
+            gather_subscription_candidates(host, port, &config.file.subscriptions),
+        );
+    }
+
     apply_cidr_filters(&mut map, &config.file.whitelist, &config.file.blacklist);
+    apply_family_filter(&mut map, config.runtime.ipv6);
     map.into_values().collect()
 }
 
+/// 并发探测候选：每个候选重复采样 `scoring.samples` 次，汇总出延迟中位数、抖动与丢包率，
+/// 与历史 EWMA 合并后按综合得分排序；本轮观测在结束时一次性写回历史。
+#[allow(clippy::too_many_arguments)]
 pub(super) async fn measure_candidates(
     host: &str,
     port: u16,
     candidates: Vec<AggregatedCandidate>,
     runtime_cfg: &IpPoolRuntimeConfig,
+    history: &IpHistoryStore,
     ttl_secs: u64,
     mut progress: Option<&mut (dyn FnMut(&[IpStat]) -> Result<()> + Send)>,
     prober: ProberFn,
@@ -1190,52 +1360,101 @@ pub(super) async fn measure_candidates(
     // 获取探测配置
     let probe_method = runtime_cfg.probe_method;
     let probe_path = runtime_cfg.probe_path.clone();
+    let samples = runtime_cfg.scoring.samples.clamp(1, MAX_PROBE_SAMPLES);
+    let alpha = runtime_cfg.scoring.ewma_alpha;
+    let cfg = load_or_init().unwrap_or_else(|_| AppConfig::default());
+    let env = Arc::new(ProbeEnv::from_config(&cfg));
+    // HTTP 探测使用真实 host 作为 SNI；TLS 探测沿用实际连接的 SNI 策略
+    let sni_for_probe = match probe_method {
+        ProbeMethod::Tls => probe_sni_host(&cfg, host, port),
+        _ => host.to_string(),
+    };
+    let now_ms = current_epoch_ms();
 
     for candidate in candidates {
+        let ip = candidate.candidate.address;
+        if let Some(until) = history.unusable_until(host, port, ip, now_ms) {
+            tracing::debug!(
+                target = "ip_pool",
+                host,
+                port,
+                ip = %ip,
+                unusable_until = until,
+                "skip candidate marked unusable"
+            );
+            continue;
+        }
         let permit = semaphore.clone();
         let timeout_ms = timeout;
         let host_label = host.to_string();
         let host_for_probe = host.to_string();
-        let sni_host = host.to_string(); // 对于延迟测试，使用真实 host 作为 SNI
+        let sni_host = sni_for_probe.clone();
         let path = probe_path.clone();
         let method = probe_method;
         let prober_task = prober.clone();
+        let env = Arc::clone(&env);
         join_set.spawn(async move {
             let _permit = permit
                 .acquire_owned()
                 .await
                 .map_err(|_| anyhow!("semaphore closed"))?;
-            let latency = prober_task(
-                candidate.candidate.address,
-                candidate.candidate.port,
-                &host_for_probe,
-                &sni_host,
-                &path,
-                timeout_ms,
-                method,
-            )
-            .await?;
+            let mut latencies: Vec<u32> = Vec::with_capacity(samples as usize);
+            let mut last_err: Option<anyhow::Error> = None;
+            for _ in 0..samples {
+                match prober_task(
+                    candidate.candidate.address,
+                    candidate.candidate.port,
+                    &host_for_probe,
+                    &sni_host,
+                    &path,
+                    timeout_ms,
+                    method,
+                    &env,
+                )
+                .await
+                {
+                    Ok(latency) => latencies.push(latency),
+                    // 证书不匹配是确定性结果，无需继续采样
+                    Err(err) if is_cert_mismatch(&err) => return Err(err),
+                    Err(err) => last_err = Some(err),
+                }
+            }
+            let Some(quality) = summarize_samples(&latencies, samples) else {
+                return Err(last_err.unwrap_or_else(|| anyhow!("no probe samples")));
+            };
+            let latency = quality.latency_ms.unwrap_or_default();
             tracing::debug!(
                 target = "ip_pool",
                 host = host_label.as_str(),
                 port = candidate.candidate.port,
                 ip = %candidate.candidate.address,
+                family = family_label(candidate.candidate.address),
                 latency_ms = latency,
+                jitter_ms = ?quality.jitter_ms,
+                loss_permille = ?quality.loss_permille,
                 method = ?method,
                 "probe success"
             );
-            Ok::<IpStat, anyhow::Error>(candidate.to_stat(latency, ttl_secs))
+            let mut stat = candidate.to_stat(latency, ttl_secs);
+            stat.quality = Some(quality);
+            Ok::<IpStat, anyhow::Error>(stat)
         });
     }
 
     let mut stats: Vec<IpStat> = Vec::new();
+    let mut observed = Vec::new();
     let mut last_notified: Option<(u32, IpAddr)> = None;
 
     while let Some(result) = join_set.join_next().await {
         match result {
-            Ok(Ok(stat)) => {
+            Ok(Ok(mut stat)) => {
+                if let Some(sample) = stat.quality {
+                    let prev = history.quality(host, port, stat.candidate.address);
+                    stat.quality = Some(blend_quality(prev.as_ref(), &sample, alpha));
+                    observed.push((stat.candidate.address, sample));
+                }
                 stats.push(stat);
-                stats.sort_by_key(|item| item.latency_ms.unwrap_or(u32::MAX));
+                rank_stats(&mut stats, runtime_cfg);
 
                 if let Some(callback) = progress.as_mut() {
                     if let Some(best) = stats.first() {
@@ -1260,33 +1479,79 @@ pub(super) async fn measure_candidates(
                     }
                 }
             }
-            Ok(Err(err)) => tracing::debug!(
+            Ok(Err(err)) => match err.downcast_ref::<CertMismatch>() {
+                Some(mismatch) => {
+                    tracing::warn!(
+                        target = "ip_pool",
+                        host,
+                        port,
+                        ip = %mismatch.ip,
+                        reason = %mismatch.detail,
+                        "certificate mismatch; candidate marked unusable"
+                    );
+                    let now = current_epoch_ms();
+                    if let Err(err) = history.mark_unusable(
+                        host,
+                        port,
+                        mismatch.ip,
+                        &mismatch.detail,
+                        now + CERT_MISMATCH_QUARANTINE_MS,
+                        now,
+                    ) {
+                        tracing::warn!(
+                            target = "ip_pool",
+                            host,
+                            port,
+                            error = %err,
+                            "failed to persist unusable candidate"
+                        );
+                    }
+                }
+                None => tracing::debug!(
+                    target = "ip_pool",
+                    host,
+                    port,
+                    error = %err,
+                    "probe failed"
+                ),
+            },
+            Err(err) => tracing::debug!(
                 target = "ip_pool",
                 host,
                 port,
                 error = %err,
-                "probe failed"
+                "probe join error"
             ),
-            Err(err) => tracing::debug!(
+        }
+    }
+
+    if !observed.is_empty() {
+        if let Err(err) = history.fold_quality(host, port, &observed, alpha, current_epoch_ms()) {
+            tracing::warn!(
                 target = "ip_pool",
                 host,
                 port,
                 error = %err,
-                "probe join error"
-            ),
+                "failed to persist ip quality history"
+            );
         }
     }
-
-    stats.sort_by_key(|item| item.latency_ms.unwrap_or(u32::MAX));
+    rank_stats(&mut stats, runtime_cfg);
     stats
 }
 
-/// TCP 握手延迟测试（传统方式，TUN 模式下可能返回本地延迟）
-pub async fn probe_latency_tcp(ip: IpAddr, port: u16, timeout_ms: u64) -> Result<u32> {
+/// TCP 握手延迟测试（传统方式，TUN 模式下可能返回本地延迟；目标走代理时测得经代理隧道的建连耗时）
+pub async fn probe_latency_tcp(
+    ip: IpAddr,
+    port: u16,
+    host: &str,
+    timeout_ms: u64,
+    env: &ProbeEnv,
+) -> Result<u32> {
     let addr = SocketAddr::new(ip, port);
     let timeout = Duration::from_millis(timeout_ms);
     let start = Instant::now();
-    let stream = tokio::time::timeout(timeout, TcpStream::connect(addr)).await??;
+    let stream = tokio::time::timeout(timeout, probe_connect(addr, host, &env.proxy)).await??;
     let elapsed = start.elapsed();
     drop(stream);
     Ok(elapsed.as_millis().min(u128::from(u32::MAX)) as u32)
@@ -1304,6 +1569,7 @@ pub async fn probe_latency_tcp(ip: IpAddr, port: u16, timeout_ms: u64) -> Result
 /// * `sni_host` - 用于 TLS SNI 的主机名（可以是伪装的 SNI）
 /// * `path` - HTTP 请求路径（默认 "/"）
 /// * `timeout_ms` - 超时时间（毫秒）
+/// * `env` - 本轮探测的配置快照（代理路由）
 pub async fn probe_latency_http(
     ip: IpAddr,
     port: u16,
@@ -1311,12 +1577,13 @@ pub async fn probe_latency_http(
     sni_host: &str,
     path: &str,
     timeout_ms: u64,
+    env: &ProbeEnv,
 ) -> Result<u32> {
     let addr = SocketAddr::new(ip, port);
     let timeout_duration = Duration::from_millis(timeout_ms);
 
-    // 1. 建立 TCP 连接
-    let tcp_stream = timeout(timeout_duration, TcpStream::connect(addr))
+    // 1. 建立 TCP 连接（目标走代理时经代理隧道）
+    let tcp_stream = timeout(timeout_duration, probe_connect(addr, host, &env.proxy))
         .await
         .context("tcp connect timeout")?
         .context("tcp connect failed")?;
@@ -1327,6 +1594,10 @@ pub async fn probe_latency_http(
         metrics_enabled: true,
         cert_fp_log_enabled: false, // 探测时不需要记录证书指纹
         cert_fp_max_bytes: 0,
+        custom_cas: Vec::new(),
+        use_system_trust_store: false,
+        client_certs: Vec::new(),
+        pin_policies: Vec::new(),
     };
     let tls_config = Arc::new(create_client_config_with_expected_name(&tls_cfg, host));
     let connector = TlsConnector::from(tls_config);
@@ -1375,7 +1646,121 @@ pub async fn probe_latency_http(
     Ok(elapsed.as_millis().min(u128::from(u32::MAX)) as u32)
 }
 
+/// TLS 探测的分段耗时
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TlsProbeTiming {
+    /// TCP 连接耗时（毫秒）
+    pub connect_ms: u32,
+    /// TLS 握手耗时（毫秒，含证书校验）
+    pub handshake_ms: u32,
+}
+
+impl TlsProbeTiming {
+    pub fn total_ms(&self) -> u32 {
+        self.connect_ms.saturating_add(self.handshake_ms)
+    }
+}
+
+/// 候选返回的证书与真实主机不匹配（常见于被劫持或封锁的节点），该 IP 不可用而非仅仅较慢。
+#[derive(Debug, Clone)]
+pub struct CertMismatch {
+    pub ip: IpAddr,
+    pub detail: String,
+}
+
+impl std::fmt::Display for CertMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "certificate mismatch from {}: {}", self.ip, self.detail)
+    }
+}
+
+impl std::error::Error for CertMismatch {}
+
+pub fn is_cert_mismatch(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<CertMismatch>().is_some()
+}
+
+/// 握手错误是否为证书校验失败（主机名 / 签发者 / 签名不符或 pin 不匹配）；过期类错误可能源于本地时钟，不计入。
+fn cert_mismatch_detail(err: &std::io::Error) -> Option<String> {
+    use rustls::{CertificateError, Error as TlsError};
+    let tls_err = err.get_ref()?.downcast_ref::<TlsError>()?;
+    match tls_err {
+        TlsError::InvalidCertificate(CertificateError::Expired | CertificateError::NotValidYet) => {
+            None
+        }
+        TlsError::InvalidCertificate(_) => Some(tls_err.to_string()),
+        TlsError::General(msg) if msg == "cert_fp_pin_mismatch" => Some(msg.clone()),
+        _ => None,
+    }
+}
+
+/// TLS 握手探测：以 `sni_host` 完成完整握手并按真实 `host` 校验证书，分别计量连接与握手耗时。
+///
+/// 证书校验失败时返回 [`CertMismatch`] 错误，调用方据此将候选标记为不可用。
+pub async fn probe_tls_handshake(
+    ip: IpAddr,
+    port: u16,
+    host: &str,
+    sni_host: &str,
+    env: &ProbeEnv,
+    timeout_ms: u64,
+) -> Result<TlsProbeTiming> {
+    let addr = SocketAddr::new(ip, port);
+    let timeout_duration = Duration::from_millis(timeout_ms);
+    let server_name = ServerName::try_from(sni_host)
+        .map_err(|_| anyhow!("invalid sni hostname: {}", sni_host))?;
+    let mut tls_cfg = env.tls.clone();
+    tls_cfg.cert_fp_log_enabled = false;
+    let connector = TlsConnector::from(Arc::new(create_client_config_with_expected_name(
+        &tls_cfg, host,
+    )));
+
+    let start = Instant::now();
+    let tcp_stream = timeout(timeout_duration, probe_connect(addr, host, &env.proxy))
+        .await
+        .context("tcp connect timeout")?
+        .context("tcp connect failed")?;
+    let connected = Instant::now();
+    let handshake = timeout(timeout_duration, connector.connect(server_name, tcp_stream))
+        .await
+        .context("tls handshake timeout")?;
+    let handshake_done = Instant::now();
+    if let Err(err) = handshake {
+        return match cert_mismatch_detail(&err) {
+            Some(detail) => Err(CertMismatch { ip, detail }.into()),
+            None => Err(anyhow::Error::new(err).context("tls handshake failed")),
+        };
+    }
+    let to_ms = |d: std::time::Duration| d.as_millis().min(u128::from(u32::MAX)) as u32;
+    Ok(TlsProbeTiming {
+        connect_ms: to_ms(connected - start),
+        handshake_ms: to_ms(handshake_done - connected),
+    })
+}
+
+/// 探测拨号：`host` 按路由走代理时经代理连接器隧道到候选 IP（测得经代理的路径），否则直连。
+///
+/// 路由按调用方传入的配置快照判定，不在每次探测时重新读取配置。
+async fn probe_connect(
+    addr: SocketAddr,
+    host: &str,
+    proxy: &ProxyConfig,
+) -> std::io::Result<AsyncProxyStream> {
+    if routed_through_proxy(proxy, host, addr.port()) {
+        let target = addr.ip().to_string();
+        return tunnel::connect(proxy, &target_url(host, addr.port()), &target, addr.port()).await;
+    }
+    TcpStream::connect(addr).await.map(AsyncProxyStream::from)
+}
+
+/// TLS 探测使用的 SNI：与实际连接相同的伪 SNI / 真实 SNI 决策。
+fn probe_sni_host(cfg: &AppConfig, host: &str, port: u16) -> String {
+    let proxy = proxy_in_use(&cfg.proxy, host, port);
+    decide_sni_host_with_proxy(cfg, false, host, proxy).0
+}
+
 /// 根据配置选择的探测方法测量延迟
+#[allow(clippy::too_many_arguments)]
 pub async fn probe_latency_impl(
     ip: IpAddr,
     port: u16,
@@ -1384,10 +1769,26 @@ pub async fn probe_latency_impl(
     path: &str,
     timeout_ms: u64,
     method: ProbeMethod,
+    env: &ProbeEnv,
 ) -> Result<u32> {
     match method {
-        ProbeMethod::Http => probe_latency_http(ip, port, host, sni_host, path, timeout_ms).await,
-        ProbeMethod::Tcp => probe_latency_tcp(ip, port, timeout_ms).await,
+        ProbeMethod::Http => {
+            probe_latency_http(ip, port, host, sni_host, path, timeout_ms, env).await
+        }
+        ProbeMethod::Tcp => probe_latency_tcp(ip, port, host, timeout_ms, env).await,
+        ProbeMethod::Tls => {
+            let timing = probe_tls_handshake(ip, port, host, sni_host, env, timeout_ms).await?;
+            tracing::debug!(
+                target = "ip_pool",
+                host,
+                sni = sni_host,
+                ip = %ip,
+                connect_ms = timing.connect_ms,
+                handshake_ms = timing.handshake_ms,
+                "tls probe timing"
+            );
+            Ok(timing.total_ms())
+        }
     }
 }
 
@@ -1422,6 +1823,7 @@ pub fn update_cache_and_history(
         measured_at_epoch_ms: best.measured_at_epoch_ms.unwrap_or_else(current_epoch_ms),
         expires_at_epoch_ms: best.expires_at_epoch_ms.unwrap_or_else(current_epoch_ms),
         resolver_metadata: best.resolver_metadata.clone(),
+        pending_validation: false,
     };
     history.upsert(record)?;
     Ok(())
@@ -1501,7 +1903,7 @@ pub fn user_static_lookup(host: &str, port: u16, entries: &[UserStaticIp]) -> Ve
     entries
         .iter()
         .filter(|entry| entry.host.eq_ignore_ascii_case(host) && entry.ports.contains(&port))
-        .filter_map(|entry| entry.ip.parse().ok())
+        .filter_map(|entry| parse_ip_literal(&entry.ip))
         .collect()
 }
 
@@ -1598,7 +2000,15 @@ pub(crate) const BUILTIN_IPS: &[(&str, &[&str])] = &[
     ),
     (
         "githubusercontent.com",
-        &["185.199.108.133", "185.199.110.133", "185.199.111.133"],
+        &[
+            "185.199.108.133",
+            "185.199.110.133",
+            "185.199.111.133",
+            "2606:50c0:8000::133",
+            "2606:50c0:8001::133",
+            "2606:50c0:8002::133",
+            "2606:50c0:8003::133",
+        ],
     ),
     (
         "*.githubusercontent.com",
@@ -1622,6 +2032,10 @@ pub(crate) const BUILTIN_IPS: &[(&str, &[&str])] = &[
             "185.199.109.153",
             "185.199.110.153",
             "185.199.111.153",
+            "2606:50c0:8000::153",
+            "2606:50c0:8001::153",
+            "2606:50c0:8002::153",
+            "2606:50c0:8003::153",
         ],
     ),
     (
@@ -1631,6 +2045,10 @@ pub(crate) const BUILTIN_IPS: &[(&str, &[&str])] = &[
             "185.199.109.154",
             "185.199.110.154",
             "185.199.111.154",
+            "2606:50c0:8000::154",
+            "2606:50c0:8001::154",
+            "2606:50c0:8002::154",
+            "2606:50c0:8003::154",
         ],
     ),
     (
@@ -1640,6 +2058,10 @@ pub(crate) const BUILTIN_IPS: &[(&str, &[&str])] = &[
             "185.199.109.153",
             "185.199.110.153",
             "185.199.111.153",
+            "2606:50c0:8000::153",
+            "2606:50c0:8001::153",
+            "2606:50c0:8002::153",
+            "2606:50c0:8003::153",
         ],
     ),
 ];
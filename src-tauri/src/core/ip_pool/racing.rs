@@ -0,0 +1,145 @@
+//! 多候选连接竞速（RFC 8305 Happy Eyeballs）。
+//!
+//! 候选按地址族交替排列（首选族取自排名第一的候选），依次错峰发起连接：上一次尝试失败时立即
+//! 发起下一次，否则等待 `attempt_delay_ms`。首个成功完成（TCP + TLS）的尝试胜出，其余进行中的
+//! 尝试随即取消。每个完成的尝试都会通过回调报告结果；被取消的尝试未得出结论，不计入失败。
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use futures::stream::{FuturesUnordered, StreamExt};
+
+use super::config::ConnectionRacingConfig;
+use super::{IpOutcome, IpStat};
+
+/// 一次已完成的竞速尝试
+#[derive(Debug, Clone)]
+pub struct RaceAttempt {
+    pub stat: IpStat,
+    pub outcome: IpOutcome,
+    pub elapsed: Duration,
+    pub error: Option<String>,
+}
+
+/// 竞速结果：胜出者（若有）与全部已完成的尝试
+pub struct RaceResult<T> {
+    pub winner: Option<(IpStat, T)>,
+    pub attempts: Vec<RaceAttempt>,
+    /// 发起过的尝试数（含被取消的）
+    pub started: usize,
+}
+
+impl<T> RaceResult<T> {
+    /// 失败尝试的错误描述（按完成顺序）
+    pub fn errors(&self) -> Vec<String> {
+        self.attempts
+            .iter()
+            .filter_map(|a| {
+                a.error
+                    .as_ref()
+                    .map(|e| format!("{}: {e}", a.stat.candidate.address))
+            })
+            .collect()
+    }
+}
+
+/// 是否值得竞速：开启且至少有两个候选
+pub fn should_race(cfg: &ConnectionRacingConfig, candidates: usize) -> bool {
+    cfg.enabled && cfg.max_attempts > 1 && candidates > 1
+}
+
+/// 按地址族交替排列候选（RFC 8305 §4），族内保持原有排名。
+pub fn interleave_families(candidates: Vec<IpStat>) -> Vec<IpStat> {
+    let Some(first_v6) = candidates.first().map(|c| c.candidate.address.is_ipv6()) else {
+        return candidates;
+    };
+    let (mut preferred, mut other): (VecDeque<IpStat>, VecDeque<IpStat>) = candidates
+        .into_iter()
+        .partition(|c| c.candidate.address.is_ipv6() == first_v6);
+    let mut out = Vec::with_capacity(preferred.len() + other.len());
+    loop {
+        match (preferred.pop_front(), other.pop_front()) {
+            (None, None) => break,
+            (a, b) => out.extend(a.into_iter().chain(b)),
+        }
+    }
+    out
+}
+
+/// 对前 `max_attempts` 个候选执行错峰竞速。
+///
+/// `connect` 完成单个候选的连接（通常为 TCP + TLS 握手并自带超时）；`report` 在每个尝试完成时调用。
+pub async fn race_candidates<T, F, Fut, R>(
+    candidates: Vec<IpStat>,
+    cfg: &ConnectionRacingConfig,
+    mut connect: F,
+    mut report: R,
+) -> RaceResult<T>
+where
+    F: FnMut(IpStat) -> Fut,
+    Fut: Future<Output = Result<T, String>>,
+    R: FnMut(&IpStat, IpOutcome),
+{
+    let mut pending: VecDeque<IpStat> = interleave_families(candidates)
+        .into_iter()
+        .take(cfg.max_attempts.max(1))
+        .collect();
+    let delay = Duration::from_millis(cfg.attempt_delay_ms);
+    let mut inflight = FuturesUnordered::new();
+    let mut attempts = Vec::new();
+    let mut started = 0usize;
+
+    let mut launch = |stat: IpStat| {
+        let fut = connect(stat.clone());
+        let begin = Instant::now();
+        async move {
+            let res = fut.await;
+            (stat, res, begin.elapsed())
+        }
+    };
+
+    loop {
+        if inflight.is_empty() {
+            match pending.pop_front() {
+                Some(stat) => {
+                    started += 1;
+                    inflight.push(launch(stat));
+                }
+                None => break,
+            }
+        }
+        let next_due = tokio::time::sleep(delay);
+        tokio::select! {
+            Some((stat, res, elapsed)) = inflight.next() => match res {
+                Ok(value) => {
+                    report(&stat, IpOutcome::Success);
+                    tracing::debug!(target = "ip_pool", ip = %stat.candidate.address, elapsed_ms = elapsed.as_millis() as u64, started, "connection race won");
+                    attempts.push(RaceAttempt { stat: stat.clone(), outcome: IpOutcome::Success, elapsed, error: None });
+                    return RaceResult { winner: Some((stat, value)), attempts, started };
+                }
+                Err(err) => {
+                    report(&stat, IpOutcome::Failure);
+                    tracing::debug!(target = "ip_pool", ip = %stat.candidate.address, error = %err, "connection race attempt failed");
+                    attempts.push(RaceAttempt { stat, outcome: IpOutcome::Failure, elapsed, error: Some(err) });
+                    // 失败后立即发起下一个候选
+                    if let Some(stat) = pending.pop_front() {
+                        started += 1;
+                        inflight.push(launch(stat));
+                    }
+                }
+            },
+            _ = next_due, if !pending.is_empty() => {
+                if let Some(stat) = pending.pop_front() {
+                    started += 1;
+                    inflight.push(launch(stat));
+                }
+            }
+        }
+    }
+    RaceResult {
+        winner: None,
+        attempts,
+        started,
+    }
+}
@@ -1,10 +1,11 @@
-use std::{sync::Arc, time::Duration};
+use std::{net::IpAddr, sync::Arc, time::Duration};
 
 use anyhow::Result;
 use tokio::{sync::Notify, time::timeout};
 
 use super::{
     cache::{IpCacheKey, IpCacheSlot, IpStat},
+    family::family_allowed,
     maintenance,
     manager::IpPool,
     preheat,
@@ -19,9 +20,13 @@ pub(super) fn get_fresh_cached(
     now_ms: i64,
 ) -> Option<IpCacheSlot> {
     if let Some(mut slot) = pool.cache.get(host, port) {
-        // Filter out tripped IPs from alternatives
+        // 熔断中的 IP 与当前 IPv6 策略不允许的地址族均不可用；此处只读检查，
+        // 半开试探名额仅由实际首先尝试的 IP 占用
+        let ipv6_mode = pool.config.runtime.ipv6;
+        let usable =
+            |ip: IpAddr| family_allowed(ipv6_mode, ip) && !pool.is_ip_tripped_for(host, ip);
         slot.alternatives
-            .retain(|alt| !alt.is_expired(now_ms) && !pool.is_ip_tripped(alt.candidate.address));
+            .retain(|alt| !alt.is_expired(now_ms) && usable(alt.candidate.address));
 
         match slot.best.clone() {
             Some(best) => {
@@ -31,8 +36,9 @@ pub(super) fn get_fresh_cached(
                     }
                     return None;
                 }
-                if pool.is_ip_tripped(best.candidate.address) {
-                    // Best is tripped, fall back to alternatives or refresh
+                if !usable(best.candidate.address) || !pool.admit_ip(host, best.candidate.address) {
+                    // Best is tripped or filtered, fall back to alternatives or refresh
+                    admit_leading(pool, host, &mut slot.alternatives);
                     if !slot.alternatives.is_empty() {
                         return Some(IpCacheSlot {
                             best: None,
@@ -57,6 +63,16 @@ pub(super) fn get_fresh_cached(
     None
 }
 
+/// 为首个备选占用半开试探名额；名额已被并发请求占满的备选顺延丢弃。
+fn admit_leading(pool: &IpPool, host: &str, alternatives: &mut Vec<IpStat>) {
+    while let Some(first) = alternatives.first() {
+        if pool.admit_ip(host, first.candidate.address) {
+            return;
+        }
+        alternatives.remove(0);
+    }
+}
+
 pub(super) async fn ensure_sampled(
     pool: &IpPool,
     host: &str,
@@ -112,7 +128,7 @@ async fn sample_once(pool: &IpPool, host: &str, port: u16) -> Result<Option<IpCa
 
     let mut candidates = preheat::collect_candidates(host, port, &config, history.clone()).await;
     // Filter out tripped IPs to prevent re-probing known bad IPs unnecessarily
-    candidates.retain(|c| !pool.is_ip_tripped(c.candidate.address));
+    candidates.retain(|c| !pool.is_ip_tripped_for(host, c.candidate.address));
 
     if candidates.is_empty() {
         tracing::warn!(
@@ -138,6 +154,7 @@ async fn sample_once(pool: &IpPool, host: &str, port: u16) -> Result<Option<IpCa
         port,
         candidates,
         &config.runtime,
+        &history,
         config.file.score_ttl_seconds,
         None,
         preheat::default_latency_prober(),
@@ -0,0 +1,104 @@
+//! 多维评分：延迟、抖动、丢包与被动吞吐量的加权综合得分。
+//!
+//! 预热 / 按需探测对每个候选重复采样得到延迟中位数、抖动与丢包率；真实 git 传输完成后以
+//! `IpOutcome::Throughput` 反馈吞吐量。两类观测按 EWMA 合并进 `IpHistoryStore`，排序时使用合并后的值。
+
+use super::cache::{IpQuality, IpStat};
+use super::config::{IpPoolRuntimeConfig, ScoringConfig};
+use super::family::family_bias;
+
+/// 吞吐量加分的上限（MiB/s），避免单次高速传输压过延迟与丢包
+const THROUGHPUT_CAP_MIBPS: f64 = 10.0;
+/// 无任何延迟数据时的得分
+const UNMEASURED_SCORE: f64 = u32::MAX as f64;
+
+/// 汇总一轮重复采样：延迟取中位数，抖动为相邻采样差的平均值，丢包按失败次数计算。
+/// 全部失败时返回 `None`。
+pub fn summarize_samples(latencies: &[u32], attempts: u32) -> Option<IpQuality> {
+    if latencies.is_empty() {
+        return None;
+    }
+    let mut sorted = latencies.to_vec();
+    sorted.sort_unstable();
+    let median = sorted[sorted.len() / 2];
+    let jitter = (latencies.len() > 1).then(|| {
+        let total: u64 = latencies
+            .windows(2)
+            .map(|w| u64::from(w[0].abs_diff(w[1])))
+            .sum();
+        (total / (latencies.len() as u64 - 1)) as u32
+    });
+    let attempts = attempts.max(latencies.len() as u32);
+    let lost = attempts - latencies.len() as u32;
+    Some(IpQuality {
+        latency_ms: Some(median),
+        jitter_ms: jitter,
+        loss_permille: (attempts > 1).then(|| (lost * 1000 / attempts) as u16),
+        throughput_kibps: None,
+        samples: 1,
+    })
+}
+
+/// 由传输字节数与耗时计算吞吐量（KiB/s）
+pub fn throughput_kibps(bytes: u64, elapsed_ms: u64) -> u32 {
+    let kib_per_sec = bytes.saturating_mul(1000) / elapsed_ms.max(1) / 1024;
+    kib_per_sec.min(u64::from(u32::MAX)) as u32
+}
+
+fn ewma(prev: Option<u32>, sample: Option<u32>, alpha: f64) -> Option<u32> {
+    match (prev, sample) {
+        (Some(p), Some(s)) => {
+            Some((alpha * f64::from(s) + (1.0 - alpha) * f64::from(p)).round() as u32)
+        }
+        (p, s) => s.or(p),
+    }
+}
+
+/// 将一次观测按 EWMA 合并进历史值；观测中缺失的维度保留历史值。
+pub fn blend_quality(prev: Option<&IpQuality>, sample: &IpQuality, alpha: f64) -> IpQuality {
+    let Some(prev) = prev else {
+        return *sample;
+    };
+    let alpha = alpha.clamp(0.01, 1.0);
+    IpQuality {
+        latency_ms: ewma(prev.latency_ms, sample.latency_ms, alpha),
+        jitter_ms: ewma(prev.jitter_ms, sample.jitter_ms, alpha),
+        loss_permille: ewma(
+            prev.loss_permille.map(u32::from),
+            sample.loss_permille.map(u32::from),
+            alpha,
+        )
+        .map(|v| v.min(1000) as u16),
+        throughput_kibps: ewma(prev.throughput_kibps, sample.throughput_kibps, alpha),
+        samples: prev.samples.saturating_add(sample.samples.max(1)),
+    }
+}
+
+/// 综合得分（以毫秒计，越小越好；吞吐量加分可使其为负）
+pub fn composite_score(stat: &IpStat, cfg: &ScoringConfig) -> f64 {
+    let quality = stat.quality.unwrap_or_default();
+    let Some(latency) = quality.latency_ms.or(stat.latency_ms) else {
+        return UNMEASURED_SCORE;
+    };
+    let jitter = f64::from(quality.jitter_ms.unwrap_or(0));
+    let loss_pct = f64::from(quality.loss_permille.unwrap_or(0)) / 10.0;
+    let throughput_mibps = quality
+        .throughput_kibps
+        .map(|k| (f64::from(k) / 1024.0).min(THROUGHPUT_CAP_MIBPS))
+        .unwrap_or(0.0);
+    f64::from(latency) * cfg.latency_weight
+        + jitter * cfg.jitter_weight
+        + loss_pct * cfg.loss_weight
+        - throughput_mibps * cfg.throughput_weight
+}
+
+/// 排序得分：综合得分再扣除地址族偏好
+pub fn rank_score(stat: &IpStat, runtime: &IpPoolRuntimeConfig) -> f64 {
+    let bias = family_bias(runtime.ipv6, runtime.ipv6_bias_ms, stat.candidate.address);
+    composite_score(stat, &runtime.scoring) - f64::from(bias)
+}
+
+/// 按排序得分升序排列（稳定排序，得分相同保持原顺序）。
+pub fn rank_stats(stats: &mut [IpStat], runtime: &IpPoolRuntimeConfig) {
+    stats.sort_by(|a, b| rank_score(a, runtime).total_cmp(&rank_score(b, runtime)));
+}
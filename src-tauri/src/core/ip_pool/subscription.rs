@@ -0,0 +1,416 @@
+//! 外部 IP 候选列表订阅（`IpSource::Subscription`）。
+//!
+//! 订阅源可以是本地文件或 HTTPS URL，内容为签名列表：
+//!
+//! ```json
+//! { "payload": "<Base64 编码的列表 JSON>", "signature": "<对 payload 原始字节的 Ed25519 签名，Base64>" }
+//! ```
+//!
+//! 列表 JSON 形如 `{"generatedAt": "...", "entries": [{"host": "github.com", "ips": ["140.82.112.3"], "ports": [443]}]}`。
+//! 签名必须能被订阅配置中的公钥验证；条目的域名必须命中订阅的 `allowed_domains`（支持 `*.` 通配），
+//! 否则整条丢弃。加载结果保存在进程内注册表中，由预热线程按 `refresh_interval_secs` 定期刷新，
+//! 收集候选时只读取注册表；HTTPS 源经共享的 [`HttpClient`] 拉取（遵循代理路由与 TLS 配置）。
+//! `generatedAt` 早于已加载列表的新列表视为回滚并拒绝；刷新失败保留上一次的有效列表，
+//! 并发布 `IpPoolSubscriptionUpdate` 事件。
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use anyhow::{anyhow, bail, Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use chrono::{DateTime, NaiveDate, Utc};
+use ring::signature::{UnparsedPublicKey, ED25519};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+use tokio::time::{sleep, Duration};
+use url::Url;
+
+use super::config::IpSubscription;
+use super::events::emit_ip_pool_subscription_update;
+use super::family::parse_ip_literal;
+use super::preheat::current_epoch_ms;
+use crate::core::config::loader::load_or_init;
+use crate::core::config::model::AppConfig;
+use crate::core::http::client::HttpClient;
+use crate::core::http::types::HttpRequestInput;
+use crate::core::tls::util::match_domain;
+
+/// 列表大小上限
+const MAX_LIST_BYTES: usize = 1024 * 1024;
+/// HTTPS 拉取超时（毫秒）
+const FETCH_TIMEOUT_MS: u64 = 15_000;
+/// 后台刷新循环检查到期订阅的间隔
+const REFRESH_TICK: Duration = Duration::from_secs(30);
+/// 刷新失败后的重试间隔上限（毫秒）
+const RETRY_AFTER_FAILURE_MS: i64 = 10 * 60 * 1000;
+
+/// 签名列表外层结构
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignedIpList {
+    pub payload: String,
+    pub signature: String,
+}
+
+/// 签名覆盖的列表内容
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct IpListPayload {
+    #[serde(default)]
+    pub generated_at: Option<String>,
+    #[serde(default)]
+    pub entries: Vec<IpListEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct IpListEntry {
+    pub host: String,
+    #[serde(default)]
+    pub ips: Vec<String>,
+    #[serde(default = "default_entry_ports")]
+    pub ports: Vec<u16>,
+}
+
+fn default_entry_ports() -> Vec<u16> {
+    vec![443]
+}
+
+/// 校验通过并按白名单过滤后的列表
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerifiedIpList {
+    pub generated_at: Option<String>,
+    /// (小写 host, 端口, IP)
+    pub entries: Vec<(String, u16, IpAddr)>,
+    /// 因域名不在白名单或 IP 非法而丢弃的条目数
+    pub rejected: usize,
+}
+
+/// 订阅状态（供快照展示）
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscriptionStatus {
+    pub name: String,
+    pub url: String,
+    pub enabled: bool,
+    pub entries: usize,
+    pub rejected: usize,
+    pub generated_at: Option<String>,
+    pub loaded_at_epoch_ms: Option<i64>,
+    pub next_refresh_epoch_ms: Option<i64>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct SubscriptionState {
+    source: IpSubscription,
+    list: VerifiedIpList,
+    loaded_at_ms: Option<i64>,
+    next_due_ms: i64,
+    last_error: Option<String>,
+}
+
+fn registry() -> &'static Mutex<HashMap<String, SubscriptionState>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<String, SubscriptionState>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 验证签名并解析列表内容。
+pub fn verify_signed_list(raw: &[u8], public_key_b64: &str) -> Result<IpListPayload> {
+    let signed: SignedIpList = serde_json::from_slice(raw).context("parse signed ip list")?;
+    let public_key = BASE64
+        .decode(public_key_b64.trim())
+        .context("decode subscription public key")?;
+    let payload = BASE64
+        .decode(signed.payload.trim())
+        .context("decode list payload")?;
+    let signature = BASE64
+        .decode(signed.signature.trim())
+        .context("decode list signature")?;
+    UnparsedPublicKey::new(&ED25519, &public_key)
+        .verify(&payload, &signature)
+        .map_err(|_| anyhow!("signature verification failed"))?;
+    serde_json::from_slice(&payload).context("parse list payload")
+}
+
+/// 按订阅的域名白名单过滤条目；白名单为空时拒绝全部条目。
+pub fn filter_allowed(payload: IpListPayload, allowed_domains: &[String]) -> VerifiedIpList {
+    let mut out = VerifiedIpList {
+        generated_at: payload.generated_at,
+        ..VerifiedIpList::default()
+    };
+    for entry in payload.entries {
+        let host = entry.host.trim().to_ascii_lowercase();
+        let allowed = allowed_domains
+            .iter()
+            .any(|pattern| match_domain(pattern.trim(), &host));
+        if !allowed {
+            out.rejected += 1;
+            continue;
+        }
+        let ips: Vec<IpAddr> = entry
+            .ips
+            .iter()
+            .filter_map(|ip| parse_ip_literal(ip))
+            .collect();
+        if ips.len() != entry.ips.len() || entry.ports.is_empty() {
+            out.rejected += 1;
+            continue;
+        }
+        for port in &entry.ports {
+            for ip in &ips {
+                out.entries.push((host.clone(), *port, *ip));
+            }
+        }
+    }
+    out
+}
+
+async fn fetch_https(url: &Url, cfg: AppConfig) -> Result<Vec<u8>> {
+    let input = HttpRequestInput {
+        url: url.to_string(),
+        method: "GET".into(),
+        headers: HashMap::from([(
+            "User-Agent".to_string(),
+            "fireworks-ip-subscription/1.0".to_string(),
+        )]),
+        body_base64: None,
+        timeout_ms: FETCH_TIMEOUT_MS,
+        force_real_sni: false,
+        follow_redirects: false,
+        max_redirects: 0,
+    };
+    let resp = HttpClient::new(cfg).send(input).await?;
+    if resp.status != 200 {
+        bail!("unexpected HTTP status {}", resp.status);
+    }
+    if resp.body_size > MAX_LIST_BYTES {
+        bail!("list exceeds {MAX_LIST_BYTES} bytes");
+    }
+    BASE64
+        .decode(resp.body_base64)
+        .context("decode http response body")
+}
+
+/// 读取订阅源原始内容：`https://` URL 或本地文件（可带 `file://` 前缀）。
+pub async fn fetch_source(location: &str) -> Result<Vec<u8>> {
+    let cfg = load_or_init().unwrap_or_else(|_| AppConfig::default());
+    fetch_source_with(location, cfg).await
+}
+
+/// 同 [`fetch_source`]，HTTPS 拉取使用给定配置（代理路由、TLS 与 IP 池设置）。
+pub async fn fetch_source_with(location: &str, cfg: AppConfig) -> Result<Vec<u8>> {
+    let location = location.trim();
+    if let Ok(url) = Url::parse(location) {
+        match url.scheme() {
+            "https" => return fetch_https(&url, cfg).await,
+            "file" => {
+                let path = url
+                    .to_file_path()
+                    .map_err(|_| anyhow!("invalid file url: {location}"))?;
+                return read_local(&path);
+            }
+            // Windows 盘符会被解析为单字母 scheme
+            scheme if scheme.len() > 1 => bail!("unsupported subscription scheme: {scheme}"),
+            _ => {}
+        }
+    }
+    read_local(Path::new(location))
+}
+
+fn read_local(path: &Path) -> Result<Vec<u8>> {
+    let data = std::fs::read(path).with_context(|| format!("read {}", path.display()))?;
+    if data.len() > MAX_LIST_BYTES {
+        bail!("list exceeds {MAX_LIST_BYTES} bytes");
+    }
+    Ok(data)
+}
+
+/// 拉取并校验一个订阅（不写入注册表）。
+pub async fn load_subscription(sub: &IpSubscription) -> Result<VerifiedIpList> {
+    let raw = fetch_source(&sub.url).await?;
+    let payload = verify_signed_list(&raw, &sub.public_key)?;
+    Ok(filter_allowed(payload, &sub.allowed_domains))
+}
+
+/// 解析 `generatedAt`：RFC 3339 时间点，或 `YYYY-MM-DD`（当天开始）。
+fn parse_generated_at(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(ts) = DateTime::parse_from_rfc3339(value) {
+        return Some(ts.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|dt| dt.and_utc())
+}
+
+/// 拒绝回滚：已加载列表带有可解析的 `generatedAt` 时，新列表必须不早于它。
+fn check_not_older(loaded: &VerifiedIpList, fresh: &VerifiedIpList) -> Result<()> {
+    let Some(current) = loaded.generated_at.as_deref().and_then(parse_generated_at) else {
+        return Ok(());
+    };
+    match fresh.generated_at.as_deref().and_then(parse_generated_at) {
+        Some(next) if next >= current => Ok(()),
+        Some(_) => bail!(
+            "list generatedAt {} is older than the loaded {}",
+            fresh.generated_at.as_deref().unwrap_or_default(),
+            loaded.generated_at.as_deref().unwrap_or_default()
+        ),
+        None => bail!("list is missing a valid generatedAt; the loaded list has one"),
+    }
+}
+
+fn refresh_interval_ms(sub: &IpSubscription) -> i64 {
+    (sub.refresh_interval_secs.max(60) as i64).saturating_mul(1000)
+}
+
+/// 刷新所有已启用且到期的订阅；配置变化（URL / 公钥 / 白名单）的订阅立即重新加载。
+/// 返回本次实际尝试刷新的订阅数。
+pub async fn refresh_due(subs: &[IpSubscription], now_ms: i64) -> usize {
+    let due: Vec<IpSubscription> = {
+        let Ok(mut guard) = registry().lock() else {
+            return 0;
+        };
+        let mut due = Vec::new();
+        for sub in subs.iter().filter(|s| s.enabled) {
+            let stale_config = guard.get(&sub.name).is_some_and(|st| st.source != *sub);
+            if stale_config {
+                guard.remove(&sub.name);
+            }
+            let state = guard
+                .entry(sub.name.clone())
+                .or_insert_with(|| SubscriptionState {
+                    source: sub.clone(),
+                    list: VerifiedIpList::default(),
+                    loaded_at_ms: None,
+                    next_due_ms: now_ms,
+                    last_error: None,
+                });
+            if state.next_due_ms <= now_ms {
+                // 先推迟下一次到期时间，避免并发收集重复拉取
+                state.next_due_ms = now_ms + refresh_interval_ms(sub);
+                due.push(sub.clone());
+            }
+        }
+        due
+    };
+
+    for sub in &due {
+        let result = load_subscription(sub).await;
+        let now = current_epoch_ms();
+        let Ok(mut guard) = registry().lock() else {
+            break;
+        };
+        let Some(state) = guard.get_mut(&sub.name) else {
+            continue;
+        };
+        let result = result.and_then(|list| check_not_older(&state.list, &list).map(|_| list));
+        match result {
+            Ok(list) => {
+                tracing::info!(
+                    target = "ip_pool",
+                    subscription = %sub.name,
+                    entries = list.entries.len(),
+                    rejected = list.rejected,
+                    "ip subscription refreshed"
+                );
+                emit_ip_pool_subscription_update(
+                    &sub.name,
+                    true,
+                    list.entries.len(),
+                    list.rejected,
+                    None,
+                );
+                state.list = list;
+                state.loaded_at_ms = Some(now);
+                state.last_error = None;
+            }
+            Err(err) => {
+                let message = format!("{err:#}");
+                tracing::warn!(
+                    target = "ip_pool",
+                    subscription = %sub.name,
+                    error = %message,
+                    "ip subscription refresh failed; keeping previous list"
+                );
+                emit_ip_pool_subscription_update(
+                    &sub.name,
+                    false,
+                    state.list.entries.len(),
+                    0,
+                    Some(message.clone()),
+                );
+                state.last_error = Some(message);
+                state.next_due_ms = now + refresh_interval_ms(sub).min(RETRY_AFTER_FAILURE_MS);
+            }
+        }
+    }
+    due.len()
+}
+
+/// 后台刷新循环：立即刷新一次到期订阅，之后每隔 [`REFRESH_TICK`] 检查一次，直到 `stop` 置位。
+pub async fn run_refresh_loop(
+    subs: Vec<IpSubscription>,
+    stop: Arc<AtomicBool>,
+    notify: Arc<Notify>,
+) {
+    while !stop.load(Ordering::Relaxed) {
+        refresh_due(&subs, current_epoch_ms()).await;
+        tokio::select! {
+            _ = sleep(REFRESH_TICK) => {}
+            _ = notify.notified() => {}
+        }
+    }
+}
+
+/// 查询已加载订阅中 `host:port` 的候选，返回 (IP, 订阅名)。仅包含配置中仍启用的订阅。
+pub fn lookup(host: &str, port: u16, subs: &[IpSubscription]) -> Vec<(IpAddr, String)> {
+    let Ok(guard) = registry().lock() else {
+        return Vec::new();
+    };
+    let host = host.to_ascii_lowercase();
+    let mut out = Vec::new();
+    for sub in subs.iter().filter(|s| s.enabled) {
+        let Some(state) = guard.get(&sub.name).filter(|st| st.source == *sub) else {
+            continue;
+        };
+        out.extend(
+            state
+                .list
+                .entries
+                .iter()
+                .filter(|(h, p, _)| *h == host && *p == port)
+                .map(|(_, _, ip)| (*ip, sub.name.clone())),
+        );
+    }
+    out
+}
+
+/// 订阅状态快照（按配置顺序）
+pub fn subscription_status(subs: &[IpSubscription]) -> Vec<SubscriptionStatus> {
+    let guard = registry().lock().ok();
+    subs.iter()
+        .map(|sub| {
+            let state = guard
+                .as_ref()
+                .and_then(|g| g.get(&sub.name))
+                .filter(|st| st.source == *sub);
+            SubscriptionStatus {
+                name: sub.name.clone(),
+                url: sub.url.clone(),
+                enabled: sub.enabled,
+                entries: state.map(|s| s.list.entries.len()).unwrap_or(0),
+                rejected: state.map(|s| s.list.rejected).unwrap_or(0),
+                generated_at: state.and_then(|s| s.list.generated_at.clone()),
+                loaded_at_epoch_ms: state.and_then(|s| s.loaded_at_ms),
+                next_refresh_epoch_ms: state.filter(|_| sub.enabled).map(|s| s.next_due_ms),
+                last_error: state.and_then(|s| s.last_error.clone()),
+            }
+        })
+        .collect()
+}
@@ -76,12 +76,24 @@ pub const PROXY_FALLBACK_TOTAL: MetricDescriptor = MetricDescriptor::counter(
     &["reason"],
 );
 
+pub const PROXY_UPSTREAM_FAILOVER_TOTAL: MetricDescriptor = MetricDescriptor::counter(
+    "proxy_upstream_failover_total",
+    "Upstream proxy failovers",
+    &["from", "to"],
+);
+
 pub const HTTP_STRATEGY_FALLBACK_TOTAL: MetricDescriptor = MetricDescriptor::counter(
     "http_strategy_fallback_total",
     "HTTP strategy fallback transitions",
     &["stage", "from"],
 );
 
+pub const NETWORK_PROFILE_SWITCH_TOTAL: MetricDescriptor = MetricDescriptor::counter(
+    "network_profile_switch_total",
+    "Network profile switches",
+    &["profile", "trigger"],
+);
+
 pub const SOAK_THRESHOLD_VIOLATION_TOTAL: MetricDescriptor = MetricDescriptor::counter(
     "soak_threshold_violation_total",
     "Soak threshold violations",
@@ -133,7 +145,9 @@ pub const BASIC_METRICS: &[MetricDescriptor] = &[
     CIRCUIT_BREAKER_TRIP_TOTAL,
     CIRCUIT_BREAKER_RECOVER_TOTAL,
     PROXY_FALLBACK_TOTAL,
+    PROXY_UPSTREAM_FAILOVER_TOTAL,
     HTTP_STRATEGY_FALLBACK_TOTAL,
+    NETWORK_PROFILE_SWITCH_TOTAL,
     SOAK_THRESHOLD_VIOLATION_TOTAL,
     ALERTS_FIRED_TOTAL,
     METRICS_EXPORT_REQUESTS_TOTAL,
@@ -196,6 +196,25 @@ impl EventMetricsBridge {
                 let reason_label = sanitize_label_value(&reason);
                 self.record_proxy_fallback(&reason_label);
             }
+            StrategyEvent::ProxyUpstreamFailover { from, to, .. } => {
+                let from_label = from
+                    .as_deref()
+                    .map(sanitize_label_value)
+                    .unwrap_or_else(|| "none".to_string());
+                let to_label = to
+                    .as_deref()
+                    .map(sanitize_label_value)
+                    .unwrap_or_else(|| "direct".to_string());
+                self.record_proxy_upstream_failover(&from_label, &to_label);
+            }
+            StrategyEvent::NetworkProfileSwitched { to, trigger, .. } => {
+                let profile_label = to
+                    .as_deref()
+                    .map(sanitize_label_value)
+                    .unwrap_or_else(|| "baseline".to_string());
+                let trigger_label = sanitize_label_value(&trigger);
+                self.record_network_profile_switch(&profile_label, &trigger_label);
+            }
             StrategyEvent::MetricAlert {
                 severity, state, ..
             } => {
@@ -262,6 +281,16 @@ impl EventMetricsBridge {
         record_counter(PROXY_FALLBACK_TOTAL, &labels, 1);
     }
 
+    fn record_proxy_upstream_failover(&self, from: &str, to: &str) {
+        let labels = [("from", from), ("to", to)];
+        record_counter(PROXY_UPSTREAM_FAILOVER_TOTAL, &labels, 1);
+    }
+
+    fn record_network_profile_switch(&self, profile: &str, trigger: &str) {
+        let labels = [("profile", profile), ("trigger", trigger)];
+        record_counter(NETWORK_PROFILE_SWITCH_TOTAL, &labels, 1);
+    }
+
     fn record_http_strategy_fallback(&self, stage: &str, from: &str) {
         let labels = [("stage", stage), ("from", from)];
         record_counter(HTTP_STRATEGY_FALLBACK_TOTAL, &labels, 1);
@@ -1,5 +1,8 @@
 //! Proxy configuration types and parsing
 
+use super::pool::{ProxyUpstream, UpstreamStrategy};
+use super::routing::{NamedProxy, ProxyRoute, ProxyRule};
+use super::tls::ProxyTlsConfig;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
@@ -43,7 +46,8 @@ pub struct ProxyConfig {
     pub mode: ProxyMode,
 
     /// Proxy server URL (e.g., "<http://proxy.example.com:8080>" or "<socks5://127.0.0.1:1080>")
-    /// Only used when mode is Http or Socks5
+    /// Only used when mode is Http or Socks5. `https://` connects to the proxy over TLS;
+    /// `socks5://` resolves target names locally, `socks5h://` lets the proxy resolve them
     #[serde(default)]
     pub url: String,
 
@@ -104,6 +108,49 @@ pub struct ProxyConfig {
     /// When true, outputs detailed connection info including sanitized URLs, auth status, timing
     #[serde(default)]
     pub debug_proxy_logging: bool,
+
+    /// PAC script location used in System mode (http(s)://, file:// or a local path)
+    /// Takes precedence over the platform's auto-config URL and WPAD discovery
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pac_url: Option<String>,
+
+    /// Discover a PAC script via WPAD (`http://wpad.<domain>/wpad.dat`) in System mode
+    /// when neither `pac_url` nor a platform auto-config URL is available (default: false)
+    #[serde(default)]
+    pub wpad_enabled: bool,
+
+    /// Extra `NO_PROXY` entries (domains, IPs, CIDRs, `host:port`, `*`) that always connect directly
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub no_proxy: Vec<String>,
+
+    /// Also honor the `NO_PROXY`/`no_proxy` environment variables (default: true)
+    #[serde(default = "default_honor_env_no_proxy")]
+    pub honor_env_no_proxy: bool,
+
+    /// Per-host routing rules, first match wins; unmatched targets use the default proxy
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<ProxyRule>,
+
+    /// Named proxies that routing rules can refer to
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub proxies: Vec<NamedProxy>,
+
+    /// Upstream proxy pool for Http/Socks5 mode; when non-empty it replaces `url`
+    /// for the default route, with per-upstream health checks and failover
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub upstreams: Vec<ProxyUpstream>,
+
+    /// How healthy upstreams are ordered: ordered, weighted or latency (default: ordered)
+    #[serde(default)]
+    pub upstream_strategy: UpstreamStrategy,
+
+    /// How long a host keeps using the upstream it last connected through; 0 disables (default: 600)
+    #[serde(default = "default_sticky_host_ttl_seconds")]
+    pub sticky_host_ttl_seconds: u64,
+
+    /// Verification of the TLS connection to `https://` proxies (default, named, upstream and PAC `HTTPS`)
+    #[serde(default, skip_serializing_if = "ProxyTlsConfig::is_default")]
+    pub tls: ProxyTlsConfig,
 }
 
 pub fn default_timeout_seconds() -> u64 {
@@ -142,6 +189,21 @@ pub fn default_recovery_consecutive_threshold() -> u32 {
     3 // Require 3 consecutive successes
 }
 
+pub fn default_honor_env_no_proxy() -> bool {
+    true
+}
+
+pub fn default_sticky_host_ttl_seconds() -> u64 {
+    600
+}
+
+/// Proxy URL schemes accepted by the connectors
+fn is_supported_proxy_url(url: &str) -> bool {
+    ["http://", "https://", "socks5://", "socks5h://"]
+        .iter()
+        .any(|scheme| url.starts_with(scheme))
+}
+
 impl Default for ProxyConfig {
     fn default() -> Self {
         Self {
@@ -160,6 +222,16 @@ impl Default for ProxyConfig {
             probe_timeout_seconds: default_probe_timeout_seconds(),
             recovery_consecutive_threshold: default_recovery_consecutive_threshold(),
             debug_proxy_logging: false,
+            pac_url: None,
+            wpad_enabled: false,
+            no_proxy: Vec::new(),
+            honor_env_no_proxy: default_honor_env_no_proxy(),
+            rules: Vec::new(),
+            proxies: Vec::new(),
+            upstreams: Vec::new(),
+            upstream_strategy: UpstreamStrategy::default(),
+            sticky_host_ttl_seconds: default_sticky_host_ttl_seconds(),
+            tls: ProxyTlsConfig::default(),
         }
     }
 }
@@ -172,12 +244,14 @@ impl ProxyConfig {
 
     /// Check if proxy is enabled:
     /// - Off: false
-    /// - Http/Socks5: URL非空才启用
+    /// - Http/Socks5: URL非空或配置了上游代理池才启用
     /// - System: 允许空URL
     pub fn is_enabled(&self) -> bool {
         match self.mode {
             ProxyMode::Off => false,
-            ProxyMode::Http | ProxyMode::Socks5 => !self.url.trim().is_empty(),
+            ProxyMode::Http | ProxyMode::Socks5 => {
+                !self.url.trim().is_empty() || !self.upstreams.is_empty()
+            }
             ProxyMode::System => true,
         }
     }
@@ -194,19 +268,25 @@ impl ProxyConfig {
                 // But still validate other parameters
                 self.validate_timeouts()?;
                 self.validate_thresholds()?;
+                self.validate_routing()?;
                 Ok(())
             }
             ProxyMode::Http | ProxyMode::Socks5 => {
-                // URL is required
-                if self.url.is_empty() {
+                // URL is required unless an upstream pool is configured
+                if self.url.is_empty() && self.upstreams.is_empty() {
                     anyhow::bail!("Proxy URL is required when mode is {} ", self.mode);
                 }
 
-                // Validate URL format
-                self.validate_url()?;
+                if !self.url.is_empty() {
+                    // Validate URL format
+                    self.validate_url()?;
 
-                // Validate port if present
-                self.validate_port()?;
+                    // Validate port if present
+                    self.validate_port()?;
+                }
+
+                // Validate upstream pool
+                self.validate_upstreams()?;
 
                 // Validate timeouts
                 self.validate_timeouts()?;
@@ -226,6 +306,9 @@ impl ProxyConfig {
                 // Validate recovery threshold
                 self.validate_recovery_threshold()?;
 
+                // Validate routing rules and named proxies
+                self.validate_routing()?;
+
                 Ok(())
             }
         }
@@ -233,11 +316,10 @@ impl ProxyConfig {
 
     /// Validate proxy URL format
     fn validate_url(&self) -> anyhow::Result<()> {
-        if !self.url.starts_with("http://")
-            && !self.url.starts_with("https://")
-            && !self.url.starts_with("socks5://")
-        {
-            anyhow::bail!("Invalid proxy URL: must start with http://, https://, or socks5://");
+        if !is_supported_proxy_url(&self.url) {
+            anyhow::bail!(
+                "Invalid proxy URL: must start with http://, https://, socks5:// or socks5h://"
+            );
         }
 
         // Additional URL validation: check for valid characters
@@ -248,6 +330,74 @@ impl ProxyConfig {
         Ok(())
     }
 
+    /// Validate named proxies and routing rules
+    fn validate_routing(&self) -> anyhow::Result<()> {
+        let mut names = std::collections::HashSet::new();
+        for proxy in &self.proxies {
+            if proxy.name.trim().is_empty() {
+                anyhow::bail!("proxies: Proxy name cannot be empty");
+            }
+            if !names.insert(proxy.name.as_str()) {
+                anyhow::bail!("proxies: Duplicate proxy name '{}'", proxy.name);
+            }
+            if !is_supported_proxy_url(&proxy.url) {
+                anyhow::bail!(
+                    "proxies: Invalid URL for proxy '{}': must start with http://, https://, socks5:// or socks5h://",
+                    proxy.name
+                );
+            }
+        }
+
+        for (index, rule) in self.rules.iter().enumerate() {
+            if rule.hosts.iter().any(|h| h.trim().is_empty()) {
+                anyhow::bail!("rules[{}]: Host pattern cannot be empty", index);
+            }
+            if let Some(bad) = rule
+                .hosts
+                .iter()
+                .find(|h| h.contains('/') && h.trim().parse::<ipnet::IpNet>().is_err())
+            {
+                anyhow::bail!("rules[{}]: Invalid CIDR '{}'", index, bad);
+            }
+            if rule.ports.contains(&0) {
+                anyhow::bail!("rules[{}]: Port must be between 1 and 65535", index);
+            }
+            if let ProxyRoute::Proxy(name) = &rule.route {
+                if !names.contains(name.as_str()) {
+                    anyhow::bail!("rules[{}]: Unknown proxy '{}'", index, name);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Validate upstream proxies of the pool
+    fn validate_upstreams(&self) -> anyhow::Result<()> {
+        let mut names = std::collections::HashSet::new();
+        for upstream in &self.upstreams {
+            if upstream.name.trim().is_empty() {
+                anyhow::bail!("upstreams: Upstream name cannot be empty");
+            }
+            if !names.insert(upstream.name.as_str()) {
+                anyhow::bail!("upstreams: Duplicate upstream name '{}'", upstream.name);
+            }
+            if !is_supported_proxy_url(&upstream.url) || upstream.url.contains(' ') {
+                anyhow::bail!(
+                    "upstreams: Invalid URL for upstream '{}': must start with http://, https://, socks5:// or socks5h://",
+                    upstream.name
+                );
+            }
+        }
+        if self.upstream_strategy == UpstreamStrategy::Weighted
+            && !self.upstreams.is_empty()
+            && self.upstreams.iter().all(|u| u.weight == 0)
+        {
+            anyhow::bail!("upstreams: At least one upstream needs a non-zero weight");
+        }
+        Ok(())
+    }
+
     /// Validate port number in URL if present
     fn validate_port(&self) -> anyhow::Result<()> {
         // Try to extract port from URL
@@ -256,6 +406,7 @@ impl ProxyConfig {
             .strip_prefix("http://")
             .or_else(|| self.url.strip_prefix("https://"))
             .or_else(|| self.url.strip_prefix("socks5://"))
+            .or_else(|| self.url.strip_prefix("socks5h://"))
         {
             // Remove credentials if present
             let host_part = if let Some(at_pos) = url_after_scheme.find('@') {
@@ -3,6 +3,7 @@
 //! Implements sliding window-based failure detection for proxy connections.
 //! Triggers automatic fallback to direct connection when failure rate exceeds threshold.
 
+use super::pool::UpstreamStats;
 use std::sync::{Arc, Mutex};
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -280,6 +281,7 @@ impl ProxyFailureDetector {
             window_seconds: inner.window_seconds,
             threshold: inner.threshold,
             fallback_triggered: inner.fallback_triggered,
+            upstreams: Vec::new(),
         }
     }
 }
@@ -299,4 +301,6 @@ pub struct FailureStats {
     pub threshold: f64,
     /// Whether fallback has been triggered
     pub fallback_triggered: bool,
+    /// Per-upstream statistics when an upstream pool is configured (filled by `ProxyManager`)
+    pub upstreams: Vec<UpstreamStats>,
 }
@@ -1,11 +1,11 @@
 //! HTTP/HTTPS proxy connector implementation
 //!
 //! Implements HTTP CONNECT tunnel protocol for proxying TCP connections.
-//! Supports Basic authentication and timeout control.
+//! Supports Basic authentication, timeout control and TLS to `https://` proxies.
 
-use super::{ProxyConnector, ProxyError};
+use super::{tls::ProxyTlsConfig, ProxyConnector, ProxyError, ProxyStream};
 use base64::{engine::general_purpose::STANDARD, Engine};
-use std::io::{BufRead, BufReader, Write};
+use std::io::{BufRead, BufReader, Read, Write};
 use std::net::{TcpStream, ToSocketAddrs};
 use std::time::{Duration, Instant};
 use url::Url;
@@ -14,7 +14,7 @@ use url::Url;
 ///
 /// This connector establishes a TCP tunnel through an HTTP proxy server
 /// using the CONNECT method (RFC 2817). It supports:
-/// - HTTP and HTTPS proxy protocols
+/// - HTTP proxies and HTTPS proxies (CONNECT sent inside TLS to the proxy)
 /// - Basic authentication (username/password)
 /// - Configurable connection timeout
 /// - Proper error classification
@@ -30,6 +30,9 @@ pub struct HttpProxyConnector {
 
     /// Connection timeout in seconds
     pub timeout: Duration,
+
+    /// Verification of the proxy certificate for `https://` proxy URLs
+    pub tls: ProxyTlsConfig,
 }
 
 impl HttpProxyConnector {
@@ -54,9 +57,24 @@ impl HttpProxyConnector {
             username,
             password,
             timeout,
+            tls: ProxyTlsConfig::default(),
         }
     }
 
+    /// Use the given verification settings for TLS to an `https://` proxy
+    pub fn with_tls(mut self, tls: ProxyTlsConfig) -> Self {
+        self.tls = tls;
+        self
+    }
+
+    /// Whether the connection to the proxy itself uses TLS
+    pub fn is_https(&self) -> bool {
+        self.proxy_url
+            .trim()
+            .to_ascii_lowercase()
+            .starts_with("https://")
+    }
+
     /// Parse proxy URL to extract host and port
     pub fn parse_proxy_url(&self) -> Result<(String, u16), ProxyError> {
         let url = Url::parse(&self.proxy_url)
@@ -67,7 +85,10 @@ impl HttpProxyConnector {
             .ok_or_else(|| ProxyError::config("Proxy URL missing host"))?
             .to_string();
 
-        let port = url.port().unwrap_or(8080); // Default HTTP proxy port
+        // Default ports: 8080 for HTTP proxies, 443 for HTTPS proxies
+        let port = url
+            .port()
+            .unwrap_or(if url.scheme() == "https" { 443 } else { 8080 });
 
         Ok((host, port))
     }
@@ -85,9 +106,9 @@ impl HttpProxyConnector {
     }
 
     /// Send CONNECT request and parse response
-    fn send_connect_request(
+    fn send_connect_request<S: Read + Write>(
         &self,
-        stream: &mut TcpStream,
+        stream: &mut S,
         target_host: &str,
         target_port: u16,
     ) -> Result<(), ProxyError> {
@@ -166,7 +187,7 @@ impl HttpProxyConnector {
 }
 
 impl ProxyConnector for HttpProxyConnector {
-    fn connect(&self, host: &str, port: u16) -> Result<TcpStream, ProxyError> {
+    fn connect(&self, host: &str, port: u16) -> Result<ProxyStream, ProxyError> {
         let start_time = Instant::now();
 
         // Parse proxy URL
@@ -190,7 +211,7 @@ impl ProxyConnector for HttpProxyConnector {
 
         tracing::debug!(
             proxy.url = %sanitized_url,
-            proxy.type = "http",
+            proxy.type = self.proxy_type(),
             target.host = %host,
             target.port = %port,
             "Connecting through HTTP proxy"
@@ -206,7 +227,7 @@ impl ProxyConnector for HttpProxyConnector {
         tracing::debug!("Resolved proxy address: {}", proxy_addr);
 
         // Connect to proxy server with timeout
-        let mut stream = TcpStream::connect_timeout(&proxy_addr, self.timeout).map_err(|e| {
+        let stream = TcpStream::connect_timeout(&proxy_addr, self.timeout).map_err(|e| {
             let elapsed = start_time.elapsed();
             tracing::warn!(
                 error = %e,
@@ -233,6 +254,25 @@ impl ProxyConnector for HttpProxyConnector {
             .set_write_timeout(Some(self.timeout))
             .map_err(|e| ProxyError::network(format!("Failed to set write timeout: {e}")))?;
 
+        // TLS to the proxy for https:// proxy URLs; CONNECT then runs inside the session
+        let mut stream = if self.is_https() {
+            let tls_stream = super::tls::connect(stream, &proxy_host, &self.tls).map_err(|e| {
+                tracing::warn!(
+                    error = %e,
+                    elapsed_ms = start_time.elapsed().as_millis(),
+                    "TLS handshake with proxy failed"
+                );
+                e
+            })?;
+            tracing::debug!(
+                elapsed_ms = start_time.elapsed().as_millis(),
+                "TLS session with proxy established"
+            );
+            ProxyStream::Tls(Box::new(tls_stream))
+        } else {
+            ProxyStream::Tcp(stream)
+        };
+
         // Send CONNECT request
         self.send_connect_request(&mut stream, host, port)
             .map_err(|e| {
@@ -247,7 +287,7 @@ impl ProxyConnector for HttpProxyConnector {
 
         let total_elapsed = start_time.elapsed();
         tracing::info!(
-            proxy.type = "http",
+            proxy.type = self.proxy_type(),
             proxy.url = %sanitized_url,
             target.host = %host,
             target.port = %port,
@@ -259,6 +299,10 @@ impl ProxyConnector for HttpProxyConnector {
     }
 
     fn proxy_type(&self) -> &str {
-        "http"
+        if self.is_https() {
+            "https"
+        } else {
+            "http"
+        }
     }
 }
@@ -8,9 +8,12 @@ use super::{
     detector::ProxyFailureDetector,
     events::{ProxyFallbackEvent, ProxyHealthCheckEvent, ProxyRecoveredEvent},
     health_checker::{ProbeResult, ProxyHealthChecker},
+    pac,
+    pool::{self, PoolConnector, ProxyPool, UpstreamStats},
+    routing::{self, NamedProxy, ProxyRouter, RouteDecision},
     state::{ProxyState, ProxyStateContext, StateTransition},
     system_detector::SystemProxyDetector,
-    HttpProxyConnector, PlaceholderConnector, ProxyConnector, Socks5ProxyConnector,
+    HttpProxyConnector, PacConnector, PlaceholderConnector, ProxyConnector, Socks5ProxyConnector,
 };
 use anyhow::Result;
 use std::sync::{Arc, RwLock};
@@ -198,7 +201,9 @@ impl ProxyManager {
     /// - Off: `PlaceholderConnector` (direct connection)
     /// - Http: `HttpProxyConnector` (P5.1)
     /// - Socks5: `PlaceholderConnector` (P5.2 will implement `Socks5ProxyConnector`)
-    /// - System: Based on detected system proxy type
+    /// - Http/Socks5 with `upstreams`: `PoolConnector` (failover among upstreams)
+    /// - System: `PacConnector` when a PAC script is configured or discovered, otherwise the
+    ///   detected system proxy, otherwise direct
     pub fn get_connector(&self) -> Result<Box<dyn ProxyConnector>> {
         let config = self.config.read().unwrap();
 
@@ -208,6 +213,16 @@ impl ProxyManager {
                 tracing::debug!("Proxy mode is Off, using direct connection");
                 Ok(Box::new(PlaceholderConnector))
             }
+            ProxyMode::Http | ProxyMode::Socks5 if !config.upstreams.is_empty() => {
+                tracing::debug!(
+                    "Creating upstream pool connector ({} upstreams, {} strategy)",
+                    config.upstreams.len(),
+                    config.upstream_strategy
+                );
+                let pool = pool::shared_pool(&config)
+                    .ok_or_else(|| anyhow::anyhow!("upstream pool unavailable"))?;
+                Ok(Box::new(PoolConnector::new(pool)))
+            }
             ProxyMode::Http => {
                 // P5.1: Return HttpProxyConnector
                 tracing::debug!(
@@ -220,7 +235,8 @@ impl ProxyManager {
                     config.username.clone(),
                     config.password.clone(),
                     config.timeout(),
-                );
+                )
+                .with_tls(config.tls.clone());
 
                 Ok(Box::new(connector))
             }
@@ -240,16 +256,129 @@ impl ProxyManager {
 
                 Ok(Box::new(connector))
             }
-            ProxyMode::System => {
-                // Use system-detected proxy type
-                // For now, fall back to placeholder
-                // P5.2: Will implement proper system proxy handling after detection
+            ProxyMode::System => Ok(Self::system_connector(&config)),
+        }
+    }
+
+    /// Connector for System mode: `PacConnector` when a PAC script is available, otherwise the
+    /// detected system proxy, otherwise direct
+    fn system_connector(config: &ProxyConfig) -> Box<dyn ProxyConnector> {
+        // PAC script (explicit URL, platform auto-config or WPAD) takes precedence
+        if let Some(location) = pac::resolve_pac_location(config) {
+            match pac::load_cached(&location) {
+                Ok(script) => {
+                    tracing::debug!("Using PAC script from {} for system proxy", location);
+                    return Box::new(PacConnector::new(script, config.clone()));
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "PAC script {} unavailable, connecting directly: {}",
+                        location,
+                        e
+                    );
+                }
+            }
+        }
+        // Otherwise the detected platform/environment proxy, with this configuration's
+        // timeout and proxy TLS settings
+        if let Some(mut detected) = SystemProxyDetector::detect() {
+            if matches!(detected.mode, ProxyMode::Http | ProxyMode::Socks5) {
+                detected.timeout_seconds = config.timeout_seconds;
+                detected.tls = config.tls.clone();
                 tracing::debug!(
-                    "System proxy mode configured, but using PlaceholderConnector (system proxy resolution pending)"
+                    "Using detected system proxy {} without PAC",
+                    detected.sanitized_url()
                 );
-                Ok(Box::new(PlaceholderConnector))
+                match ProxyManager::new(detected).get_connector() {
+                    Ok(connector) => return connector,
+                    Err(e) => tracing::warn!("Detected system proxy unusable: {}", e),
+                }
             }
         }
+        tracing::debug!("System proxy mode configured without PAC or detected proxy, using PlaceholderConnector");
+        Box::new(PlaceholderConnector)
+    }
+
+    /// Resolve the routing decision for a target (`NO_PROXY`, then rules, then the default proxy)
+    pub fn route(&self, host: &str, port: u16) -> RouteDecision {
+        ProxyRouter::new(&self.config.read().unwrap()).route(host, port)
+    }
+
+    /// Get the connector for a specific target, applying routing rules
+    pub fn connector_for(&self, host: &str, port: u16) -> Result<Box<dyn ProxyConnector>> {
+        let route = self.route(host, port);
+        tracing::debug!("Proxy route for {}:{} is {}", host, port, route.label());
+        match route {
+            RouteDecision::Direct => Ok(Box::new(PlaceholderConnector)),
+            RouteDecision::System => Ok(Self::system_connector(&self.config.read().unwrap())),
+            RouteDecision::Named(proxy) => {
+                Self::named_connector(&proxy, &self.config.read().unwrap())
+            }
+            RouteDecision::Default => self.get_connector(),
+        }
+    }
+
+    /// Connector for a named proxy; `socks5://`/`socks5h://` URLs use `Socks5ProxyConnector`, others HTTP CONNECT
+    fn named_connector(
+        proxy: &NamedProxy,
+        config: &ProxyConfig,
+    ) -> Result<Box<dyn ProxyConnector>> {
+        tracing::debug!(
+            "Creating connector for named proxy '{}' ({})",
+            proxy.name,
+            proxy.sanitized_url()
+        );
+        if proxy.url.starts_with("socks5://") || proxy.url.starts_with("socks5h://") {
+            Ok(Box::new(Socks5ProxyConnector::new(
+                proxy.url.clone(),
+                proxy.username.clone(),
+                proxy.password.clone(),
+                config.timeout(),
+            )?))
+        } else {
+            Ok(Box::new(
+                HttpProxyConnector::new(
+                    proxy.url.clone(),
+                    proxy.username.clone(),
+                    proxy.password.clone(),
+                    config.timeout(),
+                )
+                .with_tls(config.tls.clone()),
+            ))
+        }
+    }
+
+    /// Ordered connectors to try for a specific target URL
+    ///
+    /// Routing rules are applied first; when the target ends up on the system proxy with
+    /// a PAC script this evaluates `FindProxyForURL` and returns one connector per result
+    /// entry, otherwise the single routed connector.
+    pub fn connectors_for_url(&self, url: &str) -> Result<Vec<Box<dyn ProxyConnector>>> {
+        let (host, port) = routing::host_port_of(url)
+            .ok_or_else(|| anyhow::anyhow!("invalid target url: {url}"))?;
+        let route = self.route(&host, port);
+        let system = match route {
+            RouteDecision::System => true,
+            RouteDecision::Default => self.mode() == ProxyMode::System,
+            _ => false,
+        };
+        if system {
+            let config = self.config.read().unwrap().clone();
+            if let Some(location) = pac::resolve_pac_location(&config) {
+                if let Ok(script) = pac::load_cached(&location) {
+                    let directives = script.evaluate(url, &host).unwrap_or_else(|e| {
+                        tracing::warn!(
+                            "PAC evaluation for {} failed, connecting directly: {}",
+                            host,
+                            e
+                        );
+                        vec![pac::PacDirective::Direct]
+                    });
+                    return Ok(pac::connectors_for(&directives, &config));
+                }
+            }
+        }
+        Ok(vec![self.connector_for(&host, port)?])
     }
 
     /// Record a proxy connection failure
@@ -396,6 +525,13 @@ impl ProxyManager {
         Ok(())
     }
 
+    /// Probe target (`probe_url` as host:port)
+    fn probe_target(&self) -> Option<(String, u16)> {
+        let config = self.config.read().unwrap();
+        let (host, port) = config.probe_url.rsplit_once(':')?;
+        Some((host.to_string(), port.parse().ok()?))
+    }
+
     /// Perform a health check probe (P5.5)
     ///
     /// This should be called periodically (e.g., every 60 seconds) when in Fallback state.
@@ -415,8 +551,16 @@ impl ProxyManager {
             });
         }
 
-        // Get connector for probing
-        let connector = self.get_connector()?;
+        // Give down upstreams a chance to rejoin before probing through the pool
+        if let Some(pool) = self.pool() {
+            pool.health_check();
+        }
+
+        // Get connector for probing; the probe target follows the same routing rules
+        let connector = match self.probe_target() {
+            Some((host, port)) => self.connector_for(&host, port)?,
+            None => self.get_connector()?,
+        };
 
         // Perform probe
         let result = {
@@ -615,8 +759,33 @@ impl ProxyManager {
     /// - Number of failures
     /// - Current failure rate
     /// - Whether fallback was triggered
+    /// - Per-upstream statistics when an upstream pool is configured
     pub fn get_failure_stats(&self) -> super::detector::FailureStats {
-        self.failure_detector.get_stats()
+        let mut stats = self.failure_detector.get_stats();
+        stats.upstreams = self.upstream_stats();
+        stats
+    }
+
+    /// Shared upstream pool for the current configuration (Http/Socks5 mode with `upstreams`)
+    pub fn pool(&self) -> Option<Arc<ProxyPool>> {
+        let config = self.config.read().unwrap();
+        match config.mode {
+            ProxyMode::Http | ProxyMode::Socks5 => pool::shared_pool(&config),
+            _ => None,
+        }
+    }
+
+    /// Probe every upstream of the pool with its own health checker
+    ///
+    /// Should be scheduled every `health_check_interval()` while a pool is configured,
+    /// independent of the manager state; down upstreams rejoin after recovery.
+    pub fn health_check_upstreams(&self) -> Vec<(String, ProbeResult)> {
+        self.pool().map(|p| p.health_check()).unwrap_or_default()
+    }
+
+    /// Per-upstream statistics (empty without an upstream pool)
+    pub fn upstream_stats(&self) -> Vec<UpstreamStats> {
+        self.pool().map(|p| p.stats()).unwrap_or_default()
     }
 }
 
@@ -3,7 +3,11 @@
 //! This module provides:
 //! - Proxy configuration and state management
 //! - System proxy detection (Windows/macOS/Linux)
-//! - Proxy connector trait for unified interface
+//! - PAC script evaluation and WPAD discovery for System mode
+//! - Per-host routing rules and `NO_PROXY` bypass
+//! - Upstream proxy pool with per-upstream health checks, failover and sticky hosts
+//! - Proxy connector trait for unified interface (TLS to `https://` proxies, `socks5`/`socks5h` DNS)
+//! - Async tunnels so the tokio-based transports dial through the routed connectors
 //! - Automatic fallback and recovery mechanisms (P5.4: fallback, P5.5: recovery)
 //! - Failure detection with sliding window statistics (P5.4)
 
@@ -14,9 +18,15 @@ pub mod events;
 pub mod health_checker;
 pub mod http_connector;
 pub mod manager;
+pub mod pac;
+pub mod pool;
+pub mod routing;
 pub mod socks5_connector;
 pub mod state;
+pub mod stream;
 pub mod system_detector;
+pub mod tls;
+pub mod tunnel;
 
 pub use config::{ProxyConfig, ProxyMode};
 pub use detector::{FailureStats, ProxyFailureDetector};
@@ -25,9 +35,15 @@ pub use events::{ProxyFallbackEvent, ProxyHealthCheckEvent, ProxyRecoveredEvent,
 pub use health_checker::{HealthCheckConfig, ProbeResult, ProxyHealthChecker};
 pub use http_connector::HttpProxyConnector;
 pub use manager::ProxyManager;
+pub use pac::{PacConnector, PacDirective};
+pub use pool::{PoolConnector, ProxyPool, ProxyUpstream, UpstreamStats, UpstreamStrategy};
+pub use routing::{NamedProxy, NoProxy, ProxyRoute, ProxyRouter, ProxyRule, RouteDecision};
 pub use socks5_connector::Socks5ProxyConnector;
 pub use state::{ProxyState, ProxyStateContext, StateTransition};
+pub use stream::ProxyStream;
 pub use system_detector::SystemProxyDetector;
+pub use tls::ProxyTlsConfig;
+pub use tunnel::AsyncProxyStream;
 
 use std::net::TcpStream;
 
@@ -43,8 +59,8 @@ pub trait ProxyConnector: Send + Sync {
     /// * `port` - Target port to connect to
     ///
     /// # Returns
-    /// A TCP stream connected through the proxy, or a `ProxyError`
-    fn connect(&self, host: &str, port: u16) -> Result<TcpStream, ProxyError>;
+    /// A stream tunneled through the proxy (TLS-wrapped for `https://` proxies), or a `ProxyError`
+    fn connect(&self, host: &str, port: u16) -> Result<ProxyStream, ProxyError>;
 
     /// Get the proxy type name for logging
     fn proxy_type(&self) -> &str {
@@ -59,9 +75,10 @@ pub trait ProxyConnector: Send + Sync {
 pub struct PlaceholderConnector;
 
 impl ProxyConnector for PlaceholderConnector {
-    fn connect(&self, host: &str, port: u16) -> Result<TcpStream, ProxyError> {
+    fn connect(&self, host: &str, port: u16) -> Result<ProxyStream, ProxyError> {
         tracing::debug!("PlaceholderConnector: falling back to direct connection to {host}:{port}");
         TcpStream::connect((host, port))
+            .map(ProxyStream::Tcp)
             .map_err(|e| ProxyError::network(format!("Direct connection failed: {e}")))
     }
 
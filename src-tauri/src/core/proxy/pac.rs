@@ -0,0 +1,682 @@
+//! PAC (Proxy Auto-Config) script support for System proxy mode
+//!
+//! The PAC script is located in this order:
+//! 1. `ProxyConfig::pac_url` (http(s)://, file:// or a local path)
+//! 2. The platform auto-config URL (Windows `AutoConfigURL`, macOS `ProxyAutoConfigURLString`)
+//! 3. WPAD discovery via DNS (`http://wpad.<domain>/wpad.dat`) when `wpad_enabled` is set
+//!
+//! `FindProxyForURL(url, host)` is evaluated per target in an embedded QuickJS runtime with
+//! the standard PAC helper functions. The compiled script is kept per thread and reused across
+//! evaluations. Its result (e.g. `"PROXY a:8080; SOCKS b:1080; DIRECT"`) becomes an ordered
+//! connector list that is tried front to back.
+
+use super::{
+    config::ProxyConfig, system_detector::SystemProxyDetector, HttpProxyConnector,
+    PlaceholderConnector, ProxyConnector, ProxyError, ProxyStream, Socks5ProxyConnector,
+};
+use crate::core::config::loader::load_or_init;
+use crate::core::config::network_profile::{dns_suffixes, local_addrs};
+use anyhow::{anyhow, bail, Context as _, Result};
+use rquickjs::{Context, Ctx, Function, Runtime};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{IpAddr, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use url::Url;
+
+/// Maximum accepted PAC script size
+const MAX_PAC_BYTES: usize = 1024 * 1024;
+
+/// Timeout for fetching a PAC script over HTTP
+const PAC_FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Upper bound for a single `FindProxyForURL` evaluation
+const PAC_EVAL_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Memory limit for the QuickJS runtime evaluating PAC scripts
+const PAC_MEMORY_LIMIT: usize = 32 * 1024 * 1024;
+
+/// Compiled PAC scripts kept per thread (QuickJS runtimes cannot move between threads)
+const PAC_ENGINES_PER_THREAD: usize = 4;
+
+/// How long a successfully loaded PAC script (or WPAD discovery result) is reused
+const PAC_CACHE_TTL: Duration = Duration::from_secs(30 * 60);
+
+/// How long a failed PAC load is remembered before retrying
+const PAC_FAILURE_TTL: Duration = Duration::from_secs(60);
+
+/// Standard PAC helper functions; `dnsResolve` and `myIpAddress` are provided natively.
+const PAC_UTILS: &str = r#"
+var __pacDays = ['SUN', 'MON', 'TUE', 'WED', 'THU', 'FRI', 'SAT'];
+var __pacMonths = ['JAN', 'FEB', 'MAR', 'APR', 'MAY', 'JUN', 'JUL', 'AUG', 'SEP', 'OCT', 'NOV', 'DEC'];
+function isPlainHostName(host) { return host.indexOf('.') < 0; }
+function dnsDomainIs(host, domain) {
+  host = String(host).toLowerCase(); domain = String(domain).toLowerCase();
+  return host.length >= domain.length && host.substring(host.length - domain.length) === domain;
+}
+function localHostOrDomainIs(host, hostdom) {
+  return host === hostdom || hostdom.lastIndexOf(host + '.', 0) === 0;
+}
+function isResolvable(host) { return dnsResolve(host) != null; }
+function dnsDomainLevels(host) { return host.split('.').length - 1; }
+function convert_addr(ipchars) {
+  var bytes = ipchars.split('.');
+  return (((bytes[0] & 0xff) << 24) | ((bytes[1] & 0xff) << 16) | ((bytes[2] & 0xff) << 8) | (bytes[3] & 0xff)) >>> 0;
+}
+function isInNet(ipaddr, pattern, maskstr) {
+  if (!/^\d+\.\d+\.\d+\.\d+$/.test(ipaddr)) {
+    ipaddr = dnsResolve(ipaddr);
+    if (ipaddr == null) { return false; }
+  }
+  var mask = convert_addr(maskstr);
+  return ((convert_addr(ipaddr) & mask) >>> 0) === ((convert_addr(pattern) & mask) >>> 0);
+}
+function shExpMatch(str, shexp) {
+  var re = String(shexp).replace(/[.+^${}()|[\]\\]/g, '\\$&').replace(/\*/g, '.*').replace(/\?/g, '.');
+  return new RegExp('^' + re + '$').test(str);
+}
+function __pacGmt(args) {
+  if (args.length && args[args.length - 1] === 'GMT') { args.pop(); return true; }
+  return false;
+}
+function __pacInRange(cur, start, end) {
+  return start <= end ? (cur >= start && cur <= end) : (cur >= start || cur <= end);
+}
+function weekdayRange() {
+  var args = Array.prototype.slice.call(arguments);
+  var gmt = __pacGmt(args);
+  var now = new Date();
+  var day = gmt ? now.getUTCDay() : now.getDay();
+  var start = __pacDays.indexOf(String(args[0]).toUpperCase());
+  var end = args.length > 1 ? __pacDays.indexOf(String(args[1]).toUpperCase()) : start;
+  if (start < 0 || end < 0) { return false; }
+  return __pacInRange(day, start, end);
+}
+function timeRange() {
+  var args = Array.prototype.slice.call(arguments);
+  var gmt = __pacGmt(args);
+  var now = new Date();
+  var h = gmt ? now.getUTCHours() : now.getHours();
+  var m = gmt ? now.getUTCMinutes() : now.getMinutes();
+  var s = gmt ? now.getUTCSeconds() : now.getSeconds();
+  var cur = h * 3600 + m * 60 + s;
+  switch (args.length) {
+    case 1: return h === args[0];
+    case 2: return __pacInRange(cur, args[0] * 3600, args[1] * 3600 - 1);
+    case 4: return __pacInRange(cur, args[0] * 3600 + args[1] * 60, args[2] * 3600 + args[3] * 60 + 59);
+    case 6: return __pacInRange(cur, args[0] * 3600 + args[1] * 60 + args[2], args[3] * 3600 + args[4] * 60 + args[5]);
+    default: return false;
+  }
+}
+function dateRange() {
+  var args = Array.prototype.slice.call(arguments);
+  var gmt = __pacGmt(args);
+  var now = new Date();
+  var cur = {
+    d: gmt ? now.getUTCDate() : now.getDate(),
+    m: gmt ? now.getUTCMonth() : now.getMonth(),
+    y: gmt ? now.getUTCFullYear() : now.getFullYear()
+  };
+  function parse(list) {
+    var r = {};
+    for (var i = 0; i < list.length; i++) {
+      var month = __pacMonths.indexOf(String(list[i]).toUpperCase());
+      if (month >= 0) { r.m = month; }
+      else if (Number(list[i]) > 31) { r.y = Number(list[i]); }
+      else { r.d = Number(list[i]); }
+    }
+    return r;
+  }
+  function key(p, shape) {
+    return (shape.y !== undefined ? p.y : 0) * 10000 + (shape.m !== undefined ? p.m : 0) * 100 + (shape.d !== undefined ? p.d : 0);
+  }
+  if (args.length === 1) {
+    var only = parse(args);
+    return (only.d === undefined || only.d === cur.d) && (only.m === undefined || only.m === cur.m) && (only.y === undefined || only.y === cur.y);
+  }
+  if (args.length === 0 || args.length % 2 !== 0) { return false; }
+  var start = parse(args.slice(0, args.length / 2));
+  var end = parse(args.slice(args.length / 2));
+  return __pacInRange(key(cur, start), key(start, start), key(end, start));
+}
+"#;
+
+/// One entry of a `FindProxyForURL` result
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PacDirective {
+    /// Connect directly
+    Direct,
+    /// `PROXY host:port` / `HTTP host:port`
+    Http(String),
+    /// `HTTPS host:port` (TLS to the proxy)
+    Https(String),
+    /// `SOCKS host:port` / `SOCKS5 host:port` (the proxy resolves target names, as in browsers)
+    Socks(String),
+}
+
+impl PacDirective {
+    /// Proxy URL for this directive, `None` for `DIRECT`
+    pub fn proxy_url(&self) -> Option<String> {
+        match self {
+            Self::Direct => None,
+            Self::Http(addr) => Some(format!("http://{addr}")),
+            Self::Https(addr) => Some(format!("https://{addr}")),
+            Self::Socks(addr) => Some(format!("socks5h://{addr}")),
+        }
+    }
+}
+
+/// Parse a `FindProxyForURL` result into ordered directives
+///
+/// Unknown entries are skipped; an empty result means `DIRECT`.
+/// `SOCKS`/`SOCKS4` entries are treated as SOCKS5 since that is the only SOCKS
+/// version supported by the connectors.
+pub fn parse_pac_result(result: &str) -> Vec<PacDirective> {
+    let mut directives = Vec::new();
+    for entry in result.split(';') {
+        let mut parts = entry.split_whitespace();
+        let Some(keyword) = parts.next() else {
+            continue;
+        };
+        let addr = parts.next().map(str::to_string);
+        let directive = match (keyword.to_ascii_uppercase().as_str(), addr) {
+            ("DIRECT", _) => PacDirective::Direct,
+            ("PROXY" | "HTTP", Some(addr)) => PacDirective::Http(addr),
+            ("HTTPS", Some(addr)) => PacDirective::Https(addr),
+            ("SOCKS" | "SOCKS4" | "SOCKS5", Some(addr)) => PacDirective::Socks(addr),
+            _ => {
+                tracing::debug!(entry = entry.trim(), "Ignoring unsupported PAC directive");
+                continue;
+            }
+        };
+        if !directives.contains(&directive) {
+            directives.push(directive);
+        }
+    }
+    if directives.is_empty() {
+        directives.push(PacDirective::Direct);
+    }
+    directives
+}
+
+/// A loaded PAC script
+#[derive(Debug, Clone)]
+pub struct PacScript {
+    id: u64,
+    source: Arc<str>,
+}
+
+/// A PAC script compiled into its own QuickJS runtime
+struct PacEngine {
+    script_id: u64,
+    context: Context,
+    deadline: Arc<Mutex<Instant>>,
+    _runtime: Runtime,
+}
+
+thread_local! {
+    static PAC_ENGINES: RefCell<Vec<PacEngine>> = const { RefCell::new(Vec::new()) };
+}
+
+impl PacScript {
+    /// Create a PAC script from its source text
+    pub fn new(source: impl Into<String>) -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            source: Arc::from(source.into()),
+        }
+    }
+
+    /// Load a PAC script from an http(s):// URL, a file:// URL or a local path
+    pub fn load(location: &str) -> Result<Self> {
+        let data = fetch_pac(location.trim())?;
+        let source = String::from_utf8(data).context("PAC script is not valid UTF-8")?;
+        Ok(Self::new(source))
+    }
+
+    /// Evaluate `FindProxyForURL(url, host)` and return the raw result string
+    ///
+    /// The compiled script is reused on this thread; an engine whose evaluation failed is
+    /// discarded so a timed-out or broken run never leaks into the next one.
+    pub fn find_proxy(&self, url: &str, host: &str) -> Result<String> {
+        PAC_ENGINES.with(|engines| {
+            let mut engines = engines.borrow_mut();
+            let index = match engines.iter().position(|e| e.script_id == self.id) {
+                Some(index) => index,
+                None => {
+                    if engines.len() >= PAC_ENGINES_PER_THREAD {
+                        engines.remove(0);
+                    }
+                    engines.push(self.compile()?);
+                    engines.len() - 1
+                }
+            };
+            let result = engines[index].call(url, host);
+            if result.is_err() {
+                engines.remove(index);
+            }
+            result
+        })
+    }
+
+    /// Create a runtime with the PAC helpers and evaluate the script source once
+    fn compile(&self) -> Result<PacEngine> {
+        let runtime = Runtime::new().map_err(|e| anyhow!("create PAC runtime: {e}"))?;
+        runtime.set_memory_limit(PAC_MEMORY_LIMIT);
+        let deadline = Arc::new(Mutex::new(Instant::now() + PAC_EVAL_TIMEOUT));
+        let limit = deadline.clone();
+        runtime.set_interrupt_handler(Some(Box::new(move || {
+            limit.lock().map(|d| Instant::now() > *d).unwrap_or(true)
+        })));
+        let context = Context::full(&runtime).map_err(|e| anyhow!("create PAC context: {e}"))?;
+
+        context.with(|ctx| {
+            let load = || -> rquickjs::Result<()> {
+                let globals = ctx.globals();
+                globals.set(
+                    "dnsResolve",
+                    Function::new(ctx.clone(), |host: String| dns_resolve(&host))?,
+                )?;
+                globals.set("myIpAddress", Function::new(ctx.clone(), my_ip_address)?)?;
+                ctx.eval::<(), _>(PAC_UTILS)?;
+                ctx.eval::<(), _>(self.source.as_bytes())
+            };
+            load().map_err(|err| js_error(&ctx, err))
+        })?;
+        Ok(PacEngine {
+            script_id: self.id,
+            context,
+            deadline,
+            _runtime: runtime,
+        })
+    }
+
+    /// Evaluate the script for a target and parse the result into directives
+    pub fn evaluate(&self, url: &str, host: &str) -> Result<Vec<PacDirective>> {
+        self.find_proxy(url, host)
+            .map(|result| parse_pac_result(&result))
+    }
+}
+
+impl PacEngine {
+    fn call(&self, url: &str, host: &str) -> Result<String> {
+        if let Ok(mut deadline) = self.deadline.lock() {
+            *deadline = Instant::now() + PAC_EVAL_TIMEOUT;
+        }
+        self.context.with(|ctx| {
+            let run = || -> rquickjs::Result<String> {
+                let find: Function = ctx.globals().get("FindProxyForURL")?;
+                let result: Option<String> = find.call((url, host))?;
+                Ok(result.unwrap_or_default())
+            };
+            run().map_err(|err| js_error(&ctx, err))
+        })
+    }
+}
+
+fn js_error(ctx: &Ctx<'_>, err: rquickjs::Error) -> anyhow::Error {
+    match err {
+        rquickjs::Error::Exception => {
+            let exception = ctx.catch();
+            let message = exception
+                .as_exception()
+                .and_then(|e| e.message())
+                .unwrap_or_else(|| format!("{exception:?}"));
+            anyhow!("PAC evaluation failed: {message}")
+        }
+        other => anyhow!("PAC evaluation failed: {other}"),
+    }
+}
+
+/// URL passed to `FindProxyForURL` for a `host:port` tunnel target
+pub fn target_url(host: &str, port: u16) -> String {
+    let host = if host.contains(':') && !host.starts_with('[') {
+        format!("[{host}]")
+    } else {
+        host.to_string()
+    };
+    match port {
+        443 => format!("https://{host}/"),
+        80 => format!("http://{host}/"),
+        _ => format!("https://{host}:{port}/"),
+    }
+}
+
+/// Build the ordered connector list for PAC directives
+///
+/// Proxy credentials from the configuration are only sent to the entry that names the
+/// configured proxy (`ProxyConfig::url`); every other PAC entry connects without them.
+/// Entries whose connector cannot be created are skipped.
+pub fn connectors_for(
+    directives: &[PacDirective],
+    config: &ProxyConfig,
+) -> Vec<Box<dyn ProxyConnector>> {
+    let mut connectors: Vec<Box<dyn ProxyConnector>> = Vec::new();
+    for directive in directives {
+        let Some(url) = directive.proxy_url() else {
+            connectors.push(Box::new(PlaceholderConnector));
+            continue;
+        };
+        let (username, password) = if same_proxy(&url, &config.url) {
+            (config.username.clone(), config.password.clone())
+        } else {
+            (None, None)
+        };
+        match directive {
+            PacDirective::Socks(_) => {
+                match Socks5ProxyConnector::new(url, username, password, config.timeout()) {
+                    Ok(connector) => connectors.push(Box::new(connector)),
+                    Err(e) => tracing::warn!("Skipping invalid PAC SOCKS entry: {}", e),
+                }
+            }
+            _ => connectors.push(Box::new(
+                HttpProxyConnector::new(url, username, password, config.timeout())
+                    .with_tls(config.tls.clone()),
+            )),
+        }
+    }
+    connectors
+}
+
+/// Whether a PAC proxy URL points at the configured proxy (same host and port)
+fn same_proxy(pac_url: &str, configured: &str) -> bool {
+    let endpoint = |raw: &str| {
+        let raw = raw.trim();
+        let parsed = if raw.contains("://") {
+            Url::parse(raw)
+        } else {
+            Url::parse(&format!("http://{raw}"))
+        };
+        parsed.ok().and_then(|u| {
+            let host = u.host_str()?.to_ascii_lowercase();
+            Some((host, u.port_or_known_default()?))
+        })
+    };
+    !configured.trim().is_empty()
+        && matches!((endpoint(pac_url), endpoint(configured)), (Some(a), Some(b)) if a == b)
+}
+
+/// Connector that evaluates the PAC script per target and tries the resulting
+/// connectors in order until one succeeds
+pub struct PacConnector {
+    script: Arc<PacScript>,
+    config: ProxyConfig,
+}
+
+impl PacConnector {
+    pub fn new(script: Arc<PacScript>, config: ProxyConfig) -> Self {
+        Self { script, config }
+    }
+
+    /// Ordered connectors for a target; PAC errors fall back to `DIRECT` like browsers do
+    pub fn connectors_for_target(&self, host: &str, port: u16) -> Vec<Box<dyn ProxyConnector>> {
+        let directives = self
+            .script
+            .evaluate(&target_url(host, port), host)
+            .unwrap_or_else(|e| {
+                tracing::warn!("PAC evaluation for {host} failed, connecting directly: {e}");
+                vec![PacDirective::Direct]
+            });
+        connectors_for(&directives, &self.config)
+    }
+}
+
+impl ProxyConnector for PacConnector {
+    fn connect(&self, host: &str, port: u16) -> Result<ProxyStream, ProxyError> {
+        let mut last_error = ProxyError::config("PAC returned no usable entries");
+        for connector in self.connectors_for_target(host, port) {
+            match connector.connect(host, port) {
+                Ok(stream) => return Ok(stream),
+                Err(e) => {
+                    tracing::debug!(
+                        proxy_type = connector.proxy_type(),
+                        "PAC entry failed for {host}:{port}: {e}"
+                    );
+                    last_error = e;
+                }
+            }
+        }
+        Err(last_error)
+    }
+
+    fn proxy_type(&self) -> &str {
+        "pac"
+    }
+}
+
+/// Determine the PAC script location for a System mode configuration
+pub fn resolve_pac_location(config: &ProxyConfig) -> Option<String> {
+    if let Some(url) = config.pac_url.as_ref().filter(|u| !u.trim().is_empty()) {
+        return Some(url.trim().to_string());
+    }
+    if let Some(url) = SystemProxyDetector::detect_pac_url() {
+        return Some(url);
+    }
+    if config.wpad_enabled {
+        return discover_wpad();
+    }
+    None
+}
+
+/// Load a PAC script through the process-wide cache
+pub fn load_cached(location: &str) -> Result<Arc<PacScript>> {
+    let cache = script_cache();
+    if let Some((at, entry)) = cache.lock().unwrap().get(location) {
+        let ttl = if entry.is_some() {
+            PAC_CACHE_TTL
+        } else {
+            PAC_FAILURE_TTL
+        };
+        if at.elapsed() < ttl {
+            return entry
+                .clone()
+                .ok_or_else(|| anyhow!("PAC script unavailable (cached failure): {location}"));
+        }
+    }
+    let loaded = PacScript::load(location).map(Arc::new);
+    if let Err(e) = &loaded {
+        tracing::warn!("Failed to load PAC script from {location}: {e}");
+    }
+    cache.lock().unwrap().insert(
+        location.to_string(),
+        (Instant::now(), loaded.as_ref().ok().cloned()),
+    );
+    loaded
+}
+
+/// Drop cached PAC scripts and WPAD discovery results
+pub fn clear_cache() {
+    script_cache().lock().unwrap().clear();
+    *wpad_cache().lock().unwrap() = None;
+}
+
+type ScriptCache = Mutex<HashMap<String, (Instant, Option<Arc<PacScript>>)>>;
+
+fn script_cache() -> &'static ScriptCache {
+    static CACHE: OnceLock<ScriptCache> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+type WpadCache = Mutex<Option<(Instant, Option<String>)>>;
+
+fn wpad_cache() -> &'static WpadCache {
+    static CACHE: OnceLock<WpadCache> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(None))
+}
+
+/// WPAD candidate URLs for the given DNS suffixes, most specific first
+///
+/// Each suffix is queried as configured (when it has at least two labels), then devolved
+/// one label at a time while at least three labels remain: `eng.corp.example.com` yields
+/// `wpad.eng.corp.example.com` and `wpad.corp.example.com`. Devolution never reaches a
+/// two-label name, since that may be a public suffix such as `co.uk` whose `wpad` host
+/// anyone could register.
+pub fn wpad_candidates(suffixes: &[String]) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for suffix in suffixes {
+        let labels: Vec<&str> = suffix
+            .trim_matches('.')
+            .split('.')
+            .filter(|l| !l.is_empty())
+            .collect();
+        if labels.len() < 2 {
+            continue;
+        }
+        let devolved = (1..labels.len().saturating_sub(2)).map(|start| &labels[start..]);
+        for domain in std::iter::once(&labels[..]).chain(devolved) {
+            let url = format!("http://wpad.{}/wpad.dat", domain.join("."));
+            if !out.contains(&url) {
+                out.push(url);
+            }
+        }
+    }
+    out
+}
+
+/// Discover a PAC URL via DNS WPAD; the result (including "none found") is cached
+pub fn discover_wpad() -> Option<String> {
+    if let Some((at, found)) = wpad_cache().lock().unwrap().as_ref() {
+        let ttl = if found.is_some() {
+            PAC_CACHE_TTL
+        } else {
+            PAC_FAILURE_TTL
+        };
+        if at.elapsed() < ttl {
+            return found.clone();
+        }
+    }
+    let found = wpad_candidates(&dns_suffixes())
+        .into_iter()
+        .find(|url| match load_cached(url) {
+            Ok(_) => true,
+            Err(e) => {
+                tracing::debug!("WPAD candidate {url} unavailable: {e}");
+                false
+            }
+        });
+    if let Some(url) = &found {
+        tracing::info!("Discovered PAC script via WPAD: {url}");
+    }
+    *wpad_cache().lock().unwrap() = Some((Instant::now(), found.clone()));
+    found
+}
+
+fn fetch_pac(location: &str) -> Result<Vec<u8>> {
+    if let Ok(url) = Url::parse(location) {
+        match url.scheme() {
+            "http" => return fetch_http(&url),
+            "https" => {
+                // Reuse the async HTTPS fetcher on a dedicated thread so this works
+                // both inside and outside a Tokio runtime. The PAC script decides the
+                // proxy itself, so it is fetched directly like browsers do.
+                let location = location.to_string();
+                let mut cfg = load_or_init().unwrap_or_default();
+                cfg.proxy = ProxyConfig::default();
+                return std::thread::spawn(move || -> Result<Vec<u8>> {
+                    let runtime = tokio::runtime::Builder::new_current_thread()
+                        .enable_all()
+                        .build()?;
+                    runtime.block_on(crate::core::ip_pool::subscription::fetch_source_with(
+                        &location, cfg,
+                    ))
+                })
+                .join()
+                .map_err(|_| anyhow!("PAC fetch thread panicked"))?;
+            }
+            "file" => {
+                let path = url
+                    .to_file_path()
+                    .map_err(|_| anyhow!("invalid file url: {location}"))?;
+                return read_local(&path);
+            }
+            // Windows drive letters parse as single-letter schemes
+            scheme if scheme.len() > 1 => bail!("unsupported PAC scheme: {scheme}"),
+            _ => {}
+        }
+    }
+    read_local(std::path::Path::new(location))
+}
+
+fn read_local(path: &std::path::Path) -> Result<Vec<u8>> {
+    let data = std::fs::read(path).with_context(|| format!("read {}", path.display()))?;
+    if data.len() > MAX_PAC_BYTES {
+        bail!("PAC script exceeds {MAX_PAC_BYTES} bytes");
+    }
+    Ok(data)
+}
+
+/// Minimal blocking HTTP/1.0 GET, enough for intranet PAC/WPAD servers
+fn fetch_http(url: &Url) -> Result<Vec<u8>> {
+    let host = url
+        .host_str()
+        .ok_or_else(|| anyhow!("PAC url missing host"))?;
+    let port = url.port_or_known_default().unwrap_or(80);
+    let addr = (host, port)
+        .to_socket_addrs()
+        .with_context(|| format!("resolve {host}"))?
+        .next()
+        .ok_or_else(|| anyhow!("no address for {host}"))?;
+    let mut stream =
+        TcpStream::connect_timeout(&addr, PAC_FETCH_TIMEOUT).context("connect PAC server")?;
+    stream.set_read_timeout(Some(PAC_FETCH_TIMEOUT))?;
+    stream.set_write_timeout(Some(PAC_FETCH_TIMEOUT))?;
+
+    let path = match url.query() {
+        Some(q) => format!("{}?{}", url.path(), q),
+        None => url.path().to_string(),
+    };
+    let request = format!(
+        "GET {path} HTTP/1.0\r\nHost: {host}\r\nUser-Agent: fireworks-pac/1.0\r\nAccept: application/x-ns-proxy-autoconfig, */*\r\nConnection: close\r\n\r\n"
+    );
+    stream.write_all(request.as_bytes())?;
+
+    let mut response = Vec::new();
+    stream
+        .take((MAX_PAC_BYTES + 16 * 1024) as u64)
+        .read_to_end(&mut response)
+        .context("read PAC response")?;
+    let split = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| anyhow!("malformed HTTP response"))?;
+    let head = String::from_utf8_lossy(&response[..split]);
+    let status = head
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or_else(|| anyhow!("malformed HTTP status line"))?;
+    if status != 200 {
+        bail!("unexpected HTTP status {status}");
+    }
+    let body = response[split + 4..].to_vec();
+    if body.len() > MAX_PAC_BYTES {
+        bail!("PAC script exceeds {MAX_PAC_BYTES} bytes");
+    }
+    Ok(body)
+}
+
+fn dns_resolve(host: &str) -> Option<String> {
+    let addrs: Vec<IpAddr> = (host, 0)
+        .to_socket_addrs()
+        .ok()?
+        .map(|addr| addr.ip())
+        .collect();
+    addrs
+        .iter()
+        .find(|ip| ip.is_ipv4())
+        .or_else(|| addrs.first())
+        .map(ToString::to_string)
+}
+
+fn my_ip_address() -> String {
+    local_addrs()
+        .into_iter()
+        .find(IpAddr::is_ipv4)
+        .map(|ip| ip.to_string())
+        .unwrap_or_else(|| "127.0.0.1".to_string())
+}
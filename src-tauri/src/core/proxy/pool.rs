@@ -0,0 +1,589 @@
+//! Upstream proxy pool with per-upstream health, failover and sticky hosts
+//!
+//! When `ProxyConfig::upstreams` is non-empty, Http/Socks5 mode connects through
+//! the pool instead of the single `url`. Each upstream keeps its own
+//! `ProxyFailureDetector` and `ProxyHealthChecker`: an upstream whose failure
+//! rate crosses `fallback_threshold` is taken out of rotation until health
+//! probes succeed `recovery_consecutive_threshold` times. Connections try the
+//! remaining upstreams in strategy order before the manager falls back to
+//! direct. A host that connected through an upstream sticks to it for
+//! `sticky_host_ttl_seconds`.
+
+use super::{
+    config::ProxyConfig,
+    detector::ProxyFailureDetector,
+    health_checker::{ProbeResult, ProxyHealthChecker},
+    routing::strip_userinfo,
+    tls::ProxyTlsConfig,
+    HttpProxyConnector, ProxyConnector, ProxyError, ProxyStream, Socks5ProxyConnector,
+};
+use crate::events::structured::{publish_global, Event, StrategyEvent};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Smoothing factor for the latency moving average
+const LATENCY_EWMA_ALPHA: f64 = 0.3;
+
+/// How the pool orders healthy upstreams for a new host
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum UpstreamStrategy {
+    /// Configuration order; later upstreams are standbys
+    #[default]
+    Ordered,
+    /// Random order biased by `weight`
+    Weighted,
+    /// Lowest average connect latency first (unmeasured upstreams last, in order)
+    Latency,
+}
+
+impl std::fmt::Display for UpstreamStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UpstreamStrategy::Ordered => write!(f, "ordered"),
+            UpstreamStrategy::Weighted => write!(f, "weighted"),
+            UpstreamStrategy::Latency => write!(f, "latency"),
+        }
+    }
+}
+
+/// An upstream proxy in the pool
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyUpstream {
+    /// Name used in stats and events
+    pub name: String,
+
+    /// Proxy URL (`http://`, `https://`, `socks5://` or `socks5h://`)
+    pub url: String,
+
+    /// Optional username for proxy authentication
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+
+    /// Optional password for proxy authentication
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+
+    /// Relative weight for the weighted strategy; 0 only serves when others fail (default: 1)
+    #[serde(default = "default_upstream_weight")]
+    pub weight: u32,
+}
+
+pub fn default_upstream_weight() -> u32 {
+    1
+}
+
+impl ProxyUpstream {
+    /// Proxy URL with credentials removed (for logging)
+    pub fn sanitized_url(&self) -> String {
+        strip_userinfo(&self.url)
+    }
+}
+
+/// Per-upstream statistics snapshot
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpstreamStats {
+    pub name: String,
+    /// URL without credentials
+    pub url: String,
+    /// Whether the upstream is in rotation
+    pub healthy: bool,
+    /// Attempts in the current failure window
+    pub total_attempts: usize,
+    /// Failures in the current failure window
+    pub failures: usize,
+    /// Failure rate in the current window (0.0 to 1.0)
+    pub failure_rate: f64,
+    /// Successful connects since the pool was created
+    pub successes_total: u64,
+    /// Failed connects since the pool was created
+    pub failures_total: u64,
+    /// Moving average of connect latency
+    pub avg_latency_ms: Option<u64>,
+    pub last_error: Option<String>,
+    /// Hosts currently pinned to this upstream
+    pub sticky_hosts: usize,
+}
+
+#[derive(Debug, Default)]
+struct UpstreamCounters {
+    down: bool,
+    successes_total: u64,
+    failures_total: u64,
+    latency_ewma_ms: Option<f64>,
+    last_error: Option<String>,
+}
+
+struct UpstreamEntry {
+    upstream: ProxyUpstream,
+    detector: ProxyFailureDetector,
+    health: Mutex<ProxyHealthChecker>,
+    counters: Mutex<UpstreamCounters>,
+}
+
+impl UpstreamEntry {
+    fn is_healthy(&self) -> bool {
+        !self.counters.lock().unwrap().down
+    }
+}
+
+/// Pool of upstream proxies shared by all connections using the same configuration
+pub struct ProxyPool {
+    entries: Vec<UpstreamEntry>,
+    strategy: UpstreamStrategy,
+    sticky_ttl: Duration,
+    timeout: Duration,
+    tls: ProxyTlsConfig,
+    sticky: Mutex<HashMap<String, (usize, Instant)>>,
+}
+
+impl ProxyPool {
+    /// Build a pool from the configured upstreams; `None` when there are none
+    pub fn from_config(config: &ProxyConfig) -> Option<Self> {
+        if config.upstreams.is_empty() {
+            return None;
+        }
+        let entries = config
+            .upstreams
+            .iter()
+            .map(|upstream| UpstreamEntry {
+                upstream: upstream.clone(),
+                detector: ProxyFailureDetector::new(
+                    config.fallback_window_seconds,
+                    config.fallback_threshold,
+                ),
+                health: Mutex::new(ProxyHealthChecker::from_proxy_config(config)),
+                counters: Mutex::new(UpstreamCounters::default()),
+            })
+            .collect();
+        Some(Self {
+            entries,
+            strategy: config.upstream_strategy,
+            sticky_ttl: Duration::from_secs(config.sticky_host_ttl_seconds),
+            timeout: config.timeout(),
+            tls: config.tls.clone(),
+            sticky: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn strategy(&self) -> UpstreamStrategy {
+        self.strategy
+    }
+
+    pub fn upstreams(&self) -> Vec<ProxyUpstream> {
+        self.entries.iter().map(|e| e.upstream.clone()).collect()
+    }
+
+    /// Healthy upstreams for a host in the order they should be tried
+    ///
+    /// The sticky upstream (if still healthy and not expired) comes first, then
+    /// the strategy order. Empty when every upstream is down.
+    pub fn candidates(&self, host: &str) -> Vec<ProxyUpstream> {
+        self.candidate_indices(host)
+            .into_iter()
+            .map(|i| self.entries[i].upstream.clone())
+            .collect()
+    }
+
+    fn candidate_indices(&self, host: &str) -> Vec<usize> {
+        let healthy: Vec<usize> = (0..self.entries.len())
+            .filter(|&i| self.entries[i].is_healthy())
+            .collect();
+        let mut order = match self.strategy {
+            UpstreamStrategy::Ordered => healthy,
+            UpstreamStrategy::Weighted => self.weighted_order(healthy),
+            UpstreamStrategy::Latency => self.latency_order(healthy),
+        };
+        if let Some(pinned) = self.sticky_for(host) {
+            if let Some(pos) = order.iter().position(|&i| i == pinned) {
+                order.remove(pos);
+                order.insert(0, pinned);
+            }
+        }
+        order
+    }
+
+    /// Weighted random permutation (A-ES keys `u^(1/w)`); zero weights go last
+    fn weighted_order(&self, indices: Vec<usize>) -> Vec<usize> {
+        let mut rng = rand::thread_rng();
+        let mut keyed: Vec<(f64, usize)> = indices
+            .into_iter()
+            .map(|i| {
+                let weight = self.entries[i].upstream.weight;
+                let key = if weight == 0 {
+                    -1.0
+                } else {
+                    rng.gen::<f64>().powf(1.0 / weight as f64)
+                };
+                (key, i)
+            })
+            .collect();
+        keyed.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+        keyed.into_iter().map(|(_, i)| i).collect()
+    }
+
+    fn latency_order(&self, mut indices: Vec<usize>) -> Vec<usize> {
+        let latency = |i: usize| {
+            self.entries[i]
+                .counters
+                .lock()
+                .unwrap()
+                .latency_ewma_ms
+                .unwrap_or(f64::INFINITY)
+        };
+        indices.sort_by(|&a, &b| latency(a).total_cmp(&latency(b)).then(a.cmp(&b)));
+        indices
+    }
+
+    fn sticky_for(&self, host: &str) -> Option<usize> {
+        let mut sticky = self.sticky.lock().unwrap();
+        match sticky.get(host) {
+            Some((idx, at)) if at.elapsed() < self.sticky_ttl => Some(*idx),
+            Some(_) => {
+                sticky.remove(host);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Upstream a host is currently pinned to
+    pub fn sticky_upstream(&self, host: &str) -> Option<String> {
+        self.sticky_for(host)
+            .map(|i| self.entries[i].upstream.name.clone())
+    }
+
+    fn connector(&self, idx: usize) -> Result<Box<dyn ProxyConnector>, ProxyError> {
+        let upstream = &self.entries[idx].upstream;
+        if upstream.url.starts_with("socks5://") || upstream.url.starts_with("socks5h://") {
+            Ok(Box::new(Socks5ProxyConnector::new(
+                upstream.url.clone(),
+                upstream.username.clone(),
+                upstream.password.clone(),
+                self.timeout,
+            )?))
+        } else {
+            Ok(Box::new(
+                HttpProxyConnector::new(
+                    upstream.url.clone(),
+                    upstream.username.clone(),
+                    upstream.password.clone(),
+                    self.timeout,
+                )
+                .with_tls(self.tls.clone()),
+            ))
+        }
+    }
+
+    fn record_latency(&self, idx: usize, latency_ms: u64) {
+        let mut counters = self.entries[idx].counters.lock().unwrap();
+        let sample = latency_ms as f64;
+        counters.latency_ewma_ms = Some(match counters.latency_ewma_ms {
+            Some(avg) => avg + LATENCY_EWMA_ALPHA * (sample - avg),
+            None => sample,
+        });
+    }
+
+    fn record_success(&self, idx: usize, host: &str, latency_ms: u64) {
+        let entry = &self.entries[idx];
+        entry.detector.report_success();
+        {
+            let mut counters = entry.counters.lock().unwrap();
+            counters.successes_total += 1;
+        }
+        self.record_latency(idx, latency_ms);
+        if self.sticky_ttl > Duration::ZERO {
+            self.sticky
+                .lock()
+                .unwrap()
+                .insert(host.to_string(), (idx, Instant::now()));
+        }
+    }
+
+    fn record_failure(&self, idx: usize, error: &str) {
+        let entry = &self.entries[idx];
+        entry.detector.report_failure();
+        {
+            let mut counters = entry.counters.lock().unwrap();
+            counters.failures_total += 1;
+            counters.last_error = Some(error.to_string());
+        }
+        if entry.detector.should_fallback() {
+            entry.detector.mark_fallback_triggered();
+            self.mark_down(idx, error);
+        }
+    }
+
+    fn mark_down(&self, idx: usize, reason: &str) {
+        let entry = &self.entries[idx];
+        {
+            let mut counters = entry.counters.lock().unwrap();
+            if counters.down {
+                return;
+            }
+            counters.down = true;
+        }
+        entry.health.lock().unwrap().record_fallback();
+        self.sticky.lock().unwrap().retain(|_, (i, _)| *i != idx);
+        let failure_rate = entry.detector.get_stats().failure_rate;
+        emit_upstream_down(&entry.upstream.name, reason, failure_rate);
+    }
+
+    fn mark_recovered(&self, idx: usize, consecutive_successes: u32) {
+        let entry = &self.entries[idx];
+        entry.counters.lock().unwrap().down = false;
+        entry.detector.reset();
+        entry.health.lock().unwrap().reset();
+        emit_upstream_recovered(&entry.upstream.name, consecutive_successes);
+    }
+
+    /// Connect to a target through the pool
+    ///
+    /// Tries healthy upstreams in candidate order; outcomes feed each upstream's
+    /// failure detector. Returns the last error when every upstream failed (or
+    /// none is healthy) so the caller can fall back to a direct connection.
+    pub fn connect(&self, host: &str, port: u16) -> Result<ProxyStream, ProxyError> {
+        let candidates = self.candidate_indices(host);
+        let Some(&first) = candidates.first() else {
+            emit_upstream_failover(host, None, None, "no healthy upstream");
+            return Err(ProxyError::proxy("No healthy upstream proxy available"));
+        };
+
+        let mut last_error = None;
+        for &idx in &candidates {
+            let name = &self.entries[idx].upstream.name;
+            let connector = match self.connector(idx) {
+                Ok(c) => c,
+                Err(e) => {
+                    self.record_failure(idx, &e.to_string());
+                    last_error = Some(e);
+                    continue;
+                }
+            };
+            let start = Instant::now();
+            match connector.connect(host, port) {
+                Ok(stream) => {
+                    self.record_success(idx, host, start.elapsed().as_millis() as u64);
+                    if idx != first {
+                        let reason = last_error
+                            .as_ref()
+                            .map(|e: &ProxyError| e.to_string())
+                            .unwrap_or_default();
+                        emit_upstream_failover(
+                            host,
+                            Some(&self.entries[first].upstream.name),
+                            Some(name),
+                            &reason,
+                        );
+                    }
+                    return Ok(stream);
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "Upstream proxy '{}' failed for {}:{}: {}",
+                        name,
+                        host,
+                        port,
+                        e
+                    );
+                    self.record_failure(idx, &e.to_string());
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        let error = last_error.unwrap_or_else(|| ProxyError::proxy("All upstream proxies failed"));
+        emit_upstream_failover(
+            host,
+            Some(&self.entries[first].upstream.name),
+            None,
+            &error.to_string(),
+        );
+        Err(error)
+    }
+
+    /// Probe every upstream with its own health checker
+    ///
+    /// Healthy upstreams refresh their latency average (failures count toward
+    /// their failure window); down upstreams are probed after their cooldown and
+    /// rejoin the rotation once the recovery threshold is met.
+    pub fn health_check(&self) -> Vec<(String, ProbeResult)> {
+        let mut results = Vec::with_capacity(self.entries.len());
+        for (idx, entry) in self.entries.iter().enumerate() {
+            let result = match self.connector(idx) {
+                Ok(connector) => entry.health.lock().unwrap().probe(connector.as_ref()),
+                Err(e) => ProbeResult::Failure {
+                    error: e.to_string(),
+                },
+            };
+            let healthy = entry.is_healthy();
+            match &result {
+                ProbeResult::Success { latency_ms } => {
+                    self.record_latency(idx, *latency_ms);
+                    if !healthy {
+                        let (recover, successes) = {
+                            let health = entry.health.lock().unwrap();
+                            (health.should_recover(), health.consecutive_successes())
+                        };
+                        if recover {
+                            self.mark_recovered(idx, successes);
+                        }
+                    }
+                }
+                ProbeResult::Failure { error } => {
+                    if healthy {
+                        self.record_failure(idx, error);
+                    } else {
+                        entry.counters.lock().unwrap().last_error = Some(error.clone());
+                    }
+                }
+                ProbeResult::Skipped { .. } => {}
+            }
+            results.push((entry.upstream.name.clone(), result));
+        }
+        results
+    }
+
+    /// Per-upstream statistics
+    pub fn stats(&self) -> Vec<UpstreamStats> {
+        let sticky = self.sticky.lock().unwrap();
+        self.entries
+            .iter()
+            .enumerate()
+            .map(|(idx, entry)| {
+                let window = entry.detector.get_stats();
+                let counters = entry.counters.lock().unwrap();
+                UpstreamStats {
+                    name: entry.upstream.name.clone(),
+                    url: entry.upstream.sanitized_url(),
+                    healthy: !counters.down,
+                    total_attempts: window.total_attempts,
+                    failures: window.failures,
+                    failure_rate: window.failure_rate,
+                    successes_total: counters.successes_total,
+                    failures_total: counters.failures_total,
+                    avg_latency_ms: counters.latency_ewma_ms.map(|v| v.round() as u64),
+                    last_error: counters.last_error.clone(),
+                    sticky_hosts: sticky
+                        .values()
+                        .filter(|(i, at)| *i == idx && at.elapsed() < self.sticky_ttl)
+                        .count(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Connector that dials through a `ProxyPool`
+pub struct PoolConnector {
+    pool: Arc<ProxyPool>,
+}
+
+impl PoolConnector {
+    pub fn new(pool: Arc<ProxyPool>) -> Self {
+        Self { pool }
+    }
+}
+
+impl ProxyConnector for PoolConnector {
+    fn connect(&self, host: &str, port: u16) -> Result<ProxyStream, ProxyError> {
+        self.pool.connect(host, port)
+    }
+
+    fn proxy_type(&self) -> &str {
+        "pool"
+    }
+}
+
+type PoolRegistry = Mutex<HashMap<String, Arc<ProxyPool>>>;
+
+fn pool_registry() -> &'static PoolRegistry {
+    static REGISTRY: OnceLock<PoolRegistry> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Process-wide pool for the configuration's upstreams
+///
+/// `ProxyManager`s are created per operation, so upstream health and sticky
+/// hosts live here, keyed by the pool-related settings.
+pub fn shared_pool(config: &ProxyConfig) -> Option<Arc<ProxyPool>> {
+    if config.upstreams.is_empty() {
+        return None;
+    }
+    let key = format!(
+        "{:?}|{:?}|{}|{}|{}|{}|{}|{}|{}|{}|{}",
+        config.upstreams,
+        config.tls,
+        config.upstream_strategy,
+        config.sticky_host_ttl_seconds,
+        config.timeout_seconds,
+        config.fallback_window_seconds,
+        config.fallback_threshold,
+        config.recovery_cooldown_seconds,
+        config.recovery_consecutive_threshold,
+        config.probe_url,
+        config.probe_timeout_seconds,
+    );
+    let mut registry = pool_registry().lock().unwrap();
+    if let Some(pool) = registry.get(&key) {
+        return Some(pool.clone());
+    }
+    let pool = Arc::new(ProxyPool::from_config(config)?);
+    registry.insert(key, pool.clone());
+    Some(pool)
+}
+
+/// Drop shared pools (health state and sticky hosts), e.g. after a network change
+pub fn clear_shared_pool() {
+    pool_registry().lock().unwrap().clear();
+}
+
+fn emit_upstream_failover(host: &str, from: Option<&str>, to: Option<&str>, reason: &str) {
+    tracing::warn!(
+        target = "proxy",
+        host,
+        from = from.unwrap_or("-"),
+        to = to.unwrap_or("direct"),
+        reason,
+        "upstream proxy failover"
+    );
+    publish_global(Event::Strategy(StrategyEvent::ProxyUpstreamFailover {
+        host: host.to_string(),
+        from: from.map(str::to_string),
+        to: to.map(str::to_string),
+        reason: reason.to_string(),
+    }));
+}
+
+fn emit_upstream_down(upstream: &str, reason: &str, failure_rate: f64) {
+    tracing::warn!(
+        target = "proxy",
+        upstream,
+        reason,
+        failure_rate,
+        "upstream proxy marked down"
+    );
+    publish_global(Event::Strategy(StrategyEvent::ProxyUpstreamDown {
+        upstream: upstream.to_string(),
+        reason: reason.to_string(),
+        failure_rate,
+    }));
+}
+
+fn emit_upstream_recovered(upstream: &str, consecutive_successes: u32) {
+    tracing::info!(
+        target = "proxy",
+        upstream,
+        consecutive_successes,
+        "upstream proxy recovered"
+    );
+    publish_global(Event::Strategy(StrategyEvent::ProxyUpstreamRecovered {
+        upstream: upstream.to_string(),
+        consecutive_successes,
+    }));
+}
@@ -0,0 +1,446 @@
+//! Per-host proxy routing and `NO_PROXY` bypass rules
+//!
+//! Resolution order for a `host:port` target:
+//! 1. Mode `Off` (or `FWC_PROXY_FORCE_DISABLE`) → direct, rules are ignored
+//! 2. `NO_PROXY` entries (config `noProxy` plus the `NO_PROXY`/`no_proxy` environment
+//!    variables when `honorEnvNoProxy` is set) → direct
+//! 3. First matching entry in `rules` → direct, the system proxy, or a named proxy
+//! 4. Otherwise the default proxy from `mode`/`url`
+//!
+//! Every network path (git subtransport, `core::http::client`, IP pool probes and the
+//! proxy health checker) asks the same router so a host is never proxied on one path
+//! and sent direct on another. Routed targets are dialed through the proxy connectors, so
+//! each selected proxy resolves names (or not) per its own type; `proxy_resolves_names`
+//! reports that decision for the proxy a target would use.
+
+use super::config::{ProxyConfig, ProxyMode};
+use super::pool::shared_pool;
+use super::socks5_connector::Socks5ProxyConnector;
+use super::system_detector::SystemProxyDetector;
+use crate::core::tls::util::{proxy_force_disabled, proxy_present};
+use ipnet::IpNet;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+/// Route target of a matching rule
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProxyRoute {
+    /// Connect directly (`"direct"`)
+    Direct,
+    /// Use the system proxy / PAC script (`"system"`)
+    System,
+    /// Use a proxy from `ProxyConfig::proxies` (`{"proxy": "name"}`)
+    Proxy(String),
+}
+
+/// A named upstream proxy that rules can refer to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamedProxy {
+    /// Name referenced by `ProxyRoute::Proxy`
+    pub name: String,
+
+    /// Proxy URL (`http://`, `https://`, `socks5://` or `socks5h://`)
+    pub url: String,
+
+    /// Optional username for proxy authentication
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+
+    /// Optional password for proxy authentication
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+}
+
+impl NamedProxy {
+    /// Proxy URL with credentials removed (for logging)
+    pub fn sanitized_url(&self) -> String {
+        strip_userinfo(&self.url)
+    }
+}
+
+/// A routing rule: host globs / IPs / CIDRs plus optional ports mapped to a route
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyRule {
+    /// Host patterns: globs (`*.github.com`, `git?.corp`), IP literals or CIDRs (`10.0.0.0/8`).
+    /// Empty matches every host
+    #[serde(default)]
+    pub hosts: Vec<String>,
+
+    /// Target ports; empty matches every port
+    #[serde(default)]
+    pub ports: Vec<u16>,
+
+    /// Where matching connections go
+    pub route: ProxyRoute,
+}
+
+impl ProxyRule {
+    /// Whether this rule matches the given target
+    pub fn matches(&self, host: &str, port: u16) -> bool {
+        if !self.ports.is_empty() && !self.ports.contains(&port) {
+            return false;
+        }
+        if self.hosts.is_empty() {
+            return true;
+        }
+        let host = normalize_host(host);
+        let ip = host.parse::<IpAddr>().ok();
+        self.hosts.iter().any(|pattern| {
+            let pattern = pattern.trim();
+            if let Ok(net) = pattern.parse::<IpNet>() {
+                return ip.is_some_and(|ip| net.contains(&ip));
+            }
+            if let Ok(addr) = normalize_host(pattern).parse::<IpAddr>() {
+                return ip == Some(addr);
+            }
+            glob_match(&pattern.to_ascii_lowercase(), &host)
+        })
+    }
+}
+
+/// Resolved route for one target
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouteDecision {
+    /// Connect directly
+    Direct,
+    /// Use the system proxy / PAC script
+    System,
+    /// Use a named proxy
+    Named(NamedProxy),
+    /// Use the default proxy configured by `mode`/`url`
+    Default,
+}
+
+impl RouteDecision {
+    /// Route label for logs and metrics
+    pub fn label(&self) -> String {
+        match self {
+            Self::Direct => "direct".to_string(),
+            Self::System => "system".to_string(),
+            Self::Named(proxy) => format!("proxy:{}", proxy.name),
+            Self::Default => "default".to_string(),
+        }
+    }
+}
+
+/// One parsed `NO_PROXY` entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum NoProxyEntry {
+    /// `*`: bypass everything
+    All,
+    /// IP literal or CIDR, with optional port
+    Net(IpNet, Option<u16>),
+    /// Domain suffix (`example.com`, `.example.com`, `*.example.com`), with optional port
+    Domain(String, Option<u16>),
+}
+
+/// Parsed `NO_PROXY` list with curl-compatible semantics
+///
+/// Entries are comma or whitespace separated. A domain matches itself and all
+/// subdomains (a leading `.` or `*.` is ignored); IPs and CIDRs only match IP literal
+/// hosts (no DNS lookups); `host:port` limits the entry to that port; `*` bypasses all.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NoProxy {
+    entries: Vec<NoProxyEntry>,
+}
+
+impl NoProxy {
+    /// Parse a `NO_PROXY` style list
+    pub fn parse(list: &str) -> Self {
+        let mut no_proxy = Self::default();
+        no_proxy.extend(list);
+        no_proxy
+    }
+
+    /// `NO_PROXY` / `no_proxy` from the environment
+    pub fn from_env() -> Self {
+        let mut no_proxy = Self::default();
+        for key in ["NO_PROXY", "no_proxy"] {
+            if let Ok(value) = std::env::var(key) {
+                no_proxy.extend(&value);
+            }
+        }
+        no_proxy
+    }
+
+    /// Append entries from another `NO_PROXY` style list
+    pub fn extend(&mut self, list: &str) {
+        for raw in list.split(|c: char| c == ',' || c.is_whitespace()) {
+            if let Some(entry) = parse_no_proxy_entry(raw) {
+                if !self.entries.contains(&entry) {
+                    self.entries.push(entry);
+                }
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Whether the target bypasses the proxy
+    pub fn matches(&self, host: &str, port: u16) -> bool {
+        let host = normalize_host(host);
+        let ip = host.parse::<IpAddr>().ok();
+        let port_ok = |p: &Option<u16>| p.is_none_or(|p| p == port);
+        self.entries.iter().any(|entry| match entry {
+            NoProxyEntry::All => true,
+            NoProxyEntry::Net(net, p) => port_ok(p) && ip.is_some_and(|ip| net.contains(&ip)),
+            NoProxyEntry::Domain(domain, p) => {
+                port_ok(p)
+                    && (host == *domain
+                        || (host.len() > domain.len()
+                            && host.ends_with(domain.as_str())
+                            && host.as_bytes()[host.len() - domain.len() - 1] == b'.'))
+            }
+        })
+    }
+}
+
+fn parse_no_proxy_entry(raw: &str) -> Option<NoProxyEntry> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return None;
+    }
+    if raw == "*" {
+        return Some(NoProxyEntry::All);
+    }
+    if let Ok(net) = raw.parse::<IpNet>() {
+        return Some(NoProxyEntry::Net(net, None));
+    }
+    if let Ok(ip) = normalize_host(raw).parse::<IpAddr>() {
+        return Some(NoProxyEntry::Net(IpNet::from(ip), None));
+    }
+    // host:port / [v6]:port
+    let (host, port) = match raw.rsplit_once(':') {
+        Some((host, port)) if !host.is_empty() && port.parse::<u16>().is_ok() => {
+            (host, port.parse::<u16>().ok())
+        }
+        _ => (raw, None),
+    };
+    if let Ok(ip) = normalize_host(host).parse::<IpAddr>() {
+        return Some(NoProxyEntry::Net(IpNet::from(ip), port));
+    }
+    let domain = host
+        .trim_start_matches("*.")
+        .trim_start_matches('.')
+        .trim_end_matches('.')
+        .to_ascii_lowercase();
+    if domain.is_empty() {
+        return None;
+    }
+    Some(NoProxyEntry::Domain(domain, port))
+}
+
+/// Router built from a `ProxyConfig` snapshot
+#[derive(Debug, Clone)]
+pub struct ProxyRouter {
+    mode: ProxyMode,
+    no_proxy: NoProxy,
+    rules: Vec<ProxyRule>,
+    proxies: Vec<NamedProxy>,
+}
+
+impl ProxyRouter {
+    pub fn new(config: &ProxyConfig) -> Self {
+        let mut no_proxy = NoProxy::default();
+        for entry in &config.no_proxy {
+            no_proxy.extend(entry);
+        }
+        if config.honor_env_no_proxy {
+            let env = NoProxy::from_env();
+            for entry in env.entries {
+                if !no_proxy.entries.contains(&entry) {
+                    no_proxy.entries.push(entry);
+                }
+            }
+        }
+        Self {
+            mode: config.mode,
+            no_proxy,
+            rules: config.rules.clone(),
+            proxies: config.proxies.clone(),
+        }
+    }
+
+    /// Resolve the route for a target
+    pub fn route(&self, host: &str, port: u16) -> RouteDecision {
+        if self.mode == ProxyMode::Off || proxy_force_disabled() {
+            return RouteDecision::Direct;
+        }
+        if self.no_proxy.matches(host, port) {
+            return RouteDecision::Direct;
+        }
+        let Some(rule) = self.rules.iter().find(|rule| rule.matches(host, port)) else {
+            return RouteDecision::Default;
+        };
+        match &rule.route {
+            ProxyRoute::Direct => RouteDecision::Direct,
+            ProxyRoute::System => RouteDecision::System,
+            ProxyRoute::Proxy(name) => match self.proxies.iter().find(|p| &p.name == name) {
+                Some(proxy) => RouteDecision::Named(proxy.clone()),
+                None => {
+                    tracing::warn!(
+                        "Proxy rule refers to unknown proxy '{}', using default",
+                        name
+                    );
+                    RouteDecision::Default
+                }
+            },
+        }
+    }
+
+    /// Resolve the route for a URL (`https://host[:port]/...`)
+    pub fn route_url(&self, url: &str) -> RouteDecision {
+        match host_port_of(url) {
+            Some((host, port)) => self.route(&host, port),
+            None => RouteDecision::Default,
+        }
+    }
+
+    /// Whether some targets may be routed directly while the proxy is enabled
+    pub fn has_direct_routes(&self) -> bool {
+        !self.no_proxy.is_empty()
+            || self
+                .rules
+                .iter()
+                .any(|rule| rule.route == ProxyRoute::Direct)
+    }
+}
+
+/// Whether connections to `host:port` go through a proxy under this configuration
+///
+/// With mode `Off` this falls back to proxy environment variables (minus `NO_PROXY`),
+/// matching how Fake SNI and transport selection treated an environment proxy before.
+pub fn proxy_in_use(config: &ProxyConfig, host: &str, port: u16) -> bool {
+    if proxy_force_disabled() {
+        return false;
+    }
+    if config.mode == ProxyMode::Off {
+        return proxy_present() && !NoProxy::from_env().matches(host, port);
+    }
+    match ProxyRouter::new(config).route(host, port) {
+        RouteDecision::Direct => false,
+        RouteDecision::Named(_) => true,
+        RouteDecision::System => system_proxy_configured(config),
+        RouteDecision::Default => {
+            config.mode != ProxyMode::System || system_proxy_configured(config)
+        }
+    }
+}
+
+/// Whether transports must dial `host:port` through the routed proxy connectors
+///
+/// Unlike [`proxy_in_use`] this ignores environment proxies under mode `Off`: only a
+/// configured proxy has connectors to tunnel through. Such targets skip Fake SNI and
+/// the IP pool; the connector reaches the target (and resolves it where the proxy
+/// type says so).
+pub fn routed_through_proxy(config: &ProxyConfig, host: &str, port: u16) -> bool {
+    config.mode != ProxyMode::Off && proxy_in_use(config, host, port)
+}
+
+/// Whether the proxy used for `host:port` resolves the target name itself
+///
+/// True for HTTP(S) CONNECT, `socks5h://` and system/PAC routes; false for direct routes and
+/// `socks5://` proxies, which resolve locally. For an upstream pool the answer follows the
+/// upstream the pool would try first for `host`, since that is the one a transfer dials.
+/// Mode `Off` ignores environment proxies here, since only configured proxies declare how
+/// names are resolved.
+pub fn proxy_resolves_names(config: &ProxyConfig, host: &str, port: u16) -> bool {
+    if config.mode == ProxyMode::Off || !proxy_in_use(config, host, port) {
+        return false;
+    }
+    match ProxyRouter::new(config).route(host, port) {
+        RouteDecision::Direct => false,
+        RouteDecision::System => true,
+        RouteDecision::Named(proxy) => Socks5ProxyConnector::resolves_remotely(&proxy.url),
+        RouteDecision::Default => match config.mode {
+            ProxyMode::Http | ProxyMode::Socks5 if !config.upstreams.is_empty() => {
+                shared_pool(config)
+                    .and_then(|pool| pool.candidates(host).into_iter().next())
+                    .is_some_and(|u| Socks5ProxyConnector::resolves_remotely(&u.url))
+            }
+            ProxyMode::Http | ProxyMode::Socks5 => {
+                Socks5ProxyConnector::resolves_remotely(&config.url)
+            }
+            ProxyMode::System => true,
+            ProxyMode::Off => false,
+        },
+    }
+}
+
+/// Whether System mode has anything to route through (explicit/platform PAC or a detected proxy)
+///
+/// WPAD is not probed here to keep per-connection decisions free of network I/O.
+fn system_proxy_configured(config: &ProxyConfig) -> bool {
+    config
+        .pac_url
+        .as_ref()
+        .is_some_and(|url| !url.trim().is_empty())
+        || SystemProxyDetector::detect_pac_url().is_some()
+        || SystemProxyDetector::detect().is_some()
+}
+
+/// `proxy_in_use` for a URL; URLs without a host count as proxied when any proxy is present
+pub fn proxy_in_use_for_url(config: &ProxyConfig, url: &str) -> bool {
+    match host_port_of(url) {
+        Some((host, port)) => proxy_in_use(config, &host, port),
+        None => config.is_enabled() || proxy_present(),
+    }
+}
+
+/// Host and port (scheme default) of a URL
+pub fn host_port_of(url: &str) -> Option<(String, u16)> {
+    let parsed = url::Url::parse(url).ok()?;
+    let host = parsed.host_str()?.to_string();
+    let port = parsed.port_or_known_default().unwrap_or(443);
+    Some((normalize_host(&host), port))
+}
+
+fn normalize_host(host: &str) -> String {
+    host.trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .trim_end_matches('.')
+        .to_ascii_lowercase()
+}
+
+pub(crate) fn strip_userinfo(url: &str) -> String {
+    match url.split_once("://") {
+        Some((scheme, rest)) => match rest.rsplit_once('@') {
+            Some((_, host)) => format!("{scheme}://{host}"),
+            None => url.to_string(),
+        },
+        None => url.to_string(),
+    }
+}
+
+/// Shell-style glob match (`*` any sequence, `?` one character)
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let (p, t) = (pattern.as_bytes(), text.as_bytes());
+    let (mut pi, mut ti) = (0, 0);
+    let (mut star, mut mark) = (None, 0);
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == b'?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == b'*' {
+            star = Some(pi);
+            mark = ti;
+            pi += 1;
+        } else if let Some(s) = star {
+            pi = s + 1;
+            mark += 1;
+            ti = mark;
+        } else {
+            return false;
+        }
+    }
+    while pi < p.len() && p[pi] == b'*' {
+        pi += 1;
+    }
+    pi == p.len()
+}
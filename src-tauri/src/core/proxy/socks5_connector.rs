@@ -6,6 +6,12 @@
 //! - IPv4、IPv6 和域名地址类型
 //! - 超时控制和错误分类
 //!
+//! # DNS 解析位置
+//!
+//! - `socks5://`：本地解析目标域名，向代理发送 IP 地址（与 curl 一致）
+//! - `socks5h://`：由代理解析，向代理发送域名，本地不产生 DNS 查询
+//! - `socks://` 或无 scheme 的 `host:port`：沿用旧行为，由代理解析
+//!
 //! # SOCKS5 协议流程
 //!
 //! 1. 版本协商：客户端发送支持的认证方法列表，服务器选择一个
@@ -14,7 +20,7 @@
 //! 4. 连接响应：服务器返回连接结果
 
 use super::errors::ProxyError;
-use super::ProxyConnector;
+use super::{ProxyConnector, ProxyStream};
 use std::io::{Read, Write};
 use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
 use std::time::Duration;
@@ -65,6 +71,8 @@ pub struct Socks5ProxyConnector {
     pub password: Option<String>,
     /// 连接超时
     pub timeout: Duration,
+    /// 是否由代理解析目标域名（`socks5h`）；为 false 时本地解析后发送 IP
+    pub remote_dns: bool,
 }
 
 impl Socks5ProxyConnector {
@@ -87,6 +95,7 @@ impl Socks5ProxyConnector {
         timeout: Duration,
     ) -> Result<Self, ProxyError> {
         let (host, port) = Self::parse_proxy_url(&proxy_url)?;
+        let remote_dns = Self::resolves_remotely(&proxy_url);
 
         Ok(Self {
             proxy_url,
@@ -95,13 +104,20 @@ impl Socks5ProxyConnector {
             username,
             password,
             timeout,
+            remote_dns,
         })
     }
 
+    /// 代理 URL 是否要求由代理解析目标域名：仅 `socks5://` 在本地解析
+    pub fn resolves_remotely(url: &str) -> bool {
+        !url.trim().to_ascii_lowercase().starts_with("socks5://")
+    }
+
     /// 解析代理 URL
     ///
     /// 支持的格式:
     /// - socks5://host:port
+    /// - socks5h://host:port (由代理解析域名)
     /// - socks://host:port (视为 SOCKS5)
     /// - host:port (默认为 SOCKS5)
     ///
@@ -116,6 +132,7 @@ impl Socks5ProxyConnector {
         // 移除 scheme 前缀
         let url = url
             .trim_start_matches("socks5://")
+            .trim_start_matches("socks5h://")
             .trim_start_matches("socks://");
 
         // 解析 host:port
@@ -146,13 +163,33 @@ impl Socks5ProxyConnector {
 
     /// 获取脱敏的代理 URL（隐藏凭证）
     pub fn sanitized_url(&self) -> String {
+        let scheme = if self.proxy_url.trim().starts_with("socks5h://") {
+            "socks5h"
+        } else {
+            "socks5"
+        };
         if self.username.is_some() {
-            format!("socks5://***:***@{}:{}", self.proxy_host, self.proxy_port)
+            format!("{scheme}://***:***@{}:{}", self.proxy_host, self.proxy_port)
         } else {
-            format!("socks5://{}:{}", self.proxy_host, self.proxy_port)
+            format!("{scheme}://{}:{}", self.proxy_host, self.proxy_port)
         }
     }
 
+    /// 本地解析目标主机（`socks5://`）；IP 字面量原样返回
+    fn resolve_target(&self, host: &str, port: u16) -> Result<String, ProxyError> {
+        let bare = host.trim_start_matches('[').trim_end_matches(']');
+        if bare.parse::<std::net::IpAddr>().is_ok() {
+            return Ok(bare.to_string());
+        }
+        let addr = (bare, port)
+            .to_socket_addrs()
+            .map_err(|e| ProxyError::network(format!("Failed to resolve target '{host}': {e}")))?
+            .next()
+            .ok_or_else(|| ProxyError::network(format!("No addresses resolved for '{host}'")))?;
+        tracing::debug!(target.host = %host, resolved = %addr.ip(), "Resolved target locally for SOCKS5");
+        Ok(addr.ip().to_string())
+    }
+
     /// 版本协商
     ///
     /// 发送支持的认证方法列表，接收服务器选择的方法
@@ -438,7 +475,7 @@ impl Socks5ProxyConnector {
 }
 
 impl ProxyConnector for Socks5ProxyConnector {
-    fn connect(&self, host: &str, port: u16) -> Result<TcpStream, ProxyError> {
+    fn connect(&self, host: &str, port: u16) -> Result<ProxyStream, ProxyError> {
         let start = std::time::Instant::now();
 
         tracing::debug!(
@@ -446,6 +483,7 @@ impl ProxyConnector for Socks5ProxyConnector {
             target.host = %host,
             target.port = %port,
             timeout_secs = self.timeout.as_secs(),
+            remote_dns = self.remote_dns,
             "Attempting SOCKS5 proxy connection"
         );
 
@@ -501,8 +539,13 @@ impl ProxyConnector for Socks5ProxyConnector {
             }
         }
 
-        // 4. 发送连接请求
-        self.send_connect_request(&mut stream, host, port)?;
+        // 4. 发送连接请求（socks5 先在本地解析目标，socks5h 交由代理解析）
+        let target = if self.remote_dns {
+            host.to_string()
+        } else {
+            self.resolve_target(host, port)?
+        };
+        self.send_connect_request(&mut stream, &target, port)?;
 
         // 5. 解析连接响应
         self.parse_connect_response(&mut stream)?;
@@ -517,7 +560,7 @@ impl ProxyConnector for Socks5ProxyConnector {
             "SOCKS5 tunnel established successfully"
         );
 
-        Ok(stream)
+        Ok(ProxyStream::Tcp(stream))
     }
 
     fn proxy_type(&self) -> &str {
@@ -0,0 +1,85 @@
+//! Stream returned by proxy connectors
+//!
+//! A tunnel through a plain HTTP or SOCKS5 proxy is the TCP connection itself;
+//! through an `https://` proxy the tunnel runs inside the TLS session with the
+//! proxy, so callers read and write through `ProxyStream` instead of the socket.
+
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+
+use rustls::{ClientConnection, StreamOwned};
+
+/// Tunnel to the target, either the raw socket or TLS to the proxy
+pub enum ProxyStream {
+    /// Direct connection or tunnel through a plain-text proxy
+    Tcp(TcpStream),
+    /// Tunnel inside a TLS session with an `https://` proxy
+    Tls(Box<StreamOwned<ClientConnection, TcpStream>>),
+}
+
+impl ProxyStream {
+    /// Underlying socket (to the target or to the proxy)
+    pub fn tcp(&self) -> &TcpStream {
+        match self {
+            ProxyStream::Tcp(s) => s,
+            ProxyStream::Tls(s) => s.get_ref(),
+        }
+    }
+
+    /// Whether the connection to the proxy is encrypted
+    pub fn is_tls(&self) -> bool {
+        matches!(self, ProxyStream::Tls(_))
+    }
+
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.tcp().peer_addr()
+    }
+
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.tcp().set_read_timeout(timeout)
+    }
+
+    pub fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.tcp().set_write_timeout(timeout)
+    }
+
+    /// The plain socket; `None` when the tunnel runs inside TLS to the proxy
+    pub fn into_tcp(self) -> Option<TcpStream> {
+        match self {
+            ProxyStream::Tcp(s) => Some(s),
+            ProxyStream::Tls(_) => None,
+        }
+    }
+}
+
+impl From<TcpStream> for ProxyStream {
+    fn from(stream: TcpStream) -> Self {
+        ProxyStream::Tcp(stream)
+    }
+}
+
+impl Read for ProxyStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            ProxyStream::Tcp(s) => s.read(buf),
+            ProxyStream::Tls(s) => s.read(buf),
+        }
+    }
+}
+
+impl Write for ProxyStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            ProxyStream::Tcp(s) => s.write(buf),
+            ProxyStream::Tls(s) => s.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            ProxyStream::Tcp(s) => s.flush(),
+            ProxyStream::Tls(s) => s.flush(),
+        }
+    }
+}
@@ -37,6 +37,69 @@ impl SystemProxyDetector {
         Self::detect_from_env()
     }
 
+    /// Detect the platform's proxy auto-config (PAC) URL
+    ///
+    /// Windows: `AutoConfigURL` in Internet Settings; macOS: `ProxyAutoConfigURLString`
+    /// when `ProxyAutoConfigEnable` is set. Other platforms have no system PAC setting.
+    pub fn detect_pac_url() -> Option<String> {
+        if proxy_force_disabled() {
+            return None;
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            use winreg::enums::*;
+            use winreg::RegKey;
+
+            let hkcu = RegKey::predef(HKEY_CURRENT_USER);
+            let internet_settings = hkcu
+                .open_subkey("Software\\Microsoft\\Windows\\CurrentVersion\\Internet Settings")
+                .ok()?;
+            let url: String = internet_settings.get_value("AutoConfigURL").ok()?;
+            let url = url.trim();
+            if !url.is_empty() {
+                tracing::info!("Detected Windows PAC URL: {}", url);
+                return Some(url.to_string());
+            }
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            use std::process::Command;
+
+            let output = Command::new("scutil").arg("--proxy").output().ok()?;
+            if output.status.success() {
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                let lines: Vec<&str> = stdout.lines().collect();
+                if let Some(url) = Self::parse_scutil_pac(&lines) {
+                    tracing::info!("Detected macOS PAC URL: {}", url);
+                    return Some(url);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Parse the PAC URL out of `scutil --proxy` output
+    #[cfg(target_os = "macos")]
+    pub fn parse_scutil_pac(lines: &[&str]) -> Option<String> {
+        let value_of = |key: &str| {
+            lines
+                .iter()
+                .find(|line| line.trim_start().starts_with(key))
+                .and_then(|line| line.split_once(':'))
+                .map(|(_, val)| val.trim().to_string())
+        };
+        let enabled = value_of("ProxyAutoConfigEnable")
+            .and_then(|v| v.parse::<u32>().ok())
+            .unwrap_or(0);
+        if enabled == 0 {
+            return None;
+        }
+        value_of("ProxyAutoConfigURLString").filter(|url| !url.is_empty())
+    }
+
     /// Detect proxy from environment variables (Linux and fallback for other platforms)
     ///
     /// Reuses the logic from `tls::util::proxy_present()` but also extracts the URL
@@ -188,7 +251,10 @@ impl SystemProxyDetector {
         let url = url.trim();
 
         // Determine proxy mode from URL scheme
-        let mode = if url.starts_with("socks5://") || url.starts_with("socks://") {
+        let mode = if url.starts_with("socks5://")
+            || url.starts_with("socks5h://")
+            || url.starts_with("socks://")
+        {
             ProxyMode::Socks5
         } else if url.starts_with("http://") || url.starts_with("https://") {
             ProxyMode::Http
@@ -0,0 +1,149 @@
+//! TLS to `https://` proxies
+//!
+//! The proxy certificate is verified independently of the target: public roots
+//! plus optional extra CAs / the OS store from `ProxyTlsConfig`. The app-wide
+//! TLS settings (pins, fake SNI, custom CA scopes) only apply to the tunneled
+//! connection to the target.
+
+use super::ProxyError;
+use crate::core::tls::trust::{add_pem_file, pem_files};
+use crate::core::tls::verifier::build_root_store;
+use rustls::client::{ServerCertVerified, ServerCertVerifier};
+use rustls::{Certificate, ClientConfig, ClientConnection, ServerName, StreamOwned};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::TcpStream;
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Verification settings for the TLS connection to `https://` proxies
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyTlsConfig {
+    /// Extra CA certificates (PEM file or directory) trusted for proxy certificates
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_path: Option<String>,
+
+    /// Also trust the operating system certificate store (default: false)
+    #[serde(default)]
+    pub use_system_trust_store: bool,
+
+    /// Name to verify (and send as SNI) instead of the proxy URL host
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_name: Option<String>,
+
+    /// Skip proxy certificate verification entirely (default: false; testing only)
+    #[serde(default)]
+    pub insecure_skip_verify: bool,
+}
+
+impl ProxyTlsConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Accepts any proxy certificate (`insecure_skip_verify`)
+struct NoVerification;
+
+impl ServerCertVerifier for NoVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: std::time::SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+}
+
+/// rustls client configuration for proxy connections (cached per settings)
+pub fn client_config(tls: &ProxyTlsConfig) -> Result<Arc<ClientConfig>, ProxyError> {
+    if let Some(cfg) = config_cache().lock().unwrap().get(tls) {
+        return Ok(cfg.clone());
+    }
+
+    let mut roots = build_root_store();
+    if let Some(ca_path) = tls.ca_path.as_deref().filter(|p| !p.trim().is_empty()) {
+        let path = Path::new(ca_path.trim());
+        let files = pem_files(path)
+            .map_err(|e| ProxyError::config(format!("Proxy CA {}: {e}", path.display())))?;
+        let mut added = 0;
+        for file in files {
+            added += add_pem_file(&mut roots, &file)
+                .map_err(|e| ProxyError::config(format!("Proxy CA {e}")))?;
+        }
+        if added == 0 {
+            return Err(ProxyError::config(format!(
+                "Proxy CA {}: no usable certificate",
+                path.display()
+            )));
+        }
+    }
+    if tls.use_system_trust_store {
+        match rustls_native_certs::load_native_certs() {
+            Ok(certs) => {
+                let ders: Vec<Vec<u8>> = certs.into_iter().map(|c| c.0).collect();
+                roots.add_parsable_certificates(&ders);
+            }
+            Err(e) => tracing::warn!("System trust store unavailable for proxy TLS: {}", e),
+        }
+    }
+
+    let mut cfg = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    if tls.insecure_skip_verify {
+        tracing::warn!("Proxy certificate verification disabled (insecureSkipVerify)");
+        cfg.dangerous()
+            .set_certificate_verifier(Arc::new(NoVerification));
+    }
+    let cfg = Arc::new(cfg);
+    config_cache()
+        .lock()
+        .unwrap()
+        .insert(tls.clone(), cfg.clone());
+    Ok(cfg)
+}
+
+/// Perform the TLS handshake with the proxy over an established TCP connection
+pub fn connect(
+    stream: TcpStream,
+    proxy_host: &str,
+    tls: &ProxyTlsConfig,
+) -> Result<StreamOwned<ClientConnection, TcpStream>, ProxyError> {
+    let name = tls.server_name.as_deref().unwrap_or(proxy_host);
+    let server_name = ServerName::try_from(name.trim_start_matches('[').trim_end_matches(']'))
+        .map_err(|_| ProxyError::config(format!("Invalid proxy TLS server name: {name}")))?;
+    let conn = ClientConnection::new(client_config(tls)?, server_name)
+        .map_err(|e| ProxyError::proxy(format!("Proxy TLS setup failed: {e}")))?;
+    let mut tls_stream = StreamOwned::new(conn, stream);
+    while tls_stream.conn.is_handshaking() {
+        tls_stream
+            .conn
+            .complete_io(&mut tls_stream.sock)
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock => {
+                    ProxyError::timeout(format!("Proxy TLS handshake timed out: {e}"))
+                }
+                _ => ProxyError::proxy(format!("Proxy TLS handshake failed: {e}")),
+            })?;
+    }
+    Ok(tls_stream)
+}
+
+/// Drop cached proxy TLS configurations (e.g. after the CA file changed)
+pub fn clear_cache() {
+    config_cache().lock().unwrap().clear();
+}
+
+type ConfigCache = Mutex<HashMap<ProxyTlsConfig, Arc<ClientConfig>>>;
+
+fn config_cache() -> &'static ConfigCache {
+    static CACHE: OnceLock<ConfigCache> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
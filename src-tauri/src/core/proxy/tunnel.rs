@@ -0,0 +1,329 @@
+//! Proxy tunnels for the tokio-based transports
+//!
+//! The git subtransport, `HttpClient` and IP pool probes run on tokio while
+//! `ProxyConnector`s are blocking. [`connect`] runs the connectors routed for a
+//! target URL on the blocking pool, in order, and hands the tunnel back as an
+//! [`AsyncProxyStream`]. A tunnel through an `https://` proxy keeps its TLS
+//! session with the proxy, which is driven asynchronously by [`TlsTunnel`].
+
+use std::io::{self, Read, Write};
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use rustls::{ClientConnection, StreamOwned};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpStream;
+
+use super::{ProxyConfig, ProxyError, ProxyManager, ProxyStream};
+
+/// Async counterpart of [`ProxyStream`]: a direct socket, a plain tunnel, or a tunnel inside TLS
+pub enum AsyncProxyStream {
+    /// Direct connection or tunnel through a plain-text proxy
+    Tcp(TcpStream),
+    /// Tunnel inside a TLS session with an `https://` proxy
+    Tls(Box<TlsTunnel>),
+}
+
+impl AsyncProxyStream {
+    /// Move a blocking tunnel onto the current tokio runtime
+    pub fn from_blocking(stream: ProxyStream) -> io::Result<Self> {
+        match stream {
+            ProxyStream::Tcp(tcp) => {
+                tcp.set_nonblocking(true)?;
+                let tcp = TcpStream::from_std(tcp)?;
+                tcp.set_nodelay(true).ok();
+                Ok(AsyncProxyStream::Tcp(tcp))
+            }
+            ProxyStream::Tls(tls) => {
+                let StreamOwned { conn, sock } = *tls;
+                sock.set_nonblocking(true)?;
+                let io = TcpStream::from_std(sock)?;
+                io.set_nodelay(true).ok();
+                Ok(AsyncProxyStream::Tls(Box::new(TlsTunnel {
+                    conn,
+                    io,
+                    eof: false,
+                    close_sent: false,
+                })))
+            }
+        }
+    }
+
+    /// Whether the connection to the proxy is encrypted
+    pub fn is_tls(&self) -> bool {
+        matches!(self, AsyncProxyStream::Tls(_))
+    }
+}
+
+impl From<TcpStream> for AsyncProxyStream {
+    fn from(stream: TcpStream) -> Self {
+        AsyncProxyStream::Tcp(stream)
+    }
+}
+
+/// Dial `target:port` through the connectors routed for `url`, trying them in order
+///
+/// `url` drives routing and PAC evaluation (a PAC result may list several proxies and
+/// `DIRECT`); `target` is what the proxy is asked to reach, normally the URL's host.
+/// Upstream pools fail over internally and record per-upstream outcomes.
+pub async fn connect(
+    config: &ProxyConfig,
+    url: &str,
+    target: &str,
+    port: u16,
+) -> io::Result<AsyncProxyStream> {
+    let (config, url, target) = (config.clone(), url.to_string(), target.to_string());
+    let stream =
+        tokio::task::spawn_blocking(move || connect_blocking(&config, &url, &target, port))
+            .await
+            .map_err(io::Error::other)??;
+    AsyncProxyStream::from_blocking(stream)
+}
+
+/// Blocking form of [`connect`]
+pub fn connect_blocking(
+    config: &ProxyConfig,
+    url: &str,
+    target: &str,
+    port: u16,
+) -> io::Result<ProxyStream> {
+    let connectors = ProxyManager::new(config.clone())
+        .connectors_for_url(url)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?;
+    let mut last_error: Option<ProxyError> = None;
+    for connector in connectors {
+        match connector.connect(target, port) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => {
+                tracing::debug!(
+                    proxy_type = connector.proxy_type(),
+                    "Tunnel to {target}:{port} failed: {e}"
+                );
+                last_error = Some(e);
+            }
+        }
+    }
+    Err(match last_error {
+        Some(ProxyError::Timeout(msg)) => io::Error::new(io::ErrorKind::TimedOut, msg),
+        Some(e) => io::Error::other(e.to_string()),
+        None => io::Error::other(format!("No proxy connector for {target}:{port}")),
+    })
+}
+
+/// TLS session with an `https://` proxy carrying a CONNECT tunnel
+///
+/// The handshake and CONNECT exchange already happened on the blocking socket; this
+/// drives the established `rustls` session over the non-blocking socket.
+pub struct TlsTunnel {
+    conn: ClientConnection,
+    io: TcpStream,
+    eof: bool,
+    close_sent: bool,
+}
+
+/// Adapts the async socket to `Read`/`Write` for rustls, mapping `Pending` to `WouldBlock`
+struct SyncIo<'a, 'b> {
+    io: &'a mut TcpStream,
+    cx: &'a mut Context<'b>,
+}
+
+impl Read for SyncIo<'_, '_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut buf = ReadBuf::new(buf);
+        match Pin::new(&mut *self.io).poll_read(self.cx, &mut buf) {
+            Poll::Ready(Ok(())) => Ok(buf.filled().len()),
+            Poll::Ready(Err(e)) => Err(e),
+            Poll::Pending => Err(io::ErrorKind::WouldBlock.into()),
+        }
+    }
+}
+
+impl Write for SyncIo<'_, '_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match Pin::new(&mut *self.io).poll_write(self.cx, buf) {
+            Poll::Ready(result) => result,
+            Poll::Pending => Err(io::ErrorKind::WouldBlock.into()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match Pin::new(&mut *self.io).poll_flush(self.cx) {
+            Poll::Ready(result) => result,
+            Poll::Pending => Err(io::ErrorKind::WouldBlock.into()),
+        }
+    }
+}
+
+impl TlsTunnel {
+    /// Read TLS records from the socket and process them
+    fn read_tls(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<usize>> {
+        let mut io = SyncIo {
+            io: &mut self.io,
+            cx,
+        };
+        let n = match self.conn.read_tls(&mut io) {
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Poll::Pending,
+            Err(e) => return Poll::Ready(Err(e)),
+        };
+        if let Err(e) = self.conn.process_new_packets() {
+            // Best effort: let the proxy see the alert before failing
+            let _ = self.write_tls(cx);
+            return Poll::Ready(Err(io::Error::new(io::ErrorKind::InvalidData, e)));
+        }
+        Poll::Ready(Ok(n))
+    }
+
+    /// Write pending TLS records to the socket
+    fn write_tls(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<usize>> {
+        let mut io = SyncIo {
+            io: &mut self.io,
+            cx,
+        };
+        match self.conn.write_tls(&mut io) {
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => Poll::Pending,
+            result => Poll::Ready(result),
+        }
+    }
+
+    fn flush_tls(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while self.conn.wants_write() {
+            if ready!(self.write_tls(cx))? == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncRead for TlsTunnel {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let mut io_pending = false;
+        while !this.eof && this.conn.wants_read() {
+            match this.read_tls(cx) {
+                Poll::Ready(Ok(0)) => this.eof = true,
+                Poll::Ready(Ok(_)) => {}
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                Poll::Pending => {
+                    io_pending = true;
+                    break;
+                }
+            }
+        }
+        // Handshake messages such as key updates may need a reply
+        if this.conn.wants_write() {
+            let _ = this.flush_tls(cx);
+        }
+        match this.conn.reader().read(buf.initialize_unfilled()) {
+            Ok(n) => {
+                buf.advance(n);
+                Poll::Ready(Ok(()))
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                if this.eof {
+                    return Poll::Ready(Err(io::ErrorKind::UnexpectedEof.into()));
+                }
+                if !io_pending {
+                    cx.waker().wake_by_ref();
+                }
+                Poll::Pending
+            }
+            Err(e) => Poll::Ready(Err(e)),
+        }
+    }
+}
+
+impl AsyncWrite for TlsTunnel {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let mut written = 0;
+        while written < buf.len() {
+            written += this.conn.writer().write(&buf[written..])?;
+            let mut blocked = false;
+            while this.conn.wants_write() {
+                match this.write_tls(cx) {
+                    Poll::Ready(Ok(0)) | Poll::Pending => {
+                        blocked = true;
+                        break;
+                    }
+                    Poll::Ready(Ok(_)) => {}
+                    Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                }
+            }
+            if blocked {
+                return if written == 0 {
+                    Poll::Pending
+                } else {
+                    Poll::Ready(Ok(written))
+                };
+            }
+        }
+        Poll::Ready(Ok(written))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        this.conn.writer().flush()?;
+        ready!(this.flush_tls(cx))?;
+        Pin::new(&mut this.io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if !this.close_sent {
+            this.conn.send_close_notify();
+            this.close_sent = true;
+        }
+        ready!(this.flush_tls(cx))?;
+        Pin::new(&mut this.io).poll_shutdown(cx)
+    }
+}
+
+impl AsyncRead for AsyncProxyStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            AsyncProxyStream::Tcp(s) => Pin::new(s).poll_read(cx, buf),
+            AsyncProxyStream::Tls(s) => Pin::new(s.as_mut()).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for AsyncProxyStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            AsyncProxyStream::Tcp(s) => Pin::new(s).poll_write(cx, buf),
+            AsyncProxyStream::Tls(s) => Pin::new(s.as_mut()).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            AsyncProxyStream::Tcp(s) => Pin::new(s).poll_flush(cx),
+            AsyncProxyStream::Tls(s) => Pin::new(s.as_mut()).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            AsyncProxyStream::Tcp(s) => Pin::new(s).poll_shutdown(cx),
+            AsyncProxyStream::Tls(s) => Pin::new(s.as_mut()).poll_shutdown(cx),
+        }
+    }
+}
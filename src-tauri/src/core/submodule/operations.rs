@@ -3,8 +3,11 @@
 //! 实现子模块的初始化、更新、同步等核心操作
 
 use super::model::{SubmoduleConfig, SubmoduleInfo};
+use crate::core::config::loader::load_or_init;
+use crate::core::git::default_impl::helpers::map_git2_error;
 use crate::core::git::errors::GitError;
 use crate::core::git::runner::GitRunner;
+use crate::core::git::transport::{run_with_failover, UrlPurpose};
 use git2::Repository;
 use std::path::Path;
 use tracing::{error, info};
@@ -51,6 +54,53 @@ impl SubmoduleError {
     }
 }
 
+/// 按镜像规则更新单个子模块。
+///
+/// 在父仓库配置中临时替换 `submodule.<name>.url`（已检出的子模块同时替换其 origin），
+/// 镜像失败时依次切换候选，结束后恢复原 URL，不改动 `.gitmodules`。
+fn update_with_mirrors(
+    repo: &Repository,
+    submodule: &mut git2::Submodule<'_>,
+    opts: &mut git2::SubmoduleUpdateOptions<'_>,
+) -> SubmoduleResult<()> {
+    let mirror_cfg = load_or_init().map(|c| c.mirror).unwrap_or_default();
+    let declared = submodule.url().map(str::to_string);
+    let (true, Some(declared), Some(name)) = (
+        mirror_cfg.enabled,
+        declared,
+        submodule.name().map(str::to_string),
+    ) else {
+        submodule.update(true, Some(opts))?;
+        return Ok(());
+    };
+    submodule.init(false)?;
+    let key = format!("submodule.{name}.url");
+    let mut config = repo.config()?;
+    let original = config.get_string(&key).unwrap_or(declared);
+    let checkout = submodule.open().ok();
+    let checkout_origin = checkout
+        .as_ref()
+        .and_then(|r| r.find_remote("origin").ok())
+        .and_then(|o| o.url().map(str::to_string));
+
+    let result = run_with_failover(&mirror_cfg, &original, UrlPurpose::Read, |candidate| {
+        config
+            .set_str(&key, &candidate.url)
+            .and_then(|()| match (&checkout, &checkout_origin) {
+                (Some(sub_repo), Some(_)) => sub_repo.remote_set_url("origin", &candidate.url),
+                _ => Ok(()),
+            })
+            .and_then(|()| submodule.update(true, Some(&mut *opts)))
+            .map_err(|e| GitError::new(map_git2_error(&e), e.message().to_string()))
+    });
+
+    let _ = config.set_str(&key, &original);
+    if let (Some(sub_repo), Some(url)) = (&checkout, &checkout_origin) {
+        let _ = sub_repo.remote_set_url("origin", url);
+    }
+    result.map_err(SubmoduleError::Runner)
+}
+
 /// 子模块操作管理器
 pub struct SubmoduleManager {
     config: SubmoduleConfig,
@@ -164,7 +214,7 @@ impl SubmoduleManager {
         // git2 submodule update signature: pub fn update(&mut self, init: bool, options: Option<&mut SubmoduleUpdateOptions<'_>>) -> Result<(), Error>
 
         for mut submodule in repo.submodules()? {
-            submodule.update(true, Some(&mut update_opts))?;
+            update_with_mirrors(&repo, &mut submodule, &mut update_opts)?;
             // Handle recursion if configured?
             // git2 doesn't do recursion automatically with update call unless we traverse.
             // But for now, we just update one level or basic equivalent of `git submodule update`.
@@ -206,7 +256,7 @@ impl SubmoduleManager {
         let mut submodule = repo.find_submodule(submodule_name)?;
 
         let mut update_opts = git2::SubmoduleUpdateOptions::new();
-        submodule.update(true, Some(&mut update_opts))?;
+        update_with_mirrors(&repo, &mut submodule, &mut update_opts)?;
 
         if self.config.recursive_update {
             if let Some(path) = submodule.path().to_str() {
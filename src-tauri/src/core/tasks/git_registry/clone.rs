@@ -45,6 +45,7 @@ impl TaskRegistry {
                     StrategyEvent as StructuredStrategyEvent,
                 };
                 let fallback_events = tl_take_fallback_events();
+                super::helpers::emit_mirror_outcome(id, kind);
                 if metrics_enabled() {
                     let snap = tl_snapshot();
                     if let Some(t) = snap.timing {
@@ -65,6 +66,7 @@ impl TaskRegistry {
                                 ip_source: snap.ip_source.clone(),
                                 ip_latency_ms: snap.ip_latency_ms,
                                 ip_selection_stage: snap.ip_strategy.map(|s| s.to_string()),
+                                http_version: snap.http_version.map(|s| s.to_string()),
                             },
                         ));
                     }
@@ -115,6 +117,7 @@ impl TaskRegistry {
                     bytes: None,
                     total_hint: None,
                     retried_times: None,
+                    throttle: None,
                 };
                 emit_all(app_ref, EV_PROGRESS, &prog);
                 if let Some(hook) = &progress_hook {
@@ -142,6 +145,7 @@ impl TaskRegistry {
             let mut depth_applied: Option<u32> = None;
             let mut filter_requested: Option<String> = None;
             let mut applied_codes: Vec<String> = vec![];
+            let mut bandwidth_override: Option<u64> = None;
             if let Err(e) = parsed_options_res {
                 let msg_string = e.to_string();
                 if msg_string.contains("unsupported filter:") {
@@ -243,6 +247,15 @@ impl TaskRegistry {
                         applied_codes.push("retry_strategy_override_applied".into());
                     }
                 }
+                if let Some(limit) = opts
+                    .strategy_override
+                    .as_ref()
+                    .and_then(|s| s.bandwidth.as_ref())
+                    .and_then(|b| b.limit_kib_per_sec)
+                {
+                    bandwidth_override = Some(limit);
+                    applied_codes.push("bandwidth_strategy_override_applied".into());
+                }
                 tracing::info!(
                     target = "git",
                     depth = ?opts.depth,
@@ -289,6 +302,15 @@ impl TaskRegistry {
                 }
             }
 
+            // 限速作用域覆盖整个任务（含重试）；批量任务的子任务适用时间窗口，任务取消时中断等待
+            let _throttle = crate::core::git::transport::enter_throttle_scope(
+                &global_cfg.bandwidth,
+                id,
+                "GitClone",
+                bandwidth_override,
+                this.parent_of(&id).is_some(),
+            )
+            .cancel_on(token.clone());
             let plan = retry_plan.clone();
             let mut attempt: u32 = 0;
             loop {
@@ -337,6 +359,7 @@ impl TaskRegistry {
                                     bytes: p.bytes,
                                     total_hint: p.total_hint,
                                     retried_times: None,
+                                    throttle: crate::core::git::transport::tl_throttle_info(),
                                 };
                                 emit_all(app_ref, EV_PROGRESS, &prog);
                                 if let Some(hook) = &hook_for_cb {
@@ -373,6 +396,7 @@ impl TaskRegistry {
                                     bytes: None,
                                     total_hint: None,
                                     retried_times: None,
+                                    throttle: None,
                                 };
                                 emit_all(app_ref, EV_PROGRESS, &prog);
                                 if let Some(hook) = &progress_hook {
@@ -403,6 +427,7 @@ impl TaskRegistry {
                                     bytes: None,
                                     total_hint: None,
                                     retried_times: None,
+                                    throttle: None,
                                 };
                                 emit_all(app_ref, EV_PROGRESS, &prog);
                                 if let Some(hook) = &progress_hook {
@@ -430,6 +455,7 @@ impl TaskRegistry {
                                 bytes: None,
                                 total_hint: None,
                                 retried_times: None,
+                                throttle: None,
                             };
                             emit_all(app_ref, EV_PROGRESS, &prog);
                             if let Some(hook) = &progress_hook {
@@ -471,6 +497,7 @@ impl TaskRegistry {
                                     bytes: None,
                                     total_hint: None,
                                     retried_times: Some(attempt),
+                                    throttle: None,
                                 };
                                 emit_all(app_ref, EV_PROGRESS, &prog);
                             }
@@ -80,6 +80,34 @@ pub(super) fn emit_strategy_summary(
     ));
 }
 
+/// 发布镜像选择结果（依赖 git 操作在当前线程记录的镜像结果）。
+pub(super) fn emit_mirror_outcome(id: Uuid, kind: &str) {
+    let Some(outcome) = crate::core::git::transport::tl_take_mirror_outcome() else {
+        return;
+    };
+    for failover in &outcome.failovers {
+        publish_global(StructuredEvent::Strategy(
+            StructuredStrategyEvent::MirrorFailover {
+                id: id.to_string(),
+                kind: kind.to_string(),
+                mirror: failover.mirror.clone(),
+                reason: failover.reason.clone(),
+            },
+        ));
+    }
+    publish_global(StructuredEvent::Strategy(
+        StructuredStrategyEvent::MirrorServed {
+            id: id.to_string(),
+            kind: kind.to_string(),
+            origin: outcome.origin,
+            served_by: outcome.served_by,
+            mirror: outcome.mirror,
+            failover_count: outcome.failovers.len() as u32,
+            success: outcome.success,
+        },
+    ));
+}
+
 impl TaskRegistry {
     pub(super) fn runtime_config() -> AppConfig {
         runtime_config()
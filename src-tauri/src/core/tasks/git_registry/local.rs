@@ -45,6 +45,7 @@ impl TaskRegistry {
                                 bytes: None,
                                 total_hint: None,
                                 retried_times: None,
+                                throttle: None,
                             };
                             emit_all(app_ref, EV_PROGRESS, &prog);
                         }
@@ -111,6 +112,7 @@ impl TaskRegistry {
                                 bytes: p.bytes,
                                 total_hint: p.total_hint,
                                 retried_times: None,
+                                throttle: None,
                             };
                             emit_all(app_ref, EV_PROGRESS, &prog);
                         }
@@ -187,6 +189,7 @@ impl TaskRegistry {
                                 bytes: p.bytes,
                                 total_hint: p.total_hint,
                                 retried_times: None,
+                                throttle: None,
                             };
                             emit_all(app_ref, EV_PROGRESS, &prog);
                         }
@@ -255,6 +258,7 @@ impl TaskRegistry {
                                 bytes: p.bytes,
                                 total_hint: p.total_hint,
                                 retried_times: None,
+                                throttle: None,
                             };
                             emit_all(app_ref, EV_PROGRESS, &prog);
                         }
@@ -321,6 +325,7 @@ impl TaskRegistry {
                                 bytes: p.bytes,
                                 total_hint: p.total_hint,
                                 retried_times: None,
+                                throttle: None,
                             };
                             emit_all(app_ref, EV_PROGRESS, &prog);
                         }
@@ -392,6 +397,7 @@ impl TaskRegistry {
                                 bytes: p.bytes,
                                 total_hint: p.total_hint,
                                 retried_times: None,
+                                throttle: None,
                             };
                             emit_all(app_ref, EV_PROGRESS, &prog);
                         }
@@ -458,6 +464,7 @@ impl TaskRegistry {
                                 bytes: p.bytes,
                                 total_hint: p.total_hint,
                                 retried_times: None,
+                                throttle: None,
                             };
                             emit_all(app_ref, EV_PROGRESS, &prog);
                         }
@@ -524,6 +531,7 @@ impl TaskRegistry {
                                 bytes: p.bytes,
                                 total_hint: p.total_hint,
                                 retried_times: None,
+                                throttle: None,
                             };
                             emit_all(app_ref, EV_PROGRESS, &prog);
                         }
@@ -588,6 +596,7 @@ impl TaskRegistry {
                                 bytes: p.bytes,
                                 total_hint: p.total_hint,
                                 retried_times: None,
+                                throttle: None,
                             };
                             emit_all(app_ref, EV_PROGRESS, &prog);
                         }
@@ -654,6 +663,7 @@ impl TaskRegistry {
                                 bytes: p.bytes,
                                 total_hint: p.total_hint,
                                 retried_times: None,
+                                throttle: None,
                             };
                             emit_all(app_ref, EV_PROGRESS, &prog);
                         }
@@ -44,6 +44,7 @@ impl TaskRegistry {
                     StrategyEvent as StructuredStrategyEvent,
                 };
                 let fallback_events = tl_take_fallback_events();
+                super::helpers::emit_mirror_outcome(id, kind);
                 if metrics_enabled() {
                     let snap = tl_snapshot();
                     if let Some(t) = snap.timing {
@@ -64,6 +65,7 @@ impl TaskRegistry {
                                 ip_source: snap.ip_source.clone(),
                                 ip_latency_ms: snap.ip_latency_ms,
                                 ip_selection_stage: snap.ip_strategy.map(|s| s.to_string()),
+                                http_version: snap.http_version.map(|s| s.to_string()),
                             },
                         ));
                     }
@@ -118,6 +120,7 @@ impl TaskRegistry {
                     bytes: None,
                     total_hint: None,
                     retried_times: None,
+                    throttle: None,
                 };
                 emit_all(app_ref, EV_PROGRESS, &prog);
                 if let Some(hook) = &progress_hook {
@@ -150,6 +153,7 @@ impl TaskRegistry {
             let mut retry_plan: crate::core::tasks::retry::RetryPlan =
                 global_cfg.retry.clone().into();
             let mut applied_codes: Vec<String> = vec![];
+            let mut bandwidth_override: Option<u64> = None;
             if let Some(raw) = strategy_override.clone() {
                 use crate::core::git::default_impl::opts::parse_strategy_override;
                 match parse_strategy_override(Some(raw)) {
@@ -244,6 +248,12 @@ impl TaskRegistry {
                                 }
                                 retry_plan = plan_new;
                             }
+                            if let Some(limit) =
+                                parsed.bandwidth.as_ref().and_then(|b| b.limit_kib_per_sec)
+                            {
+                                bandwidth_override = Some(limit);
+                                applied_codes.push("bandwidth_strategy_override_applied".into());
+                            }
                             tracing::info!(
                                 target = "strategy",
                                 kind = "push",
@@ -301,6 +311,15 @@ impl TaskRegistry {
             }
 
             let plan = retry_plan;
+            // 限速作用域覆盖整个任务（含重试）；批量任务的子任务适用时间窗口，任务取消时中断等待
+            let _throttle = crate::core::git::transport::enter_throttle_scope(
+                &global_cfg.bandwidth,
+                id,
+                "GitPush",
+                bandwidth_override,
+                this.parent_of(&id).is_some(),
+            )
+            .cancel_on(token.clone());
             let mut attempt: u32 = 0;
             let upload_started = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
             loop {
@@ -368,6 +387,7 @@ impl TaskRegistry {
                                     bytes: p.bytes,
                                     total_hint: p.total_hint,
                                     retried_times: None,
+                                    throttle: crate::core::git::transport::tl_throttle_info(),
                                 };
                                 emit_all(app_ref, EV_PROGRESS, &prog);
                                 if let Some(hook) = &hook_for_cb {
@@ -412,6 +432,7 @@ impl TaskRegistry {
                                 bytes: None,
                                 total_hint: None,
                                 retried_times: None,
+                                throttle: None,
                             };
                             emit_all(app_ref, EV_PROGRESS, &prog);
                             if let Some(hook) = &progress_hook {
@@ -463,6 +484,7 @@ impl TaskRegistry {
                                     bytes: None,
                                     total_hint: None,
                                     retried_times: Some(attempt),
+                                    throttle: None,
                                 };
                                 emit_all(app_ref, EV_PROGRESS, &prog);
                                 if let Some(hook) = &progress_hook {
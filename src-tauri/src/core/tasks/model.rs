@@ -222,6 +222,9 @@ pub struct TaskProgressEvent {
     /// MP1.4: 可选的重试计数（仅在重试事件中出现）
     #[serde(skip_serializing_if = "Option::is_none")]
     pub retried_times: Option<u32>,
+    /// 当前生效的传输限速（仅在 git 传输进度事件中出现）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub throttle: Option<crate::core::git::transport::ThrottleInfo>,
 }
 
 /// MP1.5: 标准化错误事件负载
@@ -256,6 +259,7 @@ impl TaskErrorEvent {
                 ErrorCategory::Network => "Network".into(),
                 ErrorCategory::Tls => "Tls".into(),
                 ErrorCategory::Verify => "Verify".into(),
+                ErrorCategory::ClientCert => "ClientCert".into(),
                 ErrorCategory::Protocol => "Protocol".into(),
                 ErrorCategory::Proxy => "Proxy".into(),
                 ErrorCategory::Auth => "Auth".into(),
@@ -366,6 +366,7 @@ impl TaskRegistry {
                         bytes: None,
                         total_hint: None,
                         retried_times: None,
+                        throttle: None,
                     };
                     emit_all(app_ref, EV_PROGRESS, &prog);
                 }
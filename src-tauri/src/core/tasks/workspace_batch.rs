@@ -549,6 +549,7 @@ fn emit_parent_progress(
             bytes: None,
             total_hint: Some(snapshot.total as u64),
             retried_times: None,
+            throttle: None,
         };
         emit_all(app_ref, EV_PROGRESS, &event);
     }
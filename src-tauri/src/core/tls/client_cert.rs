@@ -0,0 +1,226 @@
+//! mTLS 客户端证书：按主机模式选择证书，加载 PEM / PKCS#12，并挂到 rustls `ClientConfig` 上。
+//!
+//! 私钥可以放在加密凭证库中（见 [`ClientCertCfg::key_credential_host`]），凭证库由应用在
+//! 初始化或解锁后通过 [`set_key_store`] 注册。加载失败与服务端拒绝证书的错误消息均带有
+//! `client certificate` 前缀，由 `map_git2_error` 归类为 `ErrorCategory::ClientCert`。
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+
+use rustls::client::ResolvesClientCert;
+use rustls::sign::{any_supported_type, CertifiedKey};
+use rustls::{Certificate, ClientConfig, PrivateKey, SignatureScheme};
+
+use crate::core::config::model::{ClientCertCfg, TlsCfg};
+use crate::core::credential::CredentialStore;
+use crate::core::tls::util::match_domain;
+
+/// 错误消息前缀，供错误分类识别
+pub const CLIENT_CERT_ERROR_PREFIX: &str = "client certificate";
+
+/// 服务端拒绝客户端证书时会发出的 TLS 告警
+const REJECTION_ALERTS: &[&str] = &[
+    "CertificateRequired",
+    "BadCertificate",
+    "UnsupportedCertificate",
+    "CertificateRevoked",
+    "CertificateExpired",
+    "CertificateUnknown",
+    "UnknownCA",
+];
+
+fn key_store() -> &'static RwLock<Option<Arc<dyn CredentialStore>>> {
+    static STORE: OnceLock<RwLock<Option<Arc<dyn CredentialStore>>>> = OnceLock::new();
+    STORE.get_or_init(|| RwLock::new(None))
+}
+
+/// 注册（或清除）保存客户端私钥的凭证库；会清空已加载的证书缓存。
+pub fn set_key_store(store: Option<Arc<dyn CredentialStore>>) {
+    if let Ok(mut guard) = key_store().write() {
+        *guard = store;
+    }
+    reset_client_cert_cache();
+}
+
+fn cache() -> &'static Mutex<HashMap<ClientCertCfg, Arc<CertifiedKey>>> {
+    static CACHE: OnceLock<Mutex<HashMap<ClientCertCfg, Arc<CertifiedKey>>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 清空证书缓存；证书文件或凭证库内容变化后调用。
+pub fn reset_client_cert_cache() {
+    if let Ok(mut m) = cache().lock() {
+        m.clear();
+    }
+}
+
+/// 按主机选择客户端证书配置（首个匹配项）
+pub fn select_client_cert<'a>(tls: &'a TlsCfg, host: &str) -> Option<&'a ClientCertCfg> {
+    tls.client_certs
+        .iter()
+        .find(|c| c.hosts.iter().any(|p| match_domain(p.trim(), host)))
+}
+
+fn is_pkcs12(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| matches!(e.to_ascii_lowercase().as_str(), "p12" | "pfx"))
+        .unwrap_or(false)
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, String> {
+    fs::read(path).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+/// 从凭证库读取私钥文本或 PKCS#12 口令；未配置凭证主机时返回 `None`。
+fn stored_secret(cfg: &ClientCertCfg) -> Result<Option<String>, String> {
+    let Some(host) = cfg.key_credential_host.as_deref() else {
+        return Ok(None);
+    };
+    let guard = key_store()
+        .read()
+        .map_err(|_| "credential store lock poisoned".to_string())?;
+    let store = guard
+        .as_ref()
+        .ok_or_else(|| "credential store is not available (locked?)".to_string())?;
+    let cred = store
+        .get(host, cfg.key_credential_username.as_deref())
+        .map_err(|e| format!("credential store: {e}"))?
+        .ok_or_else(|| format!("no credential stored for {host}"))?;
+    Ok(Some(cred.password_or_token))
+}
+
+fn first_pem_key(pem: &[u8]) -> Result<Option<PrivateKey>, String> {
+    let items = rustls_pemfile::read_all(&mut BufReader::new(pem)).map_err(|e| e.to_string())?;
+    Ok(items.into_iter().find_map(|item| match item {
+        rustls_pemfile::Item::PKCS8Key(k)
+        | rustls_pemfile::Item::RSAKey(k)
+        | rustls_pemfile::Item::ECKey(k) => Some(PrivateKey(k)),
+        _ => None,
+    }))
+}
+
+fn load_pem(cfg: &ClientCertCfg, path: &Path) -> Result<(Vec<Certificate>, PrivateKey), String> {
+    let pem = read_file(path)?;
+    let certs: Vec<Certificate> = rustls_pemfile::certs(&mut BufReader::new(pem.as_slice()))
+        .map_err(|e| format!("{}: {e}", path.display()))?
+        .into_iter()
+        .map(Certificate)
+        .collect();
+    if certs.is_empty() {
+        return Err(format!("{}: no certificate found", path.display()));
+    }
+    let key = if let Some(key_path) = cfg.key_path.as_deref().filter(|p| !p.trim().is_empty()) {
+        let key_path = Path::new(key_path.trim());
+        first_pem_key(&read_file(key_path)?).map_err(|e| format!("{}: {e}", key_path.display()))?
+    } else if let Some(secret) = stored_secret(cfg)? {
+        first_pem_key(secret.as_bytes()).map_err(|e| format!("stored key: {e}"))?
+    } else {
+        first_pem_key(&pem).map_err(|e| format!("{}: {e}", path.display()))?
+    };
+    let key = key.ok_or_else(|| format!("{}: no private key found", path.display()))?;
+    Ok((certs, key))
+}
+
+fn load_pkcs12(cfg: &ClientCertCfg, path: &Path) -> Result<(Vec<Certificate>, PrivateKey), String> {
+    let der = read_file(path)?;
+    let pass = stored_secret(cfg)?.unwrap_or_default();
+    let parsed = openssl::pkcs12::Pkcs12::from_der(&der)
+        .and_then(|p| p.parse2(&pass))
+        .map_err(|e| format!("{}: {e}", path.display()))?;
+    let leaf = parsed
+        .cert
+        .ok_or_else(|| format!("{}: no certificate found", path.display()))?;
+    let pkey = parsed
+        .pkey
+        .ok_or_else(|| format!("{}: no private key found", path.display()))?;
+    let mut certs = vec![Certificate(leaf.to_der().map_err(|e| e.to_string())?)];
+    if let Some(chain) = parsed.ca {
+        for ca in chain.iter() {
+            certs.push(Certificate(ca.to_der().map_err(|e| e.to_string())?));
+        }
+    }
+    let key = PrivateKey(pkey.private_key_to_pkcs8().map_err(|e| e.to_string())?);
+    Ok((certs, key))
+}
+
+/// 加载一张客户端证书（不使用缓存）。
+pub fn load_client_identity(cfg: &ClientCertCfg) -> Result<Arc<CertifiedKey>, String> {
+    let path = Path::new(cfg.cert_path.trim());
+    let (certs, key) = if is_pkcs12(path) {
+        load_pkcs12(cfg, path)?
+    } else {
+        load_pem(cfg, path)?
+    };
+    let signing_key =
+        any_supported_type(&key).map_err(|e| format!("unsupported private key: {e}"))?;
+    Ok(Arc::new(CertifiedKey::new(certs, signing_key)))
+}
+
+/// 读取（并缓存）主机对应的客户端证书；未配置时返回 `Ok(None)`。
+pub fn client_identity_for(tls: &TlsCfg, host: &str) -> Result<Option<Arc<CertifiedKey>>, String> {
+    let Some(cfg) = select_client_cert(tls, host) else {
+        return Ok(None);
+    };
+    if let Some(hit) = cache().lock().ok().and_then(|m| m.get(cfg).cloned()) {
+        return Ok(Some(hit));
+    }
+    let loaded = load_client_identity(cfg).map_err(|e| {
+        tracing::warn!(target = "tls", host = %host, cert = %cfg.cert_path, error = %e, "client certificate not loaded");
+        format!("{CLIENT_CERT_ERROR_PREFIX} for {host} not loaded: {e}")
+    })?;
+    if let Ok(mut m) = cache().lock() {
+        m.insert(cfg.clone(), Arc::clone(&loaded));
+    }
+    Ok(Some(loaded))
+}
+
+/// 固定返回同一张证书；签名算法与服务端要求不匹配时不出示证书。
+struct StaticClientCert(Arc<CertifiedKey>);
+
+impl ResolvesClientCert for StaticClientCert {
+    fn resolve(
+        &self,
+        _acceptable_issuers: &[&[u8]],
+        sigschemes: &[SignatureScheme],
+    ) -> Option<Arc<CertifiedKey>> {
+        self.0
+            .key
+            .choose_scheme(sigschemes)
+            .map(|_| Arc::clone(&self.0))
+    }
+
+    fn has_certs(&self) -> bool {
+        true
+    }
+}
+
+/// 为 `host` 挂上匹配的客户端证书；返回是否配置了证书。
+pub fn apply_client_cert(cfg: &mut ClientConfig, tls: &TlsCfg, host: &str) -> Result<bool, String> {
+    match client_identity_for(tls, host)? {
+        Some(key) => {
+            cfg.client_auth_cert_resolver = Arc::new(StaticClientCert(key));
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// 握手错误是否为服务端拒绝（或要求）客户端证书
+pub fn is_client_cert_rejection(err: &str) -> bool {
+    err.find("received fatal alert: ")
+        .map(|i| &err[i + "received fatal alert: ".len()..])
+        .is_some_and(|alert| REJECTION_ALERTS.iter().any(|a| alert.starts_with(a)))
+}
+
+/// 握手错误描述：服务端拒绝客户端证书时加上可归类的前缀。
+pub fn describe_handshake_error(host: &str, err: &str) -> String {
+    if is_client_cert_rejection(err) {
+        format!("{CLIENT_CERT_ERROR_PREFIX} rejected by {host}: {err}")
+    } else {
+        err.to_string()
+    }
+}
@@ -1,3 +1,6 @@
+pub mod client_cert;
+pub mod pin_policy;
 pub mod spki;
+pub mod trust;
 pub mod util;
 pub mod verifier;
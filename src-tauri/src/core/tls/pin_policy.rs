@@ -0,0 +1,215 @@
+//! 按主机的 SPKI Pin 策略：主 / 备 pin、过期日与仅报告模式。
+//!
+//! 策略匹配证书链中任意一张证书的 SPKI（叶证书或中间 CA），因此可以把上级 CA 作为备用 pin
+//! 以平滑轮换叶证书密钥。策略过期后不再阻断连接，只发送 `CertFpPinMismatch` 报告，避免过期
+//! pin 把主机“锁死”。[`capture_spki_fingerprints`] 通过一次真实握手抓取当前证书链指纹，用于初始化 pin。
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use rustls::{Certificate, Error as TlsError, ServerName};
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use tokio_rustls::TlsConnector;
+
+use crate::core::config::model::{SpkiPinPolicy, TlsCfg};
+use crate::core::tls::client_cert::apply_client_cert;
+use crate::core::tls::spki::{compute_fingerprint_bundle, compute_spki_sha256_b64};
+use crate::core::tls::util::match_domain;
+use crate::core::tls::verifier::{create_client_config, validate_pins};
+
+/// 按主机选择 pin 策略（首个匹配项）
+pub fn select_pin_policy<'a>(
+    policies: &'a [SpkiPinPolicy],
+    host: &str,
+) -> Option<&'a SpkiPinPolicy> {
+    policies
+        .iter()
+        .find(|p| p.hosts.iter().any(|h| match_domain(h.trim(), host)))
+}
+
+/// 解析过期日：RFC 3339 时间点，或 `YYYY-MM-DD`（当天结束时过期）。
+pub fn parse_expiry(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(ts) = DateTime::parse_from_rfc3339(value) {
+        return Some(ts.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.succ_opt())
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|dt| dt.and_utc())
+}
+
+/// 策略是否已过期；无法解析的过期日按已过期处理（只报告，不阻断）。
+pub fn policy_expired(policy: &SpkiPinPolicy, now: DateTime<Utc>) -> bool {
+    match policy
+        .expires_at
+        .as_deref()
+        .filter(|v| !v.trim().is_empty())
+    {
+        Some(v) => parse_expiry(v).is_none_or(|exp| now >= exp),
+        None => false,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PinVerdict {
+    Match,
+    /// 不匹配；`enforced` 为假时仅报告
+    Mismatch {
+        enforced: bool,
+    },
+    /// pin 非法或为空，本次连接不校验
+    Disabled,
+}
+
+/// 以证书链 SPKI 指纹评估策略
+pub fn evaluate_pin_policy(
+    policy: &SpkiPinPolicy,
+    chain_spki: &[String],
+    now: DateTime<Utc>,
+) -> PinVerdict {
+    let pins: Vec<String> = policy
+        .primary
+        .iter()
+        .chain(policy.backup.iter())
+        .cloned()
+        .collect();
+    let Some(valid) = validate_pins(&pins).filter(|p| !p.is_empty()) else {
+        return PinVerdict::Disabled;
+    };
+    if chain_spki.iter().any(|s| valid.contains(s)) {
+        return PinVerdict::Match;
+    }
+    PinVerdict::Mismatch {
+        enforced: !policy.report_only && !policy_expired(policy, now),
+    }
+}
+
+/// 在证书链验证通过后执行策略：不匹配时发送事件，强制模式下返回 `cert_fp_pin_mismatch`。
+pub fn check_pin_policy(
+    policy: &SpkiPinPolicy,
+    host: &str,
+    end_entity: &Certificate,
+    intermediates: &[Certificate],
+) -> Result<(), TlsError> {
+    let chain_spki: Vec<String> = std::iter::once(end_entity)
+        .chain(intermediates.iter())
+        .map(|c| compute_spki_sha256_b64(c).0)
+        .collect();
+    let pin_count = (policy.primary.len() + policy.backup.len()).min(u8::MAX as usize) as u8;
+    match evaluate_pin_policy(policy, &chain_spki, Utc::now()) {
+        PinVerdict::Match => {
+            tracing::debug!(target="git.transport", host=%host, pin_enforced="policy", pin_count=%pin_count, "pin_match");
+            Ok(())
+        }
+        PinVerdict::Disabled => {
+            tracing::warn!(target="git.transport", host=%host, pin_enforced="off", reason="invalid_pins", "pin_disabled_this_conn");
+            Ok(())
+        }
+        PinVerdict::Mismatch { enforced } => {
+            let leaf_spki = chain_spki[0].clone();
+            tracing::warn!(target="git.transport", host=%host, pin_enforced=%enforced, pin_count=%pin_count, cert_spki=%leaf_spki, "pin_mismatch");
+            use crate::events::structured::{
+                publish_global, Event as StructuredEvent, StrategyEvent as StructuredStrategyEvent,
+            };
+            publish_global(StructuredEvent::Strategy(
+                StructuredStrategyEvent::CertFpPinMismatch {
+                    id: host.to_string(),
+                    host: host.to_string(),
+                    spki_sha256: leaf_spki,
+                    pin_count,
+                    report_only: !enforced,
+                },
+            ));
+            if enforced {
+                Err(TlsError::General("cert_fp_pin_mismatch".into()))
+            } else {
+                Ok(())
+            }
+        }
+    }
+}
+
+/// 抓取到的证书链中一张证书的指纹
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CapturedSpki {
+    /// 0 为叶证书，其余为服务端发送的中间证书
+    pub position: usize,
+    pub subject: String,
+    pub issuer: String,
+    pub spki_sha256: String,
+    pub cert_sha256: String,
+    /// 证书到期时间（RFC 3339）
+    pub not_after: Option<String>,
+}
+
+fn describe_cert(position: usize, cert: &Certificate) -> CapturedSpki {
+    let bundle = compute_fingerprint_bundle(cert);
+    let parsed = x509_parser::parse_x509_certificate(&cert.0).ok();
+    CapturedSpki {
+        position,
+        subject: parsed
+            .as_ref()
+            .map(|(_, c)| c.subject().to_string())
+            .unwrap_or_default(),
+        issuer: parsed
+            .as_ref()
+            .map(|(_, c)| c.issuer().to_string())
+            .unwrap_or_default(),
+        spki_sha256: bundle.spki_sha256,
+        cert_sha256: bundle.cert_sha256,
+        not_after: parsed.as_ref().and_then(|(_, c)| {
+            DateTime::from_timestamp(c.validity().not_after.timestamp(), 0).map(|t| t.to_rfc3339())
+        }),
+    }
+}
+
+/// 以真实 SNI 直连 `host:port` 完成一次握手，返回证书链各证书的 SPKI 指纹，并写入证书指纹日志。
+///
+/// 证书链仍按公共根 / 自定义 CA 校验，但不执行任何 pin，便于在 pin 失配后重新采集。
+pub async fn capture_spki_fingerprints(
+    tls: &TlsCfg,
+    host: &str,
+    port: u16,
+    limit: Duration,
+) -> Result<Vec<CapturedSpki>> {
+    let mut unpinned = tls.clone();
+    unpinned.spki_pins.clear();
+    unpinned.pin_policies.clear();
+    let mut cfg = create_client_config(&unpinned);
+    apply_client_cert(&mut cfg, &unpinned, host).map_err(|e| anyhow!(e))?;
+    let server_name =
+        ServerName::try_from(host).map_err(|_| anyhow!("invalid hostname: {host}"))?;
+    let tcp = timeout(limit, TcpStream::connect((host, port)))
+        .await
+        .context("tcp connect timeout")?
+        .context("tcp connect failed")?;
+    let stream = timeout(
+        limit,
+        TlsConnector::from(Arc::new(cfg)).connect(server_name, tcp),
+    )
+    .await
+    .context("tls handshake timeout")?
+    .context("tls handshake failed")?;
+    let chain = stream
+        .get_ref()
+        .1
+        .peer_certificates()
+        .map(<[_]>::to_vec)
+        .unwrap_or_default();
+    if chain.is_empty() {
+        return Err(anyhow!("server presented no certificate"));
+    }
+    crate::core::git::transport::record_certificate(host, &chain);
+    Ok(chain
+        .iter()
+        .enumerate()
+        .map(|(i, c)| describe_cert(i, c))
+        .collect())
+}
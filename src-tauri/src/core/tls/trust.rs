@@ -0,0 +1,259 @@
+//! 额外信任锚：自定义 CA（PEM 文件 / 目录）与可选的操作系统证书库。
+//!
+//! 公共根（webpki-roots）始终优先验证；仅当公共根验证失败时，才依次尝试与主机匹配的额外信任作用域。
+//! 额外作用域验证成功时发布 `CustomCaTrusted` 事件，便于安全审查发现被私有 CA 签发的连接
+//! （典型如企业 TLS 检查代理）。
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+
+use rustls::client::{ServerCertVerified, ServerCertVerifier, WebPkiVerifier};
+use rustls::{Certificate, Error as TlsError, RootCertStore, ServerName};
+
+use crate::core::config::model::TlsCfg;
+use crate::core::tls::util::match_domain;
+
+/// 系统证书库作用域的来源标签
+pub const SYSTEM_SOURCE: &str = "system";
+
+/// 一个额外信任作用域：同一来源的证书与生效主机
+pub struct TrustScope {
+    pub source: String,
+    pub hosts: Vec<String>,
+    pub anchors: usize,
+    verifier: WebPkiVerifier,
+}
+
+impl TrustScope {
+    pub fn applies_to(&self, host: &str) -> bool {
+        self.hosts.is_empty() || self.hosts.iter().any(|p| match_domain(p.trim(), host))
+    }
+}
+
+/// 从配置加载出的全部额外信任作用域；加载问题记录在 `errors` 中而不中断连接。
+#[derive(Default)]
+pub struct CustomTrust {
+    pub scopes: Vec<TrustScope>,
+    pub errors: Vec<String>,
+}
+
+impl CustomTrust {
+    pub fn is_empty(&self) -> bool {
+        self.scopes.is_empty()
+    }
+}
+
+fn is_cert_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| matches!(e.to_ascii_lowercase().as_str(), "pem" | "crt" | "cer"))
+        .unwrap_or(false)
+}
+
+pub(crate) fn pem_files(path: &Path) -> std::io::Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut files: Vec<PathBuf> = fs::read_dir(path)?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_file() && is_cert_file(p))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+pub(crate) fn add_pem_file(store: &mut RootCertStore, file: &Path) -> Result<usize, String> {
+    let f = fs::File::open(file).map_err(|e| format!("{}: {e}", file.display()))?;
+    let ders = rustls_pemfile::certs(&mut BufReader::new(f))
+        .map_err(|e| format!("{}: {e}", file.display()))?;
+    let mut added = 0;
+    for der in ders {
+        store
+            .add(&Certificate(der))
+            .map_err(|e| format!("{}: {e}", file.display()))?;
+        added += 1;
+    }
+    Ok(added)
+}
+
+fn scope_from_store(source: String, hosts: Vec<String>, store: RootCertStore) -> TrustScope {
+    TrustScope {
+        source,
+        hosts,
+        anchors: store.len(),
+        verifier: WebPkiVerifier::new(store, None),
+    }
+}
+
+/// 按配置读取额外信任锚（不使用缓存）。
+pub fn load_custom_trust(tls: &TlsCfg) -> CustomTrust {
+    let mut out = CustomTrust::default();
+    for ca in &tls.custom_cas {
+        let path = Path::new(ca.path.trim());
+        let mut store = RootCertStore::empty();
+        let errors_before = out.errors.len();
+        match pem_files(path) {
+            Ok(files) => {
+                for file in files {
+                    if let Err(e) = add_pem_file(&mut store, &file) {
+                        out.errors.push(e);
+                    }
+                }
+            }
+            Err(e) => out.errors.push(format!("{}: {e}", path.display())),
+        }
+        if store.is_empty() {
+            if out.errors.len() == errors_before {
+                out.errors
+                    .push(format!("{}: no usable certificate", path.display()));
+            }
+            continue;
+        }
+        out.scopes
+            .push(scope_from_store(ca.path.clone(), ca.hosts.clone(), store));
+    }
+    if tls.use_system_trust_store {
+        match rustls_native_certs::load_native_certs() {
+            Ok(certs) => {
+                let mut store = RootCertStore::empty();
+                let ders: Vec<Vec<u8>> = certs.into_iter().map(|c| c.0).collect();
+                let (_, ignored) = store.add_parsable_certificates(&ders);
+                if ignored > 0 {
+                    tracing::debug!(
+                        target = "tls",
+                        ignored,
+                        "system trust store: unparsable certificates skipped"
+                    );
+                }
+                if store.is_empty() {
+                    out.errors.push("system trust store is empty".into());
+                } else {
+                    out.scopes
+                        .push(scope_from_store(SYSTEM_SOURCE.into(), Vec::new(), store));
+                }
+            }
+            Err(e) => out.errors.push(format!("system trust store: {e}")),
+        }
+    }
+    for e in &out.errors {
+        tracing::warn!(target = "tls", error = %e, "custom trust anchor not loaded");
+    }
+    out
+}
+
+type TrustCacheKey = (Vec<(String, Vec<String>)>, bool);
+
+fn cache() -> &'static Mutex<HashMap<TrustCacheKey, Arc<CustomTrust>>> {
+    static CACHE: OnceLock<Mutex<HashMap<TrustCacheKey, Arc<CustomTrust>>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 读取（并缓存）配置对应的额外信任锚；CA 文件内容变化后需调用 [`reset_custom_trust_cache`]。
+pub fn custom_trust(tls: &TlsCfg) -> Arc<CustomTrust> {
+    if tls.custom_cas.is_empty() && !tls.use_system_trust_store {
+        return Arc::new(CustomTrust::default());
+    }
+    let key: TrustCacheKey = (
+        tls.custom_cas
+            .iter()
+            .map(|c| (c.path.clone(), c.hosts.clone()))
+            .collect(),
+        tls.use_system_trust_store,
+    );
+    if let Some(hit) = cache().lock().ok().and_then(|m| m.get(&key).cloned()) {
+        return hit;
+    }
+    let loaded = Arc::new(load_custom_trust(tls));
+    if let Ok(mut m) = cache().lock() {
+        m.insert(key, Arc::clone(&loaded));
+    }
+    loaded
+}
+
+pub fn reset_custom_trust_cache() {
+    if let Ok(mut m) = cache().lock() {
+        m.clear();
+    }
+}
+
+fn issuer_of(end_entity: &Certificate) -> String {
+    x509_parser::parse_x509_certificate(&end_entity.0)
+        .map(|(_, cert)| cert.issuer().to_string())
+        .unwrap_or_default()
+}
+
+/// 公共根优先、额外信任作用域兜底的证书验证器
+pub struct ScopedTrustVerifier {
+    public: WebPkiVerifier,
+    trust: Arc<CustomTrust>,
+}
+
+impl ScopedTrustVerifier {
+    pub fn new(public_roots: RootCertStore, trust: Arc<CustomTrust>) -> Self {
+        Self {
+            public: WebPkiVerifier::new(public_roots, None),
+            trust,
+        }
+    }
+}
+
+impl ServerCertVerifier for ScopedTrustVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        server_name: &ServerName,
+        scts: &mut dyn Iterator<Item = &[u8]>,
+        ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> Result<ServerCertVerified, TlsError> {
+        let public_err = match self.public.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            scts,
+            ocsp_response,
+            now,
+        ) {
+            Ok(v) => return Ok(v),
+            Err(e) => e,
+        };
+        let host = match server_name {
+            ServerName::DnsName(n) => n.as_ref().to_string(),
+            ServerName::IpAddress(ip) => ip.to_string(),
+            _ => String::new(),
+        };
+        for scope in self.trust.scopes.iter().filter(|s| s.applies_to(&host)) {
+            let verified = scope.verifier.verify_server_cert(
+                end_entity,
+                intermediates,
+                server_name,
+                &mut std::iter::empty(),
+                ocsp_response,
+                now,
+            );
+            if verified.is_ok() {
+                let issuer = issuer_of(end_entity);
+                tracing::warn!(target = "tls", host = %host, source = %scope.source, issuer = %issuer, "certificate validated by non-public CA");
+                use crate::events::structured::{
+                    publish_global, Event as StructuredEvent,
+                    StrategyEvent as StructuredStrategyEvent,
+                };
+                publish_global(StructuredEvent::Strategy(
+                    StructuredStrategyEvent::CustomCaTrusted {
+                        id: host.clone(),
+                        host: host.clone(),
+                        source: scope.source.clone(),
+                        issuer,
+                    },
+                ));
+                return verified;
+            }
+        }
+        Err(public_err)
+    }
+}
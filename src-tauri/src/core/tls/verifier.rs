@@ -1,7 +1,9 @@
 use std::sync::Arc;
 
-use crate::core::config::model::TlsCfg;
+use crate::core::config::model::{SpkiPinPolicy, TlsCfg};
+use crate::core::tls::pin_policy::{check_pin_policy, select_pin_policy};
 use crate::core::tls::spki::{compute_spki_sha256_b64, SpkiSource};
+use crate::core::tls::trust::{custom_trust, ScopedTrustVerifier};
 use base64::Engine;
 use rustls::client::{ServerCertVerified, ServerCertVerifier};
 use rustls::ClientConfig;
@@ -11,6 +13,8 @@ pub struct RealHostCertVerifier {
     pub inner: Arc<dyn ServerCertVerifier>,
     pub override_host: Option<String>,
     pub spki_pins: Vec<String>,
+    /// 按主机的 pin 策略；匹配的主机忽略全局 `spki_pins`
+    pub pin_policies: Vec<SpkiPinPolicy>,
 }
 
 impl RealHostCertVerifier {
@@ -23,8 +27,14 @@ impl RealHostCertVerifier {
             inner,
             override_host,
             spki_pins,
+            pin_policies: Vec::new(),
         }
     }
+
+    pub fn with_pin_policies(mut self, pin_policies: Vec<SpkiPinPolicy>) -> Self {
+        self.pin_policies = pin_policies;
+        self
+    }
 }
 
 impl ServerCertVerifier for RealHostCertVerifier {
@@ -71,6 +81,17 @@ impl ServerCertVerifier for RealHostCertVerifier {
             )?;
         }
 
+        // 按主机的 pin 策略优先于全局列表
+        let policy_host = match (&self.override_host, server_name) {
+            (Some(h), _) => h.as_str(),
+            (None, ServerName::DnsName(n)) => n.as_ref(),
+            _ => "",
+        };
+        if let Some(policy) = select_pin_policy(&self.pin_policies, policy_host) {
+            check_pin_policy(policy, policy_host, end_entity, intermediates)?;
+            return Ok(ServerCertVerified::assertion());
+        }
+
         // P3.4: SPKI Pin 强校验（若配置非空）。在链与主机名验证成功、白名单通过后执行。
         if !self.spki_pins.is_empty() {
             // 仅当 pin 列表全部合法时才执行；否则视为禁用（记录一次调试日志）。
@@ -98,6 +119,7 @@ impl ServerCertVerifier for RealHostCertVerifier {
                             host: host_to_log.to_string(),
                             spki_sha256: spki_b64.clone(),
                             pin_count,
+                            report_only: false,
                         },
                     ));
                     return Err(TlsError::General("cert_fp_pin_mismatch".into()));
@@ -128,7 +150,8 @@ impl ServerCertVerifier for RealHostCertVerifier {
     }
 }
 
-fn build_root_store() -> RootCertStore {
+/// 公共根（webpki-roots）。额外信任锚按主机作用域单独验证，见 [`crate::core::tls::trust`]。
+pub fn build_root_store() -> RootCertStore {
     let mut root_store = RootCertStore::empty();
     root_store.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|ta| {
         OwnedTrustAnchor::from_subject_spki_name_constraints(
@@ -140,13 +163,34 @@ fn build_root_store() -> RootCertStore {
     root_store
 }
 
-/// 基于白名单验证器创建 rustls ClientConfig（无客户端证书）
-pub fn create_client_config(_tls: &TlsCfg) -> ClientConfig {
-    let cfg = ClientConfig::builder()
+/// 公共根验证器；配置了额外信任锚时换成按主机作用域兜底的验证器。
+fn build_server_verifier(tls: &TlsCfg) -> Arc<dyn ServerCertVerifier> {
+    let trust = custom_trust(tls);
+    if trust.is_empty() {
+        Arc::new(rustls::client::WebPkiVerifier::new(
+            build_root_store(),
+            None,
+        ))
+    } else {
+        Arc::new(ScopedTrustVerifier::new(build_root_store(), trust))
+    }
+}
+
+/// 基于白名单验证器创建 rustls ClientConfig（无客户端证书）。
+/// 真实 SNI 连接只执行按主机的 pin 策略，全局 `spki_pins` 仍仅作用于伪 SNI 握手。
+pub fn create_client_config(tls: &TlsCfg) -> ClientConfig {
+    let mut cfg = ClientConfig::builder()
         .with_safe_defaults()
         .with_root_certificates(build_root_store())
         .with_no_client_auth();
-
+    if !tls.pin_policies.is_empty() {
+        let verifier = RealHostCertVerifier::new(build_server_verifier(tls), None, Vec::new())
+            .with_pin_policies(tls.pin_policies.clone());
+        cfg.dangerous().set_certificate_verifier(Arc::new(verifier));
+    } else if !custom_trust(tls).is_empty() {
+        cfg.dangerous()
+            .set_certificate_verifier(build_server_verifier(tls));
+    }
     cfg
 }
 
@@ -159,15 +203,15 @@ pub fn create_client_config_with_expected_name(tls: &TlsCfg, expected_host: &str
         .with_no_client_auth();
 
     // 构造 Fake SNI 验证器：默认跳过链路校验，仅执行自定义校验（如 SPKI Pin）。
-    let inner = Arc::new(rustls::client::WebPkiVerifier::new(
-        build_root_store(),
-        None,
-    ));
-    let verifier = Arc::new(RealHostCertVerifier::new(
-        inner,
-        Some(expected_host.to_string()),
-        tls.spki_pins.clone(),
-    ));
+    let inner = build_server_verifier(tls);
+    let verifier = Arc::new(
+        RealHostCertVerifier::new(
+            inner,
+            Some(expected_host.to_string()),
+            tls.spki_pins.clone(),
+        )
+        .with_pin_policies(tls.pin_policies.clone()),
+    );
     cfg.dangerous().set_certificate_verifier(verifier);
     cfg
 }
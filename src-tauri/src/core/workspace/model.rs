@@ -6,6 +6,8 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
 
+use crate::core::credential::binding::{normalize_remote, CredentialBinding};
+
 /// 工作区结构
 ///
 /// 表示一个包含多个相关仓库的工作区
@@ -56,6 +58,9 @@ pub struct RepositoryEntry {
     /// 自定义配置（可继承工作区配置）
     #[serde(default)]
     pub custom_config: HashMap<String, serde_json::Value>,
+    /// 绑定的凭证身份（优先于凭证配置中的 URL 规则）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credential: Option<CredentialBinding>,
 }
 
 fn default_branch() -> String {
@@ -177,6 +182,14 @@ impl Workspace {
         self.repositories.iter_mut().find(|r| r.id == id)
     }
 
+    /// 按远程 URL 查找仓库（忽略协议、用户信息与 `.git` 后缀差异）
+    pub fn find_repository_by_remote(&self, remote_url: &str) -> Option<&RepositoryEntry> {
+        let target = normalize_remote(remote_url);
+        self.repositories
+            .iter()
+            .find(|r| normalize_remote(&r.remote_url) == target)
+    }
+
     /// 获取所有仓库
     pub fn get_repositories(&self) -> &[RepositoryEntry] {
         &self.repositories
@@ -230,6 +243,7 @@ impl RepositoryEntry {
             enabled: default_enabled(),
             has_submodules: false,
             custom_config: HashMap::new(),
+            credential: None,
         }
     }
 
@@ -1,32 +1,63 @@
+use std::sync::Arc;
+
 use serde::Serialize;
 
+/// Headless event listener: receives `(event_name, payload)` for every emitted event.
+pub type EventListener = Arc<dyn Fn(&str, &serde_json::Value) + Send + Sync>;
+
 /// AppHandle wrapper that provides a unified interface for event emission.
 ///
 /// With `tauri-core` feature: wraps `tauri::AppHandle<Wry>` and emits via Tauri
 /// Without `tauri-core`: no-op placeholder that silently drops events
+///
+/// A headless handle (see [`AppHandle::headless`]) forwards events to a listener
+/// instead, so non-GUI callers such as the `fwc` CLI receive the same
+/// `task://state` / `task://progress` / `task://error` payloads.
 #[derive(Clone)]
 pub struct AppHandle {
     #[cfg(feature = "tauri-app")]
-    inner: tauri::AppHandle,
+    inner: Option<tauri::AppHandle>,
+    listener: Option<EventListener>,
 }
 
 impl AppHandle {
     /// Create from a real Tauri AppHandle
     #[cfg(feature = "tauri-app")]
     pub fn from_tauri(handle: tauri::AppHandle) -> Self {
-        Self { inner: handle }
+        Self {
+            inner: Some(handle),
+            listener: None,
+        }
     }
 
     /// Create from anything (placeholder for non-tauri-app modes)
     #[cfg(not(feature = "tauri-app"))]
     pub fn from_tauri<T>(_handle: T) -> Self {
-        Self {}
+        Self { listener: None }
+    }
+
+    /// Create a handle without Tauri that forwards every event to `listener`
+    pub fn headless(listener: EventListener) -> Self {
+        Self {
+            #[cfg(feature = "tauri-app")]
+            inner: None,
+            listener: Some(listener),
+        }
     }
 
-    /// Get the inner Tauri handle (only available with tauri-app feature)
+    /// Get the inner Tauri handle (only available with tauri-app feature; `None` when headless)
     #[cfg(feature = "tauri-app")]
-    pub fn inner(&self) -> &tauri::AppHandle {
-        &self.inner
+    pub fn inner(&self) -> Option<&tauri::AppHandle> {
+        self.inner.as_ref()
+    }
+
+    fn notify_listener<T: Serialize>(&self, event: &str, payload: &T) {
+        if let Some(listener) = &self.listener {
+            match serde_json::to_value(payload) {
+                Ok(value) => listener(event, &value),
+                Err(e) => tracing::warn!(target = "event", "serialize failed: {} -> {}", event, e),
+            }
+        }
     }
 }
 
@@ -34,15 +65,20 @@ impl AppHandle {
 ///
 /// With `tauri-app`: emits via Tauri's event system
 /// Otherwise: silently drops the event (structured event bus handles test assertions)
+/// Headless handles always forward to their listener.
 #[cfg(feature = "tauri-app")]
 pub fn emit_all<T: Serialize + Clone>(app: &AppHandle, event: &str, payload: &T) {
     use tauri::Emitter;
-    if let Err(e) = app.inner.emit(event, payload.clone()) {
-        tracing::warn!(target = "event", "emit failed: {} -> {:?}", event, e);
+    if let Some(inner) = &app.inner {
+        if let Err(e) = inner.emit(event, payload.clone()) {
+            tracing::warn!(target = "event", "emit failed: {} -> {:?}", event, e);
+        }
     }
+    app.notify_listener(event, payload);
 }
 
 #[cfg(not(feature = "tauri-app"))]
-pub fn emit_all<T: Serialize + Clone>(_app: &AppHandle, _event: &str, _payload: &T) {
-    // Silently drop - structured event bus handles test assertions
+pub fn emit_all<T: Serialize + Clone>(app: &AppHandle, event: &str, payload: &T) {
+    // Silently drop for Tauri - structured event bus handles test assertions
+    app.notify_listener(event, payload);
 }
@@ -92,6 +92,14 @@ pub enum StrategyEvent {
     IpPoolAutoDisable { reason: String, until_ms: i64 },
     /// IP池全局自动恢复事件
     IpPoolAutoEnable {},
+    /// IP 列表订阅刷新结果；失败时保留上一次的有效列表
+    IpPoolSubscriptionUpdate {
+        name: String,
+        success: bool,
+        entries: u32,
+        rejected: u32,
+        error: Option<String>,
+    },
     HttpApplied {
         id: String,
         follow: bool,
@@ -142,6 +150,9 @@ pub enum StrategyEvent {
         ip_latency_ms: Option<u32>,
         #[serde(skip_serializing_if = "Option::is_none")]
         ip_selection_stage: Option<String>,
+        /// 协商出的 HTTP 版本（"HTTP/1.1" / "HTTP/2"）
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        http_version: Option<String>,
     },
     AdaptiveTlsFallback {
         id: String,
@@ -172,6 +183,17 @@ pub enum StrategyEvent {
         host: String,
         spki_sha256: String,
         pin_count: u8,
+        /// 仅报告（策略为 report-only 或已过期）：连接未被中断
+        #[serde(default)]
+        report_only: bool,
+    },
+    /// 证书链未被公共根（webpki-roots）信任，而由自定义 CA / 系统信任库验证通过
+    CustomCaTrusted {
+        id: String,
+        host: String,
+        /// 验证通过的信任来源：CA 文件/目录路径，或 `system`
+        source: String,
+        issuer: String,
     },
     IpPoolSelection {
         id: String,
@@ -218,6 +240,24 @@ pub enum StrategyEvent {
         latency_ms: Option<u32>,
         probe_url: String,
     },
+    /// 上游代理切换事件：首选上游失败后改用 `to`（为空表示全部上游失败，将回落直连）
+    ProxyUpstreamFailover {
+        host: String,
+        from: Option<String>,
+        to: Option<String>,
+        reason: String,
+    },
+    /// 上游代理失败率超过阈值，移出轮换
+    ProxyUpstreamDown {
+        upstream: String,
+        reason: String,
+        failure_rate: f64,
+    },
+    /// 上游代理健康检查连续成功，重新加入轮换
+    ProxyUpstreamRecovered {
+        upstream: String,
+        consecutive_successes: u32,
+    },
     /// 系统代理检测事件
     SystemProxyDetected {
         id: String,
@@ -242,6 +282,29 @@ pub enum StrategyEvent {
         threshold_bytes: u64,
         raw_samples_disabled: bool,
     },
+    /// 镜像：本次任务实际由哪个地址提供服务（`mirror` 为空表示源站）
+    MirrorServed {
+        id: String,
+        kind: String,
+        origin: String,
+        served_by: String,
+        mirror: Option<String>,
+        failover_count: u32,
+        success: bool,
+    },
+    /// 镜像失败后切换到下一个候选
+    MirrorFailover {
+        id: String,
+        kind: String,
+        mirror: String,
+        reason: String,
+    },
+    /// 网络环境配置档切换（`to` 为空表示恢复基线配置）
+    NetworkProfileSwitched {
+        from: Option<String>,
+        to: Option<String>,
+        trigger: String,
+    },
     /// 可观测性层级变化
     ObservabilityLayerChanged {
         from: String,
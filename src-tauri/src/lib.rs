@@ -3,6 +3,7 @@
 
 #[cfg(any(feature = "tauri-app", feature = "tauri-core"))]
 pub mod app;
+pub mod cli;
 pub mod core;
 pub mod events;
 pub mod logging; // 新增：暴露 app 模块供 main.rs 调用
@@ -0,0 +1,326 @@
+//! `fwc` 命令行测试
+//!
+//! 覆盖参数解析、进度行格式化、无界面事件监听，以及基于本地裸仓库的
+//! clone / fetch / workspace 批量克隆端到端执行，以及凭证库的主密码解锁。
+
+use fireworks_collaboration_lib::cli::{
+    format_event, format_progress, parse_args, run, CliCommand, CredentialCommand, IpPoolCommand,
+};
+use fireworks_collaboration_lib::core::config::{loader, model::AppConfig};
+use fireworks_collaboration_lib::core::credential::{
+    config::{CredentialConfig, StorageType},
+    file_store::EncryptedFileStore,
+    Credential, CredentialStore,
+};
+use fireworks_collaboration_lib::core::tasks::model::{TaskProgressEvent, WorkspaceBatchOperation};
+use fireworks_collaboration_lib::core::workspace::{RepositoryEntry, Workspace, WorkspaceStorage};
+use fireworks_collaboration_lib::events::emitter::{emit_all, AppHandle};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tempfile::TempDir;
+use uuid::Uuid;
+
+fn init_origin(root: &Path) -> PathBuf {
+    let work = root.join("seed");
+    let repo = git2::Repository::init(&work).unwrap();
+    std::fs::write(work.join("README.md"), "hello").unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new("README.md")).unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let sig = git2::Signature::now("fwc", "fwc@example.com").unwrap();
+    repo.commit(Some("HEAD"), &sig, &sig, "init", &tree, &[])
+        .unwrap();
+
+    let origin = root.join("origin.git");
+    git2::build::RepoBuilder::new()
+        .bare(true)
+        .clone(work.to_str().unwrap(), &origin)
+        .unwrap();
+    origin
+}
+
+fn args(config_dir: &Path, rest: &[&str]) -> Vec<String> {
+    let mut out = vec![
+        "--config-dir".to_string(),
+        config_dir.to_string_lossy().into_owned(),
+    ];
+    out.extend(rest.iter().map(|s| s.to_string()));
+    out
+}
+
+#[test]
+fn test_parse_clone_with_options() {
+    let opts = parse_args([
+        "clone",
+        "https://github.com/o/r",
+        "./r",
+        "--depth",
+        "1",
+        "--filter=blob:none",
+        "--recurse-submodules",
+        "--json",
+    ])
+    .unwrap();
+    assert!(opts.json, "全局选项可出现在任意位置");
+    let CliCommand::Clone(clone) = opts.command else {
+        panic!("expected clone");
+    };
+    assert_eq!(clone.repo, "https://github.com/o/r");
+    assert_eq!(clone.depth, Some(1));
+    assert_eq!(clone.filter.as_deref(), Some("blob:none"));
+    assert!(clone.recurse_submodules);
+}
+
+#[test]
+fn test_parse_workspace_and_subcommands() {
+    let opts = parse_args([
+        "--config-dir",
+        "/tmp/cfg",
+        "workspace",
+        "push",
+        "ws.json",
+        "--repo",
+        "a",
+        "--repo",
+        "b",
+        "--concurrency",
+        "2",
+        "--use-stored-credential",
+    ])
+    .unwrap();
+    assert_eq!(opts.config_dir, Some(PathBuf::from("/tmp/cfg")));
+    assert_eq!(opts.master_password, None);
+    let CliCommand::Workspace(ws) = opts.command else {
+        panic!("expected workspace");
+    };
+    assert_eq!(ws.operation, WorkspaceBatchOperation::Push);
+    assert_eq!(ws.repo_ids, vec!["a", "b"]);
+    assert_eq!(ws.max_concurrency, Some(2));
+    assert!(ws.use_stored_credential);
+
+    assert_eq!(
+        parse_args(["ip-pool", "pick", "github.com"])
+            .unwrap()
+            .command,
+        CliCommand::IpPool(IpPoolCommand::Pick {
+            host: "github.com".into(),
+            port: 443
+        })
+    );
+    assert_eq!(
+        parse_args(["credential", "remove", "github.com", "alice"])
+            .unwrap()
+            .command,
+        CliCommand::Credential(CredentialCommand::Remove {
+            host: "github.com".into(),
+            username: "alice".into()
+        })
+    );
+    assert_eq!(
+        parse_args(Vec::<String>::new()).unwrap().command,
+        CliCommand::Help
+    );
+}
+
+#[test]
+fn test_parse_master_password_option() {
+    let opts = parse_args(["credential", "list", "--master-password", "s3cret"]).unwrap();
+    assert_eq!(opts.master_password.as_deref(), Some("s3cret"));
+    assert_eq!(
+        opts.command,
+        CliCommand::Credential(CredentialCommand::List)
+    );
+
+    let opts = parse_args(["--master-password=p=w", "proxy", "status"]).unwrap();
+    assert_eq!(opts.master_password.as_deref(), Some("p=w"));
+    assert!(parse_args(["credential", "list", "--master-password"]).is_err());
+}
+
+#[test]
+fn test_parse_rejects_invalid_input() {
+    assert!(parse_args(["clone", "only-repo"]).is_err());
+    assert!(parse_args(["fetch", "./r", "--bogus"]).is_err());
+    assert!(parse_args(["clone", "a", "b", "--depth", "x"]).is_err());
+    assert!(parse_args(["workspace", "pull", "ws.json"]).is_err());
+    assert!(parse_args(["workspace", "fetch", "ws.json", "--concurrency", "0"]).is_err());
+    assert!(parse_args(["push", "./r", "--strategy-override", "{bad"]).is_err());
+    assert!(parse_args(["frobnicate"]).is_err());
+}
+
+#[test]
+fn test_format_progress_line() {
+    let id = Uuid::parse_str("1a2b3c4d-0000-0000-0000-000000000000").unwrap();
+    let line = format_progress(&TaskProgressEvent {
+        task_id: id,
+        kind: "GitClone".into(),
+        phase: "Receiving".into(),
+        percent: 45,
+        objects: Some(120),
+        bytes: Some(4096),
+        total_hint: Some(300),
+        retried_times: Some(1),
+        throttle: None,
+    });
+    assert_eq!(
+        line,
+        "[GitClone 1a2b3c4d] Receiving 45% objects=120/300 bytes=4096 retry=1"
+    );
+}
+
+#[test]
+fn test_headless_app_handle_forwards_events() {
+    let seen: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+    let sink = seen.clone();
+    let app = AppHandle::headless(Arc::new(move |event, payload| {
+        if let Some(line) = format_event(event, payload) {
+            sink.lock().unwrap().push(line);
+        }
+    }));
+
+    let progress = TaskProgressEvent {
+        task_id: Uuid::nil(),
+        kind: "GitFetch".into(),
+        phase: "Fetching".into(),
+        percent: 10,
+        objects: None,
+        bytes: None,
+        total_hint: None,
+        retried_times: None,
+        throttle: None,
+    };
+    emit_all(&app, "task://progress", &progress);
+    emit_all(&app, "custom://ignored", &progress);
+
+    assert_eq!(
+        *seen.lock().unwrap(),
+        vec!["[GitFetch 00000000] Fetching 10%".to_string()]
+    );
+}
+
+#[test]
+fn test_run_clone_and_fetch_local_repo() {
+    let tmp = TempDir::new().unwrap();
+    let origin = init_origin(tmp.path());
+    let config_dir = tmp.path().join("config");
+    let dest = tmp.path().join("clone");
+
+    let code = run(parse_args(args(
+        &config_dir,
+        &["clone", origin.to_str().unwrap(), dest.to_str().unwrap()],
+    ))
+    .unwrap())
+    .unwrap();
+    assert_eq!(code, 0);
+    assert!(dest.join("README.md").exists());
+    assert!(
+        config_dir.join("config/config.json").exists(),
+        "应复用配置加载器初始化配置"
+    );
+
+    let code =
+        run(parse_args(args(&config_dir, &["fetch", dest.to_str().unwrap()])).unwrap()).unwrap();
+    assert_eq!(code, 0);
+
+    let missing = tmp.path().join("missing.git");
+    let code = run(parse_args(args(
+        &config_dir,
+        &[
+            "clone",
+            missing.to_str().unwrap(),
+            tmp.path().join("never").to_str().unwrap(),
+        ],
+    ))
+    .unwrap())
+    .unwrap();
+    assert_eq!(code, 1, "失败任务应返回非零退出码");
+}
+
+#[test]
+fn test_run_workspace_batch_clone() {
+    let tmp = TempDir::new().unwrap();
+    let origin = init_origin(tmp.path());
+    let config_dir = tmp.path().join("config");
+
+    let mut workspace = Workspace::new("ci".into(), tmp.path().join("ws"));
+    for id in ["one", "two"] {
+        workspace
+            .add_repository(RepositoryEntry::new(
+                id.into(),
+                id.into(),
+                PathBuf::from(id),
+                origin.to_string_lossy().into_owned(),
+            ))
+            .unwrap();
+    }
+    let ws_file = tmp.path().join("workspace.json");
+    WorkspaceStorage::new(ws_file.clone())
+        .save(&workspace)
+        .unwrap();
+
+    let code = run(parse_args(args(
+        &config_dir,
+        &["workspace", "clone", ws_file.to_str().unwrap()],
+    ))
+    .unwrap())
+    .unwrap();
+    assert_eq!(code, 0);
+    assert!(tmp.path().join("ws/one/README.md").exists());
+    assert!(tmp.path().join("ws/two/README.md").exists());
+}
+
+fn write_credential_config(config_dir: &Path, credential: CredentialConfig) {
+    let mut cfg = AppConfig::default();
+    cfg.credential = credential;
+    loader::save_at(&cfg, config_dir).unwrap();
+}
+
+#[test]
+fn test_credential_commands_unlock_file_store_with_master_password() {
+    let tmp = TempDir::new().unwrap();
+    let config_dir = tmp.path().join("config");
+    let file = tmp.path().join("creds.enc");
+    let cred_config = CredentialConfig::new()
+        .with_storage(StorageType::File)
+        .with_file_path(file.to_string_lossy().into_owned());
+    write_credential_config(&config_dir, cred_config.clone());
+
+    let seeded = EncryptedFileStore::new(&cred_config).unwrap();
+    seeded.set_master_password("correct".into()).unwrap();
+    seeded
+        .add(Credential::new(
+            "github.com".into(),
+            "alice".into(),
+            "ghp_token".into(),
+        ))
+        .unwrap();
+
+    let list = |password: &str| {
+        run(parse_args(args(
+            &config_dir,
+            &["--master-password", password, "credential", "list"],
+        ))
+        .unwrap())
+    };
+    assert_eq!(list("correct").unwrap(), 0);
+    let err = list("wrong").unwrap_err();
+    assert!(
+        format!("{err:#}").contains("unlock"),
+        "错误的主密码应在打开凭证库时报错: {err:#}"
+    );
+}
+
+#[test]
+fn test_credential_commands_refuse_memory_only_store() {
+    let tmp = TempDir::new().unwrap();
+    let config_dir = tmp.path().join("config");
+    write_credential_config(
+        &config_dir,
+        CredentialConfig::new().with_storage(StorageType::Memory),
+    );
+
+    let err = run(parse_args(args(&config_dir, &["credential", "list"])).unwrap()).unwrap_err();
+    assert!(
+        format!("{err:#}").contains("does not persist"),
+        "仅内存存储应显式报错: {err:#}"
+    );
+}
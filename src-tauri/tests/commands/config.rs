@@ -135,7 +135,8 @@ async fn test_export_team_config_template() {
     let temp = tempfile::tempdir().unwrap();
     let (app, _, _) = create_mock_app(temp.path().to_path_buf());
 
-    let result = export_team_config_template(None, None, app.state(), app.state()).await;
+    let result =
+        export_team_config_template(None, None, app.state(), app.state(), app.state()).await;
 
     assert!(result.is_ok());
     let path = result.unwrap();
@@ -177,7 +178,8 @@ async fn test_export_then_import_roundtrip() {
     }
 
     // Export
-    let export_result = export_team_config_template(None, None, app.state(), app.state()).await;
+    let export_result =
+        export_team_config_template(None, None, app.state(), app.state(), app.state()).await;
     assert!(export_result.is_ok());
     let template_path = export_result.unwrap();
 
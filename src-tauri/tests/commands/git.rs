@@ -568,6 +568,7 @@ async fn test_git_worktree_ops() {
         None,
         None,
         app.state(), // credential_factory
+        app.handle().clone(),
     )
     .await;
     assert!(remove_result.is_ok());
@@ -88,6 +88,14 @@ fn test_classify_error_msg_tls() {
     assert_eq!(cat, "Tls");
 }
 
+#[test]
+fn test_classify_error_msg_client_cert() {
+    let (cat, _msg) = classify_error_msg(
+        "tls handshake error with 10.0.0.1:443: client certificate rejected by git.example.com: received fatal alert: CertificateRequired",
+    );
+    assert_eq!(cat, "ClientCert");
+}
+
 #[test]
 fn test_classify_error_msg_network_timeout() {
     let (cat, _msg) = classify_error_msg("connect timeout after 30s");
@@ -142,6 +142,7 @@ async fn test_add_repository_command() {
         remote_url: "https://git.example.com/repo1.git".to_string(),
         tags: Some(vec!["tag1".to_string()]),
         enabled: Some(true),
+        credential: None,
     };
 
     let result = add_repository(req, state).await;
@@ -912,6 +913,7 @@ async fn test_workspace_duplicate_repo_prevention() {
         remote_url: "url1".into(),
         tags: None,
         enabled: Some(true),
+        credential: None,
     };
     add_repository(req1, app.state()).await.unwrap();
 
@@ -923,6 +925,7 @@ async fn test_workspace_duplicate_repo_prevention() {
         remote_url: "url2".into(),
         tags: None,
         enabled: Some(true),
+        credential: None,
     };
     let result = add_repository(req2, app.state()).await;
 
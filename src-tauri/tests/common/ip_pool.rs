@@ -53,6 +53,7 @@ pub fn user_static_only_config(host: &str, address: IpAddr, port: u16) -> Effect
         history: false,
         user_static: true,
         fallback: false,
+        subscription: false,
     };
     cfg.file.user_static.push(UserStaticIp {
         host: host.to_string(),
@@ -71,6 +72,7 @@ pub fn disable_all_sources(cfg: &mut EffectiveIpPoolConfig) {
         history: false,
         user_static: false,
         fallback: false,
+        subscription: false,
     };
 }
 
@@ -120,6 +122,7 @@ pub fn history_record(host: &str, port: u16, stat: &IpStat) -> IpHistoryRecord {
         measured_at_epoch_ms: stat.measured_at_epoch_ms.unwrap_or_default(),
         expires_at_epoch_ms: stat.expires_at_epoch_ms.unwrap_or_default(),
         resolver_metadata: stat.resolver_metadata.clone(),
+        pending_validation: false,
     }
 }
 
@@ -147,6 +150,7 @@ pub fn make_history_record(
         measured_at_epoch_ms: measured_at_ms,
         expires_at_epoch_ms: expires_at_ms,
         resolver_metadata: Vec::new(),
+        pending_validation: false,
     }
 }
 
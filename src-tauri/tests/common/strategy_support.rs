@@ -214,6 +214,7 @@ pub fn test_emit_adaptive_tls_observability(task_id: uuid::Uuid, kind: &str) {
                 ip_source: None,
                 ip_latency_ms: None,
                 ip_selection_stage: None,
+                http_version: None,
             },
         ));
     }
@@ -777,6 +777,52 @@ mod section_team_template {
                 && entry.reason == "strategyKeepLocal"));
     }
 
+    #[test]
+    fn test_credential_binding_rules_roundtrip_and_merge() {
+        use fireworks_collaboration_lib::core::credential::{
+            CredentialBinding, CredentialBindingRule,
+        };
+
+        let work_rule = CredentialBindingRule {
+            pattern: "github.com/acme/*".into(),
+            binding: CredentialBinding::new("alice-work"),
+        };
+        let local_rule = CredentialBindingRule {
+            pattern: "github.com/alice/*".into(),
+            binding: CredentialBinding::new("alice-personal"),
+        };
+
+        let mut source = AppConfig::default();
+        source.credential.file_path = Some("/tmp/credential.enc".into());
+        source.credential.bindings = vec![work_rule.clone()];
+
+        let template = with_temp_dir("bindings", |base| {
+            export_template(&source, base, &TemplateExportOptions::default())
+                .expect("export template")
+        });
+        let exported = template
+            .sections
+            .credential
+            .as_ref()
+            .expect("credential section present");
+        assert!(exported.file_path.is_none());
+        assert_eq!(exported.bindings, vec![work_rule.clone()]);
+
+        let mut cfg = AppConfig::default();
+        cfg.credential.bindings = vec![local_rule.clone(), work_rule.clone()];
+
+        let mut options = TemplateImportOptions::default();
+        options.strategies.credential = SectionStrategy::Merge;
+        apply_template_to_config(&mut cfg, None, &template, &options)
+            .expect("merge strategy should succeed");
+
+        assert_eq!(
+            cfg.credential.bindings,
+            vec![local_rule, work_rule],
+            "merge should keep local rules and skip duplicates"
+        );
+    }
+
     #[test]
     fn test_import_respects_disabled_sections() {
         let mut cfg = AppConfig::default();
@@ -973,3 +1019,203 @@ mod section_team_template {
         });
     }
 }
+
+mod section_network_profile {
+    use fireworks_collaboration_lib::core::config::model::AppConfig;
+    use fireworks_collaboration_lib::core::config::network_profile::{
+        detect_profile, emit_profile_switch, parse_proc_net_route, parse_resolv_conf,
+        rebase_saved_config, switch_profile, NetworkMatch, NetworkProfile, NetworkSignals,
+        TRIGGER_MANUAL,
+    };
+    use fireworks_collaboration_lib::core::proxy::config::ProxyMode;
+    use fireworks_collaboration_lib::events::structured::{
+        set_test_event_bus, Event, MemoryEventBus, StrategyEvent,
+    };
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn profile(name: &str, matcher: NetworkMatch) -> NetworkProfile {
+        NetworkProfile {
+            name: name.into(),
+            matcher,
+            ip_pool: None,
+            proxy: None,
+            http: None,
+        }
+    }
+
+    #[test]
+    fn test_network_signal_parsing_and_detection() {
+        let route = "Iface\tDestination\tGateway \tFlags\n\
+                     eth0\t00000000\t0101A8C0\t0003\n\
+                     eth0\t0001A8C0\t00000000\t0001\n";
+        assert_eq!(
+            parse_proc_net_route(route),
+            vec!["192.168.1.1".parse::<std::net::IpAddr>().unwrap()]
+        );
+        let resolv = "# generated\nnameserver 10.0.0.2\nsearch Campus.Example.edu. lab.example.edu\ndomain campus.example.edu\n";
+        assert_eq!(
+            parse_resolv_conf(resolv),
+            vec![
+                "campus.example.edu".to_string(),
+                "lab.example.edu".to_string()
+            ]
+        );
+
+        let signals = NetworkSignals {
+            gateways: parse_proc_net_route(route),
+            local_addrs: vec!["192.168.1.23".parse().unwrap()],
+            dns_suffixes: parse_resolv_conf(resolv),
+        };
+        let profiles = vec![
+            profile("manual-only", NetworkMatch::default()),
+            profile(
+                "office",
+                NetworkMatch {
+                    gateways: vec!["10.1.0.1".into()],
+                    ..Default::default()
+                },
+            ),
+            profile(
+                "campus-vpn",
+                NetworkMatch {
+                    dns_suffixes: vec!["example.edu".into()],
+                    reachable: vec!["vpn.example.edu:443".into()],
+                    ..Default::default()
+                },
+            ),
+            profile(
+                "campus",
+                NetworkMatch {
+                    gateways: vec!["192.168.1.1".into()],
+                    subnets: vec!["192.168.1.0/24".into()],
+                    dns_suffixes: vec!["example.edu".into()],
+                    ..Default::default()
+                },
+            ),
+        ];
+
+        let mut probed = Vec::new();
+        let detected = detect_profile(&profiles, &signals, |target| {
+            probed.push(target.to_string());
+            false
+        });
+        assert_eq!(detected.map(|p| p.name.as_str()), Some("campus"));
+        assert_eq!(probed, vec!["vpn.example.edu:443".to_string()]);
+
+        let detected = detect_profile(&profiles, &signals, |_| true);
+        assert_eq!(detected.map(|p| p.name.as_str()), Some("campus-vpn"));
+
+        let elsewhere = NetworkSignals {
+            gateways: vec!["172.20.10.1".parse().unwrap()],
+            local_addrs: vec!["172.20.10.2".parse().unwrap()],
+            dns_suffixes: Vec::new(),
+        };
+        assert!(detect_profile(&profiles, &elsewhere, |_| true).is_none());
+    }
+
+    #[test]
+    fn test_network_profile_switch_restores_baseline() {
+        let bus = Arc::new(MemoryEventBus::new());
+        set_test_event_bus(bus.clone());
+
+        let mut cfg = AppConfig::default();
+        cfg.http.fake_sni_enabled = true;
+        let mut campus = profile("campus", NetworkMatch::default());
+        campus.proxy = Some(json!({ "mode": "http", "url": "http://proxy.campus:3128" }));
+        let mut hotspot = profile("hotspot", NetworkMatch::default());
+        hotspot.http = Some(json!({ "fakeSniEnabled": false }));
+        cfg.network_profiles.profiles = vec![campus, hotspot];
+
+        let switch = switch_profile(&mut cfg, Some("campus")).unwrap();
+        assert!(switch.changed);
+        assert_eq!(cfg.proxy.mode, ProxyMode::Http);
+        assert!(cfg.http.fake_sni_enabled);
+        assert_eq!(cfg.network_profiles.active.as_deref(), Some("campus"));
+        emit_profile_switch(&switch, TRIGGER_MANUAL);
+
+        // 切换到另一个配置档时先恢复基线，代理覆盖不会残留
+        let switch = switch_profile(&mut cfg, Some("hotspot")).unwrap();
+        assert_eq!(switch.from.as_deref(), Some("campus"));
+        assert_eq!(cfg.proxy.mode, ProxyMode::Off);
+        assert!(!cfg.http.fake_sni_enabled);
+
+        assert!(!switch_profile(&mut cfg, Some("hotspot")).unwrap().changed);
+        assert!(switch_profile(&mut cfg, Some("missing")).is_err());
+
+        // 配置档生效期间持久化再加载，基线仍可恢复
+        let json = serde_json::to_string(&cfg).unwrap();
+        let mut cfg: AppConfig = serde_json::from_str(&json).unwrap();
+        let switch = switch_profile(&mut cfg, None).unwrap();
+        assert_eq!(switch.to, None);
+        assert!(cfg.http.fake_sni_enabled);
+        assert!(cfg.network_profiles.baseline.is_none());
+        assert!(cfg.network_profiles.active.is_none());
+
+        let events = bus.snapshot();
+        assert!(events.iter().any(|evt| matches!(
+            evt,
+            Event::Strategy(StrategyEvent::NetworkProfileSwitched { to, trigger, .. })
+                if to.as_deref() == Some("campus") && trigger == "manual"
+        )));
+    }
+
+    #[test]
+    fn test_partial_profile_keeps_untouched_baseline_fields() {
+        let mut cfg = AppConfig::default();
+        cfg.proxy.timeout_seconds = 42;
+        cfg.proxy.no_proxy = vec!["intranet.example".into()];
+        cfg.http.fake_sni_enabled = false;
+        cfg.ip_pool.max_parallel_probes = 3;
+        let mut campus = profile("campus", NetworkMatch::default());
+        campus.proxy = Some(json!({ "mode": "http", "url": "http://proxy.campus:3128" }));
+        campus.ip_pool = Some(json!({ "probeTimeoutMs": 900 }));
+        let mut broken = profile("broken", NetworkMatch::default());
+        broken.proxy = Some(json!({ "mode": "carrier-pigeon" }));
+        cfg.network_profiles.profiles = vec![campus, broken];
+
+        switch_profile(&mut cfg, Some("campus")).unwrap();
+        assert_eq!(cfg.proxy.mode, ProxyMode::Http);
+        assert_eq!(cfg.proxy.url, "http://proxy.campus:3128");
+        // 补丁未写的字段保持基线值，而不是回到默认值
+        assert_eq!(cfg.proxy.timeout_seconds, 42);
+        assert_eq!(cfg.proxy.no_proxy, vec!["intranet.example".to_string()]);
+        assert_eq!(cfg.ip_pool.probe_timeout_ms, 900);
+        assert_eq!(cfg.ip_pool.max_parallel_probes, 3);
+        assert!(!cfg.http.fake_sni_enabled);
+
+        // 无效覆盖项报错且不改动配置
+        let before = serde_json::to_value(&cfg).unwrap();
+        assert!(switch_profile(&mut cfg, Some("broken")).is_err());
+        assert_eq!(serde_json::to_value(&cfg).unwrap(), before);
+
+        // 配置档生效期间保存：改动写回基线，覆盖项不会被当作基线保存
+        let previous = cfg.clone();
+        let mut saved = cfg.clone();
+        saved.proxy.timeout_seconds = 7;
+        saved.http.fake_sni_enabled = true;
+        rebase_saved_config(&previous, &mut saved).unwrap();
+        assert_eq!(saved.proxy.mode, ProxyMode::Http);
+        assert_eq!(saved.proxy.timeout_seconds, 7);
+        assert!(saved.http.fake_sni_enabled);
+        let baseline = saved.network_profiles.baseline.clone().unwrap();
+        assert_eq!(baseline.proxy.mode, ProxyMode::Off);
+        assert_eq!(baseline.proxy.timeout_seconds, 7);
+        assert!(baseline.http.fake_sni_enabled);
+        assert_eq!(
+            baseline.ip_pool.probe_timeout_ms,
+            previous
+                .network_profiles
+                .baseline
+                .as_ref()
+                .unwrap()
+                .ip_pool
+                .probe_timeout_ms
+        );
+
+        switch_profile(&mut saved, None).unwrap();
+        assert_eq!(saved.proxy.mode, ProxyMode::Off);
+        assert_eq!(saved.proxy.timeout_seconds, 7);
+        assert!(saved.http.fake_sni_enabled);
+    }
+}
@@ -650,6 +650,7 @@ fn test_task_error_event_from_parts_categories() {
         (TaskErrorCategory::Network, "Network"),
         (TaskErrorCategory::Tls, "Tls"),
         (TaskErrorCategory::Verify, "Verify"),
+        (TaskErrorCategory::ClientCert, "ClientCert"),
         (TaskErrorCategory::Protocol, "Protocol"),
         (TaskErrorCategory::Proxy, "Proxy"),
         (TaskErrorCategory::Auth, "Auth"),
@@ -193,3 +193,41 @@ fn test_apply_template_merge_tls() {
         .unwrap();
     assert!(matches!(applied.strategy, SectionStrategy::Merge));
 }
+
+#[test]
+fn test_apply_template_ip_history_section() {
+    use fireworks_collaboration_lib::core::ip_pool::IpHistoryExport;
+
+    let mut template = TeamConfigTemplate::new();
+    template.sections.ip_history = Some(IpHistoryExport {
+        version: 1,
+        ..IpHistoryExport::default()
+    });
+    let json = serde_json::to_value(&template).unwrap();
+    assert!(json["sections"]["ipHistory"].is_object());
+
+    // 默认按合并策略交给调用方导入
+    let mut cfg = AppConfig::default();
+    let options = TemplateImportOptions::default();
+    let outcome = apply_template_to_config(&mut cfg, None, &template, &options).unwrap();
+    let (_, strategy) = outcome.ip_history.expect("history handed to caller");
+    assert_eq!(strategy, SectionStrategy::Merge);
+    assert!(outcome
+        .report
+        .applied
+        .iter()
+        .any(|a| a.section == TemplateSectionKind::IpHistory));
+
+    let mut options = TemplateImportOptions::default();
+    options.strategies.ip_history = SectionStrategy::KeepLocal;
+    let outcome = apply_template_to_config(&mut cfg, None, &template, &options).unwrap();
+    assert!(outcome.ip_history.is_none());
+    assert!(outcome
+        .report
+        .skipped
+        .iter()
+        .any(|s| s.section == TemplateSectionKind::IpHistory && s.reason == "strategyKeepLocal"));
+
+    // 默认导出不包含历史
+    assert!(!TemplateExportOptions::default().include_ip_history);
+}
@@ -1,9 +1,26 @@
+use fireworks_collaboration_lib::core::ip_pool::family::{family_allowed, parse_ip_literal};
+use fireworks_collaboration_lib::core::ip_pool::preheat::{
+    builtin_lookup, collect_candidates, is_cert_mismatch, probe_tls_handshake, CertMismatch,
+    ProbeEnv,
+};
+use fireworks_collaboration_lib::core::ip_pool::racing::{
+    interleave_families, race_candidates, should_race,
+};
+use fireworks_collaboration_lib::core::ip_pool::scoring::{
+    blend_quality, composite_score, rank_stats, summarize_samples,
+};
+use fireworks_collaboration_lib::core::ip_pool::subscription::{
+    filter_allowed, lookup, refresh_due, subscription_status, verify_signed_list,
+};
 use fireworks_collaboration_lib::core::ip_pool::{
-    EffectiveIpPoolConfig, IpCacheKey, IpCacheSlot, IpCandidate, IpOutcome, IpPool, IpSource,
-    IpStat,
+    ConnectionRacingConfig, EffectiveIpPoolConfig, IpCacheKey, IpCacheSlot, IpCandidate,
+    IpHistoryRecord, IpHistoryStore, IpOutcome, IpPool, IpPoolRuntimeConfig, IpPoolSourceToggle,
+    IpQuality, IpSource, IpStat, IpSubscription, Ipv6Mode, ScoringConfig, UserStaticIp,
 };
 use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::Instant;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 fn create_test_pool() -> IpPool {
@@ -153,3 +170,864 @@ async fn test_sampling_expiration() {
     );
     assert!(selection.is_system_default());
 }
+
+fn racing_cfg(max_attempts: usize, attempt_delay_ms: u64) -> ConnectionRacingConfig {
+    ConnectionRacingConfig {
+        enabled: true,
+        max_attempts,
+        attempt_delay_ms,
+    }
+}
+
+#[test]
+fn test_racing_interleaves_families_keeping_rank() {
+    let ordered = interleave_families(vec![
+        create_dummy_stat("2001:db8::1", 10, None),
+        create_dummy_stat("2001:db8::2", 20, None),
+        create_dummy_stat("192.0.2.1", 30, None),
+        create_dummy_stat("2001:db8::3", 40, None),
+        create_dummy_stat("192.0.2.2", 50, None),
+    ]);
+    let ips: Vec<String> = ordered
+        .iter()
+        .map(|s| s.candidate.address.to_string())
+        .collect();
+    assert_eq!(
+        ips,
+        vec![
+            "2001:db8::1",
+            "192.0.2.1",
+            "2001:db8::2",
+            "192.0.2.2",
+            "2001:db8::3"
+        ]
+    );
+
+    assert!(should_race(&racing_cfg(4, 250), 2));
+    assert!(!should_race(&racing_cfg(4, 250), 1));
+    assert!(!should_race(&racing_cfg(1, 250), 3));
+    let mut disabled = racing_cfg(4, 250);
+    disabled.enabled = false;
+    assert!(!should_race(&disabled, 3));
+}
+
+#[tokio::test]
+async fn test_racing_faster_later_candidate_wins() {
+    let reports: Arc<Mutex<Vec<(String, IpOutcome)>>> = Arc::default();
+    let sink = Arc::clone(&reports);
+    let candidates = vec![
+        create_dummy_stat("192.0.2.1", 10, None),
+        create_dummy_stat("192.0.2.2", 20, None),
+        create_dummy_stat("192.0.2.3", 30, None),
+    ];
+    let started = Instant::now();
+    let result = race_candidates(
+        candidates,
+        &racing_cfg(3, 30),
+        |stat| async move {
+            // 首选候选很慢，第二个候选在错峰延迟后很快完成
+            let wait = if stat.candidate.address.to_string() == "192.0.2.1" {
+                2_000
+            } else {
+                10
+            };
+            tokio::time::sleep(Duration::from_millis(wait)).await;
+            Ok::<_, String>(stat.candidate.address)
+        },
+        move |stat, outcome| {
+            sink.lock()
+                .unwrap()
+                .push((stat.candidate.address.to_string(), outcome))
+        },
+    )
+    .await;
+
+    let (winner, value) = result.winner.expect("race should have a winner");
+    assert_eq!(winner.candidate.address.to_string(), "192.0.2.2");
+    assert_eq!(value, winner.candidate.address);
+    assert!(started.elapsed() < Duration::from_millis(1_000));
+    assert!(result.started >= 2);
+    // 被取消的慢速尝试不报告
+    assert_eq!(
+        *reports.lock().unwrap(),
+        vec![("192.0.2.2".to_string(), IpOutcome::Success)]
+    );
+}
+
+#[tokio::test]
+async fn test_racing_failure_launches_next_immediately() {
+    let reports: Arc<Mutex<Vec<(String, IpOutcome)>>> = Arc::default();
+    let sink = Arc::clone(&reports);
+    let candidates = vec![
+        create_dummy_stat("192.0.2.1", 10, None),
+        create_dummy_stat("192.0.2.2", 20, None),
+        create_dummy_stat("192.0.2.3", 30, None),
+    ];
+    let started = Instant::now();
+    let result = race_candidates(
+        candidates,
+        &racing_cfg(2, 5_000),
+        |stat| async move {
+            if stat.candidate.address.to_string() == "192.0.2.1" {
+                Err::<(), _>("connection refused".to_string())
+            } else {
+                Err("tls handshake: timeout".to_string())
+            }
+        },
+        move |stat, outcome| {
+            sink.lock()
+                .unwrap()
+                .push((stat.candidate.address.to_string(), outcome))
+        },
+    )
+    .await;
+
+    // 失败后不等待错峰延迟；只尝试前 max_attempts 个候选
+    assert!(started.elapsed() < Duration::from_millis(1_000));
+    assert!(result.winner.is_none());
+    assert_eq!(result.started, 2);
+    assert_eq!(result.attempts.len(), 2);
+    assert_eq!(
+        result.errors(),
+        vec![
+            "192.0.2.1: connection refused".to_string(),
+            "192.0.2.2: tls handshake: timeout".to_string()
+        ]
+    );
+    assert!(reports
+        .lock()
+        .unwrap()
+        .iter()
+        .all(|(_, o)| *o == IpOutcome::Failure));
+}
+
+#[test]
+fn test_ipv6_literals_and_family_scoring() {
+    assert_eq!(
+        parse_ip_literal(" [2001:db8::1] "),
+        Some("2001:db8::1".parse().unwrap())
+    );
+    // IPv4 映射地址还原为 IPv4
+    assert_eq!(
+        parse_ip_literal("::ffff:192.0.2.1"),
+        Some("192.0.2.1".parse().unwrap())
+    );
+    assert_eq!(parse_ip_literal("not-an-ip"), None);
+
+    let v6: IpAddr = "2001:db8::1".parse().unwrap();
+    assert!(family_allowed(Ipv6Mode::Allow, v6));
+    assert!(!family_allowed(Ipv6Mode::Forbid, v6));
+    assert!(family_allowed(
+        Ipv6Mode::Forbid,
+        "::ffff:192.0.2.1".parse().unwrap()
+    ));
+
+    let stats = vec![
+        create_dummy_stat("192.0.2.1", 80, None),
+        create_dummy_stat("2001:db8::1", 100, None),
+        create_dummy_stat("192.0.2.2", 200, None),
+    ];
+    let mut runtime = IpPoolRuntimeConfig::default();
+    let mut allow = stats.clone();
+    rank_stats(&mut allow, &runtime);
+    assert_eq!(allow[0].candidate.address.to_string(), "192.0.2.1");
+
+    runtime.ipv6 = Ipv6Mode::Prefer;
+    runtime.ipv6_bias_ms = 30;
+    let mut prefer = stats;
+    rank_stats(&mut prefer, &runtime);
+    assert_eq!(prefer[0].candidate.address.to_string(), "2001:db8::1");
+    assert_eq!(prefer[1].candidate.address.to_string(), "192.0.2.1");
+}
+
+#[tokio::test]
+async fn test_ipv6_static_candidates_follow_policy_and_cidr_filters() {
+    assert!(builtin_lookup("github.io").iter().any(|ip| ip.is_ipv6()));
+
+    let mut config = EffectiveIpPoolConfig::default();
+    config.runtime.sources.builtin = false;
+    config.runtime.sources.dns = false;
+    config.runtime.sources.history = false;
+    config.runtime.sources.fallback = false;
+    config.file.user_static = ["192.0.2.10", "[2001:db8:1::10]", "2001:db8:2::10"]
+        .iter()
+        .map(|ip| UserStaticIp {
+            host: "v6.test".into(),
+            ip: ip.to_string(),
+            ports: vec![443],
+        })
+        .collect();
+    config.file.blacklist = vec!["2001:db8:2::/48".into()];
+    let history = Arc::new(IpHistoryStore::in_memory());
+
+    let collect = |config: EffectiveIpPoolConfig| {
+        let history = Arc::clone(&history);
+        async move {
+            let mut ips: Vec<String> = collect_candidates("v6.test", 443, &config, history)
+                .await
+                .into_iter()
+                .map(|c| c.candidate.address.to_string())
+                .collect();
+            ips.sort();
+            ips
+        }
+    };
+
+    assert_eq!(
+        collect(config.clone()).await,
+        vec!["192.0.2.10", "2001:db8:1::10"]
+    );
+
+    let mut whitelisted = config.clone();
+    whitelisted.file.whitelist = vec!["2001:db8::/32".into()];
+    assert_eq!(collect(whitelisted).await, vec!["2001:db8:1::10"]);
+
+    let mut forbidden = config;
+    forbidden.runtime.ipv6 = Ipv6Mode::Forbid;
+    assert_eq!(collect(forbidden).await, vec!["192.0.2.10"]);
+}
+
+#[tokio::test]
+async fn test_ipv6_forbid_skips_cached_v6_entries() {
+    let mut config = EffectiveIpPoolConfig::default();
+    config.runtime.ipv6 = Ipv6Mode::Forbid;
+    let pool = IpPool::new(config);
+    pool.cache().insert(
+        IpCacheKey::new("cached.v6.test", 443),
+        IpCacheSlot {
+            best: Some(create_dummy_stat("2001:db8::1", 10, None)),
+            alternatives: vec![
+                create_dummy_stat("2001:db8::2", 20, None),
+                create_dummy_stat("192.0.2.30", 40, None),
+            ],
+        },
+    );
+
+    let selection = pool.pick_best("cached.v6.test", 443).await;
+    let ips: Vec<String> = selection
+        .iter_candidates()
+        .map(|s| s.candidate.address.to_string())
+        .collect();
+    assert_eq!(ips, vec!["192.0.2.30"]);
+}
+
+fn with_quality(ip_str: &str, quality: IpQuality) -> IpStat {
+    let mut stat = create_dummy_stat(ip_str, quality.latency_ms.unwrap_or(0), None);
+    stat.quality = Some(quality);
+    stat
+}
+
+#[test]
+fn test_scoring_summarizes_samples_and_blends_history() {
+    // 5 次尝试 4 次成功：中位数 30，抖动 = (10+20+30)/3 = 20，丢包 200‰
+    let q = summarize_samples(&[20, 30, 50, 20], 5).unwrap();
+    assert_eq!(q.latency_ms, Some(30));
+    assert_eq!(q.jitter_ms, Some(20));
+    assert_eq!(q.loss_permille, Some(200));
+    assert!(summarize_samples(&[], 3).is_none());
+
+    let single = summarize_samples(&[42], 1).unwrap();
+    assert_eq!(single.jitter_ms, None);
+    assert_eq!(single.loss_permille, None);
+
+    let prev = IpQuality {
+        latency_ms: Some(100),
+        throughput_kibps: Some(4096),
+        samples: 3,
+        ..IpQuality::default()
+    };
+    let blended = blend_quality(Some(&prev), &q, 0.5);
+    assert_eq!(blended.latency_ms, Some(65));
+    assert_eq!(blended.jitter_ms, Some(20));
+    // 观测缺失的维度保留历史值
+    assert_eq!(blended.throughput_kibps, Some(4096));
+    assert_eq!(blended.samples, 4);
+}
+
+#[test]
+fn test_composite_score_penalizes_loss_and_jitter() {
+    let cfg = ScoringConfig::default();
+    let stable = with_quality(
+        "192.0.2.1",
+        IpQuality {
+            latency_ms: Some(60),
+            jitter_ms: Some(2),
+            loss_permille: Some(0),
+            ..IpQuality::default()
+        },
+    );
+    let lossy = with_quality(
+        "192.0.2.2",
+        IpQuality {
+            latency_ms: Some(30),
+            jitter_ms: Some(5),
+            loss_permille: Some(333),
+            ..IpQuality::default()
+        },
+    );
+    let jittery = with_quality(
+        "192.0.2.3",
+        IpQuality {
+            latency_ms: Some(40),
+            jitter_ms: Some(80),
+            loss_permille: Some(0),
+            ..IpQuality::default()
+        },
+    );
+    assert!(composite_score(&stable, &cfg) < composite_score(&lossy, &cfg));
+    assert!(composite_score(&stable, &cfg) < composite_score(&jittery, &cfg));
+
+    let mut stats = vec![lossy, jittery, stable];
+    rank_stats(&mut stats, &IpPoolRuntimeConfig::default());
+    assert_eq!(stats[0].candidate.address.to_string(), "192.0.2.1");
+
+    // 仅调高延迟权重时，低延迟候选重新领先
+    let latency_only = ScoringConfig {
+        jitter_weight: 0.0,
+        loss_weight: 0.0,
+        ..ScoringConfig::default()
+    };
+    assert!(composite_score(&stats[1], &latency_only) < composite_score(&stats[0], &latency_only));
+}
+
+#[test]
+fn test_quality_history_persists_across_reload() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("ip-history.json");
+    let ip: IpAddr = "192.0.2.9".parse().unwrap();
+    let now = current_ts_ms();
+    {
+        let store = IpHistoryStore::load_or_init_from_file(&path).unwrap();
+        let first = IpQuality {
+            latency_ms: Some(100),
+            samples: 1,
+            ..IpQuality::default()
+        };
+        store
+            .fold_quality("github.com", 443, &[(ip, first)], 0.5, now)
+            .unwrap();
+        let second = IpQuality {
+            latency_ms: Some(50),
+            samples: 1,
+            ..IpQuality::default()
+        };
+        let merged = store
+            .fold_quality("github.com", 443, &[(ip, second)], 0.5, now)
+            .unwrap();
+        assert_eq!(merged[0].latency_ms, Some(75));
+    }
+    let reloaded = IpHistoryStore::load_or_init_from_file(&path).unwrap();
+    let quality = reloaded.quality("github.com", 443, ip).unwrap();
+    assert_eq!(quality.latency_ms, Some(75));
+    assert_eq!(quality.samples, 2);
+    assert!(reloaded.quality("github.com", 22, ip).is_none());
+}
+
+#[test]
+fn test_throughput_outcome_reranks_cached_slot() {
+    let pool = IpPool::new(EffectiveIpPoolConfig::default());
+    pool.cache().insert(
+        IpCacheKey::new("codeload.test", 443),
+        IpCacheSlot {
+            best: Some(create_dummy_stat("192.0.2.1", 40, None)),
+            alternatives: vec![create_dummy_stat("192.0.2.2", 60, None)],
+        },
+    );
+
+    // 8 MiB / 1 s：吞吐量加分足以抵消 20ms 的延迟差
+    pool.report_ip_outcome(
+        "codeload.test",
+        443,
+        "192.0.2.2".parse().unwrap(),
+        IpOutcome::Throughput {
+            bytes: 8 * 1024 * 1024,
+            elapsed_ms: 1000,
+        },
+    );
+
+    let slot = pool.cache().get("codeload.test", 443).unwrap();
+    let best = slot.best.unwrap();
+    assert_eq!(best.candidate.address.to_string(), "192.0.2.2");
+    assert_eq!(best.quality.unwrap().throughput_kibps, Some(8192));
+    assert_eq!(slot.alternatives.len(), 1);
+    let metrics = pool
+        .candidate_outcome_metrics("codeload.test", 443, "192.0.2.2".parse().unwrap())
+        .unwrap();
+    assert_eq!(metrics.success, 1);
+}
+
+#[tokio::test]
+async fn test_tls_probe_times_handshake_and_flags_cert_mismatch() {
+    use fireworks_collaboration_lib::core::config::model::{AppConfig, CustomCaCfg};
+    use rcgen::{BasicConstraints, Certificate as RcCert, CertificateParams, IsCa};
+    use rustls::{Certificate, PrivateKey, ServerConfig};
+
+    let mut ca_params = CertificateParams::new(Vec::new());
+    ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    let ca = RcCert::from_params(ca_params).unwrap();
+    let server = RcCert::from_params(CertificateParams::new(vec!["localhost".into()])).unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let ca_path = dir.path().join("ca.pem");
+    std::fs::write(&ca_path, ca.serialize_pem().unwrap()).unwrap();
+
+    let server_cfg = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(
+            vec![Certificate(server.serialize_der_with_signer(&ca).unwrap())],
+            PrivateKey(server.serialize_private_key_der()),
+        )
+        .unwrap();
+    let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(server_cfg));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        while let Ok((tcp, _)) = listener.accept().await {
+            let acceptor = acceptor.clone();
+            tokio::spawn(async move {
+                let _ = acceptor.accept(tcp).await;
+            });
+        }
+    });
+
+    let mut cfg = AppConfig::default();
+    cfg.tls.custom_cas = vec![CustomCaCfg {
+        path: ca_path.display().to_string(),
+        hosts: vec!["localhost".into()],
+    }];
+    let env = ProbeEnv::from_config(&cfg);
+    let ip: IpAddr = "127.0.0.1".parse().unwrap();
+
+    let timing = probe_tls_handshake(ip, port, "localhost", "localhost", &env, 2000)
+        .await
+        .unwrap();
+    assert_eq!(timing.total_ms(), timing.connect_ms + timing.handshake_ms);
+
+    // 伪 SNI 下仍按真实主机校验：证书不属于该主机时为不匹配而非超时
+    let err = probe_tls_handshake(ip, port, "github.com", "localhost", &env, 2000)
+        .await
+        .unwrap_err();
+    assert!(is_cert_mismatch(&err), "unexpected error: {err:#}");
+    assert_eq!(err.downcast_ref::<CertMismatch>().unwrap().ip, ip);
+
+    // 连接失败不属于证书不匹配
+    let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let closed_port = closed.local_addr().unwrap().port();
+    drop(closed);
+    let err = probe_tls_handshake(ip, closed_port, "localhost", "localhost", &env, 500)
+        .await
+        .unwrap_err();
+    assert!(!is_cert_mismatch(&err));
+}
+
+#[test]
+fn test_unusable_marks_expire_and_persist() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("ip-history.json");
+    let ip: IpAddr = "192.0.2.77".parse().unwrap();
+    let now = current_ts_ms();
+    {
+        let store = IpHistoryStore::load_or_init_from_file(&path).unwrap();
+        assert!(store.unusable_until("github.com", 443, ip, now).is_none());
+        store
+            .mark_unusable("github.com", 443, ip, "NotValidForName", now + 60_000, now)
+            .unwrap();
+    }
+    let store = IpHistoryStore::load_or_init_from_file(&path).unwrap();
+    assert_eq!(
+        store.unusable_until("github.com", 443, ip, now),
+        Some(now + 60_000)
+    );
+    assert!(store
+        .unusable_until("github.com", 443, ip, now + 60_000)
+        .is_none());
+    assert!(store
+        .unusable_until("api.github.com", 443, ip, now)
+        .is_none());
+    let record = &store.quality_snapshot()[0];
+    assert_eq!(record.unusable_reason.as_deref(), Some("NotValidForName"));
+}
+
+mod subscription_support {
+    use base64::engine::general_purpose::STANDARD as BASE64;
+    use base64::Engine as _;
+    use ring::rand::SystemRandom;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    pub struct Signer(Ed25519KeyPair);
+
+    impl Signer {
+        pub fn new() -> Self {
+            let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+            Self(Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap())
+        }
+
+        pub fn public_key(&self) -> String {
+            BASE64.encode(self.0.public_key().as_ref())
+        }
+
+        pub fn sign(&self, payload: &serde_json::Value) -> Vec<u8> {
+            let bytes = serde_json::to_vec(payload).unwrap();
+            serde_json::to_vec(&serde_json::json!({
+                "payload": BASE64.encode(&bytes),
+                "signature": BASE64.encode(self.0.sign(&bytes).as_ref()),
+            }))
+            .unwrap()
+        }
+    }
+}
+
+fn sample_list() -> serde_json::Value {
+    serde_json::json!({
+        "generatedAt": "2026-10-12",
+        "entries": [
+            { "host": "github.com", "ips": ["140.82.112.3", "[2001:db8::3]"] },
+            { "host": "codeload.github.com", "ips": ["140.82.113.9"], "ports": [443, 8443] },
+            { "host": "evil.example", "ips": ["203.0.113.1"] },
+            { "host": "api.github.com", "ips": ["not-an-ip"] }
+        ]
+    })
+}
+
+#[test]
+fn test_subscription_signature_and_domain_whitelist() {
+    let signer = subscription_support::Signer::new();
+    let raw = signer.sign(&sample_list());
+
+    let payload = verify_signed_list(&raw, &signer.public_key()).unwrap();
+    let list = filter_allowed(
+        payload.clone(),
+        &["github.com".to_string(), "*.github.com".to_string()],
+    );
+    assert_eq!(list.generated_at.as_deref(), Some("2026-10-12"));
+    // 非白名单域名与含非法 IP 的条目被整条丢弃
+    assert_eq!(list.rejected, 2);
+    assert_eq!(list.entries.len(), 4);
+    assert!(list.entries.contains(&(
+        "codeload.github.com".into(),
+        8443,
+        "140.82.113.9".parse().unwrap()
+    )));
+    assert_eq!(filter_allowed(payload, &[]).entries.len(), 0);
+
+    // 其他密钥签名或内容被篡改时拒绝
+    let other = subscription_support::Signer::new();
+    assert!(verify_signed_list(&raw, &other.public_key()).is_err());
+    let mut tampered: serde_json::Value = serde_json::from_slice(&raw).unwrap();
+    let forged = signer.sign(&serde_json::json!({ "entries": [] }));
+    let forged: serde_json::Value = serde_json::from_slice(&forged).unwrap();
+    tampered["payload"] = forged["payload"].clone();
+    let tampered = serde_json::to_vec(&tampered).unwrap();
+    assert!(verify_signed_list(&tampered, &signer.public_key()).is_err());
+}
+
+#[tokio::test]
+async fn test_subscription_candidates_merge_into_collection() {
+    use fireworks_collaboration_lib::events::structured::{
+        set_test_event_bus, Event, MemoryEventBus, StrategyEvent,
+    };
+    let bus = Arc::new(MemoryEventBus::new());
+    set_test_event_bus(bus.clone());
+
+    let signer = subscription_support::Signer::new();
+    let dir = tempfile::tempdir().unwrap();
+    let list_path = dir.path().join("github-ips.json");
+    std::fs::write(&list_path, signer.sign(&sample_list())).unwrap();
+    let sub = IpSubscription {
+        name: "merge-test".into(),
+        url: list_path.display().to_string(),
+        enabled: true,
+        public_key: signer.public_key(),
+        allowed_domains: vec!["github.com".into()],
+        refresh_interval_secs: 3600,
+    };
+
+    let mut config = EffectiveIpPoolConfig::default();
+    config.runtime.sources = IpPoolSourceToggle {
+        builtin: false,
+        dns: false,
+        history: false,
+        user_static: false,
+        fallback: false,
+        subscription: true,
+    };
+    config.file.subscriptions = vec![sub.clone()];
+    let history = Arc::new(IpHistoryStore::in_memory());
+
+    // 收集候选只读取已加载的列表，加载由刷新负责
+    assert!(
+        collect_candidates("github.com", 443, &config, history.clone())
+            .await
+            .is_empty()
+    );
+    assert_eq!(
+        refresh_due(&config.file.subscriptions, current_ts_ms()).await,
+        1
+    );
+    let candidates = collect_candidates("github.com", 443, &config, history.clone()).await;
+    let mut ips: Vec<String> = candidates
+        .iter()
+        .map(|c| c.candidate.address.to_string())
+        .collect();
+    ips.sort();
+    assert_eq!(ips, vec!["140.82.112.3", "2001:db8::3"]);
+    assert!(candidates
+        .iter()
+        .all(|c| c.candidate.source == IpSource::Subscription));
+
+    let status = subscription_status(&config.file.subscriptions);
+    assert_eq!(status[0].entries, 2);
+    assert_eq!(status[0].rejected, 3);
+    assert!(status[0].last_error.is_none());
+
+    // 未到期不重复拉取；损坏的文件不会冲掉已有列表
+    std::fs::write(&list_path, b"garbage").unwrap();
+    assert_eq!(
+        refresh_due(&config.file.subscriptions, current_ts_ms()).await,
+        0
+    );
+    assert_eq!(
+        lookup("github.com", 443, &config.file.subscriptions).len(),
+        2
+    );
+
+    // 配置变化后立即重新加载；新配置下尚无已验证的列表
+    let mut changed = sub.clone();
+    changed.allowed_domains.push("*.github.com".into());
+    assert_eq!(
+        refresh_due(std::slice::from_ref(&changed), current_ts_ms()).await,
+        1
+    );
+    let status = subscription_status(std::slice::from_ref(&changed));
+    assert!(status[0].last_error.is_some());
+    assert_eq!(status[0].entries, 0);
+
+    // 关闭订阅或来源开关后不再提供候选
+    let mut disabled = changed;
+    disabled.enabled = false;
+    assert!(lookup("github.com", 443, &[disabled]).is_empty());
+    config.runtime.sources.subscription = false;
+    assert!(collect_candidates("github.com", 443, &config, history)
+        .await
+        .is_empty());
+
+    let updates: Vec<(String, bool)> = bus
+        .snapshot()
+        .into_iter()
+        .filter_map(|e| match e {
+            Event::Strategy(StrategyEvent::IpPoolSubscriptionUpdate { name, success, .. }) => {
+                Some((name, success))
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        updates,
+        vec![
+            ("merge-test".to_string(), true),
+            ("merge-test".to_string(), false)
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_subscription_rejects_rolled_back_list() {
+    let signer = subscription_support::Signer::new();
+    let dir = tempfile::tempdir().unwrap();
+    let list_path = dir.path().join("github-ips.json");
+    std::fs::write(&list_path, signer.sign(&sample_list())).unwrap();
+    let subs = vec![IpSubscription {
+        name: "rollback-test".into(),
+        url: list_path.display().to_string(),
+        enabled: true,
+        public_key: signer.public_key(),
+        allowed_domains: vec!["github.com".into()],
+        refresh_interval_secs: 60,
+    }];
+    let now = current_ts_ms();
+    assert_eq!(refresh_due(&subs, now).await, 1);
+    assert_eq!(lookup("github.com", 443, &subs).len(), 2);
+
+    // 签名有效但 generatedAt 更早的旧列表不能替换当前列表
+    let older = serde_json::json!({
+        "generatedAt": "2026-10-01T00:00:00Z",
+        "entries": [{ "host": "github.com", "ips": ["203.0.113.7"] }]
+    });
+    std::fs::write(&list_path, signer.sign(&older)).unwrap();
+    assert_eq!(refresh_due(&subs, now + 120_000).await, 1);
+    let status = subscription_status(&subs);
+    assert!(status[0].last_error.as_deref().unwrap().contains("older"));
+    assert_eq!(status[0].generated_at.as_deref(), Some("2026-10-12"));
+    assert_eq!(lookup("github.com", 443, &subs).len(), 2);
+
+    // 更新的列表正常替换
+    let newer = serde_json::json!({
+        "generatedAt": "2026-10-13T08:00:00Z",
+        "entries": [{ "host": "github.com", "ips": ["203.0.113.7"] }]
+    });
+    std::fs::write(&list_path, signer.sign(&newer)).unwrap();
+    assert_eq!(refresh_due(&subs, now + 20 * 60_000).await, 1);
+    let ips: Vec<IpAddr> = lookup("github.com", 443, &subs)
+        .into_iter()
+        .map(|(ip, _)| ip)
+        .collect();
+    assert_eq!(ips, vec!["203.0.113.7".parse::<IpAddr>().unwrap()]);
+}
+
+fn shared_record(host: &str, ip: &str, latency_ms: u32, measured_at: i64) -> IpHistoryRecord {
+    let ip: IpAddr = ip.parse().unwrap();
+    IpHistoryRecord {
+        host: host.into(),
+        port: 443,
+        candidate: IpCandidate::new(ip, 443, IpSource::Dns),
+        sources: vec![IpSource::Dns],
+        latency_ms,
+        measured_at_epoch_ms: measured_at,
+        expires_at_epoch_ms: measured_at + 3_600_000,
+        resolver_metadata: Vec::new(),
+        pending_validation: false,
+    }
+}
+
+#[test]
+fn test_history_import_merges_by_freshness_and_latency() {
+    let now = current_ts_ms();
+    let minute = 60_000;
+    let ip = |s: &str| -> IpAddr { s.parse().unwrap() };
+
+    let exporter = IpHistoryStore::in_memory();
+    for record in [
+        shared_record("new.example", "192.0.2.1", 40, now),
+        shared_record("stale-local.example", "192.0.2.2", 80, now),
+        shared_record("fresh-local.example", "192.0.2.3", 10, now - 30 * minute),
+        shared_record("close-faster.example", "192.0.2.4", 20, now - 2 * minute),
+        shared_record("close-slower.example", "192.0.2.5", 90, now - 2 * minute),
+    ] {
+        exporter.upsert(record).unwrap();
+    }
+    let mut pending = shared_record("unverified.example", "192.0.2.6", 5, now);
+    pending.pending_validation = true;
+    exporter.upsert(pending).unwrap();
+    exporter
+        .fold_quality(
+            "new.example",
+            443,
+            &[(
+                ip("192.0.2.1"),
+                IpQuality {
+                    latency_ms: Some(40),
+                    ..IpQuality::default()
+                },
+            )],
+            0.3,
+            now,
+        )
+        .unwrap();
+    exporter
+        .mark_unusable(
+            "new.example",
+            443,
+            ip("192.0.2.9"),
+            "cert",
+            now + minute,
+            now,
+        )
+        .unwrap();
+
+    let snapshot = exporter.export_snapshot(now);
+    // 未经本机确认的记录不外传，不可用标记被剥离
+    assert!(snapshot
+        .entries
+        .iter()
+        .all(|e| e.host != "unverified.example"));
+    assert!(snapshot
+        .quality
+        .iter()
+        .all(|q| q.unusable_until_epoch_ms.is_none()));
+
+    let importer = IpHistoryStore::in_memory();
+    for record in [
+        shared_record("stale-local.example", "198.51.100.2", 30, now - 30 * minute),
+        shared_record("fresh-local.example", "198.51.100.3", 60, now),
+        shared_record("close-faster.example", "198.51.100.4", 50, now),
+        shared_record("close-slower.example", "198.51.100.5", 50, now),
+    ] {
+        importer.upsert(record).unwrap();
+    }
+    let mut expired = shared_record("expired.example", "192.0.2.7", 5, now - 7_200_000);
+    expired.expires_at_epoch_ms = now - 1;
+    let mut snapshot = snapshot;
+    snapshot.entries.push(expired);
+
+    let report = importer.import_snapshot(&snapshot, false, now).unwrap();
+    assert_eq!(report.imported, 3);
+    assert_eq!(report.kept_local, 2);
+    assert_eq!(report.discarded, 1);
+    assert_eq!(report.quality_merged, 2);
+
+    let addr = |host: &str| importer.get(host, 443).unwrap().candidate.address;
+    assert_eq!(addr("new.example"), ip("192.0.2.1"));
+    assert_eq!(addr("stale-local.example"), ip("192.0.2.2"));
+    assert_eq!(addr("fresh-local.example"), ip("198.51.100.3"));
+    assert_eq!(addr("close-faster.example"), ip("192.0.2.4"));
+    assert_eq!(addr("close-slower.example"), ip("198.51.100.5"));
+    assert!(importer
+        .quality("new.example", 443, ip("192.0.2.1"))
+        .is_some());
+    assert!(importer
+        .unusable_until("new.example", 443, ip("192.0.2.9"), now)
+        .is_none());
+
+    let mut pending = importer.pending_validation(now);
+    pending.sort();
+    assert_eq!(
+        pending,
+        vec![
+            ("close-faster.example".to_string(), 443),
+            ("new.example".to_string(), 443),
+            ("stale-local.example".to_string(), 443),
+        ]
+    );
+
+    // 覆盖模式先清空本地历史
+    let report = importer.import_snapshot(&snapshot, true, now).unwrap();
+    assert_eq!(report.kept_local, 0);
+    assert!(
+        importer
+            .get("fresh-local.example", 443)
+            .unwrap()
+            .pending_validation
+    );
+}
+
+#[test]
+fn test_circuit_breaker_state_persists_per_host() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = EffectiveIpPoolConfig::default();
+    config.runtime.failure_threshold = 2;
+    config.runtime.history_path = Some(dir.path().join("ip-history.json").display().to_string());
+    let addr: IpAddr = "192.0.2.30".parse().unwrap();
+    let stat = create_dummy_stat("192.0.2.30", 100, None);
+
+    {
+        let pool = IpPool::new(config.clone());
+        pool.report_candidate_outcome("github.com", 443, &stat, IpOutcome::Failure);
+        pool.report_candidate_outcome("github.com", 443, &stat, IpOutcome::Failure);
+        assert!(pool.is_ip_tripped_for("github.com", addr));
+        assert!(!pool.is_ip_tripped_for("codeload.github.com", addr));
+    }
+
+    // 重启后恢复按主机的熔断状态
+    let pool = IpPool::new(config);
+    assert!(pool.is_ip_tripped_for("github.com", addr));
+    assert!(!pool.is_ip_tripped_for("codeload.github.com", addr));
+    let snapshot = pool.circuit_breaker_snapshot();
+    assert_eq!(snapshot.len(), 1);
+    assert_eq!(snapshot[0].host, "github.com");
+    assert_eq!(snapshot[0].trip_count, 1);
+
+    pool.reset_circuit_breaker(addr);
+    assert!(IpPool::new(pool.config().clone())
+        .circuit_breaker_snapshot()
+        .is_empty());
+}
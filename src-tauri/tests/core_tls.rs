@@ -62,3 +62,303 @@ fn test_validate_pins_deduplication() {
     assert_eq!(validated.len(), 1);
     assert_eq!(validated[0], pin);
 }
+
+// ---------------- mTLS client certificates ----------------
+
+mod client_cert {
+    use std::io::Write;
+    use std::path::Path;
+    use std::sync::Arc;
+
+    use fireworks_collaboration_lib::core::config::model::{ClientCertCfg, CustomCaCfg, TlsCfg};
+    use fireworks_collaboration_lib::core::credential::storage::MemoryCredentialStore;
+    use fireworks_collaboration_lib::core::credential::{Credential, CredentialStore};
+    use fireworks_collaboration_lib::core::tls::client_cert::{
+        apply_client_cert, client_identity_for, describe_handshake_error, select_client_cert,
+        set_key_store,
+    };
+    use fireworks_collaboration_lib::core::tls::verifier::create_client_config;
+    use rcgen::{BasicConstraints, Certificate as RcCert, CertificateParams, IsCa};
+    use rustls::server::AllowAnyAuthenticatedClient;
+    use rustls::{Certificate, PrivateKey, RootCertStore, ServerConfig, ServerName};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    struct Pki {
+        ca: RcCert,
+        server: RcCert,
+        client: RcCert,
+    }
+
+    fn pki() -> Pki {
+        let mut ca_params = CertificateParams::new(Vec::new());
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        Pki {
+            ca: RcCert::from_params(ca_params).unwrap(),
+            server: RcCert::from_params(CertificateParams::new(vec!["localhost".to_string()]))
+                .unwrap(),
+            client: RcCert::from_params(CertificateParams::new(vec!["dev".to_string()])).unwrap(),
+        }
+    }
+
+    fn write(path: &Path, content: &[u8]) {
+        std::fs::File::create(path)
+            .unwrap()
+            .write_all(content)
+            .unwrap();
+    }
+
+    fn tls_with(certs: Vec<ClientCertCfg>) -> TlsCfg {
+        let mut tls = fireworks_collaboration_lib::core::config::model::AppConfig::default().tls;
+        tls.client_certs = certs;
+        tls
+    }
+
+    fn cert_cfg(hosts: &[&str], cert_path: &Path) -> ClientCertCfg {
+        ClientCertCfg {
+            hosts: hosts.iter().map(|h| h.to_string()).collect(),
+            cert_path: cert_path.display().to_string(),
+            key_path: None,
+            key_credential_host: None,
+            key_credential_username: None,
+        }
+    }
+
+    #[test]
+    fn selects_by_host_pattern_and_loads_pem() {
+        let pki = pki();
+        let dir = tempfile::tempdir().unwrap();
+        let cert = dir.path().join("client.pem");
+        let key = dir.path().join("client.key");
+        let combined = dir.path().join("combined.pem");
+        let cert_pem = pki.client.serialize_pem_with_signer(&pki.ca).unwrap();
+        let key_pem = pki.client.serialize_private_key_pem();
+        write(&cert, cert_pem.as_bytes());
+        write(&key, key_pem.as_bytes());
+        write(&combined, format!("{cert_pem}{key_pem}").as_bytes());
+
+        let mut separate = cert_cfg(&["*.corp.example"], &cert);
+        separate.key_path = Some(key.display().to_string());
+        let tls = tls_with(vec![separate, cert_cfg(&["gitea.example"], &combined)]);
+
+        assert!(select_client_cert(&tls, "github.com").is_none());
+        assert_eq!(
+            select_client_cert(&tls, "git.corp.example")
+                .unwrap()
+                .cert_path,
+            cert.display().to_string()
+        );
+        assert!(client_identity_for(&tls, "github.com").unwrap().is_none());
+        let id = client_identity_for(&tls, "git.corp.example")
+            .unwrap()
+            .unwrap();
+        assert_eq!(id.cert.len(), 1);
+        assert!(client_identity_for(&tls, "gitea.example")
+            .unwrap()
+            .is_some());
+
+        let missing = tls_with(vec![cert_cfg(
+            &["broken.example"],
+            &dir.path().join("missing.pem"),
+        )]);
+        let err = client_identity_for(&missing, "broken.example")
+            .err()
+            .expect("missing file must fail");
+        assert!(err.starts_with("client certificate"), "{err}");
+    }
+
+    #[test]
+    fn loads_pkcs12_with_passphrase_from_credential_store() {
+        let pki = pki();
+        let dir = tempfile::tempdir().unwrap();
+        let p12_path = dir.path().join("client.p12");
+        let pkey = openssl::pkey::PKey::private_key_from_pem(
+            pki.client.serialize_private_key_pem().as_bytes(),
+        )
+        .unwrap();
+        let x509 =
+            openssl::x509::X509::from_der(&pki.client.serialize_der_with_signer(&pki.ca).unwrap())
+                .unwrap();
+        let p12 = openssl::pkcs12::Pkcs12::builder()
+            .name("dev")
+            .pkey(&pkey)
+            .cert(&x509)
+            .build2("s3cret")
+            .unwrap();
+        write(&p12_path, &p12.to_der().unwrap());
+
+        let mut cfg = cert_cfg(&["gitea.example"], &p12_path);
+        cfg.key_credential_host = Some("mtls:gitea.example".into());
+        let tls = tls_with(vec![cfg]);
+
+        set_key_store(None);
+        let err = client_identity_for(&tls, "gitea.example")
+            .err()
+            .expect("locked store must fail");
+        assert!(err.contains("credential store"), "{err}");
+
+        let store = Arc::new(MemoryCredentialStore::new());
+        store
+            .add(Credential::new(
+                "mtls:gitea.example".into(),
+                "dev".into(),
+                "s3cret".into(),
+            ))
+            .unwrap();
+        set_key_store(Some(store));
+        let id = client_identity_for(&tls, "gitea.example").unwrap().unwrap();
+        assert_eq!(id.cert[0].0, x509.to_der().unwrap());
+        set_key_store(None);
+    }
+
+    #[test]
+    fn rejection_alerts_are_described_as_client_certificate_errors() {
+        let described =
+            describe_handshake_error("gitea.example", "received fatal alert: CertificateRequired");
+        assert!(described.starts_with("client certificate rejected by gitea.example"));
+        assert_eq!(
+            describe_handshake_error("gitea.example", "received fatal alert: HandshakeFailure"),
+            "received fatal alert: HandshakeFailure"
+        );
+    }
+
+    #[tokio::test]
+    async fn handshake_presents_certificate_only_when_configured() {
+        let pki = pki();
+        let dir = tempfile::tempdir().unwrap();
+        let ca_path = dir.path().join("ca.pem");
+        let cert_path = dir.path().join("client.pem");
+        write(&ca_path, pki.ca.serialize_pem().unwrap().as_bytes());
+        write(
+            &cert_path,
+            format!(
+                "{}{}",
+                pki.client.serialize_pem_with_signer(&pki.ca).unwrap(),
+                pki.client.serialize_private_key_pem()
+            )
+            .as_bytes(),
+        );
+
+        let mut roots = RootCertStore::empty();
+        roots
+            .add(&Certificate(pki.ca.serialize_der().unwrap()))
+            .unwrap();
+        let server_cfg = ServerConfig::builder()
+            .with_safe_defaults()
+            .with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots).boxed())
+            .with_single_cert(
+                vec![Certificate(
+                    pki.server.serialize_der_with_signer(&pki.ca).unwrap(),
+                )],
+                PrivateKey(pki.server.serialize_private_key_der()),
+            )
+            .unwrap();
+        let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(server_cfg));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (tcp, _) = listener.accept().await.unwrap();
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    if let Ok(mut tls) = acceptor.accept(tcp).await {
+                        let mut buf = [0u8; 4];
+                        if tls.read_exact(&mut buf).await.is_ok() {
+                            tls.write_all(b"pong").await.ok();
+                        }
+                    }
+                });
+            }
+        });
+
+        let mut tls = tls_with(vec![cert_cfg(&["localhost"], &cert_path)]);
+        tls.custom_cas = vec![CustomCaCfg {
+            path: ca_path.display().to_string(),
+            hosts: vec!["localhost".into()],
+        }];
+        let roundtrip = |host: &'static str| {
+            let mut cfg = create_client_config(&tls);
+            let presented = apply_client_cert(&mut cfg, &tls, host).unwrap();
+            async move {
+                let tcp = tokio::net::TcpStream::connect(addr).await.unwrap();
+                let connector = tokio_rustls::TlsConnector::from(Arc::new(cfg));
+                let server_name = ServerName::try_from("localhost").unwrap();
+                let res: std::io::Result<[u8; 4]> = async {
+                    let mut s = connector.connect(server_name, tcp).await?;
+                    s.write_all(b"ping").await?;
+                    let mut buf = [0u8; 4];
+                    s.read_exact(&mut buf).await?;
+                    Ok(buf)
+                }
+                .await;
+                (presented, res)
+            }
+        };
+
+        let (presented, ok) = roundtrip("localhost").await;
+        assert!(presented);
+        assert_eq!(&ok.unwrap(), b"pong");
+
+        // 选择规则不匹配时不出示证书，服务端拒绝后错误归类为客户端证书问题
+        let (presented, err) = roundtrip("other.example").await;
+        assert!(!presented);
+        let described = describe_handshake_error("localhost", &err.unwrap_err().to_string());
+        assert!(
+            described.starts_with("client certificate rejected"),
+            "{described}"
+        );
+    }
+
+    #[tokio::test]
+    async fn capture_spki_reads_live_chain_without_pins() {
+        use fireworks_collaboration_lib::core::config::loader;
+        use fireworks_collaboration_lib::core::config::model::SpkiPinPolicy;
+        use fireworks_collaboration_lib::core::tls::pin_policy::capture_spki_fingerprints;
+        use fireworks_collaboration_lib::core::tls::spki::compute_spki_sha256_b64;
+
+        let pki = pki();
+        let dir = tempfile::tempdir().unwrap();
+        loader::set_global_base_dir(dir.path());
+        let ca_path = dir.path().join("ca.pem");
+        write(&ca_path, pki.ca.serialize_pem().unwrap().as_bytes());
+        let leaf = Certificate(pki.server.serialize_der_with_signer(&pki.ca).unwrap());
+        let server_cfg = ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![leaf.clone()],
+                PrivateKey(pki.server.serialize_private_key_der()),
+            )
+            .unwrap();
+        let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(server_cfg));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (tcp, _) = listener.accept().await.unwrap();
+            let _ = acceptor.accept(tcp).await;
+        });
+
+        let mut tls = tls_with(Vec::new());
+        tls.custom_cas = vec![CustomCaCfg {
+            path: ca_path.display().to_string(),
+            hosts: vec!["localhost".into()],
+        }];
+        // 现有 pin 即使失配也不影响采集
+        tls.pin_policies = vec![SpkiPinPolicy {
+            hosts: vec!["localhost".into()],
+            primary: vec!["47DEQpj8HBSa-_TImW-5JCeuQeRkm5NMpJWZG3hSuFU".into()],
+            backup: Vec::new(),
+            expires_at: None,
+            report_only: false,
+        }];
+        let captured =
+            capture_spki_fingerprints(&tls, "localhost", port, std::time::Duration::from_secs(5))
+                .await
+                .expect("capture");
+        assert_eq!(captured.len(), 1);
+        assert_eq!(captured[0].position, 0);
+        assert_eq!(captured[0].spki_sha256, compute_spki_sha256_b64(&leaf).0);
+        assert!(captured[0].not_after.is_some());
+        let log = std::fs::read_to_string(dir.path().join("cert-fp.log")).unwrap_or_default();
+        assert!(log.contains(&captured[0].spki_sha256));
+    }
+}
@@ -0,0 +1,338 @@
+//! 审计日志哈希链与索引查询测试
+//!
+//! 覆盖篡改检测（编辑/删除/重排）、保留策略截断后的链完整性、
+//! 旧版日志迁移、加载时断链的封存与新链段，以及按操作类型/主机/结果/时间范围查询。
+
+use fireworks_collaboration_lib::core::credential::audit::{
+    AuditChainViolationKind, AuditLogger, AuditQuery, OperationType,
+};
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tempfile::TempDir;
+
+fn seed(logger: &AuditLogger) {
+    logger.log_operation(
+        OperationType::Add,
+        "github.com",
+        "alice",
+        Some("t1"),
+        true,
+        None,
+    );
+    logger.log_operation(OperationType::Get, "github.com", "alice", None, true, None);
+    logger.log_operation(
+        OperationType::Get,
+        "gitlab.com",
+        "bob",
+        None,
+        false,
+        Some("not found".to_string()),
+    );
+    logger.log_operation(
+        OperationType::Remove,
+        "GitHub.com",
+        "alice",
+        None,
+        true,
+        None,
+    );
+}
+
+fn read_json(path: &Path) -> serde_json::Value {
+    serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+}
+
+fn write_json(path: &Path, value: &serde_json::Value) {
+    fs::write(path, serde_json::to_string_pretty(value).unwrap()).unwrap();
+}
+
+#[test]
+fn test_chain_verifies_after_reload() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("audit.json");
+    seed(&AuditLogger::with_log_file(true, &path).unwrap());
+
+    assert!(
+        dir.path().join("audit.json.key").exists(),
+        "应生成链密钥文件"
+    );
+
+    let reopened = AuditLogger::with_log_file(true, &path).unwrap();
+    reopened.log_operation(OperationType::List, "github.com", "", None, true, None);
+    let report = reopened.verify_chain().unwrap();
+    assert!(report.valid, "未篡改的链应通过校验: {report:?}");
+    assert_eq!(report.checked, 5);
+    assert_eq!((report.first_seq, report.last_seq), (Some(0), Some(4)));
+}
+
+#[test]
+fn test_chain_detects_edited_record() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("audit.json");
+    let logger = AuditLogger::with_log_file(true, &path).unwrap();
+    seed(&logger);
+
+    let mut value = read_json(&path);
+    value["records"][2]["success"] = serde_json::json!(true);
+    write_json(&path, &value);
+
+    let report = logger.verify_chain().unwrap();
+    assert!(!report.valid);
+    assert_eq!(report.violations.len(), 1);
+    assert_eq!(report.violations[0].seq, 2);
+    assert_eq!(
+        report.violations[0].kind,
+        AuditChainViolationKind::MacMismatch
+    );
+}
+
+#[test]
+fn test_chain_detects_deleted_and_reordered_records() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("audit.json");
+    let logger = AuditLogger::with_log_file(true, &path).unwrap();
+    seed(&logger);
+    let original = read_json(&path);
+
+    // 删除中间记录
+    let mut deleted = original.clone();
+    deleted["records"].as_array_mut().unwrap().remove(1);
+    write_json(&path, &deleted);
+    let report = logger.verify_chain().unwrap();
+    assert!(!report.valid);
+    assert!(report
+        .violations
+        .iter()
+        .any(|v| v.kind == AuditChainViolationKind::BrokenLink && v.seq == 2));
+
+    // 删除链首记录（无锚点时同样可检出）
+    let mut head_removed = original.clone();
+    head_removed["records"].as_array_mut().unwrap().remove(0);
+    write_json(&path, &head_removed);
+    assert!(!logger.verify_chain().unwrap().valid);
+
+    // 交换两条记录
+    let mut swapped = original;
+    swapped["records"].as_array_mut().unwrap().swap(0, 1);
+    write_json(&path, &swapped);
+    assert!(!logger.verify_chain().unwrap().valid);
+}
+
+#[test]
+fn test_retention_keeps_chain_intact() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("audit.json");
+    let logger = AuditLogger::with_log_file(true, &path).unwrap();
+    seed(&logger);
+
+    // retention_days=0 会清掉全部现有记录，只留下锚点
+    let removed = logger.cleanup_expired_logs(0).unwrap();
+    assert_eq!(removed, 4);
+    logger.log_operation(OperationType::Add, "github.com", "carol", None, true, None);
+
+    let report = logger.verify_chain().unwrap();
+    assert!(report.valid, "截断后新记录应接续原链: {report:?}");
+    assert_eq!(report.removed_by_retention, 4);
+    assert_eq!(report.first_seq, Some(4), "序号不应因清理而重置");
+
+    // 重新加载后仍然有效
+    let reopened = AuditLogger::with_log_file(true, &path).unwrap();
+    assert!(reopened.verify_chain().unwrap().valid);
+
+    // 伪造锚点以掩盖删除应被检出
+    let mut value = read_json(&path);
+    value["anchor"]["removed"] = serde_json::json!(0);
+    write_json(&path, &value);
+    let report = reopened.verify_chain().unwrap();
+    assert!(report
+        .violations
+        .iter()
+        .any(|v| v.kind == AuditChainViolationKind::InvalidAnchor));
+}
+
+#[test]
+fn test_retention_removes_only_expired_prefix() {
+    let logger = AuditLogger::new(false);
+    seed(&logger);
+
+    assert_eq!(logger.cleanup_expired_logs(30).unwrap(), 0);
+    assert_eq!(logger.event_count(), 4);
+    assert!(logger.verify_chain().unwrap().valid);
+}
+
+#[test]
+fn test_legacy_array_log_is_migrated() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("audit.json");
+    let legacy = r#"[
+        {"operation":"add","host":"github.com","username":"alice","timestamp":1700000000,"success":true},
+        {"operation":"get","host":"github.com","username":"alice","timestamp":1700000100,"success":true}
+    ]"#;
+    fs::write(&path, legacy).unwrap();
+
+    let logger = AuditLogger::with_log_file(false, &path).unwrap();
+    assert_eq!(logger.event_count(), 2);
+    assert_eq!(read_json(&path)["version"], 2, "旧版日志应被改写为链格式");
+    assert!(logger.verify_chain().unwrap().valid);
+}
+
+#[test]
+fn test_missing_chain_key_fails_verification() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("audit.json");
+    seed(&AuditLogger::with_log_file(true, &path).unwrap());
+
+    fs::remove_file(dir.path().join("audit.json.key")).unwrap();
+    let logger = AuditLogger::with_log_file(true, &path).unwrap();
+    let chain_break = logger
+        .chain_break()
+        .expect("新密钥无法校验旧记录，应记录断点");
+    assert_eq!(chain_break.records, 4);
+    let archived = read_json(Path::new(&chain_break.archived_path));
+    assert_eq!(
+        archived["records"].as_array().unwrap().len(),
+        4,
+        "旧记录应被封存以保留证据"
+    );
+}
+
+#[test]
+fn test_tampered_log_starts_new_segment_on_load() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("audit.json");
+    seed(&AuditLogger::with_log_file(true, &path).unwrap());
+
+    let mut value = read_json(&path);
+    value["records"][1]["success"] = serde_json::json!(false);
+    write_json(&path, &value);
+
+    let logger = AuditLogger::with_log_file(true, &path).unwrap();
+    logger.log_operation(OperationType::List, "github.com", "", None, true, None);
+
+    // 新记录不依附于被篡改的历史：新链段可独立通过校验，断点通过报告暴露
+    let report = logger.verify_chain().unwrap();
+    assert!(report.valid, "新链段应可校验: {report:?}");
+    assert_eq!((report.first_seq, report.last_seq), (Some(4), Some(5)));
+    assert_eq!(report.removed_by_retention, 4);
+    let chain_break = report.chain_break.expect("应暴露断点");
+    assert_eq!(chain_break.violations.len(), 1);
+    assert_eq!(chain_break.violations[0].seq, 1);
+    assert_eq!(
+        chain_break.violations[0].kind,
+        AuditChainViolationKind::MacMismatch
+    );
+    assert_eq!(read_json(Path::new(&chain_break.archived_path)), value);
+
+    // 断点本身作为一条失败的校验记录写入链中
+    let marker = logger.query(&AuditQuery {
+        operation: Some(OperationType::Validate),
+        success: Some(false),
+        ..Default::default()
+    });
+    assert_eq!(marker.len(), 1);
+    assert!(marker[0]
+        .event
+        .error
+        .as_deref()
+        .unwrap()
+        .contains(&chain_break.archived_path));
+}
+
+#[test]
+fn test_query_by_operation_host_and_result() {
+    let logger = AuditLogger::new(false);
+    seed(&logger);
+
+    let gets = logger.query(&AuditQuery {
+        operation: Some(OperationType::Get),
+        ..Default::default()
+    });
+    assert_eq!(gets.len(), 2);
+
+    let github = logger.query(&AuditQuery {
+        host: Some("github.com".to_string()),
+        ..Default::default()
+    });
+    assert_eq!(github.len(), 3, "主机匹配应不区分大小写");
+
+    let failures = logger.query(&AuditQuery {
+        success: Some(false),
+        ..Default::default()
+    });
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].event.host, "gitlab.com");
+
+    let combined = logger.query(&AuditQuery {
+        operation: Some(OperationType::Get),
+        host: Some("github.com".to_string()),
+        success: Some(true),
+        ..Default::default()
+    });
+    assert_eq!(combined.len(), 1);
+    assert_eq!(combined[0].seq, 1);
+
+    let latest = logger.query(&AuditQuery {
+        limit: Some(2),
+        ..Default::default()
+    });
+    assert_eq!(
+        latest.iter().map(|r| r.seq).collect::<Vec<_>>(),
+        vec![2, 3],
+        "limit 应保留最近的记录"
+    );
+}
+
+#[test]
+fn test_query_by_time_range() {
+    let logger = AuditLogger::new(false);
+    seed(&logger);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    let recent = logger.query(&AuditQuery {
+        since: Some(now - 60),
+        until: Some(now + 60),
+        ..Default::default()
+    });
+    assert_eq!(recent.len(), 4);
+
+    let past = logger.query(&AuditQuery {
+        until: Some(now - 3600),
+        ..Default::default()
+    });
+    assert!(past.is_empty());
+
+    let inverted = logger.query(&AuditQuery {
+        since: Some(now + 10),
+        until: Some(now - 10),
+        ..Default::default()
+    });
+    assert!(inverted.is_empty());
+}
+
+#[test]
+fn test_clear_keeps_persisted_chain() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("audit.json");
+    let logger = AuditLogger::with_log_file(true, &path).unwrap();
+    seed(&logger);
+
+    logger.clear();
+    assert_eq!(logger.event_count(), 0);
+    assert!(logger.query(&AuditQuery::default()).is_empty());
+    assert_eq!(read_json(&path)["records"].as_array().unwrap().len(), 4);
+
+    // 后续记录接续原链写入文件，清空不会删除已持久化的记录
+    logger.log_operation(OperationType::Get, "github.com", "alice", None, true, None);
+    assert_eq!(logger.event_count(), 1);
+    let file = read_json(&path);
+    assert_eq!(file["records"].as_array().unwrap().len(), 5);
+    assert!(file["anchor"].is_null());
+    assert!(logger.verify_chain().unwrap().valid);
+
+    let reloaded = AuditLogger::with_log_file(true, &path).unwrap();
+    assert_eq!(reloaded.event_count(), 5);
+}
@@ -0,0 +1,316 @@
+//! 凭证加密备份导出/导入测试
+//!
+//! 覆盖往返一致性、跨后端导入、冲突策略、口令错误/篡改检测以及覆盖失败时的回滚。
+
+use fireworks_collaboration_lib::core::credential::{
+    backup::{CredentialBackup, ImportAction, ImportConflictStrategy},
+    config::CredentialConfig,
+    file_store::{Argon2Params, EncryptedFileStore},
+    model::Credential,
+    storage::{
+        CredentialStore, CredentialStoreError, CredentialStoreResult, MemoryCredentialStore,
+    },
+};
+use std::time::{Duration, SystemTime};
+use tempfile::TempDir;
+
+fn fast_params() -> Argon2Params {
+    Argon2Params {
+        m_cost: 1024,
+        t_cost: 1,
+        p_cost: 1,
+    }
+}
+
+fn cred_at(host: &str, user: &str, token: &str, age_secs: u64) -> Credential {
+    let mut cred = Credential::new(host.to_string(), user.to_string(), token.to_string());
+    cred.created_at = SystemTime::now() - Duration::from_secs(age_secs);
+    cred
+}
+
+#[test]
+fn test_backup_roundtrip_preserves_fields() {
+    let source = MemoryCredentialStore::new();
+    let mut cred = cred_at("github.com", "alice", "ghp_alice", 7200);
+    cred.expires_at = Some(SystemTime::now() + Duration::from_secs(86400));
+    cred.last_used_at = Some(SystemTime::now() - Duration::from_secs(60));
+    source.add(cred.clone()).unwrap();
+    source
+        .add(cred_at("gitlab.com", "bob", "glpat_bob", 10))
+        .unwrap();
+
+    let json = CredentialBackup::export(&source, "passphrase", fast_params())
+        .unwrap()
+        .to_json()
+        .unwrap();
+    assert!(!json.contains("ghp_alice"), "备份包不应包含明文令牌");
+    assert!(!json.contains("github.com"), "备份包不应包含明文主机");
+
+    let target = MemoryCredentialStore::new();
+    let report = CredentialBackup::from_json(&json)
+        .unwrap()
+        .import_into(&target, "passphrase", ImportConflictStrategy::Skip)
+        .unwrap();
+    assert_eq!(report.imported, 2);
+    assert_eq!(report.failed, 0);
+
+    let restored = target.get("github.com", Some("alice")).unwrap().unwrap();
+    assert_eq!(restored.password_or_token, "ghp_alice");
+    let secs = |t: SystemTime| t.duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs();
+    assert_eq!(secs(restored.created_at), secs(cred.created_at));
+    assert_eq!(
+        restored.expires_at.map(secs),
+        cred.expires_at.map(secs),
+        "过期时间应被保留"
+    );
+    assert!(restored.last_used_at.is_some());
+}
+
+#[test]
+fn test_backup_cross_backend_file_to_memory() {
+    let dir = TempDir::new().unwrap();
+    let config = CredentialConfig::new()
+        .with_file_path(dir.path().join("creds.enc").to_string_lossy().to_string());
+    let file_store = EncryptedFileStore::new(&config)
+        .unwrap()
+        .with_argon2_params(fast_params())
+        .unwrap();
+    file_store
+        .set_master_password("master".to_string())
+        .unwrap();
+    file_store
+        .add(cred_at("github.com", "alice", "ghp_file", 0))
+        .unwrap();
+
+    let backup = CredentialBackup::export(&file_store, "portable", fast_params()).unwrap();
+
+    let memory = MemoryCredentialStore::new();
+    let report = backup
+        .import_into(&memory, "portable", ImportConflictStrategy::Skip)
+        .unwrap();
+    assert_eq!(report.imported, 1);
+    assert_eq!(
+        memory
+            .get("github.com", Some("alice"))
+            .unwrap()
+            .unwrap()
+            .password_or_token,
+        "ghp_file"
+    );
+}
+
+#[test]
+fn test_backup_wrong_passphrase_fails_without_changes() {
+    let source = MemoryCredentialStore::new();
+    source.add(cred_at("github.com", "alice", "t", 0)).unwrap();
+    let backup = CredentialBackup::export(&source, "right", fast_params()).unwrap();
+
+    let target = MemoryCredentialStore::new();
+    let result = backup.import_into(&target, "wrong", ImportConflictStrategy::Overwrite);
+    assert!(result.is_err());
+    assert!(target.list_all().unwrap().is_empty());
+}
+
+#[test]
+fn test_backup_tampered_header_detected() {
+    let source = MemoryCredentialStore::new();
+    source.add(cred_at("github.com", "alice", "t", 0)).unwrap();
+    let json = CredentialBackup::export(&source, "pass", fast_params())
+        .unwrap()
+        .to_json()
+        .unwrap();
+
+    let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    value["createdAt"] = serde_json::json!(1);
+    let tampered = CredentialBackup::from_json(&value.to_string()).unwrap();
+    assert!(
+        tampered.decrypt("pass").is_err(),
+        "包头被篡改时应解密失败（AAD 校验）"
+    );
+}
+
+#[test]
+fn test_backup_rejects_unknown_format() {
+    let json = r#"{"format":"other","version":1,"createdAt":0,
+        "kdf":{"mCost":1024,"tCost":1,"pCost":1},"salt":"","nonce":"","ciphertext":""}"#;
+    assert!(CredentialBackup::from_json(json).is_err());
+    assert!(CredentialBackup::export(&MemoryCredentialStore::new(), "", fast_params()).is_err());
+}
+
+fn conflicting_setup() -> (MemoryCredentialStore, CredentialBackup) {
+    // 备份：alice 较新，bob 较旧，carol 为新增
+    let source = MemoryCredentialStore::new();
+    source
+        .add(cred_at("github.com", "alice", "backup_alice", 10))
+        .unwrap();
+    source
+        .add(cred_at("github.com", "bob", "backup_bob", 10_000))
+        .unwrap();
+    source
+        .add(cred_at("github.com", "carol", "backup_carol", 10))
+        .unwrap();
+    let backup = CredentialBackup::export(&source, "pass", fast_params()).unwrap();
+
+    let target = MemoryCredentialStore::new();
+    target
+        .add(cred_at("github.com", "alice", "local_alice", 5_000))
+        .unwrap();
+    target
+        .add(cred_at("github.com", "bob", "local_bob", 100))
+        .unwrap();
+    (target, backup)
+}
+
+fn token(store: &MemoryCredentialStore, user: &str) -> String {
+    store
+        .get("github.com", Some(user))
+        .unwrap()
+        .unwrap()
+        .password_or_token
+}
+
+#[test]
+fn test_import_strategy_skip() {
+    let (target, backup) = conflicting_setup();
+    let report = backup
+        .import_into(&target, "pass", ImportConflictStrategy::Skip)
+        .unwrap();
+    assert_eq!((report.imported, report.skipped), (1, 2));
+    assert_eq!(token(&target, "alice"), "local_alice");
+    assert_eq!(token(&target, "bob"), "local_bob");
+    assert_eq!(token(&target, "carol"), "backup_carol");
+}
+
+#[test]
+fn test_import_strategy_overwrite() {
+    let (target, backup) = conflicting_setup();
+    let report = backup
+        .import_into(&target, "pass", ImportConflictStrategy::Overwrite)
+        .unwrap();
+    assert_eq!((report.imported, report.overwritten), (1, 2));
+    assert_eq!(token(&target, "alice"), "backup_alice");
+    assert_eq!(token(&target, "bob"), "backup_bob");
+}
+
+#[test]
+fn test_import_strategy_keep_newest() {
+    let (target, backup) = conflicting_setup();
+    let report = backup
+        .import_into(&target, "pass", ImportConflictStrategy::KeepNewest)
+        .unwrap();
+    assert_eq!(
+        (report.imported, report.overwritten, report.skipped),
+        (1, 1, 1)
+    );
+    assert_eq!(token(&target, "alice"), "backup_alice", "备份较新应覆盖");
+    assert_eq!(token(&target, "bob"), "local_bob", "本地较新应保留");
+
+    let bob = report.entries.iter().find(|e| e.username == "bob").unwrap();
+    assert_eq!(bob.action, ImportAction::Skipped);
+}
+
+/// 写入备份来源令牌时失败的存储（模拟 I/O/加密错误）
+struct FailingAddStore(MemoryCredentialStore);
+
+impl CredentialStore for FailingAddStore {
+    fn get(&self, host: &str, username: Option<&str>) -> CredentialStoreResult<Option<Credential>> {
+        self.0.get(host, username)
+    }
+    fn add(&self, credential: Credential) -> CredentialStoreResult<()> {
+        if credential.password_or_token.starts_with("backup_") {
+            return Err(CredentialStoreError::AccessError("disk full".to_string()));
+        }
+        self.0.add(credential)
+    }
+    fn remove(&self, host: &str, username: &str) -> CredentialStoreResult<()> {
+        self.0.remove(host, username)
+    }
+    fn list(&self) -> CredentialStoreResult<Vec<Credential>> {
+        self.0.list()
+    }
+    fn list_all(&self) -> CredentialStoreResult<Vec<Credential>> {
+        self.0.list_all()
+    }
+    fn update_last_used(&self, host: &str, username: &str) -> CredentialStoreResult<()> {
+        self.0.update_last_used(host, username)
+    }
+}
+
+#[test]
+fn test_import_overwrite_failure_keeps_existing() {
+    let (target, backup) = conflicting_setup();
+    let store = FailingAddStore(target);
+    let report = backup
+        .import_into(&store, "pass", ImportConflictStrategy::Overwrite)
+        .unwrap();
+    assert_eq!((report.failed, report.overwritten), (3, 0));
+    assert_eq!(
+        token(&store.0, "alice"),
+        "local_alice",
+        "覆盖失败应恢复原凭证"
+    );
+    assert_eq!(token(&store.0, "bob"), "local_bob");
+    assert!(store.0.get("github.com", Some("carol")).unwrap().is_none());
+}
+
+#[test]
+fn test_backup_rejects_excessive_kdf_params() {
+    let source = MemoryCredentialStore::new();
+    source.add(cred_at("github.com", "alice", "t", 0)).unwrap();
+    let json = CredentialBackup::export(&source, "pass", fast_params())
+        .unwrap()
+        .to_json()
+        .unwrap();
+
+    for (field, value) in [("mCost", 4_000_000_000u32), ("tCost", 1_000), ("pCost", 64)] {
+        let mut bundle: serde_json::Value = serde_json::from_str(&json).unwrap();
+        bundle["kdf"][field] = serde_json::json!(value);
+        let crafted = CredentialBackup::from_json(&bundle.to_string()).unwrap();
+        let err = crafted.decrypt("pass").unwrap_err();
+        assert!(err.contains("exceed limits"), "{field}: {err}");
+    }
+}
+
+/// 无法读取已有凭证的存储（如文件损坏或解密失败）
+struct UnreadableStore(MemoryCredentialStore);
+
+impl CredentialStore for UnreadableStore {
+    fn get(
+        &self,
+        _host: &str,
+        _username: Option<&str>,
+    ) -> CredentialStoreResult<Option<Credential>> {
+        Err(CredentialStoreError::CryptoError("bad key".to_string()))
+    }
+    fn add(&self, credential: Credential) -> CredentialStoreResult<()> {
+        self.0.add(credential)
+    }
+    fn remove(&self, host: &str, username: &str) -> CredentialStoreResult<()> {
+        self.0.remove(host, username)
+    }
+    fn list(&self) -> CredentialStoreResult<Vec<Credential>> {
+        Err(CredentialStoreError::CryptoError("bad key".to_string()))
+    }
+    fn list_all(&self) -> CredentialStoreResult<Vec<Credential>> {
+        Err(CredentialStoreError::CryptoError("bad key".to_string()))
+    }
+    fn update_last_used(&self, host: &str, username: &str) -> CredentialStoreResult<()> {
+        self.0.update_last_used(host, username)
+    }
+}
+
+#[test]
+fn test_import_propagates_store_read_errors() {
+    let (target, backup) = conflicting_setup();
+    let store = UnreadableStore(target);
+    let err = backup
+        .import_into(&store, "pass", ImportConflictStrategy::Overwrite)
+        .unwrap_err();
+    assert!(err.contains("Failed to list existing credentials"), "{err}");
+    assert_eq!(
+        token(&store.0, "alice"),
+        "local_alice",
+        "读取失败时不应写入"
+    );
+    assert!(store.0.get("github.com", Some("carol")).unwrap().is_none());
+}
@@ -4,6 +4,9 @@
 
 mod advanced_concurrent_tests;
 mod audit_advanced_tests;
+mod audit_chain_tests;
+mod backup_tests;
+mod binding_tests;
 mod boundary_tests;
 mod command_tests;
 mod encryption_tests;
@@ -13,6 +16,7 @@ mod file_corruption_tests;
 mod key_cache_tests;
 mod model_tests; // Credential 模型测试
 mod platform_integration;
+mod rekey_tests;
 mod security_audit_tests;
 mod security_enhancement_tests;
 mod stress_tests;
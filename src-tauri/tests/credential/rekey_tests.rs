@@ -0,0 +1,208 @@
+//! 主密码轮换与重新加密测试
+//!
+//! 验证 `EncryptedFileStore::change_master_password` 的解密/重新加密、
+//! Argon2 参数迁移以及失败回滚行为。
+
+use fireworks_collaboration_lib::core::credential::{
+    config::CredentialConfig,
+    file_store::{Argon2Params, EncryptedFileStore},
+    model::Credential,
+    storage::CredentialStore,
+};
+use std::fs;
+use tempfile::TempDir;
+
+/// 测试用低成本参数，避免 64MB Argon2 拖慢测试
+fn fast_params() -> Argon2Params {
+    Argon2Params {
+        m_cost: 1024,
+        t_cost: 1,
+        p_cost: 1,
+    }
+}
+
+fn open_store(dir: &TempDir, password: &str) -> EncryptedFileStore {
+    let path = dir.path().join("creds.enc");
+    let config = CredentialConfig::new().with_file_path(path.to_string_lossy().to_string());
+    let store = EncryptedFileStore::new(&config)
+        .expect("应该创建文件存储")
+        .with_argon2_params(fast_params())
+        .expect("参数应有效");
+    store
+        .set_master_password(password.to_string())
+        .expect("应该设置主密码");
+    store
+}
+
+fn seed(store: &EncryptedFileStore) {
+    for (host, user, token) in [
+        ("github.com", "alice", "ghp_alice_token"),
+        ("gitlab.com", "bob", "glpat_bob_token"),
+    ] {
+        store
+            .add(Credential::new(
+                host.to_string(),
+                user.to_string(),
+                token.to_string(),
+            ))
+            .expect("应该添加凭证");
+    }
+}
+
+#[test]
+fn test_change_master_password_reencrypts_all_entries() {
+    let dir = TempDir::new().unwrap();
+    let store = open_store(&dir, "old-password");
+    seed(&store);
+
+    let before = fs::read_to_string(dir.path().join("creds.enc")).unwrap();
+    let report = store
+        .change_master_password("old-password", "new-password", None)
+        .expect("轮换应成功");
+    assert_eq!(report.credentials_rekeyed, 2);
+    assert_eq!(report.previous_params, report.new_params);
+
+    let after = fs::read_to_string(dir.path().join("creds.enc")).unwrap();
+    let before_json: serde_json::Value = serde_json::from_str(&before).unwrap();
+    let after_json: serde_json::Value = serde_json::from_str(&after).unwrap();
+    assert_ne!(
+        before_json["salt"], after_json["salt"],
+        "轮换后应使用新盐值"
+    );
+
+    // 同一实例已切换到新密码
+    let cred = store.get("github.com", Some("alice")).unwrap().unwrap();
+    assert_eq!(cred.password_or_token, "ghp_alice_token");
+
+    // 新实例：新密码可读，旧密码不可读
+    let reopened = open_store(&dir, "new-password");
+    assert_eq!(reopened.list().unwrap().len(), 2);
+    let stale = open_store(&dir, "old-password");
+    assert!(stale.list().is_err(), "旧密码应无法解密");
+}
+
+#[test]
+fn test_change_master_password_wrong_old_password_keeps_file() {
+    let dir = TempDir::new().unwrap();
+    let store = open_store(&dir, "correct-password");
+    seed(&store);
+    let path = dir.path().join("creds.enc");
+    let before = fs::read(&path).unwrap();
+
+    let result = store.change_master_password("wrong-password", "new-password", None);
+    assert!(result.is_err(), "旧密码错误时应失败");
+
+    assert_eq!(fs::read(&path).unwrap(), before, "失败时文件不应被修改");
+    assert!(!dir.path().join("creds.enc.bak").exists());
+    assert!(!dir.path().join("creds.enc.tmp").exists());
+    assert_eq!(store.list().unwrap().len(), 2, "原密码应仍可用");
+}
+
+#[test]
+fn test_change_master_password_migrates_argon2_params() {
+    let dir = TempDir::new().unwrap();
+    let store = open_store(&dir, "old-password");
+    seed(&store);
+
+    let hardened = Argon2Params {
+        m_cost: 2048,
+        t_cost: 2,
+        p_cost: 1,
+    };
+    let report = store
+        .change_master_password("old-password", "new-password", Some(hardened))
+        .expect("轮换应成功");
+    assert_eq!(report.previous_params, fast_params());
+    assert_eq!(report.new_params, hardened);
+    assert_eq!(store.argon2_params(), hardened);
+
+    let content = fs::read_to_string(dir.path().join("creds.enc")).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&content).unwrap();
+    assert_eq!(json["kdf"]["mCost"], 2048);
+    assert_eq!(json["kdf"]["tCost"], 2);
+
+    // 新实例即使默认参数不同，也按文件头参数解密
+    let path = dir.path().join("creds.enc");
+    let config = CredentialConfig::new().with_file_path(path.to_string_lossy().to_string());
+    let reopened = EncryptedFileStore::new(&config).unwrap();
+    reopened
+        .set_master_password("new-password".to_string())
+        .unwrap();
+    assert_eq!(reopened.list().unwrap().len(), 2);
+    assert_eq!(reopened.argon2_params(), hardened);
+}
+
+#[test]
+fn test_change_master_password_rejects_invalid_params() {
+    let dir = TempDir::new().unwrap();
+    let store = open_store(&dir, "old-password");
+    seed(&store);
+
+    let invalid = Argon2Params {
+        m_cost: 1,
+        t_cost: 0,
+        p_cost: 0,
+    };
+    assert!(store
+        .change_master_password("old-password", "new-password", Some(invalid))
+        .is_err());
+    assert_eq!(store.list().unwrap().len(), 2);
+}
+
+#[test]
+fn test_change_master_password_without_existing_file() {
+    let dir = TempDir::new().unwrap();
+    let store = open_store(&dir, "old-password");
+
+    let report = store
+        .change_master_password("old-password", "new-password", None)
+        .expect("空存储也应允许轮换");
+    assert_eq!(report.credentials_rekeyed, 0);
+
+    let reopened = open_store(&dir, "new-password");
+    assert!(reopened.list().unwrap().is_empty());
+}
+
+#[test]
+fn test_change_master_password_preserves_expired_entries() {
+    use std::time::{Duration, SystemTime};
+
+    let dir = TempDir::new().unwrap();
+    let store = open_store(&dir, "old-password");
+    let mut expired = Credential::new_with_expiry(
+        "old.example.com".to_string(),
+        "carol".to_string(),
+        "legacy".to_string(),
+        SystemTime::now() - Duration::from_secs(60),
+    );
+    expired.created_at = SystemTime::now() - Duration::from_secs(3600);
+    store.add(expired).unwrap();
+
+    store
+        .change_master_password("old-password", "new-password", None)
+        .unwrap();
+    assert_eq!(store.list_all().unwrap().len(), 1, "过期凭证也应一并迁移");
+}
+
+#[test]
+fn test_tampered_kdf_header_rejected_before_key_derivation() {
+    let dir = TempDir::new().unwrap();
+    let store = open_store(&dir, "password");
+    seed(&store);
+    let path = dir.path().join("creds.enc");
+    let original = fs::read_to_string(&path).unwrap();
+
+    for (field, value) in [("mCost", 4_000_000_000u32), ("tCost", 1_000), ("pCost", 64)] {
+        let mut json: serde_json::Value = serde_json::from_str(&original).unwrap();
+        json["kdf"][field] = serde_json::json!(value);
+        fs::write(&path, json.to_string()).unwrap();
+
+        // 超限参数在派生密钥前即被拒绝，而不是尝试分配数 GB 内存
+        let reopened = open_store(&dir, "password");
+        let err = reopened.list().unwrap_err().to_string();
+        assert!(err.contains("exceed limits"), "{field}: {err}");
+        assert!(reopened
+            .change_master_password("password", "other", None)
+            .is_err());
+    }
+}
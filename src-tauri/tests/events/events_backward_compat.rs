@@ -1,7 +1,7 @@
 ﻿//! Event Backward Compatibility Tests (P4.4)
 //! -----------------------------------------
 //! 验证新增可选字段的向后兼容性：
-//! - `ip_source`, `ip_latency_ms`, `ip_selection_stage` 在 `AdaptiveTlsTiming` 中
+//! - `ip_source`, `ip_latency_ms`, `ip_selection_stage`, `http_version` 在 `AdaptiveTlsTiming` 中
 //! - `ip_source`, `ip_latency_ms` 在 `AdaptiveTlsFallback` 中
 //! - 确保旧版本客户端能正常解析新事件（字段缺失时为 None）
 
@@ -48,6 +48,7 @@ mod section_backward_compatibility {
                 ip_source,
                 ip_latency_ms,
                 ip_selection_stage,
+                http_version,
             } => {
                 assert_eq!(id, "task123");
                 assert_eq!(kind, "GitClone");
@@ -61,6 +62,7 @@ mod section_backward_compatibility {
                 assert!(ip_source.is_none());
                 assert!(ip_latency_ms.is_none());
                 assert!(ip_selection_stage.is_none());
+                assert!(http_version.is_none());
             }
             _ => panic!("expected AdaptiveTlsTiming"),
         }
@@ -81,6 +83,7 @@ mod section_backward_compatibility {
             ip_source: None,
             ip_latency_ms: None,
             ip_selection_stage: None,
+            http_version: None,
         };
 
         let json = serde_json::to_value(&event).expect("serialize event");
@@ -89,6 +92,7 @@ mod section_backward_compatibility {
         assert!(!obj.as_object().unwrap().contains_key("ip_source"));
         assert!(!obj.as_object().unwrap().contains_key("ip_latency_ms"));
         assert!(!obj.as_object().unwrap().contains_key("ip_selection_stage"));
+        assert!(!obj.as_object().unwrap().contains_key("http_version"));
     }
 
     #[test]
@@ -106,6 +110,7 @@ mod section_backward_compatibility {
             ip_source: Some("Builtin,Dns".into()),
             ip_latency_ms: Some(25),
             ip_selection_stage: Some("Cached".into()),
+            http_version: Some("HTTP/2".into()),
         };
 
         let json = serde_json::to_value(&event).expect("serialize event");
@@ -119,6 +124,7 @@ mod section_backward_compatibility {
             obj.get("ip_selection_stage").unwrap().as_str().unwrap(),
             "Cached"
         );
+        assert_eq!(obj.get("http_version").unwrap().as_str().unwrap(), "HTTP/2");
     }
 
     #[test]
@@ -1,4 +1,4 @@
-﻿//! 聚合测试：Events Structure & Contract (Roadmap 12.12)
+//! 聚合测试：Events Structure & Contract (Roadmap 12.12)
 //! ----------------------------------------------------
 //! 迁移来源（legacy 将保留占位）：
 //!   - `events_structured_basic.rs`
@@ -210,6 +210,7 @@ mod section_adaptive_tls_metrics {
             ip_source: None,
             ip_latency_ms: None,
             ip_selection_stage: None,
+            http_version: None,
         }));
         let events = bus.snapshot();
         let timing = events.into_iter().find_map(|e| match e {
@@ -587,6 +588,7 @@ mod section_tls_fingerprint_log {
 // ---------------- section_tls_pin_enforcement ----------------
 mod section_tls_pin_enforcement {
     use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+    use fireworks_collaboration_lib::core::config::model::SpkiPinPolicy;
     use fireworks_collaboration_lib::core::tls::spki::compute_spki_sha256_b64;
     use fireworks_collaboration_lib::core::tls::verifier::RealHostCertVerifier;
     use fireworks_collaboration_lib::events::structured::{
@@ -812,4 +814,200 @@ mod section_tls_pin_enforcement {
         let err = result.expect_err("inner verifier failure should propagate");
         assert!(format!("{err}").contains("forced failure"));
     }
+
+    fn policy(hosts: &[&str], primary: Vec<String>, backup: Vec<String>) -> SpkiPinPolicy {
+        SpkiPinPolicy {
+            hosts: hosts.iter().map(|h| h.to_string()).collect(),
+            primary,
+            backup,
+            expires_at: None,
+            report_only: false,
+        }
+    }
+
+    fn verify_real(verifier: &RealHostCertVerifier, leaf: &Certificate, host: &str) -> bool {
+        verifier
+            .verify_server_cert(
+                leaf,
+                &[],
+                &ServerName::try_from(host).unwrap(),
+                &mut std::iter::empty::<&[u8]>(),
+                &[],
+                std::time::SystemTime::now(),
+            )
+            .is_ok()
+    }
+
+    fn mismatch_flags(bus: &MemoryEventBus, host: &str) -> Vec<bool> {
+        bus.snapshot()
+            .iter()
+            .filter_map(|e| match e {
+                Event::Strategy(StrategyEvent::CertFpPinMismatch {
+                    host: h,
+                    report_only,
+                    ..
+                }) if h == host => Some(*report_only),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn pin_policy_rotation_report_only_and_expiry() {
+        let bus = Arc::new(MemoryEventBus::new());
+        set_test_event_bus(bus.clone());
+        let suffix = uuid::Uuid::new_v4();
+        let rotated = format!("rotated-{suffix}.corp.test");
+        let report = format!("report-{suffix}.corp.test");
+        let expired = format!("expired-{suffix}.corp.test");
+        let other = format!("other-{suffix}.test");
+        let cert = generate_simple_self_signed(vec![rotated.clone()]).unwrap();
+        let leaf = Certificate(cert.serialize_der().unwrap());
+        let (spki, _) = compute_spki_sha256_b64(&leaf);
+        let wrong = URL_SAFE_NO_PAD.encode([7u8; 32]);
+
+        let mut report_only = policy(&[&report], vec![wrong.clone()], vec![]);
+        report_only.report_only = true;
+        let mut stale = policy(&[&expired], vec![wrong.clone()], vec![]);
+        stale.expires_at = Some("2000-01-01".into());
+        let verifier = RealHostCertVerifier::new(
+            Arc::new(AlwaysOkVerifier),
+            None,
+            // 全局列表只作用于没有策略的主机
+            vec![wrong.clone()],
+        )
+        .with_pin_policies(vec![
+            policy(&[&rotated], vec![wrong.clone()], vec![spki.clone()]),
+            report_only,
+            stale,
+            policy(&["*.corp.test"], vec![wrong.clone()], vec![]),
+        ]);
+
+        // 备用 pin 命中即放行
+        assert!(verify_real(&verifier, &leaf, &rotated));
+        assert!(mismatch_flags(&bus, &rotated).is_empty());
+        // 仅报告 / 已过期：放行但发送 report_only 事件
+        assert!(verify_real(&verifier, &leaf, &report));
+        assert_eq!(mismatch_flags(&bus, &report), vec![true]);
+        assert!(verify_real(&verifier, &leaf, &expired));
+        assert_eq!(mismatch_flags(&bus, &expired), vec![true]);
+        // 通配策略强制生效
+        let wildcard = format!("git-{suffix}.corp.test");
+        assert!(!verify_real(&verifier, &leaf, &wildcard));
+        assert_eq!(mismatch_flags(&bus, &wildcard), vec![false]);
+        // 无策略主机回落到全局列表
+        assert!(!verify_real(&verifier, &leaf, &other));
+        assert_eq!(mismatch_flags(&bus, &other), vec![false]);
+    }
+
+    #[test]
+    fn pin_policy_expiry_parsing() {
+        use chrono::{TimeZone, Utc};
+        use fireworks_collaboration_lib::core::tls::pin_policy::{parse_expiry, policy_expired};
+
+        assert_eq!(
+            parse_expiry("2030-06-30"),
+            Some(Utc.with_ymd_and_hms(2030, 7, 1, 0, 0, 0).unwrap())
+        );
+        assert_eq!(
+            parse_expiry("2030-06-30T12:00:00+08:00"),
+            Some(Utc.with_ymd_and_hms(2030, 6, 30, 4, 0, 0).unwrap())
+        );
+        let mut p = policy(&["a.test"], vec![], vec![]);
+        let now = Utc.with_ymd_and_hms(2030, 6, 30, 23, 0, 0).unwrap();
+        assert!(!policy_expired(&p, now));
+        p.expires_at = Some("2030-06-30".into());
+        assert!(!policy_expired(&p, now));
+        p.expires_at = Some("2030-06-29".into());
+        assert!(policy_expired(&p, now));
+        p.expires_at = Some("next year".into());
+        assert!(
+            policy_expired(&p, now),
+            "unparsable expiry must not enforce"
+        );
+    }
+}
+
+// ---------------- section_tls_custom_ca ----------------
+mod section_tls_custom_ca {
+    use fireworks_collaboration_lib::core::config::model::{AppConfig, CustomCaCfg};
+    use fireworks_collaboration_lib::core::tls::trust::{load_custom_trust, ScopedTrustVerifier};
+    use fireworks_collaboration_lib::core::tls::verifier::build_root_store;
+    use fireworks_collaboration_lib::events::structured::{
+        set_test_event_bus, Event, MemoryEventBus, StrategyEvent,
+    };
+    use rcgen::{BasicConstraints, Certificate as RcCert, CertificateParams, IsCa};
+    use rustls::client::ServerCertVerifier;
+    use rustls::{Certificate, ServerName};
+    use std::sync::Arc;
+
+    fn corp_ca_and_leaf(names: &[&str]) -> (RcCert, Certificate) {
+        let mut ca_params = CertificateParams::new(Vec::new());
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca = RcCert::from_params(ca_params).unwrap();
+        let leaf = RcCert::from_params(CertificateParams::new(
+            names.iter().map(|n| n.to_string()).collect::<Vec<_>>(),
+        ))
+        .unwrap();
+        let der = leaf.serialize_der_with_signer(&ca).unwrap();
+        (ca, Certificate(der))
+    }
+
+    fn verify(verifier: &ScopedTrustVerifier, leaf: &Certificate, host: &str) -> bool {
+        verifier
+            .verify_server_cert(
+                leaf,
+                &[],
+                &ServerName::try_from(host).unwrap(),
+                &mut std::iter::empty(),
+                &[],
+                std::time::SystemTime::now(),
+            )
+            .is_ok()
+    }
+
+    #[test]
+    fn scoped_custom_ca_validates_and_emits_event() {
+        let bus = Arc::new(MemoryEventBus::new());
+        set_test_event_bus(bus.clone());
+        let (ca, leaf) = corp_ca_and_leaf(&["git.corp.test", "git.other.test"]);
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("corp.crt"), ca.serialize_pem().unwrap()).unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+        let mut tls = AppConfig::default().tls;
+        tls.custom_cas = vec![
+            CustomCaCfg {
+                path: dir.path().to_string_lossy().to_string(),
+                hosts: vec!["*.corp.test".into()],
+            },
+            CustomCaCfg {
+                path: dir.path().join("missing.pem").to_string_lossy().to_string(),
+                hosts: vec![],
+            },
+        ];
+        let trust = load_custom_trust(&tls);
+        assert_eq!(trust.scopes.len(), 1);
+        assert_eq!(trust.scopes[0].anchors, 1);
+        assert!(!trust.errors.is_empty(), "missing CA file is reported");
+
+        let verifier = ScopedTrustVerifier::new(build_root_store(), Arc::new(trust));
+        assert!(verify(&verifier, &leaf, "git.corp.test"));
+        // 同一证书对作用域外的主机不被信任
+        assert!(!verify(&verifier, &leaf, "git.other.test"));
+
+        let trusted: Vec<_> = bus
+            .snapshot()
+            .into_iter()
+            .filter_map(|e| match e {
+                Event::Strategy(StrategyEvent::CustomCaTrusted { host, source, .. }) => {
+                    Some((host, source))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(trusted.len(), 1);
+        assert_eq!(trusted[0].0, "git.corp.test");
+        assert_eq!(trusted[0].1, dir.path().to_string_lossy());
+    }
 }
@@ -0,0 +1,159 @@
+//! 传输限速：令牌桶、时间窗口、单任务上限来源优先级、运行期调整、读写路径计量与取消时中断等待。
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use fireworks_collaboration_lib::core::config::model::{BandwidthCfg, BandwidthWindow};
+use fireworks_collaboration_lib::core::git::default_impl::opts::parse_strategy_override;
+use fireworks_collaboration_lib::core::git::transport::throttle::{
+    active_task_throttles, active_window, enter_task, set_task_limit, ThrottleSource, TokenBucket,
+};
+use fireworks_collaboration_lib::core::git::transport::{throttle_transfer, tl_throttle_info};
+use serde_json::json;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+fn window(start: &str, end: &str, limit: u64) -> BandwidthWindow {
+    BandwidthWindow {
+        start: start.into(),
+        end: end.into(),
+        limit_kib_per_sec: limit,
+    }
+}
+
+#[test]
+fn token_bucket_allows_one_second_burst_then_paces() {
+    let bucket = TokenBucket::new(64 * 1024);
+    assert_eq!(bucket.reserve(64 * 1024), Duration::ZERO);
+    let wait = bucket.reserve(32 * 1024);
+    assert!(
+        wait >= Duration::from_millis(400) && wait <= Duration::from_millis(600),
+        "unexpected wait {wait:?}"
+    );
+
+    let unlimited = TokenBucket::new(0);
+    assert_eq!(unlimited.reserve(usize::MAX / 2), Duration::ZERO);
+    unlimited.set_rate(1024);
+    assert_eq!(unlimited.rate(), 1024);
+}
+
+#[test]
+fn windows_match_local_minutes_including_midnight_wrap() {
+    let windows = vec![
+        window("bad", "09:00", 1),
+        window("09:00", "18:00", 100),
+        window("22:30", "06:00", 50),
+    ];
+    let at = |h: u32, m: u32| active_window(&windows, h * 60 + m).map(|w| w.limit_kib_per_sec);
+    assert_eq!(at(9, 0), Some(100));
+    assert_eq!(at(17, 59), Some(100));
+    assert_eq!(at(18, 0), None);
+    assert_eq!(at(23, 0), Some(50));
+    assert_eq!(at(5, 59), Some(50));
+    assert_eq!(at(6, 0), None);
+    // start == end 表示全天
+    assert!(active_window(&[window("00:00", "00:00", 1)], 12 * 60).is_some());
+}
+
+#[test]
+fn task_limit_resolution_and_runtime_adjustment() {
+    let cfg = BandwidthCfg {
+        per_task_kib_per_sec: 512,
+        per_kind: HashMap::from([("GitClone".to_string(), 256)]),
+        windows: vec![window("00:00", "00:00", 64)],
+        ..BandwidthCfg::default()
+    };
+
+    // 前台任务：按类型覆盖默认值，时间窗口不生效
+    let id = Uuid::new_v4();
+    {
+        let _scope = enter_task(&cfg, id, "GitClone", None, false);
+        let info = tl_throttle_info().expect("throttle info in scope");
+        assert_eq!(info.task_kib_per_sec, Some(256));
+        assert_eq!(info.source, ThrottleSource::Kind);
+        assert!(!info.window_active);
+
+        assert!(set_task_limit(&id, Some(1024)));
+        let info = tl_throttle_info().unwrap();
+        assert_eq!(info.task_kib_per_sec, Some(1024));
+        assert_eq!(info.source, ThrottleSource::Runtime);
+        assert!(active_task_throttles()
+            .iter()
+            .any(|(t, k, _)| *t == id && k == "GitClone"));
+
+        assert!(set_task_limit(&id, Some(0)));
+        assert_eq!(
+            tl_throttle_info().unwrap().source,
+            ThrottleSource::Unlimited
+        );
+    }
+    assert!(tl_throttle_info().is_none());
+    assert!(!set_task_limit(&id, Some(1)));
+
+    // 后台批量子任务：策略覆盖优先于类型配置，窗口上限更小时取窗口
+    let _scope = enter_task(&cfg, Uuid::new_v4(), "GitFetch", Some(128), true);
+    let info = tl_throttle_info().unwrap();
+    assert_eq!(info.task_kib_per_sec, Some(64));
+    assert_eq!(info.source, ThrottleSource::Window);
+    assert!(info.window_active);
+}
+
+#[test]
+fn strategy_override_accepts_bandwidth_section() {
+    let res = parse_strategy_override(Some(json!({
+        "bandwidth": { "limitKibPerSec": 300, "burst": 1 }
+    })))
+    .unwrap();
+    let bw = res.parsed.unwrap().bandwidth.unwrap();
+    assert_eq!(bw.limit_kib_per_sec, Some(300));
+    assert!(res.ignored_top_level.is_empty());
+    assert_eq!(
+        res.ignored_nested,
+        vec![("bandwidth".to_string(), "burst".to_string())]
+    );
+}
+
+#[test]
+fn transfer_path_is_paced_by_task_limit() {
+    let cfg = BandwidthCfg {
+        per_task_kib_per_sec: 256,
+        ..BandwidthCfg::default()
+    };
+    let _scope = enter_task(&cfg, Uuid::new_v4(), "GitFetch", None, false);
+    let started = Instant::now();
+    // 首秒配额直接放行，超出部分按速率等待
+    for _ in 0..24 {
+        throttle_transfer(16 * 1024);
+    }
+    let elapsed = started.elapsed();
+    assert!(
+        elapsed >= Duration::from_millis(400),
+        "transfer not throttled: {elapsed:?}"
+    );
+}
+
+#[test]
+fn throttle_wait_ends_when_task_is_cancelled() {
+    let cfg = BandwidthCfg {
+        per_task_kib_per_sec: 16,
+        ..BandwidthCfg::default()
+    };
+    let token = CancellationToken::new();
+    let _scope = enter_task(&cfg, Uuid::new_v4(), "GitFetch", None, false).cancel_on(token.clone());
+    let canceller = {
+        let token = token.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            token.cancel();
+        })
+    };
+    let started = Instant::now();
+    // 超出首秒配额 10 倍，不取消需要等待约 10 秒
+    throttle_transfer(16 * 1024 * 11);
+    let elapsed = started.elapsed();
+    canceller.join().unwrap();
+    assert!(
+        elapsed < Duration::from_secs(2),
+        "cancelled task still waited {elapsed:?}"
+    );
+}
@@ -0,0 +1,308 @@
+//! URL 改写与镜像规则：insteadOf / pushInsteadOf、镜像候选排序、健康冷却与失败切换。
+
+use std::sync::atomic::AtomicBool;
+
+use fireworks_collaboration_lib::core::config::model::{HostMirror, MirrorCfg, UrlRewriteRule};
+use fireworks_collaboration_lib::core::git::default_impl::ops::{clone_with_mirrors, do_clone};
+use fireworks_collaboration_lib::core::git::errors::{ErrorCategory, GitError};
+use fireworks_collaboration_lib::core::git::runner::{Git2Runner, GitRunner};
+use fireworks_collaboration_lib::core::git::transport::mirror::{
+    is_mirror_available, mirror_health_snapshot, report_mirror_failure, report_mirror_success,
+};
+use fireworks_collaboration_lib::core::git::transport::{
+    apply_rewrite_rules, resolve_candidates, run_with_failover, tl_take_mirror_outcome, UrlPurpose,
+};
+
+use super::common::fixtures::{commit_files, create_empty_repo, create_repo_with_initial_commit};
+use super::common::test_env::init_test_env;
+
+fn rule(prefix: &str, replacement: &str, push_only: bool) -> UrlRewriteRule {
+    UrlRewriteRule {
+        prefix: prefix.into(),
+        replacement: replacement.into(),
+        push_only,
+    }
+}
+
+fn mirror_cfg(host: &str, mirrors: &[&str]) -> MirrorCfg {
+    MirrorCfg {
+        enabled: true,
+        host_mirrors: vec![HostMirror {
+            host: host.into(),
+            mirrors: mirrors.iter().map(|m| m.to_string()).collect(),
+        }],
+        ..MirrorCfg::default()
+    }
+}
+
+#[test]
+fn rewrite_rules_prefer_longest_prefix_and_push_rules() {
+    let cfg = MirrorCfg {
+        enabled: true,
+        rewrite_rules: vec![
+            rule("https://github.com/", "https://gh.example/", false),
+            rule("https://github.com/org/", "https://org.example/", false),
+            rule("https://github.com/", "git@github.com:", true),
+        ],
+        ..MirrorCfg::default()
+    };
+    let url = "https://github.com/org/repo.git";
+    assert_eq!(
+        apply_rewrite_rules(&cfg, url, UrlPurpose::Read),
+        "https://org.example/repo.git"
+    );
+    // pushInsteadOf 优先于 insteadOf
+    assert_eq!(
+        apply_rewrite_rules(&cfg, url, UrlPurpose::Push),
+        "git@github.com:org/repo.git"
+    );
+    assert_eq!(
+        apply_rewrite_rules(&cfg, "https://gitlab.com/a/b", UrlPurpose::Read),
+        "https://gitlab.com/a/b"
+    );
+    let disabled = MirrorCfg {
+        enabled: false,
+        ..cfg
+    };
+    assert_eq!(apply_rewrite_rules(&disabled, url, UrlPurpose::Read), url);
+}
+
+#[test]
+fn read_uses_mirrors_then_origin_and_push_stays_on_origin() {
+    let cfg = mirror_cfg(
+        "github.com",
+        &["https://m1.example/github.com/", "https://m2.example/gh"],
+    );
+    let url = "https://github.com/owner/repo.git";
+    let read: Vec<_> = resolve_candidates(&cfg, url, UrlPurpose::Read)
+        .into_iter()
+        .map(|c| (c.url, c.mirror.is_some()))
+        .collect();
+    assert_eq!(
+        read,
+        vec![
+            ("https://m1.example/github.com/owner/repo.git".into(), true),
+            ("https://m2.example/gh/owner/repo.git".into(), true),
+            (url.to_string(), false),
+        ]
+    );
+    let push = resolve_candidates(&cfg, url, UrlPurpose::Push);
+    assert_eq!(push.len(), 1);
+    assert_eq!(push[0].url, url);
+    assert!(push[0].mirror.is_none());
+
+    let no_fallback = MirrorCfg {
+        fallback_to_origin: false,
+        ..cfg.clone()
+    };
+    assert_eq!(
+        resolve_candidates(&no_fallback, url, UrlPurpose::Read).len(),
+        2
+    );
+}
+
+#[test]
+fn failing_mirror_cools_down_and_success_resets() {
+    let mirror = "https://cooldown.example/m";
+    let cfg = MirrorCfg {
+        failure_threshold: 2,
+        cooldown_sec: 600,
+        ..mirror_cfg("cooldown-host.example", &[mirror])
+    };
+    report_mirror_success(mirror);
+    assert!(!report_mirror_failure(&cfg, mirror, "timeout"));
+    assert!(is_mirror_available(mirror));
+    assert!(report_mirror_failure(&cfg, mirror, "timeout"));
+    assert!(!is_mirror_available(mirror));
+
+    let snap = mirror_health_snapshot()
+        .into_iter()
+        .find(|s| s.mirror == mirror)
+        .expect("snapshot entry");
+    assert!(snap.cooling_down);
+    assert!(snap.cooldown_remaining_sec > 0);
+    assert_eq!(snap.failures, 2);
+    assert_eq!(snap.last_error.as_deref(), Some("timeout"));
+
+    // 冷却中的镜像不再参与选择，只剩源站
+    let candidates = resolve_candidates(
+        &cfg,
+        "https://cooldown-host.example/a/b.git",
+        UrlPurpose::Read,
+    );
+    assert_eq!(candidates.len(), 1);
+    assert!(candidates[0].mirror.is_none());
+
+    report_mirror_success(mirror);
+    assert!(is_mirror_available(mirror));
+}
+
+#[test]
+fn failover_only_on_transport_errors() {
+    let cfg = MirrorCfg {
+        failure_threshold: 100,
+        ..mirror_cfg(
+            "failover-host.example",
+            &["https://fo1.example", "https://fo2.example"],
+        )
+    };
+    let url = "https://failover-host.example/a/b.git";
+
+    let mut tried = Vec::new();
+    let r = run_with_failover(&cfg, url, UrlPurpose::Read, |c| {
+        tried.push(c.url.clone());
+        if tried.len() == 1 {
+            Err(GitError::new(ErrorCategory::Network, "connection refused"))
+        } else {
+            Ok(())
+        }
+    });
+    assert!(r.is_ok());
+    assert_eq!(
+        tried,
+        vec!["https://fo1.example/a/b.git", "https://fo2.example/a/b.git"]
+    );
+    let outcome = tl_take_mirror_outcome().expect("outcome recorded");
+    assert_eq!(outcome.origin, url);
+    assert_eq!(outcome.mirror.as_deref(), Some("https://fo2.example"));
+    assert_eq!(outcome.failovers.len(), 1);
+    assert!(outcome.success);
+
+    // 非传输类错误（如取消）不切换镜像
+    let mut attempts = 0;
+    let r: Result<(), GitError> = run_with_failover(&cfg, url, UrlPurpose::Read, |_| {
+        attempts += 1;
+        Err(GitError::new(ErrorCategory::Cancel, "user canceled"))
+    });
+    assert!(r.is_err());
+    assert_eq!(attempts, 1);
+    assert!(!tl_take_mirror_outcome().unwrap().success);
+
+    // 未启用时直接使用原地址且不记录结果
+    let disabled = MirrorCfg::default();
+    run_with_failover(&disabled, url, UrlPurpose::Read, |c| {
+        assert_eq!(c.url, url);
+        Ok(())
+    })
+    .unwrap();
+    assert!(tl_take_mirror_outcome().is_none());
+}
+
+#[test]
+fn clone_is_served_by_next_mirror_when_first_is_unreachable() {
+    let origin = create_repo_with_initial_commit("mirror seed");
+    let parent = origin
+        .path
+        .parent()
+        .unwrap()
+        .to_string_lossy()
+        .replace('\\', "/");
+    let name = origin
+        .path
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .to_string();
+    let local_mirror = format!("file:///{}", parent.trim_start_matches('/'));
+    let cfg = mirror_cfg(
+        "mirror-e2e.invalid",
+        &["http://127.0.0.1:9/unreachable", &local_mirror],
+    );
+    let dest = std::env::temp_dir().join(format!("fwc-mirror-clone-{}", uuid::Uuid::new_v4()));
+    let interrupt = AtomicBool::new(false);
+    let url = format!("https://mirror-e2e.invalid/{name}");
+
+    run_with_failover(&cfg, &url, UrlPurpose::Read, |c| {
+        do_clone(&c.url, &dest, None, &interrupt, |_p| {})
+    })
+    .expect("clone served by local mirror");
+
+    assert!(dest.join(".git").exists());
+    let outcome = tl_take_mirror_outcome().expect("outcome");
+    assert_eq!(outcome.mirror.as_deref(), Some(local_mirror.as_str()));
+    assert_eq!(outcome.failovers.len(), 1);
+    assert_eq!(
+        outcome.failovers[0].mirror,
+        "http://127.0.0.1:9/unreachable"
+    );
+    let _ = std::fs::remove_dir_all(&dest);
+}
+
+fn head_of(repo: &std::path::Path, branch: &str) -> git2::Oid {
+    git2::Repository::open(repo)
+        .unwrap()
+        .refname_to_id(&format!("refs/heads/{branch}"))
+        .unwrap()
+}
+
+#[test]
+fn push_after_mirrored_clone_targets_origin() {
+    init_test_env();
+    let seed = create_empty_repo();
+    commit_files(&seed.path, &[("README.md", "seed\n")], "seed", false).unwrap();
+    let root = tempfile::tempdir().unwrap();
+    for side in ["origin", "mirror"] {
+        git2::build::RepoBuilder::new()
+            .bare(true)
+            .clone(
+                seed.path.to_str().unwrap(),
+                &root.path().join(side).join("repo.git"),
+            )
+            .unwrap();
+    }
+    let base = format!(
+        "file:///{}",
+        root.path()
+            .to_string_lossy()
+            .replace('\\', "/")
+            .trim_start_matches('/')
+    );
+    let origin_url = format!("{base}/origin/repo.git");
+    // 读取经 insteadOf 改写到镜像
+    let cfg = MirrorCfg {
+        enabled: true,
+        rewrite_rules: vec![rule(
+            &format!("{base}/origin/"),
+            &format!("{base}/mirror/"),
+            false,
+        )],
+        ..MirrorCfg::default()
+    };
+
+    let dest = root.path().join("work");
+    let interrupt = AtomicBool::new(false);
+    clone_with_mirrors(&cfg, &origin_url, &dest, |c| {
+        assert!(
+            c.url.contains("/mirror/"),
+            "clone should be served by the mirror"
+        );
+        do_clone(&c.url, &dest, None, &interrupt, |_p| {})
+    })
+    .expect("mirrored clone");
+    let _ = tl_take_mirror_outcome();
+
+    let work = git2::Repository::open(&dest).unwrap();
+    assert_eq!(
+        work.find_remote("origin").unwrap().url(),
+        Some(origin_url.as_str()),
+        "origin should point back at the original URL"
+    );
+    let branch = work.head().unwrap().shorthand().unwrap().to_string();
+    let mirror_before = head_of(&root.path().join("mirror/repo.git"), &branch);
+
+    commit_files(&dest, &[("CHANGE.md", "change\n")], "change", false).unwrap();
+    let pushed = work.head().unwrap().target().unwrap();
+    Git2Runner::new()
+        .push_repo(&dest, None, None, None, &interrupt, &mut |_p| {})
+        .expect("push");
+
+    assert_eq!(
+        head_of(&root.path().join("origin/repo.git"), &branch),
+        pushed
+    );
+    assert_eq!(
+        head_of(&root.path().join("mirror/repo.git"), &branch),
+        mirror_before,
+        "push must not land on the read-only mirror"
+    );
+}
@@ -6,18 +6,21 @@ pub(crate) mod common;
 
 mod adaptive_tls; // HTTP adaptive TLS tests (from http_adaptive_tls.rs)
 mod git_add_and_commit;
+mod git_bandwidth_throttle;
 mod git_basic_operations;
 mod git_branch_and_checkout;
 mod git_clone_recursive_submodules;
 mod git_clone_shallow_and_depth;
 mod git_credential_autofill;
 mod git_fetch_core_and_shallow;
+mod git_mirror_rules;
 mod git_preconditions_and_cancel;
 mod git_push_and_retry;
 mod git_reset;
 mod git_strategy_and_override;
 mod git_tag_and_remote;
 mod opts;
+mod proxy_tunnel;
 mod submodule;
 mod transport;
 mod utils;
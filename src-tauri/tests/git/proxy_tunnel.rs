@@ -0,0 +1,205 @@
+//! 经代理隧道的 Git 克隆测试
+//!
+//! 本地 HTTPS smart-git 服务（`git upload-pack --stateless-rpc`）+ 本地 SOCKS5 桩：
+//! 自定义传输必须经代理连接器拨号，`socks5h://` 时由代理解析域名。
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::{Arc, OnceLock};
+
+use fireworks_collaboration_lib::core::config::model::{AppConfig, CustomCaCfg};
+use fireworks_collaboration_lib::core::git::http_transport::CustomHttpsSubtransport;
+use fireworks_collaboration_lib::core::proxy::ProxyMode;
+
+use crate::common::fixtures::{commit_files, create_empty_repo};
+use crate::common::test_env::init_test_env;
+
+/// 多连接 SOCKS5 桩：回传每个 CONNECT 请求的地址类型与地址，并转发到 127.0.0.1 的目标端口
+fn start_socks5_relay() -> (u16, Receiver<(u8, Vec<u8>)>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (tx, rx) = channel();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut client) = stream else { return };
+            let tx = tx.clone();
+            std::thread::spawn(move || {
+                let mut head = [0u8; 2];
+                client.read_exact(&mut head).unwrap();
+                let mut methods = vec![0u8; head[1] as usize];
+                client.read_exact(&mut methods).unwrap();
+                client.write_all(&[0x05, 0x00]).unwrap();
+
+                let mut req = [0u8; 4];
+                client.read_exact(&mut req).unwrap();
+                let len = match req[3] {
+                    0x01 => 4,
+                    0x04 => 16,
+                    _ => {
+                        let mut n = [0u8; 1];
+                        client.read_exact(&mut n).unwrap();
+                        n[0] as usize
+                    }
+                };
+                let mut addr = vec![0u8; len + 2];
+                client.read_exact(&mut addr).unwrap();
+                let target_port = u16::from_be_bytes([addr[len], addr[len + 1]]);
+                addr.truncate(len);
+                let _ = tx.send((req[3], addr));
+
+                let upstream = TcpStream::connect(("127.0.0.1", target_port)).unwrap();
+                client
+                    .write_all(&[0x05, 0x00, 0x00, 0x01, 127, 0, 0, 1, 0, 0])
+                    .unwrap();
+                let (mut up_read, mut client_write) =
+                    (upstream.try_clone().unwrap(), client.try_clone().unwrap());
+                std::thread::spawn(move || {
+                    let _ = std::io::copy(&mut up_read, &mut client_write);
+                    let _ = client_write.shutdown(std::net::Shutdown::Write);
+                });
+                let mut upstream = upstream;
+                let _ = std::io::copy(&mut client, &mut upstream);
+                let _ = upstream.shutdown(std::net::Shutdown::Write);
+            });
+        }
+    });
+    (port, rx)
+}
+
+fn pkt_line(payload: &str) -> Vec<u8> {
+    format!("{:04x}{payload}", payload.len() + 4).into_bytes()
+}
+
+fn upload_pack(repo: &Path, advertise: bool, input: &[u8]) -> Vec<u8> {
+    let mut cmd = Command::new("git");
+    cmd.arg("upload-pack").arg("--stateless-rpc");
+    if advertise {
+        cmd.arg("--advertise-refs");
+    }
+    let mut child = cmd
+        .arg(repo)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    child.wait_with_output().unwrap().stdout
+}
+
+/// 本地 HTTPS smart-git 服务，证书由临时 CA 签发给 `localhost`；返回端口与 CA 路径
+fn start_git_https_server(repo: PathBuf, dir: &Path) -> (u16, PathBuf) {
+    use hyper::{Body, Request, Response};
+    use rcgen::{BasicConstraints, Certificate as RcCert, CertificateParams, IsCa};
+    use rustls::{Certificate, PrivateKey, ServerConfig};
+
+    let mut ca_params = CertificateParams::new(Vec::new());
+    ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    let ca = RcCert::from_params(ca_params).unwrap();
+    let server = RcCert::from_params(CertificateParams::new(vec!["localhost".into()])).unwrap();
+    let ca_path = dir.join("ca.pem");
+    std::fs::write(&ca_path, ca.serialize_pem().unwrap()).unwrap();
+    let server_cfg = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(
+            vec![Certificate(server.serialize_der_with_signer(&ca).unwrap())],
+            PrivateKey(server.serialize_private_key_der()),
+        )
+        .unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    listener.set_nonblocking(true).unwrap();
+    std::thread::spawn(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async move {
+            let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(server_cfg));
+            let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+            while let Ok((tcp, _)) = listener.accept().await {
+                let (acceptor, repo) = (acceptor.clone(), repo.clone());
+                tokio::spawn(async move {
+                    let Ok(tls) = acceptor.accept(tcp).await else {
+                        return;
+                    };
+                    let service = hyper::service::service_fn(move |req: Request<Body>| {
+                        let repo = repo.clone();
+                        async move {
+                            let advertise = req.uri().path().ends_with("/info/refs");
+                            let input = hyper::body::to_bytes(req.into_body()).await?;
+                            let (content_type, body) = if advertise {
+                                let mut body = pkt_line("# service=git-upload-pack\n");
+                                body.extend_from_slice(b"0000");
+                                body.extend(upload_pack(&repo, true, &[]));
+                                ("application/x-git-upload-pack-advertisement", body)
+                            } else {
+                                let body = upload_pack(&repo, false, &input);
+                                ("application/x-git-upload-pack-result", body)
+                            };
+                            Ok::<_, hyper::Error>(
+                                Response::builder()
+                                    .header("Content-Type", content_type)
+                                    .body(Body::from(body))
+                                    .unwrap(),
+                            )
+                        }
+                    });
+                    let _ = hyper::server::conn::Http::new()
+                        .http1_only(true)
+                        .serve_connection(tls, service)
+                        .await;
+                });
+            }
+        });
+    });
+    (port, ca_path)
+}
+
+static TUNNEL_CFG: OnceLock<AppConfig> = OnceLock::new();
+
+#[test]
+fn test_clone_through_socks5h_stub() {
+    init_test_env();
+    let source = create_empty_repo();
+    commit_files(&source.path, &[("README.md", "init\n")], "init", false).unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let (git_port, ca_path) = start_git_https_server(source.path.clone(), dir.path());
+    let (socks_port, requests) = start_socks5_relay();
+
+    let mut cfg = AppConfig::default();
+    cfg.proxy.mode = ProxyMode::Socks5;
+    cfg.proxy.url = format!("socks5h://127.0.0.1:{socks_port}");
+    cfg.proxy.honor_env_no_proxy = false;
+    cfg.tls.custom_cas = vec![CustomCaCfg {
+        path: ca_path.display().to_string(),
+        hosts: vec!["localhost".into()],
+    }];
+    TUNNEL_CFG.set(cfg).unwrap();
+    // 安全：测试专用 scheme，仅在此注册一次
+    unsafe {
+        git2::transport::register("https+sockstest", |remote| {
+            let sub = CustomHttpsSubtransport::new(TUNNEL_CFG.get().unwrap().clone());
+            git2::transport::Transport::smart(remote, true, sub)
+        })
+        .unwrap();
+    }
+
+    let dest = dir.path().join("clone");
+    let repo = git2::Repository::clone(
+        &format!("https+sockstest://localhost:{git_port}/repo.git"),
+        &dest,
+    )
+    .expect("clone through socks5h stub");
+    assert!(repo.head().is_ok());
+    assert!(dest.join("README.md").exists());
+
+    // 每条连接都经 SOCKS5 桩建立，且以域名（ATYP=0x03）交给代理解析
+    let seen: Vec<_> = requests.try_iter().collect();
+    assert!(!seen.is_empty());
+    for (atyp, addr) in seen {
+        assert_eq!(atyp, 0x03);
+        assert_eq!(addr, b"localhost");
+    }
+}
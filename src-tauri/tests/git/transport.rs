@@ -322,6 +322,39 @@ fn test_rewrite_disabled_when_proxy_env_present() {
     assert!(!out.eligible);
 }
 
+#[test]
+fn test_rewrite_follows_proxy_routing() {
+    let mut cfg = AppConfig::default();
+    cfg.http.fake_sni_enabled = true;
+    cfg.http.fake_sni_rollout_percent = 100;
+    cfg.proxy.mode = ProxyMode::Http;
+    cfg.proxy.url = "http://proxy.example.com:8080".to_string();
+    cfg.proxy.honor_env_no_proxy = false;
+    let url = "https://github.com/owner/repo";
+
+    // 默认路由走代理 -> 不改写
+    assert!(decision_with_proxy(&cfg, url, false).rewritten.is_none());
+
+    // NO_PROXY 命中 -> 直连，可改写
+    cfg.proxy.no_proxy = vec![".github.com".to_string()];
+    assert!(decision_with_proxy(&cfg, url, false).rewritten.is_some());
+
+    // 环境代理 + 环境 NO_PROXY 命中 -> 直连，可改写
+    let cfg_env = {
+        let mut c = AppConfig::default();
+        c.http.fake_sni_enabled = true;
+        c.http.fake_sni_rollout_percent = 100;
+        c
+    };
+    let _guard = env_lock().lock().unwrap();
+    std::env::set_var("HTTP_PROXY", "http://proxy:8080");
+    std::env::set_var("NO_PROXY", "localhost,github.com");
+    let out = decide_https_to_custom(&cfg_env, url);
+    std::env::remove_var("HTTP_PROXY");
+    std::env::remove_var("NO_PROXY");
+    assert!(out.rewritten.is_some());
+}
+
 #[test]
 fn test_rollout_sampling_zero() {
     let mut cfg = AppConfig::default();
@@ -973,3 +1006,475 @@ fn test_roundtrip_serde() {
     assert_eq!(back.timing.total_ms, 4);
     assert_eq!(back.redirects.len(), 1);
 }
+
+// ============================================================================
+// Keep-alive connection pool tests
+// ============================================================================
+
+mod connection_pool {
+    use std::net::SocketAddr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use fireworks_collaboration_lib::core::config::model::AppConfig;
+    use fireworks_collaboration_lib::core::git::http_transport::{
+        shared_client_config, ConnectionPool, HttpVersion, PoolKey, PooledConnection,
+    };
+    use hyper::service::service_fn;
+    use hyper::{Body, Request, Response};
+
+    /// 本地明文 HTTP/1.1 服务：统计 TCP 接入次数，所有请求返回 "ok"。
+    async fn spawn_counting_server() -> (SocketAddr, Arc<AtomicUsize>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let counter = accepted.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let svc = service_fn(|_req: Request<Body>| async {
+                        Ok::<_, hyper::Error>(Response::new(Body::from("ok")))
+                    });
+                    let _ = hyper::server::conn::Http::new()
+                        .serve_connection(stream, svc)
+                        .await;
+                });
+            }
+        });
+        (addr, accepted)
+    }
+
+    async fn connect(addr: SocketAddr, sni: &str, used_fake: bool) -> PooledConnection {
+        let tcp = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let key = PoolKey::new("127.0.0.1", addr.port(), sni, Some(addr.ip()));
+        PooledConnection::handshake(key, used_fake, tcp)
+            .await
+            .unwrap()
+            .wait_ready(Duration::from_secs(1))
+            .await
+            .expect("fresh connection ready")
+    }
+
+    async fn get_ok(conn: &mut PooledConnection) {
+        let req = Request::builder()
+            .uri("/repo.git/info/refs?service=git-upload-pack")
+            .header("Host", "127.0.0.1")
+            .body(Body::empty())
+            .unwrap();
+        let resp = conn.send_request(req).await.unwrap();
+        let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+        assert_eq!(&body[..], b"ok");
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn pool_reuses_keep_alive_connection_across_requests() {
+        let (addr, accepted) = spawn_counting_server().await;
+        let pool = ConnectionPool::new(4, Duration::from_secs(30));
+        let mut conn = connect(addr, "127.0.0.1", false).await;
+        pool.record_created();
+        assert!(!conn.is_reused());
+
+        for _ in 0..3 {
+            get_ok(&mut conn).await;
+            let idle = conn
+                .wait_ready(Duration::from_secs(1))
+                .await
+                .expect("connection ready after body drained");
+            pool.checkin(idle);
+            conn = pool
+                .checkout("127.0.0.1", addr.port(), false, |_| true)
+                .expect("pooled connection");
+            assert!(conn.is_reused());
+        }
+        get_ok(&mut conn).await;
+
+        assert_eq!(accepted.load(Ordering::SeqCst), 1, "single tcp connection");
+        let stats = pool.stats();
+        assert_eq!(stats.created, 1);
+        assert_eq!(stats.reused, 3);
+        assert_eq!(stats.returned, 3);
+        assert_eq!(stats.idle, 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn pool_checkout_respects_fake_policy_and_host() {
+        let (addr, _) = spawn_counting_server().await;
+        let pool = ConnectionPool::new(4, Duration::from_secs(30));
+        pool.checkin(connect(addr, "fake.example", true).await);
+
+        assert!(pool
+            .checkout("other.host", addr.port(), true, |_| true)
+            .is_none());
+        assert!(pool
+            .checkout("127.0.0.1", addr.port(), false, |_| true)
+            .is_none());
+        let conn = pool
+            .checkout("127.0.0.1", addr.port(), true, |_| true)
+            .expect("fake sni allowed");
+        assert!(conn.used_fake());
+        assert_eq!(conn.key().sni, "fake.example");
+        assert_eq!(conn.key().ip, Some(addr.ip()));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn pool_checkout_evicts_connections_to_rejected_ips() {
+        let (addr, _) = spawn_counting_server().await;
+        let pool = ConnectionPool::new(4, Duration::from_secs(30));
+        pool.checkin(connect(addr, "127.0.0.1", false).await);
+
+        // 所连 IP 不再被准入（如已熔断）时不复用，直接淘汰
+        assert!(pool
+            .checkout("127.0.0.1", addr.port(), false, |key| key.ip
+                != Some(addr.ip()))
+            .is_none());
+        assert_eq!(pool.stats().idle, 0);
+        assert_eq!(pool.stats().evicted, 1);
+        assert_eq!(pool.stats().reused, 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn pool_limits_and_idle_timeout_evict_connections() {
+        let (addr, _) = spawn_counting_server().await;
+        let pool = ConnectionPool::new(1, Duration::from_secs(30));
+        pool.checkin(connect(addr, "127.0.0.1", false).await);
+        pool.checkin(connect(addr, "127.0.0.1", false).await);
+        assert_eq!(pool.stats().idle, 1);
+        assert_eq!(pool.stats().evicted, 1);
+
+        // 超时即淘汰
+        pool.configure(1, Duration::ZERO);
+        assert!(pool
+            .checkout("127.0.0.1", addr.port(), false, |_| true)
+            .is_none());
+        assert_eq!(pool.stats().idle, 0);
+
+        // 上限为 0 等价于禁用复用
+        pool.configure(0, Duration::from_secs(30));
+        assert!(!pool.is_enabled());
+        pool.checkin(connect(addr, "127.0.0.1", false).await);
+        assert_eq!(pool.stats().idle, 0);
+    }
+
+    #[test]
+    fn shared_client_config_is_reused_for_session_resumption() {
+        let tls = AppConfig::default().tls;
+        let real_a = shared_client_config(&tls, "github.com", None, true).unwrap();
+        let real_b = shared_client_config(&tls, "github.com", None, true).unwrap();
+        assert!(Arc::ptr_eq(&real_a, &real_b));
+        let fake_a = shared_client_config(&tls, "github.com", Some("github.com"), true).unwrap();
+        let fake_b = shared_client_config(&tls, "github.com", Some("github.com"), true).unwrap();
+        assert!(Arc::ptr_eq(&fake_a, &fake_b));
+        assert!(!Arc::ptr_eq(&real_a, &fake_a));
+        assert!(!Arc::ptr_eq(
+            &fake_a,
+            &shared_client_config(&tls, "gitlab.com", Some("gitlab.com"), true).unwrap()
+        ));
+    }
+
+    #[test]
+    fn shared_client_config_advertises_h2_via_alpn_only_when_enabled() {
+        let tls = AppConfig::default().tls;
+        for expected in [None, Some("github.com")] {
+            let h2 = shared_client_config(&tls, "github.com", expected, true).unwrap();
+            assert_eq!(
+                h2.alpn_protocols,
+                vec![b"h2".to_vec(), b"http/1.1".to_vec()]
+            );
+            let h1 = shared_client_config(&tls, "github.com", expected, false).unwrap();
+            assert!(h1.alpn_protocols.is_empty());
+            assert!(!Arc::ptr_eq(&h1, &h2));
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn pool_multiplexes_http2_connection_between_streams() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let counter = accepted.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let svc = service_fn(|req: Request<Body>| async move {
+                        assert_eq!(req.version(), hyper::Version::HTTP_2);
+                        Ok::<_, hyper::Error>(Response::new(Body::from("ok")))
+                    });
+                    let _ = hyper::server::conn::Http::new()
+                        .http2_only(true)
+                        .serve_connection(stream, svc)
+                        .await;
+                });
+            }
+        });
+
+        let pool = ConnectionPool::new(4, Duration::from_secs(30));
+        let tcp = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let key = PoolKey::new("127.0.0.1", addr.port(), "127.0.0.1", Some(addr.ip()));
+        let conn = PooledConnection::handshake_version(key, false, tcp, HttpVersion::Http2)
+            .await
+            .unwrap();
+        assert_eq!(conn.version(), HttpVersion::Http2);
+        pool.checkin(conn);
+
+        // 两个并发借出共享同一条连接，连接始终留在池中
+        let mut a = pool
+            .checkout("127.0.0.1", addr.port(), false, |_| true)
+            .unwrap();
+        let mut b = pool
+            .checkout("127.0.0.1", addr.port(), false, |_| true)
+            .unwrap();
+        assert_eq!(pool.stats().idle, 1);
+        assert_eq!(pool.stats().http2, 1);
+        let req = |n: u8| {
+            Request::builder()
+                .uri(format!("http://127.0.0.1:{}/r{n}", addr.port()))
+                .version(hyper::Version::HTTP_2)
+                .body(Body::empty())
+                .unwrap()
+        };
+        let (ra, rb) = tokio::join!(a.send_request(req(1)), b.send_request(req(2)));
+        for resp in [ra.unwrap(), rb.unwrap()] {
+            let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+            assert_eq!(&body[..], b"ok");
+        }
+        pool.checkin(a);
+        pool.checkin(b);
+        assert_eq!(pool.stats().idle, 1, "handles fold back into one entry");
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+    }
+}
+
+// ============================================================================
+// Git protocol v2 translation tests
+// ============================================================================
+
+mod protocol_v2 {
+    use fireworks_collaboration_lib::core::git::http_transport::ref_prefixes_for_refspecs;
+    use fireworks_collaboration_lib::core::git::http_transport::testing::{
+        build_ls_refs_request, parse_capability_advertisement, parse_ls_refs_response, pkt_line,
+        synthesize_v0_advertisement, translate_fetch_request, AdvertisedRef,
+        FetchResponseTranslator, ProtocolV2Session, ServerCapabilities,
+    };
+
+    const A: &str = "1111111111111111111111111111111111111111";
+    const B: &str = "2222222222222222222222222222222222222222";
+    const C: &str = "3333333333333333333333333333333333333333";
+
+    fn pkts(lines: &[&str]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for l in lines {
+            match *l {
+                "0000" | "0001" | "0002" => out.extend_from_slice(l.as_bytes()),
+                _ => out.extend(pkt_line(l.as_bytes())),
+            }
+        }
+        out
+    }
+
+    fn v2_caps(fetch: &str) -> ServerCapabilities {
+        ServerCapabilities {
+            ls_refs: true,
+            fetch: Some(fetch.split_whitespace().map(str::to_string).collect()),
+            object_format: None,
+            agent: None,
+        }
+    }
+
+    #[test]
+    fn capability_advertisement_detects_v2_and_v0() {
+        let v2 = pkts(&[
+            "# service=git-upload-pack\n",
+            "0000",
+            "version 2\n",
+            "agent=git/github-1\n",
+            "ls-refs=unborn\n",
+            "fetch=shallow wait-for-done filter\n",
+            "object-format=sha1\n",
+            "0000",
+        ]);
+        let caps = parse_capability_advertisement(&v2).unwrap().expect("v2");
+        assert!(caps.ls_refs);
+        assert!(caps.fetch_feature("filter"));
+        assert!(caps.fetch_feature("wait-for-done"));
+        assert_eq!(caps.object_format.as_deref(), Some("sha1"));
+
+        let v0 = pkts(&[
+            "# service=git-upload-pack\n",
+            "0000",
+            &format!("{A} HEAD\0multi_ack side-band-64k\n"),
+            "0000",
+        ]);
+        assert!(parse_capability_advertisement(&v0).unwrap().is_none());
+    }
+
+    #[test]
+    fn ls_refs_request_filters_by_refspec_prefixes() {
+        let prefixes =
+            ref_prefixes_for_refspecs(&["+refs/heads/main:refs/remotes/origin/main", "dev"], true);
+        assert_eq!(
+            prefixes,
+            vec![
+                "HEAD",
+                "refs/heads/main",
+                "refs/heads/dev",
+                "refs/tags/dev",
+                "refs/tags/"
+            ]
+        );
+        let body = build_ls_refs_request(&v2_caps("shallow"), &prefixes);
+        let text = String::from_utf8(body).unwrap();
+        assert!(text.starts_with("0014command=ls-refs\n"));
+        assert!(text.contains("0001"));
+        assert!(text.contains("ref-prefix refs/heads/main\n"));
+        assert!(text.contains("symrefs\n") && text.contains("peel\n"));
+        assert!(text.ends_with("0000"));
+    }
+
+    #[test]
+    fn ls_refs_response_is_synthesized_as_v0_advertisement() {
+        let listing = pkts(&[
+            &format!("{A} HEAD symref-target:refs/heads/main\n"),
+            &format!("{A} refs/heads/main\n"),
+            &format!("{B} refs/tags/v1 peeled:{C}\n"),
+            "0000",
+        ]);
+        let refs = parse_ls_refs_response(&listing).unwrap();
+        assert_eq!(refs.len(), 3);
+        assert_eq!(refs[0].symref_target.as_deref(), Some("refs/heads/main"));
+        assert_eq!(refs[2].peeled.as_deref(), Some(C));
+
+        let caps = v2_caps("shallow filter");
+        let advert = String::from_utf8(synthesize_v0_advertisement(&refs, &caps)).unwrap();
+        assert!(advert.starts_with("001e# service=git-upload-pack\n0000"));
+        let first = advert.lines().nth(1).unwrap();
+        assert!(first.contains(&format!("{A} HEAD\0")));
+        assert!(first.contains("multi_ack_detailed"));
+        assert!(first.contains("symref=HEAD:refs/heads/main"));
+        assert!(first.contains(" filter"));
+        assert!(advert.contains(&format!("{C} refs/tags/v1^{{}}\n")));
+        assert!(advert.ends_with("0000"));
+        // v0 通告中的每一行都是合法的 pkt-line
+        assert!(parse_capability_advertisement(advert.as_bytes())
+            .unwrap()
+            .is_none());
+
+        let empty = String::from_utf8(synthesize_v0_advertisement(
+            &Vec::<AdvertisedRef>::new(),
+            &caps,
+        ))
+        .unwrap();
+        assert!(empty.contains("capabilities^{}\0"));
+    }
+
+    #[test]
+    fn fetch_request_translation_and_fallbacks() {
+        let caps = v2_caps("shallow wait-for-done");
+        let v0 = pkts(&[
+            &format!("want {A} multi_ack_detailed side-band-64k thin-pack ofs-delta include-tag agent=git/2.46.0\n"),
+            &format!("want {B}\n"),
+            "0000",
+            &format!("have {C}\n"),
+            "0000",
+        ]);
+        let req = translate_fetch_request(&v0, &caps)
+            .unwrap()
+            .expect("translated");
+        assert!(!req.done);
+        let text = String::from_utf8(req.body).unwrap();
+        assert!(text.starts_with("0012command=fetch\n"));
+        for line in [
+            "thin-pack\n".to_string(),
+            "ofs-delta\n".to_string(),
+            "include-tag\n".to_string(),
+            format!("want {A}\n"),
+            format!("want {B}\n"),
+            format!("have {C}\n"),
+            "wait-for-done\n".to_string(),
+        ] {
+            assert!(text.contains(&line), "missing {line:?}");
+        }
+        assert!(!text.contains("side-band-64k"));
+
+        let done = pkts(&[&format!("want {A} ofs-delta\n"), "0000", "done\n"]);
+        let req = translate_fetch_request(&done, &caps).unwrap().unwrap();
+        assert!(req.done);
+        assert!(String::from_utf8(req.body).unwrap().contains("done\n"));
+
+        // 浅克隆与服务端不支持的 filter 回落 v0
+        let shallow = pkts(&[&format!("want {A}\n"), "deepen 1\n", "0000", "done\n"]);
+        assert!(translate_fetch_request(&shallow, &caps).unwrap().is_none());
+        let filtered = pkts(&[
+            &format!("want {A}\n"),
+            "filter blob:none\n",
+            "0000",
+            "done\n",
+        ]);
+        assert!(translate_fetch_request(&filtered, &caps).unwrap().is_none());
+        assert!(translate_fetch_request(&filtered, &v2_caps("filter"))
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn fetch_response_translates_acks_and_packfile() {
+        // 非 done 轮次：ACK -> "ACK <oid> common"，以 NAK 结束
+        let mut session = ProtocolV2Session::default();
+        let round = pkts(&["acknowledgments\n", &format!("ACK {A}\n"), "0000"]);
+        let mut t = FetchResponseTranslator::new(false, None);
+        let out = t.feed(&round).unwrap();
+        t.finish(&mut session).unwrap();
+        assert_eq!(out, pkts(&[&format!("ACK {A} common\n"), "NAK\n"]),);
+        assert_eq!(session.last_common.as_deref(), Some(A));
+
+        // done 轮次：逐字节输入，pack 的 side-band pkt 原样转发
+        let pack = pkts(&["\u{1}PACK-DATA", "\u{2}progress\n", "0000"]);
+        let mut response = pkts(&["packfile\n"]);
+        response.extend(&pack);
+        let mut t = FetchResponseTranslator::new(true, session.last_common.clone());
+        let mut out = Vec::new();
+        for b in &response {
+            out.extend(t.feed(std::slice::from_ref(b)).unwrap());
+        }
+        t.finish(&mut session).unwrap();
+        let mut expected = pkts(&[&format!("ACK {A}\n")]);
+        expected.extend(&pack);
+        assert_eq!(out, expected);
+
+        // 截断的响应报错
+        let mut t = FetchResponseTranslator::new(true, None);
+        t.feed(&pkts(&["packfile\n", "\u{1}PACK"])).unwrap();
+        assert!(t.finish(&mut ProtocolV2Session::default()).is_err());
+    }
+
+    #[test]
+    fn packfile_sent_with_ready_is_held_until_done() {
+        let mut session = ProtocolV2Session::default();
+        let pack = pkts(&["\u{1}PACK-DATA", "0000"]);
+        let mut response = pkts(&[
+            "acknowledgments\n",
+            &format!("ACK {B}\n"),
+            "ready\n",
+            "0001",
+            "packfile\n",
+        ]);
+        response.extend(&pack);
+        let mut t = FetchResponseTranslator::new(false, None);
+        let out = t.feed(&response).unwrap();
+        t.finish(&mut session).unwrap();
+        assert_eq!(
+            out,
+            pkts(&[
+                &format!("ACK {B} common\n"),
+                &format!("ACK {B} ready\n"),
+                "NAK\n"
+            ]),
+        );
+        let mut expected = pkts(&[&format!("ACK {B}\n")]);
+        expected.extend(&pack);
+        assert_eq!(session.take_pending_response(), Some(expected));
+        assert!(session.take_pending_response().is_none());
+    }
+}
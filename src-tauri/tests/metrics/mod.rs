@@ -20,7 +20,8 @@ use fireworks_collaboration_lib::core::metrics::{
     GIT_TASK_DURATION_MS, HTTP_STRATEGY_FALLBACK_TOTAL, IP_POOL_AUTO_DISABLE_TOTAL,
     IP_POOL_LATENCY_MS, IP_POOL_REFRESH_TOTAL, IP_POOL_SELECTION_TOTAL,
     METRICS_EXPORT_RATE_LIMITED_TOTAL, METRICS_EXPORT_REQUESTS_TOTAL, METRICS_EXPORT_SERIES_TOTAL,
-    METRIC_MEMORY_PRESSURE_TOTAL, OBSERVABILITY_LAYER, PROXY_FALLBACK_TOTAL, TLS_HANDSHAKE_MS,
+    METRIC_MEMORY_PRESSURE_TOTAL, NETWORK_PROFILE_SWITCH_TOTAL, OBSERVABILITY_LAYER,
+    PROXY_FALLBACK_TOTAL, PROXY_UPSTREAM_FAILOVER_TOTAL, TLS_HANDSHAKE_MS,
 };
 use fireworks_collaboration_lib::core::metrics::{
     configure_tls_sampling, set_runtime_debug_mode, set_runtime_ip_mode, set_runtime_memory_limit,
@@ -306,6 +307,7 @@ fn strategy_events_emit_metrics() {
         ip_source: Some("Builtin".into()),
         ip_latency_ms: Some(18),
         ip_selection_stage: None,
+        http_version: None,
     }));
 
     publish_global(Event::Strategy(StrategyEvent::AdaptiveTlsFallback {
@@ -446,6 +448,78 @@ fn proxy_and_circuit_events_emit_metrics() {
     assert_eq!(recover_after, recover_before + 1);
 }
 
+#[test]
+fn network_profile_switch_emits_metrics() {
+    ensure_metrics_init();
+    let registry = global_registry();
+    let profile_labels = [("profile", "campus_wi_fi"), ("trigger", "auto")];
+    let baseline_labels = [("profile", "baseline"), ("trigger", "manual")];
+
+    let profile_before = registry
+        .get_counter(NETWORK_PROFILE_SWITCH_TOTAL, &profile_labels)
+        .unwrap_or(0);
+    let baseline_before = registry
+        .get_counter(NETWORK_PROFILE_SWITCH_TOTAL, &baseline_labels)
+        .unwrap_or(0);
+
+    publish_global(Event::Strategy(StrategyEvent::NetworkProfileSwitched {
+        from: None,
+        to: Some("Campus Wi-Fi".into()),
+        trigger: "auto".into(),
+    }));
+    publish_global(Event::Strategy(StrategyEvent::NetworkProfileSwitched {
+        from: Some("Campus Wi-Fi".into()),
+        to: None,
+        trigger: "manual".into(),
+    }));
+
+    let profile_after = registry
+        .get_counter(NETWORK_PROFILE_SWITCH_TOTAL, &profile_labels)
+        .unwrap();
+    let baseline_after = registry
+        .get_counter(NETWORK_PROFILE_SWITCH_TOTAL, &baseline_labels)
+        .unwrap();
+    assert_eq!(profile_after, profile_before + 1);
+    assert_eq!(baseline_after, baseline_before + 1);
+}
+
+#[test]
+fn proxy_upstream_failover_emits_metrics() {
+    ensure_metrics_init();
+    let registry = global_registry();
+    let switch_labels = [("from", "corp_a"), ("to", "corp_b")];
+    let direct_labels = [("from", "corp_a"), ("to", "direct")];
+
+    let switch_before = registry
+        .get_counter(PROXY_UPSTREAM_FAILOVER_TOTAL, &switch_labels)
+        .unwrap_or(0);
+    let direct_before = registry
+        .get_counter(PROXY_UPSTREAM_FAILOVER_TOTAL, &direct_labels)
+        .unwrap_or(0);
+
+    publish_global(Event::Strategy(StrategyEvent::ProxyUpstreamFailover {
+        host: "github.com".into(),
+        from: Some("corp-a".into()),
+        to: Some("corp-b".into()),
+        reason: "connection refused".into(),
+    }));
+    publish_global(Event::Strategy(StrategyEvent::ProxyUpstreamFailover {
+        host: "github.com".into(),
+        from: Some("corp-a".into()),
+        to: None,
+        reason: "all upstreams failed".into(),
+    }));
+
+    let switch_after = registry
+        .get_counter(PROXY_UPSTREAM_FAILOVER_TOTAL, &switch_labels)
+        .unwrap();
+    let direct_after = registry
+        .get_counter(PROXY_UPSTREAM_FAILOVER_TOTAL, &direct_labels)
+        .unwrap();
+    assert_eq!(switch_after, switch_before + 1);
+    assert_eq!(direct_after, direct_before + 1);
+}
+
 #[test]
 fn duplicate_completion_events_do_not_double_count() {
     ensure_metrics_init();
@@ -507,6 +581,7 @@ fn tls_timing_failure_records_fail_outcome() {
         ip_source: None,
         ip_latency_ms: None,
         ip_selection_stage: None,
+        http_version: None,
     }));
 
     let target = before.0 + 1;
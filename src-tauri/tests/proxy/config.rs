@@ -4,7 +4,10 @@ use fireworks_collaboration_lib::core::proxy::config::{
     default_health_check_interval_seconds, default_recovery_cooldown_seconds,
     default_recovery_strategy, default_timeout_seconds,
 };
-use fireworks_collaboration_lib::core::proxy::{ProxyConfig, ProxyMode};
+use fireworks_collaboration_lib::core::proxy::{
+    NamedProxy, ProxyConfig, ProxyMode, ProxyRoute, ProxyRule, ProxyTlsConfig, ProxyUpstream,
+    UpstreamStrategy,
+};
 
 #[test]
 fn test_proxy_mode_default() {
@@ -244,6 +247,9 @@ fn test_validate_url_format() {
 
     config.url = "socks5://proxy.example.com:1080".to_string();
     assert!(config.validate().is_ok());
+
+    config.url = "socks5h://proxy.example.com:1080".to_string();
+    assert!(config.validate().is_ok());
 }
 
 #[test]
@@ -264,6 +270,35 @@ fn test_config_json_roundtrip() {
         probe_timeout_seconds: 20,
         recovery_consecutive_threshold: 5,
         debug_proxy_logging: true,
+        pac_url: Some("http://wpad.corp.example/wpad.dat".to_string()),
+        wpad_enabled: true,
+        no_proxy: vec!["localhost,.corp.example".to_string()],
+        honor_env_no_proxy: false,
+        rules: vec![ProxyRule {
+            hosts: vec!["*.github.com".to_string()],
+            ports: vec![443],
+            route: ProxyRoute::Proxy("gh".to_string()),
+        }],
+        proxies: vec![NamedProxy {
+            name: "gh".to_string(),
+            url: "socks5://127.0.0.1:1080".to_string(),
+            username: None,
+            password: None,
+        }],
+        upstreams: vec![ProxyUpstream {
+            name: "backup".to_string(),
+            url: "http://10.0.0.2:3128".to_string(),
+            username: None,
+            password: None,
+            weight: 2,
+        }],
+        upstream_strategy: UpstreamStrategy::Latency,
+        sticky_host_ttl_seconds: 120,
+        tls: ProxyTlsConfig {
+            ca_path: Some("/etc/proxy-ca.pem".to_string()),
+            server_name: Some("proxy.internal".to_string()),
+            ..Default::default()
+        },
     };
 
     let json = serde_json::to_string(&original).unwrap();
@@ -293,6 +328,19 @@ fn test_config_json_roundtrip() {
     );
     assert_eq!(restored.recovery_strategy, original.recovery_strategy);
     assert_eq!(restored.probe_url, original.probe_url);
+    assert_eq!(restored.pac_url, original.pac_url);
+    assert_eq!(restored.wpad_enabled, original.wpad_enabled);
+    assert_eq!(restored.no_proxy, original.no_proxy);
+    assert_eq!(restored.honor_env_no_proxy, original.honor_env_no_proxy);
+    assert_eq!(restored.rules, original.rules);
+    assert_eq!(restored.proxies, original.proxies);
+    assert_eq!(restored.upstreams, original.upstreams);
+    assert_eq!(restored.upstream_strategy, original.upstream_strategy);
+    assert_eq!(restored.tls, original.tls);
+    assert_eq!(
+        restored.sticky_host_ttl_seconds,
+        original.sticky_host_ttl_seconds
+    );
     assert_eq!(
         restored.probe_timeout_seconds,
         original.probe_timeout_seconds
@@ -5,9 +5,12 @@
 //! - URL parsing for various formats (HTTP/HTTPS, IPv4/IPv6, ports)
 //! - Basic authentication header generation
 //! - Edge cases and error scenarios
+//! - TLS to `https://` proxies with independent certificate verification
 
 use fireworks_collaboration_lib::core::proxy::http_connector::HttpProxyConnector;
-use fireworks_collaboration_lib::core::proxy::{ProxyConnector, ProxyError};
+use fireworks_collaboration_lib::core::proxy::{ProxyConnector, ProxyError, ProxyTlsConfig};
+use std::io::{Read, Write};
+use std::sync::Arc;
 use std::time::Duration;
 
 #[test]
@@ -432,3 +435,117 @@ fn test_generate_auth_header_unicode_edge() {
     let auth_value = auth.unwrap();
     assert!(auth_value.starts_with("Basic "));
 }
+
+/// One-shot `https://` proxy: TLS with a self-signed `localhost` certificate, then CONNECT
+/// and a ping/pong through the tunnel. Returns the port and the certificate PEM.
+fn start_tls_proxy() -> (u16, String) {
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let pem = cert.serialize_pem().unwrap();
+    let server_cfg = rustls::ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(
+            vec![rustls::Certificate(cert.serialize_der().unwrap())],
+            rustls::PrivateKey(cert.serialize_private_key_der()),
+        )
+        .unwrap();
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    std::thread::spawn(move || {
+        let (tcp, _) = listener.accept().unwrap();
+        let conn = rustls::ServerConnection::new(Arc::new(server_cfg)).unwrap();
+        let mut tls = rustls::StreamOwned::new(conn, tcp);
+        let mut request = Vec::new();
+        let mut byte = [0u8; 1];
+        while !request.ends_with(b"\r\n\r\n") {
+            if tls.read(&mut byte).unwrap_or(0) == 0 {
+                return; // handshake rejected by the client
+            }
+            request.push(byte[0]);
+        }
+        assert!(request.starts_with(b"CONNECT example.com:443 HTTP/1.1"));
+        tls.write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+            .unwrap();
+        let mut ping = [0u8; 4];
+        tls.read_exact(&mut ping).unwrap();
+        assert_eq!(&ping, b"ping");
+        tls.write_all(b"pong").unwrap();
+        tls.flush().unwrap();
+    });
+    (port, pem)
+}
+
+fn https_connector(port: u16, tls: ProxyTlsConfig) -> HttpProxyConnector {
+    HttpProxyConnector::new(
+        format!("https://localhost:{port}"),
+        None,
+        None,
+        Duration::from_secs(5),
+    )
+    .with_tls(tls)
+}
+
+#[test]
+fn test_https_proxy_tunnel_with_proxy_ca() {
+    let (port, pem) = start_tls_proxy();
+    let dir = tempfile::tempdir().unwrap();
+    let ca = dir.path().join("proxy-ca.pem");
+    std::fs::write(&ca, pem).unwrap();
+
+    let connector = https_connector(
+        port,
+        ProxyTlsConfig {
+            ca_path: Some(ca.to_string_lossy().to_string()),
+            ..Default::default()
+        },
+    );
+    assert!(connector.is_https());
+    assert_eq!(connector.proxy_type(), "https");
+
+    let mut stream = connector.connect("example.com", 443).unwrap();
+    assert!(stream.is_tls());
+    stream.write_all(b"ping").unwrap();
+    stream.flush().unwrap();
+    let mut pong = [0u8; 4];
+    stream.read_exact(&mut pong).unwrap();
+    assert_eq!(&pong, b"pong");
+}
+
+#[test]
+fn test_https_proxy_rejects_untrusted_certificate() {
+    let (port, _) = start_tls_proxy();
+    let result = https_connector(port, ProxyTlsConfig::default()).connect("example.com", 443);
+    match result {
+        Err(ProxyError::Proxy(msg)) => assert!(msg.contains("TLS"), "{msg}"),
+        Err(other) => panic!("Expected Proxy error, got {other:?}"),
+        Ok(_) => panic!("Untrusted proxy certificate must be rejected"),
+    }
+}
+
+#[test]
+fn test_https_proxy_insecure_skip_verify() {
+    let (port, _) = start_tls_proxy();
+    let connector = https_connector(
+        port,
+        ProxyTlsConfig {
+            insecure_skip_verify: true,
+            ..Default::default()
+        },
+    );
+    let mut stream = connector.connect("example.com", 443).unwrap();
+    assert!(stream.is_tls());
+    stream.write_all(b"ping").unwrap();
+    let mut pong = [0u8; 4];
+    stream.read_exact(&mut pong).unwrap();
+    assert_eq!(&pong, b"pong");
+
+    // Plain http:// proxies never negotiate TLS
+    let plain = HttpProxyConnector::new(
+        "http://proxy.example.com".to_string(),
+        None,
+        None,
+        Duration::from_secs(5),
+    );
+    assert!(!plain.is_https());
+    assert_eq!(plain.proxy_type(), "http");
+}
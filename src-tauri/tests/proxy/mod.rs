@@ -10,8 +10,12 @@ mod http_connector;
 mod manager;
 mod manager_commands; // P5.6 proxy commands and events tests
 mod manager_recovery; // P5.5 proxy recovery tests
+mod pac;
+mod pool;
+mod routing;
 mod socks5_connector;
 mod state;
+mod tunnel;
 mod unit_tests;
 
 #[cfg(feature = "tauri-app")]
@@ -0,0 +1,301 @@
+//! Tests for PAC script evaluation and System mode PAC routing
+//!
+//! These tests verify:
+//! - Parsing of `FindProxyForURL` results
+//! - PAC helper functions inside the embedded script engine
+//! - Reuse of the compiled script across evaluations
+//! - WPAD candidate generation
+//! - System mode connector selection, credential scoping and DIRECT fallback
+
+use fireworks_collaboration_lib::core::proxy::pac::{
+    connectors_for, parse_pac_result, target_url, wpad_candidates, PacScript,
+};
+use fireworks_collaboration_lib::core::proxy::{
+    tunnel, PacDirective, ProxyConfig, ProxyManager, ProxyMode,
+};
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+
+const CORP_PAC: &str = r#"
+function FindProxyForURL(url, host) {
+  if (isPlainHostName(host) || dnsDomainIs(host, ".corp.example")) { return "DIRECT"; }
+  if (isInNet(host, "10.0.0.0", "255.0.0.0")) { return "SOCKS 10.1.1.1:1080"; }
+  if (shExpMatch(host, "*.github.com") || host === "github.com") {
+    return "PROXY gh-proxy.example:3128; SOCKS5 socks.example:1080; DIRECT";
+  }
+  return "PROXY default.example:8080";
+}
+"#;
+
+fn write_pac(dir: &tempfile::TempDir, name: &str, source: &str) -> String {
+    let path = dir.path().join(name);
+    std::fs::File::create(&path)
+        .unwrap()
+        .write_all(source.as_bytes())
+        .unwrap();
+    path.to_string_lossy().into_owned()
+}
+
+fn pac_system_config(pac_url: String) -> ProxyConfig {
+    ProxyConfig {
+        mode: ProxyMode::System,
+        pac_url: Some(pac_url),
+        timeout_seconds: 1,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_parse_pac_result() {
+    assert_eq!(
+        parse_pac_result("PROXY a:8080; HTTPS b:443;SOCKS5 c:1080 ; DIRECT"),
+        vec![
+            PacDirective::Http("a:8080".into()),
+            PacDirective::Https("b:443".into()),
+            PacDirective::Socks("c:1080".into()),
+            PacDirective::Direct,
+        ]
+    );
+    // Unknown entries and entries without an address are skipped
+    assert_eq!(
+        parse_pac_result("QUIC q:443; PROXY; socks s:1080"),
+        vec![PacDirective::Socks("s:1080".into())]
+    );
+    assert_eq!(parse_pac_result(""), vec![PacDirective::Direct]);
+    assert_eq!(
+        PacDirective::Socks("s:1080".into()).proxy_url().as_deref(),
+        Some("socks5h://s:1080")
+    );
+    assert_eq!(PacDirective::Direct.proxy_url(), None);
+}
+
+#[test]
+fn test_pac_script_helpers() {
+    let script = PacScript::new(CORP_PAC);
+    let eval = |host: &str| script.find_proxy(&target_url(host, 443), host).unwrap();
+
+    assert_eq!(eval("intranet"), "DIRECT");
+    assert_eq!(eval("git.corp.example"), "DIRECT");
+    assert_eq!(eval("10.2.3.4"), "SOCKS 10.1.1.1:1080");
+    assert_eq!(
+        script
+            .evaluate(&target_url("api.github.com", 443), "api.github.com")
+            .unwrap(),
+        vec![
+            PacDirective::Http("gh-proxy.example:3128".into()),
+            PacDirective::Socks("socks.example:1080".into()),
+            PacDirective::Direct,
+        ]
+    );
+    assert_eq!(eval("192.168.1.1"), "PROXY default.example:8080");
+
+    assert_eq!(target_url("github.com", 443), "https://github.com/");
+    assert_eq!(target_url("::1", 8443), "https://[::1]:8443/");
+}
+
+#[test]
+fn test_pac_script_errors() {
+    let missing = PacScript::new("var x = 1;");
+    assert!(missing.find_proxy("https://a/", "a").is_err());
+
+    let throwing = PacScript::new("function FindProxyForURL(u, h) { throw new Error('boom'); }");
+    let err = throwing.find_proxy("https://a/", "a").unwrap_err();
+    assert!(err.to_string().contains("boom"), "{err}");
+
+    let looping = PacScript::new("function FindProxyForURL(u, h) { while (true) {} }");
+    assert!(looping.find_proxy("https://a/", "a").is_err());
+}
+
+#[test]
+fn test_pac_script_reuses_compiled_context() {
+    let source =
+        "var calls = 0; function FindProxyForURL(u, h) { calls++; return 'PROXY p:' + calls; }";
+    let script = PacScript::new(source);
+    assert_eq!(script.find_proxy("https://a/", "a").unwrap(), "PROXY p:1");
+    assert_eq!(script.find_proxy("https://b/", "b").unwrap(), "PROXY p:2");
+    // A separately loaded script gets its own engine
+    let reloaded = PacScript::new(source);
+    assert_eq!(reloaded.find_proxy("https://a/", "a").unwrap(), "PROXY p:1");
+    assert_eq!(script.find_proxy("https://c/", "c").unwrap(), "PROXY p:3");
+}
+
+#[test]
+fn test_wpad_candidates() {
+    let candidates = wpad_candidates(&[
+        "eng.corp.example.com".to_string(),
+        "corp.example.com.".to_string(),
+        "localdomain".to_string(),
+        "lab.example.co.uk".to_string(),
+        "example.org".to_string(),
+    ]);
+    assert_eq!(
+        candidates,
+        vec![
+            "http://wpad.eng.corp.example.com/wpad.dat",
+            "http://wpad.corp.example.com/wpad.dat",
+            "http://wpad.lab.example.co.uk/wpad.dat",
+            "http://wpad.example.co.uk/wpad.dat",
+            "http://wpad.example.org/wpad.dat",
+        ]
+    );
+    // Devolution never reaches a two-label name that could be a public suffix
+    assert!(!candidates.iter().any(|c| c.contains("wpad.co.uk")));
+    assert!(!candidates.iter().any(|c| c.contains("wpad.example.com/")));
+}
+
+/// HTTP CONNECT stub that reports whether each request carried `Proxy-Authorization`
+fn start_auth_reporting_proxy() -> (u16, std::sync::mpsc::Receiver<bool>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { return };
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut authorized = false;
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap_or(0) > 0 && line != "\r\n" {
+                authorized |= line
+                    .to_ascii_lowercase()
+                    .starts_with("proxy-authorization:");
+                line.clear();
+            }
+            let _ = tx.send(authorized);
+            let _ = stream.write_all(b"HTTP/1.1 200 Connection established\r\n\r\n");
+        }
+    });
+    (port, rx)
+}
+
+#[test]
+fn test_pac_credentials_only_reach_configured_proxy() {
+    let (configured_port, configured) = start_auth_reporting_proxy();
+    let (other_port, other) = start_auth_reporting_proxy();
+    let config = ProxyConfig {
+        mode: ProxyMode::System,
+        url: format!("http://127.0.0.1:{configured_port}"),
+        username: Some("alice".into()),
+        password: Some("secret".into()),
+        timeout_seconds: 2,
+        ..Default::default()
+    };
+    let directives = parse_pac_result(&format!(
+        "PROXY 127.0.0.1:{other_port}; PROXY 127.0.0.1:{configured_port}"
+    ));
+    let connectors = connectors_for(&directives, &config);
+    assert_eq!(connectors.len(), 2);
+
+    connectors[0].connect("github.com", 443).unwrap();
+    assert!(!other.recv().unwrap(), "credentials leaked to a PAC proxy");
+    connectors[1].connect("github.com", 443).unwrap();
+    assert!(configured.recv().unwrap());
+}
+
+#[test]
+fn test_system_mode_uses_pac_connectors() {
+    let dir = tempfile::tempdir().unwrap();
+    let pac = write_pac(&dir, "proxy.pac", CORP_PAC);
+    let manager = ProxyManager::new(pac_system_config(pac));
+
+    assert_eq!(manager.get_connector().unwrap().proxy_type(), "pac");
+
+    let types = |url: &str| -> Vec<String> {
+        manager
+            .connectors_for_url(url)
+            .unwrap()
+            .iter()
+            .map(|c| c.proxy_type().to_string())
+            .collect()
+    };
+    assert_eq!(
+        types("https://github.com/owner/repo.git"),
+        vec!["http", "socks5", "placeholder"]
+    );
+    assert_eq!(
+        types("https://git.corp.example/repo.git"),
+        vec!["placeholder"]
+    );
+
+    // Without a PAC script (or a detected system proxy), System mode keeps connecting directly
+    let plain = ProxyManager::new(ProxyConfig {
+        mode: ProxyMode::System,
+        ..Default::default()
+    });
+    assert_eq!(plain.get_connector().unwrap().proxy_type(), "placeholder");
+    assert_eq!(
+        plain
+            .connectors_for_url("https://github.com/")
+            .unwrap()
+            .len(),
+        1
+    );
+}
+
+#[test]
+fn test_pac_connector_falls_through_to_direct() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let dead = TcpListener::bind("127.0.0.1:0").unwrap();
+    let dead_port = dead.local_addr().unwrap().port();
+    drop(dead);
+
+    let dir = tempfile::tempdir().unwrap();
+    let pac = write_pac(
+        &dir,
+        "fallthrough.pac",
+        &format!(
+            "function FindProxyForURL(url, host) {{ return 'PROXY 127.0.0.1:{dead_port}; DIRECT'; }}"
+        ),
+    );
+    let manager = ProxyManager::new(pac_system_config(pac));
+    let connector = manager.get_connector().unwrap();
+
+    let stream = connector.connect("127.0.0.1", port).unwrap();
+    assert_eq!(stream.peer_addr().unwrap().port(), port);
+}
+
+#[test]
+fn test_tunnel_follows_pac_list_with_fallback() {
+    // One-shot HTTP CONNECT proxy that reports the requested target
+    let proxy = TcpListener::bind("127.0.0.1:0").unwrap();
+    let proxy_port = proxy.local_addr().unwrap().port();
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let (mut stream, _) = proxy.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).unwrap();
+        let mut line = String::new();
+        while reader.read_line(&mut line).unwrap_or(0) > 0 && line != "\r\n" {
+            line.clear();
+        }
+        tx.send(request_line).unwrap();
+        let _ = stream.write_all(b"HTTP/1.1 200 Connection established\r\n\r\n");
+    });
+    let dead = TcpListener::bind("127.0.0.1:0").unwrap();
+    let dead_port = dead.local_addr().unwrap().port();
+    drop(dead);
+
+    let dir = tempfile::tempdir().unwrap();
+    let pac = write_pac(
+        &dir,
+        "ordered.pac",
+        &format!(
+            "function FindProxyForURL(url, host) {{ \
+               return 'PROXY 127.0.0.1:{dead_port}; PROXY 127.0.0.1:{proxy_port}; DIRECT'; }}"
+        ),
+    );
+    let mut config = pac_system_config(pac);
+    config.honor_env_no_proxy = false;
+
+    // Transports dial through the tunnel, which walks the PAC result in order
+    let stream = tunnel::connect_blocking(
+        &config,
+        "https://github.com/owner/repo.git",
+        "github.com",
+        443,
+    )
+    .unwrap();
+    assert_eq!(stream.peer_addr().unwrap().port(), proxy_port);
+    assert!(rx.recv().unwrap().starts_with("CONNECT github.com:443 "));
+}
@@ -0,0 +1,256 @@
+//! Tests for per-host proxy routing and `NO_PROXY` semantics
+//!
+//! These tests verify:
+//! - `NO_PROXY` parsing (domains, IPs, CIDRs, ports, `*`)
+//! - Rule matching by host glob, CIDR and port
+//! - Resolution order (Off → NO_PROXY → rules → default)
+//! - Connector selection and validation of rules / named proxies
+//! - Which routes leave name resolution to the proxy (IP pool bypass)
+
+use fireworks_collaboration_lib::core::proxy::routing::{
+    proxy_in_use, proxy_resolves_names, NoProxy,
+};
+use fireworks_collaboration_lib::core::proxy::{
+    NamedProxy, ProxyConfig, ProxyManager, ProxyMode, ProxyRoute, ProxyRouter, ProxyRule,
+    ProxyUpstream, RouteDecision,
+};
+
+fn named(name: &str, url: &str) -> NamedProxy {
+    NamedProxy {
+        name: name.to_string(),
+        url: url.to_string(),
+        username: None,
+        password: None,
+    }
+}
+
+fn rule(hosts: &[&str], ports: &[u16], route: ProxyRoute) -> ProxyRule {
+    ProxyRule {
+        hosts: hosts.iter().map(|h| h.to_string()).collect(),
+        ports: ports.to_vec(),
+        route,
+    }
+}
+
+/// Internal Gitea goes direct, GitHub through a named SOCKS5 proxy, the rest via HTTP
+fn routed_config() -> ProxyConfig {
+    ProxyConfig {
+        mode: ProxyMode::Http,
+        url: "http://default-proxy.example.com:8080".to_string(),
+        honor_env_no_proxy: false,
+        no_proxy: vec!["localhost, 127.0.0.1".to_string()],
+        proxies: vec![named("gh", "socks5://127.0.0.1:1080")],
+        rules: vec![
+            rule(
+                &["gitea.corp.example", "10.0.0.0/8"],
+                &[],
+                ProxyRoute::Direct,
+            ),
+            rule(
+                &["github.com", "*.github.com"],
+                &[443],
+                ProxyRoute::Proxy("gh".to_string()),
+            ),
+            rule(&["*.pac.example"], &[], ProxyRoute::System),
+        ],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_no_proxy_semantics() {
+    let no_proxy = NoProxy::parse(".corp.example, example.org:8443 10.0.0.0/8,::1,[fd00::1]:22");
+    assert!(no_proxy.matches("corp.example", 443));
+    assert!(no_proxy.matches("git.corp.example", 443));
+    assert!(!no_proxy.matches("notcorp.example", 443));
+    assert!(no_proxy.matches("example.org", 8443));
+    assert!(!no_proxy.matches("example.org", 443));
+    assert!(no_proxy.matches("10.2.3.4", 443));
+    assert!(!no_proxy.matches("11.2.3.4", 443));
+    assert!(no_proxy.matches("[::1]", 80));
+    assert!(no_proxy.matches("fd00::1", 22));
+    assert!(!no_proxy.matches("fd00::1", 443));
+    assert!(no_proxy.matches("GIT.Corp.Example.", 443));
+
+    assert!(NoProxy::parse("*").matches("anything.example", 1));
+    assert!(NoProxy::parse("*.github.com").matches("github.com", 443));
+    assert!(NoProxy::parse("").is_empty());
+}
+
+#[test]
+fn test_rule_matching() {
+    let glob = rule(&["git?.corp.*"], &[], ProxyRoute::Direct);
+    assert!(glob.matches("git1.corp.example", 443));
+    assert!(!glob.matches("git12.corp.example", 443));
+
+    let cidr = rule(
+        &["192.168.0.0/16", "2001:db8::/32"],
+        &[22, 443],
+        ProxyRoute::Direct,
+    );
+    assert!(cidr.matches("192.168.10.1", 443));
+    assert!(cidr.matches("2001:db8::5", 22));
+    assert!(!cidr.matches("192.168.10.1", 80));
+    assert!(!cidr.matches("gitea.corp.example", 443));
+
+    let any_host = rule(&[], &[9418], ProxyRoute::Direct);
+    assert!(any_host.matches("example.com", 9418));
+    assert!(!any_host.matches("example.com", 443));
+}
+
+#[test]
+fn test_route_resolution_order() {
+    let config = routed_config();
+    let router = ProxyRouter::new(&config);
+
+    assert_eq!(router.route("localhost", 443), RouteDecision::Direct);
+    assert_eq!(
+        router.route("gitea.corp.example", 443),
+        RouteDecision::Direct
+    );
+    assert_eq!(router.route("10.1.2.3", 3000), RouteDecision::Direct);
+    assert_eq!(
+        router.route("api.github.com", 443),
+        RouteDecision::Named(named("gh", "socks5://127.0.0.1:1080"))
+    );
+    // Port mismatch falls through to the default proxy
+    assert_eq!(router.route("github.com", 22), RouteDecision::Default);
+    assert_eq!(router.route("x.pac.example", 443), RouteDecision::System);
+    assert_eq!(router.route("crates.io", 443), RouteDecision::Default);
+    assert_eq!(
+        router.route_url("https://gitea.corp.example/org/repo.git"),
+        RouteDecision::Direct
+    );
+
+    // NO_PROXY wins over rules
+    let mut config = routed_config();
+    config.no_proxy.push("github.com".to_string());
+    assert_eq!(
+        ProxyRouter::new(&config).route("api.github.com", 443),
+        RouteDecision::Direct
+    );
+
+    // Mode Off ignores every rule
+    let mut off = routed_config();
+    off.mode = ProxyMode::Off;
+    assert_eq!(
+        ProxyRouter::new(&off).route("api.github.com", 443),
+        RouteDecision::Direct
+    );
+
+    let config = routed_config();
+    assert!(!proxy_in_use(&config, "gitea.corp.example", 443));
+    assert!(proxy_in_use(&config, "github.com", 443));
+    assert!(proxy_in_use(&config, "crates.io", 443));
+    assert!(router.has_direct_routes());
+}
+
+#[test]
+fn test_proxy_resolves_names() {
+    let mut config = routed_config();
+    // HTTP CONNECT default proxy resolves names; direct routes and socks5:// resolve locally
+    assert!(proxy_resolves_names(&config, "crates.io", 443));
+    assert!(!proxy_resolves_names(&config, "gitea.corp.example", 443));
+    assert!(!proxy_resolves_names(&config, "github.com", 443));
+
+    config.proxies = vec![named("gh", "socks5h://127.0.0.1:1080")];
+    assert!(proxy_resolves_names(&config, "github.com", 443));
+
+    let mut socks = ProxyConfig {
+        mode: ProxyMode::Socks5,
+        url: "socks5://127.0.0.1:1080".to_string(),
+        honor_env_no_proxy: false,
+        ..Default::default()
+    };
+    assert!(!proxy_resolves_names(&socks, "crates.io", 443));
+    socks.url = "socks5h://127.0.0.1:1080".to_string();
+    assert!(proxy_resolves_names(&socks, "crates.io", 443));
+    // Legacy `socks://` keeps resolving on the proxy side
+    socks.url = "socks://127.0.0.1:1080".to_string();
+    assert!(proxy_resolves_names(&socks, "crates.io", 443));
+
+    // Pools decide by the upstream that would be tried first, not by any upstream
+    let upstream = |name: &str, url: &str| ProxyUpstream {
+        name: name.to_string(),
+        url: url.to_string(),
+        username: None,
+        password: None,
+        weight: 1,
+    };
+    socks.url.clear();
+    socks.upstreams = vec![
+        upstream("local", "socks5://127.0.0.1:1081"),
+        upstream("remote", "socks5h://127.0.0.1:1082"),
+    ];
+    assert!(!proxy_resolves_names(&socks, "crates.io", 443));
+    socks.upstreams.reverse();
+    assert!(proxy_resolves_names(&socks, "crates.io", 443));
+
+    config.mode = ProxyMode::Off;
+    assert!(!proxy_resolves_names(&config, "crates.io", 443));
+}
+
+#[test]
+fn test_manager_connectors_follow_routes() {
+    let manager = ProxyManager::new(routed_config());
+
+    let kind = |host: &str, port: u16| {
+        manager
+            .connector_for(host, port)
+            .unwrap()
+            .proxy_type()
+            .to_string()
+    };
+    assert_eq!(kind("gitea.corp.example", 443), "placeholder");
+    assert_eq!(kind("github.com", 443), "socks5");
+    assert_eq!(kind("crates.io", 443), "http");
+
+    let via_url = manager
+        .connectors_for_url("https://api.github.com/repos")
+        .unwrap();
+    assert_eq!(via_url.len(), 1);
+    assert_eq!(via_url[0].proxy_type(), "socks5");
+    assert!(manager.connectors_for_url("not a url").is_err());
+}
+
+#[test]
+fn test_routing_validation_and_serde() {
+    assert!(routed_config().validate().is_ok());
+
+    let mut unknown = routed_config();
+    unknown
+        .rules
+        .push(rule(&[], &[], ProxyRoute::Proxy("missing".into())));
+    let err = unknown.validate().unwrap_err().to_string();
+    assert!(err.contains("Unknown proxy 'missing'"), "{err}");
+
+    let mut bad_cidr = routed_config();
+    bad_cidr
+        .rules
+        .push(rule(&["10.0.0.0/99"], &[], ProxyRoute::Direct));
+    assert!(bad_cidr.validate().is_err());
+
+    let mut dup = routed_config();
+    dup.proxies.push(named("gh", "http://other:3128"));
+    assert!(dup.validate().is_err());
+
+    let mut bad_url = routed_config();
+    bad_url.proxies[0].url = "ftp://nope".to_string();
+    assert!(bad_url.validate().is_err());
+
+    let json = serde_json::to_value(routed_config()).unwrap();
+    assert_eq!(json["rules"][0]["route"], "direct");
+    assert_eq!(json["rules"][1]["route"]["proxy"], "gh");
+    assert_eq!(json["noProxy"][0], "localhost, 127.0.0.1");
+    assert_eq!(json["honorEnvNoProxy"], false);
+
+    let restored: ProxyConfig = serde_json::from_value(json).unwrap();
+    assert_eq!(restored.rules, routed_config().rules);
+    assert_eq!(restored.proxies, routed_config().proxies);
+
+    // Older configs without routing fields keep defaults
+    let legacy: ProxyConfig =
+        serde_json::from_str(r#"{"mode":"http","url":"http://p:1"}"#).unwrap();
+    assert!(legacy.rules.is_empty());
+    assert!(legacy.honor_env_no_proxy);
+}
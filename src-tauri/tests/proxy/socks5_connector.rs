@@ -828,3 +828,91 @@ fn test_error_password_too_long() {
     // authenticate_password 会检查长度并返回错误
     assert_eq!(connector.password.as_ref().unwrap().len(), 256);
 }
+
+/// 单次握手的 SOCKS5 模拟服务器，回传 CONNECT 请求中的地址类型与地址
+fn start_mock_socks5() -> (u16, std::sync::mpsc::Receiver<(u8, Vec<u8>)>) {
+    use std::io::{Read, Write};
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut head = [0u8; 2];
+        stream.read_exact(&mut head).unwrap();
+        let mut methods = vec![0u8; head[1] as usize];
+        stream.read_exact(&mut methods).unwrap();
+        stream.write_all(&[0x05, 0x00]).unwrap();
+
+        let mut req = [0u8; 4];
+        stream.read_exact(&mut req).unwrap();
+        let len = match req[3] {
+            0x01 => 4,
+            0x04 => 16,
+            _ => {
+                let mut n = [0u8; 1];
+                stream.read_exact(&mut n).unwrap();
+                n[0] as usize
+            }
+        };
+        let mut addr = vec![0u8; len + 2];
+        stream.read_exact(&mut addr).unwrap();
+        addr.truncate(len);
+        tx.send((req[3], addr)).unwrap();
+        stream
+            .write_all(&[0x05, 0x00, 0x00, 0x01, 127, 0, 0, 1, 0, 80])
+            .unwrap();
+    });
+    (port, rx)
+}
+
+#[test]
+fn test_remote_dns_from_scheme() {
+    let connector = |url: &str| {
+        Socks5ProxyConnector::new(url.to_string(), None, None, Duration::from_secs(5)).unwrap()
+    };
+    assert!(!connector("socks5://proxy:1080").remote_dns);
+    assert!(connector("socks5h://proxy:1080").remote_dns);
+    // 旧格式保持由代理解析
+    assert!(connector("socks://proxy:1080").remote_dns);
+    assert!(connector("proxy:1080").remote_dns);
+
+    let h = connector("socks5h://proxy:1080");
+    assert_eq!(h.proxy_host, "proxy");
+    assert_eq!(h.sanitized_url(), "socks5h://proxy:1080");
+}
+
+#[test]
+fn test_socks5h_sends_domain_to_proxy() {
+    let (port, rx) = start_mock_socks5();
+    let connector = Socks5ProxyConnector::new(
+        format!("socks5h://127.0.0.1:{port}"),
+        None,
+        None,
+        Duration::from_secs(5),
+    )
+    .unwrap();
+    let stream = connector.connect("localhost", 443).unwrap();
+    assert!(!stream.is_tls());
+
+    let (atyp, addr) = rx.recv().unwrap();
+    assert_eq!(atyp, 0x03);
+    assert_eq!(addr, b"localhost");
+}
+
+#[test]
+fn test_socks5_resolves_locally() {
+    let (port, rx) = start_mock_socks5();
+    let connector = Socks5ProxyConnector::new(
+        format!("socks5://127.0.0.1:{port}"),
+        None,
+        None,
+        Duration::from_secs(5),
+    )
+    .unwrap();
+    connector.connect("localhost", 443).unwrap();
+
+    // 本地解析后只发送 IP 地址（IPv4 或 IPv6），域名不会到达代理
+    let (atyp, addr) = rx.recv().unwrap();
+    assert!(atyp == 0x01 || atyp == 0x04, "unexpected ATYP {atyp:#x}");
+    assert_ne!(addr, b"localhost");
+}
@@ -0,0 +1,160 @@
+//! Tests for the async proxy tunnels used by the tokio-based transports
+//!
+//! These tests verify:
+//! - `socks5h://` tunnels hand the target name to the proxy (no local DNS lookup)
+//! - Tunnels through an `https://` proxy keep the TLS session with the proxy
+
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Arc;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use fireworks_collaboration_lib::core::proxy::{tunnel, ProxyConfig, ProxyMode, ProxyTlsConfig};
+
+/// Name under the reserved `.invalid` TLD: any local lookup fails
+const UNRESOLVABLE: &str = "fwc-tunnel-test.invalid";
+
+/// One-shot SOCKS5 proxy that reports the CONNECT address and echoes the tunnel
+fn start_socks5_echo() -> (u16, Receiver<(u8, Vec<u8>)>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (tx, rx) = channel();
+    std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut head = [0u8; 2];
+        stream.read_exact(&mut head).unwrap();
+        let mut methods = vec![0u8; head[1] as usize];
+        stream.read_exact(&mut methods).unwrap();
+        stream.write_all(&[0x05, 0x00]).unwrap();
+
+        let mut req = [0u8; 4];
+        stream.read_exact(&mut req).unwrap();
+        let len = match req[3] {
+            0x01 => 4,
+            0x04 => 16,
+            _ => {
+                let mut n = [0u8; 1];
+                stream.read_exact(&mut n).unwrap();
+                n[0] as usize
+            }
+        };
+        let mut addr = vec![0u8; len + 2];
+        stream.read_exact(&mut addr).unwrap();
+        addr.truncate(len);
+        tx.send((req[3], addr)).unwrap();
+        stream
+            .write_all(&[0x05, 0x00, 0x00, 0x01, 127, 0, 0, 1, 0, 80])
+            .unwrap();
+        let mut reader = stream.try_clone().unwrap();
+        let _ = std::io::copy(&mut reader, &mut stream);
+    });
+    (port, rx)
+}
+
+/// One-shot `https://` proxy with a self-signed `localhost` certificate; echoes the tunnel
+/// after a CONNECT. Returns the port and the certificate PEM.
+fn start_tls_echo_proxy() -> (u16, String) {
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let pem = cert.serialize_pem().unwrap();
+    let server_cfg = rustls::ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(
+            vec![rustls::Certificate(cert.serialize_der().unwrap())],
+            rustls::PrivateKey(cert.serialize_private_key_der()),
+        )
+        .unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    std::thread::spawn(move || {
+        let (tcp, _) = listener.accept().unwrap();
+        let conn = rustls::ServerConnection::new(Arc::new(server_cfg)).unwrap();
+        let mut tls = rustls::StreamOwned::new(conn, tcp);
+        let mut request = Vec::new();
+        let mut byte = [0u8; 1];
+        while !request.ends_with(b"\r\n\r\n") {
+            if tls.read(&mut byte).unwrap_or(0) == 0 {
+                return;
+            }
+            request.push(byte[0]);
+        }
+        assert!(request.starts_with(format!("CONNECT {UNRESOLVABLE}:443 HTTP/1.1").as_bytes()));
+        tls.write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+            .unwrap();
+        let mut buf = [0u8; 8192];
+        loop {
+            match tls.read(&mut buf) {
+                Ok(0) | Err(_) => return,
+                Ok(n) => {
+                    tls.write_all(&buf[..n]).unwrap();
+                    tls.flush().unwrap();
+                }
+            }
+        }
+    });
+    (port, pem)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_socks5h_tunnel_leaves_resolution_to_proxy() {
+    let (port, rx) = start_socks5_echo();
+    let config = ProxyConfig {
+        mode: ProxyMode::Socks5,
+        url: format!("socks5h://127.0.0.1:{port}"),
+        honor_env_no_proxy: false,
+        timeout_seconds: 5,
+        ..Default::default()
+    };
+
+    let url = format!("https://{UNRESOLVABLE}/");
+    let mut stream = tunnel::connect(&config, &url, UNRESOLVABLE, 443)
+        .await
+        .expect("socks5h tunnel must not resolve the target locally");
+    assert!(!stream.is_tls());
+    let (atyp, addr) = rx.recv().unwrap();
+    assert_eq!(atyp, 0x03);
+    assert_eq!(addr, UNRESOLVABLE.as_bytes());
+
+    stream.write_all(b"ping").await.unwrap();
+    let mut pong = [0u8; 4];
+    stream.read_exact(&mut pong).await.unwrap();
+    assert_eq!(&pong, b"ping");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_https_proxy_tunnel_keeps_tls_session() {
+    let (port, pem) = start_tls_echo_proxy();
+    let dir = tempfile::tempdir().unwrap();
+    let ca = dir.path().join("proxy-ca.pem");
+    std::fs::write(&ca, pem).unwrap();
+    let config = ProxyConfig {
+        mode: ProxyMode::Http,
+        url: format!("https://localhost:{port}"),
+        honor_env_no_proxy: false,
+        timeout_seconds: 5,
+        tls: ProxyTlsConfig {
+            ca_path: Some(ca.to_string_lossy().to_string()),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let url = format!("https://{UNRESOLVABLE}/");
+    let mut stream = tunnel::connect(&config, &url, UNRESOLVABLE, 443)
+        .await
+        .unwrap();
+    assert!(stream.is_tls());
+
+    // Several TLS records each way through the async session
+    let payload: Vec<u8> = (0..64 * 1024).map(|i| (i % 251) as u8).collect();
+    for chunk in payload.chunks(16 * 1024) {
+        stream.write_all(chunk).await.unwrap();
+        stream.flush().await.unwrap();
+        let mut echoed = vec![0u8; chunk.len()];
+        stream.read_exact(&mut echoed).await.unwrap();
+        assert_eq!(echoed, chunk);
+    }
+    stream.shutdown().await.unwrap();
+}
@@ -95,6 +95,15 @@ mod section_error_mapping {
                 ErrorCategory::Verify,
                 "cert",
             ),
+            (
+                mk_err(
+                    ErrorCode::GenericError,
+                    ErrorClass::Net,
+                    "tls handshake: client certificate rejected by git.example.com: received fatal alert: CertificateRequired",
+                ),
+                ErrorCategory::ClientCert,
+                "mtls",
+            ),
             (
                 mk_err(ErrorCode::GenericError, ErrorClass::Http, "HTTP 501"),
                 ErrorCategory::Protocol,
@@ -461,6 +461,7 @@ fn aggregator_threshold_detects_high_fallback_ratio() {
             ip_source: None,
             ip_latency_ms: None,
             ip_selection_stage: None,
+            http_version: None,
         }),
         Event::Strategy(StrategyEvent::AdaptiveTlsFallback {
             id: "test-clone".into(),
@@ -828,7 +828,7 @@ mod section_ip_pool_core_manager {
     use super::super::common::prelude::*; // brings in enabled_config
     use fireworks_collaboration_lib::core::ip_pool::manager::IpPool;
     use fireworks_collaboration_lib::core::ip_pool::{
-        IpCandidate, IpOutcome, IpSelection, IpSource, IpStat,
+        IpCacheKey, IpCacheSlot, IpCandidate, IpOutcome, IpSelection, IpSource, IpStat,
     };
     use std::net::{IpAddr, Ipv4Addr};
 
@@ -871,6 +871,37 @@ mod section_ip_pool_core_manager {
         assert_eq!(aggregate.failure, 0);
         assert!(aggregate.last_outcome_ms > 0);
     }
+
+    #[tokio::test]
+    async fn pick_best_claims_half_open_trial_only_for_attempted_ip() {
+        let mut cfg = enabled_config();
+        cfg.runtime.failure_threshold = 1;
+        cfg.runtime.cooldown_seconds = 0;
+        cfg.runtime.half_open_max_trials = 1;
+        let pool = IpPool::new(cfg);
+        let host = "half-open.test";
+        let best = make_stat([127, 0, 0, 4], 443);
+        let alt = make_stat([127, 0, 0, 5], 443);
+        pool.cache().insert(
+            IpCacheKey::new(host.to_string(), 443),
+            IpCacheSlot {
+                best: Some(best.clone()),
+                alternatives: vec![alt.clone()],
+            },
+        );
+        pool.report_candidate_outcome(host, 443, &best, IpOutcome::Failure);
+        pool.report_candidate_outcome(host, 443, &alt, IpOutcome::Failure);
+
+        let selection = pool.pick_best(host, 443).await;
+        assert_eq!(
+            selection.selected().unwrap().candidate.address,
+            best.candidate.address
+        );
+        // 首选占用了唯一的试探名额；备选只做只读检查，名额仍在
+        assert!(pool.is_ip_tripped_for(host, best.candidate.address));
+        assert!(!pool.is_ip_tripped_for(host, alt.candidate.address));
+        assert_eq!(selection.alternatives().len(), 1);
+    }
 }
 
 // ---------------- section_ip_pool_cache ----------------
@@ -1224,6 +1255,94 @@ mod section_circuit_breaker {
             assert_eq!(retrieved.failure_rate_threshold, 0.6);
         }
     }
+
+    #[test]
+    fn circuit_breaker_host_scopes_and_half_open() {
+        // 场景 1: 按 (host, IP) 熔断，其他主机不受影响；全局熔断对所有主机生效
+        {
+            let breaker = CircuitBreaker::new(CircuitBreakerConfig {
+                enabled: true,
+                consecutive_failure_threshold: 2,
+                ..Default::default()
+            });
+            let ip: IpAddr = "192.0.2.20".parse().unwrap();
+            breaker.record_failure_for("github.com", ip);
+            breaker.record_failure_for("github.com", ip);
+            assert!(breaker.is_tripped_for("github.com", ip));
+            assert!(breaker.is_tripped_for("GitHub.com", ip));
+            assert!(!breaker.is_tripped_for("codeload.github.com", ip));
+            assert!(breaker.is_tripped(ip));
+            assert_eq!(breaker.get_tripped_ips(), vec![ip]);
+
+            let other: IpAddr = "192.0.2.21".parse().unwrap();
+            breaker.record_failure(other);
+            breaker.record_failure(other);
+            assert!(breaker.is_tripped_for("codeload.github.com", other));
+
+            let snapshot = breaker.snapshot();
+            assert_eq!(snapshot.len(), 2);
+            assert_eq!(snapshot[0].host, "");
+            assert_eq!(snapshot[1].host, "github.com");
+            assert_eq!(snapshot[1].state, CircuitState::Cooldown);
+            assert!(snapshot[1].half_open_at_epoch_ms.is_some());
+        }
+
+        // 场景 2: 冷却结束进入半开，只放行有限试探；全部成功后恢复
+        {
+            let breaker = CircuitBreaker::new(CircuitBreakerConfig {
+                enabled: true,
+                consecutive_failure_threshold: 1,
+                cooldown_seconds: 0,
+                half_open_max_trials: 2,
+                ..Default::default()
+            });
+            let ip: IpAddr = "192.0.2.22".parse().unwrap();
+            breaker.record_failure_for("github.com", ip);
+            assert_eq!(
+                breaker.get_stats_for("github.com", ip).unwrap().state,
+                CircuitState::Cooldown
+            );
+            assert!(breaker.admit("github.com", ip));
+            assert_eq!(
+                breaker.get_stats_for("github.com", ip).unwrap().state,
+                CircuitState::HalfOpen
+            );
+            assert!(breaker.admit("github.com", ip));
+            assert!(!breaker.admit("github.com", ip), "trial budget exhausted");
+            assert!(breaker.is_tripped_for("github.com", ip));
+
+            breaker.record_success_for("github.com", ip);
+            assert!(!breaker.admit("github.com", ip));
+            breaker.record_success_for("github.com", ip);
+            assert_eq!(
+                breaker.get_stats_for("github.com", ip).unwrap().state,
+                CircuitState::Normal
+            );
+            assert!(breaker.admit("github.com", ip));
+            assert!(breaker.snapshot().is_empty());
+        }
+
+        // 场景 3: 半开试探失败立即重新熔断
+        {
+            let breaker = CircuitBreaker::new(CircuitBreakerConfig {
+                enabled: true,
+                consecutive_failure_threshold: 1,
+                cooldown_seconds: 0,
+                ..Default::default()
+            });
+            let ip: IpAddr = "192.0.2.23".parse().unwrap();
+            breaker.record_failure_for("github.com", ip);
+            assert!(breaker.admit("github.com", ip));
+            breaker.record_failure_for("github.com", ip);
+            assert_eq!(
+                breaker.get_stats_for("github.com", ip).unwrap().state,
+                CircuitState::Cooldown
+            );
+            assert_eq!(breaker.snapshot()[0].trip_count, 2);
+            assert!(breaker.take_dirty());
+            assert!(!breaker.take_dirty());
+        }
+    }
 }
 
 // 已移除 section_ip_pool_events 模块：其事件发布测试与 section_event_emission 重复。
@@ -1231,6 +1350,7 @@ mod section_circuit_breaker {
 // ---------------- section_preheat ----------------
 mod section_preheat {
     use super::super::common::ip_pool::make_history_record;
+    use fireworks_collaboration_lib::core::config::model::AppConfig;
     use fireworks_collaboration_lib::core::ip_pool::cache::IpScoreCache;
     use fireworks_collaboration_lib::core::ip_pool::config::{
         EffectiveIpPoolConfig, PreheatDomain, UserStaticIp,
@@ -1239,6 +1359,7 @@ mod section_preheat {
     use fireworks_collaboration_lib::core::ip_pool::preheat::{
         builtin_lookup, collect_candidates, current_epoch_ms, next_due_schedule, probe_latency_tcp,
         update_cache_and_history, user_static_lookup, AggregatedCandidate, DomainSchedule,
+        ProbeEnv,
     };
     use fireworks_collaboration_lib::core::ip_pool::IpSource;
     use std::net::IpAddr;
@@ -1367,7 +1488,10 @@ mod section_preheat {
             let start = std::time::Instant::now();
             let ip_probe: IpAddr = "198.51.100.1".parse().unwrap();
             let timeout_ms = 150;
-            let result = rt.block_on(async { probe_latency_tcp(ip_probe, 9, timeout_ms).await });
+            let env = ProbeEnv::from_config(&AppConfig::default());
+            let result = rt.block_on(async {
+                probe_latency_tcp(ip_probe, 9, "github.com", timeout_ms, &env).await
+            });
             let elapsed_ms = start.elapsed().as_millis() as u64;
             assert!(elapsed_ms <= timeout_ms * 2 + 50);
             if let Ok(lat) = result {
@@ -70,7 +70,8 @@ fn mock_latency_prober(fixed_latency_ms: Option<u32>) -> (ProberFn, Arc<AtomicUs
               _sni,
               _path: &str,
               _timeout,
-              _method|
+              _method,
+              _env|
               -> BoxFuture<'static, Result<u32>> {
             call_count_clone.fetch_add(1, Ordering::SeqCst);
             mock_probe_impl(ip, fixed_latency_ms)
@@ -192,3 +193,73 @@ async fn test_preheat_probe_success_updates_cache_mock() {
 
     assert!(prober_count.load(Ordering::SeqCst) >= 1);
 }
+
+#[tokio::test]
+async fn test_preheat_revalidates_imported_history_mock() {
+    use fireworks_collaboration_lib::core::ip_pool::history::IpHistoryExport;
+    use fireworks_collaboration_lib::core::ip_pool::preheat::current_epoch_ms;
+    use fireworks_collaboration_lib::core::ip_pool::{IpCandidate, IpHistoryRecord, IpSource};
+
+    let mut runtime_config = IpPoolRuntimeConfig::default();
+    runtime_config.sources.dns = false;
+    runtime_config.sources.builtin = false;
+    runtime_config.sources.history = true;
+    runtime_config.sources.user_static = false;
+    runtime_config.sources.fallback = false;
+    let config = Arc::new(EffectiveIpPoolConfig::from_parts(
+        runtime_config,
+        IpPoolFileConfig::default(),
+    ));
+
+    let cache = Arc::new(fireworks_collaboration_lib::core::ip_pool::cache::IpScoreCache::new());
+    let history =
+        Arc::new(fireworks_collaboration_lib::core::ip_pool::history::IpHistoryStore::in_memory());
+    let now = current_epoch_ms();
+    let ip: IpAddr = "198.51.100.7".parse().unwrap();
+    let snapshot = IpHistoryExport {
+        version: 1,
+        exported_at_epoch_ms: now,
+        entries: vec![IpHistoryRecord {
+            host: "shared.example.com".into(),
+            port: 443,
+            candidate: IpCandidate::new(ip, 443, IpSource::Dns),
+            sources: vec![IpSource::Dns],
+            latency_ms: 12,
+            measured_at_epoch_ms: now,
+            expires_at_epoch_ms: now + 600_000,
+            resolver_metadata: Vec::new(),
+            pending_validation: false,
+        }],
+        quality: Vec::new(),
+    };
+    history.import_snapshot(&snapshot, false, now).unwrap();
+    assert_eq!(
+        history.pending_validation(now),
+        vec![("shared.example.com".to_string(), 443)]
+    );
+    // 导入的记录只作为候选，探测前不进入缓存
+    assert!(cache.get("shared.example.com", 443).is_none());
+
+    let (resolver, _) = mock_dns_resolver(None);
+    let (prober, prober_count) = mock_latency_prober(Some(30));
+    let preheater = PreheatService::spawn(
+        config.clone(),
+        cache.clone(),
+        history.clone(),
+        Some(resolver),
+        Some(prober),
+    )
+    .expect("spawn preheater");
+    preheater.request_refresh();
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+
+    assert!(prober_count.load(Ordering::SeqCst) >= 1);
+    let slot = cache
+        .get("shared.example.com", 443)
+        .expect("revalidated entry");
+    assert_eq!(slot.best.unwrap().latency_ms, Some(30));
+    let record = history.get("shared.example.com", 443).unwrap();
+    assert!(!record.pending_validation);
+    assert_eq!(record.latency_ms, 30);
+    assert!(history.pending_validation(current_epoch_ms()).is_empty());
+}
@@ -18,6 +18,7 @@ fn __init_env() {
 }
 
 use common::{repo_factory::RepoBuilder, task_wait::wait_until_task_done};
+use fireworks_collaboration_lib::core::credential::CredentialBinding;
 use fireworks_collaboration_lib::core::submodule::{SubmoduleConfig, SubmoduleManager};
 use fireworks_collaboration_lib::core::tasks::{
     model::{TaskKind, TaskState, WorkspaceBatchOperation},
@@ -155,6 +156,45 @@ fn test_enabled_repository_filtering() {
     assert!(!enabled.iter().any(|r| r.id == "repo2"));
 }
 
+#[test]
+fn test_find_repository_by_remote_normalizes_url() {
+    let mut ws = Workspace::new("test".to_string(), PathBuf::from("/test"));
+    ws.add_repository(RepositoryEntry::new(
+        "app".to_string(),
+        "App".to_string(),
+        PathBuf::from("app"),
+        "https://github.com/acme/app.git".to_string(),
+    ))
+    .unwrap();
+
+    let found = ws.find_repository_by_remote("git@github.com:Acme/app.git");
+    assert_eq!(found.map(|r| r.id.as_str()), Some("app"));
+    assert!(ws
+        .find_repository_by_remote("https://github.com/acme/other.git")
+        .is_none());
+}
+
+#[test]
+fn test_repository_credential_binding_serialization() {
+    let mut repo = RepositoryEntry::new(
+        "app".to_string(),
+        "App".to_string(),
+        PathBuf::from("app"),
+        "https://github.com/acme/app.git".to_string(),
+    );
+    let plain = serde_json::to_value(&repo).unwrap();
+    assert!(plain.get("credential").is_none(), "未绑定时不应输出字段");
+
+    repo.credential = Some(CredentialBinding::new("alice-work"));
+    let json = serde_json::to_string(&repo).unwrap();
+    let restored: RepositoryEntry = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.credential, repo.credential);
+
+    // 旧版工作区文件没有 credential 字段
+    let legacy: RepositoryEntry = serde_json::from_value(plain).unwrap();
+    assert!(legacy.credential.is_none());
+}
+
 // ============================================================================
 // 工作区配置测试
 // ============================================================================
@@ -14,8 +14,13 @@ import {
   getCredential,
   setMasterPassword,
   unlockStore,
+  changeMasterPassword,
+  exportCredentialsBackup,
+  importCredentialsBackup,
   exportAuditLog,
   cleanupAuditLogs,
+  verifyAuditLog,
+  queryAuditLog,
   isCredentialLocked,
   resetCredentialLock,
   remainingAuthAttempts,
@@ -212,6 +217,71 @@ describe("api/credential", () => {
     });
   });
 
+  describe("changeMasterPassword", () => {
+    it("should call change_master_password command", async () => {
+      const report = {
+        credentialsRekeyed: 2,
+        previousParams: { mCost: 65536, tCost: 3, pCost: 1 },
+        newParams: { mCost: 131072, tCost: 4, pCost: 1 },
+      };
+      mockInvoke.mockResolvedValueOnce(report);
+
+      const request = {
+        oldPassword: "old",
+        newPassword: "new",
+        argon2Params: { mCost: 131072, tCost: 4, pCost: 1 },
+      };
+      const result = await changeMasterPassword(request, { storage: "file" });
+
+      expect(mockInvoke).toHaveBeenCalledWith("change_master_password", {
+        request,
+        config: { storage: "file" },
+      });
+      expect(result).toEqual(report);
+    });
+  });
+
+  describe("exportCredentialsBackup", () => {
+    it("should call export_credentials_backup command", async () => {
+      mockInvoke.mockResolvedValueOnce("{}");
+
+      const result = await exportCredentialsBackup("passphrase");
+
+      expect(mockInvoke).toHaveBeenCalledWith("export_credentials_backup", {
+        request: { passphrase: "passphrase", argon2Params: undefined },
+      });
+      expect(result).toBe("{}");
+    });
+  });
+
+  describe("importCredentialsBackup", () => {
+    it("should call import_credentials_backup command", async () => {
+      const report = {
+        imported: 1,
+        overwritten: 0,
+        skipped: 0,
+        failed: 0,
+        entries: [
+          { host: "github.com", username: "alice", action: "imported" },
+        ],
+      };
+      mockInvoke.mockResolvedValueOnce(report);
+
+      const result = await importCredentialsBackup(
+        "{}",
+        "passphrase",
+        "keepNewest"
+      );
+
+      expect(mockInvoke).toHaveBeenCalledWith("import_credentials_backup", {
+        bundle: "{}",
+        passphrase: "passphrase",
+        strategy: "keepNewest",
+      });
+      expect(result).toEqual(report);
+    });
+  });
+
   describe("exportAuditLog", () => {
     it("should call export_credential_audit_log command", async () => {
       const mockLog = JSON.stringify([{ event: "test" }]);
@@ -300,6 +370,45 @@ describe("api/credential", () => {
     });
   });
 
+  describe("verifyAuditLog", () => {
+    it("should call verify_audit_log command", async () => {
+      const report = {
+        valid: false,
+        checked: 3,
+        firstSeq: 0,
+        lastSeq: 2,
+        removedByRetention: 0,
+        violations: [{ seq: 1, kind: "macMismatch" }],
+      };
+      mockInvoke.mockResolvedValueOnce(report);
+
+      const result = await verifyAuditLog();
+
+      expect(mockInvoke).toHaveBeenCalledWith("verify_audit_log");
+      expect(result).toEqual(report);
+    });
+  });
+
+  describe("queryAuditLog", () => {
+    it("should pass filters to query_audit_log command", async () => {
+      mockInvoke.mockResolvedValueOnce([]);
+
+      await queryAuditLog({ operation: "get", host: "github.com", success: false });
+
+      expect(mockInvoke).toHaveBeenCalledWith("query_audit_log", {
+        query: { operation: "get", host: "github.com", success: false },
+      });
+    });
+
+    it("should default to an empty query", async () => {
+      mockInvoke.mockResolvedValueOnce([]);
+
+      await queryAuditLog();
+
+      expect(mockInvoke).toHaveBeenCalledWith("query_audit_log", { query: {} });
+    });
+  });
+
   describe("isCredentialLocked", () => {
     it("should call is_credential_locked command", async () => {
       mockInvoke.mockResolvedValueOnce(false);
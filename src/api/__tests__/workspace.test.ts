@@ -20,6 +20,7 @@ import {
   removeRepository,
   toggleRepositoryEnabled,
   updateRepositoryTags,
+  setRepositoryCredential,
   reorderRepositories,
   listRepositories,
   listEnabledRepositories,
@@ -192,6 +193,21 @@ describe("api/workspace", () => {
     });
   });
 
+  it("setRepositoryCredential calls set_repository_credential", async () => {
+    mockInvoke.mockResolvedValue(undefined);
+    await setRepositoryCredential("r1", { username: "alice-work" });
+    expect(mockInvoke).toHaveBeenCalledWith("set_repository_credential", {
+      repoId: "r1",
+      credential: { username: "alice-work" },
+    });
+
+    await setRepositoryCredential("r1", null);
+    expect(mockInvoke).toHaveBeenLastCalledWith("set_repository_credential", {
+      repoId: "r1",
+      credential: null,
+    });
+  });
+
   it("reorderRepositories calls reorder_repositories", async () => {
     mockInvoke.mockResolvedValueOnce([]);
     await reorderRepositories(["r1", "r2"]);
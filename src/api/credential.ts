@@ -51,6 +51,128 @@ export interface CredentialConfig {
   auditMode?: boolean;
 }
 
+/**
+ * Argon2id key derivation parameters of the encrypted file store
+ */
+export interface Argon2Params {
+  mCost: number;
+  tCost: number;
+  pCost: number;
+}
+
+/**
+ * Request to rotate the master password of the encrypted file store
+ */
+export interface ChangeMasterPasswordRequest {
+  oldPassword: string;
+  newPassword: string;
+  argon2Params?: Argon2Params;
+}
+
+/**
+ * Result of a master password rotation
+ */
+export interface RekeyReport {
+  credentialsRekeyed: number;
+  previousParams: Argon2Params;
+  newParams: Argon2Params;
+}
+
+/**
+ * Conflict strategy when importing a credential backup
+ */
+export type ImportConflictStrategy = "skip" | "overwrite" | "keepNewest";
+
+/**
+ * Per-entry outcome of a credential backup import
+ */
+export interface ImportEntryResult {
+  host: string;
+  username: string;
+  action: "imported" | "overwritten" | "skipped" | "failed";
+  error?: string;
+}
+
+/**
+ * Summary of a credential backup import
+ */
+export interface ImportReport {
+  imported: number;
+  overwritten: number;
+  skipped: number;
+  failed: number;
+  entries: ImportEntryResult[];
+}
+
+export type AuditOperation =
+  | "add"
+  | "get"
+  | "update"
+  | "remove"
+  | "list"
+  | "validate"
+  | "expired"
+  | "unlock"
+  | "rekey"
+  | "export"
+  | "import";
+
+/**
+ * A hash-chained audit log record
+ */
+export interface AuditRecord {
+  seq: number;
+  operation: AuditOperation;
+  host: string;
+  username: string;
+  timestamp: number; // Unix timestamp in seconds
+  success: boolean;
+  error?: string;
+  credentialHash?: string;
+  prevMac: string;
+  mac: string;
+}
+
+/**
+ * Audit log query filters; omitted fields match everything
+ */
+export interface AuditQuery {
+  operation?: AuditOperation;
+  host?: string;
+  success?: boolean;
+  since?: number; // Unix seconds, inclusive
+  until?: number; // Unix seconds, inclusive
+  limit?: number;
+}
+
+/**
+ * Result of verifying the audit log hash chain
+ */
+export interface AuditChainReport {
+  valid: boolean;
+  checked: number;
+  firstSeq: number | null;
+  lastSeq: number | null;
+  removedByRetention: number;
+  violations: Array<{
+    seq: number;
+    kind: "macMismatch" | "brokenLink" | "sequenceGap" | "invalidAnchor";
+  }>;
+  /**
+   * Broken segment detected on load; it was archived and a new
+   * anchored segment started instead of appending to tampered history
+   */
+  chainBreak?: {
+    archivedPath: string;
+    records: number;
+    violations: Array<{
+      seq: number;
+      kind: "macMismatch" | "brokenLink" | "sequenceGap" | "invalidAnchor";
+    }>;
+    detectedAt: number;
+  };
+}
+
 /**
  * Add a new credential to the store
  */
@@ -116,6 +238,43 @@ export async function unlockStore(
   await invoke("unlock_store", { password, config });
 }
 
+/**
+ * Change master password and re-encrypt the encrypted file store
+ */
+export async function changeMasterPassword(
+  request: ChangeMasterPasswordRequest,
+  config: CredentialConfig
+): Promise<RekeyReport> {
+  return await invoke("change_master_password", { request, config });
+}
+
+/**
+ * Export all credentials as a passphrase-encrypted backup bundle (JSON)
+ */
+export async function exportCredentialsBackup(
+  passphrase: string,
+  argon2Params?: Argon2Params
+): Promise<string> {
+  return await invoke("export_credentials_backup", {
+    request: { passphrase, argon2Params },
+  });
+}
+
+/**
+ * Import credentials from a passphrase-encrypted backup bundle
+ */
+export async function importCredentialsBackup(
+  bundle: string,
+  passphrase: string,
+  strategy?: ImportConflictStrategy
+): Promise<ImportReport> {
+  return await invoke("import_credentials_backup", {
+    bundle,
+    passphrase,
+    strategy,
+  });
+}
+
 /**
  * Export audit log as JSON
  */
@@ -146,6 +305,24 @@ export async function cleanupAuditLogs(retentionDays: number): Promise<number> {
   return await invoke("cleanup_audit_logs", { retentionDays });
 }
 
+/**
+ * Verify the audit log hash chain
+ *
+ * Detects edited, deleted or reordered records.
+ */
+export async function verifyAuditLog(): Promise<AuditChainReport> {
+  return await invoke("verify_audit_log");
+}
+
+/**
+ * Query audit log records by operation, host, result and time range
+ */
+export async function queryAuditLog(
+  query: AuditQuery = {}
+): Promise<AuditRecord[]> {
+  return await invoke("query_audit_log", { query });
+}
+
 /**
  * Check if credential store is locked due to authentication failures
  *
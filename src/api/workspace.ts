@@ -1,5 +1,11 @@
 import { invoke } from "./tauri";
 
+/** 仓库绑定的凭证身份；host 为空时使用远程 URL 的主机 */
+export interface CredentialBinding {
+  host?: string;
+  username: string;
+}
+
 export interface RepositoryInfo {
   id: string;
   name: string;
@@ -7,6 +13,7 @@ export interface RepositoryInfo {
   remoteUrl: string;
   tags: string[];
   enabled: boolean;
+  credential?: CredentialBinding | null;
 }
 
 export interface WorkspaceInfo {
@@ -31,6 +38,7 @@ export interface AddRepositoryRequest {
   remoteUrl: string;
   tags?: string[];
   enabled?: boolean;
+  credential?: CredentialBinding;
 }
 
 export interface WorkspaceStatusFilter {
@@ -159,6 +167,8 @@ export interface WorkspaceBatchPushRequest {
   refspecs?: string[];
   username?: string;
   password?: string;
+  /** 使用已存储的凭证；仓库有凭证绑定时始终按绑定取凭证 */
+  useStoredCredential?: boolean;
   strategyOverride?: unknown;
 }
 
@@ -198,6 +208,13 @@ export async function updateRepositoryTags(repoId: string, tags: string[]): Prom
   await invoke<void>("update_repository_tags", { repoId, tags });
 }
 
+export async function setRepositoryCredential(
+  repoId: string,
+  credential: CredentialBinding | null,
+): Promise<void> {
+  await invoke<void>("set_repository_credential", { repoId, credential });
+}
+
 export async function reorderRepositories(order: string[]): Promise<RepositoryInfo[]> {
   return invoke<RepositoryInfo[]>("reorder_repositories", { orderedIds: order });
 }